@@ -0,0 +1,1014 @@
+// Non-interactive subcommands - scriptable entry points for CI pipelines
+// Every command returns an Outcome (JSON document + exit code) so --json and
+// exit codes behave the same across commands
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::{Args, Parser, Subcommand};
+use mdns_sd::{ServiceDaemon, ServiceEvent};
+use reqwest::Client;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::CliError;
+use crate::output::{self, info, Outcome};
+use crate::report;
+use crate::template::{self, Template};
+use crate::top;
+use crate::{build_client, new_runtime, prompt_test_settings, run_test_with_retry, TestParams, SCHEDULED_RETRIES};
+
+// DNS-SD service type engines advertise themselves under
+const MDNS_SERVICE_TYPE: &str = "_mogwai._tcp.local.";
+
+// Command-line arguments - with no subcommand the interactive menu is started
+#[derive(Parser)]
+#[command(name = "mogwai", version, about = "Mogwai stress test CLI")]
+pub struct Cli {
+    /// Server (engine or controller) URL; prompted for in interactive mode when omitted
+    #[arg(long, global = true, env = "MOGWAI_SERVER")]
+    pub server: Option<String>,
+
+    /// Print a single JSON document to stdout (progress goes to stderr)
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+// Top-level subcommands
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Submit a single test
+    Run {
+        /// Test name (defaults to Test-<id>)
+        #[arg(long)]
+        name: Option<String>,
+        #[command(flatten)]
+        spec: TestSpecArgs,
+        #[command(flatten)]
+        targets: TargetArgs,
+        #[command(flatten)]
+        wait: WaitArgs,
+    },
+    /// Manage and run saved test templates
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Map intensity levels 1-10 onto a node's hardware (stored on the engine)
+    Calibrate {
+        /// Target node (when talking to the controller)
+        #[arg(long, default_value = "minikube")]
+        node: String,
+        /// Show the stored calibration instead of running a new one
+        #[arg(long)]
+        show: bool,
+    },
+    /// Run multi-step scenarios on the controller and follow them
+    Scenario {
+        #[command(subcommand)]
+        action: ScenarioAction,
+    },
+    /// Render a batch's results for GitHub issues and wikis
+    Report {
+        /// A batch report exported by the GUI (JSON file), or a scenario/campaign job id
+        batch: String,
+        /// Output format
+        #[arg(long, default_value = "md", value_parser = ["md"])]
+        format: String,
+    },
+    /// List the nodes tests can run on
+    Nodes {
+        /// Find engines on the local network over mDNS instead of asking the server
+        #[arg(long)]
+        discover: bool,
+        /// Seconds to listen for engines with --discover
+        #[arg(long, default_value_t = 3)]
+        timeout: u64,
+    },
+    /// Watch the cluster's nodes, running tasks and generated load live, like top
+    Top {
+        /// Cluster to watch (default: the controller's own)
+        #[arg(long)]
+        cluster: Option<String>,
+        /// Seconds between refreshes
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+        /// Print one frame and exit (implied by --json)
+        #[arg(long)]
+        once: bool,
+    },
+}
+
+// `scenario` subcommands
+#[derive(Subcommand)]
+pub enum ScenarioAction {
+    /// Submit a scenario file (YAML or JSON, see endpoints.md)
+    Run {
+        /// Scenario file
+        file: PathBuf,
+        /// The file is an A/B campaign; its arms are compared when it finishes
+        #[arg(long)]
+        campaign: bool,
+        /// Follow the steps until the scenario finishes
+        #[arg(long)]
+        wait: bool,
+        /// Seconds to follow before giving up; implies --wait
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+    /// List the controller's scenario jobs
+    List,
+    /// Show a scenario job and the status of its steps
+    Status {
+        /// Job id, e.g. scenario-1
+        id: String,
+    },
+    /// Stop a scenario: its running tests are stopped and its pending steps skipped
+    Stop {
+        /// Job id
+        id: String,
+    },
+    /// Make a completed step the baseline later runs on the same nodes are checked against
+    Baseline {
+        /// Job id
+        id: String,
+        /// Step name
+        step: String,
+        /// Percent a metric may get worse before it counts as a regression (default: the controller's)
+        #[arg(long)]
+        threshold: Option<f64>,
+    },
+}
+
+// `template` subcommands
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// Save a template from flags, or interactively when --type is omitted
+    Save {
+        /// Template name
+        name: String,
+        #[command(flatten)]
+        spec: TestSpecArgs,
+    },
+    /// List saved templates
+    List,
+    /// Run a saved template once
+    Run {
+        /// Template name
+        name: String,
+        /// Override the template's target node
+        #[arg(long, conflicts_with_all = ["nodes", "all_nodes"])]
+        node: Option<String>,
+        #[command(flatten)]
+        targets: TargetArgs,
+        #[command(flatten)]
+        wait: WaitArgs,
+    },
+    /// Delete a saved template
+    Delete {
+        /// Template name
+        name: String,
+    },
+    /// Fetch shared templates from the controller's /templates endpoint
+    Sync {
+        /// Replace local templates that have the same name
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+// Test parameters given as flags
+#[derive(Args)]
+pub struct TestSpecArgs {
+    /// Test type
+    #[arg(long = "type", value_parser = ["cpu", "mem", "disk", "compress", "crypto", "lock", "pagefault", "syscall"])]
+    pub test_type: Option<String>,
+    /// Number of threads
+    #[arg(long)]
+    pub threads: Option<u32>,
+    /// Duration in seconds
+    #[arg(long, default_value_t = 60)]
+    pub duration: u32,
+    /// CPU load percentage (cpu tests)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub load: Option<u32>,
+    /// Size in MB (mem and disk tests)
+    #[arg(long)]
+    pub size: Option<u32>,
+    /// Fork processes instead of threads (cpu tests)
+    #[arg(long)]
+    pub fork: bool,
+    /// Target node
+    #[arg(long)]
+    pub node: Option<String>,
+}
+
+// Fan-out targets for run commands
+#[derive(Args)]
+pub struct TargetArgs {
+    /// Submit to several nodes concurrently (comma-separated)
+    #[arg(long, value_delimiter = ',', conflicts_with = "all_nodes")]
+    pub nodes: Vec<String>,
+    /// Submit to every node reported by the server's /nodes endpoint
+    #[arg(long)]
+    pub all_nodes: bool,
+}
+
+// Options for waiting on a submitted task
+#[derive(Args, Clone, Copy)]
+pub struct WaitArgs {
+    /// Wait for the task to finish before exiting
+    #[arg(long)]
+    pub wait: bool,
+    /// Seconds to wait before giving up (default: test duration + 60); implies --wait
+    #[arg(long)]
+    pub timeout: Option<u64>,
+}
+
+// Run a parsed subcommand against the given server
+pub fn execute(command: Commands, server_url: &str) -> Result<Outcome, CliError> {
+    match command {
+        Commands::Run { name, spec, targets, wait } => {
+            if spec.node.is_some() && (targets.all_nodes || !targets.nodes.is_empty()) {
+                return Err(CliError::Usage("--node cannot be combined with --nodes or --all-nodes".to_string()));
+            }
+            let test_type = spec
+                .test_type
+                .clone()
+                .ok_or_else(|| CliError::Usage("run requires --type (cpu, mem, disk, compress, crypto, lock, pagefault or syscall)".to_string()))?;
+            let id = Uuid::new_v4().to_string();
+            // Only CPU tests fork
+            let fork = (test_type == "cpu").then_some(spec.fork);
+            let params = TestParams {
+                name: name.unwrap_or_else(|| format!("Test-{}", &id[0..8])),
+                id,
+                test_type,
+                threads: spec.threads,
+                duration: spec.duration,
+                load: spec.load,
+                size: spec.size,
+                fork,
+                scheduled_time: None,
+                node: spec.node.unwrap_or_else(|| "minikube".to_string()),
+            };
+            submit(server_url, params, &targets, wait)
+        }
+        Commands::Template { action } => run_template_command(action, server_url),
+        Commands::Calibrate { node, show } => calibrate(server_url, &node, show),
+        Commands::Scenario { action } => run_scenario_command(action, server_url),
+        Commands::Report { batch, format } => report(server_url, &batch, &format),
+        Commands::Nodes { discover: false, .. } => nodes(server_url),
+        Commands::Nodes { discover: true, timeout } => discover(Duration::from_secs(timeout)),
+        Commands::Top { cluster, interval, once } => top::run(server_url, cluster.as_deref(), interval, once),
+    }
+}
+
+// Function to list the server's nodes (the controller's, or the engine's own)
+fn nodes(server_url: &str) -> Result<Outcome, CliError> {
+    let rt = new_runtime()?;
+    let client = build_client(30)?;
+    let nodes = rt.block_on(fetch_nodes(&client, server_url))?;
+    if !output::is_json() {
+        for node in &nodes {
+            println!("{}", node);
+        }
+    }
+    Ok(Outcome::ok(json!({ "ok": true, "server": server_url, "nodes": nodes })))
+}
+
+// Function to find engines advertised over mDNS (engines started with MOGWAI_MDNS=1)
+// The URLs printed can be passed to --server as they are
+fn discover(timeout: Duration) -> Result<Outcome, CliError> {
+    let daemon = ServiceDaemon::new().map_err(|e| CliError::Process(format!("cannot start mDNS discovery: {}", e)))?;
+    let events = daemon
+        .browse(MDNS_SERVICE_TYPE)
+        .map_err(|e| CliError::Process(format!("cannot start mDNS discovery: {}", e)))?;
+    info!("Looking for engines on the local network for {}s...", timeout.as_secs());
+    let deadline = Instant::now() + timeout;
+    let mut found: Vec<(String, Value)> = Vec::new(); // By instance name
+    while let Ok(event) = events.recv_deadline(deadline) {
+        let ServiceEvent::ServiceResolved(service) = event else {
+            continue;
+        };
+        // IPv4 before IPv6, loopback only when there is nothing else; link-local IPv6
+        // (fe80::) is skipped, since the resolved addresses carry no scope id to reach it by
+        let mut addresses: Vec<&IpAddr> = service
+            .get_addresses()
+            .iter()
+            .filter(|ip| !matches!(ip, IpAddr::V6(ip) if ip.is_unicast_link_local()))
+            .collect();
+        addresses.sort_by_key(|ip| (ip.is_loopback(), ip.is_ipv6(), **ip));
+        let Some(address) = addresses.first() else {
+            continue;
+        };
+        let url = match address {
+            IpAddr::V6(ip) => format!("http://[{}]:{}", ip, service.get_port()),
+            IpAddr::V4(ip) => format!("http://{}:{}", ip, service.get_port()),
+        };
+        let node = service.get_property_val_str("node").unwrap_or(service.get_fullname()).to_string();
+        let engine = json!({ "node": node, "url": url, "version": service.get_property_val_str("version") });
+        // Addresses come in over several answers; the latest resolution has them all
+        match found.iter_mut().find(|(name, _)| name == service.get_fullname()) {
+            Some((_, known)) => *known = engine,
+            None => found.push((service.get_fullname().to_string(), engine)),
+        }
+    }
+    let mut engines: Vec<Value> = found.into_iter().map(|(_, engine)| engine).collect();
+    let _ = daemon.shutdown();
+    engines.sort_by(|a, b| a["node"].as_str().cmp(&b["node"].as_str()));
+    if !output::is_json() {
+        if engines.is_empty() {
+            println!("No engines found (engines advertise themselves when started with MOGWAI_MDNS=1)");
+        } else {
+            println!("{:<24} {:<32} VERSION", "NODE", "URL");
+            for engine in &engines {
+                println!(
+                    "{:<24} {:<32} {}",
+                    engine["node"].as_str().unwrap_or("-"),
+                    engine["url"].as_str().unwrap_or("-"),
+                    engine["version"].as_str().unwrap_or("-")
+                );
+            }
+        }
+    }
+    Ok(Outcome::ok(json!({ "ok": true, "engines": engines })))
+}
+
+// Function to render a batch as Markdown: a GUI batch report file, or else a job on the controller
+fn report(server_url: &str, batch: &str, format: &str) -> Result<Outcome, CliError> {
+    let markdown = if Path::new(batch).is_file() {
+        let data = std::fs::read_to_string(batch).map_err(CliError::Io)?;
+        let parsed: Value = serde_json::from_str(&data)
+            .map_err(|e| CliError::Usage(format!("{} is not a batch report: {}", batch, e)))?;
+        // Multi-node runs export one batch object per node
+        let reports = match parsed {
+            Value::Array(reports) => reports,
+            report => vec![report],
+        };
+        if reports.is_empty() || reports.iter().any(|r| r["tests"].as_array().is_none()) {
+            return Err(CliError::Usage(format!("{} is not a batch report", batch)));
+        }
+        report::batch_markdown(&reports)
+    } else {
+        let rt = new_runtime()?;
+        let client = build_client(30)?;
+        let job = rt.block_on(async {
+            let response = client.get(format!("{}/scenarios/{}", server_url, batch)).send().await?;
+            scenario_response(response).await
+        })?;
+        report::job_markdown(&job)
+    };
+    if !output::is_json() {
+        print!("{}", markdown);
+    }
+    Ok(Outcome::ok(json!({ "ok": true, "batch": batch, "format": format, "report": markdown })))
+}
+
+// Function to handle `scenario` subcommands against the controller's /scenarios endpoints
+fn run_scenario_command(action: ScenarioAction, server_url: &str) -> Result<Outcome, CliError> {
+    let rt = new_runtime()?;
+    let client = build_client(30)?;
+    match action {
+        ScenarioAction::Run { file, campaign, wait, timeout } => {
+            let body = std::fs::read(&file).map_err(CliError::Io)?;
+            let endpoint = if campaign { "campaigns" } else { "scenarios" };
+            let job = rt.block_on(async {
+                let response = client
+                    .post(format!("{}/{}", server_url, endpoint))
+                    .header("Content-Type", "application/yaml")
+                    .body(body)
+                    .send()
+                    .await?;
+                scenario_response(response).await
+            })?;
+            let id = job["id"].as_str().unwrap_or_default().to_string();
+            info!("{} started ({} steps)", id, job["steps"].as_array().map_or(0, Vec::len));
+            if !wait && timeout.is_none() {
+                return Ok(Outcome::ok(json!({ "ok": true, "server": server_url, "scenario": job })));
+            }
+            let deadline = timeout.map(|secs| Instant::now() + Duration::from_secs(secs));
+            rt.block_on(follow_scenario(&client, server_url, &id, deadline))
+        }
+        ScenarioAction::List => {
+            let jobs = rt.block_on(async {
+                let response = client.get(format!("{}/scenarios", server_url)).send().await?;
+                scenario_response(response).await
+            })?;
+            if !output::is_json() {
+                println!("{:<16} {:<24} {:<10} STEPS", "ID", "NAME", "STATUS");
+                for job in jobs.as_array().into_iter().flatten() {
+                    println!(
+                        "{:<16} {:<24} {:<10} {}",
+                        job["id"].as_str().unwrap_or("-"),
+                        job["name"].as_str().unwrap_or("-"),
+                        job["status"].as_str().unwrap_or("-"),
+                        job["steps"].as_array().map_or(0, Vec::len)
+                    );
+                }
+            }
+            Ok(Outcome::ok(json!({ "ok": true, "server": server_url, "scenarios": jobs })))
+        }
+        ScenarioAction::Status { id } => {
+            let job = rt.block_on(async {
+                let response = client.get(format!("{}/scenarios/{}", server_url, id)).send().await?;
+                scenario_response(response).await
+            })?;
+            if !output::is_json() {
+                print_scenario(&job);
+            }
+            Ok(scenario_outcome(server_url, job))
+        }
+        ScenarioAction::Stop { id } => {
+            let job = rt.block_on(async {
+                let response = client.post(format!("{}/scenarios/{}/stop", server_url, id)).send().await?;
+                scenario_response(response).await
+            })?;
+            info!("Stopping scenario {}", id);
+            Ok(Outcome::ok(json!({ "ok": true, "server": server_url, "scenario": job })))
+        }
+        ScenarioAction::Baseline { id, step, threshold } => {
+            let mut body = json!({ "job": id, "step": step });
+            if let Some(threshold) = threshold {
+                body["threshold_percent"] = json!(threshold);
+            }
+            let baselines = rt.block_on(async {
+                let response = client.post(format!("{}/baselines", server_url)).json(&body).send().await?;
+                scenario_response(response).await
+            })?;
+            for baseline in baselines.as_array().into_iter().flatten() {
+                info!(
+                    "Baseline set for {} on {} ({} metrics, threshold {}%)",
+                    baseline["type"].as_str().unwrap_or("-"),
+                    baseline["node"].as_str().unwrap_or("-"),
+                    baseline["metrics"].as_object().map_or(0, |metrics| metrics.len()),
+                    baseline["threshold_percent"]
+                );
+            }
+            Ok(Outcome::ok(json!({ "ok": true, "server": server_url, "baselines": baselines })))
+        }
+    }
+}
+
+// Function to read a /scenarios answer, turning error statuses into errors
+async fn scenario_response(response: reqwest::Response) -> Result<Value, CliError> {
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CliError::Status(status.as_u16(), body));
+    }
+    serde_json::from_str(&body).map_err(|e| CliError::Process(format!("unexpected scenario response: {}", e)))
+}
+
+// Function to poll a scenario job until it finishes, reporting each step as its status changes
+async fn follow_scenario(client: &Client, server_url: &str, id: &str, deadline: Option<Instant>) -> Result<Outcome, CliError> {
+    let mut reported: Vec<String> = Vec::new();
+    loop {
+        let response = client.get(format!("{}/scenarios/{}", server_url, id)).send().await?;
+        let job = scenario_response(response).await?;
+        let steps = job["steps"].as_array().cloned().unwrap_or_default();
+        reported.resize(steps.len(), String::new());
+        for (step, last) in steps.iter().zip(reported.iter_mut()) {
+            let status = step["status"].as_str().unwrap_or("-");
+            if status != last && status != "pending" {
+                info!("  {:<24} {}", step["name"].as_str().unwrap_or("-"), status);
+                *last = status.to_string();
+            }
+        }
+        if job["status"] != "running" {
+            if !output::is_json() {
+                print_scenario(&job);
+            }
+            return Ok(scenario_outcome(server_url, job));
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            let e = CliError::Timeout(format!("scenario {} still running", id));
+            info!("{}", e);
+            return Ok(Outcome {
+                json: json!({ "ok": false, "server": server_url, "scenario": job, "error": e.to_string() }),
+                code: e.exit_code(),
+            });
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+// Function to print a scenario job as a table of steps and their tasks
+fn print_scenario(job: &Value) {
+    println!(
+        "\n=== Scenario {} ({}): {} ===",
+        job["id"].as_str().unwrap_or("-"),
+        job["name"].as_str().unwrap_or("unnamed"),
+        job["status"].as_str().unwrap_or("-")
+    );
+    println!("{:<24} {:<10} {:<10} TASKS", "STEP", "KIND", "STATUS");
+    for step in job["steps"].as_array().into_iter().flatten() {
+        let tasks: Vec<String> = step["tasks"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|task| {
+                let error = task["error"].as_str().map(|e| format!(" ({})", e)).unwrap_or_default();
+                format!(
+                    "{}@{} {}{}",
+                    task["type"].as_str().unwrap_or("-"),
+                    task["node"].as_str().unwrap_or("-"),
+                    task["task_id"].as_str().unwrap_or("-"),
+                    error
+                )
+            })
+            .collect();
+        println!(
+            "{:<24} {:<10} {:<10} {}",
+            step["name"].as_str().unwrap_or("-"),
+            step["kind"].as_str().unwrap_or("-"),
+            step["status"].as_str().unwrap_or("-"),
+            tasks.join(", ")
+        );
+        for task in step["tasks"].as_array().into_iter().flatten() {
+            for regression in task["regressions"].as_array().into_iter().flatten() {
+                let change = regression["change_percent"].as_f64().map_or("-".to_string(), |c| format!("{:+.1}%", c));
+                println!(
+                    "    REGRESSION {} {}: {} {:.2} -> {:.2} ({}, threshold {}%)",
+                    task["task_id"].as_str().unwrap_or("-"),
+                    task["node"].as_str().unwrap_or("-"),
+                    regression["metric"].as_str().unwrap_or("-"),
+                    regression["baseline"].as_f64().unwrap_or_default(),
+                    regression["value"].as_f64().unwrap_or_default(),
+                    change,
+                    regression["threshold_percent"]
+                );
+            }
+        }
+        // Repeated steps: every metric over the completed runs
+        if step["repeat"].as_u64().unwrap_or(1) > 1 {
+            for test in step["summary"].as_array().into_iter().flatten() {
+                let runs = test["metrics"]["elapsed_secs"]["runs"].as_u64().unwrap_or(0);
+                if runs == 0 {
+                    continue;
+                }
+                println!("    {}@{} over {} of {} runs:", test["type"].as_str().unwrap_or("-"), test["node"].as_str().unwrap_or("-"), runs, step["repeat"]);
+                println!("    {:<28} {:>14} {:>12} {:>14} {:>14} {:>14} {:>14}", "METRIC", "MEAN", "STDDEV", "MIN", "P50", "P90", "MAX");
+                for (metric, summary) in test["metrics"].as_object().into_iter().flatten() {
+                    let value = |field: &str| summary[field].as_f64().unwrap_or_default();
+                    println!(
+                        "    {:<28} {:>14.2} {:>12.2} {:>14.2} {:>14.2} {:>14.2} {:>14.2}",
+                        metric,
+                        value("mean"),
+                        value("stddev"),
+                        value("min"),
+                        value("p50"),
+                        value("p90"),
+                        value("max")
+                    );
+                }
+            }
+        }
+    }
+
+    // Campaigns: each metric of arm b against arm a
+    if let Some(comparison) = job["comparison"].as_object() {
+        let (a, b) = (comparison["a"].as_str().unwrap_or("a"), comparison["b"].as_str().unwrap_or("b"));
+        println!("\n=== {} vs {} ===", b, a);
+        println!("{:<28} {:>14} {:>14} {:>10} {:>8} HINT", "METRIC", a, b, "DELTA %", "T");
+        for (metric, delta) in comparison["metrics"].as_object().into_iter().flatten() {
+            let percent = delta["delta_percent"].as_f64().map_or("-".to_string(), |p| format!("{:+.2}", p));
+            let t = delta["t"].as_f64().map_or("-".to_string(), |t| format!("{:.2}", t));
+            println!(
+                "{:<28} {:>14.2} {:>14.2} {:>10} {:>8} {}",
+                metric,
+                delta["a_mean"].as_f64().unwrap_or_default(),
+                delta["b_mean"].as_f64().unwrap_or_default(),
+                percent,
+                t,
+                delta["hint"].as_str().unwrap_or("-")
+            );
+        }
+    }
+}
+
+// Function to turn a scenario job into an outcome: failed, stopped or regressed scenarios exit with EXIT_TASK_FAILED
+fn scenario_outcome(server_url: &str, job: Value) -> Outcome {
+    let code = match job["status"].as_str() {
+        _ if job["regressed"] == true => output::EXIT_TASK_FAILED,
+        Some("completed") | Some("running") => output::EXIT_OK,
+        _ => output::EXIT_TASK_FAILED,
+    };
+    Outcome { json: json!({ "ok": code == output::EXIT_OK, "server": server_url, "scenario": job }), code }
+}
+
+// Function to run (or show) the intensity calibration of a node
+// Tries the engine's /calibration first, then the controller's /calibration/{node}
+fn calibrate(server_url: &str, node: &str, show: bool) -> Result<Outcome, CliError> {
+    let rt = new_runtime()?;
+    // Calibration benchmarks the node for several seconds
+    let client = build_client(60)?;
+    if !show {
+        info!("Calibrating intensity levels on {} (about 6 seconds)...", server_url);
+    }
+
+    let calibration: Value = rt.block_on(async {
+        let request = |url: String| {
+            let builder = if show { client.get(url) } else { client.post(url) };
+            async move {
+                let response = builder.send().await?;
+                let status = response.status();
+                Ok::<_, CliError>((status, response.text().await?))
+            }
+        };
+        let (mut status, mut body) = request(format!("{}/calibration", server_url)).await?;
+        if status == reqwest::StatusCode::NOT_FOUND && !body.contains("not calibrated") {
+            (status, body) = request(format!("{}/calibration/{}", server_url, node)).await?;
+        }
+
+        if body.contains("not calibrated") {
+            return Err(CliError::Process("the node is not calibrated; run `calibrate` without --show".to_string()));
+        }
+        if !status.is_success() {
+            return Err(CliError::Status(status.as_u16(), body));
+        }
+        serde_json::from_str(&body).map_err(|e| CliError::Process(format!("unexpected calibration response: {}", e)))
+    })?;
+
+    if !output::is_json() {
+        println!(
+            "=== Calibration of {} ({}) ===",
+            calibration["hostname"].as_str().unwrap_or("-"),
+            calibration["created_at"].as_str().unwrap_or("-")
+        );
+        println!("{:<6} {:<18} {:<18} DISK", "LEVEL", "CPU", "MEMORY");
+        for level in calibration["levels"].as_array().into_iter().flatten() {
+            let cpu = format!("{} x {}%", level["cpu_threads"], level["cpu_load"]);
+            let memory = format!("{} x {} MB", level["mem_threads"], level["mem_size_mb"]);
+            let disk = format!("{} x {} MB", level["disk_threads"], level["disk_size_mb"]);
+            println!("{:<6} {:<18} {:<18} {}", level["level"].to_string(), cpu, memory, disk);
+        }
+    }
+    Ok(Outcome::ok(json!({ "ok": true, "server": server_url, "calibration": calibration })))
+}
+
+// Function to handle `template` subcommands
+fn run_template_command(action: TemplateAction, server_url: &str) -> Result<Outcome, CliError> {
+    match action {
+        TemplateAction::Save { name, spec } => {
+            // Build the template from flags, or walk the usual prompts when no --type was given
+            let template = match spec.test_type.clone() {
+                Some(test_type) => Template {
+                    name: name.clone(),
+                    fork: (test_type == "cpu").then_some(spec.fork),
+                    test_type,
+                    threads: spec.threads,
+                    duration: spec.duration,
+                    load: spec.load,
+                    size: spec.size,
+                    node: spec.node,
+                    created_at: None,
+                },
+                None if output::is_json() => {
+                    return Err(CliError::Usage("template save --json requires --type".to_string()))
+                }
+                None => {
+                    let id = Uuid::new_v4().to_string();
+                    match prompt_test_settings(id, name.clone(), "minikube")? {
+                        Some(params) => template_from_params(&name, &params, spec.node),
+                        None => return Err(CliError::Usage("invalid test type".to_string())),
+                    }
+                }
+            };
+            template::save_template(template.clone())?;
+            let path = template::templates_path();
+            info!("Template '{}' saved to {}", name, path.display());
+            Ok(Outcome::ok(json!({ "ok": true, "saved": template, "path": path })))
+        }
+        TemplateAction::List => {
+            let templates = template::load_templates()?;
+            if !output::is_json() {
+                if templates.is_empty() {
+                    println!("No templates saved. Create one with `template save <name>`.");
+                } else {
+                    println!("=== Saved Templates ===");
+                    for template in templates.values() {
+                        println!("{:<20} {}", template.name, template.summary());
+                    }
+                }
+            }
+            let list: Vec<&Template> = templates.values().collect();
+            Ok(Outcome::ok(json!({ "ok": true, "templates": list })))
+        }
+        TemplateAction::Run { name, node, targets, wait } => {
+            let template = template::find_template(&name)?;
+            let mut params = params_from_template(&template, "minikube");
+            if let Some(node) = node {
+                params.node = node;
+            }
+            submit(server_url, params, &targets, wait)
+        }
+        TemplateAction::Delete { name } => {
+            if !template::delete_template(&name)? {
+                return Err(CliError::Usage(format!("no template named '{}'", name)));
+            }
+            info!("Template '{}' deleted.", name);
+            Ok(Outcome::ok(json!({ "ok": true, "deleted": name })))
+        }
+        TemplateAction::Sync { overwrite } => {
+            // Fetch the controller's shared templates and merge them into the local library
+            let rt = new_runtime()?;
+            let client = build_client(10)?;
+            let remote: Vec<Template> = rt.block_on(async {
+                let response = client.get(format!("{}/templates", server_url)).send().await?;
+                let status = response.status();
+                if !status.is_success() {
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(CliError::Status(status.as_u16(), body));
+                }
+                Ok(response.json::<Vec<Template>>().await?)
+            })?;
+
+            let count = remote.len();
+            let changed = template::merge_templates(remote, overwrite)?;
+            info!(
+                "Fetched {} template(s) from {}; {} added or updated{}",
+                count,
+                server_url,
+                changed.len(),
+                if changed.is_empty() { String::new() } else { format!(": {}", changed.join(", ")) }
+            );
+            Ok(Outcome::ok(json!({ "ok": true, "fetched": count, "changed": changed })))
+        }
+    }
+}
+
+// Function to submit a test to one or more nodes and optionally wait for it to finish
+// A single target reports its result directly; several targets are submitted concurrently
+// and reported as a per-node table, exiting with the most severe per-node exit code
+fn submit(server_url: &str, params: TestParams, targets: &TargetArgs, wait: WaitArgs) -> Result<Outcome, CliError> {
+    let rt = new_runtime()?;
+    let client = build_client(30)?;
+
+    // Resolve the node list
+    let nodes = if targets.all_nodes {
+        rt.block_on(fetch_nodes(&client, server_url))?
+    } else {
+        targets.nodes.iter().map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect()
+    };
+
+    if nodes.is_empty() {
+        if targets.all_nodes {
+            return Err(CliError::Process(format!("{}/nodes returned no nodes", server_url)));
+        }
+        return rt.block_on(submit_one(&client, server_url, params, wait));
+    }
+
+    // Fan out: one submission per node, each with its own test id
+    let handles: Vec<_> = nodes
+        .iter()
+        .map(|node| {
+            let id = Uuid::new_v4().to_string();
+            let node_params = TestParams { id, node: node.clone(), ..params.clone() };
+            let client = client.clone();
+            let server_url = server_url.to_string();
+            rt.spawn(async move { submit_one(&client, &server_url, node_params, wait).await })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    let mut code = output::EXIT_OK;
+    for (node, handle) in nodes.iter().zip(handles) {
+        let outcome = match rt.block_on(handle) {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(e)) => Outcome {
+                json: json!({ "ok": false, "status": "submit_failed", "error": e.to_string() }),
+                code: e.exit_code(),
+            },
+            Err(e) => Outcome {
+                json: json!({ "ok": false, "status": "error", "error": e.to_string() }),
+                code: output::EXIT_ERROR,
+            },
+        };
+        code = code.max(outcome.code);
+        let mut entry = outcome.json;
+        entry["node"] = json!(node);
+        entry["exit_code"] = json!(outcome.code);
+        results.push(entry);
+    }
+
+    // Per-node summary table
+    if !output::is_json() {
+        println!("\n{:<24} {:<16} {:<14} ERROR", "NODE", "TASK ID", "STATUS");
+        for entry in &results {
+            println!(
+                "{:<24} {:<16} {:<14} {}",
+                entry["node"].as_str().unwrap_or("-"),
+                entry["task_id"].as_str().unwrap_or("-"),
+                entry["status"].as_str().unwrap_or("-"),
+                entry["error"].as_str().unwrap_or("")
+            );
+        }
+    }
+
+    Ok(Outcome {
+        json: json!({ "ok": code == output::EXIT_OK, "server": server_url, "results": results }),
+        code,
+    })
+}
+
+// Function to submit a single test and optionally wait for it
+// Submission failures are returned as errors; wait results are reported in the Outcome
+async fn submit_one(client: &Client, server_url: &str, params: TestParams, wait: WaitArgs) -> Result<Outcome, CliError> {
+    // Submit once, retrying transient failures automatically (no prompting for scripted use)
+    let body = run_test_with_retry(client, server_url, &params, SCHEDULED_RETRIES).await?;
+    let task_id = parse_task_id(&body);
+
+    let mut report = json!({
+        "ok": true,
+        "server": server_url,
+        "test": params,
+        "task_id": task_id,
+        "response": body.trim(),
+        "status": "submitted",
+    });
+
+    if !wait.wait && wait.timeout.is_none() {
+        return Ok(Outcome::ok(report));
+    }
+
+    let task_id = task_id.ok_or_else(|| {
+        CliError::Process(format!("could not find a task id in the server response: {}", body.trim()))
+    })?;
+    let timeout = Duration::from_secs(wait.timeout.unwrap_or(params.duration as u64 + 60));
+
+    info!("Waiting up to {}s for task {} to finish...", timeout.as_secs(), task_id);
+    let (status, code) = match wait_for_task(client, server_url, &params.node, &task_id, timeout).await {
+        Ok(()) => ("completed", output::EXIT_OK),
+        Err(e) => {
+            info!("Task {}: {}", task_id, e);
+            report["error"] = json!(e.to_string());
+            let status = match e {
+                CliError::TaskFailed(_) => "failed",
+                CliError::Timeout(_) => "timeout",
+                _ => "unknown",
+            };
+            (status, e.exit_code())
+        }
+    };
+
+    if code == output::EXIT_OK {
+        info!("Task {} completed.", task_id);
+    }
+    report["ok"] = json!(code == output::EXIT_OK);
+    report["status"] = json!(status);
+    Ok(Outcome { json: report, code })
+}
+
+// Function to fetch node names from the server's /nodes endpoint ([{"name": "..."}])
+pub async fn fetch_nodes(client: &Client, server_url: &str) -> Result<Vec<String>, CliError> {
+    let response = client.get(format!("{}/nodes", server_url)).send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CliError::Status(status.as_u16(), body));
+    }
+
+    let nodes: Vec<Value> = serde_json::from_str(&body)
+        .map_err(|e| CliError::Process(format!("unexpected /nodes response: {}", e)))?;
+    Ok(nodes
+        .iter()
+        .filter_map(|n| n.get("name").and_then(Value::as_str).or_else(|| n.as_str()))
+        .map(str::to_string)
+        .collect())
+}
+
+// Function to extract the task id from an engine/controller start response
+// Handles both the plain-text "... started with ID: cpu-1" form and JSON bodies with a task_id/id field
+pub fn parse_task_id(body: &str) -> Option<String> {
+    if let Ok(value) = serde_json::from_str::<Value>(body) {
+        if let Some(id) = value.get("task_id").or_else(|| value.get("id")).and_then(Value::as_str) {
+            return Some(id.to_string());
+        }
+    }
+    body.rsplit_once("ID:")
+        .map(|(_, id)| id.trim().trim_matches('"').to_string())
+        .filter(|id| !id.is_empty())
+}
+
+// Function to poll the task list until the task disappears (finished), is reported failed, or time runs out
+pub async fn wait_for_task(client: &Client, server_url: &str, node: &str, task_id: &str, timeout: Duration) -> Result<(), CliError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let tasks = fetch_tasks(client, server_url, node).await?;
+        match tasks.iter().find(|(id, _)| id == task_id) {
+            None => return Ok(()),
+            Some((_, Some(state))) if is_failed_state(state) => {
+                return Err(CliError::TaskFailed(format!("task {} ended in state '{}'", task_id, state)))
+            }
+            Some(_) => {}
+        }
+
+        if Instant::now() >= deadline {
+            return Err(CliError::Timeout(format!("task {} still running after {}s", task_id, timeout.as_secs())));
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+// Function to fetch (task id, state) pairs from the server
+// Engines answer GET /tasks directly; the controller exposes POST /tasks/{node}
+pub async fn fetch_tasks(client: &Client, server_url: &str, node: &str) -> Result<Vec<(String, Option<String>)>, CliError> {
+    let mut response = client.get(format!("{}/tasks", server_url)).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND || response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        response = client.post(format!("{}/tasks/{}", server_url, node)).send().await?;
+    }
+
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CliError::Status(status.as_u16(), body));
+    }
+
+    let tasks: Vec<Value> = serde_json::from_str(&body)
+        .map_err(|e| CliError::Process(format!("unexpected task list response: {}", e)))?;
+
+    // Tasks are either bare id strings or objects with id/state fields
+    Ok(tasks
+        .iter()
+        .filter_map(|task| match task {
+            Value::String(id) => Some((id.clone(), None)),
+            Value::Object(obj) => {
+                let id = obj.get("id").or_else(|| obj.get("task_id")).and_then(Value::as_str)?;
+                let state = obj.get("state").or_else(|| obj.get("status")).and_then(Value::as_str);
+                Some((id.to_string(), state.map(str::to_string)))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+// Function to fetch the hardware report (sys_info JSON) of the target node
+// Engines answer GET /sysinfo directly; the controller exposes GET /sysinfo/{node}
+pub async fn fetch_system_info(client: &Client, server_url: &str, node: &str) -> Result<String, CliError> {
+    let mut response = client.get(format!("{}/sysinfo", server_url)).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        response = client.get(format!("{}/sysinfo/{}", server_url, node)).send().await?;
+    }
+
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CliError::Status(status.as_u16(), body));
+    }
+    Ok(body)
+}
+
+// Function to fetch a finished task's result from the engine's /results/{id},
+// falling back to the controller's /results/{node}/{id}
+pub async fn fetch_task_result(client: &Client, server_url: &str, node: &str, task_id: &str) -> Result<Value, CliError> {
+    let mut response = client.get(format!("{}/results/{}", server_url, task_id)).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        response = client.get(format!("{}/results/{}/{}", server_url, node, task_id)).send().await?;
+    }
+
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CliError::Status(status.as_u16(), body));
+    }
+    serde_json::from_str(&body).map_err(|e| CliError::Process(format!("unexpected task result response: {}", e)))
+}
+
+// Function to decide whether a reported task state means the task failed
+fn is_failed_state(state: &str) -> bool {
+    matches!(state.to_lowercase().as_str(), "failed" | "error" | "killed" | "aborted")
+}
+
+// Function to convert a template into runnable test parameters with a fresh id
+fn params_from_template(template: &Template, default_node: &str) -> TestParams {
+    TestParams {
+        id: Uuid::new_v4().to_string(),
+        name: template.name.clone(),
+        test_type: template.test_type.clone(),
+        threads: template.threads,
+        duration: template.duration,
+        load: template.load,
+        size: template.size,
+        fork: template.fork,
+        scheduled_time: None,
+        node: template.node.clone().unwrap_or_else(|| default_node.to_string()),
+    }
+}
+
+// Function to capture collected test parameters as a template
+fn template_from_params(name: &str, params: &TestParams, node: Option<String>) -> Template {
+    Template {
+        name: name.to_string(),
+        test_type: params.test_type.clone(),
+        threads: params.threads,
+        duration: params.duration,
+        load: params.load,
+        size: params.size,
+        fork: params.fork,
+        node,
+        created_at: None,
+    }
+}
@@ -0,0 +1,75 @@
+// CliError - Error type shared by all CLI operations
+// Replaces the unwraps on stdin, runtime creation and HTTP calls so failures
+// can be reported (and retried where it makes sense) instead of panicking
+use std::fmt;
+use std::io;
+
+use crate::output;
+
+#[derive(Debug)]
+pub enum CliError {
+    Eof,                     // stdin was closed (Ctrl-D or end of piped input)
+    Io(io::Error),           // Reading stdin / writing stdout failed
+    Runtime(io::Error),      // Creating the Tokio runtime failed
+    Http(reqwest::Error),    // Network-level failure talking to the server
+    Status(u16, String),     // Server answered with a non-success status (code, body)
+    Process(String),         // Running an external helper (e.g. mogAI.py) failed
+    Usage(String),           // Invalid combination of command-line arguments
+    TaskFailed(String),      // The server reported the submitted task as failed
+    Timeout(String),         // The submitted task did not finish in time
+}
+
+impl CliError {
+    // Whether retrying the same request has a reasonable chance of succeeding
+    // Connection failures, timeouts and 5xx/429 responses are treated as transient
+    pub fn is_transient(&self) -> bool {
+        match self {
+            CliError::Http(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            CliError::Status(code, _) => *code == 429 || *code >= 500,
+            _ => false,
+        }
+    }
+
+    // Process exit code for this error (see output::EXIT_*)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Http(_) | CliError::Status(_, _) => output::EXIT_SUBMIT_FAILED,
+            CliError::TaskFailed(_) => output::EXIT_TASK_FAILED,
+            CliError::Timeout(_) => output::EXIT_TIMEOUT,
+            _ => output::EXIT_ERROR,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Eof => write!(f, "input closed"),
+            CliError::Io(e) => write!(f, "I/O error: {}", e),
+            CliError::Runtime(e) => write!(f, "failed to start async runtime: {}", e),
+            CliError::Http(e) if e.is_timeout() => write!(f, "request timed out: {}", e),
+            CliError::Http(e) if e.is_connect() => write!(f, "could not connect to server: {}", e),
+            CliError::Http(e) => write!(f, "request failed: {}", e),
+            CliError::Status(code, body) if body.trim().is_empty() => write!(f, "server returned status {}", code),
+            CliError::Status(code, body) => write!(f, "server returned status {}: {}", code, body.trim()),
+            CliError::Process(msg) => write!(f, "{}", msg),
+            CliError::Usage(msg) => write!(f, "{}", msg),
+            CliError::TaskFailed(msg) => write!(f, "task failed: {}", msg),
+            CliError::Timeout(msg) => write!(f, "timed out: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for CliError {
+    fn from(e: reqwest::Error) -> Self {
+        CliError::Http(e)
+    }
+}
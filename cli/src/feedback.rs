@@ -0,0 +1,120 @@
+// AI feedback loop - what an AI-generated round actually achieved
+// After a round runs, each test's result from the engine (/results) is collected
+// and sent back to the recommender with the next request, so it can adjust the
+// parameters instead of guessing from the hardware report alone
+use serde::Serialize;
+use serde_json::Value;
+
+// One test of a round: the parameters that were run and what they achieved
+#[derive(Debug, Serialize)]
+pub struct RoundResult {
+    pub test_type: String,
+    pub threads: u32,
+    pub duration: u32,
+    pub load: Option<u32>,
+    pub size: Option<u32>,
+    pub fork: Option<bool>,
+    pub status: String,             // completed, failed, timeout or not started
+    pub achieved_load: Option<f64>, // Measured CPU % per thread (cpu)
+    pub cpu_load: Option<f64>,      // CPU time % per thread the kernel gave; lower than achieved_load when throttled (cpu)
+    pub mb_per_s: Option<f64>,      // Disk throughput
+    pub oom_kills: Option<u64>,     // Processes killed by the kernel OOM killer (mem)
+    pub errors: Vec<String>,
+}
+
+impl RoundResult {
+    // Function to record a test of the plan before it runs
+    pub fn planned(test_type: &str, threads: u32, duration: u32, load: Option<u32>, size: Option<u32>, fork: Option<bool>) -> Self {
+        RoundResult {
+            test_type: test_type.to_string(),
+            threads,
+            duration,
+            load,
+            size,
+            fork,
+            status: "not started".to_string(),
+            achieved_load: None,
+            cpu_load: None,
+            mb_per_s: None,
+            oom_kills: None,
+            errors: Vec::new(),
+        }
+    }
+
+    // Function to fill in an engine task result ({"result": {...}} or {"error": {...}})
+    pub fn apply(&mut self, task_result: &Value) {
+        if let Some(error) = task_result.get("error").filter(|e| !e.is_null()) {
+            self.status = "failed".to_string();
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            self.errors.push(message.to_string());
+            return;
+        }
+        let Some(result) = task_result.get("result") else {
+            return;
+        };
+        self.achieved_load = result.get("achieved_load").and_then(Value::as_f64);
+        self.cpu_load = result.get("cpu_load").and_then(Value::as_f64);
+        self.mb_per_s = result.get("mb_per_s").and_then(Value::as_f64);
+        self.oom_kills = result.get("oom_kills").and_then(Value::as_u64);
+        if let Some(errors) = result.get("errors").and_then(Value::as_array) {
+            self.errors.extend(errors.iter().filter_map(Value::as_str).map(str::to_string));
+        }
+    }
+
+    // Function to describe the outcome in one line for the round summary
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.status.clone()];
+        if let Some(load) = self.achieved_load {
+            match self.load {
+                Some(target) => parts.push(format!("CPU {:.1}% of {}% target", load, target)),
+                None => parts.push(format!("CPU {:.1}%", load)),
+            }
+        }
+        // Busy time the kernel did not turn into CPU time: throttled or preempted
+        if let Some(cpu) = self.cpu_load.filter(|&cpu| self.achieved_load.is_some_and(|load| load - cpu > 5.0)) {
+            parts.push(format!("only {:.1}% CPU time (throttled?)", cpu));
+        }
+        if let Some(rate) = self.mb_per_s {
+            parts.push(format!("{:.1} MB/s", rate));
+        }
+        if let Some(kills) = self.oom_kills.filter(|&k| k > 0) {
+            parts.push(format!("{} OOM kill(s)", kills));
+        }
+        if !self.errors.is_empty() {
+            parts.push(format!("errors: {}", self.errors.join("; ")));
+        }
+        format!(
+            "{} x{} for {}s: {}",
+            self.test_type.to_uppercase(),
+            self.threads,
+            self.duration,
+            parts.join(", ")
+        )
+    }
+}
+
+// Function to build the recommender input: intensity and system info (as before),
+// followed by the previous round's results once there are any
+pub fn prompt(intensity: u32, system_info: &str, round: u32, previous: &[RoundResult]) -> String {
+    let mut text = format!("{{intensity: {}}}\n{}", intensity, system_info);
+    if previous.is_empty() {
+        return text;
+    }
+
+    let results = serde_json::to_string_pretty(previous).unwrap_or_default();
+    text.push_str(&format!(
+        "\n\nResults of round {} (the tests you proposed, as they ran on this node):\n{}\n\
+         Plan round {} from these results: raise threads or load where the achieved CPU % fell short \
+         of the target, shrink memory sizes that caused OOM kills or failures, and adjust disk sizes \
+         to the measured MB/s. Include an \"intensity\" field (1-10) reflecting the next round.",
+        round - 1,
+        results,
+        round
+    ));
+    text
+}
+
+// Function to pick the next round's intensity from the AI's suggestions (highest valid value)
+pub fn next_intensity(suggestions: impl Iterator<Item = u32>, current: u32) -> u32 {
+    suggestions.filter(|i| (1..=10).contains(i)).max().unwrap_or(current)
+}
@@ -0,0 +1,50 @@
+// Input helpers - prompt the user and read a line from stdin
+// All helpers return CliError::Eof when stdin is closed so callers can exit
+// cleanly instead of looping on empty input when the CLI is driven by a pipe
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::error::CliError;
+
+// Read a single trimmed line from stdin
+pub fn read_line() -> Result<String, CliError> {
+    let mut line = String::new();
+    let bytes = io::stdin().read_line(&mut line)?;
+    if bytes == 0 {
+        return Err(CliError::Eof);
+    }
+    Ok(line.trim().to_string())
+}
+
+// Print a prompt (without newline) and read the answer
+pub fn prompt(message: &str) -> Result<String, CliError> {
+    print!("{}", message);
+    io::stdout().flush()?;
+    read_line()
+}
+
+// Prompt for a value of type T, re-prompting on invalid input
+// An empty answer selects the default
+pub fn prompt_parse<T: FromStr>(message: &str, default: T) -> Result<T, CliError> {
+    loop {
+        let answer = prompt(message)?;
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match answer.parse() {
+            Ok(value) => return Ok(value),
+            Err(_) => println!("Invalid value '{}'. Please try again (blank for default).", answer),
+        }
+    }
+}
+
+// Prompt for a yes/no answer; anything starting with 'y' counts as yes
+pub fn confirm(message: &str) -> Result<bool, CliError> {
+    Ok(prompt(message)?.to_lowercase().starts_with('y'))
+}
+
+// Wait for the user to press Enter
+pub fn pause(message: &str) -> Result<(), CliError> {
+    println!("{}", message);
+    read_line().map(|_| ())
+}
@@ -10,16 +10,34 @@
 // - serde - For serializing/deserializing data structures
 // - uuid - For generating unique identifiers
 // - std::process - For executing external commands
+// - clap - For parsing command-line subcommands and flags
 use std::io::{self, Write};
-use std::sync::{Arc, Mutex};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
 use tokio::runtime::Runtime;
 use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use chrono::{Local, NaiveTime, TimeZone};
+use chrono::{Local, TimeZone, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use clap::Parser;
+
+mod commands;
+mod error;
+mod feedback;
+mod input;
+mod output;
+mod picker;
+mod report;
+mod schedule;
+mod template;
+mod top;
+use commands::Cli;
+use error::CliError;
+use output::info;
+use schedule::ScheduleZone;
 
 // TestParams structure - Defines the parameters for a stress test
 // This structure stores all possible configuration options for any type of test
@@ -67,11 +85,35 @@ struct AiResponse {
     #[serde(default)]    // Default to None if not provided
     fork: Option<bool>,  // Whether to fork processes (Optional)
     #[serde(default)]    // Default to 0 if not provided
-    intensity: u32,      // Intensity level from AI recommendation - ignored on purpose
+    intensity: u32,      // Intensity the AI suggests for the next round (1-10, 0 = no suggestion)
 }
 
 // Main function - Entry point of the application
 fn main() {
+    let cli = Cli::parse();
+    output::set_json(cli.json);
+
+    // Run a subcommand if one was given, otherwise start the interactive menu
+    let server_url = cli.server.clone().unwrap_or_else(|| "http://localhost:8080".to_string());
+    let result = match cli.command {
+        Some(command) => commands::execute(command, server_url.trim_end_matches('/')),
+        None if cli.json => Err(CliError::Usage("--json requires a subcommand (e.g. run, template)".to_string())),
+        None => match run(cli.server) {
+            // stdin closing (Ctrl-D / end of piped input) is a normal exit
+            Ok(()) => return,
+            Err(CliError::Eof) => {
+                println!("\nExiting program. Goodbye!");
+                return;
+            }
+            Err(e) => Err(e),
+        },
+    };
+
+    std::process::exit(output::finish(result));
+}
+
+// Interactive session - shows the menu until the user exits or stdin is closed
+fn run(server: Option<String>) -> Result<(), CliError> {
     // Display an ASCII art logo and welcome message
     // This provides a visual identity to the CLI tool
     println!(
@@ -116,12 +158,16 @@ fn main() {
          You can schedule tests, view them, or change server settings.\n"
     );
 
-    // Prompt user for server URL with a default of http://localhost:8080
-    let mut server_url = get_server_url();
+    // Prompt user for server URL with a default of http://localhost:8080 (unless given with --server)
+    let mut server_url = match server {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => get_server_url()?,
+    };
     println!("\nUsing server at: {}\n", server_url);
 
-    // Set a default node for tests to run on (in this case, minikube) - unused mut on purpose
-    let mut default_node = "minikube";
+    // Nodes new tests run on (minikube to begin with), changed through menu option 4
+    // Shared with the scheduler thread, which shows them when it redraws the menu
+    let default_nodes = Arc::new(Mutex::new(vec!["minikube".to_string()]));
 
     // Create a shared collection for scheduled tests
     // Arc provides thread-safe reference counting, allowing multiple threads to safely access the data
@@ -131,24 +177,34 @@ fn main() {
     // Start a background thread to monitor and execute scheduled tests
     // This thread runs continuously and checks if any tests are due to run
     let tests_to_run = Arc::clone(&scheduled_tests);
+    let nodes_shown = Arc::clone(&default_nodes);
     let server_url_clone = server_url.clone();
     let _execution_thread = thread::spawn(move || {
         // Create a Tokio runtime for handling async operations within this thread
-        let rt = Runtime::new().unwrap();
+        let rt = match Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => {
+                println!("\nScheduler disabled: {}", CliError::Runtime(e));
+                return;
+            }
+        };
         rt.block_on(async {
             // Create an HTTP client with a timeout for API requests
-            let client = Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .unwrap();
+            let client = match build_client(30) {
+                Ok(client) => client,
+                Err(e) => {
+                    println!("\nScheduler disabled: {}", e);
+                    return;
+                }
+            };
 
             // Continuous loop to check for and execute scheduled tests
             loop {
                 // Get current time as Unix timestamp
                 let current_time = SystemTime::now()
                     .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
                 let mut tests_to_execute = Vec::new();
 
                 // Check for tests that are ready to run:
@@ -156,7 +212,7 @@ fn main() {
                 // - Tests with scheduled_time should run if current_time has reached that time
                 {
                     // Lock the shared collection to safely modify it
-                    let mut tests = tests_to_run.lock().unwrap();
+                    let mut tests = lock_tests(&tests_to_run);
                     let mut i = 0;
                     while i < tests.len() {
                         if let Some(scheduled_time) = tests[i].scheduled_time {
@@ -180,24 +236,24 @@ fn main() {
                     let client_clone = client.clone();
                     let url_clone = server_url_clone.clone();
                     let test_clone = test.clone();
+                    let nodes_clone = Arc::clone(&nodes_shown);
                     
                     // Spawn an async task for each test
                     let handle = tokio::spawn(async move {
-                        // Run the test and wait for it to complete
-                        run_test(&client_clone, &url_clone, &test_clone).await;
-                        println!("\nTest completed. Returning to main menu...");
+                        // Run the test, retrying transient network failures automatically
+                        // (the scheduler runs alongside the menu, so it can't prompt)
+                        match run_test_with_retry(&client_clone, &url_clone, &test_clone, SCHEDULED_RETRIES).await {
+                            Ok(_) => println!("\nTest completed. Returning to main menu..."),
+                            Err(e) => {
+                                println!("\nTest '{}' failed: {}", test_clone.name, e);
+                                println!("Troubleshooting: Check if the server is running at {}", url_clone);
+                            }
+                        }
                         
                         // Display the menu again after test completion
-                        println!("\n----------------------------------------------");
-                        println!("Main Menu:");
-                        println!("1. Schedule a new test");
-                        println!("2. View scheduled tests");
-                        println!("3. Change server URL (current: {})", url_clone);
-                        println!("4. Change default node (default: minikube)");
-                        println!("5. Run AI test");
-                        println!("6. Exit");
+                        print_menu(&url_clone, &lock_nodes(&nodes_clone));
                         print!("Enter your choice (1-6): ");
-                        io::stdout().flush().unwrap();
+                        let _ = io::stdout().flush();
         
                     });
                     handles.push(handle);
@@ -218,123 +274,223 @@ fn main() {
     // The loop continues until the user chooses to exit
     loop {
         // Display menu options
-        println!("\n----------------------------------------------");
-        println!("Main Menu:");
-        println!("1. Schedule a new test");
-        println!("2. View scheduled tests");
-        println!("3. Change server URL (current: {})", server_url);
-        println!("4. Change default node (default: {})", default_node);
-        println!("5. Run AI test");
-        println!("6. Exit");
-        print!("Enter your choice (1-6): ");
-        io::stdout().flush().unwrap();
+        let nodes = lock_nodes(&default_nodes).clone();
+        print_menu(&server_url, &nodes);
 
         // Read user input
-        let mut choice = String::new();
-        io::stdin().read_line(&mut choice).unwrap();
+        let choice = input::prompt("Enter your choice (1-6): ")?;
 
         // Process the user's selection
-        match choice.trim() {
+        // Errors from individual actions are reported and the menu is shown again;
+        // only a closed stdin (Ctrl-D / end of piped input) ends the session
+        match choice.as_str() {
             "1" => {
-                // Schedule a new test by collecting parameters and adding to the scheduled list
-                if let Some(test_params) = collect_test_params(default_node) {
-                    scheduled_tests.lock().unwrap().push(test_params);
+                // Schedule a new test by collecting parameters and adding to the scheduled list,
+                // once for every default node
+                if let Some(test_params) = collect_test_params(&nodes[0])? {
+                    let mut tests = lock_tests(&scheduled_tests);
+                    for node in &nodes[1..] {
+                        tests.push(TestParams {
+                            id: Uuid::new_v4().to_string(),
+                            node: node.clone(),
+                            ..test_params.clone()
+                        });
+                    }
+                    tests.push(test_params);
                 }
             }
             "2" => {
                 // View all currently scheduled tests
-                let tests = scheduled_tests.lock().unwrap();
-                if tests.is_empty() {
-                    println!("\nNo tests currently scheduled.");
-                } else {
-                    println!("\n=== Scheduled Tests ===");
-                    for (i, test) in tests.iter().enumerate() {
-                        // Display scheduled time if present, otherwise show "Run immediately"
-                        if let Some(time) = test.scheduled_time {
+                {
+                    let tests = lock_tests(&scheduled_tests);
+                    if tests.is_empty() {
+                        println!("\nNo tests currently scheduled.");
+                    } else {
+                        println!("\n=== Scheduled Tests ===");
+                        for (i, test) in tests.iter().enumerate() {
+                            // Display scheduled time if present, otherwise show "Run immediately"
                             // Convert Unix timestamp to human-readable format
-                            let dt = Local.timestamp_opt(time as i64, 0).unwrap();
-                            println!(
-                                "\n{}. [{}] {} Test - Duration: {}s - Scheduled for: {}",
-                                i + 1,
-                                test.id,
-                                test.test_type.to_uppercase(),
-                                test.duration,
-                                dt.format("%Y-%m-%d %H:%M:%S")
-                            );
-                        } else {
-                            println!(
-                                "\n{}. [{}] {} Test - Duration: {}s - Run immediately",
-                                i + 1,
-                                test.id,
-                                test.test_type.to_uppercase(),
-                                test.duration
-                            );
+                            if let Some(dt) = test.scheduled_time.and_then(|time| Local.timestamp_opt(time as i64, 0).single()) {
+                                println!(
+                                    "\n{}. [{}] {} Test - Duration: {}s - Scheduled for: {}",
+                                    i + 1,
+                                    test.id,
+                                    test.test_type.to_uppercase(),
+                                    test.duration,
+                                    dt.format("%Y-%m-%d %H:%M:%S %Z")
+                                );
+                            } else {
+                                println!(
+                                    "\n{}. [{}] {} Test - Duration: {}s - Run immediately",
+                                    i + 1,
+                                    test.id,
+                                    test.test_type.to_uppercase(),
+                                    test.duration
+                                );
+                            }
                         }
                     }
                 }
                 
                 // Pause for user to review the list before returning to menu
-                println!("\nPress Enter to return to the main menu...");
-                let mut _pause = String::new();
-                io::stdin().read_line(&mut _pause).unwrap();
+                input::pause("\nPress Enter to return to the main menu...")?;
             },
             "3" => {
                 // Change the server URL
-                server_url = get_server_url();
+                server_url = get_server_url()?;
                 println!("\nServer URL changed to: {}", server_url);
             }
             "4" => {
-                // View and change the default node
-                select_default_node(&server_url);
+                // Pick the default nodes from the server's node list
+                report_error(select_default_nodes(&server_url, &default_nodes))?;
             }
             "5" => {
-                // Run an AI-generated test battery
-                run_ai_test(&server_url);
+                // Run an AI-generated test battery (on the first default node)
+                report_error(run_ai_test(&server_url, &nodes[0]))?;
             }
             "6" => {
                 // Exit the program
                 println!("\nExiting program. Goodbye!");
-                std::process::exit(0);
+                return Ok(());
             }
             _ => println!("\nInvalid choice. Please enter a number between 1 and 6."),
         }
     }
 }
 
+// Number of automatic attempts for scheduled tests before giving up
+const SCHEDULED_RETRIES: u32 = 3;
+
+// Function to display the main menu options
+fn print_menu(server_url: &str, default_nodes: &[String]) {
+    println!("\n----------------------------------------------");
+    println!("Main Menu:");
+    println!("1. Schedule a new test");
+    println!("2. View scheduled tests");
+    println!("3. Change server URL (current: {})", server_url);
+    match default_nodes {
+        [node] => println!("4. Change default nodes (default: {})", node),
+        nodes => println!("4. Change default nodes (default: {} nodes: {})", nodes.len(), nodes.join(", ")),
+    }
+    println!("5. Run AI test");
+    println!("6. Exit");
+}
+
+// Function to lock the scheduled test list
+// A panic in another thread while holding the lock shouldn't take the menu down with it
+fn lock_tests(tests: &Mutex<Vec<TestParams>>) -> MutexGuard<'_, Vec<TestParams>> {
+    tests.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Function to lock the default node list
+fn lock_nodes(nodes: &Mutex<Vec<String>>) -> MutexGuard<'_, Vec<String>> {
+    nodes.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Function to report a non-fatal error from a menu action
+// Passes CliError::Eof through so a closed stdin still ends the session
+fn report_error(result: Result<(), CliError>) -> Result<(), CliError> {
+    match result {
+        Err(CliError::Eof) => Err(CliError::Eof),
+        Err(e) => {
+            println!("\nError: {}", e);
+            if matches!(e, CliError::Http(_) | CliError::Status(_, _)) {
+                println!("Request ID: {}", request_id());
+            }
+            Ok(())
+        }
+        Ok(()) => Ok(()),
+    }
+}
+
+// Function to build an HTTP client with the given timeout in seconds
+fn build_client(timeout_secs: u64) -> Result<Client, CliError> {
+    Ok(Client::builder()
+        .timeout(Duration::from_secs(timeout_secs))
+        .default_headers(identity_headers())
+        .build()?)
+}
+
+// Function to get the request id sent with every call of this run, so the controller and
+// engine logs of a failure can be found: MOGWAI_REQUEST_ID, or a new id per run
+pub fn request_id() -> &'static str {
+    static REQUEST_ID: OnceLock<String> = OnceLock::new();
+    REQUEST_ID.get_or_init(|| {
+        std::env::var("MOGWAI_REQUEST_ID")
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| Uuid::new_v4().simple().to_string())
+    })
+}
+
+// Function to build the headers identifying this user to the engines, which only
+// list and stop the caller's own tasks (see mogwai_identity), plus X-Request-Id
+// (see request_id)
+fn identity_headers() -> reqwest::header::HeaderMap {
+    let mut headers = mogwai_identity::headers();
+    if let Ok(value) = reqwest::header::HeaderValue::from_str(request_id()) {
+        headers.insert("x-request-id", value);
+    }
+    headers
+}
+
+// Function to create a Tokio runtime for blocking on async requests from the menu
+fn new_runtime() -> Result<Runtime, CliError> {
+    Runtime::new().map_err(CliError::Runtime)
+}
+
+// Function to run a request, asking the user whether to retry on transient network errors
+// Non-transient errors (bad status codes, invalid input) are returned immediately
+fn with_retry_prompt<T, F, Fut>(rt: &Runtime, action: &str, mut request: F) -> Result<T, CliError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, CliError>>,
+{
+    loop {
+        match rt.block_on(request()) {
+            Err(e) if e.is_transient() => {
+                println!("\n{} failed: {}", action, e);
+                if !input::confirm("Retry? (y/n): ")? {
+                    return Err(e);
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
 // Function to prompt the user for a server URL
 // Returns the user-provided URL or a default URL if none specified
-fn get_server_url() -> String {
-    print!("Enter server URL (default: http://localhost:8080): ");
-    // Flush to ensure the prompt is displayed before waiting for input
-    io::stdout().flush().unwrap();
-
-    // Read user input
-    let mut url = String::new();
-    io::stdin().read_line(&mut url).unwrap();
-
-    // Trim whitespace and return appropriate URL
-    let url = url.trim();
-    if url.is_empty() {
-        // Return default if nothing entered
-        "http://localhost:8080".to_string()
-    } else {
-        url.to_string()
+fn get_server_url() -> Result<String, CliError> {
+    loop {
+        // Read user input
+        let url = input::prompt("Enter server URL (default: http://localhost:8080): ")?;
+
+        // Return appropriate URL
+        if url.is_empty() {
+            // Return default if nothing entered
+            return Ok("http://localhost:8080".to_string());
+        }
+
+        // Reject obviously malformed URLs instead of failing on every request later
+        match reqwest::Url::parse(&url) {
+            Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {
+                return Ok(url.trim_end_matches('/').to_string())
+            }
+            _ => println!("Invalid URL '{}'. Expected something like http://localhost:8080", url),
+        }
     }
 }
 
 // Function to collect test parameters from the user
 // Returns a TestParams structure if successful, or None if the user cancels
-fn collect_test_params(default_node: &str) -> Option<TestParams> {
+fn collect_test_params(default_node: &str) -> Result<Option<TestParams>, CliError> {
     // Generate a unique test ID using UUID v4
     // This ensures each test has a globally unique identifier
     let id = Uuid::new_v4().to_string();
 
     // Get the test name from the user
-    print!("Enter a name for this test: ");
-    io::stdout().flush().unwrap();
-    let mut name = String::new();
-    io::stdin().read_line(&mut name).unwrap();
-    let name = name.trim().to_string();
+    let name = input::prompt("Enter a name for this test: ")?;
     // If no name provided, create a default name using part of the UUID
     let name = if name.is_empty() {
         format!("Test-{}", &id[0..8])
@@ -342,26 +498,77 @@ fn collect_test_params(default_node: &str) -> Option<TestParams> {
         name
     };
 
+    // Collect the test type and its parameters
+    let mut params = match prompt_test_settings(id, name, default_node)? {
+        Some(params) => params,
+        None => return Ok(None),
+    };
+
+    // Option to schedule the test for a specific time
+    if input::confirm("Schedule this test for a specific time? (y/n): ")? {
+        loop {
+            // Accept time-only, full date-time, RFC3339 or a relative offset
+            let time_str = input::prompt("Enter time (HH:MM, YYYY-MM-DD HH:MM, RFC3339, or +2h/+30m; blank to run now): ")?;
+
+            if time_str.is_empty() {
+                println!("\nNo time entered. Test will run immediately.");
+                break;
+            }
+
+            // Only ask for a timezone when the input doesn't carry its own offset
+            let zone = if schedule::needs_timezone(&time_str) {
+                let tz_str = input::prompt("Enter timezone (e.g. UTC, Europe/Berlin, +02:00; blank for local): ")?;
+                match schedule::parse_timezone(&tz_str) {
+                    Ok(zone) => zone,
+                    Err(e) => {
+                        println!("\n{}. Please try again.", e);
+                        continue;
+                    }
+                }
+            } else {
+                ScheduleZone::Local
+            };
+
+            // Parse and validate that the scheduled time is in the future
+            match schedule::parse_schedule(&time_str, zone, Utc::now()) {
+                Ok(scheduled) => {
+                    params.scheduled_time = Some(scheduled.timestamp() as u64);
+                    println!(
+                        "\nTest scheduled for {} (timezone: {}). Returning to the main menu...",
+                        scheduled.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %Z"),
+                        zone
+                    );
+                    break;
+                }
+                Err(e) => println!("\n{}. Please try again.", e),
+            }
+        }
+    }
+
+    // Return the completed test parameters
+    Ok(Some(params))
+}
+
+// Function to prompt for the test type and its type-specific parameters
+// Returns None if the user picks an invalid test type
+fn prompt_test_settings(id: String, name: String, default_node: &str) -> Result<Option<TestParams>, CliError> {
     // Display test type selection menu
     println!("\nWhich test do you want to run?");
     println!("1. CPU");
     println!("2. Memory");
     println!("3. Disk");
-    print!("Enter your choice (1-3): ");
-    io::stdout().flush().unwrap();
 
     // Read test type selection
-    let mut test_type = String::new();
-    io::stdin().read_line(&mut test_type).unwrap();
+    let test_type = input::prompt("Enter your choice (1-3): ")?;
 
     // Convert numeric choice to actual test type string
-    let test_type = match test_type.trim() {
+    let test_type = match test_type.as_str() {
         "1" => "cpu",
         "2" => "mem",
         "3" => "disk",
         _ => {
             println!("\nInvalid choice. Returning to main menu.");
-            return None;
+            return Ok(None);
         }
     };
 
@@ -383,212 +590,246 @@ fn collect_test_params(default_node: &str) -> Option<TestParams> {
     // This would be a future enhancement to let users customize the node without changing the default
 
     // Get test duration - common for all test types
-    print!("Enter test duration (in seconds): ");
-    io::stdout().flush().unwrap();
-    let mut duration = String::new();
-    io::stdin().read_line(&mut duration).unwrap();
-    // Parse as u32 or default to 60 seconds if invalid
-    params.duration = duration.trim().parse().unwrap_or(60);
+    // Re-prompts on invalid input, defaults to 60 seconds if left blank
+    params.duration = input::prompt_parse("Enter test duration (in seconds): ", 60)?;
 
     // Collect parameters specific to each test type
     match test_type {
         "cpu" => {
             // CPU test needs thread count, load percentage, and fork option
-            print!("Enter number of threads: ");
-            io::stdout().flush().unwrap();
-            let mut threads = String::new();
-            io::stdin().read_line(&mut threads).unwrap();
-            params.threads = Some(threads.trim().parse().unwrap_or(1));
-
-            print!("Enter CPU load (percentage): ");
-            io::stdout().flush().unwrap();
-            let mut load = String::new();
-            io::stdin().read_line(&mut load).unwrap();
-            params.load = Some(load.trim().parse().unwrap_or(50));
-
-            print!("Enable fork? (y/n): ");
-            io::stdout().flush().unwrap();
-            let mut fork = String::new();
-            io::stdin().read_line(&mut fork).unwrap();
-            params.fork = Some(fork.trim().to_lowercase() == "y");
+            params.threads = Some(input::prompt_parse("Enter number of threads: ", 1)?);
+            loop {
+                let load: u32 = input::prompt_parse("Enter CPU load (percentage): ", 50)?;
+                if (1..=100).contains(&load) {
+                    params.load = Some(load);
+                    break;
+                }
+                println!("CPU load must be between 1 and 100.");
+            }
+            params.fork = Some(input::confirm("Enable fork? (y/n): ")?);
         }
         "mem" => {
             // Memory test needs thread count and memory size
-            print!("Enter number of threads: ");
-            io::stdout().flush().unwrap();
-            let mut threads = String::new();
-            io::stdin().read_line(&mut threads).unwrap();
-            params.threads = Some(threads.trim().parse().unwrap_or(1));
-
-            print!("Enter memory size (in MB): ");
-            io::stdout().flush().unwrap();
-            let mut size = String::new();
-            io::stdin().read_line(&mut size).unwrap();
-            params.size = Some(size.trim().parse().unwrap_or(100));
+            params.threads = Some(input::prompt_parse("Enter number of threads: ", 1)?);
+            params.size = Some(input::prompt_parse("Enter memory size (in MB): ", 100)?);
         }
         "disk" => {
             // Disk test needs thread count and disk size
-            print!("Enter number of threads: ");
-            io::stdout().flush().unwrap();
-            let mut threads = String::new();
-            io::stdin().read_line(&mut threads).unwrap();
-            params.threads = Some(threads.trim().parse().unwrap_or(1));
-
-            print!("Enter disk size (in MB): ");
-            io::stdout().flush().unwrap();
-            let mut size = String::new();
-            io::stdin().read_line(&mut size).unwrap();
-            params.size = Some(size.trim().parse().unwrap_or(100));
+            params.threads = Some(input::prompt_parse("Enter number of threads: ", 1)?);
+            params.size = Some(input::prompt_parse("Enter disk size (in MB): ", 100)?);
         }
         _ => unreachable!(), // This should never happen due to previous validation
     }
 
-    // Option to schedule the test for a specific time
-    print!("Schedule this test for a specific time? (y/n): ");
-    io::stdout().flush().unwrap();
-    let mut schedule = String::new();
-    io::stdin().read_line(&mut schedule).unwrap();
-
-    if schedule.trim().to_lowercase() == "y" {
-        // Get time in HH:MM format
-        print!("Enter time (HH:MM): ");
-        io::stdout().flush().unwrap();
-        let mut time_str = String::new();
-        io::stdin().read_line(&mut time_str).unwrap();
-
-        // Parse the input time using chrono's time parser
-        if let Ok(time) = NaiveTime::parse_from_str(&time_str.trim(), "%H:%M") {
-            // Get current date and time
-            let now = Local::now();
-            // Combine today's date with the specified time
-            let mut scheduled_datetime = now.date_naive().and_time(time);
-            
-            // If the scheduled time has already passed today, schedule for tomorrow
-            if scheduled_datetime < now.naive_local() {
-                scheduled_datetime += chrono::Duration::days(1);
-            }
-            
-            // Convert to Unix timestamp (seconds since epoch)
-            let scheduled_timestamp = Local
-                .from_local_datetime(&scheduled_datetime)
-                .unwrap()
-                .timestamp() as u64;
-                
-            params.scheduled_time = Some(scheduled_timestamp);
-            println!(
-                "\nTest scheduled for {} Returning to the main menu...",
-                scheduled_datetime.format("%Y-%m-%d %H:%M")
-            );
-        } else {
-            println!("\nInvalid time format. Test will run immediately.");
-        }
-    }
-
-    // Return the completed test parameters
-    Some(params)
+    Ok(Some(params))
 }
 
-// Function to display available nodes and select a default node
-// Note: This function currently only displays nodes but doesn't fully implement selection
-fn select_default_node(server_url: &str) {
+// Function to pick the default nodes from the server's node list
+// The list is searched as you type, so large clusters need no exact names (see picker.rs)
+fn select_default_nodes(server_url: &str, default_nodes: &Mutex<Vec<String>>) -> Result<(), CliError> {
     println!("\nFetching available nodes...");
     
-    // Create a Tokio runtime for async HTTP request
-    let rt = Runtime::new().unwrap();
-    let nodes_response = rt.block_on(async {
-        // Create HTTP client with timeout
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .unwrap();
-            
-        // Send GET request to retrieve nodes
-        client.get(&format!("{}/nodes", server_url))
-            .send()
-            .await
-    });
-    
-    // Note: There's a comment about adding filtering capabilities for large node lists
-    // This would be a future enhancement to handle systems with many nodes
-    
-    // Display the nodes response
-    match nodes_response { 
-        // Note: The comment mentions that the node format isn't ideal
-        // Current format is like [{"name":"minikube"},{"name":"minikube-m02"}]
-        // A future enhancement could parse and display this more neatly
-        Ok(response) => {
-            match rt.block_on(async { response.text().await }) {
-                Ok(nodes_text) => {
-                    println!("\nAvailable nodes:");
-                    println!("{}", nodes_text);
-                }
-                Err(e) => println!("Failed to parse nodes response: {}", e),
-            }
+    // Create a Tokio runtime and HTTP client (with timeout) for the async request
+    let rt = new_runtime()?;
+    let client = build_client(10)?;
+
+    // Send GET request to retrieve nodes, offering a retry on transient failures
+    let nodes = match with_retry_prompt(&rt, "Fetching nodes", || commands::fetch_nodes(&client, server_url)) {
+        Ok(nodes) => nodes,
+        Err(CliError::Eof) => return Err(CliError::Eof),
+        Err(e) => {
+            println!("Failed to fetch nodes: {}", e);
+            return Ok(());
         }
-        Err(e) => println!("Failed to fetch nodes: {}", e),
+    };
+    if nodes.is_empty() {
+        println!("\nThe server lists no nodes.");
+        return Ok(());
     }
-    
-    // Note: There's a comment about adding default node selection here
-    // This would be a future enhancement to allow changing the default_node
-    
-    // Pause for user to review the nodes before returning to menu
-    println!("\nPress Enter to return to the main menu...");
-    let mut _pause = String::new();
-    io::stdin().read_line(&mut _pause).unwrap();
+
+    let current = lock_nodes(default_nodes).clone();
+    match picker::pick("Default nodes", &nodes, &current)? {
+        Some(picked) if !picked.is_empty() => {
+            println!("\nDefault nodes: {}", picked.join(", "));
+            *lock_nodes(default_nodes) = picked;
+        }
+        _ => println!("\nDefault nodes unchanged."),
+    }
+    Ok(())
 }
 
 // Function to run an AI-generated battery of stress tests
-// This uses an external AI script (mogAI.py) to generate test configurations
-/// Run an AI-generated battery of stress tests by invoking mogAI.py,
-/// showing comments, confirming, then sending each JSON block to the server.
-fn run_ai_test(server_url: &str) {
+// This uses an external AI script (mogAI.py) to generate test configurations: it proposes
+// a battery, the user confirms, each test runs to completion, and the achieved metrics are
+// fed back to mogAI.py so the next round's parameters are adjusted to what the node did
+fn run_ai_test(server_url: &str, default_node: &str) -> Result<(), CliError> {
     // Generate a unique test ID for this AI test session
     let session_id = Uuid::new_v4().to_string();
     println!("\n=== AI Test Session: {} ===", &session_id[0..8]);
 
     // 1) Prompt user for intensity level (1-10)
-    print!("Enter intensity level (1-10): ");
-    io::stdout().flush().unwrap();
-    let mut intensity_input = String::new();
-    io::stdin().read_line(&mut intensity_input).unwrap();
-    let intensity: u32 = intensity_input.trim().parse().unwrap_or(5);
+    let mut intensity: u32 = loop {
+        let value = input::prompt_parse("Enter intensity level (1-10): ", 5)?;
+        if (1..=10).contains(&value) {
+            break value;
+        }
+        println!("Intensity must be between 1 and 10.");
+    };
     
-    println!("Running mogAI.py to generate tests with intensity {}...", intensity);
+    // 2) Fetch the hardware report of the target node from the server's /sysinfo endpoint
+    println!("Fetching system information from {}...", server_url);
+    let rt = new_runtime()?;
+    let client = build_client(30)?;
+    let system_info = with_retry_prompt(&rt, "Fetching system information", || {
+        commands::fetch_system_info(&client, server_url, default_node)
+    })?;
+
+    let mut previous: Vec<feedback::RoundResult> = Vec::new();
+    for round in 1.. {
+        println!("\n--- Round {} (intensity {}) ---", round, intensity);
+        if previous.is_empty() {
+            println!("Running mogAI.py to generate tests with intensity {}...", intensity);
+        } else {
+            println!("Running mogAI.py with the results of round {}...", round - 1);
+        }
 
-    // 2) Run the mogAI.py script
+        // 3) Run the mogAI.py script with the intensity, system info and previous results
+        let prompt = feedback::prompt(intensity, &system_info, round, &previous);
+        let (comments, test_configs) = generate_ai_plan(&prompt)?;
+
+        // 4) Display generated test plan to the user
+        println!("\n=== Generated Test Plan ===");
+        for (i, comment) in comments.iter().enumerate() {
+            println!("Test {}: {}", i + 1, comment);
+        }
+        
+        // Check if any valid test configurations were found
+        if test_configs.is_empty() {
+            println!("\nNo valid test configurations found. Returning to main menu...");
+            return Ok(());
+        }
+        
+        // 5) Ask for confirmation before running tests
+        if !input::confirm(&format!("\nRun {} test(s)? (y/n): ", test_configs.len()))? {
+            println!("Test execution cancelled. Returning to main menu...");
+            return Ok(());
+        }
+
+        // 6) Execute the tests one after another, collecting what each achieved
+        println!("\nExecuting AI-generated tests...");
+        let mut results = Vec::new();
+        for (i, config) in test_configs.iter().enumerate() {
+            // Create test parameters from the AI response
+            let test_id = Uuid::new_v4().to_string();
+            let test_name = format!("AI-{}-{}", config.test_type, &test_id[0..6]);
+            
+            // Build test parameters
+            let params = TestParams {
+                id: test_id,
+                name: test_name,
+                test_type: config.test_type.clone(),
+                threads: Some(config.threads),
+                duration: config.duration,
+                load: config.load,
+                size: config.size,
+                fork: config.fork,
+                scheduled_time: None,
+                node: default_node.to_string(),
+            };
+            let mut result = feedback::RoundResult::planned(
+                &config.test_type, config.threads, config.duration, config.load, config.size, config.fork,
+            );
+            
+            // Display test progress
+            println!("\nTest {}/{}: {} test (duration: {}s)", 
+                i + 1, 
+                test_configs.len(),
+                params.test_type.to_uppercase(),
+                params.duration
+            );
+            
+            // Start the test, offering a retry on transient failures, then wait for it to finish
+            match with_retry_prompt(&rt, &format!("Test '{}'", params.name), || run_test(&client, server_url, &params)) {
+                Ok(body) => match commands::parse_task_id(&body) {
+                    Some(task_id) => rt.block_on(collect_ai_result(&client, server_url, &params, &task_id, &mut result)),
+                    None => result.errors.push(format!("no task id in response: {}", body.trim())),
+                },
+                Err(CliError::Eof) => return Err(CliError::Eof),
+                Err(e) => {
+                    println!("Test '{}' failed to execute: {}", params.name, e);
+                    println!("Troubleshooting: Check if the server is running at {}", server_url);
+                    result.errors.push(e.to_string());
+                }
+            }
+            println!("Result: {}", result.summary());
+            results.push(result);
+        }
+
+        // 7) Show what the round achieved and offer to feed it back for another round
+        println!("\n=== Round {} Results ===", round);
+        for (i, result) in results.iter().enumerate() {
+            println!("Test {}: {}", i + 1, result.summary());
+        }
+        intensity = feedback::next_intensity(test_configs.iter().map(|c| c.intensity), intensity);
+        previous = results;
+
+        if !input::confirm(&format!(
+            "\nSend these results to the AI and run round {} (intensity {})? (y/n): ",
+            round + 1,
+            intensity
+        ))? {
+            break;
+        }
+    }
+    
+    println!("\nAll AI tests completed. Returning to main menu...");
+    Ok(())
+}
+
+// Function to run mogAI.py with the given input on stdin and parse its test plan
+// Returns the comment line of each block and the parsed test configurations
+fn generate_ai_plan(prompt: &str) -> Result<(Vec<String>, Vec<AiResponse>), CliError> {
     // This executes the Python script that generates test configurations
-    // It passes the intensity and system info as inputs
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(format!("(echo \"{{intensity: {}}}\" && cargo run --bin sys_info) | python3 ./src/mogAI.py", intensity)) 
-        .output()
-        .expect("Failed to run mogAI.py");
+    let mut child = Command::new("python3")
+        .arg("./src/mogAI.py")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CliError::Process(format!("failed to run mogAI.py: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", prompt)
+            .map_err(|e| CliError::Process(format!("failed to send system info to mogAI.py: {}", e)))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| CliError::Process(format!("failed to run mogAI.py: {}", e)))?;
+
+    // mogAI.py reports its own errors on stdout before exiting non-zero
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CliError::Process(format!(
+            "mogAI.py exited with {}: {}",
+            output.status,
+            if stdout.trim().is_empty() { stderr.trim() } else { stdout.trim() }
+        )));
+    }
     
-    // Process the script output
+    // Split output into blocks separated by double newlines, filtering out empty ones
     let stdout = String::from_utf8_lossy(&output.stdout);
-    // Split output into blocks separated by double newlines
-    let blocks: Vec<&str> = stdout.split("\n\n").collect();
-    
-    // Filter out empty blocks
-    let blocks: Vec<&str> = blocks.iter()
-        .filter(|&b| !b.trim().is_empty())
-        .cloned()
+    let blocks: Vec<&str> = stdout.split("\n\n")
+        .filter(|b| !b.trim().is_empty())
         .collect();
 
-    // Check if any test configurations were generated
-    if blocks.is_empty() {
-        println!("No test configurations generated. Returning to main menu...");
-        return;
-    }
-
-    // 3) Extract comments and test configurations from each block
+    // Extract comments and test configurations from each block
     let mut comments = Vec::new();
     let mut test_configs = Vec::new();
 
     for block in &blocks {
         // Look for comment lines (starting with #)
         if let Some(comment_line) = block.lines().find(|l| l.trim_start().starts_with('#')) {
-            comments.push(comment_line.trim());
+            comments.push(comment_line.trim().to_string());
         }
         
         // Extract and parse the JSON part of the block
@@ -606,77 +847,59 @@ fn run_ai_test(server_url: &str) {
         }
     }
 
-    // Display generated test plan to the user
-    println!("\n=== Generated Test Plan ===");
-    for (i, comment) in comments.iter().enumerate() {
-        println!("Test {}: {}", i + 1, comment);
-    }
-    
-    // Check if any valid test configurations were found
-    if test_configs.is_empty() {
-        println!("\nNo valid test configurations found. Returning to main menu...");
-        return;
+    Ok((comments, test_configs))
+}
+
+// Function to wait for an AI test's task to finish and record its result for the feedback loop
+async fn collect_ai_result(client: &Client, server_url: &str, params: &TestParams, task_id: &str, result: &mut feedback::RoundResult) {
+    println!("Waiting for task {} to finish...", task_id);
+    let timeout = Duration::from_secs(params.duration as u64 + 60);
+    match commands::wait_for_task(client, server_url, &params.node, task_id, timeout).await {
+        Ok(()) => result.status = "completed".to_string(),
+        Err(CliError::Timeout(e)) => {
+            result.status = "timeout".to_string();
+            result.errors.push(e);
+            return;
+        }
+        Err(e) => {
+            result.status = "failed".to_string();
+            result.errors.push(e.to_string());
+        }
     }
-    
-    // 4) Ask for confirmation before running tests
-    print!("\nRun {} test(s)? (y/n): ", test_configs.len());
-    io::stdout().flush().unwrap();
-    let mut choice = String::new();
-    io::stdin().read_line(&mut choice).unwrap();
-    if !choice.trim().to_lowercase().starts_with('y') {
-        println!("Test execution cancelled. Returning to main menu...");
-        return;
+
+    // Engines without /results still count as completed, just without metrics
+    match commands::fetch_task_result(client, server_url, &params.node, task_id).await {
+        Ok(task_result) => result.apply(&task_result),
+        Err(e) => println!("Could not fetch the result of task {}: {}", task_id, e),
     }
+}
 
-    // 5) Execute the tests using our existing run_test function
-    // Create runtime and HTTP client
-    let rt = Runtime::new().unwrap();
-    let client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .unwrap();
-    
-    println!("\nExecuting AI-generated tests...");
-    
-    // Execute each test configuration
-    for (i, config) in test_configs.iter().enumerate() {
-        // Create test parameters from the AI response
-        let test_id = Uuid::new_v4().to_string();
-        let test_name = format!("AI-{}-{}", config.test_type, &test_id[0..6]);
-        
-        // Build test parameters
-        let params = TestParams {
-            id: test_id,
-            name: test_name,
-            test_type: config.test_type.clone(),
-            threads: Some(config.threads),
-            duration: config.duration,
-            load: config.load,
-            size: config.size,
-            fork: config.fork,
-            scheduled_time: None,
-            node: "minikube".to_string(), // Using default node
-        };
-        
-        // Display test progress
-        println!("\nTest {}/{}: {} test (duration: {}s)", 
-            i + 1, 
-            test_configs.len(),
-            params.test_type.to_uppercase(),
-            params.duration
-        );
-        
-        // Execute the test and wait for completion
-        rt.block_on(run_test(&client, server_url, &params));
+// Function to execute a test, retrying transient failures with exponential backoff
+// Used by the scheduler thread, which can't prompt the user
+async fn run_test_with_retry(client: &Client, server_url: &str, params: &TestParams, attempts: u32) -> Result<String, CliError> {
+    let mut delay = Duration::from_secs(2);
+    let mut attempt = 1;
+    loop {
+        match run_test(client, server_url, params).await {
+            Err(e) if e.is_transient() && attempt < attempts => {
+                info!(
+                    "Test '{}' attempt {}/{} failed: {}. Retrying in {}s...",
+                    params.name, attempt, attempts, e, delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
     }
-    
-    println!("\nAll AI tests completed. Returning to main menu...");
 }
 
 // Function to execute a test by sending an HTTP request to the stress test server
 // This is an async function that handles the actual test execution
-async fn run_test(client: &Client, server_url: &str, params: &TestParams) {
-    println!(
+// Returns the server's response body, or an error for network failures and non-success statuses
+async fn run_test(client: &Client, server_url: &str, params: &TestParams) -> Result<String, CliError> {
+    info!(
         "\nStarting {} test '{}' (ID: {})...",
         params.test_type, params.name, params.id
     );
@@ -696,35 +919,33 @@ async fn run_test(client: &Client, server_url: &str, params: &TestParams) {
 
     // Build the endpoint URL based on test type
     let endpoint = format!("{}/{}-stress", server_url, params.test_type);
-    println!("Sending request to: {}", endpoint);
+    info!("Sending request to: {}", endpoint);
 
     // Send the HTTP POST request with JSON payload
-    match client
+    let response = client
         .post(&endpoint)
         .header("Content-Type", "application/json")
         .json(&request)
         .send()
-        .await
-    {
-        Ok(response) => {
-            // Display the JSON request that was sent
-            println!("{}", serde_json::to_string_pretty(&request).unwrap());
-            println!(
-                "Test '{}' request sent successfully! Status: {}",
-                params.name,
-                response.status()
-            );
-            
-            // Try to read and display the response body
-            match response.text().await {
-                Ok(text) => println!("Test '{}' response: {}", params.name, text),
-                Err(e) => println!("Test '{}' failed to read response: {}", params.name, e),
-            }
-        }
-        Err(e) => {
-            // Handle request failure
-            println!("Test '{}' failed to execute: {}", params.name, e);
-            println!("Troubleshooting: Check if the server is running at {}", server_url);
-        }
+        .await?;
+
+    // Display the JSON request that was sent
+    if let Ok(pretty) = serde_json::to_string_pretty(&request) {
+        info!("{}", pretty);
     }
-}
\ No newline at end of file
+
+    // Read the response body and treat error statuses as failures
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CliError::Status(status.as_u16(), body));
+    }
+
+    info!(
+        "Test '{}' request sent successfully! Status: {}",
+        params.name,
+        status
+    );
+    info!("Test '{}' response: {}", params.name, body);
+    Ok(body)
+}
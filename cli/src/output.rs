@@ -0,0 +1,79 @@
+// Output helpers - switch between human-readable text and machine-readable JSON
+// With --json every command prints exactly one JSON document to stdout; progress
+// messages are moved to stderr so stdout can be piped straight into jq or CI tooling
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+// Enable or disable JSON output for the whole process
+pub fn set_json(enabled: bool) {
+    JSON_OUTPUT.store(enabled, Ordering::SeqCst);
+}
+
+// Whether --json was requested
+pub fn is_json() -> bool {
+    JSON_OUTPUT.load(Ordering::SeqCst)
+}
+
+// Print a progress/info line: stdout in text mode, stderr in JSON mode
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use info;
+
+// Outcome of a non-interactive command
+// `json` is printed with --json; `code` becomes the process exit code
+pub struct Outcome {
+    pub json: Value,
+    pub code: i32,
+}
+
+impl Outcome {
+    // Successful command with the given JSON document
+    pub fn ok(json: Value) -> Self {
+        Outcome { json, code: 0 }
+    }
+}
+
+// Process exit codes
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;            // Usage, local I/O or unexpected errors
+pub const EXIT_SUBMIT_FAILED: i32 = 2;    // Test could not be submitted (network error or error status)
+pub const EXIT_TASK_FAILED: i32 = 3;      // Server reported the task as failed
+pub const EXIT_TIMEOUT: i32 = 4;          // Task did not finish before --timeout
+
+// Print the final result of a command and return its exit code
+pub fn finish(result: Result<Outcome, CliError>) -> i32 {
+    match result {
+        Ok(outcome) => {
+            if is_json() {
+                println!("{}", outcome.json);
+            }
+            outcome.code
+        }
+        Err(e) => {
+            let code = e.exit_code();
+            // Server errors can be looked up in the controller and engine logs by this id
+            let request_id = matches!(e, CliError::Http(_) | CliError::Status(_, _)).then(crate::request_id);
+            if is_json() {
+                println!("{}", json!({ "ok": false, "error": e.to_string(), "exit_code": code, "request_id": request_id }));
+            } else {
+                eprintln!("\nError: {}", e);
+                if let Some(id) = request_id {
+                    eprintln!("Request ID: {}", id);
+                }
+            }
+            code
+        }
+    }
+}
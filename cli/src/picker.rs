@@ -0,0 +1,251 @@
+// Fuzzy multi-select over a list, for picking nodes out of clusters with hundreds of them
+//
+// On a terminal the list narrows as you type (the letters of the query in order, not
+// necessarily adjacent; matches at the start of the name or of a -/./_ separated part
+// rank first):
+//   Up/Down (or Ctrl-P/Ctrl-N)  move
+//   Tab                         select or unselect, then move down
+//   Ctrl-A                      select every match
+//   Enter                       accept the selection, or the highlighted entry if none
+//   Esc or Ctrl-C               cancel
+// When stdin or stdout is not a terminal (piped input), a filter and then the numbers of
+// the entries to take are read line by line instead.
+use std::io::{self, IsTerminal};
+
+use crate::error::CliError;
+use crate::input;
+
+// Matches shown at once
+const VISIBLE: usize = 10;
+
+// How well `candidate` matches `query` (None = it does not); higher is better
+fn score(query: &str, candidate: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        let offset = candidate[next..].iter().position(|c| c.to_lowercase().eq(std::iter::once(wanted)))?;
+        let index = next + offset;
+        score += 10;
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += 15; // Runs of adjacent letters
+        }
+        if index == 0 || matches!(candidate[index - 1], '-' | '.' | '_') {
+            score += 20; // Start of the name or of one of its parts
+        }
+        score -= offset as i64; // Gaps
+        previous = Some(index);
+        next = index + 1;
+    }
+    Some(score * 4 - candidate.len() as i64) // Shorter names first among equals
+}
+
+// Indices of the items matching `query`, best first
+fn matches(items: &[String], query: &str) -> Vec<usize> {
+    let mut found: Vec<(i64, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| score(query, item).map(|score| (score, index)))
+        .collect();
+    found.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| items[a.1].cmp(&items[b.1])));
+    found.into_iter().map(|(_, index)| index).collect()
+}
+
+// Let the user pick any number of items, starting with `selected` picked
+// Returns None when the user cancels
+pub fn pick(title: &str, items: &[String], selected: &[String]) -> Result<Option<Vec<String>>, CliError> {
+    let picked: Vec<bool> = items.iter().map(|item| selected.contains(item)).collect();
+    #[cfg(unix)]
+    if io::stdin().is_terminal() && io::stdout().is_terminal() {
+        return raw::pick(title, items, picked);
+    }
+    pick_lines(title, items, picked)
+}
+
+// Selection in item order
+fn chosen(items: &[String], picked: &[bool]) -> Vec<String> {
+    items.iter().zip(picked).filter(|(_, &picked)| picked).map(|(item, _)| item.clone()).collect()
+}
+
+// Line-based picking for piped input
+fn pick_lines(title: &str, items: &[String], mut picked: Vec<bool>) -> Result<Option<Vec<String>>, CliError> {
+    println!("\n{} ({} in total)", title, items.len());
+    let mut query = String::new();
+    loop {
+        let found = matches(items, &query);
+        for (number, &index) in found.iter().take(VISIBLE * 2).enumerate() {
+            println!("{:>3}. [{}] {}", number + 1, if picked[index] { "x" } else { " " }, items[index]);
+        }
+        if found.len() > VISIBLE * 2 {
+            println!("     ... {} more; type more of the name to narrow the list", found.len() - VISIBLE * 2);
+        }
+        let answer = input::prompt("Numbers to select or unselect (e.g. 1,3-5), text to filter, Enter when done: ")?;
+        if answer.is_empty() {
+            return Ok(Some(chosen(items, &picked)));
+        }
+        match parse_numbers(&answer, found.len().min(VISIBLE * 2)) {
+            Some(numbers) => {
+                for number in numbers {
+                    let index = found[number - 1];
+                    picked[index] = !picked[index];
+                }
+            }
+            None => query = answer,
+        }
+    }
+}
+
+// "1,3-5" -> [1, 3, 4, 5]; None unless every number is between 1 and `count`
+fn parse_numbers(answer: &str, count: usize) -> Option<Vec<usize>> {
+    let mut numbers = Vec::new();
+    for part in answer.split(',').map(str::trim) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let (first, last): (usize, usize) = (first.trim().parse().ok()?, last.trim().parse().ok()?);
+        if first == 0 || last > count || first > last {
+            return None;
+        }
+        numbers.extend(first..=last);
+    }
+    Some(numbers)
+}
+
+#[cfg(unix)]
+mod raw {
+    use std::io::{self, Read, Write};
+
+    use super::{chosen, matches, VISIBLE};
+    use crate::error::CliError;
+
+    // Puts the terminal in raw mode (no echo, no line buffering) until dropped
+    struct RawMode {
+        saved: libc::termios,
+    }
+
+    impl RawMode {
+        fn enable() -> io::Result<Self> {
+            // SAFETY: termios is plain data, filled in by tcgetattr before it is used
+            let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut saved) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = saved;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(RawMode { saved })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved) };
+        }
+    }
+
+    enum Key {
+        Text(String), // Typed or pasted
+        Backspace,
+        Up,
+        Down,
+        Tab,
+        SelectAll,
+        Enter,
+        Cancel,
+        Other,
+    }
+
+    // One keypress; escape sequences arrive in one read
+    fn read_key(stdin: &mut io::Stdin) -> io::Result<Key> {
+        let mut buf = [0u8; 16];
+        let n = stdin.read(&mut buf)?;
+        Ok(match &buf[..n] {
+            [] | [3] | [4] | [27] => Key::Cancel, // EOF, Ctrl-C, Ctrl-D, Esc
+            [27, b'[', b'A'] | [27, b'O', b'A'] | [16] => Key::Up,
+            [27, b'[', b'B'] | [27, b'O', b'B'] | [14] => Key::Down,
+            [b'\t'] => Key::Tab,
+            [1] => Key::SelectAll,
+            [b'\r'] | [b'\n'] => Key::Enter,
+            [127] | [8] => Key::Backspace,
+            [27, ..] => Key::Other,
+            bytes => match std::str::from_utf8(bytes) {
+                Ok(text) if !text.chars().any(char::is_control) => Key::Text(text.to_string()),
+                _ => Key::Other,
+            },
+        })
+    }
+
+    pub fn pick(title: &str, items: &[String], mut picked: Vec<bool>) -> Result<Option<Vec<String>>, CliError> {
+        let _raw = RawMode::enable()?;
+        let mut stdin = io::stdin();
+        let mut stdout = io::stdout();
+        let mut query = String::new();
+        let mut cursor = 0;
+        let mut top = 0;
+        let mut drawn = 0;
+        println!("\n{} (type to filter, Tab to select, Enter to accept, Esc to cancel)", title);
+        loop {
+            let found = matches(items, &query);
+            cursor = cursor.min(found.len().saturating_sub(1));
+            top = top.min(cursor).max((cursor + 1).saturating_sub(VISIBLE));
+
+            // Redraw in place of the previous frame
+            let mut frame = String::new();
+            if drawn > 0 {
+                frame.push_str(&format!("\x1b[{}A", drawn));
+            }
+            frame.push_str("\r\x1b[J");
+            let count = picked.iter().filter(|&&picked| picked).count();
+            frame.push_str(&format!("> {}  ({}/{} matching, {} selected)\r\n", query, found.len(), items.len(), count));
+            for (row, &index) in found.iter().enumerate().skip(top).take(VISIBLE) {
+                let pointer = if row == cursor { ">" } else { " " };
+                let mark = if picked[index] { "x" } else { " " };
+                frame.push_str(&format!("{} [{}] {}\r\n", pointer, mark, items[index]));
+            }
+            drawn = 1 + found.len().saturating_sub(top).min(VISIBLE);
+            stdout.write_all(frame.as_bytes())?;
+            stdout.flush()?;
+
+            match read_key(&mut stdin)? {
+                Key::Text(text) => {
+                    query.push_str(&text);
+                    cursor = 0;
+                }
+                Key::Backspace => {
+                    query.pop();
+                    cursor = 0;
+                }
+                Key::Up => cursor = cursor.saturating_sub(1),
+                Key::Down => cursor = (cursor + 1).min(found.len().saturating_sub(1)),
+                Key::Tab => {
+                    if let Some(&index) = found.get(cursor) {
+                        picked[index] = !picked[index];
+                        cursor = (cursor + 1).min(found.len().saturating_sub(1));
+                    }
+                }
+                Key::SelectAll => {
+                    for &index in &found {
+                        picked[index] = true;
+                    }
+                }
+                Key::Enter => {
+                    if !picked.contains(&true) {
+                        match found.get(cursor) {
+                            Some(&index) => picked[index] = true,
+                            None => continue, // Nothing to take
+                        }
+                    }
+                    return Ok(Some(chosen(items, &picked)));
+                }
+                Key::Cancel => return Ok(None),
+                Key::Other => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+// Markdown reports of batch results, to paste into GitHub issues and wikis
+// A batch is either a JSON report exported by the GUI (one batch object, or an array
+// with one per node) or a scenario/campaign job on the controller
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+// Function to render GUI batch reports (one per node) as Markdown
+pub fn batch_markdown(reports: &[Value]) -> String {
+    let batch_id = reports.first().and_then(|r| r["batch_id"].as_str()).unwrap_or("-");
+    let tests: Vec<&Value> = reports.iter().flat_map(|r| r["tests"].as_array().into_iter().flatten()).collect();
+    let passed = tests.iter().filter(|test| test_passed(test)).count();
+
+    let mut md = format!("# Mogwai report {}\n\n", cell(batch_id));
+    md.push_str(&format!("**{}**: {} of {} tests passed\n\n", verdict(passed == tests.len()), passed, tests.len()));
+
+    for report in reports {
+        if reports.len() > 1 {
+            md.push_str(&format!("## Node {}\n\n", cell(report["node"].as_str().unwrap_or("-"))));
+        }
+        md.push_str("| | |\n|---|---|\n");
+        for (label, field) in [("Started", "started_at"), ("Finished", "finished_at"), ("Server", "server_url"), ("Node", "node")] {
+            md.push_str(&format!("| {} | {} |\n", label, cell(report[field].as_str().unwrap_or("-"))));
+        }
+        if let Some(error) = report["error"].as_str() {
+            md.push_str(&format!("| Error | {} |\n", cell(error)));
+        }
+        md.push('\n');
+
+        let tests: Vec<&Value> = report["tests"].as_array().into_iter().flatten().collect();
+        if tests.is_empty() {
+            md.push_str("No tests.\n\n");
+            continue;
+        }
+        md.push_str("| Test | Parameters | Task ID | Status | Result |\n|---|---|---|---|---|\n");
+        for test in &tests {
+            let status = test["error"].as_str().or_else(|| test["status"].as_str()).unwrap_or("-");
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                cell(test["test_type"].as_str().unwrap_or("-")),
+                cell(&parameters(&test["request"])),
+                cell(test["task_id"].as_str().unwrap_or("-")),
+                cell(status),
+                verdict(test_passed(test))
+            ));
+        }
+        md.push('\n');
+
+        let rows: Vec<(String, &Value)> = tests
+            .iter()
+            .map(|test| {
+                let id = test["task_id"].as_str().or_else(|| test["test_id"].as_str()).unwrap_or("-");
+                (format!("{} {}", test["test_type"].as_str().unwrap_or("-"), id), &test["metrics"])
+            })
+            .collect();
+        md.push_str(&metrics_table("Test", &rows));
+    }
+    md
+}
+
+// Function to render a scenario or campaign job as Markdown
+pub fn job_markdown(job: &Value) -> String {
+    let steps: Vec<&Value> = job["steps"].as_array().into_iter().flatten().collect();
+    let completed = steps.iter().filter(|step| step["status"] == "completed").count();
+    let passed = job["status"] == "completed" && job["regressed"] != true;
+
+    let mut md = format!(
+        "# {} {}\n\n",
+        cell(job["id"].as_str().unwrap_or("-")),
+        cell(job["name"].as_str().unwrap_or(""))
+    );
+    md.push_str(&format!(
+        "**{}**: {}, {} of {} steps completed{}\n\n",
+        verdict(passed),
+        cell(job["status"].as_str().unwrap_or("-")),
+        completed,
+        steps.len(),
+        if job["regressed"] == true { ", regressed against its baselines" } else { "" }
+    ));
+
+    md.push_str("| Step | Type | Node | Parameters | Run | Task ID | Status | Result |\n|---|---|---|---|---|---|---|---|\n");
+    for step in &steps {
+        let tasks: Vec<&Value> = step["tasks"].as_array().into_iter().flatten().collect();
+        if tasks.is_empty() {
+            md.push_str(&format!(
+                "| {} | {} | | | | | {} | {} |\n",
+                cell(step["name"].as_str().unwrap_or("-")),
+                cell(step["kind"].as_str().unwrap_or("-")),
+                cell(step["status"].as_str().unwrap_or("-")),
+                verdict(step["status"] == "completed")
+            ));
+        }
+        for task in tasks {
+            let regressions = task["regressions"].as_array().map_or(0, Vec::len);
+            let status = match (task["error"].as_str(), regressions) {
+                (Some(error), _) => format!("{}: {}", task["status"].as_str().unwrap_or("-"), error),
+                (None, 0) => task["status"].as_str().unwrap_or("-").to_string(),
+                (None, n) => format!("{}, {} regressed metrics", task["status"].as_str().unwrap_or("-"), n),
+            };
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} | {} | {} |\n",
+                cell(step["name"].as_str().unwrap_or("-")),
+                cell(task["type"].as_str().unwrap_or("-")),
+                cell(task["node"].as_str().unwrap_or("-")),
+                cell(&params(&task["params"])),
+                task["run"],
+                cell(task["task_id"].as_str().unwrap_or("-")),
+                cell(&status),
+                verdict(task["status"] == "completed" && regressions == 0)
+            ));
+        }
+    }
+    md.push('\n');
+
+    // Metrics: the mean over the runs of every test of every step
+    for step in &steps {
+        let rows: Vec<(String, Value)> = step["summary"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|test| {
+                let means = test["metrics"]
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, summary)| (name.clone(), summary["mean"].clone()))
+                    .collect();
+                (format!("{}@{}", test["type"].as_str().unwrap_or("-"), test["node"].as_str().unwrap_or("-")), Value::Object(means))
+            })
+            .collect();
+        if !rows.is_empty() {
+            md.push_str(&format!("## {}\n\n", cell(step["name"].as_str().unwrap_or("-"))));
+            if step["repeat"].as_u64().unwrap_or(1) > 1 {
+                md.push_str(&format!("Mean over {} runs.\n\n", step["repeat"]));
+            }
+            md.push_str(&metrics_table("Test", &rows.iter().map(|(name, metrics)| (name.clone(), metrics)).collect::<Vec<_>>()));
+        }
+    }
+
+    if let Some(comparison) = job["comparison"].as_object() {
+        let (a, b) = (comparison["a"].as_str().unwrap_or("a"), comparison["b"].as_str().unwrap_or("b"));
+        md.push_str(&format!("## {} vs {}\n\n", cell(b), cell(a)));
+        md.push_str(&format!("| Metric | {} | {} | Delta | t | Hint |\n|---|---:|---:|---:|---:|---|\n", cell(a), cell(b)));
+        for (metric, delta) in comparison["metrics"].as_object().into_iter().flatten() {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                cell(metric),
+                number(&delta["a_mean"]),
+                number(&delta["b_mean"]),
+                delta["delta_percent"].as_f64().map_or("-".to_string(), |p| format!("{:+.2}%", p)),
+                number(&delta["t"]),
+                delta["hint"].as_str().unwrap_or("-")
+            ));
+        }
+        md.push('\n');
+    }
+    md
+}
+
+// Function to build a table with one row per test and one column per metric any of them reported
+fn metrics_table(label: &str, rows: &[(String, &Value)]) -> String {
+    let names: BTreeSet<&String> = rows.iter().flat_map(|(_, metrics)| metrics.as_object().into_iter().flatten().map(|(name, _)| name)).collect();
+    if names.is_empty() {
+        return String::new();
+    }
+    let mut md = format!("| {} |", label);
+    for name in &names {
+        md.push_str(&format!(" {} |", cell(name)));
+    }
+    md.push_str(&format!("\n|---|{}\n", "---:|".repeat(names.len())));
+    for (row, metrics) in rows {
+        md.push_str(&format!("| {} |", cell(row)));
+        for name in &names {
+            md.push_str(&format!(" {} |", number(&metrics[name.as_str()])));
+        }
+        md.push('\n');
+    }
+    md.push('\n');
+    md
+}
+
+// Function to summarize a GUI test request (threads, duration, load, size, fork)
+fn parameters(request: &Value) -> String {
+    if request.is_null() {
+        return "-".to_string();
+    }
+    let mut parts = vec![format!("{} threads", request["intensity"]), format!("{}s", request["duration"])];
+    if let Some(load) = request["load"].as_u64() {
+        parts.push(format!("{}% load", load));
+    }
+    if let Some(size) = request["size"].as_u64() {
+        parts.push(format!("{} MB", size));
+    }
+    if request["fork"] == true {
+        parts.push("fork".to_string());
+    }
+    parts.join(", ")
+}
+
+// Function to list engine request parameters as name=value
+fn params(params: &Value) -> String {
+    let parts: Vec<String> = params
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| match value {
+            Value::String(text) => format!("{}={}", name, text),
+            other => format!("{}={}", name, other),
+        })
+        .collect();
+    if parts.is_empty() { "-".to_string() } else { parts.join(", ") }
+}
+
+// Function to decide whether a GUI test passed: started without error and not reported failed
+fn test_passed(test: &Value) -> bool {
+    let started = test["http_status"].as_u64().is_some_and(|status| (200..300).contains(&status));
+    let failed = test["status"].as_str().is_some_and(|status| {
+        matches!(status.to_lowercase().as_str(), "failed" | "error" | "killed" | "aborted")
+    });
+    test["error"].is_null() && started && !failed
+}
+
+fn verdict(passed: bool) -> &'static str {
+    if passed { "PASS" } else { "FAIL" }
+}
+
+// Function to format a metric value: two decimals, whole numbers as they are
+fn number(value: &Value) -> String {
+    match value.as_f64() {
+        Some(n) if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", n as i64),
+        Some(n) => format!("{:.2}", n),
+        None => "-".to_string(),
+    }
+}
+
+// Function to make text safe inside a table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
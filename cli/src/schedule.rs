@@ -0,0 +1,192 @@
+// Scheduling helpers - turns user-entered schedule strings into Unix timestamps
+// Accepted forms:
+// - HH:MM                       (today, or tomorrow if already passed, in the chosen timezone)
+// - YYYY-MM-DD HH:MM[:SS]       (in the chosen timezone)
+// - RFC3339, e.g. 2025-05-01T14:30:00+02:00 (carries its own offset)
+// - Relative offsets, e.g. +90s, +15m, +2h, +1d, +1h30m
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// Timezone used to interpret schedule times that don't carry their own offset
+#[derive(Debug, Clone, Copy)]
+pub enum ScheduleZone {
+    Local,              // System local timezone (default)
+    Named(Tz),          // IANA timezone name, e.g. Europe/Berlin
+    Fixed(FixedOffset), // Fixed UTC offset, e.g. +02:00
+}
+
+impl std::fmt::Display for ScheduleZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Named(tz) => write!(f, "{}", tz.name()),
+            Self::Fixed(offset) => write!(f, "UTC{}", offset),
+        }
+    }
+}
+
+// Parse a timezone given as an IANA name, "UTC", or a fixed offset like +02:00
+// An empty input selects the system local timezone
+pub fn parse_timezone(input: &str) -> Result<ScheduleZone, String> {
+    let input = input.trim();
+    if input.is_empty() || input.eq_ignore_ascii_case("local") {
+        return Ok(ScheduleZone::Local);
+    }
+
+    if input.starts_with('+') || input.starts_with('-') {
+        return parse_fixed_offset(input)
+            .map(ScheduleZone::Fixed)
+            .ok_or_else(|| format!("Invalid UTC offset '{}' (expected e.g. +02:00 or -0530)", input));
+    }
+
+    if input.eq_ignore_ascii_case("utc") || input.eq_ignore_ascii_case("z") {
+        return Ok(ScheduleZone::Named(Tz::UTC));
+    }
+
+    input
+        .parse::<Tz>()
+        .map(ScheduleZone::Named)
+        .map_err(|_| format!("Unknown timezone '{}' (expected e.g. UTC, Europe/Berlin, +02:00)", input))
+}
+
+// Returns true if the schedule string needs a timezone to be interpreted
+// (relative offsets and RFC3339 timestamps are already unambiguous)
+pub fn needs_timezone(input: &str) -> bool {
+    let input = input.trim();
+    !(input.starts_with('+') || DateTime::parse_from_rfc3339(input).is_ok())
+}
+
+// Parse a schedule string relative to `now`, returning the scheduled instant
+// Fails if the input is malformed, does not exist in the timezone, or is not in the future
+pub fn parse_schedule(input: &str, zone: ScheduleZone, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("No time entered".to_string());
+    }
+
+    let scheduled = if let Some(offset) = input.strip_prefix('+') {
+        // Relative offset from now
+        now.checked_add_signed(parse_relative(offset)?)
+            .ok_or_else(|| format!("Relative time '+{}' is too far in the future", offset))?
+    } else if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        // Full RFC3339 timestamp with its own offset
+        dt.with_timezone(&Utc)
+    } else if let Some(naive) = parse_naive_datetime(input) {
+        // Full date and time in the selected timezone
+        localize(naive, zone)?
+    } else if let Ok(time) = NaiveTime::parse_from_str(input, "%H:%M") {
+        // Time only: today in the selected timezone, or tomorrow if it has already passed
+        let today = today_in(zone, now);
+        let mut scheduled = localize(today.and_time(time), zone)?;
+        if scheduled <= now {
+            scheduled = localize((today + Duration::days(1)).and_time(time), zone)?;
+        }
+        scheduled
+    } else {
+        return Err(format!(
+            "Unrecognized time '{}' (use HH:MM, YYYY-MM-DD HH:MM, RFC3339, or +2h)",
+            input
+        ));
+    };
+
+    if scheduled <= now {
+        return Err(format!(
+            "Scheduled time {} is not in the future",
+            scheduled.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %Z")
+        ));
+    }
+
+    Ok(scheduled)
+}
+
+// Parse relative offsets such as "90s", "15m", "2h", "1d" or combinations like "1h30m"
+fn parse_relative(input: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid relative time '+{}' (expected e.g. +30m, +2h, +1d12h)", input);
+    let too_far = || format!("Relative time '+{}' is too far in the future", input);
+
+    let mut total = Duration::zero();
+    let mut digits = String::new();
+    for c in input.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let value: i64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        // Out-of-range amounts (e.g. +99999999999999d) are rejected rather than overflowing
+        let part = match c.to_ascii_lowercase() {
+            's' => Duration::try_seconds(value),
+            'm' => Duration::try_minutes(value),
+            'h' => Duration::try_hours(value),
+            'd' => Duration::try_days(value),
+            'w' => Duration::try_weeks(value),
+            _ => return Err(invalid()),
+        };
+        total = part.and_then(|part| total.checked_add(&part)).ok_or_else(too_far)?;
+    }
+
+    // A trailing bare number is treated as minutes, e.g. "+45"
+    if !digits.is_empty() {
+        let value: i64 = digits.parse().map_err(|_| invalid())?;
+        total = Duration::try_minutes(value).and_then(|part| total.checked_add(&part)).ok_or_else(too_far)?;
+    }
+
+    if total <= Duration::zero() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+// Parse "YYYY-MM-DD HH:MM[:SS]" (space or 'T' separated)
+fn parse_naive_datetime(input: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+}
+
+// Parse a fixed offset such as +02:00, -0530 or +2
+fn parse_fixed_offset(input: &str) -> Option<FixedOffset> {
+    let (sign, rest) = if let Some(rest) = input.strip_prefix('+') {
+        (1, rest)
+    } else if let Some(rest) = input.strip_prefix('-') {
+        (-1, rest)
+    } else {
+        return None;
+    };
+
+    let (hours, minutes) = if let Some((h, m)) = rest.split_once(':') {
+        (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?)
+    } else if rest.len() == 4 {
+        // get() rather than slicing: non-ASCII input has no char boundary at byte 2
+        (rest.get(..2)?.parse::<i32>().ok()?, rest.get(2..)?.parse::<i32>().ok()?)
+    } else {
+        (rest.parse::<i32>().ok()?, 0)
+    };
+
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+// Current calendar date in the given timezone
+fn today_in(zone: ScheduleZone, now: DateTime<Utc>) -> NaiveDate {
+    match zone {
+        ScheduleZone::Local => now.with_timezone(&Local).date_naive(),
+        ScheduleZone::Named(tz) => now.with_timezone(&tz).date_naive(),
+        ScheduleZone::Fixed(offset) => now.with_timezone(&offset).date_naive(),
+    }
+}
+
+// Interpret a wall-clock datetime in the given timezone
+// Ambiguous times (DST fall-back) resolve to the earlier instant; skipped times (DST spring-forward) are rejected
+fn localize(naive: NaiveDateTime, zone: ScheduleZone) -> Result<DateTime<Utc>, String> {
+    let resolved = match zone {
+        ScheduleZone::Local => Local.from_local_datetime(&naive).earliest().map(|dt| dt.with_timezone(&Utc)),
+        ScheduleZone::Named(tz) => tz.from_local_datetime(&naive).earliest().map(|dt| dt.with_timezone(&Utc)),
+        ScheduleZone::Fixed(offset) => offset.from_local_datetime(&naive).earliest().map(|dt| dt.with_timezone(&Utc)),
+    };
+
+    resolved.ok_or_else(|| format!("{} does not exist in timezone {}", naive.format("%Y-%m-%d %H:%M"), zone))
+}
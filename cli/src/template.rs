@@ -0,0 +1,130 @@
+// Test templates - named parameter sets stored locally so recurring tests
+// (e.g. the nightly soak) can be re-run without walking the full prompt sequence
+// Templates live in <config dir>/mogwai/templates.json, or $MOGWAI_TEMPLATES if set
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CliError;
+
+// Template structure - everything needed to rebuild a TestParams except the id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,          // Template name (unique key)
+    pub test_type: String,     // Type of test (cpu, mem, disk)
+    pub threads: Option<u32>,  // Number of threads to use (Optional)
+    pub duration: u32,         // Duration of the test in seconds
+    pub load: Option<u32>,     // CPU load percentage (Optional - used for CPU tests)
+    pub size: Option<u32>,     // Size in MB (Optional - used for memory and disk tests)
+    pub fork: Option<bool>,    // Whether to fork processes (Optional - used for CPU tests)
+    #[serde(default)]
+    pub node: Option<String>,  // Target node; falls back to the default node when unset
+    #[serde(default)]
+    pub created_at: Option<String>, // RFC3339 timestamp when the template was saved
+}
+
+impl Template {
+    // Short one-line description used by `template list`
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{}s", self.duration)];
+        if let Some(threads) = self.threads {
+            parts.push(format!("{} threads", threads));
+        }
+        if let Some(load) = self.load {
+            parts.push(format!("{}% load", load));
+        }
+        if let Some(size) = self.size {
+            parts.push(format!("{} MB", size));
+        }
+        if self.fork == Some(true) {
+            parts.push("fork".to_string());
+        }
+        if let Some(node) = &self.node {
+            parts.push(format!("node {}", node));
+        }
+        format!("{} - {}", self.test_type.to_uppercase(), parts.join(", "))
+    }
+}
+
+// Location of the template file
+pub fn templates_path() -> PathBuf {
+    if let Ok(path) = std::env::var("MOGWAI_TEMPLATES") {
+        return PathBuf::from(path);
+    }
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("mogwai")
+        .join("templates.json")
+}
+
+// Load all templates keyed by name (an absent file means no templates yet)
+pub fn load_templates() -> Result<BTreeMap<String, Template>, CliError> {
+    let path = templates_path();
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read_to_string(&path)?;
+    let templates: Vec<Template> = serde_json::from_str(&data).map_err(|e| {
+        CliError::Process(format!("template file {} is invalid: {}", path.display(), e))
+    })?;
+    Ok(templates.into_iter().map(|t| (t.name.clone(), t)).collect())
+}
+
+// Write all templates back to disk, creating the config directory if needed
+pub fn store_templates(templates: &BTreeMap<String, Template>) -> Result<(), CliError> {
+    let path = templates_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let list: Vec<&Template> = templates.values().collect();
+    let data = serde_json::to_string_pretty(&list)
+        .map_err(|e| CliError::Process(format!("failed to serialize templates: {}", e)))?;
+    fs::write(&path, data)?;
+    Ok(())
+}
+
+// Save (or overwrite) a single template
+pub fn save_template(mut template: Template) -> Result<(), CliError> {
+    let mut templates = load_templates()?;
+    template.created_at = Some(Utc::now().to_rfc3339());
+    templates.insert(template.name.clone(), template);
+    store_templates(&templates)
+}
+
+// Look up a template by name
+pub fn find_template(name: &str) -> Result<Template, CliError> {
+    load_templates()?
+        .remove(name)
+        .ok_or_else(|| CliError::Process(format!("no template named '{}' (see `template list`)", name)))
+}
+
+// Remove a template by name, returning whether it existed
+pub fn delete_template(name: &str) -> Result<bool, CliError> {
+    let mut templates = load_templates()?;
+    let existed = templates.remove(name).is_some();
+    if existed {
+        store_templates(&templates)?;
+    }
+    Ok(existed)
+}
+
+// Merge templates fetched from the controller into the local library
+// Existing local templates are kept unless `overwrite` is set
+// Returns the names of templates that were added or replaced
+pub fn merge_templates(remote: Vec<Template>, overwrite: bool) -> Result<Vec<String>, CliError> {
+    let mut templates = load_templates()?;
+    let mut changed = Vec::new();
+    for template in remote {
+        if overwrite || !templates.contains_key(&template.name) {
+            changed.push(template.name.clone());
+            templates.insert(template.name.clone(), template);
+        }
+    }
+    if !changed.is_empty() {
+        store_templates(&templates)?;
+    }
+    Ok(changed)
+}
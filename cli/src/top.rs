@@ -0,0 +1,117 @@
+// `mogwai top` - a live table of the cluster's engines, for watching a campaign without the GUI
+// Polls the controller's /overview (every engine's host metrics and running tasks) and
+// redraws in place until Ctrl-C
+use std::io::{self, Write};
+use std::time::Duration;
+
+use chrono::Local;
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::error::CliError;
+use crate::output::{self, Outcome};
+use crate::{build_client, new_runtime};
+
+// Fetch the controller's overview of its (or the named cluster's) engines
+async fn fetch_overview(client: &Client, server_url: &str, cluster: Option<&str>) -> Result<Value, CliError> {
+    let mut request = client.get(format!("{}/overview", server_url));
+    if let Some(cluster) = cluster {
+        request = request.query(&[("cluster", cluster)]);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if status.as_u16() == 404 {
+        return Err(CliError::Process(format!(
+            "{} has no /overview; top needs a controller (engines are watched through one)",
+            server_url
+        )));
+    }
+    if !status.is_success() {
+        return Err(CliError::Status(status.as_u16(), body));
+    }
+    serde_json::from_str(&body).map_err(|e| CliError::Process(format!("unexpected /overview response: {}", e)))
+}
+
+// Run `top`: one frame with --once or --json, else a refreshing view
+pub fn run(server_url: &str, cluster: Option<&str>, interval: u64, once: bool) -> Result<Outcome, CliError> {
+    let rt = new_runtime()?;
+    let client = build_client(30)?;
+    if once || output::is_json() {
+        let overview = rt.block_on(fetch_overview(&client, server_url, cluster))?;
+        if !output::is_json() {
+            print!("{}", render(server_url, &overview));
+        }
+        return Ok(Outcome::ok(overview));
+    }
+
+    let interval = Duration::from_secs(interval.max(1));
+    loop {
+        // A failed poll is shown in place of the table; the next one may work again
+        let frame = match rt.block_on(fetch_overview(&client, server_url, cluster)) {
+            Ok(overview) => render(server_url, &overview),
+            Err(e @ CliError::Status(401 | 403, _)) => return Err(e),
+            Err(e) => format!("mogwai top - {} - {}\n\n{}\n", server_url, Local::now().format("%H:%M:%S"), e),
+        };
+        // Clear the screen and draw from the top left corner
+        print!("\x1b[H\x1b[2J{}", frame);
+        io::stdout().flush()?;
+        std::thread::sleep(interval);
+    }
+}
+
+// The table for one overview
+fn render(server_url: &str, overview: &Value) -> String {
+    let number = |value: &Value, field: &str| value.get(field).and_then(Value::as_f64).unwrap_or_default();
+    let totals = &overview["totals"];
+    let mut out = format!(
+        "mogwai top - {} - {} - {} of {} nodes reachable, {} tasks\n\n",
+        server_url,
+        Local::now().format("%H:%M:%S"),
+        number(totals, "reachable"),
+        number(totals, "nodes"),
+        number(totals, "tasks"),
+    );
+    out.push_str(&format!(
+        "{:<24} {:>5}  {:<18} {:>6} {:>8} {:>15} {:>10} {:>10}\n",
+        "NODE", "TASKS", "TYPES", "CPU%", "GEN CPU", "MEM USED", "MEM ALLOC", "DISK MB/s"
+    ));
+    for node in overview["nodes"].as_array().into_iter().flatten() {
+        let name = node["node"].as_str().unwrap_or_default();
+        if node["reachable"] != Value::Bool(true) {
+            let error = node["error"].as_str().unwrap_or("unreachable");
+            out.push_str(&format!("{:<24} {:>5}  {}\n", name, "-", error));
+            continue;
+        }
+        let types: Vec<String> = node["types"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(test_type, count)| format!("{}x{}", test_type, count))
+            .collect();
+        out.push_str(&format!(
+            "{:<24} {:>5}  {:<18} {:>6.1} {:>8.1} {:>15} {:>10} {:>10.1}\n",
+            name,
+            number(node, "tasks"),
+            types.join(" "),
+            number(node, "cpu_percent"),
+            number(node, "cpu_cores"),
+            format!("{:.0}/{:.0} MB", number(node, "memory_used_mb"), number(node, "memory_total_mb")),
+            format!("{:.0} MB", number(node, "memory_mb")),
+            number(node, "disk_mb_s"),
+        ));
+    }
+    out.push_str(&format!(
+        "{:<24} {:>5}  {:<18} {:>6} {:>8.1} {:>15} {:>10} {:>10.1}\n",
+        "TOTAL",
+        number(totals, "tasks"),
+        "",
+        "",
+        number(totals, "cpu_cores"),
+        "",
+        format!("{:.0} MB", number(totals, "memory_mb")),
+        number(totals, "disk_mb_s"),
+    ));
+    out.push_str("\nGEN CPU: cores' worth of load the cpu tests generate; MEM ALLOC: memory the mem tests allocate\n");
+    out
+}
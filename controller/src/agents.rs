@@ -0,0 +1,347 @@
+// Engines in agent mode, which connect out to the controller instead of being reached by DNS
+//
+// An engine started with $MOGWAI_CONTROLLER_URL keeps a WebSocket open to GET /agents/connect
+// (admin role when JWTs are required) and registers its node name, optionally under a
+// cluster name. While it is connected, calls to that node go over the socket: engine_url
+// points at the relay, a listener on a random loopback port that turns each request into
+// a message to the agent and streams the answer back. Handlers, jobs and chaos runs reach
+// agents the way they reach any engine. A newer connection from the same node replaces the
+// older one. The messages are described in the engine's agent module.
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use actix_ws::{AggregatedMessage, AggregatedMessageStream, Session};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+// How long the relay waits for an agent to start answering
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Agents ping every 20 seconds; one silent this long is dropped
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Largest message taken from an agent
+const MAX_MESSAGE: usize = 4 * 1024 * 1024;
+
+// Request bodies are sent in frames of at most this size
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Headers that belong to one hop, not to the call being relayed
+const HOP_HEADERS: [&str; 6] = ["host", "connection", "content-length", "transfer-encoding", "upgrade", "keep-alive"];
+
+// A connected engine, as listed by GET /agents
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentInfo {
+    pub node: String,
+    pub cluster: Option<String>,
+    pub version: String,
+    pub test_types: Vec<String>,
+    pub capabilities: Value,
+    pub restricted: bool,
+    pub address: Option<String>, // Where the connection comes from
+    pub connected_at: f64,
+}
+
+struct Agent {
+    info: AgentInfo,
+    connection: u64,
+    session: Session,
+}
+
+// What an agent sent for one relayed call
+enum Reply {
+    Head { status: u16, headers: Vec<(String, String)> },
+    Body(web::Bytes),
+    End,
+    Failed(String),
+}
+
+struct Pending {
+    connection: u64,
+    replies: mpsc::UnboundedSender<Reply>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FromAgent {
+    Register {
+        node: String,
+        #[serde(default)]
+        cluster: Option<String>,
+        #[serde(default)]
+        version: String,
+        #[serde(default)]
+        test_types: Vec<String>,
+        #[serde(default)]
+        capabilities: Value,
+        #[serde(default)]
+        restricted: bool,
+    },
+    Response {
+        id: u64,
+        status: u16,
+        #[serde(default)]
+        headers: Vec<(String, String)>,
+    },
+    End { id: u64 },
+    Error { id: u64, message: String },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ToAgent {
+    Request { id: u64, method: String, path: String, headers: Vec<(String, String)> },
+    End { id: u64 },
+    Cancel { id: u64 },
+}
+
+static AGENTS: Mutex<Vec<Agent>> = Mutex::new(Vec::new());
+static PENDING: Mutex<BTreeMap<u64, Pending>> = Mutex::new(BTreeMap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1); // Ids of connections and of relayed calls
+static RELAY: OnceLock<String> = OnceLock::new(); // Base URL of the relay
+
+// Start the relay on a loopback port; engine_url only points at it once it runs
+pub fn start_relay() -> std::io::Result<()> {
+    let server = HttpServer::new(|| App::new().default_service(web::to(relay)))
+        .workers(2)
+        .disable_signals()
+        .bind(("127.0.0.1", 0))?;
+    if let Some(address) = server.addrs().first() {
+        let _ = RELAY.set(format!("http://{}", address));
+    }
+    actix_web::rt::spawn(server.run());
+    Ok(())
+}
+
+// The connected agents
+pub fn list() -> Vec<AgentInfo> {
+    let agents = AGENTS.lock().unwrap_or_else(|e| e.into_inner());
+    agents.iter().map(|agent| agent.info.clone()).collect()
+}
+
+// Base URL of a node's engine through the relay, if the node is a connected agent
+pub fn engine_base(cluster: Option<&str>, node: &str) -> Option<String> {
+    let relay = RELAY.get()?;
+    let agents = AGENTS.lock().unwrap_or_else(|e| e.into_inner());
+    agents
+        .iter()
+        .find(|agent| agent.info.node == node && agent.info.cluster.as_deref() == cluster)
+        .map(|_| format!("{}/{}/{}", relay, cluster.unwrap_or("-"), node))
+}
+
+// GET /agents/connect: take over the WebSocket and serve the agent in the background
+pub fn connect(req: &HttpRequest, body: web::Payload) -> Result<HttpResponse, actix_web::Error> {
+    let (response, session, stream) = actix_ws::handle(req, body)?;
+    let address = req.peer_addr().map(|address| address.ip().to_string());
+    let stream = stream
+        .max_frame_size(MAX_MESSAGE)
+        .aggregate_continuations()
+        .max_continuation_size(MAX_MESSAGE);
+    actix_web::rt::spawn(serve(session, stream, address));
+    Ok(response)
+}
+
+// Read an agent's messages until it disconnects
+async fn serve(mut session: Session, mut stream: AggregatedMessageStream, address: Option<String>) {
+    let connection = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let mut node = None;
+    loop {
+        let message = match tokio::time::timeout(IDLE_TIMEOUT, stream.recv()).await {
+            Ok(Some(Ok(message))) => message,
+            Ok(Some(Err(e))) => {
+                tracing::warn!(node, error = %e, "Agent connection failed");
+                break;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                tracing::warn!(node, "Agent went silent; dropping it");
+                break;
+            }
+        };
+        match message {
+            AggregatedMessage::Text(text) => match serde_json::from_str::<FromAgent>(&text) {
+                Ok(FromAgent::Register { node: name, cluster, version, test_types, capabilities, restricted }) => {
+                    let info = AgentInfo {
+                        node: name.clone(),
+                        cluster,
+                        version,
+                        test_types,
+                        capabilities,
+                        restricted,
+                        address: address.clone(),
+                        connected_at: now(),
+                    };
+                    register(info, connection, session.clone());
+                    node = Some(name);
+                }
+                Ok(FromAgent::Response { id, status, headers }) => deliver(id, Reply::Head { status, headers }),
+                Ok(FromAgent::End { id }) => deliver(id, Reply::End),
+                Ok(FromAgent::Error { id, message }) => deliver(id, Reply::Failed(message)),
+                Err(e) => tracing::warn!(node, error = %e, "Unreadable message from agent"),
+            },
+            // Body pieces: the call's id, then the bytes
+            AggregatedMessage::Binary(bytes) if bytes.len() >= 8 => {
+                let mut id = [0; 8];
+                id.copy_from_slice(&bytes[..8]);
+                deliver(u64::from_be_bytes(id), Reply::Body(bytes.slice(8..)));
+            }
+            AggregatedMessage::Ping(bytes) => {
+                let _ = session.pong(&bytes).await;
+            }
+            AggregatedMessage::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    AGENTS.lock().unwrap_or_else(|e| e.into_inner()).retain(|agent| agent.connection != connection);
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).retain(|_, call| {
+        if call.connection == connection {
+            let _ = call.replies.send(Reply::Failed("Agent disconnected".to_string()));
+        }
+        call.connection != connection
+    });
+    if node.is_some() {
+        tracing::info!(node, "Agent disconnected");
+    }
+    let _ = session.close(None).await;
+}
+
+// Add an agent, closing an older connection of the same node
+fn register(info: AgentInfo, connection: u64, session: Session) {
+    tracing::info!(node = %info.node, cluster = info.cluster, version = %info.version, address = info.address, "Agent connected");
+    let mut agents = AGENTS.lock().unwrap_or_else(|e| e.into_inner());
+    agents.retain(|agent| agent.connection != connection);
+    if let Some(position) = agents.iter().position(|agent| agent.info.node == info.node && agent.info.cluster == info.cluster) {
+        let old = agents.remove(position);
+        actix_web::rt::spawn(async move {
+            let _ = old.session.close(None).await;
+        });
+    }
+    agents.push(Agent { info, connection, session });
+}
+
+// Hand a reply to the call waiting for it; the call is done after End or Failed
+fn deliver(id: u64, reply: Reply) {
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    let last = matches!(reply, Reply::End | Reply::Failed(_));
+    if let Some(call) = pending.get(&id) {
+        let _ = call.replies.send(reply);
+    }
+    if last {
+        pending.remove(&id);
+    }
+}
+
+// A relayed call; one dropped before the agent finished answering is cancelled there
+struct Call {
+    id: u64,
+    session: Session,
+    finished: bool,
+}
+
+impl Call {
+    // The agent answered in full, or cannot anymore
+    fn done(&mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        PENDING.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+        let mut session = self.session.clone();
+        let cancel = serde_json::to_string(&ToAgent::Cancel { id: self.id }).unwrap_or_default();
+        actix_web::rt::spawn(async move {
+            let _ = session.text(cancel).await;
+        });
+    }
+}
+
+// The relay: /<cluster or ->/<node>/<engine path> on the loopback listener
+async fn relay(req: HttpRequest, body: web::Bytes) -> HttpResponse {
+    let mut segments = req.path().trim_start_matches('/').splitn(3, '/');
+    let cluster = segments.next().filter(|cluster| *cluster != "-");
+    let node = segments.next().unwrap_or_default();
+    let mut path = format!("/{}", segments.next().unwrap_or_default());
+    if !req.query_string().is_empty() {
+        path = format!("{}?{}", path, req.query_string());
+    }
+
+    let found = {
+        let agents = AGENTS.lock().unwrap_or_else(|e| e.into_inner());
+        agents
+            .iter()
+            .find(|agent| agent.info.node == node && agent.info.cluster.as_deref() == cluster)
+            .map(|agent| (agent.connection, agent.session.clone()))
+    };
+    let Some((connection, mut session)) = found else {
+        return HttpResponse::BadGateway().body(format!("Agent {} is not connected", node));
+    };
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let (replies, mut answer) = mpsc::unbounded_channel();
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).insert(id, Pending { connection, replies });
+    let mut call = Call { id, session: session.clone(), finished: false };
+
+    let headers = req
+        .headers()
+        .iter()
+        .filter(|(name, _)| !HOP_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let request = ToAgent::Request { id, method: req.method().to_string(), path, headers };
+    if send(&mut session, &request, id, &body).await.is_err() {
+        call.done();
+        return HttpResponse::BadGateway().body(format!("Agent {} disconnected", node));
+    }
+
+    let (status, headers) = match tokio::time::timeout(ANSWER_TIMEOUT, answer.recv()).await {
+        Ok(Some(Reply::Head { status, headers })) => (status, headers),
+        Ok(Some(Reply::Failed(message))) => {
+            call.done();
+            return HttpResponse::BadGateway().body(format!("Agent {} failed: {}", node, message));
+        }
+        Ok(Some(_)) | Ok(None) => return HttpResponse::BadGateway().body(format!("Agent {} disconnected", node)),
+        Err(_) => return HttpResponse::GatewayTimeout().body(format!("Agent {} did not answer", node)),
+    };
+    let status = actix_web::http::StatusCode::from_u16(status).unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let mut response = HttpResponse::build(status);
+    for (name, value) in headers {
+        response.append_header((name, value));
+    }
+    // Stream the body as the pieces arrive, so log streams work through agents too
+    let body = futures::stream::unfold((answer, call), |(mut answer, mut call)| async move {
+        match answer.recv().await {
+            Some(Reply::Body(bytes)) => Some((Ok::<_, actix_web::Error>(bytes), (answer, call))),
+            Some(Reply::End | Reply::Failed(_)) => {
+                call.done();
+                None
+            }
+            _ => None,
+        }
+    });
+    response.streaming(body)
+}
+
+// A call to an agent: the request, its body as binary pieces behind the call's id
+// (bodies need not be text), then the end of the request
+async fn send(session: &mut Session, request: &ToAgent, id: u64, body: &[u8]) -> Result<(), actix_ws::Closed> {
+    session.text(serde_json::to_string(request).unwrap_or_default()).await?;
+    for piece in body.chunks(CHUNK_SIZE) {
+        let mut frame = id.to_be_bytes().to_vec();
+        frame.extend_from_slice(piece);
+        session.binary(frame).await?;
+    }
+    session.text(serde_json::to_string(&ToAgent::End { id }).unwrap_or_default()).await
+}
+
+fn now() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
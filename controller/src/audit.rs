@@ -0,0 +1,258 @@
+// Audit log of the API calls that change something
+//
+// Every request other than GET, HEAD and OPTIONS is appended as one JSON line to the file
+// at $MOGWAI_AUDIT_LOG (default: /var/lib/mogwai/audit.jsonl): when, who, what payload
+// and with which outcome. Entries are appended, and only dropped by retention (see
+// retention.rs); GET /audit queries them.
+//
+// The caller is the client named by a verified JWT (see auth), by a bearer token from
+// $MOGWAI_CLIENT_TOKENS (the same `name=token,...` list the engines use), or else by the
+// X-Mogwai-Client header. Tokens themselves are never written.
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{auth, request_id};
+
+// Payloads above this size are recorded truncated
+const MAX_PAYLOAD_BYTES: usize = 16 * 1024;
+
+// Entries returned by GET /audit unless ?limit= says otherwise
+const DEFAULT_LIMIT: usize = 100;
+
+// One recorded call
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: f64,               // Unix time in seconds the call arrived
+    pub client: Option<String>,       // Calling client, None = anonymous
+    pub authenticated: bool,          // The client was named by a known bearer token
+    pub peer: Option<String>,         // Caller address (X-Forwarded-For behind the ingress)
+    #[serde(default)]
+    pub request_id: Option<String>,   // X-Request-Id, also in the controller and engine logs
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub query: String,
+    pub payload: serde_json::Value,   // JSON body as sent, other bodies as a string, null if empty
+    pub status: u16,
+    pub duration_ms: u64,
+}
+
+// Serializes appends so concurrent calls do not interleave their lines
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+// token -> client name, from MOGWAI_CLIENT_TOKENS
+static TOKENS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+pub fn path() -> PathBuf {
+    PathBuf::from(std::env::var("MOGWAI_AUDIT_LOG").unwrap_or_else(|_| "/var/lib/mogwai/audit.jsonl".to_string()))
+}
+
+fn tokens() -> &'static HashMap<String, String> {
+    TOKENS.get_or_init(|| {
+        std::env::var("MOGWAI_CLIENT_TOKENS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (name, token) = pair.trim().split_once('=')?;
+                (!name.is_empty() && !token.is_empty()).then(|| (token.to_string(), name.to_string()))
+            })
+            .collect()
+    })
+}
+
+// Check the log can be written before serving, so a missing volume shows up at startup
+pub fn init() -> Result<(), String> {
+    let path = path();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("Cannot create audit log directory {}: {}", dir.display(), e))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Cannot open audit log {}: {}", path.display(), e))?;
+    tracing::info!(path = %path.display(), "Recording API actions");
+    Ok(())
+}
+
+// Middleware recording every call that is not a plain read
+pub async fn record(mut req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    }
+
+    let started = Instant::now();
+    let timestamp = now_secs();
+    // Read the body for the log and hand it on unchanged
+    let body = req.extract::<web::Bytes>().await?;
+    req.set_payload(Payload::from(body.clone()));
+
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let token_client = header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer ").map(|token| token.trim().to_string()))
+        .and_then(|token| tokens().get(&token).cloned());
+    let authenticated = token_client.is_some();
+    let client = token_client.or_else(|| header("x-mogwai-client").filter(|name| !name.trim().is_empty()));
+    let peer = header("x-forwarded-for")
+        .and_then(|value| value.split(',').next().map(|first| first.trim().to_string()))
+        .or_else(|| req.peer_addr().map(|addr| addr.ip().to_string()));
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let query = req.query_string().to_string();
+    let request_id = request_id::of(req.request());
+
+    let outcome = next.call(req).await;
+    let status = match &outcome {
+        Ok(response) => response.status().as_u16(),
+        Err(e) => e.as_response_error().status_code().as_u16(),
+    };
+    // A verified JWT names the client better than any header
+    let (client, authenticated) = match outcome.as_ref().ok().and_then(|response| auth::identity(response.request())) {
+        Some(identity) => (identity.client.or(client), true),
+        None => (client, authenticated),
+    };
+    append(&Entry {
+        timestamp,
+        client,
+        authenticated,
+        peer,
+        request_id,
+        method,
+        path,
+        query,
+        payload: payload_value(&body),
+        status,
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
+    outcome.map(ServiceResponse::map_into_boxed_body)
+}
+
+// JSON bodies as they are, anything else as text, cut at MAX_PAYLOAD_BYTES
+fn payload_value(body: &[u8]) -> serde_json::Value {
+    if body.is_empty() {
+        return serde_json::Value::Null;
+    }
+    if body.len() <= MAX_PAYLOAD_BYTES {
+        if let Ok(value) = serde_json::from_slice(body) {
+            return value;
+        }
+    }
+    let text = String::from_utf8_lossy(&body[..body.len().min(MAX_PAYLOAD_BYTES)]).into_owned();
+    if body.len() > MAX_PAYLOAD_BYTES {
+        serde_json::Value::String(format!("{}... ({} bytes)", text, body.len()))
+    } else {
+        serde_json::Value::String(text)
+    }
+}
+
+fn append(entry: &Entry) {
+    let Ok(mut line) = serde_json::to_string(entry) else {
+        return;
+    };
+    line.push('\n');
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path())
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(e) = written {
+        // Still visible in the controller's log when the file cannot be written
+        tracing::error!(error = %e, entry = line.trim_end(), "Audit log write failed");
+    }
+}
+
+// Drop entries older than `cutoff` (Unix seconds) and all but the newest `keep`; returns
+// how many went. The file is rewritten through a temporary file, so a crash mid-way leaves
+// it whole; lines that do not parse are kept.
+pub fn prune(cutoff: Option<f64>, keep: Option<usize>) -> Result<usize, String> {
+    let path = path();
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let lines: Vec<String> = match fs::File::open(&path) {
+        Ok(file) => BufReader::new(file).lines().map_while(Result::ok).collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Cannot read audit log {}: {}", path.display(), e)),
+    };
+    let timestamps: Vec<Option<f64>> = lines
+        .iter()
+        .map(|line| serde_json::from_str::<Entry>(line).ok().map(|entry| entry.timestamp))
+        .collect();
+    let entries = timestamps.iter().flatten().count();
+    let mut skip = entries.saturating_sub(keep.unwrap_or(usize::MAX)); // Oldest entries over the count
+    let mut kept = Vec::with_capacity(lines.len());
+    for (line, timestamp) in lines.iter().zip(&timestamps) {
+        match timestamp {
+            Some(_) if skip > 0 => skip -= 1,
+            Some(timestamp) if cutoff.is_some_and(|cutoff| *timestamp < cutoff) => {}
+            _ => kept.push(line.as_str()),
+        }
+    }
+    let pruned = lines.len() - kept.len();
+    if pruned == 0 {
+        return Ok(0);
+    }
+    let temp = path.with_extension("jsonl.tmp");
+    let mut contents = kept.join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    fs::write(&temp, contents)
+        .and_then(|_| fs::rename(&temp, &path))
+        .map_err(|e| format!("Cannot rewrite audit log {}: {}", path.display(), e))?;
+    Ok(pruned)
+}
+
+// Filters of GET /audit
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub since: Option<f64>,     // Only entries after this Unix time
+    pub until: Option<f64>,     // Only entries before this Unix time
+    pub client: Option<String>, // Only this client's calls
+    pub path: Option<String>,   // Only calls to paths starting with this
+    pub failed: Option<bool>,   // true = only calls answered with an error status
+    pub request_id: Option<String>, // Only the call with this X-Request-Id
+    pub limit: Option<usize>,   // Newest entries to return, default 100
+}
+
+// Matching entries, newest first
+pub fn query(filter: &AuditQuery) -> Result<Vec<Entry>, String> {
+    let path = path();
+    let file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Cannot read audit log {}: {}", path.display(), e)),
+    };
+    let mut entries: Vec<Entry> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<Entry>(&line).ok())
+        .filter(|entry| filter.since.is_none_or(|since| entry.timestamp > since))
+        .filter(|entry| filter.until.is_none_or(|until| entry.timestamp < until))
+        .filter(|entry| filter.client.is_none() || entry.client == filter.client)
+        .filter(|entry| filter.path.as_ref().is_none_or(|prefix| entry.path.starts_with(prefix.as_str())))
+        .filter(|entry| filter.failed.is_none_or(|failed| failed == (entry.status >= 400)))
+        .filter(|entry| filter.request_id.is_none() || entry.request_id == filter.request_id)
+        .collect();
+    let limit = filter.limit.unwrap_or(DEFAULT_LIMIT);
+    entries.reverse();
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
@@ -0,0 +1,388 @@
+// Optional JWT authentication, so the controller can sit behind the organisation's SSO
+//
+// Off unless a key source is set:
+//   $MOGWAI_JWT_SECRET       shared secret of HS256/HS384/HS512 tokens
+//   $MOGWAI_JWT_JWKS_URL     JWKS of the identity provider, for RS256/RS384/RS512, ES256 and ES384 tokens
+// Once on, every request but CORS preflights needs `Authorization: Bearer <jwt>` with a
+// valid signature, an `exp` in the future and, when set, the expected issuer and audience:
+//   $MOGWAI_JWT_ISSUER       required `iss`
+//   $MOGWAI_JWT_AUDIENCE     required in `aud`
+//
+// The token's roles decide what the caller may do:
+//   viewer     read: GET requests, task lists, estimates
+//   operator   viewer, plus start, stop and extend tests, scenarios, campaigns, chaos runs,
+//              baselines and calibrations
+//   admin      operator, plus spawn and remove engines, stop all tasks, delete baselines, read /audit,
+//              prune old data and connect engines in agent mode
+// Roles come from the claim $MOGWAI_JWT_ROLES_CLAIM (default `roles`; dots reach into
+// objects, e.g. `realm_access.roles`), a string or a list. $MOGWAI_JWT_ROLE_MAP maps the
+// provider's role names, e.g. `perf-admins=admin,perf-team=operator,staff=viewer`; without
+// it the role names are taken as they are. The highest role counts.
+//
+// The caller's name ($MOGWAI_JWT_CLIENT_CLAIM, default `sub`) is written to the audit log
+// and sent to the engines as X-Mogwai-Client, so tasks are scoped per user there.
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage, HttpResponse};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::{hmac, signature};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Clock difference tolerated on exp and nbf
+const LEEWAY_SECS: f64 = 60.0;
+
+// How long fetched keys are used before they are fetched again
+const JWKS_TTL: Duration = Duration::from_secs(3600);
+
+// A token signed with a key id the cached keys lack refetches them at most this often
+const JWKS_RETRY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    fn parse(name: &str) -> Option<Role> {
+        match name {
+            "viewer" => Some(Role::Viewer),
+            "operator" => Some(Role::Operator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+// Who a verified token names; kept in the request's extensions
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub client: Option<String>,
+    pub role: Role,
+}
+
+struct Config {
+    secret: Option<Vec<u8>>,
+    jwks_url: Option<String>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    roles_claim: String,
+    role_map: HashMap<String, Role>,
+    client_claim: String,
+}
+
+// One key of a JWKS document
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk {
+    kty: String,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    n: Option<String>, // RSA modulus
+    #[serde(default)]
+    e: Option<String>, // RSA exponent
+    #[serde(default)]
+    crv: Option<String>,
+    #[serde(default)]
+    x: Option<String>, // EC point
+    #[serde(default)]
+    y: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+struct KeyCache {
+    keys: Vec<Jwk>,
+    fetched: Option<Instant>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+static KEYS: Mutex<KeyCache> = Mutex::new(KeyCache { keys: Vec::new(), fetched: None });
+
+fn config() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+        let role_map = var("MOGWAI_JWT_ROLE_MAP")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (claim, role) = pair.trim().split_once('=')?;
+                match Role::parse(role.trim()) {
+                    Some(role) => Some((claim.trim().to_string(), role)),
+                    None => {
+                        tracing::warn!(entry = pair.trim(), "Ignoring a MOGWAI_JWT_ROLE_MAP entry: roles are viewer, operator and admin");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Config {
+            secret: var("MOGWAI_JWT_SECRET").map(String::into_bytes),
+            jwks_url: var("MOGWAI_JWT_JWKS_URL"),
+            issuer: var("MOGWAI_JWT_ISSUER"),
+            audience: var("MOGWAI_JWT_AUDIENCE"),
+            roles_claim: var("MOGWAI_JWT_ROLES_CLAIM").unwrap_or_else(|| "roles".to_string()),
+            role_map,
+            client_claim: var("MOGWAI_JWT_CLIENT_CLAIM").unwrap_or_else(|| "sub".to_string()),
+        }
+    })
+}
+
+pub fn enabled() -> bool {
+    let config = config();
+    config.secret.is_some() || config.jwks_url.is_some()
+}
+
+// One line for the startup log
+pub fn describe() -> String {
+    let config = config();
+    match (&config.secret, &config.jwks_url) {
+        (None, None) => "JWT authentication off (set MOGWAI_JWT_SECRET or MOGWAI_JWT_JWKS_URL to require it)".to_string(),
+        (Some(_), None) => "Requiring JWTs signed with the shared secret".to_string(),
+        (None, Some(url)) => format!("Requiring JWTs signed with the keys at {}", url),
+        (Some(_), Some(url)) => format!("Requiring JWTs signed with the shared secret or the keys at {}", url),
+    }
+}
+
+// The identity of a request the middleware let through; None while authentication is off
+pub fn identity(req: &actix_web::HttpRequest) -> Option<Identity> {
+    req.extensions().get::<Identity>().cloned()
+}
+
+// The role a request needs
+fn required_role(method: &Method, path: &str) -> Role {
+    let path = path.strip_prefix("/v1").unwrap_or(path);
+    let first = path.trim_start_matches('/').split('/').next().unwrap_or("");
+    match (method, first) {
+        (&Method::GET, "audit") => Role::Admin,
+        // Engines in agent mode register themselves
+        (&Method::GET, "agents") if path.trim_end_matches('/') == "/agents/connect" => Role::Admin,
+        (&Method::GET | &Method::HEAD, _) => Role::Viewer,
+        // Reads that are POSTs
+        (&Method::POST, "tasks" | "estimate") => Role::Viewer,
+        (&Method::POST, "spawn-engine" | "spawn-engines" | "remove-engine" | "stop-all" | "prune") => Role::Admin,
+        (&Method::DELETE, _) => Role::Admin,
+        _ => Role::Operator,
+    }
+}
+
+// Middleware turning away requests without a valid token or with too low a role
+pub async fn authorize(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    if !enabled() || *req.method() == Method::OPTIONS {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    }
+    let token = req
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    let identity = match token {
+        None => Err("Missing bearer token".to_string()),
+        Some(token) => verify(&token).await,
+    };
+    let identity = match identity {
+        Ok(identity) => identity,
+        Err(e) => {
+            let response = HttpResponse::Unauthorized()
+                .insert_header(("WWW-Authenticate", "Bearer"))
+                .body(format!("Unauthorized: {}", e));
+            return Ok(req.into_response(response));
+        }
+    };
+    let needed = required_role(req.method(), req.path());
+    let granted = identity.role;
+    // Kept even when refused, so the audit log names who was turned away
+    req.extensions_mut().insert(identity);
+    if granted < needed {
+        let response = HttpResponse::Forbidden().body(format!(
+            "Forbidden: {} {} needs the {} role, the token grants {}",
+            req.method(),
+            req.path(),
+            needed.name(),
+            granted.name()
+        ));
+        return Ok(req.into_response(response));
+    }
+    next.call(req).await.map(ServiceResponse::map_into_boxed_body)
+}
+
+// Check a token's signature and claims; returns who it names
+async fn verify(token: &str) -> Result<Identity, String> {
+    let config = config();
+    let malformed = || "Malformed token".to_string();
+    let (message, sig) = token.rsplit_once('.').ok_or_else(malformed)?;
+    let (header, payload) = message.split_once('.').ok_or_else(malformed)?;
+    let decode = |part: &str| URL_SAFE_NO_PAD.decode(part).map_err(|_| malformed());
+    let header: Header = serde_json::from_slice(&decode(header)?).map_err(|_| "Malformed token header".to_string())?;
+    let claims: Value = serde_json::from_slice(&decode(payload)?).map_err(|_| "Malformed token claims".to_string())?;
+    let sig = decode(sig)?;
+
+    match header.alg.as_str() {
+        "HS256" | "HS384" | "HS512" => {
+            let secret = config.secret.as_ref().ok_or("HMAC tokens are not accepted")?;
+            let algorithm = match header.alg.as_str() {
+                "HS256" => hmac::HMAC_SHA256,
+                "HS384" => hmac::HMAC_SHA384,
+                _ => hmac::HMAC_SHA512,
+            };
+            hmac::verify(&hmac::Key::new(algorithm, secret), message.as_bytes(), &sig).map_err(|_| "Invalid signature")?;
+        }
+        "RS256" | "RS384" | "RS512" | "ES256" | "ES384" => {
+            let key = find_key(header.kid.as_deref()).await?;
+            verify_with(&key, &header.alg, message.as_bytes(), &sig)?;
+        }
+        alg => return Err(format!("Unsupported algorithm {}", alg)),
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+    match claims["exp"].as_f64() {
+        None => return Err("Token has no expiry".to_string()),
+        Some(exp) if exp + LEEWAY_SECS < now => return Err("Token expired".to_string()),
+        Some(_) => {}
+    }
+    if claims["nbf"].as_f64().is_some_and(|nbf| nbf - LEEWAY_SECS > now) {
+        return Err("Token not valid yet".to_string());
+    }
+    if let Some(issuer) = &config.issuer {
+        if claims["iss"].as_str() != Some(issuer.as_str()) {
+            return Err("Unexpected issuer".to_string());
+        }
+    }
+    if let Some(audience) = &config.audience {
+        let listed = match &claims["aud"] {
+            Value::String(aud) => aud == audience,
+            Value::Array(auds) => auds.iter().any(|aud| aud.as_str() == Some(audience.as_str())),
+            _ => false,
+        };
+        if !listed {
+            return Err("Token is not meant for this audience".to_string());
+        }
+    }
+
+    let mut roles = &claims;
+    for part in config.roles_claim.split('.') {
+        roles = &roles[part];
+    }
+    let names: Vec<&str> = match roles {
+        Value::String(role) => vec![role.as_str()],
+        Value::Array(list) => list.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let role = names
+        .iter()
+        .filter_map(|name| match config.role_map.is_empty() {
+            true => Role::parse(name),
+            false => config.role_map.get(*name).copied(),
+        })
+        .max()
+        .ok_or("Token grants no mogwai role")?;
+
+    let client = claims[config.client_claim.as_str()].as_str().map(client_name);
+    Ok(Identity { client, role })
+}
+
+// Engines accept client names of A-Z a-z 0-9 _ - . only, up to 64 characters
+fn client_name(claim: &str) -> String {
+    claim
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .take(64)
+        .collect()
+}
+
+// The JWKS key a token was signed with, fetching the keys when they are stale or lack it
+async fn find_key(kid: Option<&str>) -> Result<Jwk, String> {
+    let url = config().jwks_url.as_ref().ok_or("Only HMAC tokens are accepted")?;
+    let lookup = |keys: &[Jwk]| match kid {
+        Some(kid) => keys.iter().find(|key| key.kid.as_deref() == Some(kid)).cloned(),
+        None if keys.len() == 1 => keys.first().cloned(),
+        None => None,
+    };
+    let refetch = {
+        let cache = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+        let age = cache.fetched.map(|fetched| fetched.elapsed());
+        match (lookup(&cache.keys), age) {
+            (Some(key), Some(age)) if age < JWKS_TTL => return Ok(key),
+            (None, Some(age)) if age < JWKS_RETRY => return Err("Unknown signing key".to_string()),
+            _ => true,
+        }
+    };
+    if refetch {
+        let jwks: Jwks = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Cannot fetch signing keys: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid JWKS at {}: {}", url, e))?;
+        let mut cache = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+        cache.keys = jwks.keys;
+        cache.fetched = Some(Instant::now());
+    }
+    let cache = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    lookup(&cache.keys).ok_or_else(|| "Unknown signing key".to_string())
+}
+
+fn verify_with(key: &Jwk, alg: &str, message: &[u8], sig: &[u8]) -> Result<(), String> {
+    let verified = match (alg, key.kty.as_str()) {
+        ("RS256" | "RS384" | "RS512", "RSA") => {
+            let params = match alg {
+                "RS256" => &signature::RSA_PKCS1_2048_8192_SHA256,
+                "RS384" => &signature::RSA_PKCS1_2048_8192_SHA384,
+                _ => &signature::RSA_PKCS1_2048_8192_SHA512,
+            };
+            let (n, e) = (key_part(&key.n)?, key_part(&key.e)?);
+            signature::RsaPublicKeyComponents { n: &n, e: &e }.verify(params, message, sig)
+        }
+        ("ES256" | "ES384", "EC") => {
+            let algorithm = match (alg, key.crv.as_deref()) {
+                ("ES256", Some("P-256")) => &signature::ECDSA_P256_SHA256_FIXED,
+                ("ES384", Some("P-384")) => &signature::ECDSA_P384_SHA384_FIXED,
+                _ => return Err(format!("Key does not match algorithm {}", alg)),
+            };
+            // Uncompressed point: 0x04, x, y
+            let mut point = vec![0x04];
+            point.extend(key_part(&key.x)?);
+            point.extend(key_part(&key.y)?);
+            signature::UnparsedPublicKey::new(algorithm, &point).verify(message, sig)
+        }
+        _ => return Err(format!("Key does not match algorithm {}", alg)),
+    };
+    verified.map_err(|_| "Invalid signature".to_string())
+}
+
+fn key_part(value: &Option<String>) -> Result<Vec<u8>, String> {
+    value
+        .as_deref()
+        .and_then(|value| URL_SAFE_NO_PAD.decode(value).ok())
+        .ok_or_else(|| "Incomplete signing key".to_string())
+}
@@ -0,0 +1,280 @@
+// Baselines: reference results later runs of the same test on the same node are held to
+//
+// POST /baselines marks a completed run as the baseline of its node and test type, either
+//   {"job": "scenario-3", "step": "disk-read"}    every test of a scenario step, by the mean of its runs
+//   {"node": "worker-1", "task_id": "disk-7"}     one engine result ("type" when the id has no type prefix)
+// with an optional "cluster" and "threshold_percent" (default $MOGWAI_REGRESSION_THRESHOLD, else 10).
+// Baselines are kept in the JSON file at $MOGWAI_BASELINES_FILE (default: /var/lib/mogwai/baselines.json).
+//
+// Every test a scenario or campaign completes afterwards is checked against its baseline:
+// metrics that got worse by more than the threshold are listed as regressions on the task
+// and the job is flagged. Which way is worse comes from the metric's name (see worse_when).
+// When $MOGWAI_NOTIFY_URL is set, jobs that finish with regressions are POSTed there.
+
+use reqwest::header::HeaderMap;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::clusters;
+use crate::scenarios::{self, Job};
+use crate::stats;
+
+// Threshold when neither the baseline nor $MOGWAI_REGRESSION_THRESHOLD sets one
+const DEFAULT_THRESHOLD_PERCENT: f64 = 10.0;
+
+// Serializes read-modify-write cycles of the file
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub node: String,
+    #[serde(default)]
+    pub cluster: Option<String>,
+    #[serde(rename = "type")]
+    pub test_type: String,
+    pub metrics: BTreeMap<String, f64>,
+    pub threshold_percent: f64,
+    pub source: String,  // Task id, or job/step
+    pub created_at: f64, // Unix time in seconds
+}
+
+// A metric that got worse than its baseline allows
+#[derive(Debug, Clone, Serialize)]
+pub struct Regression {
+    pub metric: String,
+    pub baseline: f64,
+    pub value: f64,
+    pub change_percent: Option<f64>, // None when the baseline is 0
+    pub threshold_percent: f64,
+}
+
+// Body of POST /baselines
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mark {
+    #[serde(default)]
+    job: Option<String>,
+    #[serde(default)]
+    step: Option<String>,
+    #[serde(default)]
+    node: Option<String>,
+    #[serde(default)]
+    task_id: Option<String>,
+    #[serde(default, rename = "type")]
+    test_type: Option<String>,
+    #[serde(default)]
+    cluster: Option<String>,
+    #[serde(default)]
+    threshold_percent: Option<f64>,
+}
+
+enum Direction {
+    Higher, // More is better: rates, throughput, achieved load
+    Lower,  // Less is better: latencies, contention, kills, corruption
+}
+
+// Which way a metric goes when it gets worse; None for metrics that only describe the run
+// (elapsed time, iteration counts that grow with the duration)
+fn worse_when(metric: &str) -> Option<Direction> {
+    let name = metric.rsplit('.').next().unwrap_or(metric);
+    if name.ends_with("_us") || matches!(name, "ns_per_call" | "contended_percent" | "oom_kills" | "corrupt_blocks") {
+        Some(Direction::Lower)
+    } else if name.ends_with("_per_s") || matches!(name, "achieved_load" | "cpu_load") {
+        Some(Direction::Higher)
+    } else {
+        None
+    }
+}
+
+fn path() -> PathBuf {
+    PathBuf::from(std::env::var("MOGWAI_BASELINES_FILE").unwrap_or_else(|_| "/var/lib/mogwai/baselines.json".to_string()))
+}
+
+fn default_threshold() -> f64 {
+    std::env::var("MOGWAI_REGRESSION_THRESHOLD")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|&threshold: &f64| threshold >= 0.0)
+        .unwrap_or(DEFAULT_THRESHOLD_PERCENT)
+}
+
+// Every stored baseline; a missing file means none
+pub fn all() -> Result<Vec<Baseline>, String> {
+    let path = path();
+    match std::fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid baselines file {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read baselines file {}: {}", path.display(), e)),
+    }
+}
+
+fn save(baselines: &[Baseline]) -> Result<(), String> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let data = serde_json::to_string_pretty(baselines).map_err(|e| e.to_string())?;
+    std::fs::write(&path, data).map_err(|e| format!("Failed to write baselines file {}: {}", path.display(), e))
+}
+
+fn same_target(baseline: &Baseline, cluster: Option<&str>, node: &str, test_type: &str) -> bool {
+    baseline.cluster.as_deref() == cluster && baseline.node == node && baseline.test_type == test_type
+}
+
+// Store baselines, replacing those of the same node and type
+fn store(new: Vec<Baseline>) -> Result<Vec<Baseline>, String> {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut baselines = all()?;
+    baselines.retain(|old| !new.iter().any(|b| same_target(old, b.cluster.as_deref(), &b.node, &b.test_type)));
+    baselines.extend(new.iter().cloned());
+    save(&baselines)?;
+    Ok(new)
+}
+
+// Forget the baseline of a node and test type; false if there was none
+pub fn remove(cluster: Option<&str>, node: &str, test_type: &str) -> Result<bool, String> {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut baselines = all()?;
+    let before = baselines.len();
+    baselines.retain(|baseline| !same_target(baseline, cluster, node, test_type));
+    if baselines.len() == before {
+        return Ok(false);
+    }
+    save(&baselines)?;
+    Ok(true)
+}
+
+// Mark a run as baseline; returns the baselines stored
+pub async fn mark(mark: Mark, client: &HttpClient, headers: &HeaderMap) -> Result<Vec<Baseline>, String> {
+    let threshold_percent = match mark.threshold_percent {
+        Some(threshold) if threshold < 0.0 => return Err("threshold_percent cannot be negative".to_string()),
+        Some(threshold) => threshold,
+        None => default_threshold(),
+    };
+    let baseline = |node: String, cluster: Option<String>, test_type: String, metrics, source: String| Baseline {
+        node,
+        cluster,
+        test_type,
+        metrics,
+        threshold_percent,
+        source,
+        created_at: now_secs(),
+    };
+
+    match (mark.job, mark.step, mark.node, mark.task_id) {
+        (Some(job), Some(step), None, None) => {
+            let job_state = scenarios::get(&job).ok_or_else(|| format!("No scenario {}", job))?;
+            let state = job_state
+                .steps
+                .iter()
+                .find(|state| state.name == step)
+                .ok_or_else(|| format!("Scenario {} has no step '{}'", job, step))?;
+            let new: Vec<Baseline> = state
+                .summary
+                .iter()
+                .filter(|test| !test.metrics.is_empty())
+                .map(|test| {
+                    let means = test.metrics.iter().map(|(name, summary)| (name.clone(), summary.mean)).collect();
+                    baseline(test.node.clone(), test.cluster.clone(), test.test_type.clone(), means, format!("{}/{}", job, step))
+                })
+                .collect();
+            if new.is_empty() {
+                return Err(format!("Step '{}' of {} has no completed runs", step, job));
+            }
+            store(new)
+        }
+        (None, None, Some(node), Some(task_id)) => {
+            let test_type = match mark.test_type {
+                Some(test_type) => test_type,
+                // Engine task ids are <type>-<n>
+                None => task_id
+                    .rsplit_once('-')
+                    .map(|(prefix, _)| prefix.to_string())
+                    .ok_or_else(|| format!("Cannot tell the test type from task id {}; add \"type\"", task_id))?,
+            };
+            let url = clusters::engine_url(mark.cluster.as_deref(), &node, &format!("/results/{}", task_id))
+                .map_err(|e| e.to_string())?;
+            let response = client
+                .get(&url)
+                .headers(headers.clone())
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("No result for task {} on {}", task_id, node));
+            }
+            let result: Value = response.json().await.map_err(|e| format!("Invalid result: {}", e))?;
+            if result["result"].is_null() || result["result"]["stopped"].as_bool() == Some(true) {
+                return Err(format!("Task {} did not complete", task_id));
+            }
+            store(vec![baseline(node, mark.cluster, test_type, stats::metrics(&result["result"]), task_id)])
+        }
+        _ => Err("Give either job and step, or node and task_id".to_string()),
+    }
+}
+
+// The metrics of a completed test that regressed against its baseline; none without one
+pub fn check(cluster: Option<&str>, node: &str, test_type: &str, metrics: &BTreeMap<String, f64>) -> Vec<Regression> {
+    let baselines = match all() {
+        Ok(baselines) => baselines,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return Vec::new();
+        }
+    };
+    let Some(baseline) = baselines.iter().find(|b| same_target(b, cluster, node, test_type)) else {
+        return Vec::new();
+    };
+    let threshold = baseline.threshold_percent;
+    baseline
+        .metrics
+        .iter()
+        .filter_map(|(metric, &base)| {
+            let direction = worse_when(metric)?;
+            let value = *metrics.get(metric)?;
+            let change_percent = (base != 0.0).then(|| (value - base) / base.abs() * 100.0);
+            let regressed = match (direction, change_percent) {
+                (Direction::Higher, Some(change)) => change < -threshold,
+                (Direction::Lower, Some(change)) => change > threshold,
+                // Nothing to scale by: only going from none to some of a bad thing counts
+                (Direction::Higher, None) => false,
+                (Direction::Lower, None) => value > 0.0,
+            };
+            regressed.then(|| Regression {
+                metric: metric.clone(),
+                baseline: base,
+                value,
+                change_percent,
+                threshold_percent: threshold,
+            })
+        })
+        .collect()
+}
+
+// Tell $MOGWAI_NOTIFY_URL about a job that finished with regressions
+pub async fn notify(client: &HttpClient, job: &Job) {
+    let Ok(url) = std::env::var("MOGWAI_NOTIFY_URL") else {
+        return;
+    };
+    if url.is_empty() {
+        return;
+    }
+    let body = json!({ "event": "regression", "job": job });
+    match client.post(&url).json(&body).send().await {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response) => tracing::info!(job_id = %job.id, status = response.status().as_u16(), "Regression notification sent"),
+        Err(e) => tracing::warn!(job_id = %job.id, error = %e, "Regression notification failed"),
+    }
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
@@ -0,0 +1,110 @@
+// A/B campaigns: the same test on two node sets, or with two sets of parameters, compared
+//
+// A campaign is a YAML (or JSON) document posted to /campaigns, e.g.
+//   name: kernel-6.9
+//   test: {type: disk, size: 512, duration: 60}
+//   repeat: 5
+//   a: {name: "6.8", nodes: [worker-1, worker-2]}
+//   b: {name: "6.9", nodes: [worker-3, worker-4]}
+// or, for two parameter variants on the same node:
+//   node: worker-1
+//   test: {type: lock, threads: 8, duration: 30}
+//   a: {params: {primitive: mutex}}
+//   b: {params: {primitive: rwlock}}
+//
+// It runs as a scenario job with one parallel step per arm, each repeated `repeat` times.
+// Arms on different nodes run at the same time; arms sharing a node run one after the
+// other, so they do not disturb each other. When the job finishes it reports, for every
+// metric, both means, the delta and whether the delta stands out from the noise.
+
+use reqwest::header::HeaderMap;
+use reqwest::Client as HttpClient;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::scenarios::{self, Arms, Job, Scenario, StepSpec, TestSpec};
+
+// Runs per arm when the campaign sets none; a significance hint needs at least 2
+const DEFAULT_REPEAT: u32 = 3;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Campaign {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    node: Option<String>,    // Node of arms that list none
+    #[serde(default)]
+    cluster: Option<String>,
+    test: TestSpec,          // The test both arms run
+    #[serde(default)]
+    repeat: Option<u32>,     // Runs per node and arm
+    a: Arm,
+    b: Arm,
+}
+
+// One side of the comparison
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Arm {
+    #[serde(default)]
+    name: Option<String>,       // Default a and b
+    #[serde(default)]
+    nodes: Vec<String>,         // Default the test's node, else the campaign's
+    #[serde(default)]
+    params: Map<String, Value>, // Replace or add test parameters
+}
+
+pub fn parse(body: &[u8]) -> Result<Campaign, String> {
+    serde_yaml::from_slice(body).map_err(|e| format!("Invalid campaign: {}", e))
+}
+
+// Turn the campaign into a scenario and start it; returns the job as it starts
+pub fn submit(campaign: Campaign, client: HttpClient, headers: HeaderMap) -> Result<Job, String> {
+    let a_name = campaign.a.name.clone().unwrap_or_else(|| "a".to_string());
+    let b_name = campaign.b.name.clone().unwrap_or_else(|| "b".to_string());
+    if a_name == b_name {
+        return Err(format!("Both arms are called '{}'", a_name));
+    }
+    let default_node = campaign.test.node.clone().or_else(|| campaign.node.clone());
+    let a_nodes = arm_nodes(&campaign.a, &default_node);
+    let b_nodes = arm_nodes(&campaign.b, &default_node);
+    let shared = a_nodes.iter().any(|node| b_nodes.contains(node));
+    let repeat = campaign.repeat.unwrap_or(DEFAULT_REPEAT);
+
+    let step = |name: &str, arm: &Arm, nodes: &[Option<String>], after: Option<Vec<String>>| {
+        let tests = nodes
+            .iter()
+            .map(|node| {
+                let mut test = campaign.test.clone();
+                test.node = node.clone();
+                test.params.extend(arm.params.clone());
+                test
+            })
+            .collect();
+        StepSpec {
+            name: Some(name.to_string()),
+            after,
+            test: None,
+            parallel: Some(tests),
+            wait: None,
+            repeat: Some(repeat),
+        }
+    };
+    let steps = vec![
+        step(&a_name, &campaign.a, &a_nodes, Some(Vec::new())),
+        // Sharing a node, b waits for a (and is skipped if a fails)
+        step(&b_name, &campaign.b, &b_nodes, if shared { None } else { Some(Vec::new()) }),
+    ];
+    let scenario = Scenario { name: campaign.name, node: campaign.node, cluster: campaign.cluster, steps };
+    scenarios::submit_campaign(scenario, Arms { a: a_name, b: b_name }, client, headers)
+}
+
+// The nodes an arm runs on; None leaves it to the scenario's node
+fn arm_nodes(arm: &Arm, default_node: &Option<String>) -> Vec<Option<String>> {
+    if arm.nodes.is_empty() {
+        vec![default_node.clone()]
+    } else {
+        arm.nodes.iter().cloned().map(Some).collect()
+    }
+}
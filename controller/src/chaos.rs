@@ -0,0 +1,305 @@
+// Chaos runs: random tests on random nodes at random times, as background noise while
+// other systems are resilience-tested
+//
+// A chaos run is a YAML (or JSON) document posted to /chaos, e.g.
+//   name: noisy-neighbours
+//   window: 3600             # seconds during which tests are launched
+//   min_interval: 30         # seconds between two launches, picked at random in between
+//   max_interval: 300
+//   nodes: [worker-1, worker-2]
+//   tests:
+//     cpu: {threads: [1, 4], load: [20, 90], duration: [30, 300]}
+//     mem: {size: [256, 2048], duration: [60, 600]}
+//     disk: {size: [128, 1024], duration: 120}
+//
+// Each launch picks a test type, a node and, for every parameter given as [min, max], a
+// value in that range (whole numbers when both bounds are); other values are sent as they
+// are. Tests always have a bounded duration (default [30, 120]), so the noise ends with
+// the window. Nodes must be listed: chaos never spreads to nodes nobody picked. Every
+// launch is printed and kept in the run; stopping a run also stops the tests it started.
+//
+// Runs live in memory; finished ones are dropped as set by retention.rs.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use reqwest::header::HeaderMap;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::Instrument;
+
+use crate::clusters;
+use crate::retention;
+use crate::scenarios::{self, Status};
+
+// Duration bounds of tests that set none
+const DEFAULT_DURATION: [u64; 2] = [30, 120];
+
+// A chaos run as submitted
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChaosSpec {
+    #[serde(default)]
+    name: Option<String>,
+    window: u64,                                   // Seconds
+    #[serde(default = "default_min_interval")]
+    min_interval: u64,                             // Seconds
+    #[serde(default = "default_max_interval")]
+    max_interval: u64,
+    nodes: Vec<String>,
+    #[serde(default)]
+    cluster: Option<String>,
+    tests: BTreeMap<String, Map<String, Value>>,   // Test type -> parameters and [min, max] bounds
+    #[serde(default)]
+    seed: Option<u64>,                             // Repeat the same sequence of launches
+}
+
+fn default_min_interval() -> u64 {
+    30
+}
+
+fn default_max_interval() -> u64 {
+    300
+}
+
+// A chaos run, as reported by GET /chaos
+#[derive(Debug, Clone, Serialize)]
+pub struct ChaosRun {
+    pub id: String,
+    pub name: Option<String>,
+    pub status: Status,     // running, completed or stopped
+    pub started_at: f64,    // Unix time in seconds
+    pub ends_at: f64,       // End of the window
+    pub finished_at: Option<f64>,
+    pub seed: u64,
+    pub launches: Vec<Launch>,
+    #[serde(skip)]
+    stop: Arc<AtomicBool>,
+}
+
+// One test the run started, or tried to
+#[derive(Debug, Clone, Serialize)]
+pub struct Launch {
+    pub at: f64,
+    #[serde(rename = "type")]
+    pub test_type: String,
+    pub node: String,
+    pub params: Map<String, Value>,
+    pub task_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+static RUNS: Mutex<VecDeque<ChaosRun>> = Mutex::new(VecDeque::new());
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn parse(body: &[u8]) -> Result<ChaosSpec, String> {
+    serde_yaml::from_slice(body).map_err(|e| format!("Invalid chaos run: {}", e))
+}
+
+// Check a chaos run and start it; returns the run as it starts
+pub fn submit(spec: ChaosSpec, client: HttpClient, headers: HeaderMap) -> Result<ChaosRun, String> {
+    check(&spec)?;
+    let seed = spec.seed.unwrap_or_else(|| rand::rng().random());
+    let now = now_secs();
+    let run = ChaosRun {
+        id: format!("chaos-{}", NEXT_ID.fetch_add(1, Ordering::SeqCst)),
+        name: spec.name.clone(),
+        status: Status::Running,
+        started_at: now,
+        ends_at: now + spec.window as f64,
+        finished_at: None,
+        seed,
+        launches: Vec::new(),
+        stop: Arc::new(AtomicBool::new(false)),
+    };
+    {
+        let mut runs = lock_runs();
+        while runs.len() >= retention::max_count().max(1) {
+            match runs.iter().position(|run| run.status != Status::Running) {
+                Some(index) => runs.remove(index),
+                None => break,
+            };
+        }
+        runs.push_back(run.clone());
+    }
+    tracing::info!(chaos_id = %run.id, window_secs = spec.window, nodes = %spec.nodes.join(", "), seed, "Starting chaos run");
+    // The run logs under its id and that of the request that started it
+    let span = tracing::info_span!("chaos", chaos_id = %run.id);
+    tokio::spawn(launch_loop(run.id.clone(), spec, seed, client, headers, Arc::clone(&run.stop)).instrument(span));
+    Ok(run)
+}
+
+pub fn list() -> Vec<ChaosRun> {
+    lock_runs().iter().cloned().collect()
+}
+
+pub fn get(id: &str) -> Option<ChaosRun> {
+    lock_runs().iter().find(|run| run.id == id).cloned()
+}
+
+// Ask a run to stop launching and to stop the tests it started
+pub fn stop(id: &str) -> Option<ChaosRun> {
+    let run = get(id)?;
+    run.stop.store(true, Ordering::SeqCst);
+    Some(run)
+}
+
+// Drop finished runs that ended before `cutoff` (Unix seconds), and all but the newest
+// `keep` finished ones; returns how many went
+pub fn prune(cutoff: Option<f64>, keep: usize) -> usize {
+    let mut runs = lock_runs();
+    let before = runs.len();
+    let mut kept = 0;
+    let mut index = runs.len();
+    while index > 0 {
+        index -= 1;
+        let run = &runs[index];
+        if run.status == Status::Running {
+            continue;
+        }
+        let expired = cutoff.zip(run.finished_at).is_some_and(|(cutoff, finished_at)| finished_at < cutoff);
+        if kept < keep && !expired {
+            kept += 1;
+        } else {
+            runs.remove(index);
+        }
+    }
+    before - runs.len()
+}
+
+fn lock_runs() -> std::sync::MutexGuard<'static, VecDeque<ChaosRun>> {
+    RUNS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn update(id: &str, change: impl FnOnce(&mut ChaosRun)) {
+    if let Some(run) = lock_runs().iter_mut().find(|run| run.id == id) {
+        change(run);
+    }
+}
+
+// Say what is wrong with a chaos run before it starts
+fn check(spec: &ChaosSpec) -> Result<(), String> {
+    if spec.window == 0 {
+        return Err("window must be at least 1 second".to_string());
+    }
+    if spec.min_interval == 0 || spec.min_interval > spec.max_interval {
+        return Err("Intervals need 1 <= min_interval <= max_interval".to_string());
+    }
+    if spec.nodes.is_empty() {
+        return Err("List the nodes chaos may use in nodes".to_string());
+    }
+    for node in &spec.nodes {
+        clusters::engine_url(spec.cluster.as_deref(), node, "").map_err(|e| e.to_string())?;
+    }
+    if spec.tests.is_empty() {
+        return Err("List at least one test type in tests".to_string());
+    }
+    for (test_type, params) in &spec.tests {
+        for (name, value) in params {
+            if let Some(bounds) = value.as_array() {
+                let numbers: Vec<f64> = bounds.iter().filter_map(Value::as_f64).collect();
+                if numbers.len() != 2 || bounds.len() != 2 || numbers[0] > numbers[1] {
+                    return Err(format!("{}.{}: bounds must be [min, max]", test_type, name));
+                }
+            }
+        }
+        match params.get("duration") {
+            Some(Value::Array(bounds)) if bounds[0].as_f64() < Some(1.0) => {
+                return Err(format!("{}.duration: tests need at least 1 second", test_type))
+            }
+            Some(Value::Array(_)) | None => {}
+            Some(duration) if duration.as_u64().is_some_and(|secs| secs > 0) => {}
+            Some(_) => return Err(format!("{}.duration: tests need a duration of at least 1 second", test_type)),
+        }
+    }
+    Ok(())
+}
+
+// Launch tests until the window closes or the run is stopped
+async fn launch_loop(id: String, spec: ChaosSpec, seed: u64, client: HttpClient, headers: HeaderMap, stop: Arc<AtomicBool>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let end = Instant::now() + Duration::from_secs(spec.window);
+    let types: Vec<&String> = spec.tests.keys().collect();
+    let mut started: Vec<(String, String)> = Vec::new(); // (node, task id)
+
+    let mut next = Instant::now();
+    loop {
+        // Sleep in short steps, so a stop is noticed quickly
+        while Instant::now() < next.min(end) && !stop.load(Ordering::SeqCst) {
+            tokio::time::sleep(next.min(end).saturating_duration_since(Instant::now()).min(Duration::from_secs(1))).await;
+        }
+        if stop.load(Ordering::SeqCst) || Instant::now() >= end {
+            break;
+        }
+
+        let test_type = types[rng.random_range(0..types.len())].clone();
+        let node = spec.nodes[rng.random_range(0..spec.nodes.len())].clone();
+        let params = draw(&spec.tests[&test_type], &mut rng);
+        let mut body = params.clone();
+        body.insert("node".to_string(), Value::String(node.clone()));
+        let outcome = scenarios::start_test(spec.cluster.as_deref(), &node, &test_type, &body, &client, &headers).await;
+        match &outcome {
+            Ok(task_id) => {
+                tracing::info!(task_id = %task_id, node = %node, test_type = %test_type, params = %serde_json::Value::Object(params.clone()), "Chaos test started");
+                started.push((node.clone(), task_id.clone()));
+            }
+            Err(e) => tracing::warn!(node = %node, test_type = %test_type, error = %e, "Chaos test failed to start"),
+        }
+        let (task_id, error) = match outcome {
+            Ok(task_id) => (Some(task_id), None),
+            Err(e) => (None, Some(e)),
+        };
+        update(&id, |run| run.launches.push(Launch { at: now_secs(), test_type, node, params, task_id, error }));
+
+        next = Instant::now() + Duration::from_secs(rng.random_range(spec.min_interval..=spec.max_interval));
+    }
+
+    let stopped = stop.load(Ordering::SeqCst);
+    if stopped {
+        for (node, task_id) in &started {
+            if let Ok(url) = clusters::engine_url(spec.cluster.as_deref(), node, &format!("/stop/{}", task_id)) {
+                let _ = client.post(url).headers(headers.clone()).send().await;
+            }
+        }
+    }
+    update(&id, |run| {
+        run.status = if stopped { Status::Stopped } else { Status::Completed };
+        run.finished_at = Some(now_secs());
+    });
+    tracing::info!(launches = started.len(), "Chaos run finished");
+}
+
+// Parameters of one launch: bounds replaced by a value in them
+fn draw(params: &Map<String, Value>, rng: &mut StdRng) -> Map<String, Value> {
+    let mut drawn = Map::new();
+    if !params.contains_key("duration") {
+        drawn.insert("duration".to_string(), Value::from(rng.random_range(DEFAULT_DURATION[0]..=DEFAULT_DURATION[1])));
+    }
+    for (name, value) in params {
+        let value = match value.as_array().map(|bounds| (&bounds[0], &bounds[1])) {
+            Some((Value::Number(low), Value::Number(high))) => match (low.as_u64(), high.as_u64()) {
+                (Some(low), Some(high)) => Value::from(rng.random_range(low..=high)),
+                _ => {
+                    let (low, high) = (low.as_f64().unwrap_or(0.0), high.as_f64().unwrap_or(0.0));
+                    Number::from_f64(rng.random_range(low..=high)).map_or(Value::Null, Value::Number)
+                }
+            },
+            _ => value.clone(),
+        };
+        drawn.insert(name.clone(), value);
+    }
+    drawn
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
@@ -0,0 +1,162 @@
+// Named clusters the controller can orchestrate besides its own
+//
+// The clusters are listed in the JSON file at $MOGWAI_CLUSTERS_FILE (default:
+// /etc/mogwai/clusters.json), e.g.
+//   [{"name": "perf", "kubeconfig": "/etc/mogwai/kube/perf.yaml", "context": "perf-admin",
+//     "engine_url": "http://mogwai-engine-{node}.perf.example.com:8080"}]
+// Requests without a `cluster` go to the cluster the controller runs in, as before.
+//
+// A cluster may instead be a static host list, for engines run by systemd or by hand on a
+// few servers: "hosts" maps node names to engine URLs, e.g.
+//   [{"name": "lab", "hosts": {"bench1": "http://10.0.0.11:8080", "bench2": "http://10.0.0.12:8080"}}]
+// and $MOGWAI_HOSTS_FILE holds such a map (the JSON object alone) for the controller's own
+// cluster. No Kubernetes API is used for these: /nodes, /engines and /stop-all read the list,
+// and spawning or removing engines is refused.
+// Engines in agent mode (see agents.rs) may name a cluster that is not listed here.
+// Engines discovered over mDNS (see discovery.rs) are reached at their advertised address.
+
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::{Client as KubeClient, Config};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::{agents, discovery};
+
+// How engines are reached when a cluster sets no engine_url: the headless service in-cluster
+const LOCAL_ENGINE_URL: &str = "http://mogwai-engine-{node}.default.svc.cluster.local:8080";
+
+// One entry of the clusters file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Cluster {
+    pub name: String,
+    #[serde(default)]
+    pub kubeconfig: Option<String>, // Kubeconfig file, default $KUBECONFIG or ~/.kube/config
+    #[serde(default)]
+    pub context: Option<String>,    // Context in the kubeconfig, default its current context
+    #[serde(default)]
+    pub engine_url: Option<String>, // Engine base URL with {node}, default the in-cluster service DNS
+    #[serde(default)]
+    pub hosts: Option<BTreeMap<String, String>>, // Node name -> engine URL, instead of Kubernetes
+}
+
+// Why a cluster cannot be used
+#[derive(Debug)]
+pub enum ClusterError {
+    Unknown(String),     // No cluster of that name is configured
+    Client(String),      // Its kubeconfig is unreadable or invalid
+    Static(String),      // It is a static host list, which has no Kubernetes API
+    UnknownNode(String), // The node is not in the cluster's host list
+}
+
+impl fmt::Display for ClusterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClusterError::Unknown(name) => write!(f, "Unknown cluster '{}'", name),
+            ClusterError::Client(message) => write!(f, "{}", message),
+            ClusterError::Static(name) => write!(f, "Cluster '{}' is a static host list; its engines are not managed by the controller", name),
+            ClusterError::UnknownNode(node) => write!(f, "Node '{}' is not in the host list", node),
+        }
+    }
+}
+
+static CLUSTERS: OnceLock<Vec<Cluster>> = OnceLock::new();
+static LOCAL_HOSTS: OnceLock<Option<BTreeMap<String, String>>> = OnceLock::new();
+
+// Read the clusters file once at startup; a missing file means only the local cluster
+pub fn load() -> Result<(), String> {
+    let path = std::env::var("MOGWAI_CLUSTERS_FILE").unwrap_or_else(|_| "/etc/mogwai/clusters.json".to_string());
+    let clusters: Vec<Cluster> = match std::fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid clusters file {}: {}", path, e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Failed to read clusters file {}: {}", path, e)),
+    };
+    for cluster in &clusters {
+        match &cluster.hosts {
+            Some(hosts) => tracing::info!(cluster = %cluster.name, hosts = hosts.len(), "Cluster configured with a static host list"),
+            None => tracing::info!(cluster = %cluster.name, "Cluster configured"),
+        }
+    }
+    let _ = CLUSTERS.set(clusters);
+
+    let local_hosts = match std::env::var("MOGWAI_HOSTS_FILE").ok().filter(|path| !path.is_empty()) {
+        Some(path) => {
+            let data = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read hosts file {}: {}", path, e))?;
+            let hosts: BTreeMap<String, String> =
+                serde_json::from_str(&data).map_err(|e| format!("Invalid hosts file {}: {}", path, e))?;
+            tracing::info!(hosts = hosts.len(), "Using a static host list instead of Kubernetes");
+            Some(hosts)
+        }
+        None => None,
+    };
+    let _ = LOCAL_HOSTS.set(local_hosts);
+    Ok(())
+}
+
+// The configured clusters
+pub fn all() -> &'static [Cluster] {
+    CLUSTERS.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+// Look up a cluster by name; None stands for the local cluster
+fn find(name: Option<&str>) -> Result<Option<&'static Cluster>, ClusterError> {
+    match name {
+        None => Ok(None),
+        Some(name) => all()
+            .iter()
+            .find(|cluster| cluster.name == name)
+            .map(Some)
+            .ok_or_else(|| ClusterError::Unknown(name.to_string())),
+    }
+}
+
+// The host list of a static cluster (None = the cluster is managed through Kubernetes)
+pub fn hosts(name: Option<&str>) -> Result<Option<&'static BTreeMap<String, String>>, ClusterError> {
+    Ok(match find(name)? {
+        Some(cluster) => cluster.hosts.as_ref(),
+        None => LOCAL_HOSTS.get().and_then(Option::as_ref),
+    })
+}
+
+// Kubernetes client for a cluster (None = the one the controller runs in)
+pub async fn kube_client(name: Option<&str>) -> Result<KubeClient, ClusterError> {
+    if hosts(name)?.is_some() {
+        return Err(ClusterError::Static(name.unwrap_or("local").to_string()));
+    }
+    let Some(cluster) = find(name)? else {
+        return KubeClient::try_default()
+            .await
+            .map_err(|e| ClusterError::Client(format!("Failed to create client: {}", e)));
+    };
+    let kubeconfig = match &cluster.kubeconfig {
+        Some(path) => Kubeconfig::read_from(path),
+        None => Kubeconfig::read(),
+    }
+    .map_err(|e| ClusterError::Client(format!("Failed to read kubeconfig of cluster {}: {}", cluster.name, e)))?;
+    let options = KubeConfigOptions {
+        context: cluster.context.clone(),
+        ..Default::default()
+    };
+    let config = Config::from_custom_kubeconfig(kubeconfig, &options)
+        .await
+        .map_err(|e| ClusterError::Client(format!("Invalid kubeconfig for cluster {}: {}", cluster.name, e)))?;
+    KubeClient::try_from(config)
+        .map_err(|e| ClusterError::Client(format!("Failed to create client for cluster {}: {}", cluster.name, e)))
+}
+
+// URL of an engine endpoint, e.g. engine_url(None, "node-1", "/metrics")
+// Nodes connected as agents are reached through the agent relay, discovered ones directly
+pub fn engine_url(name: Option<&str>, node: &str, path: &str) -> Result<String, ClusterError> {
+    if let Some(base) = agents::engine_base(name, node).or_else(|| discovery::engine_base(name, node)) {
+        return Ok(format!("{}{}", base, path));
+    }
+    if let Some(hosts) = hosts(name)? {
+        let base = hosts.get(node).ok_or_else(|| ClusterError::UnknownNode(node.to_string()))?;
+        return Ok(format!("{}{}", base.trim_end_matches('/'), path));
+    }
+    let base = find(name)?
+        .and_then(|cluster| cluster.engine_url.as_deref())
+        .unwrap_or(LOCAL_ENGINE_URL);
+    Ok(format!("{}{}", base.trim_end_matches('/').replace("{node}", node), path))
+}
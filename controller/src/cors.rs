@@ -0,0 +1,107 @@
+// Cross-origin policy of the controller API
+//
+// By default no other origin may call the API from a browser; the GUI, the CLI and
+// scripts are not browsers and are not affected. Dashboards served from elsewhere are
+// let in through the environment:
+//   $MOGWAI_CORS_ORIGINS   allowed origins separated by commas (https://dash.example.com), or * for any
+//   $MOGWAI_CORS_METHODS   allowed methods (default GET, POST, DELETE), or *
+//   $MOGWAI_CORS_HEADERS   allowed request headers (default Content-Type, Authorization, X-Mogwai-Client, X-Request-Id), or *
+//   $MOGWAI_CORS_MAX_AGE   seconds browsers may cache a preflight answer (default 3600)
+// Entries that are not valid origins, methods or header names are left out with a warning.
+
+use actix_cors::Cors;
+use actix_web::http::header::HeaderName;
+use actix_web::http::Method;
+use std::sync::OnceLock;
+
+const DEFAULT_METHODS: &str = "GET, POST, DELETE";
+const DEFAULT_HEADERS: &str = "Content-Type, Authorization, X-Mogwai-Client, X-Request-Id";
+const DEFAULT_MAX_AGE_SECS: usize = 3600;
+
+// A list from the environment: any value, or only these
+#[derive(Debug, Clone)]
+enum Allowed {
+    Any,
+    Only(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+struct Policy {
+    origins: Allowed,
+    methods: Allowed,
+    headers: Allowed,
+    max_age: usize,
+}
+
+static POLICY: OnceLock<Policy> = OnceLock::new();
+
+fn policy() -> &'static Policy {
+    POLICY.get_or_init(|| Policy {
+        origins: allowed("MOGWAI_CORS_ORIGINS", "", valid_origin),
+        methods: allowed("MOGWAI_CORS_METHODS", DEFAULT_METHODS, |method| Method::from_bytes(method.as_bytes()).is_ok()),
+        headers: allowed("MOGWAI_CORS_HEADERS", DEFAULT_HEADERS, |header| HeaderName::from_bytes(header.as_bytes()).is_ok()),
+        max_age: std::env::var("MOGWAI_CORS_MAX_AGE")
+            .ok()
+            .and_then(|secs| secs.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_SECS),
+    })
+}
+
+fn allowed(var: &str, default: &str, valid: fn(&str) -> bool) -> Allowed {
+    let value = std::env::var(var).unwrap_or_else(|_| default.to_string());
+    if value.trim() == "*" {
+        return Allowed::Any;
+    }
+    let entries = value
+        .split(',')
+        .map(|entry| entry.trim().trim_end_matches('/'))
+        .filter(|entry| !entry.is_empty())
+        .filter(|entry| {
+            let ok = valid(entry);
+            if !ok {
+                tracing::warn!(variable = var, entry, "Ignoring an invalid entry");
+            }
+            ok
+        })
+        .map(str::to_string)
+        .collect();
+    Allowed::Only(entries)
+}
+
+// Origins are a scheme and a host, with an optional port and nothing after
+fn valid_origin(origin: &str) -> bool {
+    let Some((scheme, host)) = origin.split_once("://") else {
+        return false;
+    };
+    matches!(scheme, "http" | "https") && !host.is_empty() && !host.contains(['/', '?', '#', ' '])
+}
+
+// The middleware for the configured policy; build one per worker
+pub fn middleware() -> Cors {
+    let policy = policy();
+    // Let pages read the id to quote when reporting a failure
+    let mut cors = Cors::default().max_age(policy.max_age).expose_headers([crate::request_id::HEADER]);
+    cors = match &policy.origins {
+        Allowed::Any => cors.allow_any_origin(),
+        Allowed::Only(origins) => origins.iter().fold(cors, |cors, origin| cors.allowed_origin(origin)),
+    };
+    cors = match &policy.methods {
+        Allowed::Any => cors.allow_any_method(),
+        Allowed::Only(methods) => {
+            cors.allowed_methods(methods.iter().filter_map(|method| Method::from_bytes(method.to_ascii_uppercase().as_bytes()).ok()))
+        }
+    };
+    match &policy.headers {
+        Allowed::Any => cors.allow_any_header(),
+        Allowed::Only(headers) => cors.allowed_headers(headers.iter().map(String::as_str)),
+    }
+}
+
+// One line for the startup log
+pub fn describe() -> String {
+    match &policy().origins {
+        Allowed::Any => "Cross-origin requests allowed from any origin".to_string(),
+        Allowed::Only(origins) if origins.is_empty() => "Cross-origin requests disabled (set MOGWAI_CORS_ORIGINS to allow some)".to_string(),
+        Allowed::Only(origins) => format!("Cross-origin requests allowed from {}", origins.join(", ")),
+    }
+}
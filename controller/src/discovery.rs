@@ -0,0 +1,116 @@
+// Engines found on the local network over mDNS, for lab setups without Kubernetes
+//
+// With $MOGWAI_MDNS=1 the controller browses for the _mogwai._tcp service that engines
+// started with MOGWAI_MDNS=1 advertise, and keeps the address of each one it resolves.
+// Discovered engines belong to the controller's own cluster: /nodes lists them, and
+// engine_url reaches them at their advertised address unless they are connected as
+// agents. An engine that withdraws its advertisement (it does on shutdown) is forgotten.
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// DNS-SD service type engines are advertised under
+const SERVICE_TYPE: &str = "_mogwai._tcp.local.";
+
+// An engine found on the network, as listed by GET /discovered
+#[derive(Debug, Clone, Serialize)]
+pub struct Discovered {
+    pub node: String,
+    pub url: String,             // Engine base URL, e.g. http://192.168.1.20:8080
+    pub version: Option<String>,
+    #[serde(skip)]
+    fullname: String,            // DNS-SD instance name, which removals refer to
+    pub seen_at: f64,
+}
+
+static DISCOVERED: Mutex<Vec<Discovered>> = Mutex::new(Vec::new());
+
+// Browse in the background if $MOGWAI_MDNS asks for it
+pub fn start() {
+    if !std::env::var("MOGWAI_MDNS").is_ok_and(|v| v == "1" || v == "true") {
+        return;
+    }
+    let browse = ServiceDaemon::new().and_then(|daemon| daemon.browse(SERVICE_TYPE).map(|events| (daemon, events)));
+    let (daemon, events) = match browse {
+        Ok(browse) => browse,
+        Err(e) => {
+            tracing::warn!(error = %e, "Cannot browse for engines over mDNS");
+            return;
+        }
+    };
+    tracing::info!(service = SERVICE_TYPE, "Discovering engines over mDNS");
+    std::thread::spawn(move || {
+        let _daemon = daemon; // Browsing lasts as long as the daemon
+        while let Ok(event) = events.recv() {
+            match event {
+                ServiceEvent::ServiceResolved(info) => resolved(&info),
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    let mut engines = DISCOVERED.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(index) = engines.iter().position(|engine| engine.fullname == fullname) {
+                        let engine = engines.remove(index);
+                        tracing::info!(node = %engine.node, "Discovered engine went away");
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+fn resolved(info: &ServiceInfo) {
+    let Some(url) = base_url(info) else {
+        return;
+    };
+    let node = info
+        .get_property_val_str("node")
+        .map(str::to_string)
+        .unwrap_or_else(|| info.get_fullname().trim_end_matches(SERVICE_TYPE).trim_end_matches('.').to_string());
+    let engine = Discovered {
+        node,
+        url,
+        version: info.get_property_val_str("version").map(str::to_string),
+        fullname: info.get_fullname().to_string(),
+        seen_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+    };
+    let mut engines = DISCOVERED.lock().unwrap_or_else(|e| e.into_inner());
+    match engines.iter_mut().find(|known| known.fullname == engine.fullname) {
+        Some(known) => *known = engine,
+        None => {
+            tracing::info!(node = %engine.node, url = %engine.url, "Discovered engine");
+            engines.push(engine);
+        }
+    }
+}
+
+// One address per engine: IPv4 before IPv6, loopback only when there is nothing else;
+// link-local IPv6 (fe80::) is skipped, since the resolved addresses carry no scope id
+fn base_url(info: &ServiceInfo) -> Option<String> {
+    let mut addresses: Vec<&IpAddr> = info
+        .get_addresses()
+        .iter()
+        .filter(|ip| !matches!(ip, IpAddr::V6(ip) if ip.is_unicast_link_local()))
+        .collect();
+    addresses.sort_by_key(|ip| (ip.is_loopback(), ip.is_ipv6(), **ip));
+    let address = match addresses.first()? {
+        IpAddr::V6(ip) => format!("[{}]", ip),
+        IpAddr::V4(ip) => ip.to_string(),
+    };
+    Some(format!("http://{}:{}", address, info.get_port()))
+}
+
+// The engines found so far
+pub fn list() -> Vec<Discovered> {
+    DISCOVERED.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// Base URL of a discovered node's engine; only the local cluster is discovered
+pub fn engine_base(cluster: Option<&str>, node: &str) -> Option<String> {
+    if cluster.is_some() {
+        return None;
+    }
+    let engines = DISCOVERED.lock().unwrap_or_else(|e| e.into_inner());
+    engines.iter().find(|engine| engine.node == node).map(|engine| engine.url.clone())
+}
@@ -0,0 +1,370 @@
+// Structured logs of the controller, written through `tracing`
+//
+// Every log line is an event with fields, plus the fields of the spans it happened in
+// (a request carries request_id). Written to stdout as configured by the environment:
+//   $MOGWAI_LOG_LEVEL    error, warn, info (default), debug or trace
+//   $MOGWAI_LOG_FORMAT   text (default, one key=value line per event) or json (one object
+//                        per line, for Loki, ELK and the like)
+//
+// When traces are exported (see otlp), spans also keep what is logged in them as span
+// events. Span fields named otel.<name> are exported as attribute <name> but left out of
+// log lines, and a traceparent field continues the caller's trace.
+
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use crate::otlp::{self, SpanEvent, SpanRecord};
+use rand::Rng;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_core::span::Current;
+use tracing::{Event, Level, Metadata, Subscriber};
+
+// How events are written
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Span {
+    metadata: &'static Metadata<'static>,
+    parent: Option<u64>,
+    fields: Map<String, Value>,
+    handles: usize,  // Open handles; the span ends when the last one closes
+    children: usize, // Spans still needing its fields
+    trace: Trace,
+}
+
+// What a span needs for export
+struct Trace {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    started: SystemTime,
+    attributes: Map<String, Value>, // The otel. fields
+    events: Vec<SpanEvent>,
+    dropped_events: usize,
+    error: Option<String>,
+}
+
+struct Logger {
+    level: Level,
+    format: Format,
+    export: bool,
+    spans: Mutex<HashMap<u64, Span>>,
+    next_id: AtomicU64,
+}
+
+thread_local! {
+    // Spans entered on this thread, innermost last
+    static STACK: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+// Install the logger, configured by $MOGWAI_LOG_LEVEL and $MOGWAI_LOG_FORMAT
+pub fn init() {
+    let level = std::env::var("MOGWAI_LOG_LEVEL").ok();
+    let format = match std::env::var("MOGWAI_LOG_FORMAT").unwrap_or_default().trim().to_lowercase().as_str() {
+        "json" => Format::Json,
+        _ => Format::Text,
+    };
+    let logger = Logger {
+        level: level.as_deref().and_then(parse_level).unwrap_or(Level::INFO),
+        format,
+        export: otlp::enabled(),
+        spans: Mutex::new(HashMap::new()),
+        next_id: AtomicU64::new(1),
+    };
+    let _ = tracing::subscriber::set_global_default(logger);
+    if let Some(level) = level.filter(|level| parse_level(level).is_none()) {
+        tracing::warn!(value = %level, "MOGWAI_LOG_LEVEL is not a log level; using info");
+    }
+}
+
+// A W3C traceparent header continuing the trace of the current span, when traces are exported
+pub fn traceparent() -> Option<String> {
+    tracing::dispatcher::get_default(|dispatch| {
+        let logger = dispatch.downcast_ref::<Logger>().filter(|logger| logger.export)?;
+        let id = logger.current()?;
+        let spans = logger.spans.lock().unwrap_or_else(|e| e.into_inner());
+        spans.get(&id).map(|span| otlp::traceparent(&span.trace.trace_id, &span.trace.span_id))
+    })
+}
+
+fn parse_level(level: &str) -> Option<Level> {
+    level.trim().parse().ok()
+}
+
+// Collects the fields of a span or event, keeping `message` apart
+#[derive(Default)]
+struct Fields {
+    message: Option<String>,
+    fields: Map<String, Value>,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record(field, Value::from(format!("{:?}", value)));
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, Value::from(value));
+    }
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, Value::from(value));
+    }
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record(field, Value::from(value));
+    }
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, Value::from(value));
+    }
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, Value::from(value));
+    }
+}
+
+impl Fields {
+    fn record(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            self.message = Some(value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()));
+        } else {
+            self.fields.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Logger {
+    fn current(&self) -> Option<u64> {
+        STACK.with(|stack| stack.borrow().last().copied())
+    }
+
+    // Fields of a span and its parents, outermost first
+    fn span_fields(&self, mut id: Option<u64>) -> Map<String, Value> {
+        let spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        let mut chain = Vec::new();
+        while let Some(span) = id.and_then(|id| spans.get(&id)) {
+            chain.push(&span.fields);
+            id = span.parent;
+        }
+        let mut fields = Map::new();
+        for span_fields in chain.into_iter().rev() {
+            fields.extend(span_fields.iter().map(|(name, value)| (name.clone(), value.clone())));
+        }
+        fields
+    }
+
+    // Keep an event on the span it happened in
+    fn add_event(&self, id: u64, level: Level, message: &str, fields: Map<String, Value>) {
+        let mut spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(span) = spans.get_mut(&id) else {
+            return;
+        };
+        if level <= Level::WARN && span.trace.error.is_none() {
+            span.trace.error = Some(message.to_string());
+        }
+        if span.trace.events.len() >= otlp::MAX_EVENTS {
+            span.trace.dropped_events += 1;
+            return;
+        }
+        span.trace.events.push(SpanEvent { time: SystemTime::now(), name: message.to_string(), attributes: fields });
+    }
+
+    // Export a span that ended
+    fn finish(&self, span: &mut Span) {
+        if !self.export {
+            return;
+        }
+        let mut attributes = span.fields.clone();
+        attributes.extend(std::mem::take(&mut span.trace.attributes));
+        otlp::export(SpanRecord {
+            name: span.metadata.name(),
+            trace_id: span.trace.trace_id,
+            span_id: span.trace.span_id,
+            parent_span_id: span.trace.parent_span_id,
+            start: span.trace.started,
+            end: SystemTime::now(),
+            attributes,
+            events: std::mem::take(&mut span.trace.events),
+            dropped_events: span.trace.dropped_events,
+            error: span.trace.error.take(),
+        });
+    }
+
+    fn write(&self, line: &str) {
+        let _ = writeln!(std::io::stdout().lock(), "{}", line);
+    }
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.level
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        Some(tracing::level_filters::LevelFilter::from_level(self.level))
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let parent = if attrs.is_contextual() {
+            self.current()
+        } else {
+            attrs.parent().map(Id::into_u64)
+        };
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        let (mut fields, attributes) = split_fields(fields.fields);
+        let remote = fields.remove("traceparent").and_then(|value| value.as_str().and_then(otlp::parse_traceparent));
+
+        let mut spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        // A span keeps its parent's fields alive: jobs outlive the request that started them
+        let local = parent.and_then(|parent| spans.get_mut(&parent)).map(|parent| {
+            parent.children += 1;
+            (parent.trace.trace_id, parent.trace.span_id)
+        });
+        let (trace_id, parent_span_id) = match remote.or(local) {
+            Some((trace_id, parent_span_id)) => (trace_id, Some(parent_span_id)),
+            None => (rand::rng().random(), None),
+        };
+        let trace = Trace {
+            trace_id,
+            span_id: rand::rng().random(),
+            parent_span_id,
+            started: SystemTime::now(),
+            attributes,
+            events: Vec::new(),
+            dropped_events: 0,
+            error: None,
+        };
+        spans.insert(id, Span { metadata: attrs.metadata(), parent, fields, handles: 1, children: 0, trace });
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        let (fields, attributes) = split_fields(fields.fields);
+        if let Some(span) = self.spans.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&span.into_u64()) {
+            span.fields.extend(fields);
+            span.trace.attributes.extend(attributes);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let parent = if event.is_contextual() {
+            self.current()
+        } else {
+            event.parent().map(Id::into_u64)
+        };
+        let mut own = Fields::default();
+        event.record(&mut own);
+        let message = own.message.unwrap_or_default();
+        let metadata = event.metadata();
+        let mut fields = Fields { message: None, fields: self.span_fields(parent) };
+        fields.fields.extend(own.fields.clone());
+        if let Some(parent) = parent.filter(|_| self.export) {
+            self.add_event(parent, *metadata.level(), &message, own.fields);
+        }
+
+        let line = match self.format {
+            Format::Json => {
+                let mut line = Map::new();
+                line.insert("timestamp".to_string(), Value::from(timestamp()));
+                line.insert("level".to_string(), Value::from(metadata.level().as_str()));
+                line.insert("target".to_string(), Value::from(metadata.target()));
+                line.insert("message".to_string(), Value::from(message));
+                line.extend(fields.fields);
+                Value::Object(line).to_string()
+            }
+            Format::Text => {
+                let mut line = format!("{} {:>5} {}", timestamp(), metadata.level().as_str(), message);
+                for (name, value) in &fields.fields {
+                    match value.as_str() {
+                        Some(text) if !text.is_empty() && !text.contains([' ', '"', '=']) => line.push_str(&format!(" {}={}", name, text)),
+                        _ => line.push_str(&format!(" {}={}", name, value)),
+                    }
+                }
+                line
+            }
+        };
+        self.write(&line);
+    }
+
+    fn enter(&self, span: &Id) {
+        STACK.with(|stack| stack.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(position) = stack.iter().rposition(|&id| id == span.into_u64()) {
+                stack.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) = self.spans.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&span.into_u64()) {
+            span.handles += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = spans.get_mut(&span.into_u64()) else {
+            return false;
+        };
+        entry.handles -= 1;
+        if entry.handles > 0 {
+            return false;
+        }
+        self.finish(entry);
+        // Forget it once no child needs its fields, then parents that were only kept for it
+        let mut next = Some(span.into_u64());
+        while let Some(id) = next.take() {
+            match spans.get(&id) {
+                Some(entry) if entry.handles == 0 && entry.children == 0 => {
+                    next = spans.remove(&id).and_then(|entry| entry.parent);
+                    if let Some(parent) = next.and_then(|parent| spans.get_mut(&parent)) {
+                        parent.children -= 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+        true
+    }
+
+    fn current_span(&self) -> Current {
+        let spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        match self.current().and_then(|id| spans.get(&id).map(|span| (id, span.metadata))) {
+            Some((id, metadata)) => Current::new(Id::from_u64(id), metadata),
+            None => Current::none(),
+        }
+    }
+}
+
+// Log fields, and the otel. fields meant for export only
+fn split_fields(fields: Map<String, Value>) -> (Map<String, Value>, Map<String, Value>) {
+    let mut logged = Map::new();
+    let mut exported = Map::new();
+    for (name, value) in fields {
+        match name.strip_prefix("otel.") {
+            Some(attribute) => exported.insert(attribute.to_string(), value),
+            None => logged.insert(name, value),
+        };
+    }
+    (logged, exported)
+}
+
+fn timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
@@ -1,15 +1,37 @@
 // Import necessary crates
-use actix_cors::Cors;
-use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{delete, get, post, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use serde::{Deserialize, Serialize};
 use reqwest::Client as HttpClient;
 
 use std::collections::BTreeMap;
 use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
 use kube::{Client as KubeClient, api::{Api, PostParams, ObjectMeta, ListParams, DeleteParams}};
-use k8s_openapi::api::core::v1::{Node, Pod, PodSpec, Container, LocalObjectReference, Service, ServiceSpec, ServicePort};
+use k8s_openapi::api::core::v1::{Node, Pod, PodSpec, Container, EnvVar, LocalObjectReference, ResourceRequirements, Service, ServiceSpec, ServicePort, Toleration};
+use k8s_openapi::api::core::v1::{PersistentVolumeClaim, PersistentVolumeClaimSpec, PersistentVolumeClaimVolumeSource, Volume, VolumeMount, VolumeResourceRequirements};
+use k8s_openapi::api::core::v1::{Affinity, NodeAffinity, NodeSelector, NodeSelectorRequirement, NodeSelectorTerm, PodAffinityTerm, PodAntiAffinity, TopologySpreadConstraint};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use futures::future::join_all;
 
+mod agents;
+mod audit;
+mod auth;
+mod baselines;
+mod campaigns;
+mod chaos;
+mod clusters;
+mod cors;
+mod discovery;
+mod logging;
+mod node_defaults;
+mod otlp;
+mod overview;
+mod pool;
+mod request_id;
+mod retention;
+mod scenarios;
+mod stats;
+
 // Struct used to receive and pass stress test parameters
 #[derive(Debug, Deserialize, Serialize)]
 struct TestParams {
@@ -18,7 +40,13 @@ struct TestParams {
     load: Option<f32>,      // Load percentage for CPU stress, default: 100.0
     size: Option<u32>,      // Size in MB (for memory/disk stress), default: 256
     fork: Option<bool>,     // Whether to fork processes (for fork stress), default: false
-    node: String            // Target node name for the test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<u8>,      // Calibrated intensity level (1-10) for the fields left out
+    node: String,           // Target node name for the test
+    #[serde(default, skip_serializing)]
+    cluster: Option<String>, // Named cluster of the node (see clusters.rs), default the controller's own
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>, // Test options passed through (lock, direct, ...)
 }
 
 // Provide default values for TestParams fields
@@ -30,29 +58,181 @@ impl Default for TestParams {
             load: Some(100.0),
             size: Some(256),
             fork: Some(false),
+            level: None,
             node: "UNSET".to_string(),
+            cluster: None,
+            extra: serde_json::Map::new(),
         }
     }
 }
 
+// The body sent to the engine: the request with the node's defaults and caps applied
+// (see node_defaults.rs)
+fn engine_body(params: &TestParams, test_type: &str) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let mut body = match serde_json::to_value(params) {
+        Ok(serde_json::Value::Object(body)) => body,
+        _ => serde_json::Map::new(),
+    };
+    node_defaults::apply(params.cluster.as_deref(), &params.node, test_type, &mut body)?;
+    Ok(body)
+}
+
 // Struct to serialize node info in response
 #[derive(Serialize)]
 struct NodeInfo {
     name: String
 }
 
+// Struct to serialize engine pod info in response
+// Engines of a static host list have a URL instead of a pod
+#[derive(Serialize)]
+struct EngineInfo {
+    node: String,  // Node the pod is scheduled on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pod: Option<String>, // Pod name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>, // Engine URL from the host list
+    phase: String, // Pod phase (Pending, Running, Failed, ...), or Running/Unreachable for hosts
+    ready: bool,   // Whether the pod's Ready condition is True, or the engine answered
+}
+
 // Struct used for requests that include a node name
 #[derive(Debug, Deserialize)]
 struct NodeRequest {
     node_name: String,
+    #[serde(default)]
+    cluster: Option<String>, // Named cluster of the node, default the controller's own
+    #[serde(flatten)]
+    gpu: GpuRequest, // Spawn only
+    #[serde(default)]
+    volume: Option<VolumeRequest>, // Spawn only
+}
+
+// Devices to give a spawned engine
+#[derive(Debug, Default, Deserialize)]
+struct GpuRequest {
+    #[serde(default)]
+    gpus: Option<u32>,             // Devices to request for the engine
+    #[serde(default)]
+    gpu_resource: Option<String>,  // Device plugin resource, default MOGWAI_GPU_RESOURCE or nvidia.com/gpu
+    #[serde(default)]
+    runtime_class: Option<String>, // Runtime class for GPU engines, default MOGWAI_GPU_RUNTIME_CLASS
+}
+
+// Provisioned storage for a spawned engine's disk tests
+#[derive(Debug, Deserialize)]
+struct VolumeRequest {
+    size: String,                  // Requested capacity, e.g. "20Gi"
+    #[serde(default)]
+    storage_class: Option<String>, // Storage class to provision from, default the cluster default
+    #[serde(default = "default_access_mode")]
+    access_mode: String,           // ReadWriteOnce unless the class needs another mode
+}
+
+fn default_access_mode() -> String {
+    "ReadWriteOnce".to_string()
+}
+
+// Where an engine's claim is mounted; the engine runs disk tests there (MOGWAI_DISK_DIR)
+const DISK_MOUNT_PATH: &str = "/mnt/mogwai-disk";
+
+// Claim holding an engine's disk test volume
+fn claim_name(node: &str) -> String {
+    format!("mogwai-engine-{}-data", node)
+}
+
+// Create the claim for an engine's disk test volume; an existing claim is reused
+async fn create_claim(client: &KubeClient, node: &str, volume: &VolumeRequest) -> Result<(), String> {
+    let size = Quantity(volume.size.clone());
+    let claim = PersistentVolumeClaim {
+        metadata: ObjectMeta {
+            name: Some(claim_name(node)),
+            labels: Some(BTreeMap::from([("app".to_string(), "mogwai-engine".to_string())])),
+            ..Default::default()
+        },
+        spec: Some(PersistentVolumeClaimSpec {
+            access_modes: Some(vec![volume.access_mode.clone()]),
+            storage_class_name: volume.storage_class.clone(),
+            resources: Some(VolumeResourceRequirements {
+                requests: Some(BTreeMap::from([("storage".to_string(), size)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let claims: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), "default");
+    match claims.create(&PostParams::default(), &claim).await {
+        Ok(_) => Ok(()),
+        Err(kube::Error::Api(e)) if e.code == 409 => Ok(()),
+        Err(e) => Err(format!("Volume claim creation failed: {}", e)),
+    }
+}
+
+// Device plugin resource requested for GPU engines unless MOGWAI_GPU_RESOURCE is set
+const DEFAULT_GPU_RESOURCE: &str = "nvidia.com/gpu";
+
+// Resources, runtime class and tolerations that give a GPU engine its devices
+// (no devices requested: all None)
+fn gpu_settings(payload: &GpuRequest) -> (Option<ResourceRequirements>, Option<String>, Option<Vec<Toleration>>) {
+    let Some(gpus) = payload.gpus.filter(|&gpus| gpus > 0) else {
+        return (None, None, None);
+    };
+    let resource = payload
+        .gpu_resource
+        .clone()
+        .or_else(|| std::env::var("MOGWAI_GPU_RESOURCE").ok().filter(|name| !name.is_empty()))
+        .unwrap_or_else(|| DEFAULT_GPU_RESOURCE.to_string());
+    let runtime_class = payload
+        .runtime_class
+        .clone()
+        .or_else(|| std::env::var("MOGWAI_GPU_RUNTIME_CLASS").ok().filter(|name| !name.is_empty()));
+    // Extended resources are set as limits; the request defaults to the same amount
+    let resources = ResourceRequirements {
+        limits: Some(BTreeMap::from([(resource.clone(), Quantity(gpus.to_string()))])),
+        ..Default::default()
+    };
+    // GPU nodes are commonly tainted with the resource name
+    let tolerations = vec![Toleration {
+        key: Some(resource),
+        operator: Some("Exists".to_string()),
+        effect: Some("NoSchedule".to_string()),
+        ..Default::default()
+    }];
+    (Some(resources), runtime_class, Some(tolerations))
 }
 
-// GET /nodes — List all node names in the Kubernetes cluster
+// GET /nodes — List all node names in the Kubernetes cluster (or its static host list), the
+// agents connected under it and, for the local cluster, the engines discovered over mDNS
+// A cluster of agents or discovered engines only (e.g. bare-metal hosts) needs no kubeconfig
 #[get("/nodes")]
-async fn list_nodes() -> impl Responder {
-    let client = match KubeClient::try_default().await {
+async fn list_nodes(req: HttpRequest) -> impl Responder {
+    let cluster = query_cluster(&req);
+    let discovered = discovery::list().into_iter().filter(|_| cluster.is_none()).map(|engine| engine.node);
+    let mut extra: Vec<NodeInfo> = Vec::new();
+    for name in agents::list()
+        .into_iter()
+        .filter(|agent| agent.cluster == cluster)
+        .map(|agent| agent.node)
+        .chain(discovered)
+    {
+        if !extra.iter().any(|node| node.name == name) {
+            extra.push(NodeInfo { name });
+        }
+    }
+    match clusters::hosts(cluster.as_deref()) {
+        Ok(Some(hosts)) => {
+            let mut node_names: Vec<NodeInfo> = hosts.keys().map(|name| NodeInfo { name: name.clone() }).collect();
+            node_names.extend(extra.into_iter().filter(|node| !hosts.contains_key(&node.name)));
+            return HttpResponse::Ok().json(node_names);
+        }
+        Ok(None) => {}
+        Err(e) => return cluster_error(e),
+    }
+    let client = match clusters::kube_client(cluster.as_deref()).await {
         Ok(c) => c,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to create client: {}", e)),
+        Err(_) if !extra.is_empty() => return HttpResponse::Ok().json(extra),
+        Err(e) => return cluster_error(e),
     };
 
     let nodes: Api<Node> = Api::all(client);
@@ -60,40 +240,215 @@ async fn list_nodes() -> impl Responder {
     match nodes.list(&Default::default()).await {
         Ok(node_list) => {
             // Extract node names into a Vec
-            let node_names: Vec<NodeInfo> = node_list.items.into_iter().filter_map(|n| {
+            let mut node_names: Vec<NodeInfo> = node_list.items.into_iter().filter_map(|n| {
                 n.metadata.name.clone().map(|name| NodeInfo { name })
             }).collect();
+            for node in extra {
+                if !node_names.iter().any(|known| known.name == node.name) {
+                    node_names.push(node);
+                }
+            }
 
             HttpResponse::Ok().json(node_names)
         },
+        Err(_) if !extra.is_empty() => HttpResponse::Ok().json(extra),
         Err(e) => HttpResponse::InternalServerError().body(format!("Failed to list nodes: {}", e)),
     }
 }
 
-// POST /spawn-engine — Spawn a pod and a headless service on a specific node
-#[post("/spawn-engine")]
-async fn spawn_engine(
-    payload: web::Json<NodeRequest>,
-) -> impl Responder {
-    // Initialize Kubernetes client
-    let client = match KubeClient::try_default().await {
-        Ok(c) => c,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Client error: {}", e)),
-    };
+// GET /agents — Engines connected in agent mode
+#[get("/agents")]
+async fn list_agents() -> impl Responder {
+    HttpResponse::Ok().json(agents::list())
+}
 
-    // Generate pod name from node
-    let pod_name = format!("mogwai-engine-{}", payload.node_name);
-    let label_key = "stateful-id";
+// GET /discovered — Engines found on the local network over mDNS (see discovery.rs)
+#[get("/discovered")]
+async fn list_discovered() -> impl Responder {
+    HttpResponse::Ok().json(discovery::list())
+}
 
-    let pods: Api<Pod> = Api::namespaced(client.clone(), "default");
+// GET /agents/connect — WebSocket an engine in agent mode keeps open (see agents.rs)
+#[get("/agents/connect")]
+async fn connect_agent(req: HttpRequest, body: web::Payload) -> Result<HttpResponse, actix_web::Error> {
+    agents::connect(&req, body)
+}
+
+// Controller settings handed down to every spawned engine (metrics export, result upload, safety limits,
+// client scoping)
+const FORWARDED_ENV: [&str; 26] = [
+    "MOGWAI_REMOTE_WRITE_URL",
+    "MOGWAI_REMOTE_WRITE_INTERVAL_SECS",
+    "MOGWAI_REMOTE_WRITE_TOKEN",
+    "MOGWAI_INFLUX_URL",
+    "MOGWAI_INFLUX_ORG",
+    "MOGWAI_INFLUX_BUCKET",
+    "MOGWAI_INFLUX_TOKEN",
+    "MOGWAI_INFLUX_INTERVAL_SECS",
+    "MOGWAI_STATSD_ADDR",
+    "MOGWAI_STATSD_PREFIX",
+    "MOGWAI_PUSHGATEWAY_URL",
+    "MOGWAI_PUSH_INTERVAL_SECS",
+    "MOGWAI_S3_BUCKET",
+    "MOGWAI_S3_ENDPOINT",
+    "MOGWAI_S3_REGION",
+    "MOGWAI_S3_PREFIX",
+    "MOGWAI_S3_ACCESS_KEY_ID",
+    "MOGWAI_S3_SECRET_ACCESS_KEY",
+    "MOGWAI_S3_SESSION_TOKEN",
+    "MOGWAI_MAX_MEMORY_PERCENT",
+    "MOGWAI_MAX_DISK_PERCENT",
+    "MOGWAI_ALLOW_FORK",
+    "MOGWAI_MAX_DURATION_SECS",
+    "MOGWAI_LIMIT_ACTION",
+    "MOGWAI_TRUST_CLIENT_HEADER",
+    "MOGWAI_DISK_ROOTS",
+];
+
+// Environment of an engine container: its node name plus the forwarded settings that are set
+fn engine_env(node: &str) -> Vec<EnvVar> {
+    let mut env = vec![EnvVar {
+        name: "MOGWAI_NODE_NAME".to_string(),
+        value: Some(node.to_string()),
+        ..Default::default()
+    }];
+    for name in FORWARDED_ENV {
+        if let Ok(value) = std::env::var(name) {
+            env.push(EnvVar { name: name.to_string(), value: Some(value), ..Default::default() });
+        }
+    }
+    env
+}
+
+// How spawned engines are spread over the cluster (POST /spawn-engines)
+#[derive(Debug, Deserialize)]
+struct SpreadRequest {
+    #[serde(default)]
+    cluster: Option<String>,            // Named cluster to spawn in, default the controller's own
+    #[serde(default)]
+    count: Option<usize>,               // Engines to spawn, default one on every eligible node
+    #[serde(default)]
+    nodes: Option<Vec<String>>,         // Candidate nodes, default all Ready, schedulable nodes
+    #[serde(default = "default_topology_key")]
+    topology_key: String,               // Node label whose values are spread over
+    #[serde(default = "default_max_skew")]
+    max_skew: i32,                      // Most engines one domain may have over another
+    #[serde(default = "default_when_unsatisfiable")]
+    when_unsatisfiable: String,         // DoNotSchedule or ScheduleAnyway
+    #[serde(flatten)]
+    gpu: GpuRequest,
+    #[serde(default)]
+    volume: Option<VolumeRequest>,      // A claim of this size and class for every engine
+}
+
+fn default_topology_key() -> String {
+    "topology.kubernetes.io/zone".to_string()
+}
+
+fn default_max_skew() -> i32 {
+    1
+}
+
+fn default_when_unsatisfiable() -> String {
+    "ScheduleAnyway".to_string()
+}
+
+// Where an engine pod goes
+enum Placement<'a> {
+    Pinned,                     // Bound to its node directly, bypassing the scheduler
+    Spread(&'a SpreadRequest),  // Through the scheduler, with anti-affinity and topology spread
+}
+
+// Require a node through the scheduler rather than binding to it directly
+fn node_affinity(node: &str) -> NodeAffinity {
+    NodeAffinity {
+        required_during_scheduling_ignored_during_execution: Some(NodeSelector {
+            node_selector_terms: vec![NodeSelectorTerm {
+                match_fields: Some(vec![NodeSelectorRequirement {
+                    key: "metadata.name".to_string(),
+                    operator: "In".to_string(),
+                    values: Some(vec![node.to_string()]),
+                }]),
+                ..Default::default()
+            }],
+        }),
+        ..Default::default()
+    }
+}
+
+// Engine pod for a node, with its disk test claim mounted when `with_volume` is set
+fn engine_pod(node: &str, gpu: &GpuRequest, with_volume: bool, placement: Placement) -> Pod {
+    let pod_name = format!("mogwai-engine-{}", node);
+    let (resources, runtime_class_name, tolerations) = gpu_settings(gpu);
+    let engine_selector = LabelSelector {
+        match_labels: Some(BTreeMap::from([("app".to_string(), "mogwai-engine".to_string())])),
+        ..Default::default()
+    };
+    let (node_name, affinity, topology_spread_constraints) = match placement {
+        // Volumes that bind on first use are only provisioned for scheduled pods
+        Placement::Pinned if with_volume => (
+            None,
+            Some(Affinity { node_affinity: Some(node_affinity(node)), ..Default::default() }),
+            None,
+        ),
+        Placement::Pinned => (Some(node.to_string()), None, None), // Assign pod to the requested node
+        Placement::Spread(spread) => (
+            None,
+            Some(Affinity {
+                // The chosen node, through the scheduler so the constraints below are enforced
+                node_affinity: Some(node_affinity(node)),
+                // Never two engines on one host, so their load does not add up
+                pod_anti_affinity: Some(PodAntiAffinity {
+                    required_during_scheduling_ignored_during_execution: Some(vec![PodAffinityTerm {
+                        label_selector: Some(engine_selector.clone()),
+                        topology_key: "kubernetes.io/hostname".to_string(),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            Some(vec![TopologySpreadConstraint {
+                label_selector: Some(engine_selector),
+                max_skew: spread.max_skew,
+                topology_key: spread.topology_key.clone(),
+                when_unsatisfiable: spread.when_unsatisfiable.clone(),
+                ..Default::default()
+            }]),
+        ),
+    };
+    let mut env = engine_env(node);
+    let (volumes, volume_mounts) = if with_volume {
+        env.push(EnvVar {
+            name: "MOGWAI_DISK_DIR".to_string(),
+            value: Some(DISK_MOUNT_PATH.to_string()),
+            ..Default::default()
+        });
+        (
+            Some(vec![Volume {
+                name: "disk-test".to_string(),
+                persistent_volume_claim: Some(PersistentVolumeClaimVolumeSource {
+                    claim_name: claim_name(node),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            Some(vec![VolumeMount {
+                name: "disk-test".to_string(),
+                mount_path: DISK_MOUNT_PATH.to_string(),
+                ..Default::default()
+            }]),
+        )
+    } else {
+        (None, None)
+    };
 
-    // Define pod specification
-    let pod = Pod {
+    Pod {
         metadata: ObjectMeta {
             name: Some(pod_name.clone()),
             labels: Some(BTreeMap::from([
                 ("app".to_string(), "mogwai-engine".to_string()),
-                (label_key.to_string(), pod_name.clone()),
+                (ENGINE_LABEL_KEY.to_string(), pod_name),
             ])),
             ..Default::default()
         },
@@ -106,9 +461,17 @@ async fn spawn_engine(
                     container_port: 8080,
                     ..Default::default()
                 }]),
+                env: Some(env),
+                resources,
+                volume_mounts,
                 ..Default::default()
             }],
-            node_name: Some(payload.node_name.clone()), // Assign pod to the requested node
+            node_name,
+            affinity,
+            topology_spread_constraints,
+            volumes,
+            runtime_class_name,
+            tolerations,
             restart_policy: Some("Never".into()),
             image_pull_secrets: Some(vec![LocalObjectReference {
                 name: "github-registry-secret".to_string(),
@@ -116,14 +479,20 @@ async fn spawn_engine(
             ..Default::default()
         }),
         ..Default::default()
-    };
-
-    // Create the pod in Kubernetes
-    if let Err(e) = pods.create(&PostParams::default(), &pod).await {
-        return HttpResponse::InternalServerError().body(format!("Pod creation failed: {}", e));
     }
+}
+
+// Label selecting an engine pod for its headless service
+const ENGINE_LABEL_KEY: &str = "stateful-id";
+
+// Create an engine pod and its headless service for direct DNS-based access
+async fn create_engine(client: &KubeClient, pod: Pod) -> Result<(), String> {
+    let pod_name = pod.metadata.name.clone().unwrap_or_default();
+    let pods: Api<Pod> = Api::namespaced(client.clone(), "default");
+    pods.create(&PostParams::default(), &pod)
+        .await
+        .map_err(|e| format!("Pod creation failed: {}", e))?;
 
-    // Define and create a headless service for direct DNS-based access
     let services: Api<Service> = Api::namespaced(client.clone(), "default");
     let svc = Service {
         metadata: ObjectMeta {
@@ -132,7 +501,7 @@ async fn spawn_engine(
         },
         spec: Some(ServiceSpec {
             selector: Some(BTreeMap::from([
-                (label_key.to_string(), pod_name.clone()),
+                (ENGINE_LABEL_KEY.to_string(), pod_name),
             ])),
             cluster_ip: Some("None".to_string()), // Headless service
             ports: Some(vec![ServicePort {
@@ -144,11 +513,223 @@ async fn spawn_engine(
         }),
         ..Default::default()
     };
+    services.create(&PostParams::default(), &svc)
+        .await
+        .map_err(|e| format!("Service creation failed: {}", e))?;
+    Ok(())
+}
+
+// POST /spawn-engine — Spawn a pod and a headless service on a specific node
+#[post("/spawn-engine")]
+async fn spawn_engine(
+    payload: web::Json<NodeRequest>,
+) -> impl Responder {
+    // Initialize Kubernetes client
+    let client = match clusters::kube_client(payload.cluster.as_deref()).await {
+        Ok(c) => c,
+        Err(e) => return cluster_error(e),
+    };
+
+    if let Some(volume) = &payload.volume {
+        if let Err(e) = create_claim(&client, &payload.node_name, volume).await {
+            return HttpResponse::InternalServerError().body(e);
+        }
+    }
+    let pod = engine_pod(&payload.node_name, &payload.gpu, payload.volume.is_some(), Placement::Pinned);
+    match create_engine(&client, pod).await {
+        Ok(()) => HttpResponse::Ok().body("Engine pod and headless service spawned."),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Whether a node can take an engine: Ready and not cordoned
+fn node_eligible(node: &Node) -> bool {
+    let schedulable = !node.spec.as_ref().and_then(|spec| spec.unschedulable).unwrap_or(false);
+    let ready = node
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .is_some_and(|conditions| conditions.iter().any(|c| c.type_ == "Ready" && c.status == "True"));
+    schedulable && ready
+}
 
-    // Create the service
-    match services.create(&PostParams::default(), &svc).await {
-        Ok(_) => HttpResponse::Ok().body("Engine pod and headless service spawned."),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Service creation failed: {}", e)),
+// Pick up to `count` nodes, taking one from each topology domain in turn, so no domain
+// gets more than one engine more than another
+fn spread_nodes(mut domains: BTreeMap<String, Vec<String>>, count: usize) -> Vec<(String, String)> {
+    let mut picked = Vec::new();
+    while picked.len() < count && domains.values().any(|nodes| !nodes.is_empty()) {
+        for (domain, nodes) in domains.iter_mut() {
+            if picked.len() == count {
+                break;
+            }
+            if !nodes.is_empty() {
+                picked.push((nodes.remove(0), domain.clone()));
+            }
+        }
+    }
+    picked
+}
+
+// POST /spawn-engines — Spawn engines on several nodes, spread evenly over a topology label
+#[post("/spawn-engines")]
+async fn spawn_engines(
+    payload: web::Json<SpreadRequest>,
+) -> impl Responder {
+    let client = match clusters::kube_client(payload.cluster.as_deref()).await {
+        Ok(c) => c,
+        Err(e) => return cluster_error(e),
+    };
+    if !["DoNotSchedule", "ScheduleAnyway"].contains(&payload.when_unsatisfiable.as_str()) {
+        return HttpResponse::BadRequest().body("when_unsatisfiable must be DoNotSchedule or ScheduleAnyway");
+    }
+    if payload.max_skew < 1 {
+        return HttpResponse::BadRequest().body("max_skew must be at least 1");
+    }
+
+    let node_list = match Api::<Node>::all(client.clone()).list(&ListParams::default()).await {
+        Ok(list) => list.items,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to list nodes: {}", e)),
+    };
+    // Nodes that already run an engine are skipped
+    let engine_pods = match Api::<Pod>::namespaced(client.clone(), "default")
+        .list(&ListParams::default().labels("app=mogwai-engine"))
+        .await
+    {
+        Ok(list) => list.items,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to list engine pods: {}", e)),
+    };
+    let taken: Vec<String> = engine_pods
+        .iter()
+        .filter_map(|pod| pod.metadata.name.as_deref()?.strip_prefix("mogwai-engine-").map(str::to_string))
+        .collect();
+
+    // Candidate nodes grouped by their value of the topology label (unlabelled nodes share "")
+    let mut domains: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for node in node_list.iter().filter(|node| node_eligible(node)) {
+        let Some(name) = node.metadata.name.clone() else { continue };
+        let wanted = payload.nodes.as_ref().is_none_or(|nodes| nodes.contains(&name));
+        if !wanted || taken.contains(&name) {
+            continue;
+        }
+        let domain = node
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(&payload.topology_key).cloned())
+            .unwrap_or_default();
+        domains.entry(domain).or_default().push(name);
+    }
+    let available: usize = domains.values().map(Vec::len).sum();
+    let picked = spread_nodes(domains, payload.count.unwrap_or(available));
+
+    let mut spawned = Vec::new();
+    let mut failed = Vec::new();
+    for (node, domain) in picked {
+        if let Some(volume) = &payload.volume {
+            if let Err(e) = create_claim(&client, &node, volume).await {
+                failed.push(serde_json::json!({ "node": node, "error": e }));
+                continue;
+            }
+        }
+        let pod = engine_pod(&node, &payload.gpu, payload.volume.is_some(), Placement::Spread(&payload));
+        match create_engine(&client, pod).await {
+            Ok(()) => spawned.push(serde_json::json!({ "node": node, "domain": domain })),
+            Err(e) => failed.push(serde_json::json!({ "node": node, "error": e })),
+        }
+    }
+
+    let body = serde_json::json!({
+        "requested": payload.count,
+        "available": available,
+        "spawned": spawned,
+        "failed": failed,
+    });
+    if spawned.is_empty() && !failed.is_empty() {
+        HttpResponse::InternalServerError().json(body)
+    } else {
+        HttpResponse::Ok().json(body)
+    }
+}
+
+// GET /engines — List engine pods with their node, phase and readiness
+// For a static host list, each engine is asked for its capabilities instead
+#[get("/engines")]
+async fn list_engines(req: HttpRequest, http: web::Data<HttpClient>) -> impl Responder {
+    let cluster = query_cluster(&req);
+    match clusters::hosts(cluster.as_deref()) {
+        Ok(Some(hosts)) => {
+            let probes = hosts.iter().map(|(node, url)| {
+                let probe = http
+                    .get(format!("{}/capabilities", url.trim_end_matches('/')))
+                    .headers(client_headers(&req))
+                    .timeout(std::time::Duration::from_secs(5))
+                    .send();
+                async move {
+                    let ready = probe.await.is_ok_and(|resp| resp.status().is_success());
+                    EngineInfo {
+                        node: node.clone(),
+                        pod: None,
+                        url: Some(url.clone()),
+                        phase: if ready { "Running" } else { "Unreachable" }.to_string(),
+                        ready,
+                    }
+                }
+            });
+            return HttpResponse::Ok().json(join_all(probes).await);
+        }
+        Ok(None) => {}
+        Err(e) => return cluster_error(e),
+    }
+    let client = match clusters::kube_client(cluster.as_deref()).await {
+        Ok(c) => c,
+        Err(e) => return cluster_error(e),
+    };
+
+    let pods: Api<Pod> = Api::namespaced(client, "default");
+    let lp = ListParams::default().labels("app=mogwai-engine");
+
+    match pods.list(&lp).await {
+        Ok(pod_list) => {
+            let engines: Vec<EngineInfo> = pod_list.items.into_iter().filter_map(|pod| {
+                let node = pod.spec.as_ref()?.node_name.clone()?;
+                let status = pod.status.as_ref();
+                let ready = status
+                    .and_then(|s| s.conditions.as_ref())
+                    .is_some_and(|conditions| {
+                        conditions.iter().any(|c| c.type_ == "Ready" && c.status == "True")
+                    });
+                Some(EngineInfo {
+                    node,
+                    pod: Some(pod.metadata.name.clone().unwrap_or_default()),
+                    url: None,
+                    phase: status.and_then(|s| s.phase.clone()).unwrap_or_else(|| "Unknown".to_string()),
+                    ready,
+                })
+            }).collect();
+
+            HttpResponse::Ok().json(engines)
+        },
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to list engine pods: {}", e)),
+    }
+}
+
+// GET /overview — Load of every engine in the cluster (host metrics, running tasks, generated
+// CPU and memory) with totals, polled by `mogwai top` (see overview.rs)
+#[get("/overview")]
+async fn cluster_overview(req: HttpRequest, http: web::Data<HttpClient>) -> impl Responder {
+    let cluster = query_cluster(&req);
+    match overview::collect(cluster.as_deref(), &http, client_headers(&req)).await {
+        Ok(overview) => HttpResponse::Ok().json(overview),
+        Err(e) => cluster_error(e),
+    }
+}
+
+// GET /pool — State of the warm pool of engines (see pool.rs) as of its last check
+#[get("/pool")]
+async fn pool_status() -> impl Responder {
+    match pool::status() {
+        Some(status) => HttpResponse::Ok().json(status),
+        None => HttpResponse::NotFound().body("No warm pool configured; set MOGWAI_WARM_POOL_SIZE"),
     }
 }
 
@@ -157,9 +738,9 @@ async fn spawn_engine(
 async fn remove_engine(
     payload: web::Json<NodeRequest>,
 ) -> impl Responder {
-    let client = match KubeClient::try_default().await {
+    let client = match clusters::kube_client(payload.cluster.as_deref()).await {
         Ok(c) => c,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Client error: {}", e)),
+        Err(e) => return cluster_error(e),
     };
 
     let pod_name = format!("mogwai-engine-{}", payload.node_name);
@@ -170,6 +751,9 @@ async fn remove_engine(
     // Attempt to delete the pod and service
     let pod_result = pods.delete(&pod_name, &DeleteParams::default()).await;
     let svc_result = services.delete(&pod_name, &DeleteParams::default()).await;
+    // The disk test volume goes with its engine; most engines have none
+    let claims: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), "default");
+    let claim_result = claims.delete(&claim_name(&payload.node_name), &DeleteParams::default()).await;
 
     // Prepare response messages
     let pod_msg = match pod_result {
@@ -181,23 +765,106 @@ async fn remove_engine(
         Err(e) => format!("Service deletion error: {}", e),
     };
 
-    HttpResponse::Ok().json(serde_json::json!({
+    let mut body = serde_json::json!({
         "pod": pod_msg,
         "service": svc_msg
-    }))
+    });
+    match claim_result {
+        Ok(_) => body["volume"] = format!("Volume claim {} deletion initiated.", claim_name(&payload.node_name)).into(),
+        Err(kube::Error::Api(e)) if e.code == 404 => {}
+        Err(e) => body["volume"] = format!("Volume claim deletion error: {}", e).into(),
+    }
+
+    HttpResponse::Ok().json(body)
+}
+
+// POST /{type}-stress — Send a stress request to the engine pod on a specific node
+// The engine resolves the type (cpu, mem, disk, compress, ...) and answers 404 for unknown ones
+#[post("/{test_type}-stress")]
+async fn stress(path: web::Path<String>, params: web::Json<TestParams>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let test_type = path.into_inner();
+    if !test_type.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return HttpResponse::NotFound().body(format!("Unknown test type: {}", test_type));
+    }
+    tracing::info!(node = %params.node, test_type = %test_type, intensity = ?params.intensity, duration = ?params.duration, load = ?params.load, size = ?params.size, "Starting stress test");
+
+    let url = match clusters::engine_url(params.cluster.as_deref(), &params.node, &format!("/{}-stress", test_type)) {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    let body = match engine_body(&params, &test_type) {
+        Ok(body) => body,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    match otlp::dispatch(&params.node, &url, client.post(&url).headers(client_headers(&req)).json(&body)).await {
+        Ok(resp) => relay_start(resp).await,
+        Err(e) => HttpResponse::InternalServerError().body(format!("Request failed: {}", e)),
+    }
 }
 
-// POST /cpu-stress — Send a stress request to the engine pod on a specific node
-#[post("/cpu-stress")]
-async fn cpu_stress(params: web::Json<TestParams>, client: web::Data<HttpClient>) -> impl Responder {
-    println!(
-        "Starting CPU stress test on node {} with intensity: {:?}, duration: {:?}, load: {:?}",
-        params.node, params.intensity, params.duration, params.load
-    );
+// POST /custom/{name} — Start a custom workload (engine plugin) on the node named in the body
+// The whole body is forwarded, so workload-specific fields reach the plugin
+#[post("/custom/{name}")]
+async fn custom_stress(path: web::Path<String>, body: web::Json<serde_json::Value>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let name = path.into_inner();
+    let Some(node) = body.get("node").and_then(|node| node.as_str()) else {
+        return HttpResponse::BadRequest().body("Missing \"node\" in request body");
+    };
+    let cluster = body.get("cluster").and_then(|cluster| cluster.as_str());
+    tracing::info!(node = %node, test_type = %name, "Starting custom workload");
 
-    let url = format!("http://mogwai-engine-{}.default.svc.cluster.local:8080/cpu-stress", params.node);
+    let url = match clusters::engine_url(cluster, node, &format!("/custom/{}", name)) {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
 
-    match client.post(&url).json(&*params).send().await {
+    match otlp::dispatch(node, &url, client.post(&url).headers(client_headers(&req)).json(&*body)).await {
+        Ok(resp) => relay_start(resp).await,
+        Err(e) => HttpResponse::InternalServerError().body(format!("Request failed: {}", e)),
+    }
+}
+
+// POST /estimate — Expected resource use of a battery of tests on the node named in the body,
+// and whether the node has the headroom for it now; nothing is started
+#[post("/estimate")]
+async fn estimate_battery(body: web::Json<serde_json::Value>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let Some(node) = body.get("node").and_then(|node| node.as_str()) else {
+        return HttpResponse::BadRequest().body("Missing \"node\" in request body");
+    };
+    let cluster = body.get("cluster").and_then(|cluster| cluster.as_str());
+    let url = match clusters::engine_url(cluster, node, "/estimate") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    forward_json(client.post(&url).headers(client_headers(&req)).json(&*body)).await
+}
+
+// GET /custom/{node} — Names of the custom workloads loaded by the engine pod on a node
+#[get("/custom/{node}")]
+async fn list_custom(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/custom") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    forward_json(client.get(&url).headers(client_headers(&req))).await
+}
+
+// POST /tasks/{node} — Get list of running tasks from engine pod on a node
+// The query string (e.g. ?limit=50&offset=0&sort=-started_at) is passed through to the engine
+#[post("/tasks/{node}")]
+async fn list_tasks(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let mut url = match clusters::engine_url(cluster.as_deref(), &node, "/tasks") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    url.push_str(&engine_query(&req));
+
+    match client.get(&url).headers(client_headers(&req)).send().await {
         Ok(resp) => {
             let status = resp.status();
             let body = resp.text().await.unwrap_or_default();
@@ -207,37 +874,113 @@ async fn cpu_stress(params: web::Json<TestParams>, client: web::Data<HttpClient>
     }
 }
 
-// POST /mem-stress — Trigger memory stress test
-#[post("/mem-stress")]
-async fn mem_stress(params: web::Json<TestParams>, client: web::Data<HttpClient>) -> impl Responder {
-    println!(
-        "Starting memory stress test on node {} with intensity: {:?}, duration: {:?}, size: {:?}",
-        params.node, params.intensity, params.duration, params.size
-    );
+// GET /metrics/{node} — Host metrics snapshot (CPU %, memory, disk MB/s) from the engine pod on a node
+#[get("/metrics/{node}")]
+async fn node_metrics(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/metrics") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
 
-    let url = format!("http://mogwai-engine-{}.default.svc.cluster.local:8080/mem-stress", params.node);
+    match client.get(&url).headers(client_headers(&req)).send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            HttpResponse::build(status).content_type("application/json").body(body)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Request failed: {}", e)),
+    }
+}
+
+// GET /sysinfo/{node} — Hardware report (CPU, memory, disks, network) from the engine pod on a node
+#[get("/sysinfo/{node}")]
+async fn node_sysinfo(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/sysinfo") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
 
-    match client.post(&url).json(&*params).send().await {
+    match client.get(&url).headers(client_headers(&req)).send().await {
         Ok(resp) => {
             let status = resp.status();
             let body = resp.text().await.unwrap_or_default();
-            HttpResponse::build(status).body(body)
+            HttpResponse::build(status).content_type("application/json").body(body)
         }
         Err(e) => HttpResponse::InternalServerError().body(format!("Request failed: {}", e)),
     }
 }
 
-// POST /disk-stress — Trigger disk I/O stress test
-#[post("/disk-stress")]
-async fn disk_stress(params: web::Json<TestParams>, client: web::Data<HttpClient>) -> impl Responder {
-    println!(
-        "Starting disk stress test on node {} with intensity: {:?}, duration: {:?}, size: {:?}",
-        params.node, params.intensity, params.duration, params.size
-    );
+// GET /results/{node} — Results of recently finished tasks on the engine pod on a node
+// The query string (e.g. ?state=failed&type=disk) is passed through to the engine
+#[get("/results/{node}")]
+async fn node_results(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let mut url = match clusters::engine_url(cluster.as_deref(), &node, "/results") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    url.push_str(&engine_query(&req));
+    forward_json(client.get(&url).headers(client_headers(&req))).await
+}
+
+// GET /events/{node} — Recent task events (registered, started, progress, ...) from the engine pod on a node
+// The query string (e.g. ?since=<ts>&task=<id>) is passed through to the engine
+#[get("/events/{node}")]
+async fn node_events(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let mut url = match clusters::engine_url(cluster.as_deref(), &node, "/events") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    url.push_str(&engine_query(&req));
+    forward_json(client.get(&url).headers(client_headers(&req))).await
+}
 
-    let url = format!("http://mogwai-engine-{}.default.svc.cluster.local:8080/disk-stress", params.node);
+// GET /results/{node}/{id} — Result of one finished task on the engine pod on a node
+#[get("/results/{node}/{id}")]
+async fn node_task_result(path: web::Path<(String, String)>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let (node, id) = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, &format!("/results/{}", id)) {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    forward_json(client.get(&url).headers(client_headers(&req))).await
+}
 
-    match client.post(&url).json(&*params).send().await {
+// GET /artifacts/{node}/{id} — A task's artifacts (samples, logs, histograms, kept files) as a
+// tar.gz from the engine pod on a node, streamed through as the engine writes it
+#[get("/artifacts/{node}/{id}")]
+async fn node_task_artifacts(path: web::Path<(String, String)>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let (node, id) = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, &format!("/artifacts/{}", id)) {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+
+    match client.get(&url).headers(client_headers(&req)).send().await {
+        Ok(resp) if resp.status().is_success() => {
+            let mut response = HttpResponse::Ok();
+            for name in ["content-type", "content-disposition"] {
+                if let Some(value) = resp.headers().get(name).and_then(|value| value.to_str().ok()) {
+                    response.insert_header((name, value.to_string()));
+                }
+            }
+            let body = futures::stream::unfold(resp, |mut resp| async move {
+                match resp.chunk().await {
+                    Ok(Some(chunk)) => Some((Ok::<_, actix_web::Error>(chunk), resp)),
+                    _ => None,
+                }
+            });
+            response.streaming(body)
+        }
         Ok(resp) => {
             let status = resp.status();
             let body = resp.text().await.unwrap_or_default();
@@ -247,13 +990,334 @@ async fn disk_stress(params: web::Json<TestParams>, client: web::Data<HttpClient
     }
 }
 
-// POST /tasks/{node} — Get list of running tasks from engine pod on a node
-#[post("/tasks/{node}")]
-async fn list_tasks(path: web::Path<String>, client: web::Data<HttpClient>) -> impl Responder {
+// Caller identity headers passed through to the engine, which scopes tasks per client,
+// and the request id so the engine logs the call under the same id
+// A JWT means nothing to the engines: they get the client it names instead
+fn client_headers(req: &HttpRequest) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(value) = request_id::of(req).and_then(|id| reqwest::header::HeaderValue::from_str(&id).ok()) {
+        headers.insert(request_id::HEADER, value);
+    }
+    if let Some(identity) = auth::identity(req) {
+        if let Some(value) = identity.client.and_then(|client| reqwest::header::HeaderValue::from_str(&client).ok()) {
+            headers.insert("x-mogwai-client", value);
+        }
+        return headers;
+    }
+    for name in ["authorization", "x-mogwai-client"] {
+        let value = req.headers().get(name).and_then(|value| reqwest::header::HeaderValue::from_bytes(value.as_bytes()).ok());
+        if let Some(value) = value {
+            headers.insert(name, value);
+        }
+    }
+    headers
+}
+
+// Relay an engine's answer to a start request, keeping Retry-After so clients can
+// back off while the engine is at its task limit
+async fn relay_start(resp: reqwest::Response) -> HttpResponse {
+    let status = resp.status();
+    let retry_after = resp
+        .headers()
+        .get("retry-after")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = resp.text().await.unwrap_or_default();
+    let mut response = HttpResponse::build(status);
+    if let Some(seconds) = retry_after {
+        response.insert_header(("Retry-After", seconds));
+    }
+    response.body(body)
+}
+
+// Pass an engine's JSON response (or its error status) through unchanged
+async fn forward_json(request: reqwest::RequestBuilder) -> HttpResponse {
+    match request.send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            HttpResponse::build(status).content_type("application/json").body(body)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Request failed: {}", e)),
+    }
+}
+
+// Cluster named by ?cluster= on endpoints without a request body
+#[derive(Deserialize)]
+struct ClusterQuery {
+    cluster: Option<String>,
+}
+
+fn query_cluster(req: &HttpRequest) -> Option<String> {
+    web::Query::<ClusterQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.into_inner().cluster)
+}
+
+// The request's query string for the engine, without the controller's cluster parameter
+fn engine_query(req: &HttpRequest) -> String {
+    let params: Vec<&str> = req
+        .query_string()
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("cluster="))
+        .collect();
+    if params.is_empty() { String::new() } else { format!("?{}", params.join("&")) }
+}
+
+// Unknown clusters are the caller's mistake, unreachable ones the controller's problem
+fn cluster_error(e: clusters::ClusterError) -> HttpResponse {
+    match e {
+        clusters::ClusterError::Unknown(_) => HttpResponse::BadRequest().body(e.to_string()),
+        clusters::ClusterError::Client(_) => HttpResponse::InternalServerError().body(e.to_string()),
+        clusters::ClusterError::Static(_) => HttpResponse::BadRequest().body(e.to_string()),
+        clusters::ClusterError::UnknownNode(_) => HttpResponse::NotFound().body(e.to_string()),
+    }
+}
+
+// POST /scenarios — Start a scenario (YAML or JSON body, see scenarios.rs) as one job
+#[post("/scenarios")]
+async fn submit_scenario(body: web::Bytes, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let scenario = match scenarios::parse(&body) {
+        Ok(scenario) => scenario,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    match scenarios::submit(scenario, client.get_ref().clone(), client_headers(&req)) {
+        Ok(job) => HttpResponse::Accepted().json(job),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// POST /campaigns — Start an A/B campaign (YAML or JSON body, see campaigns.rs); followed through /scenarios/{id}
+#[post("/campaigns")]
+async fn submit_campaign(body: web::Bytes, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let campaign = match campaigns::parse(&body) {
+        Ok(campaign) => campaign,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    match campaigns::submit(campaign, client.get_ref().clone(), client_headers(&req)) {
+        Ok(job) => HttpResponse::Accepted().json(job),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// GET /scenarios — Scenario jobs, running and recent
+#[get("/scenarios")]
+async fn list_scenarios() -> impl Responder {
+    HttpResponse::Ok().json(scenarios::list())
+}
+
+// GET /scenarios/{id} — One scenario job with the status of every step
+#[get("/scenarios/{id}")]
+async fn get_scenario(path: web::Path<String>) -> impl Responder {
+    match scenarios::get(&path) {
+        Some(job) => HttpResponse::Ok().json(job),
+        None => HttpResponse::NotFound().body(format!("No scenario {}", path)),
+    }
+}
+
+// POST /scenarios/{id}/stop — Stop a scenario's running tests and skip its pending steps
+#[post("/scenarios/{id}/stop")]
+async fn stop_scenario(path: web::Path<String>) -> impl Responder {
+    match scenarios::stop(&path) {
+        Some(job) => HttpResponse::Ok().json(job),
+        None => HttpResponse::NotFound().body(format!("No scenario {}", path)),
+    }
+}
+
+// POST /chaos — Start launching random tests on random nodes (YAML or JSON body, see chaos.rs)
+#[post("/chaos")]
+async fn submit_chaos(body: web::Bytes, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let spec = match chaos::parse(&body) {
+        Ok(spec) => spec,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    match chaos::submit(spec, client.get_ref().clone(), client_headers(&req)) {
+        Ok(run) => HttpResponse::Accepted().json(run),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// GET /chaos — Chaos runs, running and recent
+#[get("/chaos")]
+async fn list_chaos() -> impl Responder {
+    HttpResponse::Ok().json(chaos::list())
+}
+
+// GET /chaos/{id} — One chaos run with every launch so far
+#[get("/chaos/{id}")]
+async fn get_chaos(path: web::Path<String>) -> impl Responder {
+    match chaos::get(&path) {
+        Some(run) => HttpResponse::Ok().json(run),
+        None => HttpResponse::NotFound().body(format!("No chaos run {}", path)),
+    }
+}
+
+// POST /chaos/{id}/stop — Stop launching and stop the tests the run started
+#[post("/chaos/{id}/stop")]
+async fn stop_chaos(path: web::Path<String>) -> impl Responder {
+    match chaos::stop(&path) {
+        Some(run) => HttpResponse::Ok().json(run),
+        None => HttpResponse::NotFound().body(format!("No chaos run {}", path)),
+    }
+}
+
+// POST /baselines — Mark a completed run as the baseline of its node and test type (see baselines.rs)
+#[post("/baselines")]
+async fn mark_baseline(mark: web::Json<baselines::Mark>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    match baselines::mark(mark.into_inner(), client.get_ref(), &client_headers(&req)).await {
+        Ok(stored) => HttpResponse::Ok().json(stored),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// GET /baselines — Every stored baseline
+#[get("/baselines")]
+async fn list_baselines() -> impl Responder {
+    match baselines::all() {
+        Ok(all) => HttpResponse::Ok().json(all),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// DELETE /baselines/{node}/{type} — Forget a baseline (?cluster= for other clusters)
+#[delete("/baselines/{node}/{test_type}")]
+async fn remove_baseline(path: web::Path<(String, String)>, req: HttpRequest) -> impl Responder {
+    let (node, test_type) = path.into_inner();
+    match baselines::remove(query_cluster(&req).as_deref(), &node, &test_type) {
+        Ok(true) => HttpResponse::Ok().body(format!("Removed the {} baseline of {}", test_type, node)),
+        Ok(false) => HttpResponse::NotFound().body(format!("No {} baseline for {}", test_type, node)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// POST /prune — Drop finished jobs, chaos runs and audit entries past the retention limits
+// (see retention.rs), or past the stricter ones in the optional body
+#[post("/prune")]
+async fn prune_controller(body: web::Bytes) -> impl Responder {
+    let policy = if body.iter().all(u8::is_ascii_whitespace) {
+        retention::Policy::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(policy) => policy,
+            Err(e) => return HttpResponse::BadRequest().body(format!("Invalid prune request: {}", e)),
+        }
+    };
+    match retention::prune(policy) {
+        Ok(pruned) => HttpResponse::Ok().json(pruned),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// POST /prune/{node} — Drop old results, events and artifacts on the engine pod on a node
+// The body ({"max_age_secs": ..., "max_count": ...}, both optional) is passed through
+#[post("/prune/{node}")]
+async fn prune_node(path: web::Path<String>, body: web::Bytes, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/prune") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    let request = client
+        .post(&url)
+        .headers(client_headers(&req))
+        .header("Content-Type", "application/json")
+        .body(body);
+    forward_json(request).await
+}
+
+// GET /clusters — Clusters the controller can target besides its own
+#[get("/clusters")]
+async fn list_clusters() -> impl Responder {
+    let names: Vec<serde_json::Value> = clusters::all()
+        .iter()
+        .map(|cluster| serde_json::json!({ "name": cluster.name, "context": cluster.context }))
+        .collect();
+    HttpResponse::Ok().json(names)
+}
+
+// GET /capabilities/{node} — Privileged operations the engine pod on a node can use
+#[get("/capabilities/{node}")]
+async fn node_capabilities(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/capabilities") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    forward_json(client.get(&url).headers(client_headers(&req))).await
+}
+
+// GET /limits/{node} — Safety limits the engine pod on a node enforces
+#[get("/limits/{node}")]
+async fn node_limits(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/limits") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    forward_json(client.get(&url).headers(client_headers(&req))).await
+}
+
+// GET /calibration/{node} — Stored intensity calibration of the engine pod on a node
+#[get("/calibration/{node}")]
+async fn node_calibration(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/calibration") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    forward_json(client.get(&url).headers(client_headers(&req))).await
+}
+
+// POST /calibration/{node} — Calibrate intensity levels on the engine pod on a node (takes a few seconds)
+#[post("/calibration/{node}")]
+async fn calibrate_node(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let node = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, "/calibration") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+
+    match client.post(&url).headers(client_headers(&req)).send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            HttpResponse::build(status).content_type("application/json").body(body)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Request failed: {}", e)),
+    }
+}
+
+// GET /logs/{node}/stream — Live task output (Server-Sent Events) from the engine pod on a node
+// The query string (e.g. ?task=<id>) is passed through to the engine
+#[get("/logs/{node}/stream")]
+async fn node_logs(path: web::Path<String>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
     let node = path.into_inner();
-    let url = format!("http://mogwai-engine-{}.default.svc.cluster.local:8080/tasks", node);
+    let cluster = query_cluster(&req);
+    let mut url = match clusters::engine_url(cluster.as_deref(), &node, "/logs/stream") {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    url.push_str(&engine_query(&req));
 
-    match client.get(&url).send().await {
+    match client.get(&url).headers(client_headers(&req)).send().await {
+        Ok(resp) if resp.status().is_success() => {
+            // Forward chunks as they arrive until the engine closes the stream
+            let body = futures::stream::unfold(resp, |mut resp| async move {
+                match resp.chunk().await {
+                    Ok(Some(chunk)) => Some((Ok::<_, actix_web::Error>(chunk), resp)),
+                    _ => None,
+                }
+            });
+            HttpResponse::Ok()
+                .content_type("text/event-stream")
+                .insert_header(("Cache-Control", "no-cache"))
+                .streaming(body)
+        }
         Ok(resp) => {
             let status = resp.status();
             let body = resp.text().await.unwrap_or_default();
@@ -265,11 +1329,17 @@ async fn list_tasks(path: web::Path<String>, client: web::Data<HttpClient>) -> i
 
 // POST /stop/{node}/{id} — Stop a specific task by ID on a node
 #[post("/stop/{node}/{id}")]
-async fn stop_task(path: web::Path<(String, String)>, client: web::Data<HttpClient>) -> impl Responder {
+async fn stop_task(path: web::Path<(String, String)>, req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
     let (node, id) = path.into_inner();
-    let url = format!("http://mogwai-engine-{}.default.svc.cluster.local:8080/stop/{}", node, id);
+    let cluster = query_cluster(&req);
+    let mut url = match clusters::engine_url(cluster.as_deref(), &node, &format!("/stop/{}", id)) {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
+    };
+    // Pass ?grace= through
+    url.push_str(&engine_query(&req));
 
-    match client.post(&url).send().await {
+    match client.post(&url).headers(client_headers(&req)).send().await {
         Ok(resp) => {
             let status = resp.status();
             let body = resp.text().await.unwrap_or_default();
@@ -279,27 +1349,59 @@ async fn stop_task(path: web::Path<(String, String)>, client: web::Data<HttpClie
     }
 }
 
-// POST /stop-all — Send stop-all command to every running engine pod
-#[post("/stop-all")]
-async fn stop_all_tasks(client: web::Data<HttpClient>) -> impl Responder {
-    let kube_client = match KubeClient::try_default().await {
-        Ok(c) => c,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to create Kube client: {}", e)),
+// POST /extend/{node}/{id} — Prolong a running task on a node ({"seconds": n})
+#[post("/extend/{node}/{id}")]
+async fn extend_task(
+    path: web::Path<(String, String)>,
+    body: web::Json<serde_json::Value>,
+    req: HttpRequest,
+    client: web::Data<HttpClient>,
+) -> impl Responder {
+    let (node, id) = path.into_inner();
+    let cluster = query_cluster(&req);
+    let url = match clusters::engine_url(cluster.as_deref(), &node, &format!("/extend/{}", id)) {
+        Ok(url) => url,
+        Err(e) => return cluster_error(e),
     };
 
-    let pods_api: Api<Pod> = Api::namespaced(kube_client.clone(), "default");
-    let lp = ListParams::default().labels("app=mogwai-engine");
+    match client.post(&url).headers(client_headers(&req)).json(&*body).send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            HttpResponse::build(status).body(body)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Request failed: {}", e)),
+    }
+}
 
-    // List all mogwai-engine pods
-    let pods = match pods_api.list(&lp).await {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to list mogwai-engine pods: {}", e)),
-    };
+// POST /stop-all — Send stop-all command to every running engine pod (or every listed host)
+#[post("/stop-all")]
+async fn stop_all_tasks(req: HttpRequest, client: web::Data<HttpClient>) -> impl Responder {
+    let cluster = query_cluster(&req);
+    let target_nodes: Vec<String> = match clusters::hosts(cluster.as_deref()) {
+        Ok(Some(hosts)) => hosts.keys().cloned().collect(),
+        Ok(None) => {
+            let kube_client = match clusters::kube_client(cluster.as_deref()).await {
+                Ok(c) => c,
+                Err(e) => return cluster_error(e),
+            };
 
-    // Extract node names from pods
-    let target_nodes: Vec<String> = pods.items.into_iter()
-        .filter_map(|pod| pod.spec.and_then(|spec| spec.node_name))
-        .collect();
+            let pods_api: Api<Pod> = Api::namespaced(kube_client.clone(), "default");
+            let lp = ListParams::default().labels("app=mogwai-engine");
+
+            // List all mogwai-engine pods
+            let pods = match pods_api.list(&lp).await {
+                Ok(p) => p,
+                Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to list mogwai-engine pods: {}", e)),
+            };
+
+            // Extract node names from pods
+            pods.items.into_iter()
+                .filter_map(|pod| pod.spec.and_then(|spec| spec.node_name))
+                .collect()
+        }
+        Err(e) => return cluster_error(e),
+    };
 
     if target_nodes.is_empty() {
         return HttpResponse::Ok().body("No mogwai-engine pods found on any nodes.");
@@ -307,12 +1409,14 @@ async fn stop_all_tasks(client: web::Data<HttpClient>) -> impl Responder {
 
     // Send stop-all to each node in parallel
     let tasks = target_nodes.iter().map(|node| {
-        let url = format!("http://mogwai-engine-{}.default.svc.cluster.local:8080/stop-all", node);
+        // The cluster was checked when its nodes were listed
+        let url = clusters::engine_url(cluster.as_deref(), node, "/stop-all").unwrap_or_default() + &engine_query(&req);
         let client = client.clone();
         let node = node.clone();
+        let headers = client_headers(&req);
 
         async move {
-            match client.post(&url).send().await {
+            match client.post(&url).headers(headers).send().await {
                 Ok(resp) => {
                     let status = resp.status();
                     let body = resp.text().await.unwrap_or_default();
@@ -326,25 +1430,115 @@ async fn stop_all_tasks(client: web::Data<HttpClient>) -> impl Responder {
     HttpResponse::Ok().json(results)
 }
 
+// GET /audit — Recorded API actions, newest first
+// Filters: ?since=<ts>&until=<ts>&client=<name>&path=<prefix>&failed=true&limit=<n>
+#[get("/audit")]
+async fn list_audit(query: web::Query<audit::AuditQuery>) -> impl Responder {
+    match web::block(move || audit::query(&query)).await {
+        Ok(Ok(entries)) => HttpResponse::Ok().json(entries),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read the audit log: {}", e)),
+    }
+}
+
+// GET /templates — Shared test templates served to the CLI (`template sync`)
+// Templates are read from the JSON array at $TEMPLATES_FILE (default: /etc/mogwai/templates.json)
+#[get("/templates")]
+async fn list_templates() -> impl Responder {
+    let path = std::env::var("TEMPLATES_FILE").unwrap_or_else(|_| "/etc/mogwai/templates.json".to_string());
+
+    match std::fs::read_to_string(&path) {
+        Ok(data) => match serde_json::from_str::<Vec<serde_json::Value>>(&data) {
+            Ok(templates) => HttpResponse::Ok().json(templates),
+            Err(e) => HttpResponse::InternalServerError().body(format!("Invalid templates file {}: {}", path, e)),
+        },
+        // No templates file configured means no shared templates
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HttpResponse::Ok().json(Vec::<serde_json::Value>::new()),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read templates file {}: {}", path, e)),
+    }
+}
+
+// Every API route; served under /v1 and, for older clients, without a prefix
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(stress)
+        .service(list_nodes)
+        .service(spawn_engine)
+        .service(spawn_engines)
+        .service(list_engines)
+        .service(remove_engine)
+        .service(custom_stress)
+        .service(list_custom)
+        .service(estimate_battery)
+        .service(list_tasks)
+        .service(node_metrics)
+        .service(node_sysinfo)
+        .service(node_capabilities)
+        .service(node_limits)
+        .service(node_calibration)
+        .service(calibrate_node)
+        .service(node_results)
+        .service(node_events)
+        .service(node_task_result)
+        .service(node_task_artifacts)
+        .service(node_logs)
+        .service(stop_task)
+        .service(extend_task)
+        .service(stop_all_tasks)
+        .service(list_templates)
+        .service(list_clusters)
+        .service(list_audit)
+        .service(submit_scenario)
+        .service(list_scenarios)
+        .service(get_scenario)
+        .service(stop_scenario)
+        .service(submit_campaign)
+        .service(submit_chaos)
+        .service(list_chaos)
+        .service(get_chaos)
+        .service(stop_chaos)
+        .service(mark_baseline)
+        .service(list_baselines)
+        .service(remove_baseline)
+        .service(list_agents)
+        .service(connect_agent)
+        .service(list_discovered)
+        .service(prune_controller)
+        .service(prune_node)
+        .service(pool_status)
+        .service(cluster_overview);
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    logging::init();
+    otlp::start();
+    if let Err(e) = clusters::load() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+    }
+    if let Err(e) = node_defaults::load() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+    }
+    if let Err(e) = audit::init() {
+        tracing::warn!("{}; audit entries go to this log instead", e);
+    }
+    agents::start_relay()?;
+    discovery::start();
+    retention::start();
     let client = HttpClient::new();
-    println!("Starting controller server on 0.0.0.0:8081");
+    pool::start(client.clone());
+    tracing::info!("{}", cors::describe());
+    tracing::info!("{}", auth::describe());
+    tracing::info!(bind = "0.0.0.0:8081", "Starting controller server");
     HttpServer::new(move || {
-        let cors = Cors::permissive();
-
         App::new()
-            .wrap(cors)
+            .wrap(actix_web::middleware::from_fn(auth::authorize))
+            .wrap(actix_web::middleware::from_fn(audit::record))
+            .wrap(actix_web::middleware::from_fn(request_id::middleware))
+            .wrap(cors::middleware())
             .app_data(web::Data::new(client.clone()))
-            .service(cpu_stress)
-            .service(mem_stress)
-            .service(disk_stress)
-            .service(list_nodes)
-            .service(spawn_engine)
-            .service(remove_engine)
-            .service(list_tasks)
-            .service(stop_task)
-            .service(stop_all_tasks)
+            .service(web::scope("/v1").configure(routes))
+            // The unversioned paths predate /v1 and stay as aliases for existing clients
+            .configure(routes)
     })
     .bind(("0.0.0.0", 8081))?
     .run()
@@ -0,0 +1,129 @@
+// Per-node defaults and caps for test parameters, e.g. smaller disk tests on
+// storage-constrained edge nodes
+//
+// The rules are listed in the JSON file at $MOGWAI_NODE_DEFAULTS_FILE (default:
+// /etc/mogwai/node-defaults.json), e.g.
+//   [{"nodes": ["edge-*"], "defaults": {"disk": {"size": 64}, "*": {"duration": 30}},
+//     "max": {"mem": {"size": 512}, "*": {"intensity": 2}}},
+//    {"nodes": ["bench1"], "cluster": "lab", "max": {"disk": {"size": 4096}}}]
+// A rule applies to the nodes its patterns match (`*` matches any run of characters), in
+// its cluster if it names one, else in every cluster. Its `defaults` and `max` are keyed by
+// test type (cpu, mem, disk, ...), with `*` for every type. Rules are read in order, and
+// a later rule overrides the fields an earlier one set; a type's own entry overrides `*`.
+//
+// Before a test is sent to a node, the fields the request leaves out take the node's
+// defaults. A numeric field above the node's maximum is refused; one left out that would
+// otherwise take a global default (see TestParams) above the maximum takes the maximum.
+// This applies to tests started directly and to those of scenarios, campaigns and chaos
+// runs; custom workloads are sent as they are.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+// One entry of the node defaults file
+#[derive(Debug, Deserialize)]
+struct Rule {
+    nodes: Vec<String>,                                    // Node name patterns
+    #[serde(default)]
+    cluster: Option<String>,                               // Only nodes of this cluster
+    #[serde(default)]
+    defaults: BTreeMap<String, Map<String, Value>>,        // Test type -> field -> value
+    #[serde(default)]
+    max: BTreeMap<String, BTreeMap<String, f64>>,          // Test type -> field -> maximum
+}
+
+static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+
+// Read the node defaults file once at startup; a missing file means no per-node rules
+pub fn load() -> Result<(), String> {
+    let path = std::env::var("MOGWAI_NODE_DEFAULTS_FILE").unwrap_or_else(|_| "/etc/mogwai/node-defaults.json".to_string());
+    let rules: Vec<Rule> = match std::fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid node defaults file {}: {}", path, e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Failed to read node defaults file {}: {}", path, e)),
+    };
+    if !rules.is_empty() {
+        tracing::info!(rules = rules.len(), "Per-node test defaults configured");
+    }
+    let _ = RULES.set(rules);
+    Ok(())
+}
+
+// Whether `name` matches `pattern`, where `*` matches any run of characters
+fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty(); // No `*`: exact match
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+// Fill in the node's defaults and check its caps on the body of a test for the engine
+pub fn apply(cluster: Option<&str>, node: &str, test_type: &str, body: &mut Map<String, Value>) -> Result<(), String> {
+    let mut defaults = Map::new();
+    let mut max = BTreeMap::new();
+    let rules = RULES.get().map(Vec::as_slice).unwrap_or_default();
+    for rule in rules {
+        let in_cluster = rule.cluster.is_none() || rule.cluster.as_deref() == cluster;
+        if !in_cluster || !rule.nodes.iter().any(|pattern| matches(pattern, node)) {
+            continue;
+        }
+        for key in ["*", test_type] {
+            if let Some(fields) = rule.defaults.get(key) {
+                defaults.extend(fields.clone());
+            }
+            if let Some(fields) = rule.max.get(key) {
+                max.extend(fields.clone());
+            }
+        }
+    }
+    if defaults.is_empty() && max.is_empty() {
+        return Ok(());
+    }
+
+    let unset = |body: &Map<String, Value>, field: &str| body.get(field).is_none_or(Value::is_null);
+    for (field, value) in defaults {
+        if unset(body, &field) {
+            body.insert(field, value);
+        }
+    }
+    let global = serde_json::to_value(crate::TestParams::default()).unwrap_or_default();
+    for (field, cap) in max {
+        if unset(body, &field) {
+            // The engine would use the global default, unless a calibrated level or (for
+            // cpu) a system load stands in for the field
+            let replaced = body.contains_key("level") || (field == "load" && !unset(body, "system_load"));
+            let default = global.get(&field).and_then(Value::as_f64);
+            if !replaced && default.is_some_and(|default| default > cap) {
+                body.insert(field, number(cap));
+            }
+            continue;
+        }
+        if let Some(value) = body[&field].as_f64().filter(|&value| value > cap) {
+            return Err(format!("{} {} is above the maximum of {} on node {}", field, value, cap, node));
+        }
+    }
+    Ok(())
+}
+
+// Whole numbers stay integers, since the engine parses most fields as such
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value >= 0.0 && value <= u64::MAX as f64 {
+        Value::from(value as u64)
+    } else {
+        Value::from(value)
+    }
+}
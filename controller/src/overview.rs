@@ -0,0 +1,178 @@
+// Cluster-wide view of the engines' load, for `mogwai top` and dashboards
+//
+// GET /overview asks every engine of the cluster for its host metrics (/metrics) and the
+// caller's running tasks (/tasks) at once, and reports per node:
+//   cpu_percent, memory_used_mb, memory_total_mb    the host, as in /metrics
+//   disk_mb_s                                        read plus write throughput
+//   tasks, types                                     running tasks, and how many of each type
+//   cpu_cores                                        CPU the cpu tests generate: threads x load
+//   memory_mb                                        memory the mem tests allocate: threads x size
+// with totals over the reachable nodes. The engines are those of the host list, the engine
+// pods in Kubernetes, and the agents and discovered engines of the cluster. Tests that leave
+// out threads, load or size count with the engine's defaults (4, 100 and 256); closed-loop
+// cpu tests (system_load) show in cpu_percent only.
+
+use futures::future::join_all;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, ListParams};
+use reqwest::header::HeaderMap;
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::clusters::{self, ClusterError};
+use crate::{agents, discovery};
+
+// An engine that takes longer is reported unreachable
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Engine defaults for the fields a test leaves out
+const DEFAULT_THREADS: f64 = 4.0;
+const DEFAULT_LOAD: f64 = 100.0;
+const DEFAULT_SIZE_MB: f64 = 256.0;
+
+#[derive(Debug, Default, Serialize)]
+pub struct NodeLoad {
+    pub node: String,
+    pub reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub cpu_percent: f64,
+    pub memory_used_mb: u64,
+    pub memory_total_mb: u64,
+    pub disk_mb_s: f64,
+    pub tasks: usize,
+    pub types: BTreeMap<String, usize>,
+    pub cpu_cores: f64,
+    pub memory_mb: f64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Totals {
+    pub nodes: usize,
+    pub reachable: usize,
+    pub tasks: usize,
+    pub disk_mb_s: f64,
+    pub cpu_cores: f64,
+    pub memory_mb: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Overview {
+    pub timestamp: f64,
+    pub nodes: Vec<NodeLoad>,
+    pub totals: Totals,
+}
+
+// Nodes of the cluster that have an engine
+async fn engine_nodes(cluster: Option<&str>) -> Result<Vec<String>, ClusterError> {
+    let mut nodes: Vec<String> = match clusters::hosts(cluster)? {
+        Some(hosts) => hosts.keys().cloned().collect(),
+        None => Vec::new(),
+    };
+    let connected: Vec<String> = agents::list()
+        .into_iter()
+        .filter(|agent| agent.cluster.as_deref() == cluster)
+        .map(|agent| agent.node)
+        .chain(discovery::list().into_iter().filter(|_| cluster.is_none()).map(|engine| engine.node))
+        .collect();
+    if clusters::hosts(cluster)?.is_none() {
+        match clusters::kube_client(cluster).await {
+            Ok(client) => {
+                let pods = Api::<Pod>::namespaced(client, "default")
+                    .list(&ListParams::default().labels("app=mogwai-engine"))
+                    .await;
+                match pods {
+                    Ok(pods) => nodes.extend(pods.items.into_iter().filter_map(|pod| {
+                        pod.metadata.name.as_deref()?.strip_prefix("mogwai-engine-").map(str::to_string)
+                    })),
+                    Err(_) if !connected.is_empty() => {}
+                    Err(e) => return Err(ClusterError::Client(format!("Failed to list engine pods: {}", e))),
+                }
+            }
+            // Agents and discovered engines need no Kubernetes
+            Err(_) if !connected.is_empty() => {}
+            Err(e) => return Err(e),
+        }
+    }
+    for node in connected {
+        if !nodes.contains(&node) {
+            nodes.push(node);
+        }
+    }
+    nodes.sort();
+    Ok(nodes)
+}
+
+// Poll every engine of the cluster; `headers` name the caller, whose tasks are counted
+pub async fn collect(cluster: Option<&str>, http: &HttpClient, headers: HeaderMap) -> Result<Overview, ClusterError> {
+    let nodes = engine_nodes(cluster).await?;
+    let probes = nodes.into_iter().map(|node| {
+        let headers = headers.clone();
+        async move {
+            match probe(cluster, &node, http, headers).await {
+                Ok(load) => load,
+                Err(error) => NodeLoad { node, error: Some(error), ..Default::default() },
+            }
+        }
+    });
+    let nodes = join_all(probes).await;
+
+    let mut totals = Totals { nodes: nodes.len(), ..Default::default() };
+    for node in nodes.iter().filter(|node| node.reachable) {
+        totals.reachable += 1;
+        totals.tasks += node.tasks;
+        totals.disk_mb_s += node.disk_mb_s;
+        totals.cpu_cores += node.cpu_cores;
+        totals.memory_mb += node.memory_mb;
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    Ok(Overview { timestamp, nodes, totals })
+}
+
+async fn probe(cluster: Option<&str>, node: &str, http: &HttpClient, headers: HeaderMap) -> Result<NodeLoad, String> {
+    let fetch = |path: &str| {
+        let url = clusters::engine_url(cluster, node, path).map_err(|e| e.to_string());
+        let http = http.clone();
+        let headers = headers.clone();
+        async move {
+            let resp = http.get(url?).headers(headers).timeout(PROBE_TIMEOUT).send().await.map_err(|e| e.to_string())?;
+            if !resp.status().is_success() {
+                return Err(format!("Engine answered {}", resp.status().as_u16()));
+            }
+            resp.json::<Value>().await.map_err(|e| e.to_string())
+        }
+    };
+    let (metrics, tasks) = futures::join!(fetch("/metrics"), fetch("/tasks"));
+    let (metrics, tasks) = (metrics?, tasks?);
+
+    let number = |value: &Value, field: &str| value.get(field).and_then(Value::as_f64);
+    let mut load = NodeLoad {
+        node: node.to_string(),
+        reachable: true,
+        cpu_percent: number(&metrics, "cpu_percent").unwrap_or_default(),
+        memory_used_mb: metrics.get("memory_used_mb").and_then(Value::as_u64).unwrap_or_default(),
+        memory_total_mb: metrics.get("memory_total_mb").and_then(Value::as_u64).unwrap_or_default(),
+        disk_mb_s: number(&metrics, "disk_read_mb_s").unwrap_or_default() + number(&metrics, "disk_write_mb_s").unwrap_or_default(),
+        ..Default::default()
+    };
+    for task in tasks.as_array().into_iter().flatten() {
+        let test_type = task.get("type").and_then(Value::as_str).unwrap_or("unknown");
+        load.tasks += 1;
+        *load.types.entry(test_type.to_string()).or_default() += 1;
+        let params = task.get("params").cloned().unwrap_or_default();
+        let threads = number(&params, "intensity").unwrap_or(DEFAULT_THREADS);
+        match test_type {
+            "cpu" if params.get("system_load").is_some() => {}
+            "cpu" => load.cpu_cores += threads * number(&params, "load").unwrap_or(DEFAULT_LOAD) / 100.0,
+            "mem" => load.memory_mb += threads * number(&params, "size").unwrap_or(DEFAULT_SIZE_MB),
+            _ => {}
+        }
+    }
+    Ok(load)
+}
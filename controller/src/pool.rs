@@ -0,0 +1,231 @@
+// Warm pool of engines, so batteries and campaigns do not wait for an image pull per test
+//
+//   $MOGWAI_WARM_POOL_SIZE       idle engines to keep ready (default 0: no pool)
+//   $MOGWAI_WARM_POOL_SELECTOR   label selector of the pool's nodes, e.g. `mogwai.io/pool=warm`
+//                                (default every node)
+//
+// Every 30 seconds the controller counts the engines on the pool's Ready, schedulable nodes
+// in its own cluster: an engine is warming while its pod starts, idle when it runs no task
+// (running_tasks in its /metrics) and busy otherwise. While fewer than the pool size are
+// warming or idle, it spawns engines on pool nodes that have none. Engines of the pool whose
+// pod ended (failed, or removed by hand and left a finished pod) are deleted, so their node
+// can take a fresh one. Busy engines are left alone and the pool never removes idle ones;
+// GET /pool reports its state.
+
+use k8s_openapi::api::core::v1::{Node, Pod, Service};
+use kube::api::{Api, DeleteParams, ListParams};
+use kube::Client as KubeClient;
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clusters;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// How long an engine may take to report its running tasks
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Label marking the engine pods the pool spawned
+pub const POOL_LABEL: &str = "mogwai-pool";
+
+// State of one engine on a pool node
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineState {
+    Warming,     // Pod scheduled but not Ready yet
+    Idle,        // Ready and running no task
+    Busy,        // Running tests
+    Unreachable, // Ready, but did not answer
+    Ended,       // Pod finished or failed
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolEngine {
+    pub node: String,
+    pub pod: String,
+    pub state: EngineState,
+    pub pooled: bool, // Spawned by the pool, rather than by a spawn request
+}
+
+// The pool as of its last check, as reported by GET /pool
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PoolStatus {
+    pub size: usize,                  // Idle engines to keep
+    pub selector: Option<String>,
+    pub nodes: usize,                 // Eligible pool nodes
+    pub engines: Vec<PoolEngine>,
+    pub ready: usize,                 // Warming and idle engines
+    pub spawned: Vec<String>,         // Nodes given an engine by the last check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,        // Why the last check failed
+    pub checked_at: f64,
+}
+
+static STATUS: Mutex<Option<PoolStatus>> = Mutex::new(None);
+
+fn size() -> usize {
+    std::env::var("MOGWAI_WARM_POOL_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(0)
+}
+
+fn selector() -> Option<String> {
+    std::env::var("MOGWAI_WARM_POOL_SELECTOR").ok().filter(|selector| !selector.is_empty())
+}
+
+// Keep the pool filled in the background if $MOGWAI_WARM_POOL_SIZE asks for one
+pub fn start(http: HttpClient) {
+    let size = size();
+    if size == 0 {
+        return;
+    }
+    if clusters::hosts(None).is_ok_and(|hosts| hosts.is_some()) {
+        tracing::warn!("A warm pool needs Kubernetes; the static host list has no engines to spawn");
+        return;
+    }
+    tracing::info!(size, selector = selector().as_deref().unwrap_or("(every node)"), "Keeping a warm pool of engines");
+    tokio::spawn(async move {
+        let mut failing = false;
+        loop {
+            let status = check(size, &http).await;
+            match &status.error {
+                Some(e) if !failing => {
+                    tracing::warn!(error = %e, "Warm pool check failed");
+                    failing = true;
+                }
+                Some(_) => {}
+                None => {
+                    if failing {
+                        tracing::info!("Warm pool check recovered");
+                    }
+                    failing = false;
+                }
+            }
+            *STATUS.lock().unwrap_or_else(|e| e.into_inner()) = Some(status);
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// The pool as of its last check; None when there is no pool
+pub fn status() -> Option<PoolStatus> {
+    if size() == 0 {
+        return None;
+    }
+    let status = STATUS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    Some(status.unwrap_or_else(|| PoolStatus { size: size(), selector: selector(), ..Default::default() }))
+}
+
+async fn check(size: usize, http: &HttpClient) -> PoolStatus {
+    let mut status = PoolStatus {
+        size,
+        selector: selector(),
+        checked_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+        ..Default::default()
+    };
+    if let Err(e) = fill(&mut status, http).await {
+        status.error = Some(e);
+    }
+    status
+}
+
+async fn fill(status: &mut PoolStatus, http: &HttpClient) -> Result<(), String> {
+    let client = clusters::kube_client(None).await.map_err(|e| e.to_string())?;
+    let mut params = ListParams::default();
+    if let Some(selector) = &status.selector {
+        params = params.labels(selector);
+    }
+    let nodes: Vec<String> = Api::<Node>::all(client.clone())
+        .list(&params)
+        .await
+        .map_err(|e| format!("Failed to list pool nodes: {}", e))?
+        .items
+        .iter()
+        .filter(|node| crate::node_eligible(node))
+        .filter_map(|node| node.metadata.name.clone())
+        .collect();
+    status.nodes = nodes.len();
+
+    // Engines of the pool's nodes, by node
+    let pods: BTreeMap<String, Pod> = Api::<Pod>::namespaced(client.clone(), "default")
+        .list(&ListParams::default().labels("app=mogwai-engine"))
+        .await
+        .map_err(|e| format!("Failed to list engine pods: {}", e))?
+        .items
+        .into_iter()
+        .filter_map(|pod| {
+            let node = pod.metadata.name.as_deref()?.strip_prefix("mogwai-engine-")?.to_string();
+            nodes.contains(&node).then_some((node, pod))
+        })
+        .collect();
+    for (node, pod) in &pods {
+        let state = engine_state(node, pod, http).await;
+        let pooled = pod.metadata.labels.as_ref().is_some_and(|labels| labels.contains_key(POOL_LABEL));
+        if state == EngineState::Ended && pooled {
+            remove(&client, pod).await;
+        }
+        status.engines.push(PoolEngine {
+            node: node.clone(),
+            pod: pod.metadata.name.clone().unwrap_or_default(),
+            state,
+            pooled,
+        });
+    }
+
+    status.ready = status
+        .engines
+        .iter()
+        .filter(|engine| matches!(engine.state, EngineState::Warming | EngineState::Idle))
+        .count();
+    let free = nodes.iter().filter(|node| !pods.contains_key(*node));
+    for node in free.take(status.size.saturating_sub(status.ready)) {
+        let mut pod = crate::engine_pod(node, &Default::default(), false, crate::Placement::Pinned);
+        pod.metadata.labels.get_or_insert_with(BTreeMap::new).insert(POOL_LABEL.to_string(), "warm".to_string());
+        match crate::create_engine(&client, pod).await {
+            Ok(()) => {
+                tracing::info!(node = %node, "Spawned a warm pool engine");
+                status.spawned.push(node.clone());
+            }
+            Err(e) => tracing::warn!(node = %node, error = %e, "Cannot spawn a warm pool engine"),
+        }
+    }
+    Ok(())
+}
+
+async fn engine_state(node: &str, pod: &Pod, http: &HttpClient) -> EngineState {
+    let status = pod.status.as_ref();
+    match status.and_then(|status| status.phase.as_deref()) {
+        Some("Succeeded" | "Failed") => return EngineState::Ended,
+        Some("Running") => {}
+        _ => return EngineState::Warming,
+    }
+    let ready = status
+        .and_then(|status| status.conditions.as_ref())
+        .is_some_and(|conditions| conditions.iter().any(|c| c.type_ == "Ready" && c.status == "True"));
+    if !ready {
+        return EngineState::Warming;
+    }
+    let Ok(url) = clusters::engine_url(None, node, "/metrics") else {
+        return EngineState::Unreachable;
+    };
+    let metrics = match http.get(&url).timeout(PROBE_TIMEOUT).send().await {
+        Ok(resp) if resp.status().is_success() => resp.json::<serde_json::Value>().await.ok(),
+        _ => None,
+    };
+    match metrics.and_then(|metrics| metrics.get("running_tasks").and_then(|tasks| tasks.as_u64())) {
+        Some(0) => EngineState::Idle,
+        Some(_) => EngineState::Busy,
+        None => EngineState::Unreachable,
+    }
+}
+
+// Delete an ended pool engine and its service; the next check spawns a fresh one
+async fn remove(client: &KubeClient, pod: &Pod) {
+    let Some(name) = pod.metadata.name.as_deref() else {
+        return;
+    };
+    let _ = Api::<Pod>::namespaced(client.clone(), "default").delete(name, &DeleteParams::default()).await;
+    let _ = Api::<Service>::namespaced(client.clone(), "default").delete(name, &DeleteParams::default()).await;
+    tracing::info!(pod = %name, "Removed an ended warm pool engine");
+}
@@ -0,0 +1,108 @@
+// Request ids, to follow one call through the CLI, controller and engine logs
+//
+// Every request carries an X-Request-Id: the caller's when it sent a usable one (up to
+// 128 letters, digits and -_.:), otherwise a fresh one. The controller logs it with each
+// call that changes something or fails, forwards it to the engines (see client_headers),
+// writes it to the audit log, returns it in the X-Request-Id response header and adds it
+// to error bodies. The engines do the same, so one id ties the three logs together.
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage, HttpRequest, HttpResponse};
+use rand::Rng;
+use serde_json::Value;
+use std::time::Instant;
+use tracing::Instrument;
+
+pub const HEADER: &str = "x-request-id";
+
+const MAX_LEN: usize = 128;
+
+// The id, kept in the request's extensions
+#[derive(Debug, Clone)]
+struct RequestId(String);
+
+// The id of a request that went through the middleware
+pub fn of(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<RequestId>().map(|id| id.0.clone())
+}
+
+// The caller's id when it is usable, otherwise a new one
+fn accept_or_generate(header: Option<&str>) -> String {
+    header
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_LEN)
+        .filter(|id| id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')))
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{:032x}", rand::rng().random::<u128>()))
+}
+
+// Middleware giving every request an id; wrapped outside audit and auth so both see it
+pub async fn middleware(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let id = accept_or_generate(req.headers().get(HEADER).and_then(|value| value.to_str().ok()));
+    req.extensions_mut().insert(RequestId(id.clone()));
+    let started = Instant::now();
+    let method = req.method().clone();
+    let path = req.path().to_string();
+
+    // Everything logged while handling the request carries its id; it is also the
+    // submission span of the tests it starts (see otlp)
+    let traceparent = req.headers().get("traceparent").and_then(|value| value.to_str().ok()).map(str::to_string);
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        traceparent,
+        otel.http.request.method = %method,
+        otel.url.path = %path,
+        otel.http.response.status_code = tracing::field::Empty,
+    );
+    let response = next.call(req).instrument(span.clone()).await?.map_into_boxed_body();
+    let status = response.status();
+    let failed = status.is_client_error() || status.is_server_error();
+    span.record("otel.http.response.status_code", status.as_u16());
+    if failed || !matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        let duration_ms = started.elapsed().as_millis() as u64;
+        span.in_scope(|| {
+            if failed {
+                tracing::warn!(method = %method, path, status = status.as_u16(), duration_ms, "Request failed");
+            } else {
+                tracing::info!(method = %method, path, status = status.as_u16(), duration_ms, "Request handled");
+            }
+        });
+    }
+    let mut response = if failed { with_id_in_body(response, &id).await } else { response };
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HeaderName::from_static(HEADER), value);
+    }
+    Ok(response)
+}
+
+// Add the id to an error body: a request_id field in JSON objects, a suffix on text
+async fn with_id_in_body(response: ServiceResponse<BoxBody>, id: &str) -> ServiceResponse<BoxBody> {
+    let (req, res) = response.into_parts();
+    let json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let (head, payload) = res.into_parts();
+    let Ok(bytes) = body::to_bytes(payload).await else {
+        return ServiceResponse::new(req, head.set_body(BoxBody::new(())));
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut fields)) if json => {
+            fields.entry("request_id").or_insert_with(|| Value::from(id));
+            Value::Object(fields).to_string()
+        }
+        // Errors relayed from an engine carry the id already
+        _ if text.contains(id) => text.into_owned(),
+        _ if text.trim().is_empty() => String::new(),
+        _ => format!("{} (request ID: {})", text.trim_end(), id),
+    };
+    let res: HttpResponse = head.set_body(BoxBody::new(body));
+    ServiceResponse::new(req, res)
+}
@@ -0,0 +1,95 @@
+// Retention of the controller's job history and audit log, so a long-lived controller
+// does not grow them without bound
+//
+//   $MOGWAI_RETENTION_SECS          age past which finished jobs, chaos runs and audit entries
+//                                   are dropped (default: no age limit)
+//   $MOGWAI_RETENTION_COUNT         finished scenario/campaign jobs kept, and chaos runs (default 100)
+//   $MOGWAI_AUDIT_RETENTION_COUNT   audit entries kept (default: all)
+//
+// A background pruner applies the limits every 10 minutes; POST /prune applies them right
+// away, or stricter ones given in its body. Running jobs and chaos runs are never dropped.
+// Engines prune their own results, events and artifacts (see the engine's retention).
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{audit, chaos, scenarios};
+
+// Finished jobs and chaos runs kept unless $MOGWAI_RETENTION_COUNT is set
+const DEFAULT_COUNT: usize = 100;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(600);
+
+// Retention limits; the body of POST /prune, where unset fields take the configured ones
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct Policy {
+    pub max_age_secs: Option<u64>,
+    pub max_count: Option<usize>,       // Finished jobs and chaos runs, of each
+    pub max_audit_entries: Option<usize>,
+}
+
+// What a prune dropped
+#[derive(Debug, Default, Serialize)]
+pub struct Pruned {
+    pub jobs: usize,
+    pub chaos_runs: usize,
+    pub audit_entries: usize,
+}
+
+fn configured() -> &'static Policy {
+    static POLICY: OnceLock<Policy> = OnceLock::new();
+    POLICY.get_or_init(|| {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Policy {
+            max_age_secs: var("MOGWAI_RETENTION_SECS").filter(|&secs| secs > 0),
+            max_count: var("MOGWAI_RETENTION_COUNT").map(|count| count as usize),
+            max_audit_entries: var("MOGWAI_AUDIT_RETENTION_COUNT").map(|count| count as usize),
+        }
+    })
+}
+
+// Finished jobs and chaos runs kept as new ones start
+pub fn max_count() -> usize {
+    configured().max_count.unwrap_or(DEFAULT_COUNT)
+}
+
+// Start the background pruner
+pub fn start() {
+    let policy = configured();
+    if policy.max_age_secs.is_some() || policy.max_audit_entries.is_some() {
+        tracing::info!(
+            max_age_secs = policy.max_age_secs,
+            max_count = max_count(),
+            max_audit_entries = policy.max_audit_entries,
+            "Pruning finished jobs and the audit log"
+        );
+    }
+    tokio::spawn(async {
+        let mut tick = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            tick.tick().await;
+            match prune(Policy::default()) {
+                Ok(pruned) if pruned.jobs + pruned.chaos_runs + pruned.audit_entries > 0 => {
+                    tracing::info!(?pruned, "Pruned finished jobs and audit entries");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "Pruning failed"),
+            }
+        }
+    });
+}
+
+// Apply `policy`, falling back to the configured limits
+pub fn prune(policy: Policy) -> Result<Pruned, String> {
+    let max_age = policy.max_age_secs.or(configured().max_age_secs);
+    let cutoff = max_age.map(|secs| {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() - secs as f64
+    });
+    let max_count = policy.max_count.unwrap_or_else(max_count);
+    Ok(Pruned {
+        jobs: scenarios::prune(cutoff, max_count),
+        chaos_runs: chaos::prune(cutoff, max_count),
+        audit_entries: audit::prune(cutoff, policy.max_audit_entries.or(configured().max_audit_entries))?,
+    })
+}
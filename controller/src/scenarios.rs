@@ -0,0 +1,677 @@
+// Scenarios: multi-step test plans the controller runs as one job
+//
+// A scenario is a YAML (or JSON) document posted to /scenarios, e.g.
+//   name: ramp-then-mixed
+//   node: worker-1
+//   steps:
+//     - name: mem-ramp
+//       test: {type: mem, intensity: 2, size: 2048, duration: 300}
+//     - name: settle
+//       wait: 30
+//     - name: cpu-and-disk
+//       parallel:
+//         - {type: cpu, load: 80, duration: 600}
+//         - {type: disk, size: 512, duration: 600, node: worker-2}
+//     - name: probe
+//       after: [mem-ramp]
+//       repeat: 5
+//       test: {type: syscall, duration: 30}
+//
+// Every step is one test, a parallel group of tests, or a wait in seconds. A step starts
+// once the steps named in `after` have completed (default: the step before it; `after: []`
+// starts it right away), so steps can only depend on steps listed before them. When a step
+// fails or is stopped, the steps depending on it are skipped. Tests go to their own node,
+// else the scenario's, and take the body of the engine's /<type>-stress endpoint.
+// With `repeat: N` a test or parallel step runs N times back to back, and the step reports
+// mean, spread and percentiles of every result metric over its runs (see stats.rs).
+// Completed tests are checked against the baseline of their node and type, if there is one
+// (see baselines.rs); a job with a regressed test is flagged `regressed`.
+//
+// Jobs live in memory; finished ones are dropped as set by retention.rs.
+
+use reqwest::header::HeaderMap;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinSet;
+use tracing::Instrument;
+
+use crate::baselines::{self, Regression};
+use crate::clusters;
+use crate::node_defaults;
+use crate::otlp;
+use crate::retention;
+use crate::stats::{self, Delta, Summary};
+
+// How often running tests are checked for a result
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// How long a test may run past its duration before the job gives up on it
+const RESULT_MARGIN: Duration = Duration::from_secs(300);
+
+// How long a stopped test gets to report its result
+const STOP_MARGIN: Duration = Duration::from_secs(30);
+
+// A scenario as submitted
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub node: Option<String>,    // Default node of the tests
+    #[serde(default)]
+    pub cluster: Option<String>, // Default cluster of the tests (see clusters.rs)
+    pub steps: Vec<StepSpec>,
+}
+
+// One step as submitted: exactly one of test, parallel and wait
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepSpec {
+    #[serde(default)]
+    pub name: Option<String>,           // Default step-<n>, counted from 1
+    #[serde(default)]
+    pub after: Option<Vec<String>>,     // Steps to complete first, default the previous one
+    #[serde(default)]
+    pub test: Option<TestSpec>,
+    #[serde(default)]
+    pub parallel: Option<Vec<TestSpec>>,
+    #[serde(default)]
+    pub wait: Option<u64>,              // Seconds
+    #[serde(default)]
+    pub repeat: Option<u32>,            // Runs of a test or parallel step, default 1
+}
+
+// A test of a step: its type, where it runs and the engine request body
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestSpec {
+    #[serde(rename = "type")]
+    pub test_type: String,
+    #[serde(default)]
+    pub node: Option<String>,
+    #[serde(default)]
+    pub cluster: Option<String>,
+    #[serde(flatten)]
+    pub params: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Skipped, // A step it depends on did not complete
+    Stopped,
+}
+
+impl Status {
+    fn finished(self) -> bool {
+        !matches!(self, Status::Pending | Status::Running)
+    }
+}
+
+// A scenario run, as reported by GET /scenarios
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: String,
+    pub name: Option<String>,
+    pub status: Status,
+    pub submitted_at: f64, // Unix time in seconds
+    pub finished_at: Option<f64>,
+    pub steps: Vec<StepState>,
+    pub regressed: bool,                // A completed test did worse than its baseline
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<Comparison>, // Campaigns, once both arms finished
+    #[serde(skip)]
+    stop: Arc<AtomicBool>,
+    #[serde(skip)]
+    arms: Option<Arms>,
+}
+
+// The two steps a campaign compares (see campaigns.rs)
+#[derive(Debug, Clone)]
+pub struct Arms {
+    pub a: String, // Step names
+    pub b: String,
+}
+
+// How the second arm of a campaign did against the first, metric by metric
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub a: String,
+    pub b: String,
+    pub metrics: BTreeMap<String, Delta>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepState {
+    pub name: String,
+    pub kind: &'static str, // test, parallel or wait
+    pub after: Vec<String>,
+    pub status: Status,
+    pub started_at: Option<f64>,
+    pub finished_at: Option<f64>,
+    pub repeat: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<TaskState>,       // Every test of every run, run by run
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub summary: Vec<TestSummary>,   // One per test of the step, over its completed runs
+}
+
+// One engine task started by a step
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskState {
+    #[serde(rename = "type")]
+    pub test_type: String,
+    pub node: String,
+    pub params: Map<String, Value>,   // Engine request body, without the node
+    pub run: u32,                     // Counted from 1
+    pub task_id: Option<String>,      // Set once the engine accepted the test
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub regressions: Vec<Regression>, // Against the baseline of the node and type
+}
+
+// Result metrics of one test of a step over its runs
+#[derive(Debug, Clone, Serialize)]
+pub struct TestSummary {
+    #[serde(rename = "type")]
+    pub test_type: String,
+    pub node: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+    pub metrics: BTreeMap<String, Summary>,
+}
+
+// What a step does once it may start
+enum Action {
+    Tests(Vec<Target>),
+    Wait(Duration),
+}
+
+// A test with its node and cluster resolved
+struct Target {
+    test_type: String,
+    node: String,
+    cluster: Option<String>,
+    body: Map<String, Value>, // Engine request body
+    duration: u64,            // Seconds, for the result timeout
+}
+
+struct Step {
+    after: Vec<usize>,
+    action: Action,
+    repeat: u32,
+}
+
+static JOBS: Mutex<VecDeque<Job>> = Mutex::new(VecDeque::new());
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// Parse a scenario from YAML; JSON is valid YAML, so JSON bodies parse too
+pub fn parse(body: &[u8]) -> Result<Scenario, String> {
+    serde_yaml::from_slice(body).map_err(|e| format!("Invalid scenario: {}", e))
+}
+
+// Check a scenario and start it as a job; returns the job as it starts
+pub fn submit(scenario: Scenario, client: HttpClient, headers: HeaderMap) -> Result<Job, String> {
+    start("scenario", scenario, None, client, headers)
+}
+
+// Start a campaign's scenario; its arms are compared when the job finishes
+pub fn submit_campaign(scenario: Scenario, arms: Arms, client: HttpClient, headers: HeaderMap) -> Result<Job, String> {
+    start("campaign", scenario, Some(arms), client, headers)
+}
+
+fn start(kind: &str, scenario: Scenario, arms: Option<Arms>, client: HttpClient, headers: HeaderMap) -> Result<Job, String> {
+    let (steps, states) = plan(&scenario)?;
+    let job = Job {
+        id: format!("{}-{}", kind, NEXT_ID.fetch_add(1, Ordering::SeqCst)),
+        name: scenario.name,
+        status: Status::Running,
+        submitted_at: now_secs(),
+        finished_at: None,
+        steps: states,
+        regressed: false,
+        comparison: None,
+        stop: Arc::new(AtomicBool::new(false)),
+        arms,
+    };
+    {
+        let mut jobs = lock_jobs();
+        while jobs.len() >= retention::max_count().max(1) {
+            match jobs.iter().position(|job| job.status.finished()) {
+                Some(index) => jobs.remove(index),
+                None => break,
+            };
+        }
+        jobs.push_back(job.clone());
+    }
+    tracing::info!(job_id = %job.id, steps = steps.len(), "Starting job");
+    // The job logs under its id and that of the request that started it
+    let span = tracing::info_span!("job", job_id = %job.id);
+    tokio::spawn(run(job.id.clone(), steps, client, headers, Arc::clone(&job.stop)).instrument(span));
+    Ok(job)
+}
+
+pub fn list() -> Vec<Job> {
+    lock_jobs().iter().cloned().collect()
+}
+
+pub fn get(id: &str) -> Option<Job> {
+    lock_jobs().iter().find(|job| job.id == id).cloned()
+}
+
+// Ask a job to stop: running tests are stopped, pending steps skipped
+pub fn stop(id: &str) -> Option<Job> {
+    let job = get(id)?;
+    job.stop.store(true, Ordering::SeqCst);
+    Some(job)
+}
+
+// Drop finished jobs that ended before `cutoff` (Unix seconds), and all but the newest
+// `keep` finished ones; returns how many went
+pub fn prune(cutoff: Option<f64>, keep: usize) -> usize {
+    let mut jobs = lock_jobs();
+    let before = jobs.len();
+    let mut kept = 0;
+    let mut index = jobs.len();
+    while index > 0 {
+        index -= 1;
+        let job = &jobs[index];
+        if !job.status.finished() {
+            continue;
+        }
+        let expired = cutoff.zip(job.finished_at).is_some_and(|(cutoff, finished_at)| finished_at < cutoff);
+        if kept < keep && !expired {
+            kept += 1;
+        } else {
+            jobs.remove(index);
+        }
+    }
+    before - jobs.len()
+}
+
+fn lock_jobs() -> std::sync::MutexGuard<'static, VecDeque<Job>> {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn update(id: &str, change: impl FnOnce(&mut Job)) {
+    if let Some(job) = lock_jobs().iter_mut().find(|job| job.id == id) {
+        change(job);
+    }
+}
+
+// Resolve names, dependencies and nodes, or say what is wrong with the scenario
+fn plan(scenario: &Scenario) -> Result<(Vec<Step>, Vec<StepState>), String> {
+    if scenario.steps.is_empty() {
+        return Err("A scenario needs at least one step".to_string());
+    }
+    let mut names: Vec<String> = Vec::new();
+    let mut steps = Vec::new();
+    let mut states = Vec::new();
+    for (index, spec) in scenario.steps.iter().enumerate() {
+        let name = spec.name.clone().unwrap_or_else(|| format!("step-{}", index + 1));
+        if names.contains(&name) {
+            return Err(format!("Step name '{}' is used twice", name));
+        }
+        let after_names = match &spec.after {
+            Some(after) => after.clone(),
+            None => names.last().cloned().into_iter().collect(),
+        };
+        let after = after_names
+            .iter()
+            .map(|dependency| {
+                names.iter().position(|name| name == dependency).ok_or_else(|| {
+                    format!("Step '{}' comes after '{}', which is not a step listed before it", name, dependency)
+                })
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+
+        let (kind, action) = match (&spec.test, &spec.parallel, spec.wait) {
+            (Some(test), None, None) => ("test", Action::Tests(vec![target(scenario, &name, test)?])),
+            (None, Some(tests), None) if !tests.is_empty() => (
+                "parallel",
+                Action::Tests(tests.iter().map(|test| target(scenario, &name, test)).collect::<Result<_, _>>()?),
+            ),
+            (None, None, Some(seconds)) => ("wait", Action::Wait(Duration::from_secs(seconds))),
+            _ => return Err(format!("Step '{}' needs exactly one of test, parallel (non-empty) or wait", name)),
+        };
+        let repeat = match (spec.repeat, &action) {
+            (None, _) => 1,
+            (Some(0), _) => return Err(format!("Step '{}' needs a repeat of at least 1", name)),
+            (Some(_), Action::Wait(_)) => return Err(format!("Step '{}' is a wait and cannot be repeated", name)),
+            (Some(repeat), Action::Tests(_)) => repeat,
+        };
+        let tasks = match &action {
+            Action::Tests(targets) => (1..=repeat)
+                .flat_map(|run| {
+                    targets.iter().map(move |target| TaskState {
+                        test_type: target.test_type.clone(),
+                        node: target.node.clone(),
+                        params: target.body.iter().filter(|(name, _)| *name != "node").map(|(k, v)| (k.clone(), v.clone())).collect(),
+                        run,
+                        task_id: None,
+                        status: Status::Pending,
+                        error: None,
+                        regressions: Vec::new(),
+                    })
+                })
+                .collect(),
+            Action::Wait(_) => Vec::new(),
+        };
+        states.push(StepState {
+            name: name.clone(),
+            kind,
+            after: after_names,
+            status: Status::Pending,
+            started_at: None,
+            finished_at: None,
+            repeat,
+            tasks,
+            summary: Vec::new(),
+        });
+        steps.push(Step { after, action, repeat });
+        names.push(name);
+    }
+    Ok((steps, states))
+}
+
+fn target(scenario: &Scenario, step: &str, test: &TestSpec) -> Result<Target, String> {
+    let node = test
+        .node
+        .clone()
+        .or_else(|| scenario.node.clone())
+        .ok_or_else(|| format!("A test of step '{}' has no node, and the scenario sets none", step))?;
+    let cluster = test.cluster.clone().or_else(|| scenario.cluster.clone());
+    // Fail now rather than halfway through the scenario
+    clusters::engine_url(cluster.as_deref(), &node, "").map_err(|e| e.to_string())?;
+    let duration = match test.params.get("duration") {
+        None => 10,
+        Some(value) => value
+            .as_u64()
+            .filter(|&seconds| seconds > 0)
+            .ok_or_else(|| format!("Tests of step '{}' need a duration above 0, as a scenario waits for them", step))?,
+    };
+    let mut body = test.params.clone();
+    body.insert("node".to_string(), Value::String(node.clone()));
+    Ok(Target { test_type: test.test_type.clone(), node, cluster, body, duration })
+}
+
+// Start every step whose dependencies completed, until all steps are done
+async fn run(id: String, steps: Vec<Step>, client: HttpClient, headers: HeaderMap, stop: Arc<AtomicBool>) {
+    let steps: Vec<Arc<Step>> = steps.into_iter().map(Arc::new).collect();
+    let mut status = vec![Status::Pending; steps.len()];
+    let mut running = JoinSet::new();
+    loop {
+        for index in 0..steps.len() {
+            if status[index] != Status::Pending {
+                continue;
+            }
+            let dependencies: Vec<Status> = steps[index].after.iter().map(|&after| status[after]).collect();
+            let next = if stop.load(Ordering::SeqCst) || dependencies.iter().any(|s| s.finished() && *s != Status::Completed) {
+                Status::Skipped
+            } else if dependencies.iter().all(|&s| s == Status::Completed) {
+                Status::Running
+            } else {
+                continue;
+            };
+            status[index] = next;
+            update(&id, |job| {
+                let state = &mut job.steps[index];
+                state.status = next;
+                if next == Status::Running {
+                    state.started_at = Some(now_secs());
+                } else {
+                    state.tasks.iter_mut().for_each(|task| task.status = Status::Skipped);
+                }
+            });
+            if next == Status::Running {
+                let (id, step) = (id.clone(), Arc::clone(&steps[index]));
+                let (client, headers, stop) = (client.clone(), headers.clone(), Arc::clone(&stop));
+                running.spawn(async move { (index, run_step(&id, index, &step, &client, &headers, &stop).await) }.in_current_span());
+            }
+        }
+
+        let Some(finished) = running.join_next().await else {
+            break;
+        };
+        // A panicking step leaves its status as it was; count it as failed
+        let (index, outcome) = finished.unwrap_or_else(|_| {
+            let index = status.iter().position(|&s| s == Status::Running).unwrap_or(0);
+            (index, Status::Failed)
+        });
+        status[index] = outcome;
+        update(&id, |job| {
+            let state = &mut job.steps[index];
+            state.status = outcome;
+            state.finished_at = Some(now_secs());
+            // Runs after a failed or stopped one never started
+            state.tasks.iter_mut().filter(|task| task.status == Status::Pending).for_each(|task| task.status = Status::Skipped);
+        });
+    }
+
+    let outcome = if stop.load(Ordering::SeqCst) {
+        Status::Stopped
+    } else if status.iter().all(|&s| s == Status::Completed) {
+        Status::Completed
+    } else {
+        Status::Failed
+    };
+    update(&id, |job| {
+        job.status = outcome;
+        job.finished_at = Some(now_secs());
+        job.comparison = job.arms.as_ref().map(|arms| compare(&job.steps, arms));
+    });
+    tracing::info!(status = ?outcome, "Job finished");
+    if let Some(job) = get(&id).filter(|job| job.regressed) {
+        tracing::warn!("Job regressed against its baselines");
+        baselines::notify(&client, &job).await;
+    }
+}
+
+async fn run_step(id: &str, index: usize, step: &Step, client: &HttpClient, headers: &HeaderMap, stop: &AtomicBool) -> Status {
+    match &step.action {
+        Action::Wait(duration) => {
+            let until = Instant::now() + *duration;
+            while Instant::now() < until {
+                if stop.load(Ordering::SeqCst) {
+                    return Status::Stopped;
+                }
+                tokio::time::sleep(until.saturating_duration_since(Instant::now()).min(Duration::from_secs(1))).await;
+            }
+            Status::Completed
+        }
+        Action::Tests(targets) => {
+            // Metrics of every completed run, per test of the step
+            let mut runs: Vec<Vec<BTreeMap<String, f64>>> = vec![Vec::new(); targets.len()];
+            for run in 0..step.repeat as usize {
+                if stop.load(Ordering::SeqCst) {
+                    return Status::Stopped;
+                }
+                let tests = targets
+                    .iter()
+                    .enumerate()
+                    .map(|(test, target)| run_test(id, index, run * targets.len() + test, target, client, headers, stop));
+                let outcomes = futures::future::join_all(tests).await;
+                for (test, (_, metrics)) in outcomes.iter().enumerate() {
+                    runs[test].extend(metrics.clone());
+                }
+                let summary: Vec<TestSummary> = targets
+                    .iter()
+                    .zip(&runs)
+                    .map(|(target, runs)| TestSummary {
+                        test_type: target.test_type.clone(),
+                        node: target.node.clone(),
+                        cluster: target.cluster.clone(),
+                        metrics: stats::summarize(runs),
+                    })
+                    .collect();
+                update(id, |job| job.steps[index].summary = summary);
+
+                if outcomes.iter().any(|(s, _)| *s == Status::Failed) {
+                    return Status::Failed;
+                }
+                if outcomes.iter().any(|(s, _)| *s != Status::Completed) {
+                    return Status::Stopped;
+                }
+            }
+            Status::Completed
+        }
+    }
+}
+
+// Compare the summaries of a campaign's two steps; every node of an arm counts alike
+fn compare(steps: &[StepState], arms: &Arms) -> Comparison {
+    let summaries = |name: &str| -> Vec<&BTreeMap<String, Summary>> {
+        steps
+            .iter()
+            .filter(|step| step.name == name)
+            .flat_map(|step| step.summary.iter().map(|test| &test.metrics))
+            .collect()
+    };
+    Comparison {
+        a: arms.a.clone(),
+        b: arms.b.clone(),
+        metrics: stats::compare(&summaries(&arms.a), &summaries(&arms.b)),
+    }
+}
+
+// Start one test on its engine and wait for its result; the result's metrics if it completed
+async fn run_test(
+    id: &str,
+    step: usize,
+    task: usize,
+    target: &Target,
+    client: &HttpClient,
+    headers: &HeaderMap,
+    stop: &AtomicBool,
+) -> (Status, Option<BTreeMap<String, f64>>) {
+    let started = start_test(target.cluster.as_deref(), &target.node, &target.test_type, &target.body, client, headers).await;
+    let (status, error, metrics) = match started {
+        Ok(task_id) => {
+            tracing::info!(task_id = %task_id, node = %target.node, test_type = %target.test_type, "Step test started");
+            update(id, |job| {
+                let state = &mut job.steps[step].tasks[task];
+                state.task_id = Some(task_id.clone());
+                state.status = Status::Running;
+            });
+            wait_for_result(target, &task_id, client, headers, stop).await
+        }
+        Err(error) => {
+            tracing::warn!(node = %target.node, test_type = %target.test_type, error = %error, "Step test failed to start");
+            (Status::Failed, Some(error), None)
+        }
+    };
+    let regressions = metrics
+        .as_ref()
+        .map(|metrics| baselines::check(target.cluster.as_deref(), &target.node, &target.test_type, metrics))
+        .unwrap_or_default();
+    update(id, |job| {
+        job.regressed |= !regressions.is_empty();
+        let state = &mut job.steps[step].tasks[task];
+        state.status = status;
+        state.error = error;
+        state.regressions = regressions;
+    });
+    (status, metrics)
+}
+
+// POST a test to its engine; the task id from the answer
+pub async fn start_test(
+    cluster: Option<&str>,
+    node: &str,
+    test_type: &str,
+    body: &Map<String, Value>,
+    client: &HttpClient,
+    headers: &HeaderMap,
+) -> Result<String, String> {
+    let path = format!("/{}-stress", test_type);
+    let url = clusters::engine_url(cluster, node, &path).map_err(|e| e.to_string())?;
+    let mut body = body.clone();
+    node_defaults::apply(cluster, node, test_type, &mut body)?;
+    let response = otlp::dispatch(node, &url, client.post(&url).headers(headers.clone()).json(&body))
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("Engine answered {}: {}", status.as_u16(), body.trim()));
+    }
+    // "... stress task started with ID: <id>"
+    body.rsplit_once("ID:")
+        .map(|(_, task_id)| task_id.trim().to_string())
+        .filter(|task_id| !task_id.is_empty())
+        .ok_or_else(|| format!("No task id in the engine's answer: {}", body.trim()))
+}
+
+// Poll the engine until the task's result is in; stops the task when the job is stopped
+// Completed tests come with the metrics of their result
+async fn wait_for_result(
+    target: &Target,
+    task_id: &str,
+    client: &HttpClient,
+    headers: &HeaderMap,
+    stop: &AtomicBool,
+) -> (Status, Option<String>, Option<BTreeMap<String, f64>>) {
+    let url = |path: String| clusters::engine_url(target.cluster.as_deref(), &target.node, &path).unwrap_or_default();
+    let mut deadline = Instant::now() + Duration::from_secs(target.duration) + RESULT_MARGIN;
+    let mut stopping = false;
+    loop {
+        if stop.load(Ordering::SeqCst) && !stopping {
+            stopping = true;
+            deadline = Instant::now() + STOP_MARGIN;
+            let _ = client.post(url(format!("/stop/{}", task_id))).headers(headers.clone()).send().await;
+        }
+        let response = client.get(url(format!("/results/{}", task_id))).headers(headers.clone()).send().await;
+        match response {
+            Ok(response) if response.status().is_success() => {
+                let result: Value = response.json().await.unwrap_or_default();
+                if !result["error"].is_null() {
+                    return (Status::Failed, Some(error_message(&result["error"])), None);
+                }
+                if result["result"]["stopped"].as_bool() == Some(true) || !result["stop"].is_null() {
+                    return (Status::Stopped, None, None);
+                }
+                return (Status::Completed, None, Some(stats::metrics(&result["result"])));
+            }
+            // 404 while the task runs; other answers and network errors may pass
+            _ => {}
+        }
+        if Instant::now() >= deadline {
+            let error = format!("no result for task {} on {} in time", task_id, target.node);
+            return (if stopping { Status::Stopped } else { Status::Failed }, Some(error), None);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+// A StressError as the engine serializes it, e.g. {"InvalidParameter": "..."}
+fn error_message(error: &Value) -> String {
+    match error {
+        // Engine errors are {"kind": ..., "message": ...}
+        Value::Object(fields) => match (fields.get("kind"), fields.get("message")) {
+            (Some(Value::String(kind)), Some(Value::String(message))) => format!("{}: {}", kind, message),
+            (Some(Value::String(kind)), _) => kind.clone(),
+            _ => error.to_string(),
+        },
+        Value::String(message) => message.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
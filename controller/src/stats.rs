@@ -0,0 +1,182 @@
+// Statistics over repeated runs of the same test
+//
+// Engine results are flattened into named metrics ("mb_per_s", "lock.wait.p99_us", ...):
+// every number of the result, nested objects joined with dots. Per-thread and per-path
+// lists are left out, their totals are metrics of their own.
+// Two sets of runs are compared metric by metric with Welch's t-test, which gives a hint
+// whether a difference stands out from the run-to-run noise.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Summary of one metric over the runs that reported it
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub runs: usize,
+    pub mean: f64,
+    pub stddev: f64, // Sample standard deviation; 0 for a single run
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+impl Summary {
+    // None for no values
+    pub fn of(values: &[f64]) -> Option<Summary> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let runs = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / runs as f64;
+        let variance = if runs > 1 {
+            sorted.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (runs - 1) as f64
+        } else {
+            0.0
+        };
+        Some(Summary {
+            runs,
+            mean,
+            stddev: variance.sqrt(),
+            min: sorted[0],
+            max: sorted[runs - 1],
+            p50: quantile(&sorted, 0.50),
+            p90: quantile(&sorted, 0.90),
+            p99: quantile(&sorted, 0.99),
+        })
+    }
+}
+
+// Linear interpolation between the closest ranks of sorted values
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (low, high) = (position.floor() as usize, position.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (position - low as f64)
+}
+
+// The numeric metrics of a StressResult, by dotted name
+pub fn metrics(result: &Value) -> BTreeMap<String, f64> {
+    let mut metrics = BTreeMap::new();
+    flatten("", result, &mut metrics);
+    metrics
+}
+
+fn flatten(prefix: &str, value: &Value, metrics: &mut BTreeMap<String, f64>) {
+    match value {
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                metrics.insert(prefix.to_string(), number);
+            }
+        }
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                let name = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+                flatten(&name, value, metrics);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Summaries of every metric over a set of runs
+pub fn summarize(runs: &[BTreeMap<String, f64>]) -> BTreeMap<String, Summary> {
+    let mut values: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for run in runs {
+        for (name, &value) in run {
+            values.entry(name.as_str()).or_default().push(value);
+        }
+    }
+    values
+        .into_iter()
+        .filter_map(|(name, values)| Some((name.to_string(), Summary::of(&values)?)))
+        .collect()
+}
+
+// How one metric differs between the two arms of a comparison
+#[derive(Debug, Clone, Serialize)]
+pub struct Delta {
+    pub a_mean: f64,
+    pub b_mean: f64,
+    pub a_runs: usize,
+    pub b_runs: usize,
+    pub delta: f64,                 // b_mean - a_mean
+    pub delta_percent: Option<f64>, // Relative to a_mean; None when a_mean is 0
+    pub t: Option<f64>,             // Welch's t statistic; None with fewer than 2 runs on a side
+    pub hint: &'static str,         // significant, noise, no change or too few runs
+}
+
+// Mean, variance and count of several summaries taken together, e.g. the nodes of an arm
+fn pool(summaries: &[&Summary]) -> Option<(f64, f64, usize)> {
+    let runs: usize = summaries.iter().map(|summary| summary.runs).sum();
+    if runs == 0 {
+        return None;
+    }
+    let mean = summaries.iter().map(|summary| summary.mean * summary.runs as f64).sum::<f64>() / runs as f64;
+    if runs < 2 {
+        return Some((mean, 0.0, runs));
+    }
+    // Spread within each summary plus the spread of their means
+    let squares: f64 = summaries
+        .iter()
+        .map(|summary| {
+            (summary.runs as f64 - 1.0) * summary.stddev.powi(2) + summary.runs as f64 * (summary.mean - mean).powi(2)
+        })
+        .sum();
+    Some((mean, squares / (runs - 1) as f64, runs))
+}
+
+// Two-sided 95% critical value of Student's t for the given degrees of freedom
+fn critical_t(df: f64) -> f64 {
+    const TABLE: [(f64, f64); 15] = [
+        (1.0, 12.71), (2.0, 4.30), (3.0, 3.18), (4.0, 2.78), (5.0, 2.57), (6.0, 2.45), (7.0, 2.36), (8.0, 2.31),
+        (9.0, 2.26), (10.0, 2.23), (15.0, 2.13), (20.0, 2.09), (30.0, 2.04), (60.0, 2.00), (120.0, 1.98),
+    ];
+    // Rounding the degrees of freedom down keeps the hint on the careful side
+    TABLE.iter().rev().find(|(at, _)| df >= *at).map_or(TABLE[0].1, |(_, t)| *t)
+}
+
+// Compare every metric both arms reported; each arm is the metric summaries of its tests
+pub fn compare(a: &[&BTreeMap<String, Summary>], b: &[&BTreeMap<String, Summary>]) -> BTreeMap<String, Delta> {
+    let names: Vec<&String> = a.iter().flat_map(|metrics| metrics.keys()).collect();
+    let mut deltas = BTreeMap::new();
+    for name in names {
+        if deltas.contains_key(name) {
+            continue;
+        }
+        let side = |arm: &[&BTreeMap<String, Summary>]| pool(&arm.iter().filter_map(|metrics| metrics.get(name)).collect::<Vec<_>>());
+        let (Some((a_mean, a_var, a_runs)), Some((b_mean, b_var, b_runs))) = (side(a), side(b)) else {
+            continue;
+        };
+        let delta = b_mean - a_mean;
+        let (t, hint) = if a_runs < 2 || b_runs < 2 {
+            (None, "too few runs")
+        } else {
+            let (a_se, b_se) = (a_var / a_runs as f64, b_var / b_runs as f64);
+            let se = (a_se + b_se).sqrt();
+            if se == 0.0 {
+                (None, if delta == 0.0 { "no change" } else { "significant" })
+            } else {
+                let t = delta / se;
+                // Welch-Satterthwaite degrees of freedom
+                let df = (a_se + b_se).powi(2)
+                    / (a_se.powi(2) / (a_runs - 1) as f64 + b_se.powi(2) / (b_runs - 1) as f64);
+                (Some(t), if t.abs() >= critical_t(df) { "significant" } else { "noise" })
+            }
+        };
+        deltas.insert(name.clone(), Delta {
+            a_mean,
+            b_mean,
+            a_runs,
+            b_runs,
+            delta,
+            delta_percent: (a_mean != 0.0).then(|| delta / a_mean.abs() * 100.0),
+            t,
+            hint,
+        });
+    }
+    deltas
+}
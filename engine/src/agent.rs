@@ -0,0 +1,295 @@
+//! Agent mode: the engine connects out to the controller
+//!
+//! The controller normally reaches engines through in-cluster DNS names, which excludes
+//! bare-metal hosts and engines behind NAT. With `MOGWAI_CONTROLLER_URL` set (e.g.
+//! `https://mogwai.example.com`), the engine keeps a WebSocket open to the controller's
+//! `/v1/agents/connect` instead, registers its node name (`MOGWAI_NODE_NAME`, or the
+//! hostname), version, test types and capabilities, and answers the controller's calls
+//! over it. Each call is handed to the engine's own API, so agents behave exactly like
+//! engines reached directly, log streams included.
+//!
+//! - `MOGWAI_AGENT_TOKEN`: bearer token for the controller, when it requires JWTs (admin role)
+//! - `MOGWAI_AGENT_CLUSTER`: cluster name the node is addressed under, default none (the
+//!   controller's own cluster)
+//!
+//! The connection is pinged every 20 seconds and reopened after a drop, waiting up to a
+//! minute between attempts.
+//!
+//! Messages are JSON text frames, except response bodies:
+//!
+//! - engine: `{"type": "register", "node", "cluster", "version", "test_types", "capabilities", "restricted"}`
+//! - controller: `{"type": "request", "id", "method", "path", "headers"}`, then the request
+//!   body as binary frames (see below), then `{"type": "end", "id"}`; and `{"type": "cancel", "id"}`
+//!   when it no longer reads the answer
+//! - engine: `{"type": "response", "id", "status", "headers"}`, then binary frames of an
+//!   8-byte big-endian request id followed by a piece of the body, then `{"type": "end", "id"}`,
+//!   or `{"type": "error", "id", "message"}` when the call failed
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use crate::{capabilities, metrics, stress};
+
+/// Path of the controller's agent endpoint
+const CONNECT_PATH: &str = "/v1/agents/connect";
+
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Longest wait between connection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Body pieces are sent in frames of at most this size
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Headers that belong to one hop, not to the call being relayed
+const HOP_HEADERS: [&str; 6] = ["host", "connection", "content-length", "transfer-encoding", "upgrade", "keep-alive"];
+
+/// Where to connect, and where the engine's own API listens
+struct Config {
+    url: String,   // WebSocket URL of the controller's agent endpoint
+    token: Option<String>,
+    cluster: Option<String>,
+    local: String, // Base URL of this engine's API, e.g. http://127.0.0.1:8080
+}
+
+impl Config {
+    /// None unless MOGWAI_CONTROLLER_URL is set
+    fn from_env(local: SocketAddr) -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        let controller = var("MOGWAI_CONTROLLER_URL")?;
+        let base = controller.trim_end_matches('/');
+        let url = match base.split_once("://") {
+            Some(("https" | "wss", rest)) => format!("wss://{}{}", rest, CONNECT_PATH),
+            Some((_, rest)) => format!("ws://{}{}", rest, CONNECT_PATH),
+            None => format!("ws://{}{}", base, CONNECT_PATH),
+        };
+        // A wildcard bind address is reached over loopback
+        let host = match local.ip() {
+            ip if ip.is_unspecified() => "127.0.0.1".to_string(),
+            ip if ip.is_ipv6() => format!("[{}]", ip),
+            ip => ip.to_string(),
+        };
+        Some(Config {
+            url,
+            token: var("MOGWAI_AGENT_TOKEN"),
+            cluster: var("MOGWAI_AGENT_CLUSTER"),
+            local: format!("http://{}:{}", host, local.port()),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Outgoing {
+    Register {
+        node: String,
+        cluster: Option<String>,
+        version: &'static str,
+        test_types: Vec<String>,
+        capabilities: Vec<capabilities::CapabilityStatus>,
+        restricted: bool,
+    },
+    Response { id: u64, status: u16, headers: Vec<(String, String)> },
+    End { id: u64 },
+    Error { id: u64, message: String },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Incoming {
+    Request {
+        id: u64,
+        method: String,
+        path: String,
+        #[serde(default)]
+        headers: Vec<(String, String)>,
+    },
+    End { id: u64 },
+    Cancel { id: u64 },
+}
+
+/// A call from the controller whose body is still arriving
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Keep a connection to the controller in the background if MOGWAI_CONTROLLER_URL is set;
+/// `local` is the address the engine's API is bound to
+pub fn start(local: SocketAddr) {
+    let Some(config) = Config::from_env(local) else {
+        return;
+    };
+    tracing::info!(url = %config.url, node = %metrics::node_name(), "Agent mode: connecting to the controller");
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            match run(&config, &client).await {
+                // Closed by the controller, e.g. on a restart: start over with a short wait
+                Ok(()) => {
+                    tracing::warn!("Connection to the controller closed; reconnecting");
+                    backoff = Duration::from_secs(1);
+                }
+                Err(e) => tracing::warn!(error = %e, retry_secs = backoff.as_secs(), "Cannot connect to the controller"),
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+/// One connection, until it drops
+async fn run(config: &Config, client: &reqwest::Client) -> Result<(), String> {
+    let mut request = config.url.as_str().into_client_request().map_err(|e| e.to_string())?;
+    if let Some(token) = &config.token {
+        let value = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|e| e.to_string())?;
+        request.headers_mut().insert("authorization", value);
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(request).await.map_err(|e| e.to_string())?;
+    let (mut sink, mut stream) = socket.split();
+
+    let register = Outgoing::Register {
+        node: metrics::node_name(),
+        cluster: config.cluster.clone(),
+        version: env!("CARGO_PKG_VERSION"),
+        test_types: stress::names(),
+        capabilities: capabilities::status(),
+        restricted: capabilities::restricted(),
+    };
+    sink.send(text(&register)).await.map_err(|e| e.to_string())?;
+    tracing::info!("Registered with the controller");
+
+    // Calls in progress write their answers here
+    let (outgoing, mut answers) = mpsc::unbounded_channel::<Message>();
+    let mut requests: HashMap<u64, Request> = HashMap::new();
+    let mut calls: HashMap<u64, AbortHandle> = HashMap::new();
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<Incoming>(&text) {
+                    Ok(Incoming::Request { id, method, path, headers }) => {
+                        requests.insert(id, Request { method, path, headers, body: Vec::new() });
+                    }
+                    // The whole request is in: hand it to the engine's API
+                    Ok(Incoming::End { id }) => {
+                        let Some(request) = requests.remove(&id) else {
+                            continue;
+                        };
+                        calls.retain(|_, call| !call.is_finished());
+                        let call = relay(client.clone(), format!("{}{}", config.local, request.path), request);
+                        let outgoing = outgoing.clone();
+                        calls.insert(id, tokio::spawn(async move { call.await.answer(id, &outgoing).await }).abort_handle());
+                    }
+                    Ok(Incoming::Cancel { id }) => {
+                        requests.remove(&id);
+                        if let Some(call) = calls.remove(&id) {
+                            call.abort();
+                        }
+                    }
+                    Err(e) => tracing::warn!(error = %e, "Unreadable message from the controller"),
+                },
+                // Body pieces: the call's id, then the bytes
+                Some(Ok(Message::Binary(bytes))) if bytes.len() >= 8 => {
+                    let mut id = [0; 8];
+                    id.copy_from_slice(&bytes[..8]);
+                    if let Some(request) = requests.get_mut(&u64::from_be_bytes(id)) {
+                        request.body.extend_from_slice(&bytes[8..]);
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => break Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => break Err(e.to_string()),
+            },
+            Some(answer) = answers.recv() => {
+                if let Err(e) = sink.send(answer).await {
+                    break Err(e.to_string());
+                }
+            }
+            _ = ping.tick() => {
+                if let Err(e) = sink.send(Message::Ping(Vec::new().into())).await {
+                    break Err(e.to_string());
+                }
+            }
+        }
+    };
+    for call in calls.values() {
+        call.abort();
+    }
+    result
+}
+
+/// What calling the engine's own API gave
+enum Answer {
+    Response(reqwest::Response),
+    Failed(String),
+}
+
+impl Answer {
+    /// Send it back as a response message, the body pieces and an end message
+    async fn answer(self, id: u64, outgoing: &mpsc::UnboundedSender<Message>) {
+        let mut response = match self {
+            Answer::Response(response) => response,
+            Answer::Failed(message) => {
+                let _ = outgoing.send(text(&Outgoing::Error { id, message }));
+                return;
+            }
+        };
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| !HOP_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let _ = outgoing.send(text(&Outgoing::Response { id, status: response.status().as_u16(), headers }));
+        loop {
+            match response.chunk().await {
+                Ok(Some(bytes)) => {
+                    for piece in bytes.chunks(CHUNK_SIZE) {
+                        let mut frame = id.to_be_bytes().to_vec();
+                        frame.extend_from_slice(piece);
+                        if outgoing.send(Message::Binary(frame.into())).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = outgoing.send(text(&Outgoing::Error { id, message: e.to_string() }));
+                    return;
+                }
+            }
+        }
+        let _ = outgoing.send(text(&Outgoing::End { id }));
+    }
+}
+
+/// Hand one call from the controller to the engine's API
+async fn relay(client: reqwest::Client, url: String, call: Request) -> Answer {
+    let Ok(method) = reqwest::Method::from_bytes(call.method.as_bytes()) else {
+        return Answer::Failed(format!("Invalid method {}", call.method));
+    };
+    let mut request = client.request(method, url).body(call.body);
+    for (name, value) in call.headers.iter().filter(|(name, _)| !HOP_HEADERS.contains(&name.to_ascii_lowercase().as_str())) {
+        request = request.header(name, value);
+    }
+    match request.send().await {
+        Ok(response) => Answer::Response(response),
+        Err(e) => Answer::Failed(e.to_string()),
+    }
+}
+
+fn text(message: &Outgoing) -> Message {
+    Message::Text(serde_json::to_string(message).unwrap_or_default().into())
+}
@@ -0,0 +1,327 @@
+//! Per-task artifacts, downloadable as a tarball
+//!
+//! Results hold summaries; deep analysis needs what they were computed from, without
+//! exec'ing into the pod. While a task runs the engine keeps:
+//!
+//! - `samples.csv`: host metrics (as in GET /metrics) sampled every second
+//! - `logs.txt`: the task's log lines
+//! - `histograms.json`: the raw buckets of its latency histograms (mem, disk and lock tests)
+//! - `files/`: the disk test's files, when the request sets `"keep_files": true`
+//!
+//! GET /artifacts/{task_id} streams them as `<task_id>.tar.gz`, next to `task.json` (the
+//! task as listed by GET /tasks when it started) and `result.json` (its entry of GET
+//! /results). Artifacts of the last 50 tasks are kept; older ones are dropped together with
+//! their kept files. Long runs keep at most 3600 samples, halving the rate as they go.
+//! Tasks isolated with `MOGWAI_CGROUPS` have no histograms or kept files: the child
+//! process that ran the test does not hand them back.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use crate::disk_stress::{remove_temp_file, track_temp_file, untrack_temp_file};
+use crate::histogram::Histogram;
+use crate::logs::LogLine;
+use crate::thread_manager::{self, TaskInfo, GLOBAL_REGISTRY};
+use crate::metrics;
+
+/// Tasks whose artifacts are kept
+const MAX_TASKS: usize = 50;
+
+/// Samples kept per task; beyond this every other one is dropped and the rate halved
+const MAX_SAMPLES: usize = 3600;
+
+/// Log lines kept per task; older lines are dropped first
+const MAX_LOG_LINES: usize = 10_000;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+const SAMPLES_HEADER: &str = "timestamp,cpu_percent,memory_used_mb,memory_total_mb,disk_read_mb_s,disk_write_mb_s,running_tasks\n";
+
+/// Tarball pieces are sent in chunks of this size
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// What one task left behind
+#[derive(Clone)]
+struct Artifacts {
+    task_id: String,
+    task: Value,                 // TaskInfo when it started
+    samples: Vec<String>,        // CSV rows
+    sample_every: u64,           // Keep one of this many samples
+    sampled: u64,                // Samples taken so far
+    logs: VecDeque<String>,
+    histograms: Map<String, Value>,
+    files: Vec<(String, PathBuf)>, // Name in the tarball, file on disk
+}
+
+static STORE: Lazy<Mutex<VecDeque<Artifacts>>> = Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_TASKS)));
+
+fn with_task<T>(task_id: &str, f: impl FnOnce(&mut Artifacts) -> T) -> Option<T> {
+    let mut store = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    store.iter_mut().rev().find(|artifacts| artifacts.task_id == task_id).map(f)
+}
+
+/// Samples host metrics for a task until dropped
+pub struct Recorder {
+    sampler: AbortHandle,
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.sampler.abort();
+    }
+}
+
+/// Start collecting a task's artifacts; they are kept after the returned recorder is dropped
+pub fn start(info: &TaskInfo) -> Recorder {
+    let artifacts = Artifacts {
+        task_id: info.id.clone(),
+        task: serde_json::to_value(info).unwrap_or_default(),
+        samples: Vec::new(),
+        sample_every: 1,
+        sampled: 0,
+        logs: VecDeque::new(),
+        histograms: Map::new(),
+        files: Vec::new(),
+    };
+    {
+        let mut store = STORE.lock().unwrap_or_else(|e| e.into_inner());
+        if store.len() >= MAX_TASKS {
+            if let Some(dropped) = store.pop_front() {
+                for (_, path) in &dropped.files {
+                    remove_temp_file(path);
+                }
+            }
+        }
+        store.push_back(artifacts);
+    }
+
+    let task_id = info.id.clone();
+    let sampler = tokio::spawn(async move {
+        let mut tick = tokio::time::interval(SAMPLE_INTERVAL);
+        loop {
+            tick.tick().await;
+            let running = thread_manager::task_infos(&GLOBAL_REGISTRY).len();
+            let host = metrics::sample(running);
+            let row = format!(
+                "{:.3},{:.1},{},{},{:.2},{:.2},{}\n",
+                host.timestamp, host.cpu_percent, host.memory_used_mb, host.memory_total_mb,
+                host.disk_read_mb_s, host.disk_write_mb_s, host.running_tasks
+            );
+            let kept = with_task(&task_id, |artifacts| {
+                if artifacts.sampled % artifacts.sample_every == 0 {
+                    artifacts.samples.push(row);
+                    if artifacts.samples.len() >= MAX_SAMPLES {
+                        let mut index = 0;
+                        artifacts.samples.retain(|_| { index += 1; index % 2 == 1 });
+                        artifacts.sample_every *= 2;
+                    }
+                }
+                artifacts.sampled += 1;
+            });
+            if kept.is_none() {
+                break;
+            }
+        }
+    });
+    Recorder { sampler: sampler.abort_handle() }
+}
+
+/// Keep a log line of a task that is being recorded
+pub fn log(line: &LogLine) {
+    with_task(&line.task_id, |artifacts| {
+        if artifacts.logs.len() >= MAX_LOG_LINES {
+            artifacts.logs.pop_front();
+        }
+        let time = chrono::DateTime::from_timestamp_micros((line.timestamp * 1e6) as i64).unwrap_or_default();
+        artifacts.logs.push_back(format!("{} {}", time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true), line.message));
+    });
+}
+
+/// Keep the buckets of a task's histogram under `name`, e.g. `iteration_times`
+pub fn record_histogram(task_id: &str, name: &str, histogram: &Histogram) {
+    if histogram.count() == 0 {
+        return;
+    }
+    let buckets: Vec<Value> = histogram
+        .buckets()
+        .map(|(low, width, count)| json!({ "low_ns": low, "high_ns": low + width, "count": count }))
+        .collect();
+    with_task(task_id, |artifacts| {
+        artifacts.histograms.insert(name.to_string(), json!({ "count": histogram.count(), "buckets": buckets }));
+    });
+}
+
+/// Drop the artifacts of tasks `drop` selects, with their kept files, unless the task is
+/// still running; returns how many tasks' artifacts went
+pub fn prune(drop: impl Fn(&str) -> bool) -> usize {
+    let running = thread_manager::list_tasks(&GLOBAL_REGISTRY);
+    let mut store = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    let before = store.len();
+    store.retain(|artifacts| {
+        if running.contains(&artifacts.task_id) || !drop(&artifacts.task_id) {
+            return true;
+        }
+        for (_, path) in &artifacts.files {
+            remove_temp_file(path);
+        }
+        false
+    });
+    before - store.len()
+}
+
+/// Keep a test file for the task's artifacts instead of deleting it; false if the task is
+/// not recorded (the caller deletes the file then)
+///
+/// The file is renamed in place, so the next test can use the same name.
+pub fn keep_file(task_id: &str, path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+        return false;
+    };
+    let kept = path.with_file_name(format!("mogwai_artifact_{}_{}", task_id.replace([':', '/'], "_"), name));
+    let moved = with_task(task_id, |artifacts| {
+        if std::fs::rename(path, &kept).is_err() {
+            return false;
+        }
+        artifacts.files.push((format!("files/{}", name), kept.clone()));
+        true
+    });
+    if moved == Some(true) {
+        untrack_temp_file(path);
+        track_temp_file(&kept); // Still removed on shutdown
+        return true;
+    }
+    false
+}
+
+/// The task's artifacts as a gzip-compressed tarball, written in the background; None if
+/// the task is unknown or its artifacts were dropped
+pub fn archive(task_id: &str) -> Option<mpsc::Receiver<io::Result<Vec<u8>>>> {
+    let artifacts = with_task(task_id, |artifacts| artifacts.clone())?;
+    let result = thread_manager::get_result(task_id);
+    let (sender, receiver) = mpsc::channel(8);
+    tokio::task::spawn_blocking(move || {
+        let chunks = Chunks { sender: sender.clone() };
+        let writer = GzEncoder::new(BufWriter::with_capacity(CHUNK_SIZE, chunks), Compression::default());
+        let written = write_tar(writer, &artifacts, result.map(|result| serde_json::to_value(result).unwrap_or_default()));
+        if let Err(e) = written {
+            // A client that went away is not worth a log line
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                tracing::warn!(task_id = %artifacts.task_id, error = %e, "Failed to write artifacts");
+                let _ = sender.blocking_send(Err(e));
+            }
+        }
+    });
+    Some(receiver)
+}
+
+/// Passes written bytes on to the response
+struct Chunks {
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+}
+
+impl Write for Chunks {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .blocking_send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn write_tar(mut out: GzEncoder<BufWriter<Chunks>>, artifacts: &Artifacts, result: Option<Value>) -> io::Result<()> {
+    let dir = artifacts.task_id.replace('/', "_");
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let entry = |out: &mut GzEncoder<BufWriter<Chunks>>, name: &str, data: &[u8]| {
+        out.write_all(&header(&format!("{}/{}", dir, name), data.len() as u64, now)?)?;
+        out.write_all(data)?;
+        pad(out, data.len() as u64)
+    };
+
+    entry(&mut out, "task.json", &serde_json::to_vec_pretty(&artifacts.task).unwrap_or_default())?;
+    if let Some(result) = result {
+        entry(&mut out, "result.json", &serde_json::to_vec_pretty(&result).unwrap_or_default())?;
+    }
+    let samples: String = std::iter::once(SAMPLES_HEADER).chain(artifacts.samples.iter().map(String::as_str)).collect();
+    entry(&mut out, "samples.csv", samples.as_bytes())?;
+    let logs: String = artifacts.logs.iter().map(|line| format!("{}\n", line)).collect();
+    entry(&mut out, "logs.txt", logs.as_bytes())?;
+    if !artifacts.histograms.is_empty() {
+        entry(&mut out, "histograms.json", &serde_json::to_vec_pretty(&artifacts.histograms).unwrap_or_default())?;
+    }
+    for (name, path) in &artifacts.files {
+        let Ok(file) = File::open(path) else {
+            continue;
+        };
+        let size = file.metadata()?.len();
+        out.write_all(&header(&format!("{}/{}", dir, name), size, now)?)?;
+        // Exactly the announced size, even if the file changed meanwhile
+        let copied = io::copy(&mut file.take(size), &mut out)?;
+        io::copy(&mut io::repeat(0).take(size - copied), &mut out)?;
+        pad(&mut out, size)?;
+    }
+
+    // Two empty blocks end the archive
+    out.write_all(&[0; 1024])?;
+    out.finish()?.flush()
+}
+
+/// Zeros up to the next 512-byte block
+fn pad(out: &mut impl Write, size: u64) -> io::Result<()> {
+    let rest = (512 - size % 512) % 512;
+    out.write_all(&vec![0; rest as usize])
+}
+
+/// A ustar header for a regular file
+fn header(path: &str, size: u64, mtime: u64) -> io::Result<[u8; 512]> {
+    let mut block = [0u8; 512];
+    // Long paths are split into prefix and name at a slash
+    let (prefix, name) = match path.len() {
+        0..=100 => ("", path),
+        _ => path
+            .char_indices()
+            .filter(|&(index, c)| c == '/' && index <= 155 && path.len() - index - 1 <= 100)
+            .map(|(index, _)| (&path[..index], &path[index + 1..]))
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("path too long for a tarball: {}", path)))?,
+    };
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut block[100..108], 0o644);
+    octal(&mut block[108..116], 0);
+    octal(&mut block[116..124], 0);
+    if size < 0o77777777777 {
+        octal(&mut block[124..136], size);
+    } else {
+        // GNU base-256 for files of 8 GiB and more
+        block[124] = 0x80;
+        block[128..136].copy_from_slice(&size.to_be_bytes());
+    }
+    octal(&mut block[136..148], mtime);
+    block[156] = b'0';
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+    // The checksum is computed with its own field as spaces
+    block[148..156].fill(b' ');
+    let checksum: u32 = block.iter().map(|&byte| byte as u32).sum();
+    block[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(block)
+}
+
+/// Zero-padded octal digits and a NUL filling the field
+fn octal(field: &mut [u8], value: u64) {
+    let text = format!("{:0width$o}\0", value, width = field.len() - 1);
+    field.copy_from_slice(text.as_bytes());
+}
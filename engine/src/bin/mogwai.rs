@@ -0,0 +1,750 @@
+//! Standalone stress runner
+//!
+//! Runs the engine's stress tests on this machine, without the HTTP server, through
+//! the same library code the engine uses, so `--intensity` means the same thread count
+//! here as in an engine request and the results are comparable. Several test types
+//! (`cpu,mem`) run at the same time with the same parameters, for combined pressure.
+//! Ctrl-C stops the tests early and removes their test files.
+//!
+//! `--scenario <file>` runs the steps of a YAML file one after another instead; each
+//! step runs its tests together with its own parameters and may pause afterwards:
+//!
+//! ```yaml
+//! steps:
+//!   - name: warm-up
+//!     tests: [cpu]
+//!     load: 50
+//!     duration: 60
+//!     pause: 10          # seconds to wait after the step
+//!   - tests: [mem, disk]
+//!     intensity: 2
+//!     size: 1024
+//!     duration: 300
+//! ```
+//!
+//! `mogwai serve` runs the engine's HTTP API instead, so a bare-metal host outside
+//! Kubernetes can be driven by the CLI, GUI and controller with just this binary.
+//!
+//! `--output json` prints one result document (parameters, results, memory before and
+//! after) instead of the text summary, for the same tooling that reads engine results;
+//! the tests' own output lines then go to stderr.
+//!
+//! `--junit <file>` writes a JUnit XML report for CI test report views: one test suite
+//! per step and one test case per test type, failed when the test did not complete or
+//! missed a threshold (`--min-load`, `--min-mb-per-s`, `--max-oom-kills`, also settable
+//! per scenario step). Missed thresholds make the runner exit with 1 like failed tests.
+//!
+//! running: cargo run --bin mogwai -- disk --intensity 4 --size 512 --duration 30
+//!          cargo run --bin mogwai -- cpu,mem --intensity 4 --size 512 --duration 60
+//!          cargo run --bin mogwai -- cpu --intensity 2 --load 70 --output json --output-file cpu.json
+//!          cargo run --bin mogwai -- --scenario burn-in.yaml
+//!          cargo run --bin mogwai -- cpu --load 80 --min-load 75 --junit junit.xml
+//!          cargo run --bin mogwai -- serve --bind 0.0.0.0:8080
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+use mogwai_stress::codec_stress::DEFAULT_ZSTD_LEVEL;
+use mogwai_stress::{cgroup, disk_stress, junit, logging, metrics, s3, server, thread_manager};
+use mogwai_stress::{Backing, Codec, CodecStress, CpuStress, DiskStress, FaultStress, ForkStress, LockStress, MemoryStress};
+use mogwai_stress::{Primitive, StressError, StressResult, Syscall, SyscallStress, Touch};
+
+#[derive(Parser)]
+#[command(name = "mogwai", about = "Run stress tests on this machine")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Tests to run at the same time (e.g. cpu,mem)
+    #[arg(value_enum, value_delimiter = ',', required_unless_present = "scenario", conflicts_with = "scenario")]
+    tests: Vec<TestType>,
+
+    #[command(flatten)]
+    params: Params,
+
+    #[command(flatten)]
+    thresholds: Thresholds,
+
+    /// Run the steps of a YAML scenario file instead
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+
+    /// Print a text summary or one JSON result document
+    #[arg(long, value_enum, default_value_t = Output::Text)]
+    output: Output,
+
+    /// Also write the JSON result document to this file
+    #[arg(long)]
+    output_file: Option<PathBuf>,
+
+    /// Write a JUnit XML report to this file
+    #[arg(long)]
+    junit: Option<PathBuf>,
+
+    /// Also upload the JSON result document to the bucket set by MOGWAI_S3_BUCKET
+    #[arg(long)]
+    upload: bool,
+
+    /// Batch the uploaded document is filed under (default: run-<start time>)
+    #[arg(long, requires = "upload")]
+    batch: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the engine's HTTP API, like the engine binary
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "0.0.0.0:8080")]
+        bind: String,
+    },
+}
+
+/// Test parameters, from the command line or a scenario step
+#[derive(clap::Args, Serialize, Deserialize, Clone)]
+#[serde(default)]
+struct Params {
+    /// Worker threads (cpu, mem, disk, compress, crypto, lock, pagefault, syscall) or child processes (fork)
+    #[arg(long, default_value_t = 4)]
+    intensity: usize,
+
+    /// Duration in seconds (0 = until Ctrl-C)
+    #[arg(long, default_value_t = 10)]
+    duration: u64,
+
+    /// Target load per thread in percent (cpu; default: busy loop)
+    #[arg(long)]
+    load: Option<f64>,
+
+    /// Megabytes per thread (mem, disk) or mapped per cycle (pagefault)
+    #[arg(long, default_value_t = 256)]
+    size: usize,
+
+    /// How much of each page every pass writes (mem)
+    #[arg(long, value_enum, default_value_t = MemoryTouch::Page)]
+    touch: MemoryTouch,
+
+    /// Bypass the page cache with O_DIRECT (disk)
+    #[arg(long)]
+    direct: bool,
+
+    /// Check the data read back (disk)
+    #[arg(long)]
+    verify: bool,
+
+    /// Do all I/O in chunks of this many KiB with reused buffers; changes how MB/s is measured (disk)
+    #[arg(long)]
+    chunk_kb: Option<usize>,
+
+    /// Target directory; repeat to spread the threads over several (disk, pagefault with file backing)
+    #[arg(long = "path")]
+    paths: Vec<PathBuf>,
+
+    /// zstd compression level 1-19 (compress)
+    #[arg(long, default_value_t = DEFAULT_ZSTD_LEVEL)]
+    zstd_level: i32,
+
+    /// Algorithm (crypto)
+    #[arg(long, value_enum, default_value_t = CryptoAlgorithm::AesGcm)]
+    algorithm: CryptoAlgorithm,
+
+    /// Lock the threads contend on (lock)
+    #[arg(long, value_enum, default_value_t = LockPrimitive::Mutex)]
+    primitive: LockPrimitive,
+
+    /// Shared counters, each behind its own lock (lock)
+    #[arg(long, default_value_t = 1)]
+    counters: usize,
+
+    /// Microseconds each acquisition holds the lock (lock)
+    #[arg(long, default_value_t = 1)]
+    hold_us: u64,
+
+    /// Map test files dropped from the page cache, for major faults (pagefault; default anonymous memory)
+    #[arg(long)]
+    file_backed: bool,
+
+    /// Calls to issue, equally often, e.g. getpid,write (syscall; default all of them)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    syscalls: Vec<SyscallName>,
+}
+
+// Same defaults as the command line, for fields a scenario step leaves out
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            intensity: 4,
+            duration: 10,
+            load: None,
+            size: 256,
+            touch: MemoryTouch::Page,
+            direct: false,
+            verify: false,
+            chunk_kb: None,
+            paths: Vec::new(),
+            zstd_level: DEFAULT_ZSTD_LEVEL,
+            algorithm: CryptoAlgorithm::AesGcm,
+            primitive: LockPrimitive::Mutex,
+            counters: 1,
+            hold_us: 1,
+            file_backed: false,
+            syscalls: Vec::new(),
+        }
+    }
+}
+
+/// Pass criteria beyond completing, from the command line or a scenario step
+#[derive(clap::Args, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+struct Thresholds {
+    /// Fail cpu tests whose achieved load stays below this percent
+    #[arg(long)]
+    min_load: Option<f64>,
+
+    /// Fail disk tests with less throughput (MB/s)
+    #[arg(long)]
+    min_mb_per_s: Option<f64>,
+
+    /// Fail mem tests with more kernel OOM kills
+    #[arg(long)]
+    max_oom_kills: Option<u64>,
+}
+
+impl Thresholds {
+    /// The thresholds a result misses; metrics the test does not report are not checked
+    fn missed(&self, result: &StressResult) -> Vec<String> {
+        let mut missed = Vec::new();
+        if let (Some(min), Some(load)) = (self.min_load, result.achieved_load) {
+            if load < min {
+                missed.push(format!("achieved load {:.1}% is below the minimum of {}%", load, min));
+            }
+        }
+        if let (Some(min), Some(mb_per_s)) = (self.min_mb_per_s, result.mb_per_s) {
+            if mb_per_s < min {
+                missed.push(format!("throughput {:.1} MB/s is below the minimum of {} MB/s", mb_per_s, min));
+            }
+        }
+        if let (Some(max), Some(oom_kills)) = (self.max_oom_kills, result.oom_kills) {
+            if oom_kills > max {
+                missed.push(format!("{} OOM kills exceed the maximum of {}", oom_kills, max));
+            }
+        }
+        missed
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Output {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TestType {
+    Cpu,
+    Mem,
+    Disk,
+    Fork,
+    Compress,
+    Crypto,
+    Lock,
+    Pagefault,
+    Syscall,
+}
+
+#[derive(Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum MemoryTouch {
+    Page, // One byte per page
+    Line, // Every byte, whole cache lines at a time
+}
+
+#[derive(Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum CryptoAlgorithm {
+    AesGcm,
+    Sha256,
+}
+
+#[derive(Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SyscallName {
+    Getpid,
+    #[value(name = "clock_gettime")]
+    ClockGettime,
+    Read,
+    Write,
+}
+
+#[derive(Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LockPrimitive {
+    Mutex,
+    Rwlock,
+}
+
+/// Contents of a scenario file
+#[derive(Deserialize)]
+struct Scenario {
+    steps: Vec<Step>,
+}
+
+/// One step of a scenario: tests run together, then an optional pause
+#[derive(Deserialize)]
+struct Step {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    tests: Vec<TestType>,  // May be empty for a pause-only step
+    #[serde(default)]
+    pause: u64,            // Seconds to wait after the tests
+    #[serde(flatten)]
+    params: Params,
+    #[serde(flatten)]
+    thresholds: Thresholds,
+}
+
+/// What `--output json` prints
+#[derive(Serialize)]
+struct Report<'a> {
+    scenario: Option<String>,          // Path of the scenario file, if one was run
+    started_at: String,                // RFC3339 local time
+    hostname: String,
+    steps: Vec<StepReport<'a>>,        // One for a plain run; the steps that ran for a scenario
+    memory_before_mb: u64,             // Machine-wide used memory before the first test
+    memory_after_mb: u64,              // Machine-wide used memory right after the last one
+    memory_total_mb: u64,
+}
+
+/// Outcome of one step
+#[derive(Serialize)]
+struct StepReport<'a> {
+    name: Option<&'a str>,
+    parameters: &'a Params,
+    tests: Vec<TestReport<'a>>,        // In the order given
+}
+
+/// Outcome of one test type of a step
+#[derive(Serialize)]
+struct TestReport<'a> {
+    test: TestType,
+    result: Option<&'a StressResult>,  // Set when the test ran
+    error: Option<&'a StressError>,    // Set when it failed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missed_thresholds: Vec<String>,
+}
+
+/// Tests of a step with their outcomes
+type Outcomes = Vec<(TestType, Result<StressResult, StressError>)>;
+
+#[tokio::main]
+async fn main() {
+    // Isolated tasks of `serve` re-run this binary inside their own cgroup
+    let argv: Vec<String> = std::env::args().collect();
+    if argv.len() == 3 && argv[1] == cgroup::CHILD_FLAG {
+        if let Err(e) = cgroup::run_child(Path::new(&argv[2])).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let args = Args::parse();
+    let upload = match args.upload.then(s3::Config::from_env) {
+        Some(None) => {
+            eprintln!("--upload needs MOGWAI_S3_BUCKET and S3 credentials");
+            std::process::exit(1);
+        }
+        upload => upload.flatten(),
+    };
+    if let Some(Command::Serve { bind }) = &args.command {
+        if let Err(e) = server::serve(bind).await {
+            eprintln!("Failed to serve on {}: {}", bind, e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let steps = match &args.scenario {
+        Some(path) => match load_scenario(path) {
+            Ok(steps) => steps,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => vec![Step {
+            name: None,
+            tests: args.tests.clone(),
+            pause: 0,
+            params: args.params.clone(),
+            thresholds: args.thresholds.clone(),
+        }],
+    };
+    // Task output goes to stderr when stdout carries the JSON document
+    logging::init_bare(args.output == Output::Json);
+
+    // Ctrl-C (or SIGTERM) ends the tests like a stop request ends an engine task
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stop_flag);
+    tokio::spawn(async move {
+        thread_manager::shutdown_signal().await;
+        eprintln!("Stopping...");
+        flag.store(true, Ordering::SeqCst);
+    });
+
+    let started = chrono::Local::now();
+    let started_at = started.to_rfc3339();
+    let before = metrics::sample(0);
+    let mut done: Vec<(&Step, Outcomes)> = Vec::new();
+    for (number, step) in steps.iter().enumerate() {
+        if stop_flag.load(Ordering::SeqCst) {
+            break;
+        }
+        if args.scenario.is_some() {
+            eprintln!("Step {}/{}{}", number + 1, steps.len(), step.name.as_deref().map(|n| format!(": {}", n)).unwrap_or_default());
+        }
+        let outcomes = run_step(step, Arc::clone(&stop_flag)).await;
+        done.push((step, outcomes));
+        pause(step.pause, &stop_flag).await;
+    }
+    let after = metrics::sample(0);
+    disk_stress::remove_temp_files();
+
+    if args.output == Output::Json || args.output_file.is_some() || upload.is_some() {
+        let report = Report {
+            scenario: args.scenario.as_ref().map(|path| path.display().to_string()),
+            started_at: started_at.clone(),
+            hostname: hostname::get().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            steps: done
+                .iter()
+                .map(|(step, outcomes)| StepReport {
+                    name: step.name.as_deref(),
+                    parameters: &step.params,
+                    tests: outcomes
+                        .iter()
+                        .map(|(test, outcome)| TestReport {
+                            test: *test,
+                            result: outcome.as_ref().ok(),
+                            error: outcome.as_ref().err(),
+                            missed_thresholds: outcome.as_ref().map(|result| step.thresholds.missed(result)).unwrap_or_default(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            memory_before_mb: before.memory_used_mb,
+            memory_after_mb: after.memory_used_mb,
+            memory_total_mb: after.memory_total_mb,
+        };
+        let json = serde_json::to_string_pretty(&report).unwrap_or_default();
+        if let Some(path) = &args.output_file {
+            if let Err(e) = std::fs::write(path, format!("{}\n", json)) {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+            }
+        }
+        if let Some(config) = &upload {
+            let batch = args.batch.clone().unwrap_or_else(|| format!("run-{}", started.format("%Y%m%dT%H%M%S")));
+            let key = config.key(&format!("{}/{}/report.json", batch.replace('/', "_"), metrics::node_name().replace('/', "_")));
+            let client = reqwest::Client::new();
+            match s3::put_object(&client, config, &key, format!("{}\n", json).into_bytes(), "application/json").await {
+                Ok(()) => eprintln!("Uploaded the result document to s3://{}/{}", config.bucket(), key),
+                Err(e) => eprintln!("Failed to upload the result document: {}", e),
+            }
+        }
+        if args.output == Output::Json {
+            println!("{}", json);
+        }
+    }
+
+    if let Some(path) = &args.junit {
+        let xml = junit::render(&junit_suites(&done, &started_at, args.scenario.is_some()));
+        if let Err(e) = std::fs::write(path, xml) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+        }
+    }
+
+    let mut failed = false;
+    for (step, outcomes) in &done {
+        for (test, outcome) in outcomes {
+            match outcome {
+                Ok(result) => {
+                    if args.output == Output::Text {
+                        print_result(*test, result);
+                    }
+                    for missed in step.thresholds.missed(result) {
+                        eprintln!("{}: {}", test.name(), missed);
+                        failed = true;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", test.name(), e);
+                    failed = true;
+                }
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// JUnit suites of the steps that ran: one per step, one case per test type
+fn junit_suites(done: &[(&Step, Outcomes)], started_at: &str, scenario: bool) -> Vec<junit::TestSuite> {
+    let hostname = hostname::get().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    done.iter()
+        .enumerate()
+        .map(|(number, (step, outcomes))| {
+            let name = match (&step.name, scenario) {
+                (Some(name), _) => name.clone(),
+                (None, true) => format!("step {}", number + 1),
+                (None, false) => "mogwai".to_string(),
+            };
+            let cases = outcomes
+                .iter()
+                .map(|(test, outcome)| {
+                    let (time_secs, outcome, output) = match outcome {
+                        Ok(result) => {
+                            let missed = step.thresholds.missed(result);
+                            let outcome = if missed.is_empty() {
+                                junit::CaseOutcome::Passed
+                            } else {
+                                junit::CaseOutcome::Failed(missed)
+                            };
+                            (result.elapsed_secs, outcome, serde_json::to_string_pretty(result).unwrap_or_default())
+                        }
+                        Err(e) => (0.0, junit::CaseOutcome::Error(e.to_string()), String::new()),
+                    };
+                    junit::TestCase {
+                        name: test.name().to_string(),
+                        classname: format!("mogwai.{}", name),
+                        time_secs,
+                        outcome,
+                        output,
+                    }
+                })
+                .collect();
+            junit::TestSuite { name, timestamp: started_at.to_string(), hostname: hostname.clone(), cases }
+        })
+        .collect()
+}
+
+/// Read and check a scenario file
+fn load_scenario(path: &Path) -> Result<Vec<Step>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let scenario: Scenario = serde_yaml::from_str(&text).map_err(|e| format!("Invalid scenario {}: {}", path.display(), e))?;
+    if scenario.steps.is_empty() {
+        return Err(format!("Scenario {} has no steps", path.display()));
+    }
+    // A step that runs until Ctrl-C would keep every later step from running
+    let last = scenario.steps.len() - 1;
+    if let Some(number) = scenario
+        .steps
+        .iter()
+        .position(|step| !step.tests.is_empty() && step.params.duration == 0)
+        .filter(|&number| number < last)
+    {
+        return Err(format!("Step {} runs until stopped (duration 0); only the last step may", number + 1));
+    }
+    Ok(scenario.steps)
+}
+
+/// Run a step's tests at the same time; each test type runs once, since two disk runs
+/// would write the same files
+async fn run_step(step: &Step, stop_flag: Arc<AtomicBool>) -> Outcomes {
+    let mut tests = Vec::new();
+    for &test in &step.tests {
+        if !tests.contains(&test) {
+            tests.push(test);
+        }
+    }
+
+    let params = Arc::new(step.params.clone());
+    let mut runs = tokio::task::JoinSet::new();
+    for (index, &test) in tests.iter().enumerate() {
+        let (params, stop_flag) = (Arc::clone(&params), Arc::clone(&stop_flag));
+        runs.spawn(async move { (index, run(test, &params, stop_flag).await) });
+    }
+    let mut outcomes: Vec<Option<Result<StressResult, StressError>>> = tests.iter().map(|_| None).collect();
+    while let Some(joined) = runs.join_next().await {
+        match joined {
+            Ok((index, outcome)) => outcomes[index] = Some(outcome),
+            Err(e) => eprintln!("Test panicked: {}", e),
+        }
+    }
+    tests
+        .into_iter()
+        .zip(outcomes)
+        .map(|(test, outcome)| {
+            (test, outcome.unwrap_or_else(|| Err(StressError::WorkerPanicked("test task panicked".to_string()))))
+        })
+        .collect()
+}
+
+/// Wait between steps; Ctrl-C cuts the wait short
+async fn pause(seconds: u64, stop_flag: &AtomicBool) {
+    let until = Instant::now() + Duration::from_secs(seconds);
+    while Instant::now() < until && !stop_flag.load(Ordering::SeqCst) {
+        tokio::time::sleep(until.saturating_duration_since(Instant::now()).min(Duration::from_millis(200))).await;
+    }
+}
+
+impl TestType {
+    fn name(self) -> &'static str {
+        match self {
+            TestType::Cpu => "cpu",
+            TestType::Mem => "mem",
+            TestType::Disk => "disk",
+            TestType::Fork => "fork",
+            TestType::Compress => "compress",
+            TestType::Crypto => "crypto",
+            TestType::Lock => "lock",
+            TestType::Pagefault => "pagefault",
+            TestType::Syscall => "syscall",
+        }
+    }
+}
+
+async fn run(test: TestType, params: &Params, stop_flag: Arc<AtomicBool>) -> Result<StressResult, StressError> {
+    let (threads, duration) = (params.intensity, params.duration);
+    match test {
+        TestType::Cpu => {
+            let mut test = CpuStress::new().threads(threads).duration(duration).stop_flag(stop_flag);
+            if let Some(load) = params.load {
+                test = test.load(load);
+            }
+            test.run().await
+        }
+        TestType::Mem => {
+            let touch = match params.touch {
+                MemoryTouch::Page => Touch::Page,
+                MemoryTouch::Line => Touch::Line,
+            };
+            MemoryStress::new()
+                .threads(threads)
+                .duration(duration)
+                .stop_flag(stop_flag)
+                .size_mb(params.size)
+                .touch(touch)
+                .run()
+                .await
+        }
+        TestType::Disk => {
+            let mut test = DiskStress::new()
+                .threads(threads)
+                .duration(duration)
+                .stop_flag(stop_flag)
+                .size_mb(params.size)
+                .direct(params.direct)
+                .verify(params.verify)
+                .paths(params.paths.clone());
+            if let Some(chunk_kb) = params.chunk_kb {
+                test = test.chunked(chunk_kb);
+            }
+            test.run().await
+        }
+        TestType::Fork => ForkStress::new().threads(threads).duration(duration).stop_flag(stop_flag).run().await,
+        TestType::Compress | TestType::Crypto => {
+            let codec = match (test, params.algorithm) {
+                (TestType::Compress, _) => Codec::Zstd { level: params.zstd_level },
+                (_, CryptoAlgorithm::AesGcm) => Codec::AesGcm,
+                (_, CryptoAlgorithm::Sha256) => Codec::Sha256,
+            };
+            CodecStress::new(codec).threads(threads).duration(duration).stop_flag(stop_flag).run().await
+        }
+        TestType::Lock => {
+            let primitive = match params.primitive {
+                LockPrimitive::Mutex => Primitive::Mutex,
+                LockPrimitive::Rwlock => Primitive::RwLock,
+            };
+            LockStress::new()
+                .threads(threads)
+                .duration(duration)
+                .stop_flag(stop_flag)
+                .primitive(primitive)
+                .counters(params.counters)
+                .hold(Duration::from_micros(params.hold_us))
+                .run()
+                .await
+        }
+        TestType::Pagefault => FaultStress::new()
+            .threads(threads)
+            .duration(duration)
+            .stop_flag(stop_flag)
+            .region_mb(params.size)
+            .backing(if params.file_backed { Backing::File } else { Backing::Anonymous })
+            .paths(params.paths.clone())
+            .run()
+            .await,
+        TestType::Syscall => {
+            let mut test = SyscallStress::new().threads(threads).duration(duration).stop_flag(stop_flag);
+            if !params.syscalls.is_empty() {
+                test = test.mix(params.syscalls.iter().map(|name| {
+                    let syscall = match name {
+                        SyscallName::Getpid => Syscall::Getpid,
+                        SyscallName::ClockGettime => Syscall::ClockGettime,
+                        SyscallName::Read => Syscall::Read,
+                        SyscallName::Write => Syscall::Write,
+                    };
+                    (syscall, 1)
+                }));
+            }
+            test.run().await
+        }
+    }
+}
+
+/// Print the result fields the test filled in
+fn print_result(test: TestType, result: &StressResult) {
+    println!("\nResult ({})", test.name());
+    println!("  elapsed:       {:.1} s{}", result.elapsed_secs, if result.stopped { " (stopped)" } else { "" });
+    println!("  iterations:    {}", result.iterations);
+    if let Some(load) = result.achieved_load {
+        println!("  achieved load: {:.1}%", load);
+    }
+    if let Some(load) = result.cpu_load {
+        println!("  CPU time:      {:.1}%", load);
+    }
+    for thread in &result.thread_loads {
+        println!("  thread {}:      {:.1}% busy, {:.1}% CPU time", thread.thread, thread.busy_percent, thread.cpu_percent);
+    }
+    if let Some(mb_per_s) = result.mb_per_s {
+        println!("  throughput:    {:.1} MB/s", mb_per_s);
+    }
+    if let Some(oom_kills) = result.oom_kills {
+        println!("  OOM kills:     {}", oom_kills);
+    }
+    if let Some(corrupt) = result.corrupt_blocks {
+        println!("  corrupt:       {} blocks", corrupt);
+    }
+    if let Some(lock) = &result.lock {
+        println!("  acquisitions:  {:.0}/s, {:.1}% contended", lock.acquisitions_per_s, lock.contended_percent);
+        println!(
+            "  wait:          p50 {:.2} us, p99 {:.2} us, p99.9 {:.2} us, max {:.2} us",
+            lock.wait.p50_us, lock.wait.p99_us, lock.wait.p999_us, lock.wait.max_us
+        );
+    }
+    if let Some(faults) = &result.faults {
+        println!("  page faults:   {:.0} minor/s, {:.0} major/s", faults.minor_per_s, faults.major_per_s);
+    }
+    if let Some(syscalls) = &result.syscalls {
+        println!("  syscalls:      {:.0} calls/s, {:.0} ns per call", syscalls.calls_per_s, syscalls.ns_per_call);
+        for thread in &syscalls.threads {
+            println!("  thread {}:      {:.0} calls/s", thread.thread, thread.calls_per_s);
+        }
+    }
+    if let Some(times) = &result.iteration_times {
+        println!(
+            "  iteration:     p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+            times.p50_us / 1000.0, times.p95_us / 1000.0, times.p99_us / 1000.0, times.max_us / 1000.0
+        );
+    }
+    for path in &result.paths {
+        println!("  {}: {:.1} MB/s over {} threads", path.path, path.mb_per_s, path.threads);
+    }
+    for error in &result.errors {
+        println!("  error:         {}", error);
+    }
+}
@@ -0,0 +1,108 @@
+//! System Hardware Information Reporter
+//!
+//! Prints the hardware report of this machine in the chosen format (JSON by default,
+//! for piping). "human" prints the readable report; "save" also writes it to a file;
+//! "bench" appends baseline micro-benchmark scores (takes a few seconds);
+//! "calibrate" maps intensity levels 1-10 onto this machine and stores the mapping.
+//!
+//! running: cargo run --bin sys_info | python3 ../cli/src/mogAI.py
+//!          cargo run --bin sys_info -- --format prometheus > /var/lib/node_exporter/mogwai.prom
+
+use chrono::prelude::*;
+use clap::{Parser, Subcommand};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use mogwai_stress::calibration;
+use mogwai_stress::sys_info::{bench, gather_system_info, render, ReportFormat};
+
+#[derive(Parser)]
+#[command(name = "sys_info", about = "Report this machine's hardware")]
+struct Args {
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = ReportFormat::Json)]
+    format: ReportFormat,
+
+    #[command(subcommand)]
+    mode: Option<Mode>,
+}
+
+#[derive(Subcommand)]
+enum Mode {
+    /// Print the human-readable report (same as --format human)
+    Human,
+    /// Print the human-readable report and save the report in --format to a file
+    Save,
+    /// Run a few seconds of CPU, memory and disk micro-benchmarks and append the scores
+    Bench {
+        /// Directory for the temporary disk benchmark file
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Map intensity levels 1-10 onto this machine (about 6 seconds) and store the mapping for the engine
+    Calibrate {
+        /// Directory for the temporary disk benchmark file
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    // Calibration prints its own JSON and needs no hardware report
+    if let Some(Mode::Calibrate { dir }) = &args.mode {
+        eprintln!("Calibrating intensity levels (about 6 seconds)...");
+        let result = calibration::calibrate(dir);
+        calibration::save(&result)?;
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        eprintln!("Calibration saved to {}", calibration::path().display());
+        return Ok(());
+    }
+
+    // Gather all system information
+    let mut info = gather_system_info();
+
+    match args.mode {
+        Some(Mode::Human) => println!("{}", render(&info, ReportFormat::Human)?),
+        Some(Mode::Save) => {
+            // Print human-readable report
+            println!("{}", render(&info, ReportFormat::Human)?);
+
+            // Save in the requested format
+            let filename = format!(
+                "system_info_{}_{}.{}",
+                info.system.hostname,
+                Utc::now().format("%Y%m%d_%H%M%S"),
+                args.format.extension()
+            );
+
+            let mut file = File::create(&filename)?;
+            let report = match args.format {
+                ReportFormat::Json => serde_json::to_string_pretty(&info)?,
+                format => render(&info, format)?,
+            };
+            file.write_all(report.as_bytes())?;
+
+            println!("\nDetailed report saved to {}", filename);
+        }
+        Some(Mode::Bench { dir }) => {
+            eprintln!("Running baseline benchmarks (about 6 seconds)...");
+            info.bench = Some(bench::run(&dir));
+            print!("{}", with_newline(render(&info, args.format)?));
+        }
+        Some(Mode::Calibrate { .. }) => unreachable!("handled above"),
+        // Default: JSON for piping, or the requested format
+        None => print!("{}", with_newline(render(&info, args.format)?)),
+    }
+
+    Ok(())
+}
+
+/// Make sure the output ends with a newline
+fn with_newline(mut text: String) -> String {
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
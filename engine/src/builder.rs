@@ -0,0 +1,529 @@
+//! Builder-style API for embedding load generation
+//!
+//! Other Rust services can run the engine's stress tests in-process instead of
+//! spawning the binaries or talking to the REST API:
+//!
+//! ```no_run
+//! # async fn example() {
+//! use mogwai_stress::CpuStress;
+//!
+//! match CpuStress::new().threads(8).load(70.0).duration(60).run().await {
+//!     Ok(result) => println!("{:.1}% load for {:.1}s", result.achieved_load.unwrap_or(0.0), result.elapsed_secs),
+//!     Err(e) => eprintln!("{}", e),
+//! }
+//! # }
+//! ```
+//!
+//! A duration of 0 runs until the stop flag (see `stop_flag`) is set. Output lines
+//! go through `logs::emit` under the run's task id, like engine tasks.
+
+use std::path::PathBuf;
+use std::sync::{Arc, atomic::AtomicBool};
+use std::time::Duration;
+
+use crate::capabilities::{self, Capability};
+use crate::codec_stress::{self, Codec};
+use crate::disk_stress::DiskOptions;
+use crate::fault_stress::{self, Backing, FaultOptions};
+use crate::lock_stress::{self, LockOptions, Primitive};
+use crate::memory_stress::{Advice, Balloon, MemoryOptions, Touch};
+use crate::result::{StressError, StressResult};
+use crate::syscall_stress::{self, Syscall, SyscallOptions};
+use crate::{cpu_stress, disk_stress, logs, fork_stress, memory_stress, thread_manager};
+
+/// Settings shared by every builder
+#[derive(Debug, Clone)]
+struct Common {
+    threads: usize,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: Option<String>,
+}
+
+impl Common {
+    fn new() -> Self {
+        Common {
+            threads: 4,
+            duration: 10,
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            task_id: None,
+        }
+    }
+
+    /// Task id for log lines (generated with the test type prefix unless set)
+    fn task_id(&self, prefix: &str) -> String {
+        self.task_id.clone().unwrap_or_else(|| thread_manager::generate_task_id(prefix))
+    }
+}
+
+/// Setters every builder has
+macro_rules! common_setters {
+    () => {
+        /// Number of worker threads (default 4)
+        pub fn threads(mut self, threads: usize) -> Self {
+            self.common.threads = threads;
+            self
+        }
+
+        /// Duration in seconds (default 10, 0 = until stopped)
+        pub fn duration(mut self, seconds: u64) -> Self {
+            self.common.duration = seconds;
+            self
+        }
+
+        /// Share a stop flag; setting it to true ends the run early
+        pub fn stop_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+            self.common.stop_flag = flag;
+            self
+        }
+
+        /// Task id used to label log lines
+        pub fn task_id(mut self, id: impl Into<String>) -> Self {
+            self.common.task_id = Some(id.into());
+            self
+        }
+
+        /// Stop flag of this run, for stopping it from another task
+        pub fn stop_handle(&self) -> Arc<AtomicBool> {
+            Arc::clone(&self.common.stop_flag)
+        }
+    };
+}
+
+/// CPU stress: busy loops, or time-sliced work when a target load is set
+#[derive(Debug, Clone)]
+pub struct CpuStress {
+    common: Common,
+    load: Option<f64>,
+    system_load: Option<f64>,
+}
+
+impl CpuStress {
+    pub fn new() -> Self {
+        CpuStress { common: Common::new(), load: None, system_load: None }
+    }
+
+    common_setters!();
+
+    /// Target load per thread in percent (default: 100% busy loop)
+    pub fn load(mut self, percent: f64) -> Self {
+        self.load = Some(percent);
+        self
+    }
+
+    /// Hold node-wide CPU utilization at this percentage instead (closed loop, replaces `load`)
+    pub fn system_load(mut self, percent: f64) -> Self {
+        self.system_load = Some(percent);
+        self
+    }
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        let task_id = self.common.task_id("cpu");
+        if let Some(target) = self.system_load {
+            return cpu_stress::stress_cpu_closed_loop(
+                self.common.threads,
+                target,
+                self.common.duration,
+                Arc::clone(&self.common.stop_flag),
+                task_id,
+            )
+            .await;
+        }
+        cpu_stress::stress_cpu(
+            self.common.threads,
+            self.load.unwrap_or(100.0),
+            self.common.duration,
+            self.load.is_some(),
+            self.common.duration == 0,
+            Arc::clone(&self.common.stop_flag),
+            task_id,
+        )
+        .await
+    }
+}
+
+impl Default for CpuStress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Memory stress: each thread allocates and keeps touching a block
+#[derive(Debug, Clone)]
+pub struct MemoryStress {
+    common: Common,
+    size_mb: usize,
+    options: MemoryOptions,
+}
+
+impl MemoryStress {
+    pub fn new() -> Self {
+        MemoryStress { common: Common::new(), size_mb: 256, options: MemoryOptions::default() }
+    }
+
+    common_setters!();
+
+    /// Megabytes allocated per thread (default 256)
+    pub fn size_mb(mut self, size_mb: usize) -> Self {
+        self.size_mb = size_mb;
+        self
+    }
+
+    /// Lock each thread's memory into RAM (needs CAP_IPC_LOCK or a large RLIMIT_MEMLOCK)
+    pub fn lock(mut self, lock: bool) -> Self {
+        self.options.lock = lock;
+        self
+    }
+
+    /// Back each thread's memory with reserved huge pages (needs vm.nr_hugepages)
+    pub fn hugepages(mut self, hugepages: bool) -> Self {
+        self.options.hugepages = hugepages;
+        self
+    }
+
+    /// Grow each thread's allocation from `floor_mb` to `size_mb` and release it back,
+    /// once every `period_secs`, instead of holding a constant block
+    pub fn balloon(mut self, floor_mb: usize, period_secs: f64) -> Self {
+        self.options.balloon = Some(Balloon { floor_mb, period_secs });
+        self
+    }
+
+    /// How much of each page every pass writes (default: one byte per page)
+    pub fn touch(mut self, touch: Touch) -> Self {
+        self.options.touch = touch;
+        self
+    }
+
+    /// Transparent huge page hint for each thread's memory
+    pub fn madvise(mut self, advice: Advice) -> Self {
+        self.options.advice = Some(advice);
+        self
+    }
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        if self.options.lock {
+            capabilities::require(Capability::Mlock)?;
+        }
+        if self.options.hugepages {
+            capabilities::require(Capability::Hugepages)?;
+        }
+        let task_id = self.common.task_id("mem");
+        memory_stress::stress_memory_with(
+            self.common.threads,
+            self.size_mb,
+            self.common.duration,
+            Arc::clone(&self.common.stop_flag),
+            task_id,
+            self.options,
+        )
+        .await
+    }
+}
+
+impl Default for MemoryStress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Disk stress: each thread writes and reads back its own file
+#[derive(Debug, Clone)]
+pub struct DiskStress {
+    common: Common,
+    size_mb: usize,
+    options: DiskOptions,
+}
+
+impl DiskStress {
+    pub fn new() -> Self {
+        DiskStress { common: Common::new(), size_mb: 256, options: DiskOptions::default() }
+    }
+
+    common_setters!();
+
+    /// Size of each thread's file in megabytes (default 256)
+    pub fn size_mb(mut self, size_mb: usize) -> Self {
+        self.size_mb = size_mb;
+        self
+    }
+
+    /// Bypass the page cache with O_DIRECT (the working directory's filesystem must support it)
+    pub fn direct(mut self, direct: bool) -> Self {
+        self.options.direct = direct;
+        self
+    }
+
+    /// Spread the threads round-robin over these directories (default: working directory);
+    /// the result reports each one's throughput in `paths`
+    pub fn paths<P: Into<PathBuf>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.options.paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Write per-block patterns and check them on read; mismatches are counted in
+    /// `corrupt_blocks` (combine with `direct` so reads come from the device)
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.options.verify = verify;
+        self
+    }
+
+    /// Reuse one read buffer per thread and do all I/O in chunks of `chunk_kb` KiB (a
+    /// multiple of 4), without pauses between cycles; `mb_per_s` then counts only the time
+    /// spent in I/O, so its numbers are not comparable with whole-file runs
+    pub fn chunked(mut self, chunk_kb: usize) -> Self {
+        self.options.chunk_kb = Some(chunk_kb);
+        self
+    }
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        if self.options.direct {
+            capabilities::require(Capability::DirectIo)?;
+        }
+        if let Some(kb) = self.options.chunk_kb.filter(|&kb| kb == 0 || kb % 4 != 0) {
+            return Err(StressError::InvalidParameter(format!("chunk_kb {} is not a positive multiple of 4", kb)));
+        }
+        let task_id = self.common.task_id("disk");
+        disk_stress::stress_disk_with(
+            self.common.threads,
+            self.size_mb,
+            self.common.duration,
+            Arc::clone(&self.common.stop_flag),
+            task_id,
+            self.options,
+        )
+        .await
+    }
+}
+
+impl Default for DiskStress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fork stress: `threads` child processes that sleep for the duration
+///
+/// Setting the stop flag sends the children SIGTERM.
+#[derive(Debug, Clone)]
+pub struct ForkStress {
+    common: Common,
+}
+
+impl ForkStress {
+    pub fn new() -> Self {
+        ForkStress { common: Common::new() }
+    }
+
+    common_setters!();
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        capabilities::require(Capability::Fork)?;
+        let task_id = self.common.task_id("fork");
+        let (processes, duration) = (self.common.threads, self.common.duration);
+        logs::emit(&task_id, format!("Forking {} child processes for {} seconds...", processes, duration));
+        let stop_flag = Arc::clone(&self.common.stop_flag);
+        tokio::task::spawn_blocking(move || fork_stress::stress_fork(processes, duration, &stop_flag, &task_id)).await?
+    }
+}
+
+impl Default for ForkStress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compression or crypto workload: threads round-trip an in-memory buffer through a `Codec`
+#[derive(Debug, Clone)]
+pub struct CodecStress {
+    common: Common,
+    codec: Codec,
+}
+
+impl CodecStress {
+    pub fn new(codec: Codec) -> Self {
+        CodecStress { common: Common::new(), codec }
+    }
+
+    common_setters!();
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        if let Codec::Zstd { level } = self.codec {
+            if !(1..=19).contains(&level) {
+                return Err(StressError::InvalidParameter(format!("zstd_level {} is not between 1 and 19", level)));
+            }
+        }
+        let prefix = if matches!(self.codec, Codec::Zstd { .. }) { "compress" } else { "crypto" };
+        let task_id = self.common.task_id(prefix);
+        codec_stress::stress_codec(
+            self.codec,
+            self.common.threads,
+            self.common.duration,
+            Arc::clone(&self.common.stop_flag),
+            task_id,
+        )
+        .await
+    }
+}
+
+/// Lock contention: threads take turns on shared counters behind a Mutex or RwLock
+#[derive(Debug, Clone)]
+pub struct LockStress {
+    common: Common,
+    options: LockOptions,
+}
+
+impl LockStress {
+    pub fn new() -> Self {
+        LockStress { common: Common::new(), options: LockOptions::default() }
+    }
+
+    common_setters!();
+
+    pub fn primitive(mut self, primitive: Primitive) -> Self {
+        self.options.primitive = primitive;
+        self
+    }
+
+    /// Number of shared counters, each behind its own lock
+    pub fn counters(mut self, counters: usize) -> Self {
+        self.options.counters = counters;
+        self
+    }
+
+    /// Critical section: how long each acquisition holds the lock
+    pub fn hold(mut self, hold: Duration) -> Self {
+        self.options.hold = hold;
+        self
+    }
+
+    /// Work between two acquisitions, outside the lock
+    pub fn think(mut self, think: Duration) -> Self {
+        self.options.think = think;
+        self
+    }
+
+    /// Share of RwLock acquisitions that only read (0 - 100)
+    pub fn read_percent(mut self, percent: f64) -> Self {
+        self.options.read_percent = percent;
+        self
+    }
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        if self.options.counters == 0 {
+            return Err(StressError::InvalidParameter("counters must be at least 1".to_string()));
+        }
+        if !(0.0..=100.0).contains(&self.options.read_percent) {
+            return Err(StressError::InvalidParameter("read_percent must be between 0 and 100".to_string()));
+        }
+        let task_id = self.common.task_id("lock");
+        lock_stress::stress_lock(
+            self.common.threads,
+            self.common.duration,
+            Arc::clone(&self.common.stop_flag),
+            task_id,
+            self.options,
+        )
+        .await
+    }
+}
+
+impl Default for LockStress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Page faults: threads map a region, touch every page and unmap it, over and over
+#[derive(Debug, Clone)]
+pub struct FaultStress {
+    common: Common,
+    region_mb: usize,
+    options: FaultOptions,
+}
+
+impl FaultStress {
+    pub fn new() -> Self {
+        FaultStress { common: Common::new(), region_mb: 256, options: FaultOptions::default() }
+    }
+
+    common_setters!();
+
+    /// Megabytes each thread maps per cycle (default 256)
+    pub fn region_mb(mut self, region_mb: usize) -> Self {
+        self.region_mb = region_mb;
+        self
+    }
+
+    /// Anonymous memory (minor faults, the default) or test files (major faults)
+    pub fn backing(mut self, backing: Backing) -> Self {
+        self.options.backing = backing;
+        self
+    }
+
+    /// Directories of the test files with file backing (default: working directory)
+    pub fn paths<P: Into<PathBuf>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.options.paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        if self.region_mb == 0 {
+            return Err(StressError::InvalidParameter("size must be at least 1 MB".to_string()));
+        }
+        let task_id = self.common.task_id("pagefault");
+        fault_stress::stress_faults(
+            self.common.threads,
+            self.region_mb,
+            self.common.duration,
+            Arc::clone(&self.common.stop_flag),
+            task_id,
+            self.options,
+        )
+        .await
+    }
+}
+
+impl Default for FaultStress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Syscall overhead: threads issue a mix of cheap system calls in a tight loop
+#[derive(Debug, Clone)]
+pub struct SyscallStress {
+    common: Common,
+    options: SyscallOptions,
+}
+
+impl SyscallStress {
+    pub fn new() -> Self {
+        SyscallStress { common: Common::new(), options: SyscallOptions::default() }
+    }
+
+    common_setters!();
+
+    /// Calls to issue and their weights (default: every call, weight 1)
+    pub fn mix(mut self, mix: impl IntoIterator<Item = (Syscall, u32)>) -> Self {
+        self.options.mix = mix.into_iter().collect();
+        self
+    }
+
+    pub async fn run(self) -> Result<StressResult, StressError> {
+        let task_id = self.common.task_id("syscall");
+        syscall_stress::stress_syscalls(
+            self.common.threads,
+            self.common.duration,
+            Arc::clone(&self.common.stop_flag),
+            task_id,
+            self.options,
+        )
+        .await
+    }
+}
+
+impl Default for SyscallStress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
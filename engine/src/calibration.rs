@@ -0,0 +1,155 @@
+//! Intensity calibration
+//!
+//! Maps the abstract 1-10 intensity level onto concrete test parameters for this
+//! node, from its core count, available memory and measured disk speed, so that
+//! "level 7" asks every node in a mixed fleet for roughly 70% of what it can take.
+//! The mapping is stored in a JSON file next to the engine and applied when a
+//! stress request carries a `level` instead of explicit parameters.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use sysinfo::System;
+
+use crate::result::StressError;
+use crate::stress::TestParams;
+use crate::sys_info::bench::{self, BenchResult};
+
+/// Default file the mapping is stored in (override with MOGWAI_CALIBRATION)
+pub const DEFAULT_CALIBRATION_FILE: &str = "mogwai_calibration.json";
+
+/// Share of the available memory allocated at level 10
+const MAX_MEMORY_FRACTION: f64 = 0.75;
+
+/// Upper bound for the per-thread disk file
+const MAX_DISK_FILE_MB: usize = 1024;
+
+/// Worker threads used for memory and disk tests at most
+const MAX_IO_THREADS: usize = 4;
+
+/// Stored calibration of one node
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Calibration {
+    pub hostname: String,
+    pub created_at: String,        // RFC3339
+    pub logical_cores: usize,
+    pub memory_available_mb: u64,  // When the calibration was taken
+    pub bench: BenchResult,        // Baseline scores the mapping was derived from
+    pub levels: Vec<LevelParams>,  // Levels 1 to 10
+}
+
+/// Concrete parameters for one intensity level
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelParams {
+    pub level: u8,
+    pub cpu_threads: usize,
+    pub cpu_load: f64,       // Percent per thread
+    pub mem_threads: usize,
+    pub mem_size_mb: usize,  // Per thread
+    pub disk_threads: usize,
+    pub disk_size_mb: usize, // Per thread file
+}
+
+impl Calibration {
+    /// Parameters for a level (1-10)
+    pub fn level(&self, level: u8) -> Option<&LevelParams> {
+        self.levels.iter().find(|params| params.level == level)
+    }
+}
+
+/// Probe this node (takes about six seconds) and derive the level mapping; `dir` holds the disk benchmark file
+pub fn calibrate(dir: &Path) -> Calibration {
+    let mut system = System::new();
+    system.refresh_memory();
+    let logical_cores = num_cpus::get().max(1);
+    let memory_available_mb = system.available_memory() / (1024 * 1024);
+    let bench = bench::run(dir);
+
+    let io_threads = logical_cores.min(MAX_IO_THREADS);
+    let levels = (1..=10u8)
+        .map(|level| {
+            let fraction = level as f64 / 10.0;
+            let memory_mb = memory_available_mb as f64 * MAX_MEMORY_FRACTION * fraction;
+
+            // A disk thread writes and reads its file about twice a second, so size the
+            // files to keep the measured throughput busy by the level's fraction
+            let disk_mb = match bench.disk_write_mb_s {
+                Some(rate) => rate * fraction / 4.0,
+                None => 64.0 * fraction * io_threads as f64,
+            };
+
+            LevelParams {
+                level,
+                cpu_threads: logical_cores,
+                cpu_load: 10.0 * level as f64,
+                mem_threads: io_threads,
+                mem_size_mb: ((memory_mb / io_threads as f64) as usize).max(1),
+                disk_threads: io_threads,
+                disk_size_mb: ((disk_mb / io_threads as f64) as usize).clamp(1, MAX_DISK_FILE_MB),
+            }
+        })
+        .collect();
+
+    Calibration {
+        hostname: hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "unknown".to_string()),
+        created_at: chrono::Local::now().to_rfc3339(),
+        logical_cores,
+        memory_available_mb,
+        bench,
+        levels,
+    }
+}
+
+/// File the calibration is stored in
+pub fn path() -> PathBuf {
+    std::env::var("MOGWAI_CALIBRATION")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_CALIBRATION_FILE))
+}
+
+/// Store the calibration
+pub fn save(calibration: &Calibration) -> Result<(), String> {
+    let data = serde_json::to_string_pretty(calibration).map_err(|e| e.to_string())?;
+    let path = path();
+    fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Stored calibration, if the node has been calibrated
+pub fn load() -> Option<Calibration> {
+    let data = fs::read_to_string(path()).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+/// Fill the parameters a request left out from its `level`; explicit values win
+pub fn apply(params: &mut TestParams, test_type: &str) -> Result<(), StressError> {
+    let Some(level) = params.level else {
+        return Ok(());
+    };
+    if !(1..=10).contains(&level) {
+        return Err(StressError::InvalidParameter(format!("level {} is not between 1 and 10", level)));
+    }
+    let calibration = load().ok_or_else(|| {
+        StressError::InvalidParameter("this node is not calibrated; POST /calibration first".to_string())
+    })?;
+    let Some(mapped) = calibration.level(level) else {
+        return Err(StressError::InvalidParameter(format!("calibration has no level {}", level)));
+    };
+
+    let (threads, load, size) = match test_type {
+        "cpu" => (mapped.cpu_threads, Some(mapped.cpu_load), None),
+        "mem" => (mapped.mem_threads, None, Some(mapped.mem_size_mb)),
+        "disk" => (mapped.disk_threads, None, Some(mapped.disk_size_mb)),
+        _ => (mapped.cpu_threads, None, None),
+    };
+    params.intensity.get_or_insert(threads);
+    // A closed-loop target replaces the per-thread load
+    if let Some(load) = load.filter(|_| params.system_load.is_none()) {
+        params.load.get_or_insert(load);
+    }
+    if let Some(size) = size {
+        params.size.get_or_insert(size);
+    }
+    Ok(())
+}
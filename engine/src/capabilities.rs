@@ -0,0 +1,159 @@
+//! Privileged operations and whether this engine may use them
+//!
+//! Fork stress, locked memory (`lock`), huge pages (`hugepages`) and direct I/O
+//! (`direct`) need privileges or kernel settings that restricted PodSecurity
+//! profiles take away. Each one is probed once at startup. Requests that need an
+//! unavailable one are refused with an `unavailable` error naming the reason,
+//! instead of failing halfway through the test.
+//!
+//! With `MOGWAI_RESTRICTED=1` (hardened non-root mode) fork stress is disabled
+//! outright, since a restricted pod usually shares its process limit with the node.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::result::StressError;
+
+#[cfg(target_env = "gnu")]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(target_env = "gnu"))]
+type Resource = libc::c_int;
+
+/// Linux capability bits (see capabilities(7))
+const CAP_IPC_LOCK: u32 = 14;
+
+/// A privileged operation a stress test may need
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Fork,      // Fork stress test
+    Mlock,     // Locking test memory into RAM (mem, "lock": true)
+    Hugepages, // Backing test memory with reserved huge pages (mem, "hugepages": true)
+    DirectIo,  // Bypassing the page cache with O_DIRECT (disk, "direct": true)
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [Capability::Fork, Capability::Mlock, Capability::Hugepages, Capability::DirectIo];
+
+    fn label(self) -> &'static str {
+        match self {
+            Capability::Fork => "fork stress",
+            Capability::Mlock => "memory locking",
+            Capability::Hugepages => "huge pages",
+            Capability::DirectIo => "direct I/O",
+        }
+    }
+}
+
+/// Probe outcome for one capability
+#[derive(Serialize, Debug, Clone)]
+pub struct CapabilityStatus {
+    pub capability: Capability,
+    pub available: bool,
+    pub reason: Option<String>, // Why it is unavailable
+}
+
+/// Probed once, the first time any capability is checked
+static STATUS: Lazy<Vec<CapabilityStatus>> = Lazy::new(|| {
+    Capability::ALL
+        .iter()
+        .map(|&capability| {
+            let reason = probe(capability).err();
+            CapabilityStatus { capability, available: reason.is_none(), reason }
+        })
+        .collect()
+});
+
+/// Whether the engine runs in hardened non-root mode
+pub fn restricted() -> bool {
+    std::env::var("MOGWAI_RESTRICTED").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Status of every capability
+pub fn status() -> Vec<CapabilityStatus> {
+    STATUS.clone()
+}
+
+/// Refuse a request that needs an unavailable capability
+pub fn require(capability: Capability) -> Result<(), StressError> {
+    match STATUS.iter().find(|s| s.capability == capability).and_then(|s| s.reason.as_ref()) {
+        Some(reason) => Err(StressError::Unavailable(format!("{}: {}", capability.label(), reason))),
+        None => Ok(()),
+    }
+}
+
+/// Check whether a capability works here; Err holds the reason it does not
+fn probe(capability: Capability) -> Result<(), String> {
+    match capability {
+        Capability::Fork => {
+            if restricted() {
+                return Err("disabled in restricted mode (MOGWAI_RESTRICTED)".to_string());
+            }
+            match soft_limit(libc::RLIMIT_NPROC) {
+                Some(0) => Err("RLIMIT_NPROC is 0".to_string()),
+                _ => Ok(()),
+            }
+        }
+        Capability::Mlock => {
+            if has_capability(CAP_IPC_LOCK) {
+                return Ok(());
+            }
+            match soft_limit(libc::RLIMIT_MEMLOCK) {
+                None => Ok(()),
+                Some(bytes) => Err(format!(
+                    "CAP_IPC_LOCK is missing and RLIMIT_MEMLOCK is {} KB",
+                    bytes / 1024
+                )),
+            }
+        }
+        Capability::Hugepages => {
+            let meminfo = fs::read_to_string("/proc/meminfo").map_err(|e| format!("cannot read /proc/meminfo: {}", e))?;
+            let free = meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix("HugePages_Free:"))
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .unwrap_or(0);
+            if free == 0 {
+                return Err("no free huge pages are reserved (vm.nr_hugepages)".to_string());
+            }
+            Ok(())
+        }
+        Capability::DirectIo => {
+            // Same working directory as the disk stress test files
+            let path = "mogwai_direct_io_probe";
+            let outcome = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .custom_flags(libc::O_DIRECT)
+                .open(path)
+                .map(|_| ())
+                .map_err(|e| format!("O_DIRECT is not supported in the working directory: {}", e));
+            let _ = fs::remove_file(path);
+            outcome
+        }
+    }
+}
+
+/// Soft resource limit of this process (None = unlimited or unknown)
+fn soft_limit(resource: Resource) -> Option<u64> {
+    let mut rlim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    // SAFETY: getrlimit only writes into the provided struct
+    if unsafe { libc::getrlimit(resource, &mut rlim) } != 0 || rlim.rlim_cur == libc::RLIM_INFINITY {
+        return None;
+    }
+    Some(rlim.rlim_cur)
+}
+
+/// Whether a capability is in this process's effective set
+fn has_capability(bit: u32) -> bool {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            let hex = status.lines().find_map(|line| line.strip_prefix("CapEff:"))?;
+            u64::from_str_radix(hex.trim(), 16).ok()
+        })
+        .is_some_and(|effective| effective & (1 << bit) != 0)
+}
@@ -0,0 +1,329 @@
+//! Per-task cgroup v2 isolation
+//!
+//! With `MOGWAI_CGROUPS=1` every stress task runs in a child process (this binary
+//! re-run with `--run-task`) placed in its own cgroup below the engine's, with
+//! `memory.max` and `cpu.max` derived from the request. A runaway memory test then
+//! hits its own limit and only its cgroup is OOM-killed, instead of the engine pod
+//! or the kubelet. The cgroup v2 hierarchy must be writable (a privileged pod, or a
+//! delegated systemd unit).
+//!
+//! The child reports its log lines and, as the last line, its result on stdout.
+//! Writing "stop" (or closing stdin) asks it to stop early.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::result::{StressError, StressResult};
+use crate::stress::{self, StressTest, TestContext};
+use crate::{logging, logs, plugin, thread_manager};
+
+/// First argument of an isolated task process
+pub const CHILD_FLAG: &str = "--run-task";
+
+/// Marks the result line of an isolated task process
+const RESULT_PREFIX: &str = "@mogwai-result ";
+
+/// cgroup the task cgroups are created in (set by `init`)
+static BASE: OnceCell<PathBuf> = OnceCell::new();
+
+/// Memory allowed on top of the test's own allocations (runtime, stacks, buffers)
+const MEMORY_OVERHEAD_MB: u64 = 64;
+
+/// Smallest memory cap, for tests that allocate little on their own (cpu, fork, custom)
+const DEFAULT_MEMORY_MB: u64 = 256;
+
+/// cpu.max period in microseconds
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// First line sent to an isolated task process
+#[derive(Serialize, Deserialize)]
+struct TaskSpec {
+    test: String,
+    ctx: TestContext,
+}
+
+/// Caps written to a task's cgroup
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    memory_mb: u64,        // memory.max
+    cpu_cores: Option<f64>, // cpu.max quota in cores (None = unlimited)
+}
+
+impl Limits {
+    /// Caps for a request: what the test's estimate says it allocates plus headroom, and
+    /// for CPU tests their thread count in cores.
+    /// `memory_limit_mb` and `cpu_limit` (cores) in the request body override them.
+    fn for_task(test: &dyn StressTest, ctx: &TestContext) -> Self {
+        let threads = ctx.intensity.max(1) as u64;
+        let allocated = test.estimate(ctx).map_or(0, |estimate| estimate.memory_mb);
+        let memory_mb = allocated
+            .saturating_add(allocated / 10)
+            .saturating_add(MEMORY_OVERHEAD_MB)
+            .max(DEFAULT_MEMORY_MB);
+        let cpu_cores = match test.name() {
+            "cpu" => Some(threads as f64),
+            _ => None,
+        };
+        Limits {
+            memory_mb: ctx.extra.get("memory_limit_mb").and_then(|v| v.as_u64()).unwrap_or(memory_mb),
+            cpu_cores: ctx.extra.get("cpu_limit").and_then(|v| v.as_f64()).or(cpu_cores),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.cpu_cores {
+            Some(cores) => format!("memory.max {} MB, cpu.max {} cores", self.memory_mb, cores),
+            None => format!("memory.max {} MB, cpu.max unlimited", self.memory_mb),
+        }
+    }
+}
+
+/// Prepare the engine's cgroup for task cgroups; returns the directory they are created in
+pub fn init() -> Result<PathBuf, String> {
+    let root = PathBuf::from(std::env::var("MOGWAI_CGROUP_ROOT").unwrap_or_else(|_| "/sys/fs/cgroup".to_string()));
+    if !root.join("cgroup.controllers").exists() {
+        return Err(format!("cgroup v2 is not mounted at {}", root.display()));
+    }
+
+    let own = fs::read_to_string("/proc/self/cgroup")
+        .map_err(|e| format!("Failed to read /proc/self/cgroup: {}", e))?;
+    let own = own
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or("The engine is not in a cgroup v2 hierarchy")?;
+    let base = root.join(own.trim().trim_start_matches('/'));
+
+    // Controllers can only be delegated from a cgroup without processes of its own,
+    // so move the engine into a leaf next to the task cgroups when needed
+    let controllers = "+cpu +memory +pids";
+    if fs::write(base.join("cgroup.subtree_control"), controllers).is_err() {
+        let leaf = base.join("mogwai-engine");
+        fs::create_dir_all(&leaf).map_err(|e| format!("Failed to create {}: {}", leaf.display(), e))?;
+        let processes = fs::read_to_string(base.join("cgroup.procs")).unwrap_or_default();
+        for pid in processes.lines() {
+            let _ = fs::write(leaf.join("cgroup.procs"), pid);
+        }
+        fs::write(base.join("cgroup.subtree_control"), controllers)
+            .map_err(|e| format!("Failed to enable {} in {}: {}", controllers, base.display(), e))?;
+    }
+
+    BASE.set(base.clone()).map_err(|_| "cgroup isolation is already initialized".to_string())?;
+    Ok(base)
+}
+
+/// Whether tasks run in their own cgroups
+pub fn enabled() -> bool {
+    BASE.get().is_some()
+}
+
+/// Run a registered test in a child process inside a fresh cgroup
+pub async fn run_isolated(test: &dyn StressTest, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> Result<StressResult, StressError> {
+    let base = BASE.get().ok_or_else(|| StressError::Io("cgroup isolation is not initialized".to_string()))?;
+    let dir = base.join(format!("mogwai-{}", ctx.task_id));
+    let limits = Limits::for_task(test, &ctx);
+    let task_id = ctx.task_id.clone();
+
+    fs::create_dir(&dir).map_err(|e| StressError::Io(format!("Failed to create cgroup {}: {}", dir.display(), e)))?;
+    let outcome = match apply_limits(&dir, limits) {
+        Ok(()) => {
+            logs::emit(&task_id, format!("Running in cgroup {} ({})", dir.display(), limits.describe()));
+            run_child_process(&dir, test.name(), ctx, &stop_flag).await
+        }
+        Err(e) => Err(StressError::Io(e)),
+    };
+
+    let oom_kills = read_oom_kills(&dir);
+    remove_cgroup(&dir).await;
+
+    match outcome {
+        Ok(mut result) => {
+            if oom_kills > 0 {
+                result.oom_kills = Some(result.oom_kills.unwrap_or(0) + oom_kills);
+            }
+            Ok(result)
+        }
+        Err(StressError::Killed(_)) if oom_kills > 0 => Err(StressError::Killed(format!(
+            "out of memory in its cgroup ({} MB limit)",
+            limits.memory_mb
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the caps into the task's cgroup
+fn apply_limits(dir: &Path, limits: Limits) -> Result<(), String> {
+    let write = |file: &str, value: String| {
+        fs::write(dir.join(file), &value).map_err(|e| format!("Failed to set {} to {}: {}", file, value, e))
+    };
+    write("memory.max", limits.memory_mb.saturating_mul(1024 * 1024).to_string())?;
+    let quota = match limits.cpu_cores {
+        Some(cores) => ((cores * CPU_PERIOD_US as f64) as u64).max(1000).to_string(),
+        None => "max".to_string(),
+    };
+    write("cpu.max", format!("{} {}", quota, CPU_PERIOD_US))?;
+
+    // Kill the whole task on OOM instead of swapping; both files are optional
+    let _ = fs::write(dir.join("memory.swap.max"), "0");
+    let _ = fs::write(dir.join("memory.oom.group"), "1");
+    Ok(())
+}
+
+/// Start this binary as an isolated task process and relay its output until it exits
+async fn run_child_process(
+    dir: &Path,
+    test: &str,
+    ctx: TestContext,
+    stop_flag: &AtomicBool,
+) -> Result<StressResult, StressError> {
+    let task_id = ctx.task_id.clone();
+    let exe = std::env::current_exe().map_err(|e| StressError::Io(format!("Failed to locate the engine binary: {}", e)))?;
+    let mut child = Command::new(exe)
+        .arg(CHILD_FLAG)
+        .arg(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| StressError::Io(format!("Failed to start the task process: {}", e)))?;
+
+    let mut stdin = child.stdin.take().ok_or_else(|| StressError::Io("task process has no stdin".to_string()))?;
+    let stdout = child.stdout.take().ok_or_else(|| StressError::Io("task process has no stdout".to_string()))?;
+    let spec = serde_json::to_string(&TaskSpec { test: test.to_string(), ctx })
+        .map_err(|e| StressError::InvalidParameter(e.to_string()))?;
+    stdin
+        .write_all(format!("{}\n", spec).as_bytes())
+        .await
+        .map_err(|e| StressError::Io(format!("Failed to send the task to its process: {}", e)))?;
+
+    let log_prefix = format!("[{}] ", task_id);
+    let mut lines = tokio::io::BufReader::new(stdout).lines();
+    let mut tick = tokio::time::interval(Duration::from_millis(200));
+    let mut stop_sent = false;
+    let mut outcome = None;
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => {
+                    if let Some(json) = line.strip_prefix(RESULT_PREFIX) {
+                        outcome = serde_json::from_str::<Result<StressResult, StressError>>(json).ok();
+                    } else {
+                        let message = line.strip_prefix(&log_prefix).unwrap_or(&line);
+                        logs::emit(&task_id, message);
+                    }
+                }
+                _ => break,
+            },
+            _ = tick.tick() => {
+                if !stop_sent && stop_flag.load(Ordering::SeqCst) {
+                    let _ = stdin.write_all(b"stop\n").await;
+                    stop_sent = true;
+                }
+            }
+        }
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| StressError::Io(format!("Failed to wait for the task process: {}", e)))?;
+    outcome.unwrap_or_else(|| {
+        use std::os::unix::process::ExitStatusExt;
+        Err(StressError::Killed(match status.signal() {
+            Some(signal) => format!("signal {}", signal),
+            None => format!("exited with {} before reporting a result", status),
+        }))
+    })
+}
+
+/// Entry point of an isolated task process (`<engine> --run-task <cgroup dir>`)
+pub async fn run_child(dir: &Path) -> io::Result<()> {
+    // The parent reads task output and the result from stdout
+    logging::init_bare(false);
+    // Join the task cgroup before generating any load
+    fs::write(dir.join("cgroup.procs"), std::process::id().to_string())?;
+
+    let mut input = BufReader::new(io::stdin());
+    let mut first = String::new();
+    input.read_line(&mut first)?;
+    let spec: TaskSpec = serde_json::from_str(&first).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    // "stop" or a closed stdin (the engine went away) ends the test early
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stop_flag);
+    std::thread::spawn(move || {
+        for line in input.lines() {
+            match line {
+                Ok(line) if line.trim() != "stop" => continue,
+                _ => break,
+            }
+        }
+        flag.store(true, Ordering::SeqCst);
+    });
+    // So does Ctrl-C reaching the whole process group, so test files get removed
+    let flag = Arc::clone(&stop_flag);
+    tokio::spawn(async move {
+        thread_manager::shutdown_signal().await;
+        flag.store(true, Ordering::SeqCst);
+    });
+
+    if stress::lookup(&spec.test).is_none() {
+        let plugin_dir = std::env::var("MOGWAI_PLUGIN_DIR").unwrap_or_else(|_| plugin::DEFAULT_PLUGIN_DIR.to_string());
+        plugin::load_plugins(Path::new(&plugin_dir));
+    }
+    let outcome = match stress::lookup(&spec.test) {
+        Some(test) => test.run(spec.ctx, stop_flag).await,
+        None => Err(StressError::InvalidParameter(format!("Unknown test type: {}", spec.test))),
+    };
+
+    println!("{}{}", RESULT_PREFIX, serde_json::to_string(&outcome).unwrap_or_default());
+    Ok(())
+}
+
+/// OOM kills recorded in the task's cgroup
+fn read_oom_kills(dir: &Path) -> u64 {
+    fs::read_to_string(dir.join("memory.events"))
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Kill every process of a task's cgroup and remove it (force-stop of a stuck task)
+pub fn kill(task_id: &str) {
+    let Some(base) = BASE.get() else {
+        return;
+    };
+    let dir = base.join(format!("mogwai-{}", task_id));
+    if !dir.exists() {
+        return;
+    }
+    // cgroup.kill needs Linux 5.14; older kernels get each process killed instead
+    if fs::write(dir.join("cgroup.kill"), "1").is_err() {
+        let procs = fs::read_to_string(dir.join("cgroup.procs")).unwrap_or_default();
+        for pid in procs.lines().filter_map(|line| line.trim().parse::<libc::pid_t>().ok()) {
+            // SAFETY: plain signal to a process of the task's cgroup
+            unsafe { libc::kill(pid, libc::SIGKILL) };
+        }
+    }
+    tokio::spawn(async move { remove_cgroup(&dir).await });
+}
+
+/// Remove the task's cgroup once its processes are gone (dying processes can linger briefly)
+async fn remove_cgroup(dir: &Path) {
+    for _ in 0..20 {
+        if fs::remove_dir(dir).is_ok() || !dir.exists() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    tracing::warn!(cgroup = %dir.display(), "Failed to remove cgroup");
+}
@@ -0,0 +1,98 @@
+//! Per-client task scoping
+//!
+//! Callers are identified by a bearer token when `MOGWAI_CLIENT_TOKENS` is set
+//! (`name=token,...`; requests without a known token are rejected). The
+//! `X-Mogwai-Client` header is self-asserted, so it only names the caller when
+//! `MOGWAI_TRUST_CLIENT_HEADER` is set, for engines reached only through a proxy that
+//! authenticates users and sets it (the controller with JWT authentication); it is
+//! ignored otherwise. A client's task ids are prefixed with its name (`alice:cpu-3`),
+//! and `/tasks`, `/stop`, `/stop-all` and `/results` only see that client's tasks.
+//! Anonymous callers only see the tasks started anonymously (unprefixed ids).
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+/// Header naming the calling client
+pub const CLIENT_HEADER: &str = "X-Mogwai-Client";
+
+/// Separates the client name from the task id
+const SEPARATOR: char = ':';
+
+/// Longest accepted client name
+const MAX_NAME_LEN: usize = 64;
+
+/// token -> client name, from MOGWAI_CLIENT_TOKENS
+static TOKENS: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    std::env::var("MOGWAI_CLIENT_TOKENS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| {
+            let (name, token) = pair.trim().split_once('=')?;
+            (valid_name(name) && !token.is_empty()).then(|| (token.to_string(), name.to_string()))
+        })
+        .collect()
+});
+
+/// Whether callers must authenticate with a token
+pub fn tokens_required() -> bool {
+    !TOKENS.is_empty()
+}
+
+/// Whether the client header is trusted without a token (MOGWAI_TRUST_CLIENT_HEADER)
+fn header_trusted() -> bool {
+    static TRUSTED: Lazy<bool> = Lazy::new(|| {
+        std::env::var("MOGWAI_TRUST_CLIENT_HEADER").is_ok_and(|v| v == "1" || v == "true")
+    });
+    *TRUSTED
+}
+
+/// The calling client from the request's Authorization and client headers
+/// (Ok(None) = anonymous, Err = rejected)
+pub fn identify(authorization: Option<&str>, client_header: Option<&str>) -> Result<Option<String>, String> {
+    if tokens_required() {
+        let token = authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or("Missing bearer token")?;
+        return TOKENS
+            .get(token.trim())
+            .map(|name| Some(name.clone()))
+            .ok_or_else(|| "Unknown bearer token".to_string());
+    }
+
+    // An unauthenticated header could name anyone, so it is not an identity
+    if !header_trusted() {
+        return Ok(None);
+    }
+    match client_header.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) if valid_name(name) => Ok(Some(name.to_string())),
+        Some(name) => Err(format!(
+            "Invalid {} '{}' (use up to {} of A-Z a-z 0-9 _ - .)",
+            CLIENT_HEADER, name, MAX_NAME_LEN
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Task id as seen by a client (prefixed with the client's name)
+pub fn scoped_id(client: Option<&str>, task_id: String) -> String {
+    match client {
+        Some(client) => format!("{}{}{}", client, SEPARATOR, task_id),
+        None => task_id,
+    }
+}
+
+/// Whether a client may see and stop a task; anonymous callers own the unprefixed ids
+pub fn owns(client: Option<&str>, task_id: &str) -> bool {
+    match client {
+        Some(client) => task_id
+            .strip_prefix(client)
+            .is_some_and(|rest| rest.starts_with(SEPARATOR)),
+        None => !task_id.contains(SEPARATOR),
+    }
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
@@ -0,0 +1,161 @@
+// Compression and crypto workloads: threads round-trip an in-memory buffer through
+// zstd, AES-256-GCM or SHA-256 as fast as they can and report the MB/s processed
+// The buffer is the same on every node, so the numbers can be compared across nodes
+
+use std::hint::black_box;
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+use aes_gcm::aead::{AeadInPlace, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use sha2::{Digest, Sha256};
+use tokio::task;
+use crate::logs;
+use crate::result::{StressError, StressResult};
+use crate::stress::Estimate;
+
+// Size of the buffer each thread processes per iteration
+const BUFFER_MB: usize = 4;
+
+// Compression level used unless the request sets zstd_level
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+// What the threads do with their buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Codec {
+    Zstd { level: i32 }, // Compress, then decompress again
+    AesGcm,              // Encrypt, then decrypt and authenticate again
+    Sha256,              // Hash
+}
+
+impl Codec {
+    // Name used in requests and log lines
+    pub fn name(&self) -> &'static str {
+        match self {
+            Codec::Zstd { .. } => "zstd",
+            Codec::AesGcm => "aes-gcm",
+            Codec::Sha256 => "sha256",
+        }
+    }
+}
+
+// Every thread keeps a core busy with its buffer and the processed copy
+pub fn estimate(threads: usize) -> Estimate {
+    Estimate {
+        memory_mb: (threads * BUFFER_MB * 2) as u64,
+        cpu_cores: threads as f64,
+        ..Default::default()
+    }
+}
+
+// Roughly service-like content: text-ish runs mixed with incompressible noise,
+// seeded so every node processes the same bytes
+fn sample_buffer() -> Vec<u8> {
+    const WORDS: [&[u8]; 8] = [b"mogwai ", b"request ", b"node ", b"latency ", b"{\"id\": ", b"status ", b"200 ", b"\n"];
+    let mut rng = StdRng::seed_from_u64(0x6d6f_6777_6169);
+    let mut buffer = Vec::with_capacity(BUFFER_MB * 1024 * 1024);
+    while buffer.len() < BUFFER_MB * 1024 * 1024 {
+        if rng.random_bool(0.25) {
+            let start = buffer.len();
+            buffer.resize(start + 64, 0);
+            rng.fill(&mut buffer[start..]);
+        } else {
+            buffer.extend_from_slice(WORDS[rng.random_range(0..WORDS.len())]);
+        }
+    }
+    buffer.truncate(BUFFER_MB * 1024 * 1024);
+    buffer
+}
+
+// One pass over the buffer
+fn round_trip(codec: Codec, data: &[u8], scratch: &mut Vec<u8>, cipher: &Aes256Gcm, counter: u64) -> Result<(), String> {
+    match codec {
+        Codec::Zstd { level } => {
+            let compressed = zstd::bulk::compress(data, level).map_err(|e| format!("zstd compression failed: {}", e))?;
+            let restored = zstd::bulk::decompress(&compressed, data.len()).map_err(|e| format!("zstd decompression failed: {}", e))?;
+            if restored.len() != data.len() {
+                return Err("zstd round trip changed the buffer size".to_string());
+            }
+        }
+        Codec::AesGcm => {
+            // A fresh nonce per iteration, like a real sender would use
+            let mut nonce = [0u8; 12];
+            nonce[4..].copy_from_slice(&counter.to_le_bytes());
+            let nonce = Nonce::from_slice(&nonce);
+            scratch.clear();
+            scratch.extend_from_slice(data);
+            let tag = cipher
+                .encrypt_in_place_detached(nonce, b"", scratch)
+                .map_err(|_| "AES-GCM encryption failed".to_string())?;
+            cipher
+                .decrypt_in_place_detached(nonce, b"", scratch, &tag)
+                .map_err(|_| "AES-GCM authentication failed".to_string())?;
+        }
+        Codec::Sha256 => {
+            black_box(Sha256::digest(data));
+        }
+    }
+    Ok(())
+}
+
+pub async fn stress_codec(
+    codec: Codec,
+    threads: usize,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: String,
+) -> Result<StressResult, StressError> {
+    let data = Arc::new(sample_buffer());
+    let started = Instant::now();
+    let mut handles = Vec::new();
+
+    for thread_id in 0..threads {
+        let data = Arc::clone(&data);
+        let stop = Arc::clone(&stop_flag);
+        let task_id = task_id.clone();
+
+        // Each thread returns (iterations, first error)
+        let handle = task::spawn_blocking(move || {
+            let start = Instant::now();
+            let cipher = Aes256Gcm::new(&[thread_id as u8; 32].into());
+            let mut scratch = Vec::with_capacity(data.len());
+            let mut iterations = 0u64;
+            let mut error = None;
+
+            while (duration == 0 || start.elapsed() < Duration::from_secs(duration))
+                && !stop.load(Ordering::SeqCst)
+            {
+                if let Err(e) = round_trip(codec, &data, &mut scratch, &cipher, iterations) {
+                    error = Some(format!("[Thread {}] {}", thread_id, e));
+                    break;
+                }
+                iterations += 1;
+            }
+
+            logs::emit(&task_id, format!("[Thread {}] {} stress test completed.", thread_id, codec.name()));
+            (iterations, error)
+        });
+        handles.push(handle);
+    }
+
+    let mut result = StressResult::default();
+    for handle in handles {
+        let (iterations, error) = handle.await?;
+        result.iterations += iterations;
+        result.errors.extend(error);
+    }
+    result.elapsed_secs = started.elapsed().as_secs_f64();
+    result.stopped = stop_flag.load(Ordering::SeqCst);
+    if result.elapsed_secs > 0.0 {
+        result.mb_per_s = Some((result.iterations * BUFFER_MB as u64) as f64 / result.elapsed_secs);
+    }
+
+    logs::emit(&task_id, format!(
+        "{} processed {:.1} MB/s over {} threads",
+        codec.name(),
+        result.mb_per_s.unwrap_or(0.0),
+        threads
+    ));
+    Ok(result)
+}
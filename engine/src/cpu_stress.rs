@@ -1,28 +1,82 @@
 use std::thread;
-use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
 use std::time::{Duration, Instant};
+use sysinfo::System;
 use tokio::task;
+use crate::logs;
+use crate::result::{StressError, StressResult, ThreadLoad};
 
-pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_provided: bool, indefinite: bool, stop_flag: Arc<AtomicBool>,task_id: String,) {
+// Percentage points a thread's busy time may drift from the target, or its CPU time
+// fall short of its busy time, before the log points it out
+const DRIFT_WARNING: f64 = 5.0;
+
+// What one worker thread did
+struct ThreadSample {
+    iterations: u64,
+    busy: Duration, // Wall time spent in the work phase
+    cpu: Duration,  // CPU time the thread got
+    wall: Duration, // The thread's own run time
+}
+
+// CPU time the calling thread has used so far
+fn thread_cpu_time() -> Duration {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: only writes the current thread's CPU clock into ts
+    unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+// Fill the load figures of a result from the worker samples: per thread over its own
+// run time, and aggregated over the test's elapsed time
+fn summarize(result: &mut StressResult, samples: &[ThreadSample], elapsed: f64, target: Option<f64>, task_id: &str) {
+    let percent = |part: Duration, whole: f64| if whole > 0.0 { part.as_secs_f64() / whole * 100.0 } else { 0.0 };
+    let (mut busy, mut cpu) = (Duration::ZERO, Duration::ZERO);
+    for (thread, sample) in samples.iter().enumerate() {
+        result.iterations += sample.iterations;
+        busy += sample.busy;
+        cpu += sample.cpu;
+        let load = ThreadLoad {
+            thread,
+            busy_percent: percent(sample.busy, sample.wall.as_secs_f64()),
+            cpu_percent: percent(sample.cpu, sample.wall.as_secs_f64()),
+        };
+        let drifted = target.is_some_and(|target| (load.busy_percent - target).abs() > DRIFT_WARNING);
+        if drifted || load.busy_percent - load.cpu_percent > DRIFT_WARNING {
+            logs::emit(task_id, format!(
+                "[Thread {}] achieved {:.1}% busy and {:.1}% CPU time{}",
+                thread,
+                load.busy_percent,
+                load.cpu_percent,
+                target.map(|target| format!(" (target {:.1}%)", target)).unwrap_or_default()
+            ));
+        }
+        result.thread_loads.push(load);
+    }
+    if !samples.is_empty() && elapsed > 0.0 {
+        let capacity = elapsed * samples.len() as f64;
+        result.achieved_load = Some(percent(busy, capacity));
+        result.cpu_load = Some(percent(cpu, capacity));
+    }
+    result.elapsed_secs = elapsed;
+}
+
+pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_provided: bool, indefinite: bool, stop_flag: Arc<AtomicBool>,task_id: String,) -> Result<StressResult, StressError> {
     // Error check for target load if load is provided
     if load_provided {
-        if target_load < 0.0 || target_load > 100.0 {
-            println!("Error: Target load must be between 0 and 100");
-            return;
+        if !(0.0..=100.0).contains(&target_load) {
+            logs::emit(&task_id, "Error: Target load must be between 0 and 100");
+            return Err(StressError::InvalidParameter(format!("target load {} is not between 0 and 100", target_load)));
         }
 
         if target_load == 0.0 {
-            println!("Warning: Target load is 0%. The system will not stress the CPU.");
-            return;
+            logs::emit(&task_id, "Warning: Target load is 0%. The system will not stress the CPU.");
+            return Ok(StressResult { achieved_load: Some(0.0), ..Default::default() });
         }
     }
 
-    if indefinite {
-        println!(
-            "Running CPU stress test indefinitely. To stop, send a POST request to: http://localhost:8080/stop/{}", task_id);
-    }
-    // Vector to store thread handles
+    // Vector to store thread handles; each thread returns (iterations, busy time)
     let mut handles = Vec::new();
+    let started = Instant::now();
 
     // Define behavior based on whether load is provided or not
     if load_provided {
@@ -31,6 +85,7 @@ pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_pr
 
         for thread_id in 0..threads {
             let stop = Arc::clone(&stop_flag);
+            let task_id = task_id.clone();
 
             let handle = task::spawn_blocking(move || {
                 let cycle_time = Duration::from_millis(100);
@@ -39,13 +94,17 @@ pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_pr
 
                 //global start time
                 let start_time = Instant::now();
+                let cpu_start = thread_cpu_time();
+                let (mut iterations, mut busy) = (0u64, Duration::ZERO);
 
                 while !stop.load(Ordering::SeqCst) {
                     let start = Instant::now();
                     // Work Phase: Simulate CPU-bound work
                     while start.elapsed() < work_time && !stop.load(Ordering::SeqCst) {
                         let _ = (0..1_000_000).fold(0u64, |acc, x| acc.wrapping_add(x));
+                        iterations += 1;
                     }
+                    busy += start.elapsed();
                     // Sleep Phase
                     thread::sleep(sleep_time);
 
@@ -55,7 +114,8 @@ pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_pr
                     }
                 }
 
-                println!("[Thread {}] Completed busy loop stress.", thread_id);
+                logs::emit(&task_id, format!("[Thread {}] Completed busy loop stress.", thread_id));
+                ThreadSample { iterations, busy, cpu: thread_cpu_time() - cpu_start, wall: start_time.elapsed() }
             });
 
             handles.push(handle);
@@ -64,13 +124,18 @@ pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_pr
         // Busy loop with no time slice (if load is not provided)
         for thread_id in 0..threads {
             let stop = Arc::clone(&stop_flag);
+            let task_id = task_id.clone();
 
             let handle = task::spawn_blocking(move || {
+                let start = Instant::now();
+                let cpu_start = thread_cpu_time();
+                let mut iterations = 0u64;
                 // If duration is indefinite, don't stop the loop
                 if indefinite {
                     while !stop.load(Ordering::SeqCst) {
                         // Simulate CPU-bound work (busy loop)
                         let _ = (0..1_000_000).fold(0u64, |acc, x| acc.wrapping_add(x));
+                        iterations += 1;
                     }
                 } else {
                     // For finite duration, run for the specified time
@@ -79,10 +144,13 @@ pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_pr
                     while Instant::now() < end_time && !stop.load(Ordering::SeqCst) {
                         // Simulate CPU-bound work (busy loop)
                         let _ = (0..1_000_000).fold(0u64, |acc, x| acc.wrapping_add(x));
+                        iterations += 1;
                     }
                 }
 
-                println!("[Thread {}] Completed busy loop stress.", thread_id);
+                logs::emit(&task_id, format!("[Thread {}] Completed busy loop stress.", thread_id));
+                let wall = start.elapsed();
+                ThreadSample { iterations, busy: wall, cpu: thread_cpu_time() - cpu_start, wall }
             });
 
             handles.push(handle);
@@ -90,9 +158,132 @@ pub async fn stress_cpu(threads: usize, target_load: f64 ,duration: u64, load_pr
     }
     
     // Wait for all threads to complete
+    let mut result = StressResult::default();
+    let mut samples = Vec::new();
     for handle in handles {
-        handle.await.unwrap();
+        samples.push(handle.await?);
+    }
+
+    summarize(&mut result, &samples, started.elapsed().as_secs_f64(), load_provided.then_some(target_load), &task_id);
+    result.stopped = stop_flag.load(Ordering::SeqCst);
+
+    logs::emit(&task_id, "CPU stress test completed.");
+    Ok(result)
+}
+
+// How often the closed-loop controller samples node CPU utilization
+const CONTROL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Share of the measured error corrected per sample; below 1 to avoid oscillating
+// when other workloads react to ours
+const CONTROL_GAIN: f64 = 0.5;
+
+// Closed-loop mode: hold node-wide CPU utilization at target_system_load percent
+// Worker threads run the same work/sleep cycle as above, but a controller samples
+// the node's actual utilization and adjusts their shared duty cycle, so the target
+// holds even while other workloads on the node come and go
+pub async fn stress_cpu_closed_loop(threads: usize, target_system_load: f64, duration: u64, stop_flag: Arc<AtomicBool>, task_id: String) -> Result<StressResult, StressError> {
+    if !(0.0..=100.0).contains(&target_system_load) {
+        return Err(StressError::InvalidParameter(format!("system load {} is not between 0 and 100", target_system_load)));
     }
+    if threads == 0 {
+        return Err(StressError::InvalidParameter("closed-loop mode needs at least one thread".to_string()));
+    }
+    // Duty cycle of every worker (0.0 - 1.0), stored as f64 bits
+    let cores = num_cpus::get().max(1) as f64;
+    let initial = (target_system_load / 100.0 * cores / threads as f64).clamp(0.0, 1.0);
+    let duty = Arc::new(AtomicU64::new(initial.to_bits()));
+    let done = Arc::new(AtomicBool::new(false));
+    let started = Instant::now();
+    let finished = move |stop: &AtomicBool| {
+        stop.load(Ordering::SeqCst) || (duration > 0 && started.elapsed() >= Duration::from_secs(duration))
+    };
+
+    let mut handles = Vec::new();
+    for thread_id in 0..threads {
+        let stop = Arc::clone(&stop_flag);
+        let duty = Arc::clone(&duty);
+        let task_id = task_id.clone();
+
+        let handle = task::spawn_blocking(move || {
+            let cycle_time = Duration::from_millis(100);
+            let (start_time, cpu_start) = (Instant::now(), thread_cpu_time());
+            let (mut iterations, mut busy) = (0u64, Duration::ZERO);
+
+            while !finished(&stop) {
+                let work_time = cycle_time.mul_f64(f64::from_bits(duty.load(Ordering::Relaxed)));
+                let start = Instant::now();
+                // Work Phase
+                while start.elapsed() < work_time && !stop.load(Ordering::SeqCst) {
+                    let _ = (0..1_000_000).fold(0u64, |acc, x| acc.wrapping_add(x));
+                    iterations += 1;
+                }
+                busy += start.elapsed();
+                // Sleep Phase
+                thread::sleep(cycle_time.saturating_sub(start.elapsed()));
+            }
+
+            logs::emit(&task_id, format!("[Thread {}] Completed closed-loop stress.", thread_id));
+            ThreadSample { iterations, busy, cpu: thread_cpu_time() - cpu_start, wall: start_time.elapsed() }
+        });
+
+        handles.push(handle);
+    }
+
+    // Controller: sample node utilization and correct the duty cycle
+    let controller = {
+        let (duty, done, task_id) = (Arc::clone(&duty), Arc::clone(&done), task_id.clone());
+        task::spawn_blocking(move || {
+            let mut system = System::new();
+            system.refresh_cpu_usage();
+            let (mut samples, mut sum) = (0u64, 0.0f64);
+            let mut last_report = Instant::now();
+
+            while !done.load(Ordering::SeqCst) {
+                thread::sleep(CONTROL_INTERVAL);
+                system.refresh_cpu_usage();
+                let measured = system.global_cpu_usage() as f64;
+                samples += 1;
+                sum += measured;
+
+                // Our threads move node utilization by threads/cores per unit of duty
+                let current = f64::from_bits(duty.load(Ordering::Relaxed));
+                let correction = (target_system_load - measured) / 100.0 * cores / threads as f64;
+                let next = (current + CONTROL_GAIN * correction).clamp(0.0, 1.0);
+                duty.store(next.to_bits(), Ordering::Relaxed);
+
+                if last_report.elapsed() >= Duration::from_secs(5) {
+                    logs::emit(&task_id, format!(
+                        "Node CPU {:.1}% (target {:.1}%), duty cycle {:.0}%", measured, target_system_load, next * 100.0));
+                    last_report = Instant::now();
+                }
+            }
+
+            (samples > 0).then(|| sum / samples as f64)
+        })
+    };
+
+    let mut result = StressResult::default();
+    let mut samples = Vec::new();
+    let mut worker_error = None;
+    for handle in handles {
+        match handle.await {
+            Ok(sample) => samples.push(sample),
+            Err(e) => { worker_error.get_or_insert(e); }
+        }
+    }
+    done.store(true, Ordering::SeqCst);
+    result.system_load = controller.await?;
+    if let Some(e) = worker_error {
+        return Err(e.into());
+    }
+
+    // The duty cycle follows the node, so there is no fixed per-thread target
+    summarize(&mut result, &samples, started.elapsed().as_secs_f64(), None, &task_id);
+    result.stopped = stop_flag.load(Ordering::SeqCst);
 
-    println!("CPU stress test completed.");
+    logs::emit(&task_id, format!(
+        "CPU stress test completed. Mean node CPU {:.1}% (target {:.1}%).",
+        result.system_load.unwrap_or(0.0), target_system_load));
+    Ok(result)
 }
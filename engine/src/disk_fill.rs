@@ -0,0 +1,163 @@
+// Disk-fill test: write until the target filesystem reaches a used percentage,
+// hold it there for the duration, then delete the fill file again
+// A watchdog aborts (and cleans up) as soon as free space drops below the safety
+// floor, so "disk almost full" alerting can be tested without filling the node
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+use tokio::task;
+use crate::disk_stress::{remove_temp_file, track_temp_file};
+use crate::logs;
+use crate::result::{StressError, StressResult};
+
+// Size of each write while filling
+const CHUNK_MB: usize = 64;
+
+// How often free space is checked while holding
+const HOLD_CHECK: Duration = Duration::from_secs(1);
+
+// Default free space that must always remain
+pub const DEFAULT_SAFETY_FLOOR_MB: u64 = 1024;
+
+// Size and free space of a filesystem in bytes
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    pub used: u64,
+    pub available: u64, // Free for unprivileged users (excludes reserved blocks)
+}
+
+impl Usage {
+    // Used percentage as df reports it (reserved blocks do not count)
+    pub fn used_percent(&self) -> f64 {
+        let size = self.used + self.available;
+        if size == 0 {
+            return 0.0;
+        }
+        self.used as f64 / size as f64 * 100.0
+    }
+}
+
+// Function to read the usage of the filesystem holding dir
+pub fn usage(dir: &Path) -> Result<Usage, String> {
+    let c_path = std::ffi::CString::new(dir.as_os_str().to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: statvfs only writes into the provided struct
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(format!("statvfs {}: {}", dir.display(), std::io::Error::last_os_error()));
+    }
+    let block = stat.f_frsize as u64;
+    Ok(Usage {
+        used: (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64) * block,
+        available: stat.f_bavail as u64 * block,
+    })
+}
+
+// Function to check a fill request against the safety floor; returns the bytes to write
+pub fn plan(dir: &Path, fill_percent: f64, safety_floor_mb: u64) -> Result<u64, StressError> {
+    if !(1.0..100.0).contains(&fill_percent) {
+        return Err(StressError::InvalidParameter(format!("fill_percent {} is not between 1 and 99", fill_percent)));
+    }
+    let usage = usage(dir).map_err(StressError::Io)?;
+    let target_available = (usage.used + usage.available) as f64 * (1.0 - fill_percent / 100.0);
+    let floor = safety_floor_mb * 1024 * 1024;
+    if target_available < floor as f64 {
+        return Err(StressError::InvalidParameter(format!(
+            "filling {} to {}% would leave {} MB free, below the safety floor of {} MB",
+            dir.display(),
+            fill_percent,
+            (target_available / (1024.0 * 1024.0)) as u64,
+            safety_floor_mb
+        )));
+    }
+    Ok((usage.available as f64 - target_available).max(0.0) as u64)
+}
+
+// Removes the fill file however the test ends
+struct FillFile(PathBuf);
+
+impl FillFile {
+    fn new(path: PathBuf) -> Self {
+        track_temp_file(&path);
+        FillFile(path)
+    }
+}
+
+impl Drop for FillFile {
+    fn drop(&mut self) {
+        remove_temp_file(&self.0);
+    }
+}
+
+pub async fn fill_disk(
+    dir: PathBuf,
+    fill_percent: f64,
+    safety_floor_mb: u64,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: String,
+) -> Result<StressResult, StressError> {
+    let to_write = plan(&dir, fill_percent, safety_floor_mb)?;
+    let floor = safety_floor_mb * 1024 * 1024;
+
+    task::spawn_blocking(move || {
+        let file = FillFile::new(dir.join(format!("mogwai_fill_{}", task_id.replace(':', "_"))));
+        let started = Instant::now();
+        let mut result = StressResult::default();
+        let watchdog = |stage: &str| -> Result<Usage, StressError> {
+            let usage = usage(&dir).map_err(StressError::Io)?;
+            if usage.available < floor {
+                let message = format!(
+                    "aborted while {}: {} MB free is below the safety floor of {} MB",
+                    stage,
+                    usage.available / (1024 * 1024),
+                    safety_floor_mb
+                );
+                return Err(StressError::Io(message));
+            }
+            Ok(usage)
+        };
+
+        // Fill Phase
+        logs::emit(&task_id, format!("Filling {} with {} MB to reach {}% used...", dir.display(), to_write / (1024 * 1024), fill_percent));
+        let mut handle = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&file.0)
+            .map_err(|e| StressError::Io(format!("Failed to create {}: {}", file.0.display(), e)))?;
+        let chunk = vec![0xA5u8; CHUNK_MB * 1024 * 1024];
+        let mut written = 0u64;
+        while written < to_write && !stop_flag.load(Ordering::SeqCst) {
+            let len = chunk.len().min((to_write - written) as usize);
+            handle
+                .write_all(&chunk[..len])
+                .and_then(|()| handle.sync_data())
+                .map_err(|e| StressError::Io(format!("Write to {} failed: {}", file.0.display(), e)))?;
+            written += len as u64;
+            result.iterations += 1;
+            watchdog("filling")?;
+        }
+        let fill_secs = started.elapsed().as_secs_f64();
+        if fill_secs > 0.0 {
+            result.mb_per_s = Some(written as f64 / (1024.0 * 1024.0) / fill_secs);
+        }
+        let usage = watchdog("filling")?;
+        logs::emit(&task_id, format!("{} is {:.1}% used; holding for {} seconds", dir.display(), usage.used_percent(), duration));
+
+        // Hold Phase (duration 0 holds until stopped)
+        let hold_start = Instant::now();
+        while (duration == 0 || hold_start.elapsed() < Duration::from_secs(duration)) && !stop_flag.load(Ordering::SeqCst) {
+            std::thread::sleep(HOLD_CHECK);
+            watchdog("holding")?;
+        }
+
+        result.elapsed_secs = started.elapsed().as_secs_f64();
+        result.stopped = stop_flag.load(Ordering::SeqCst);
+        logs::emit(&task_id, format!("Removing {} MB fill file", written / (1024 * 1024)));
+        Ok(result)
+    })
+    .await?
+}
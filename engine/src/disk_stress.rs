@@ -2,12 +2,174 @@
 // if you wanna see it, uncomment them
 
 
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
 use std::fs::{OpenOptions, remove_file};
 use std::io::{Write, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 use std::time::{Instant, Duration};
 use std::thread::sleep;
 use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
 use tokio::task;
+use crate::artifacts;
+use crate::histogram::Histogram;
+use crate::logs;
+use crate::result::{Mismatch, PathThroughput, StressError, StressResult};
+
+// Test files that exist right now, so a shutting-down engine can remove what
+// stuck worker threads leave behind (see remove_temp_files)
+static TEMP_FILES: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Function to note a test file before it is created
+pub(crate) fn track_temp_file(path: &Path) {
+    TEMP_FILES.lock().unwrap_or_else(|e| e.into_inner()).insert(path.to_path_buf());
+}
+
+// Function to delete a test file and stop tracking it
+pub(crate) fn remove_temp_file(path: &Path) {
+    let _ = remove_file(path);
+    TEMP_FILES.lock().unwrap_or_else(|e| e.into_inner()).remove(path);
+}
+
+// Function to stop tracking a test file that was moved elsewhere
+pub(crate) fn untrack_temp_file(path: &Path) {
+    TEMP_FILES.lock().unwrap_or_else(|e| e.into_inner()).remove(path);
+}
+
+// Function to delete every test file still on disk; returns how many were removed
+pub fn remove_temp_files() -> usize {
+    let mut files = TEMP_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    files.drain().filter(|path| remove_file(path).is_ok()).count()
+}
+
+// O_DIRECT needs buffers aligned to the logical block size; a page covers common devices
+const DIRECT_IO_ALIGN: usize = 4096;
+
+// Zeroed buffer aligned for O_DIRECT
+struct AlignedBuffer {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+// The buffer is owned by exactly one worker thread
+unsafe impl Send for AlignedBuffer {}
+
+impl AlignedBuffer {
+    fn new(len: usize) -> Self {
+        let layout = Layout::from_size_align(len.max(DIRECT_IO_ALIGN), DIRECT_IO_ALIGN).expect("valid buffer layout");
+        // SAFETY: the layout has a non-zero size
+        let ptr = unsafe { alloc_zeroed(layout) };
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+        AlignedBuffer { ptr, layout }
+    }
+}
+
+impl std::ops::Deref for AlignedBuffer {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        // SAFETY: ptr is valid for layout.size() bytes until Drop
+        unsafe { std::slice::from_raw_parts(self.ptr, self.layout.size()) }
+    }
+}
+
+impl std::ops::DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: ptr is valid for layout.size() bytes until Drop
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: allocated in new with the same layout
+        unsafe { dealloc(self.ptr, self.layout) };
+    }
+}
+
+// Size of a verified block; each carries its own pattern
+const VERIFY_BLOCK: usize = 4096;
+
+// Mismatching blocks listed in a result (all are counted)
+const MAX_MISMATCHES: usize = 20;
+
+// I/O size of chunked disk tests unless chunk_kb says otherwise
+pub const DEFAULT_CHUNK_KB: usize = 1024;
+
+// What one disk thread did
+#[derive(Default)]
+struct ThreadOutcome {
+    cycles: u64,
+    bytes: u64,                 // Written plus read
+    error: Option<String>,      // First failure
+    corrupt_blocks: u64,        // Blocks read back differently than written
+    mismatches: Vec<Mismatch>,  // The first few of them
+    io_secs: f64,               // Time spent in writes and reads (chunked)
+    times: Histogram,           // Time of each write/read cycle
+}
+
+impl ThreadOutcome {
+    // Compare data read back from `offset` of a file with the pattern written this cycle
+    fn verify(&mut self, buffer: &[u8], offset: usize, file_name: &Path, thread_id: usize, task_id: &str) {
+        let mut expected = vec![0u8; VERIFY_BLOCK];
+        let first_block = offset / VERIFY_BLOCK;
+        for (block, actual) in buffer.chunks(VERIFY_BLOCK).enumerate() {
+            let block = first_block + block;
+            let expected = &mut expected[..actual.len()];
+            block_pattern(expected, thread_id, self.cycles, block as u64);
+            if actual == expected {
+                continue;
+            }
+            if self.corrupt_blocks == 0 {
+                logs::emit(task_id, format!(
+                    "[Thread {}] Data mismatch in {} at offset {} (cycle {})",
+                    thread_id, file_name.display(), block * VERIFY_BLOCK, self.cycles));
+            }
+            self.corrupt_blocks += 1;
+            if self.mismatches.len() < MAX_MISMATCHES {
+                self.mismatches.push(Mismatch {
+                    file: file_name.display().to_string(),
+                    offset: (block * VERIFY_BLOCK) as u64,
+                    cycle: self.cycles,
+                });
+            }
+        }
+    }
+}
+
+// Fill a buffer with this thread's pattern for a cycle, block by block
+fn fill_pattern(buffer: &mut [u8], thread_id: usize, cycle: u64) {
+    for (block, chunk) in buffer.chunks_mut(VERIFY_BLOCK).enumerate() {
+        block_pattern(chunk, thread_id, cycle, block as u64);
+    }
+}
+
+// Pseudo-random content of one block, derived from where and when it was written,
+// so misplaced, stale and flipped data all read back differently
+fn block_pattern(block: &mut [u8], thread_id: usize, cycle: u64, index: u64) {
+    let mut state = (thread_id as u64) << 48 ^ cycle << 32 ^ index ^ 0x9E37_79B9_7F4A_7C15;
+    for word in block.chunks_mut(8) {
+        // splitmix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        word.copy_from_slice(&z.to_le_bytes()[..word.len()]);
+    }
+}
+
+// Flush a written file and drop it from the page cache, so verification reads the device
+fn drop_cached(file: &std::fs::File) {
+    let _ = file.sync_data();
+    // SAFETY: plain advice on an open descriptor
+    unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+}
 
 pub async fn stress_disk(
     threads: usize,
@@ -15,58 +177,215 @@ pub async fn stress_disk(
     duration: u64,
     stop_flag: Arc<AtomicBool>,
     task_id: String,
-) {
-    if duration == 0 {
-        println!("Running disk stress test indefinitely. To stop, send a POST request to: http://localhost:8080/stop/{}", task_id);
-    }
+) -> Result<StressResult, StressError> {
+    stress_disk_with(threads, file_size_mb, duration, stop_flag, task_id, DiskOptions::default()).await
+}
 
+// Where and how the disk test writes
+#[derive(Debug, Clone, Default)]
+pub struct DiskOptions {
+    pub direct: bool,        // Bypass the page cache (O_DIRECT), so the device itself is measured
+    pub paths: Vec<PathBuf>, // Target directories, threads spread round-robin (empty = working directory)
+    pub verify: bool,        // Write per-block patterns and check them on read
+    // Reuse one chunk-sized read buffer per thread and do all I/O in chunks of this many
+    // KiB (a multiple of 4), without pauses between cycles; throughput then counts only
+    // the time spent in I/O. None = whole-file I/O as before, whose numbers differ
+    pub chunk_kb: Option<usize>,
+    pub keep_files: bool,    // Hand the files to the task's artifacts instead of deleting them
+}
+
+pub async fn stress_disk_with(
+    threads: usize,
+    file_size_mb: usize,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: String,
+    options: DiskOptions,
+) -> Result<StressResult, StressError> {
     let mut handles = Vec::new();
+    let started = Instant::now();
 
+    let dirs = if options.paths.is_empty() { vec![PathBuf::from(".")] } else { options.paths.clone() };
     for thread_id in 0..threads {
-        let file_name = format!("disk_test_file_{}", thread_id);
-        let data = vec![0u8; file_size_mb * 1024 * 1024];
+        // The task id keeps concurrent tasks in one directory off each other's files and patterns
+        let file_name = dirs[thread_id % dirs.len()]
+            .join(format!("disk_test_file_{}_{}", task_id.replace([':', '/'], "_"), thread_id));
+        track_temp_file(&file_name);
+        let mut data = AlignedBuffer::new(file_size_mb * 1024 * 1024);
+        let flags = if options.direct { libc::O_DIRECT } else { 0 };
+        let verify = options.verify;
+        let keep_files = options.keep_files;
+        let chunk = options.chunk_kb.map(|kb| kb * 1024);
+        // Reused every cycle: one chunk when chunked, else the whole file
+        let mut buffer = AlignedBuffer::new(chunk.unwrap_or(data.len()));
         let stop = Arc::clone(&stop_flag);
+        let task_id = task_id.clone();
 
         let handle = task::spawn_blocking(move || {
             let start = Instant::now();
+            let mut outcome = ThreadOutcome::default();
 
             while (duration == 0 || start.elapsed() < Duration::from_secs(duration))
                 && !stop.load(Ordering::SeqCst)
             {
+                let cycle_start = Instant::now();
+
+                // A fresh pattern every cycle, so stale data from the last one is caught too
+                if verify {
+                    fill_pattern(&mut data, thread_id, outcome.cycles);
+                }
+
                 // Write Phase
-                if let Ok(mut file) = OpenOptions::new().create(true).write(true).open(&file_name) {
-                    let write_start = Instant::now();
-                    let _ = file.write_all(&data);
-                    let write_time = write_start.elapsed().as_secs_f64();
-                    let _write_speed = file_size_mb as f64 / write_time;
-                   // println!("[Thread {}] Write speed: {:.2} MB/s", thread_id, write_speed);
+                match OpenOptions::new().create(true).write(true).truncate(true).custom_flags(flags).open(&file_name) {
+                    Ok(mut file) => {
+                        let write_start = Instant::now();
+                        let written = match chunk {
+                            Some(chunk) => data.chunks(chunk).try_for_each(|part| file.write_all(part)),
+                            None => file.write_all(&data),
+                        };
+                        match written {
+                            Ok(()) => outcome.bytes += data.len() as u64,
+                            Err(e) => { outcome.error.get_or_insert_with(|| format!("[Thread {}] Write failed: {}", thread_id, e)); }
+                        }
+                        if verify {
+                            drop_cached(&file);
+                        }
+                        let write_time = write_start.elapsed().as_secs_f64();
+                        outcome.io_secs += write_time;
+                        let _write_speed = file_size_mb as f64 / write_time;
+                       // println!("[Thread {}] Write speed: {:.2} MB/s", thread_id, write_speed);
+                    }
+                    Err(e) => { outcome.error.get_or_insert_with(|| format!("[Thread {}] Failed to open {}: {}", thread_id, file_name.display(), e)); }
+                }
+
+                // Read Phase, chunked: back through the one chunk buffer, verifying as it goes
+                if let Some(chunk) = chunk {
+                    if let Ok(mut file) = OpenOptions::new().read(true).custom_flags(flags).open(&file_name) {
+                        let mut offset = 0;
+                        while offset < data.len() {
+                            let part = &mut buffer[..(data.len() - offset).min(chunk)];
+                            let read_start = Instant::now();
+                            let read = file.read_exact(part);
+                            outcome.io_secs += read_start.elapsed().as_secs_f64();
+                            if let Err(e) = read {
+                                outcome.error.get_or_insert_with(|| format!("[Thread {}] Read failed: {}", thread_id, e));
+                                break;
+                            }
+                            outcome.bytes += part.len() as u64;
+                            if verify {
+                                outcome.verify(part, offset, &file_name, thread_id, &task_id);
+                            }
+                            offset += part.len();
+                        }
+                    }
+                    outcome.times.record(cycle_start.elapsed());
+                    outcome.cycles += 1;
+                    continue;
                 }
 
                 // Read Phase
-                let mut buffer = vec![0u8; file_size_mb * 1024 * 1024];
-                if let Ok(mut file) = OpenOptions::new().read(true).open(&file_name) {
+                if let Ok(mut file) = OpenOptions::new().read(true).custom_flags(flags).open(&file_name) {
                     let read_start = Instant::now();
-                    let _ = file.read_exact(&mut buffer);
+                    match file.read_exact(&mut buffer) {
+                        Ok(()) => {
+                            outcome.bytes += buffer.len() as u64;
+                            if verify {
+                                outcome.verify(&buffer, 0, &file_name, thread_id, &task_id);
+                            }
+                        }
+                        Err(e) => { outcome.error.get_or_insert_with(|| format!("[Thread {}] Read failed: {}", thread_id, e)); }
+                    }
                     let read_time = read_start.elapsed().as_secs_f64();
                     let _read_speed = file_size_mb as f64 / read_time;
                    // println!("[Thread {}] Read speed: {:.2} MB/s", thread_id, read_speed);
                 }
 
+                outcome.times.record(cycle_start.elapsed());
+                outcome.cycles += 1;
                 sleep(Duration::from_millis(500));
             }
 
-            println!("[Thread {}] Disk stress test completed.", thread_id);
-            if std::path::Path::new(&file_name).exists() {
-                let _ = remove_file(&file_name);
+            logs::emit(&task_id, format!("[Thread {}] Disk stress test completed.", thread_id));
+            if !(keep_files && artifacts::keep_file(&task_id, &file_name)) {
+                remove_temp_file(&file_name);
             }
+            outcome
         });
 
         handles.push(handle);
     }
 
-    for handle in handles {
-        handle.await.unwrap();
+    let mut result = StressResult::default();
+    let mut total_bytes = 0u64;
+    // Chunked tests add up each thread's bytes per second of I/O
+    let mut io_rate = 0.0;
+    let mut per_path: Vec<(PathThroughput, u64, f64)> = options
+        .paths
+        .iter()
+        .map(|path| (PathThroughput { path: path.display().to_string(), ..Default::default() }, 0, 0.0))
+        .collect();
+    let mut times = Histogram::new();
+    if options.verify {
+        result.corrupt_blocks = Some(0);
     }
+    for (thread_id, handle) in handles.into_iter().enumerate() {
+        let outcome = handle.await?;
+        result.iterations += outcome.cycles;
+        times.merge(&outcome.times);
+        total_bytes += outcome.bytes;
+        let rate = if outcome.io_secs > 0.0 { outcome.bytes as f64 / outcome.io_secs } else { 0.0 };
+        io_rate += rate;
+        if let Some((path, path_bytes, path_rate)) = per_path.get_mut(thread_id % dirs.len()) {
+            path.threads += 1;
+            *path_bytes += outcome.bytes;
+            *path_rate += rate;
+            path.errors.extend(outcome.error.clone());
+            if options.verify {
+                *path.corrupt_blocks.get_or_insert(0) += outcome.corrupt_blocks;
+            }
+        }
+        result.errors.extend(outcome.error);
+        if let Some(corrupt) = result.corrupt_blocks.as_mut() {
+            *corrupt += outcome.corrupt_blocks;
+        }
+        let room = MAX_MISMATCHES.saturating_sub(result.mismatches.len());
+        result.mismatches.extend(outcome.mismatches.into_iter().take(room));
+    }
+    result.elapsed_secs = started.elapsed().as_secs_f64();
+    result.iteration_times = times.percentiles();
+    artifacts::record_histogram(&task_id, "iteration_times", &times);
+    if let Some(times) = &result.iteration_times {
+        logs::emit(&task_id, format!(
+            "Iteration time p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+            times.p50_us / 1000.0,
+            times.p95_us / 1000.0,
+            times.p99_us / 1000.0,
+            times.max_us / 1000.0
+        ));
+    }
+
+    // Throughput of each target directory, reported separately
+    for (mut path, bytes, rate) in per_path {
+        if options.chunk_kb.is_some() {
+            path.mb_per_s = rate / (1024.0 * 1024.0);
+        } else if result.elapsed_secs > 0.0 {
+            path.mb_per_s = bytes as f64 / (1024.0 * 1024.0) / result.elapsed_secs;
+        }
+        logs::emit(&task_id, format!("{}: {:.2} MB/s with {} threads", path.path, path.mb_per_s, path.threads));
+        result.paths.push(path);
+    }
+    result.stopped = stop_flag.load(Ordering::SeqCst);
+
+    logs::emit(&task_id, "Disk stress test finished.");
 
-    println!("Disk stress test finished.");
+    // Nothing was written or read at all
+    if total_bytes == 0 && !result.errors.is_empty() {
+        return Err(StressError::Io(result.errors.join("; ")));
+    }
+    if options.chunk_kb.is_some() {
+        result.mb_per_s = Some(io_rate / (1024.0 * 1024.0));
+    } else if result.elapsed_secs > 0.0 {
+        result.mb_per_s = Some(total_bytes as f64 / (1024.0 * 1024.0) / result.elapsed_secs);
+    }
+    Ok(result)
 }
@@ -0,0 +1,210 @@
+//! Pre-run resource estimation
+//!
+//! `POST /estimate` takes a battery of test specs and returns what each is expected to
+//! take (memory, disk space, CPU seconds) and whether this node has the headroom for
+//! the battery right now, so planners can sanity-check a generated battery before
+//! starting it. Nothing is started. The node side comes from the same host snapshot as
+//! `/metrics`, so it includes the load of tasks already running.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use crate::stress::{self, Estimate, TestContext, TestParams};
+use crate::{calibration, disk_fill, limits, metrics};
+use crate::thread_manager::{self, GLOBAL_REGISTRY};
+
+/// Request body of `POST /estimate`
+#[derive(Deserialize, Debug)]
+pub struct EstimateRequest {
+    pub tests: Vec<TestSpec>,
+    #[serde(default)]
+    pub concurrent: bool, // The tests run at the same time rather than one after another
+}
+
+/// One test of the battery: its type and the body its start endpoint would get
+#[derive(Deserialize, Debug)]
+pub struct TestSpec {
+    #[serde(rename = "type")]
+    pub test_type: String, // cpu, mem, disk, fork, compress, crypto, lock, pagefault, syscall or a custom workload
+    #[serde(flatten)]
+    pub params: TestParams,
+}
+
+/// Expected use of one test
+#[derive(Serialize, Debug)]
+pub struct TestEstimate {
+    #[serde(rename = "type")]
+    pub test_type: String,
+    pub duration_secs: u64,      // 0 = until stopped; disk fill tests take longer by the time filling takes
+    pub memory_mb: u64,
+    pub disk_mb: u64,
+    pub cpu_cores: f64,
+    pub cpu_secs: Option<f64>,   // None for tests that run until stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,   // Why the test would be rejected
+}
+
+/// Expected use of the whole battery
+#[derive(Serialize, Debug, Default)]
+pub struct Totals {
+    pub duration_secs: u64,
+    pub memory_mb: u64,        // Peak
+    pub disk_mb: u64,          // Peak
+    pub cpu_cores: f64,        // Peak
+    pub cpu_secs: Option<f64>, // None if any test runs until stopped
+}
+
+/// What the node has to spare right now
+#[derive(Serialize, Debug)]
+pub struct NodeHeadroom {
+    pub cpu_cores: usize,
+    pub cpu_percent: f32,
+    pub running_tasks: usize,
+    pub memory_available_mb: u64,
+    pub disk_available_mb: BTreeMap<PathBuf, u64>, // For each directory the tests write to
+}
+
+#[derive(Serialize, Debug)]
+pub struct EstimateResponse {
+    pub tests: Vec<TestEstimate>,
+    pub total: Totals,
+    pub node: NodeHeadroom,
+    pub fits: bool,            // Every test is valid and the peaks fit the headroom
+    pub warnings: Vec<String>, // Why it does not fit
+}
+
+/// Estimate a battery against the current state of this node
+pub fn estimate(request: EstimateRequest) -> EstimateResponse {
+    let mut tests = Vec::new();
+    let mut peaks: Vec<Estimate> = Vec::new();
+    let mut warnings = Vec::new();
+    for spec in request.tests {
+        let (test_estimate, peak) = estimate_test(spec);
+        if let Some(error) = &test_estimate.error {
+            warnings.push(format!("{} test is invalid: {}", test_estimate.test_type, error));
+        }
+        tests.push(test_estimate);
+        peaks.extend(peak);
+    }
+
+    let total = totals(&tests, &peaks, request.concurrent);
+    let running_tasks = thread_manager::task_infos(&GLOBAL_REGISTRY).len();
+    let host = metrics::sample(running_tasks);
+    let mut disk_needed: BTreeMap<PathBuf, u64> = BTreeMap::new();
+    for peak in &peaks {
+        for (dir, mb) in &peak.disk_mb {
+            let needed = disk_needed.entry(dir.clone()).or_default();
+            *needed = if request.concurrent { needed.saturating_add(*mb) } else { (*needed).max(*mb) };
+        }
+    }
+    let node = NodeHeadroom {
+        cpu_cores: num_cpus::get(),
+        cpu_percent: host.cpu_percent,
+        running_tasks,
+        memory_available_mb: host.memory_total_mb.saturating_sub(host.memory_used_mb),
+        disk_available_mb: disk_needed
+            .keys()
+            .filter_map(|dir| Some((dir.clone(), disk_fill::usage(dir).ok()?.available / (1024 * 1024))))
+            .collect(),
+    };
+
+    if total.memory_mb > node.memory_available_mb {
+        warnings.push(format!(
+            "needs {} MB of memory, {} MB are available",
+            total.memory_mb, node.memory_available_mb
+        ));
+    }
+    for (dir, needed) in &disk_needed {
+        match node.disk_available_mb.get(dir) {
+            Some(available) if needed > available => warnings.push(format!(
+                "needs {} MB in {}, {} MB are free",
+                needed,
+                dir.display(),
+                available
+            )),
+            Some(_) => {}
+            None => warnings.push(format!("cannot read the free space of {}", dir.display())),
+        }
+    }
+    let idle_cores = node.cpu_cores as f64 * (1.0 - node.cpu_percent as f64 / 100.0);
+    if total.cpu_cores > idle_cores {
+        warnings.push(format!(
+            "keeps {:.1} cores busy, {:.1} of {} are idle",
+            total.cpu_cores, idle_cores, node.cpu_cores
+        ));
+    }
+
+    EstimateResponse { tests, total, node, fits: warnings.is_empty(), warnings }
+}
+
+/// Expected use of one spec, and its peak for the totals unless it is invalid
+fn estimate_test(spec: TestSpec) -> (TestEstimate, Option<Estimate>) {
+    let TestSpec { test_type, mut params } = spec;
+    // As on the start endpoints, the fork flag turns a CPU test into a fork test
+    let name = if test_type == "cpu" && params.fork == Some(true) { "fork".to_string() } else { test_type };
+    let outcome = match stress::lookup(&name) {
+        // Estimate what would run, after the safety limits clamped it
+        Some(test) => calibration::apply(&mut params, test.name())
+            .and_then(|()| limits::enforce(test.as_ref(), &mut params))
+            .and_then(|_| test.validate(&params))
+            .and_then(|()| {
+                let ctx = TestContext::new(String::new(), &params);
+                test.estimate(&ctx)
+            })
+            .map_err(|e| e.to_string()),
+        None => Err(format!("unknown test type '{}'", name)),
+    };
+    let duration_secs = params.duration.unwrap_or(10);
+    match outcome {
+        Ok(peak) => {
+            let estimate = TestEstimate {
+                test_type: name,
+                duration_secs,
+                memory_mb: peak.memory_mb,
+                disk_mb: peak.disk_mb.values().copied().fold(0, u64::saturating_add),
+                cpu_cores: peak.cpu_cores,
+                cpu_secs: (duration_secs > 0).then_some(peak.cpu_cores * duration_secs as f64),
+                error: None,
+            };
+            (estimate, Some(peak))
+        }
+        Err(error) => {
+            let estimate = TestEstimate {
+                test_type: name,
+                duration_secs,
+                memory_mb: 0,
+                disk_mb: 0,
+                cpu_cores: 0.0,
+                cpu_secs: None,
+                error: Some(error),
+            };
+            (estimate, None)
+        }
+    }
+}
+
+/// Peaks and sums of a battery run concurrently or one test after another
+fn totals(tests: &[TestEstimate], peaks: &[Estimate], concurrent: bool) -> Totals {
+    let valid = || tests.iter().filter(|test| test.error.is_none());
+    // Resources add up when tests overlap; one after another, the largest test counts
+    let peak = |values: &mut dyn Iterator<Item = u64>| -> u64 {
+        if concurrent { values.fold(0, u64::saturating_add) } else { values.max().unwrap_or(0) }
+    };
+    let durations = valid().map(|test| test.duration_secs);
+    let unbounded = valid().any(|test| test.duration_secs == 0);
+    Totals {
+        duration_secs: match (unbounded, concurrent) {
+            (true, _) => 0,
+            (false, true) => durations.max().unwrap_or(0),
+            (false, false) => durations.sum(),
+        },
+        memory_mb: peak(&mut peaks.iter().map(|peak| peak.memory_mb)),
+        disk_mb: peak(&mut peaks.iter().map(|peak| peak.disk_mb.values().copied().fold(0, u64::saturating_add))),
+        cpu_cores: if concurrent {
+            peaks.iter().map(|peak| peak.cpu_cores).sum()
+        } else {
+            peaks.iter().map(|peak| peak.cpu_cores).fold(0.0, f64::max)
+        },
+        cpu_secs: if unbounded { None } else { Some(valid().filter_map(|test| test.cpu_secs).sum()) },
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+// Events kept for GET /events; the oldest are dropped first
+const CAPACITY: usize = 1000;
+
+// How often a running task records a progress event
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+// What happened to a task
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Registered, // Accepted and added to the task list
+    Started,    // Began generating load
+    Progress,   // Still running (every 10 seconds)
+    Extended,   // Duration prolonged through /extend
+    Stopping,   // Stop requested
+    Finished,   // Ended with a result
+    Failed,     // Ended with an error
+    Killed,     // Aborted after the stop grace period
+    CleanedUp,  // Removed from the task list
+}
+
+// One entry of the event buffer
+#[derive(Serialize, Clone, Debug)]
+pub struct TaskEvent {
+    pub timestamp: f64,         // Unix time in seconds
+    pub task_id: String,
+    pub kind: EventKind,
+    pub detail: Option<String>, // e.g. elapsed time, error message
+}
+
+static EVENTS: Lazy<Mutex<VecDeque<TaskEvent>>> = Lazy::new(|| Mutex::new(VecDeque::with_capacity(CAPACITY)));
+
+// Add an event to the ring buffer
+pub fn record(task_id: &str, kind: EventKind, detail: Option<String>) {
+    let event = TaskEvent {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64(),
+        task_id: task_id.to_string(),
+        kind,
+        detail,
+    };
+    let mut events = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    if events.len() >= CAPACITY {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
+// Events recorded after `since` (Unix seconds), oldest first
+pub fn since(since: f64) -> Vec<TaskEvent> {
+    let events = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    events.iter().filter(|event| event.timestamp > since).cloned().collect()
+}
+
+// Drop the events `matches` selects (by task ID) recorded before `cutoff`; returns how many
+pub fn prune(cutoff: f64, matches: impl Fn(&str) -> bool) -> usize {
+    let mut events = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    let before = events.len();
+    events.retain(|event| event.timestamp >= cutoff || !matches(&event.task_id));
+    before - events.len()
+}
+
+// Run a task's future, recording a progress event every PROGRESS_INTERVAL
+pub async fn with_progress<F: Future>(task_id: &str, run: F) -> F::Output {
+    let started = Instant::now();
+    let mut tick = tokio::time::interval_at(tokio::time::Instant::now() + PROGRESS_INTERVAL, PROGRESS_INTERVAL);
+    tokio::pin!(run);
+    loop {
+        tokio::select! {
+            output = &mut run => return output,
+            _ = tick.tick() => record(
+                task_id,
+                EventKind::Progress,
+                Some(format!("{} seconds elapsed", started.elapsed().as_secs())),
+            ),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+// Page-fault workload: threads map a region, touch every page of it and unmap it again,
+// over and over, so the kernel keeps faulting pages in and tearing them down
+// Used to evaluate kernel memory-management changes (fault paths, reclaim, THP, mTHP)
+// Anonymous regions give minor faults; file-backed regions whose pages are dropped from
+// the page cache before every mapping give major faults, each one a read from the device
+
+use std::fs::OpenOptions;
+use std::hint::black_box;
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+use tokio::task;
+use crate::disk_stress::{remove_temp_file, track_temp_file};
+use crate::logs;
+use crate::result::{FaultStats, StressError, StressResult};
+
+const PAGE_SIZE: usize = 4096;
+
+// What backs the mapped regions
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Backing {
+    #[default]
+    Anonymous, // Private anonymous memory: minor faults, zero-filled pages
+    File,      // A test file dropped from the page cache before each mapping: major faults
+}
+
+impl Backing {
+    // Name used in requests and log lines
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backing::Anonymous => "anonymous",
+            Backing::File => "file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FaultOptions {
+    pub backing: Backing,
+    pub paths: Vec<PathBuf>, // Directories of the test files (file), threads spread round-robin (empty = working directory)
+}
+
+// Minor and major faults of the calling thread so far
+fn thread_faults() -> (u64, u64) {
+    // SAFETY: getrusage fills the zeroed struct it is given
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) } != 0 {
+        return (0, 0);
+    }
+    (usage.ru_minflt as u64, usage.ru_majflt as u64)
+}
+
+// One map/touch/unmap cycle over an anonymous region
+fn cycle_anonymous(bytes: usize, pass: u64) -> Result<(), String> {
+    // SAFETY: fresh private mapping, unmapped below; nothing else refers to it
+    unsafe {
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            bytes,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        if ptr == libc::MAP_FAILED {
+            return Err(format!("mapping failed: {}", std::io::Error::last_os_error()));
+        }
+        // One fault per 4 KiB page rather than one per transparent huge page
+        libc::madvise(ptr, bytes, libc::MADV_NOHUGEPAGE);
+        let base = ptr as *mut u8;
+        for offset in (0..bytes).step_by(PAGE_SIZE) {
+            base.add(offset).write_volatile((pass as u8) | 1);
+        }
+        libc::munmap(ptr, bytes);
+    }
+    Ok(())
+}
+
+// One map/read/unmap cycle over a test file, after dropping it from the page cache
+fn cycle_file(file: &std::fs::File, bytes: usize) -> Result<(), String> {
+    let fd = file.as_raw_fd();
+    // SAFETY: shared read-only mapping of an open file of at least `bytes`, unmapped below
+    unsafe {
+        libc::posix_fadvise(fd, 0, 0, libc::POSIX_FADV_DONTNEED);
+        let ptr = libc::mmap(std::ptr::null_mut(), bytes, libc::PROT_READ, libc::MAP_SHARED, fd, 0);
+        if ptr == libc::MAP_FAILED {
+            return Err(format!("mapping failed: {}", std::io::Error::last_os_error()));
+        }
+        // Without read-ahead every page is its own major fault
+        libc::madvise(ptr, bytes, libc::MADV_RANDOM);
+        let base = ptr as *const u8;
+        let mut sum = 0u8;
+        for offset in (0..bytes).step_by(PAGE_SIZE) {
+            sum = sum.wrapping_add(base.add(offset).read_volatile());
+        }
+        black_box(sum);
+        libc::munmap(ptr, bytes);
+    }
+    Ok(())
+}
+
+// Create a test file of `bytes` with non-zero content and flush it, so its pages can be dropped
+fn create_file(path: &PathBuf, bytes: usize) -> std::io::Result<std::fs::File> {
+    let mut file = OpenOptions::new().create(true).read(true).write(true).truncate(true).open(path)?;
+    let chunk = vec![0xa5u8; 1024 * 1024];
+    let mut left = bytes;
+    while left > 0 {
+        let part = left.min(chunk.len());
+        file.write_all(&chunk[..part])?;
+        left -= part;
+    }
+    file.sync_all()?;
+    Ok(file)
+}
+
+pub async fn stress_faults(
+    threads: usize,
+    region_mb: usize,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: String,
+    options: FaultOptions,
+) -> Result<StressResult, StressError> {
+    let bytes = region_mb.max(1) * 1024 * 1024;
+    let dirs = if options.paths.is_empty() { vec![PathBuf::from(".")] } else { options.paths.clone() };
+    let started = Instant::now();
+    let mut handles = Vec::new();
+
+    for thread_id in 0..threads {
+        let stop = Arc::clone(&stop_flag);
+        let task_id = task_id.clone();
+        // The task id keeps concurrent tasks in one directory off each other's files
+        let file_name = dirs[thread_id % dirs.len()]
+            .join(format!("fault_test_file_{}_{}", task_id.replace([':', '/'], "_"), thread_id));
+        let backing = options.backing;
+
+        let handle = task::spawn_blocking(move || {
+            let start = Instant::now();
+            let mut cycles = 0u64;
+            let mut error = None;
+
+            let file = match backing {
+                Backing::Anonymous => None,
+                Backing::File => {
+                    track_temp_file(&file_name);
+                    match create_file(&file_name, bytes) {
+                        Ok(file) => Some(file),
+                        Err(e) => {
+                            remove_temp_file(&file_name);
+                            let message = format!("[Thread {}] Failed to create {}: {}", thread_id, file_name.display(), e);
+                            return (0, 0, 0, Some(message));
+                        }
+                    }
+                }
+            };
+
+            // Faults of this thread only, so the engine's other work does not count
+            let (minor_before, major_before) = thread_faults();
+            while (duration == 0 || start.elapsed() < Duration::from_secs(duration))
+                && !stop.load(Ordering::SeqCst)
+            {
+                let outcome = match &file {
+                    Some(file) => cycle_file(file, bytes),
+                    None => cycle_anonymous(bytes, cycles),
+                };
+                if let Err(e) = outcome {
+                    error = Some(format!("[Thread {}] {}", thread_id, e));
+                    break;
+                }
+                cycles += 1;
+            }
+            let (minor_after, major_after) = thread_faults();
+
+            if file.is_some() {
+                remove_temp_file(&file_name);
+            }
+            logs::emit(&task_id, format!("[Thread {}] Page fault stress test completed.", thread_id));
+            (cycles, minor_after.saturating_sub(minor_before), major_after.saturating_sub(major_before), error)
+        });
+        handles.push(handle);
+    }
+
+    let mut result = StressResult::default();
+    let mut stats = FaultStats::default();
+    for handle in handles {
+        let (cycles, minor, major, error) = handle.await?;
+        result.iterations += cycles;
+        stats.minor += minor;
+        stats.major += major;
+        result.errors.extend(error);
+    }
+    result.elapsed_secs = started.elapsed().as_secs_f64();
+    result.stopped = stop_flag.load(Ordering::SeqCst);
+    if result.elapsed_secs > 0.0 {
+        stats.minor_per_s = stats.minor as f64 / result.elapsed_secs;
+        stats.major_per_s = stats.major as f64 / result.elapsed_secs;
+    }
+
+    logs::emit(&task_id, format!(
+        "{} regions of {} MB: {} cycles, {:.0} minor and {:.0} major faults/s over {} threads",
+        options.backing.name(),
+        region_mb,
+        result.iterations,
+        stats.minor_per_s,
+        stats.major_per_s,
+        threads
+    ));
+    result.faults = Some(stats);
+    Ok(result)
+}
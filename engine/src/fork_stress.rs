@@ -1,35 +1,97 @@
+use std::collections::HashMap;
+use std::io;
 use std::process::exit;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
-use std::time::Duration;
-use libc::{fork, waitpid, c_int};
+use std::time::{Duration, Instant};
+use libc::{fork, kill, pid_t, waitpid, c_int, SIGKILL, SIGTERM, WNOHANG};
+use once_cell::sync::Lazy;
+use crate::result::{StressError, StressResult};
 
-pub fn stress_fork(num_processes: usize, duration: u64) {
-    let mut children = vec![];
+// Children of each running fork test that have not been reaped yet, so a stuck
+// test's processes can still be killed (see kill_children)
+static CHILDREN: Lazy<Mutex<HashMap<String, Vec<pid_t>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// How often the parent checks for exited children and the stop flag
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn stress_fork(num_processes: usize, duration: u64, stop_flag: &AtomicBool, task_id: &str) -> Result<StressResult, StressError> {
+    let mut forked = 0u64;
+    let mut fork_error = None;
+    let started = Instant::now();
 
     for _ in 0..num_processes {
+        if stop_flag.load(Ordering::SeqCst) {
+            break;
+        }
         unsafe {
             let pid = fork();
             if pid == 0 {
-                // Child process
+                // Child process; the engine's own SIGTERM/SIGINT handlers must not outlive the fork
+                libc::signal(SIGTERM, libc::SIG_DFL);
+                libc::signal(libc::SIGINT, libc::SIG_DFL);
                 thread::sleep(Duration::from_secs(duration));
                 exit(0);
             } else if pid > 0 {
                 // Parent process
-                children.push(pid);
+                CHILDREN
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .entry(task_id.to_string())
+                    .or_default()
+                    .push(pid);
+                forked += 1;
                 thread::sleep(Duration::from_millis(1));
             } else {
-                eprintln!("Fork failed");
-                exit(1);
+                // Stop forking, but still reap the children created so far
+                fork_error = Some(io::Error::last_os_error());
+                break;
             }
         }
     }
-    println!("Created {} child processes.", children.len());
 
-    // Parent waits for all children
-    for pid in children {
-        unsafe {
-            let mut status: c_int = 0;
-            waitpid(pid, &mut status, 0);
+    // Parent reaps the children as they exit, and asks them to end on a stop request
+    let mut terminated = false;
+    loop {
+        {
+            let mut all = CHILDREN.lock().unwrap_or_else(|e| e.into_inner());
+            let pids = all.entry(task_id.to_string()).or_default();
+            pids.retain(|&pid| unsafe {
+                let mut status: c_int = 0;
+                waitpid(pid, &mut status, WNOHANG) == 0
+            });
+            if pids.is_empty() {
+                all.remove(task_id);
+                break;
+            }
+            if !terminated && stop_flag.load(Ordering::SeqCst) {
+                for &pid in pids.iter() {
+                    unsafe { kill(pid, SIGTERM) };
+                }
+                terminated = true;
+            }
         }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    match fork_error {
+        Some(e) => Err(StressError::Fork(format!("{} after {} child processes", e, forked))),
+        None => Ok(StressResult {
+            iterations: forked,
+            elapsed_secs: started.elapsed().as_secs_f64(),
+            stopped: stop_flag.load(Ordering::SeqCst),
+            ..Default::default()
+        }),
+    }
+}
+
+// Kill the remaining children of a fork test outright; returns how many were signalled
+pub fn kill_children(task_id: &str) -> usize {
+    let all = CHILDREN.lock().unwrap_or_else(|e| e.into_inner());
+    let pids = all.get(task_id).map(Vec::as_slice).unwrap_or_default();
+    for &pid in pids {
+        unsafe { kill(pid, SIGKILL) };
     }
+    pids.len()
 }
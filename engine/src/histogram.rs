@@ -0,0 +1,127 @@
+//! Latency histograms
+//!
+//! A small HDR-style histogram for timings recorded in hot loops: fixed memory,
+//! constant-time recording, and roughly 3% relative precision at any magnitude.
+//! Values are nanoseconds; each power of two is split into 16 linear buckets.
+//! Worker threads keep their own histogram and the results are merged at the end.
+
+use std::time::Duration;
+use crate::result::Percentiles;
+
+/// Linear buckets per power of two
+const SUB_BUCKETS: usize = 16;
+
+/// log2(SUB_BUCKETS)
+const SUB_BITS: u32 = 4;
+
+/// Values below SUB_BUCKETS get a bucket each, then 16 per power of two up to 2^64
+const BUCKETS: usize = SUB_BUCKETS + (64 - SUB_BITS as usize) * SUB_BUCKETS;
+
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    sum: u128, // Nanoseconds, for an exact mean
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram { counts: vec![0; BUCKETS], count: 0, sum: 0, max: 0 }
+    }
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, value: Duration) {
+        let nanos = value.as_nanos().min(u64::MAX as u128) as u64;
+        self.counts[bucket(nanos)] += 1;
+        self.count += 1;
+        self.sum += nanos as u128;
+        self.max = self.max.max(nanos);
+    }
+
+    /// Number of recorded values
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Add another histogram's values to this one
+    pub fn merge(&mut self, other: &Histogram) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.max = self.max.max(other.max);
+    }
+
+    /// Value at quantile q (0.0 - 1.0) in nanoseconds: the midpoint of its bucket
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let (low, width) = bucket_range(index);
+                return (low + width / 2).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Non-empty buckets as (lowest value, width, count), in nanoseconds
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| {
+                let (low, width) = bucket_range(index);
+                (low, width, count)
+            })
+    }
+
+    /// Summary in microseconds; None if nothing was recorded
+    pub fn percentiles(&self) -> Option<Percentiles> {
+        if self.count == 0 {
+            return None;
+        }
+        let micros = |nanos: u64| nanos as f64 / 1000.0;
+        Some(Percentiles {
+            count: self.count,
+            mean_us: self.sum as f64 / self.count as f64 / 1000.0,
+            p50_us: micros(self.quantile(0.50)),
+            p90_us: micros(self.quantile(0.90)),
+            p95_us: micros(self.quantile(0.95)),
+            p99_us: micros(self.quantile(0.99)),
+            p999_us: micros(self.quantile(0.999)),
+            max_us: micros(self.max),
+        })
+    }
+}
+
+fn bucket(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+    let magnitude = 63 - nanos.leading_zeros(); // Position of the highest bit, >= SUB_BITS
+    let sub = (nanos >> (magnitude - SUB_BITS)) as usize & (SUB_BUCKETS - 1);
+    SUB_BUCKETS + (magnitude - SUB_BITS) as usize * SUB_BUCKETS + sub
+}
+
+/// Lowest value and width of a bucket
+fn bucket_range(index: usize) -> (u64, u64) {
+    if index < SUB_BUCKETS {
+        return (index as u64, 1);
+    }
+    let shift = ((index - SUB_BUCKETS) / SUB_BUCKETS) as u32;
+    let sub = ((index - SUB_BUCKETS) % SUB_BUCKETS) as u64;
+    ((SUB_BUCKETS as u64 + sub) << shift, 1 << shift)
+}
@@ -0,0 +1,245 @@
+//! InfluxDB export
+//!
+//! With `MOGWAI_INFLUX_URL`, `MOGWAI_INFLUX_ORG`, `MOGWAI_INFLUX_BUCKET` and
+//! `MOGWAI_INFLUX_TOKEN` set, the engine writes line protocol to the InfluxDB 2 write
+//! API every `MOGWAI_INFLUX_INTERVAL_SECS` (default 10), so stress runs can be charted
+//! next to application metrics:
+//!
+//! - `mogwai_host`: cpu_percent, memory_used_mb, disk_read_mb_s, disk_write_mb_s, running_tasks
+//! - `mogwai_task` (each running task): elapsed_secs
+//! - `mogwai_result` (each task finished since the last write): iterations, elapsed_secs,
+//!   achieved_load, mb_per_s, minor_faults_per_s, major_faults_per_s, failed
+//! - `mogwai_event` (each lifecycle event since the last write, see `events`): detail
+//!
+//! Every point is tagged with `node` (`MOGWAI_NODE_NAME`, or the hostname); task points
+//! add `task_id`, running tasks `type`, and events `kind`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::{events, metrics};
+use crate::thread_manager::{self, GLOBAL_REGISTRY};
+
+/// Write interval unless MOGWAI_INFLUX_INTERVAL_SECS is set
+const DEFAULT_INTERVAL_SECS: u64 = 10;
+
+/// Give up on a write after this long; the next one carries fresh points anyway
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where and how often to write
+struct Config {
+    url: String, // Write endpoint with org, bucket and precision in the query
+    token: String,
+    interval: Duration,
+    node: String,
+}
+
+impl Config {
+    /// None unless URL, org, bucket and token are all set
+    fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let (base, org, bucket, token) = (
+            var("MOGWAI_INFLUX_URL")?,
+            var("MOGWAI_INFLUX_ORG")?,
+            var("MOGWAI_INFLUX_BUCKET")?,
+            var("MOGWAI_INFLUX_TOKEN")?,
+        );
+        let interval = var("MOGWAI_INFLUX_INTERVAL_SECS")
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/api/v2/write", base.trim_end_matches('/')),
+            [("org", org.as_str()), ("bucket", bucket.as_str()), ("precision", "ms")],
+        )
+        .ok()?;
+        Some(Config {
+            url: url.to_string(),
+            token,
+            interval: Duration::from_secs(interval),
+            node: metrics::node_name(),
+        })
+    }
+}
+
+/// Start writing in the background if InfluxDB is configured
+pub fn start() {
+    let Some(config) = Config::from_env() else {
+        return;
+    };
+    tracing::info!(interval_secs = config.interval.as_secs(), "Writing metrics and task events to InfluxDB");
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder().timeout(WRITE_TIMEOUT).build().unwrap_or_default();
+        let mut tick = tokio::time::interval(config.interval);
+        let mut last_write = now_secs();
+        let mut failing = false;
+        loop {
+            tick.tick().await;
+            let written_at = now_secs();
+            let body = collect(&config.node, last_write);
+            last_write = written_at;
+
+            match write(&client, &config, body).await {
+                Ok(()) if failing => {
+                    tracing::info!("InfluxDB writes work again");
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Report a failing endpoint once, not on every write
+                Err(e) if !failing => {
+                    tracing::warn!(error = %e, "InfluxDB write failed");
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+/// One line of line protocol
+struct Point {
+    line: String,
+    has_fields: bool,
+}
+
+impl Point {
+    fn new(measurement: &str, tags: &[(&str, &str)]) -> Self {
+        let mut line = escape(measurement, &[',', ' ']);
+        for (key, value) in tags {
+            line.push_str(&format!(",{}={}", escape(key, &[',', '=', ' ']), escape(value, &[',', '=', ' '])));
+        }
+        Point { line, has_fields: false }
+    }
+
+    fn field(mut self, key: &str, value: impl FieldValue) -> Self {
+        self.line.push(if self.has_fields { ',' } else { ' ' });
+        self.line.push_str(&format!("{}={}", escape(key, &[',', '=', ' ']), value.render()));
+        self.has_fields = true;
+        self
+    }
+
+    fn field_opt(self, key: &str, value: Option<impl FieldValue>) -> Self {
+        match value {
+            Some(value) => self.field(key, value),
+            None => self,
+        }
+    }
+
+    /// The finished line at a Unix time in seconds
+    fn at(self, timestamp: f64) -> String {
+        format!("{} {}", self.line, (timestamp * 1000.0) as i64)
+    }
+}
+
+/// Field value types of line protocol
+trait FieldValue {
+    fn render(&self) -> String;
+}
+
+impl FieldValue for f64 {
+    fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FieldValue for u64 {
+    fn render(&self) -> String {
+        format!("{}i", self)
+    }
+}
+
+impl FieldValue for bool {
+    fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FieldValue for &str {
+    fn render(&self) -> String {
+        format!("\"{}\"", self.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Backslash-escape the characters line protocol treats specially in names and tags
+fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(&c) || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The current points, and those of tasks and events after `since` (Unix seconds)
+fn collect(node: &str, since: f64) -> String {
+    let now = now_secs();
+    let tasks = thread_manager::task_infos(&GLOBAL_REGISTRY);
+    let host = metrics::sample(tasks.len());
+    let mut lines = vec![Point::new("mogwai_host", &[("node", node)])
+        .field("cpu_percent", host.cpu_percent as f64)
+        .field("memory_used_mb", host.memory_used_mb)
+        .field("disk_read_mb_s", host.disk_read_mb_s)
+        .field("disk_write_mb_s", host.disk_write_mb_s)
+        .field("running_tasks", tasks.len() as u64)
+        .at(now)];
+
+    for task in &tasks {
+        lines.push(
+            Point::new("mogwai_task", &[("node", node), ("task_id", &task.id), ("type", &task.test_type)])
+                .field("elapsed_secs", now - task.started_at)
+                .at(now),
+        );
+    }
+
+    for finished in thread_manager::list_results().iter().filter(|entry| entry.finished_at > since) {
+        let result = finished.result.as_ref();
+        lines.push(
+            Point::new("mogwai_result", &[("node", node), ("task_id", &finished.task_id)])
+                .field("failed", finished.error.is_some())
+                .field_opt("iterations", result.map(|r| r.iterations))
+                .field_opt("elapsed_secs", result.map(|r| r.elapsed_secs))
+                .field_opt("achieved_load", result.and_then(|r| r.achieved_load))
+                .field_opt("mb_per_s", result.and_then(|r| r.mb_per_s))
+                .field_opt("minor_faults_per_s", result.and_then(|r| r.faults.as_ref()).map(|f| f.minor_per_s))
+                .field_opt("major_faults_per_s", result.and_then(|r| r.faults.as_ref()).map(|f| f.major_per_s))
+                .at(finished.finished_at),
+        );
+    }
+
+    for event in events::since(since) {
+        let kind = serde_json::to_value(event.kind).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+        lines.push(
+            Point::new("mogwai_event", &[("node", node), ("task_id", &event.task_id), ("kind", &kind)])
+                .field("detail", event.detail.as_deref().unwrap_or(""))
+                .at(event.timestamp),
+        );
+    }
+
+    lines.join("\n")
+}
+
+/// Send one batch of lines
+async fn write(client: &reqwest::Client, config: &Config, body: String) -> Result<(), String> {
+    let response = client
+        .post(&config.url)
+        .header("Authorization", format!("Token {}", config.token))
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("{} {}", status, text.trim()));
+    }
+    Ok(())
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
@@ -0,0 +1,106 @@
+//! JUnit XML rendering
+//!
+//! Maps test runs onto JUnit test suites and cases, so CI servers (Jenkins, GitLab,
+//! GitHub Actions reporters) show them in their test report views. A failed check
+//! (e.g. a missed metric threshold) becomes a `<failure>`, a test that could not run
+//! an `<error>`.
+
+/// A group of test cases, e.g. one scenario step
+pub struct TestSuite {
+    pub name: String,
+    pub timestamp: String, // ISO 8601 start time
+    pub hostname: String,
+    pub cases: Vec<TestCase>,
+}
+
+/// One test with its outcome
+pub struct TestCase {
+    pub name: String,
+    pub classname: String,
+    pub time_secs: f64,
+    pub outcome: CaseOutcome,
+    pub output: String, // Shown as the case's <system-out> when not empty
+}
+
+pub enum CaseOutcome {
+    Passed,
+    Failed(Vec<String>), // Checks that failed
+    Error(String),       // Why the test could not run
+}
+
+/// Render the suites as a JUnit XML document
+pub fn render(suites: &[TestSuite]) -> String {
+    let count = |wanted: fn(&CaseOutcome) -> bool| -> usize {
+        suites.iter().flat_map(|suite| &suite.cases).filter(|case| wanted(&case.outcome)).count()
+    };
+    let total_time: f64 = suites.iter().flat_map(|suite| &suite.cases).map(|case| case.time_secs).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"mogwai\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        count(|_| true),
+        count(|outcome| matches!(outcome, CaseOutcome::Failed(_))),
+        count(|outcome| matches!(outcome, CaseOutcome::Error(_))),
+        total_time
+    ));
+    for suite in suites {
+        let failures = suite.cases.iter().filter(|case| matches!(case.outcome, CaseOutcome::Failed(_))).count();
+        let errors = suite.cases.iter().filter(|case| matches!(case.outcome, CaseOutcome::Error(_))).count();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\" timestamp=\"{}\" hostname=\"{}\">\n",
+            escape(&suite.name),
+            suite.cases.len(),
+            failures,
+            errors,
+            suite.cases.iter().map(|case| case.time_secs).sum::<f64>(),
+            escape(&suite.timestamp),
+            escape(&suite.hostname)
+        ));
+        for case in &suite.cases {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+                escape(&case.name),
+                escape(&case.classname),
+                case.time_secs
+            ));
+            match &case.outcome {
+                CaseOutcome::Passed => {}
+                CaseOutcome::Failed(checks) => xml.push_str(&format!(
+                    "      <failure message=\"{}\" type=\"threshold\">{}</failure>\n",
+                    escape(&checks.join("; ")),
+                    escape(&checks.join("\n"))
+                )),
+                CaseOutcome::Error(message) => xml.push_str(&format!(
+                    "      <error message=\"{}\" type=\"error\">{}</error>\n",
+                    escape(message),
+                    escape(message)
+                )),
+            }
+            if !case.output.is_empty() {
+                xml.push_str(&format!("      <system-out>{}</system-out>\n", escape(&case.output)));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for XML attributes and content, dropping characters XML 1.0 cannot hold
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\t' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
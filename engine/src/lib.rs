@@ -1,5 +1,50 @@
+pub mod agent;
+pub mod artifacts;
+pub mod builder;
+pub mod calibration;
+pub mod capabilities;
+pub mod cgroup;
+pub mod clients;
+pub mod codec_stress;
+pub mod cors;
 pub mod cpu_stress;
 pub mod memory_stress;
 pub mod disk_stress;
+pub mod disk_fill;
+pub mod estimate;
+pub mod events;
+pub mod fault_stress;
 pub mod fork_stress;
-pub mod thread_manager;
\ No newline at end of file
+pub mod histogram;
+pub mod influx;
+pub mod junit;
+pub mod limits;
+pub mod listing;
+pub mod lock_stress;
+pub mod logging;
+pub mod logs;
+pub mod mdns;
+pub mod metrics;
+pub mod otlp;
+pub mod plugin;
+pub mod push;
+pub mod remote_write;
+pub mod request_id;
+pub mod result;
+pub mod retention;
+pub mod s3;
+pub mod server;
+pub mod soak;
+pub mod stress;
+pub mod sys_info;
+pub mod syscall_stress;
+pub mod thread_manager;
+pub mod watchdog;
+
+pub use builder::{CodecStress, CpuStress, DiskStress, FaultStress, ForkStress, LockStress, MemoryStress, SyscallStress};
+pub use codec_stress::Codec;
+pub use fault_stress::Backing;
+pub use lock_stress::Primitive;
+pub use memory_stress::{Advice, Touch};
+pub use result::{StressError, StressResult};
+pub use syscall_stress::Syscall;
@@ -0,0 +1,211 @@
+//! Operator safety limits
+//!
+//! Limits every incoming request has to respect, so engines can be deployed broadly
+//! without a mistyped request (say a 100 GB memory test) taking a node down. They are
+//! read once from the JSON file named by `MOGWAI_LIMITS_FILE` (default
+//! `/etc/mogwai/limits.json`), e.g.
+//!
+//! ```json
+//! {"max_memory_percent": 50, "max_disk_percent": 80, "allow_fork": false, "max_duration": 3600, "action": "clamp"}
+//! ```
+//!
+//! and field by field from `MOGWAI_MAX_MEMORY_PERCENT`, `MOGWAI_MAX_DISK_PERCENT`,
+//! `MOGWAI_ALLOW_FORK`, `MOGWAI_MAX_DURATION_SECS` and `MOGWAI_LIMIT_ACTION`, which win
+//! over the file. Unset limits do not apply.
+//!
+//! With the `reject` action (the default) a request beyond a limit is refused. With
+//! `clamp` it is scaled down to the limit where that is possible (duration, the `size`
+//! of memory, disk and page fault tests, the fill target) and refused otherwise. Fork
+//! tests are always refused when `allow_fork` is false.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use crate::disk_fill;
+use crate::result::StressError;
+use crate::stress::{StressTest, TestContext, TestParams};
+
+/// What happens to a request beyond a limit
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    #[default]
+    Reject,
+    Clamp,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Limits {
+    pub max_memory_percent: Option<f64>, // Memory one test may allocate, in percent of the node's total
+    pub max_disk_percent: Option<f64>,   // How full a test may make a filesystem, in percent
+    pub allow_fork: Option<bool>,        // false = fork tests are refused
+    pub max_duration: Option<u64>,       // Seconds, extensions included; also refuses "until stopped"
+    pub action: Action,
+}
+
+static LIMITS: Lazy<Limits> = Lazy::new(|| {
+    let limits = load();
+    if limits.max_memory_percent.is_some()
+        || limits.max_disk_percent.is_some()
+        || limits.allow_fork.is_some()
+        || limits.max_duration.is_some()
+    {
+        tracing::info!(limits = %serde_json::to_string(&limits).unwrap_or_default(), "Safety limits");
+    }
+    limits
+});
+
+/// The limits in force
+pub fn current() -> &'static Limits {
+    &LIMITS
+}
+
+fn load() -> Limits {
+    let path = std::env::var("MOGWAI_LIMITS_FILE").unwrap_or_else(|_| "/etc/mogwai/limits.json".to_string());
+    let mut limits = match std::fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+            tracing::warn!(path = %path, error = %e, "Limits file is invalid; using the environment only");
+            Limits::default()
+        }),
+        Err(_) => Limits::default(),
+    };
+    let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    if let Some(percent) = var("MOGWAI_MAX_MEMORY_PERCENT").and_then(|v| v.parse().ok()) {
+        limits.max_memory_percent = Some(percent);
+    }
+    if let Some(percent) = var("MOGWAI_MAX_DISK_PERCENT").and_then(|v| v.parse().ok()) {
+        limits.max_disk_percent = Some(percent);
+    }
+    if let Some(allow) = var("MOGWAI_ALLOW_FORK") {
+        limits.allow_fork = Some(matches!(allow.as_str(), "1" | "true" | "yes"));
+    }
+    if let Some(secs) = var("MOGWAI_MAX_DURATION_SECS").and_then(|v| v.parse().ok()) {
+        limits.max_duration = Some(secs).filter(|&secs| secs > 0);
+    }
+    match var("MOGWAI_LIMIT_ACTION").as_deref() {
+        Some("clamp") => limits.action = Action::Clamp,
+        Some("reject") => limits.action = Action::Reject,
+        _ => {}
+    }
+    limits
+}
+
+/// Hold a request to the limits: Ok with the adjustments made to it (clamp only),
+/// or the error refusing it
+pub fn enforce(test: &dyn StressTest, params: &mut TestParams) -> Result<Vec<String>, StressError> {
+    let limits = current();
+    let clamp = limits.action == Action::Clamp;
+    let mut adjustments = Vec::new();
+
+    if test.name() == "fork" && limits.allow_fork == Some(false) {
+        return Err(refused("fork tests are not allowed on this engine".to_string()));
+    }
+
+    if let Some(max) = limits.max_duration {
+        let duration = params.duration.unwrap_or(10);
+        if duration == 0 || duration > max {
+            let asked = if duration == 0 { "until stopped".to_string() } else { format!("{} seconds", duration) };
+            if !clamp {
+                return Err(refused(format!("duration {} exceeds the limit of {} seconds", asked, max)));
+            }
+            params.duration = Some(max);
+            adjustments.push(format!("duration {} clamped to {} seconds", asked, max));
+        }
+    }
+
+    // Before estimating anything, as the estimate of a fill test checks fill_percent
+    let fill_mode = params.extra.get("mode").and_then(Value::as_str) == Some("fill");
+    if let (Some(max_percent), true) = (limits.max_disk_percent, fill_mode) {
+        let fill = params.extra.get("fill_percent").and_then(Value::as_f64).unwrap_or(90.0);
+        if fill > max_percent {
+            if !clamp {
+                return Err(refused(format!("fill_percent {} exceeds the limit of {}%", fill, max_percent)));
+            }
+            params.extra.insert("fill_percent".to_string(), max_percent.into());
+            adjustments.push(format!("fill_percent {} clamped to {}", fill, max_percent));
+        }
+    }
+
+    if let Some(max_percent) = limits.max_memory_percent {
+        let limit_mb = (total_memory_mb() as f64 * max_percent / 100.0) as u64;
+        let needed = test.estimate(&TestContext::new(String::new(), params))?.memory_mb;
+        if needed > limit_mb {
+            let message = format!("{} MB of memory exceeds the limit of {} MB ({}%)", needed, limit_mb, max_percent);
+            let threads = TestContext::new(String::new(), params).intensity.max(1) as u64;
+            let size = resize(test, params, clamp, limit_mb / threads, &message)?;
+            adjustments.push(format!("size clamped to {} MB per thread: {}", size, message));
+        }
+    }
+
+    if let (Some(max_percent), false) = (limits.max_disk_percent, fill_mode) {
+        let estimate = test.estimate(&TestContext::new(String::new(), params))?;
+        let file_mb = params.size.unwrap_or(256).max(1) as u64;
+        for (dir, needed) in estimate.disk_mb {
+            let (used, size) = filesystem_mb(&dir)?;
+            let limit_mb = ((size as f64 * max_percent / 100.0) as u64).saturating_sub(used);
+            if needed > limit_mb {
+                let message = format!(
+                    "{} MB in {} would fill it beyond the limit of {}% ({} MB left)",
+                    needed,
+                    dir.display(),
+                    max_percent,
+                    limit_mb
+                );
+                // Each of the directory's files is `size` MB
+                let files = (needed / file_mb).max(1);
+                let size = resize(test, params, clamp, limit_mb / files, &message)?;
+                adjustments.push(format!("size clamped to {} MB per file: {}", size, message));
+            }
+        }
+    }
+    Ok(adjustments)
+}
+
+/// Seconds a running task may be extended by, given its duration so far
+pub fn extension(duration: u64, seconds: u64) -> Result<u64, StressError> {
+    let limits = current();
+    let Some(max) = limits.max_duration else {
+        return Ok(seconds);
+    };
+    let allowed = max.saturating_sub(duration);
+    if seconds <= allowed {
+        return Ok(seconds);
+    }
+    if limits.action == Action::Clamp && allowed > 0 {
+        return Ok(allowed);
+    }
+    Err(refused(format!(
+        "extending by {} seconds exceeds the limit of {} seconds ({} seconds left)",
+        seconds, max, allowed
+    )))
+}
+
+/// Shrink a memory, disk or page fault test's `size` (MB per thread or file) to `size`, or refuse
+/// the request with `message` if that is not possible
+fn resize(test: &dyn StressTest, params: &mut TestParams, clamp: bool, size: u64, message: &str) -> Result<u64, StressError> {
+    if !clamp || !matches!(test.name(), "mem" | "disk" | "pagefault") || size == 0 {
+        return Err(refused(message.to_string()));
+    }
+    // Another directory may have asked for less already
+    let size = size.min(params.size.unwrap_or(256) as u64);
+    params.size = Some(size as usize);
+    Ok(size)
+}
+
+fn refused(message: String) -> StressError {
+    StressError::Limit(message)
+}
+
+fn total_memory_mb() -> u64 {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    system.total_memory() / (1024 * 1024)
+}
+
+/// Used and total MB of the filesystem holding dir
+fn filesystem_mb(dir: &Path) -> Result<(u64, u64), StressError> {
+    let usage = disk_fill::usage(dir).map_err(StressError::Io)?;
+    Ok((usage.used / (1024 * 1024), (usage.used + usage.available) / (1024 * 1024)))
+}
@@ -0,0 +1,153 @@
+//! Paging, sorting and filtering of the task listings (`/tasks` and `/results`)
+//!
+//! Without query parameters a listing is the plain array it always was. With any of
+//! `limit`, `offset`, `sort`, `state`, `type` or `node` it is wrapped in an envelope:
+//! `{"items": [...], "total": <matching entries>, "offset": 0, "limit": 50}`.
+//! `sort` names a field, descending with a leading `-` (e.g. `sort=-finished_at`);
+//! filters take one value or several separated by commas.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use crate::result::StressError;
+use crate::thread_manager::{TaskInfo, TaskResult, TaskState};
+
+/// Query parameters of a listing
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub sort: Option<String>,
+    pub state: Option<String>,
+    #[serde(rename = "type")]
+    pub test_type: Option<String>,
+    pub node: Option<String>,
+}
+
+/// One page of a listing, with the metadata to fetch the next
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,          // Entries matching the filters, on all pages
+    pub offset: usize,
+    pub limit: Option<usize>,  // None when the page runs to the end
+}
+
+/// An entry of a listing: the fields it can be filtered and sorted by
+pub trait Listed {
+    /// Fields `sort` accepts
+    const SORT_FIELDS: &'static [&'static str];
+    /// The value of a sort field, or of `state`, `type` or `node`
+    fn field(&self, name: &str) -> Value;
+}
+
+impl Listed for TaskInfo {
+    const SORT_FIELDS: &'static [&'static str] = &["id", "type", "state", "node", "name", "started_at", "duration"];
+
+    fn field(&self, name: &str) -> Value {
+        match name {
+            "id" => Value::from(self.id.as_str()),
+            "type" => Value::from(self.test_type.as_str()),
+            "state" => Value::from(match self.state {
+                TaskState::Running => "running",
+                TaskState::Stopping => "stopping",
+            }),
+            "node" => self.node.as_deref().map_or(Value::Null, Value::from),
+            "name" => self.name.as_deref().map_or(Value::Null, Value::from),
+            "started_at" => Value::from(self.started_at),
+            "duration" => Value::from(self.duration),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl Listed for TaskResult {
+    const SORT_FIELDS: &'static [&'static str] = &["task_id", "type", "state", "node", "finished_at"];
+
+    fn field(&self, name: &str) -> Value {
+        match name {
+            "task_id" => Value::from(self.task_id.as_str()),
+            "type" => self.test_type.as_deref().map_or(Value::Null, Value::from),
+            // failed, stopped (by a stop request or the deadline) or completed
+            "state" => Value::from(if self.error.is_some() {
+                "failed"
+            } else if self.stop.is_some() || self.result.as_ref().is_some_and(|result| result.stopped) {
+                "stopped"
+            } else {
+                "completed"
+            }),
+            "node" => self.node.as_deref().map_or(Value::Null, Value::from),
+            "finished_at" => Value::from(self.finished_at),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl ListQuery {
+    /// Whether the caller asked for anything but the plain listing
+    pub fn is_empty(&self) -> bool {
+        self.limit.is_none()
+            && self.offset.is_none()
+            && self.sort.is_none()
+            && self.state.is_none()
+            && self.test_type.is_none()
+            && self.node.is_none()
+    }
+
+    /// Filter, sort and cut `entries`, which come in their default order
+    pub fn page<T: Listed>(&self, mut entries: Vec<T>) -> Result<Page<T>, StressError> {
+        if self.limit == Some(0) {
+            return Err(StressError::InvalidParameter("limit must be at least 1".to_string()));
+        }
+        let filters = [("state", &self.state), ("type", &self.test_type), ("node", &self.node)];
+        entries.retain(|entry| {
+            filters.iter().all(|(name, wanted)| match wanted {
+                None => true,
+                Some(wanted) => {
+                    let value = entry.field(name);
+                    wanted.split(',').any(|wanted| value.as_str() == Some(wanted.trim()))
+                }
+            })
+        });
+
+        if let Some(sort) = &self.sort {
+            let (name, descending) = match sort.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (sort.as_str(), false),
+            };
+            if !T::SORT_FIELDS.contains(&name) {
+                return Err(StressError::InvalidParameter(format!(
+                    "cannot sort by {}; use one of {}",
+                    name,
+                    T::SORT_FIELDS.join(", ")
+                )));
+            }
+            // Stable, so entries with equal values keep their default order; missing values go last
+            entries.sort_by(|a, b| match (a.field(name), b.field(name)) {
+                (Value::Null, Value::Null) => Ordering::Equal,
+                (Value::Null, _) => Ordering::Greater,
+                (_, Value::Null) => Ordering::Less,
+                (a, b) if descending => compare(&b, &a),
+                (a, b) => compare(&a, &b),
+            });
+        }
+
+        let total = entries.len();
+        let offset = self.offset.unwrap_or(0);
+        let items = entries
+            .into_iter()
+            .skip(offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(Page { items, total, offset, limit: self.limit })
+    }
+}
+
+/// Numbers by value, text alphabetically
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
@@ -0,0 +1,190 @@
+// Lock-contention workload: threads take turns on shared counters behind a Mutex or an
+// RwLock, holding the lock for a configurable critical section, and report how often
+// they got it and how long they waited for it
+// Reproduces contention pathologies (convoys, reader or writer starvation) of services
+
+use std::hint::black_box;
+use std::sync::{Arc, Mutex, RwLock, TryLockError, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use tokio::task;
+use crate::artifacts;
+use crate::histogram::Histogram;
+use crate::logs;
+use crate::result::{LockStats, StressError, StressResult};
+
+// Which lock guards the counters
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Primitive {
+    #[default]
+    Mutex,
+    RwLock, // Readers share it, writers take it alone
+}
+
+impl Primitive {
+    // Name used in requests and log lines
+    pub fn name(&self) -> &'static str {
+        match self {
+            Primitive::Mutex => "mutex",
+            Primitive::RwLock => "rwlock",
+        }
+    }
+}
+
+// Shape of the contention
+#[derive(Debug, Clone, Copy)]
+pub struct LockOptions {
+    pub primitive: Primitive,
+    pub counters: usize,   // Shared counters, each behind its own lock; threads pick one at random each time
+    pub hold: Duration,    // Critical section: how long a thread spins while holding the lock
+    pub think: Duration,   // Work between two acquisitions, outside the lock
+    pub read_percent: f64, // Share of RwLock acquisitions that only read
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        LockOptions {
+            primitive: Primitive::Mutex,
+            counters: 1,
+            hold: Duration::from_micros(1),
+            think: Duration::ZERO,
+            read_percent: 80.0,
+        }
+    }
+}
+
+// The counters every thread contends on
+enum Shared {
+    Mutex(Vec<Mutex<u64>>),
+    RwLock(Vec<RwLock<u64>>),
+}
+
+// What one thread did
+struct ThreadOutcome {
+    acquisitions: u64,
+    contended: u64, // Acquisitions that found the lock taken
+    waits: Histogram,
+}
+
+// Busy-wait, so the thread keeps its core like real work would (sleeping would hand it over)
+fn spin(time: Duration) {
+    if time.is_zero() {
+        return;
+    }
+    let start = Instant::now();
+    while start.elapsed() < time {
+        std::hint::spin_loop();
+    }
+}
+
+pub async fn stress_lock(
+    threads: usize,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: String,
+    options: LockOptions,
+) -> Result<StressResult, StressError> {
+    let counters = options.counters.max(1);
+    let shared = Arc::new(match options.primitive {
+        Primitive::Mutex => Shared::Mutex((0..counters).map(|_| Mutex::new(0)).collect()),
+        Primitive::RwLock => Shared::RwLock((0..counters).map(|_| RwLock::new(0)).collect()),
+    });
+    let started = Instant::now();
+    let mut handles = Vec::new();
+
+    for thread_id in 0..threads {
+        let shared = Arc::clone(&shared);
+        let stop = Arc::clone(&stop_flag);
+        let task_id = task_id.clone();
+
+        let handle = task::spawn_blocking(move || {
+            let start = Instant::now();
+            let mut rng = StdRng::seed_from_u64(thread_id as u64);
+            let read_fraction = (options.read_percent / 100.0).clamp(0.0, 1.0);
+            let mut outcome = ThreadOutcome { acquisitions: 0, contended: 0, waits: Histogram::new() };
+
+            while (duration == 0 || start.elapsed() < Duration::from_secs(duration))
+                && !stop.load(Ordering::SeqCst)
+            {
+                let counter = if counters == 1 { 0 } else { rng.random_range(0..counters) };
+                let asked = Instant::now();
+                let (waited, contended) = match &*shared {
+                    Shared::Mutex(locks) => {
+                        let (mut guard, contended) = match locks[counter].try_lock() {
+                            Ok(guard) => (guard, false),
+                            Err(TryLockError::WouldBlock) => (locks[counter].lock().unwrap_or_else(|e| e.into_inner()), true),
+                            Err(TryLockError::Poisoned(e)) => (e.into_inner(), false),
+                        };
+                        let waited = asked.elapsed();
+                        spin(options.hold);
+                        *guard += 1;
+                        (waited, contended)
+                    }
+                    Shared::RwLock(locks) if rng.random_bool(read_fraction) => {
+                        let (guard, contended) = match locks[counter].try_read() {
+                            Ok(guard) => (guard, false),
+                            Err(TryLockError::WouldBlock) => (locks[counter].read().unwrap_or_else(|e| e.into_inner()), true),
+                            Err(TryLockError::Poisoned(e)) => (e.into_inner(), false),
+                        };
+                        let waited = asked.elapsed();
+                        spin(options.hold);
+                        black_box(*guard);
+                        (waited, contended)
+                    }
+                    Shared::RwLock(locks) => {
+                        let (mut guard, contended) = match locks[counter].try_write() {
+                            Ok(guard) => (guard, false),
+                            Err(TryLockError::WouldBlock) => (locks[counter].write().unwrap_or_else(|e| e.into_inner()), true),
+                            Err(TryLockError::Poisoned(e)) => (e.into_inner(), false),
+                        };
+                        let waited = asked.elapsed();
+                        spin(options.hold);
+                        *guard += 1;
+                        (waited, contended)
+                    }
+                };
+                // Recorded after the lock is released, so recording does not lengthen the critical section
+                outcome.waits.record(waited);
+                outcome.acquisitions += 1;
+                outcome.contended += contended as u64;
+                spin(options.think);
+            }
+
+            logs::emit(&task_id, format!("[Thread {}] Lock stress test completed.", thread_id));
+            outcome
+        });
+        handles.push(handle);
+    }
+
+    let mut result = StressResult::default();
+    let mut waits = Histogram::new();
+    let mut contended = 0u64;
+    for handle in handles {
+        let outcome = handle.await?;
+        result.iterations += outcome.acquisitions;
+        contended += outcome.contended;
+        waits.merge(&outcome.waits);
+    }
+    result.elapsed_secs = started.elapsed().as_secs_f64();
+    result.stopped = stop_flag.load(Ordering::SeqCst);
+
+    let stats = LockStats {
+        acquisitions_per_s: if result.elapsed_secs > 0.0 { result.iterations as f64 / result.elapsed_secs } else { 0.0 },
+        contended_percent: if result.iterations > 0 { contended as f64 / result.iterations as f64 * 100.0 } else { 0.0 },
+        wait: waits.percentiles().unwrap_or_default(),
+    };
+    artifacts::record_histogram(&task_id, "lock_waits", &waits);
+    logs::emit(&task_id, format!(
+        "{} {} acquisitions/s over {} threads, {:.1}% contended, wait p50 {:.2} us, p99 {:.2} us, max {:.2} us",
+        options.primitive.name(),
+        stats.acquisitions_per_s.round(),
+        threads,
+        stats.contended_percent,
+        stats.wait.p50_us,
+        stats.wait.p99_us,
+        stats.wait.max_us
+    ));
+    result.lock = Some(stats);
+    Ok(result)
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use crate::artifacts;
+
+// Lines kept for clients that connect after a task has started
+const BACKLOG: usize = 200;
+
+// One line of task output, streamed to GET /logs/stream subscribers
+#[derive(Clone, Serialize)]
+pub struct LogLine {
+    pub timestamp: f64,  // Unix time in seconds
+    pub task_id: String,
+    pub message: String,
+}
+
+struct LogHub {
+    sender: broadcast::Sender<LogLine>,
+    backlog: Mutex<VecDeque<LogLine>>,
+}
+
+static HUB: Lazy<LogHub> = Lazy::new(|| LogHub {
+    sender: broadcast::channel(1024).0,
+    backlog: Mutex::new(VecDeque::with_capacity(BACKLOG)),
+});
+
+// Log a task's output line and publish it to live log subscribers
+pub fn emit(task_id: &str, message: impl Into<String>) {
+    let line = LogLine {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64(),
+        task_id: task_id.to_string(),
+        message: message.into(),
+    };
+    tracing::info!(task_id = %line.task_id, "{}", line.message);
+
+    artifacts::log(&line);
+    // Publish under the backlog lock so subscribe() never misses or repeats a line
+    let mut backlog = HUB.backlog.lock().unwrap_or_else(|e| e.into_inner());
+    if backlog.len() >= BACKLOG {
+        backlog.pop_front();
+    }
+    backlog.push_back(line.clone());
+    let _ = HUB.sender.send(line); // No subscribers is fine
+}
+
+// Recent lines plus a receiver for everything emitted afterwards
+pub fn subscribe() -> (Vec<LogLine>, broadcast::Receiver<LogLine>) {
+    let backlog = HUB.backlog.lock().unwrap_or_else(|e| e.into_inner());
+    (backlog.iter().cloned().collect(), HUB.sender.subscribe())
+}
+
+// Format a line as a Server-Sent Event
+pub fn to_event(line: &LogLine) -> String {
+    format!("data: {}\n\n", serde_json::to_string(line).unwrap_or_default())
+}
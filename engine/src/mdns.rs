@@ -0,0 +1,78 @@
+//! LAN discovery over mDNS
+//!
+//! Lab setups without Kubernetes have no service DNS to find engines by. With
+//! `MOGWAI_MDNS=1` the engine advertises itself on the local network as a
+//! `_mogwai._tcp` DNS-SD service, so `mogwai nodes --discover` and a controller
+//! started with `MOGWAI_MDNS=1` find it without a URL being entered anywhere.
+//!
+//! The instance is named after the node (`MOGWAI_NODE_NAME`, or the hostname) and
+//! carries these TXT properties:
+//!
+//! - `node`: the node name, as given to `/start?node=`
+//! - `version`: the engine version
+//!
+//! The advertised address is the one the API is bound to, or every interface address
+//! when it is bound to a wildcard address.
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use once_cell::sync::OnceCell;
+use std::net::SocketAddr;
+use crate::metrics;
+
+/// DNS-SD service type engines are advertised under
+pub const SERVICE_TYPE: &str = "_mogwai._tcp.local.";
+
+/// The responder, kept for the life of the process; it answers queries on its own thread
+static DAEMON: OnceCell<(ServiceDaemon, String)> = OnceCell::new();
+
+/// Whether MOGWAI_MDNS asks for advertising
+fn enabled() -> bool {
+    std::env::var("MOGWAI_MDNS").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Advertise the engine if MOGWAI_MDNS is set; `local` is the address the API is bound to
+pub fn start(local: SocketAddr) {
+    if !enabled() {
+        return;
+    }
+    match advertise(local) {
+        Ok((daemon, fullname)) => {
+            tracing::info!(service = %fullname, port = local.port(), "Advertising the engine over mDNS");
+            let _ = DAEMON.set((daemon, fullname));
+        }
+        Err(e) => tracing::warn!(error = %e, "Cannot advertise the engine over mDNS"),
+    }
+}
+
+fn advertise(local: SocketAddr) -> Result<(ServiceDaemon, String), mdns_sd::Error> {
+    let node = metrics::node_name();
+    // DNS labels: a dotted hostname would read as several
+    let instance: String = node
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+        .collect();
+    let address = match local.ip() {
+        ip if ip.is_unspecified() => String::new(),
+        ip => ip.to_string(),
+    };
+    let properties = [("node", node.as_str()), ("version", env!("CARGO_PKG_VERSION"))];
+    let mut info = ServiceInfo::new(SERVICE_TYPE, &instance, &format!("{}.local.", instance), address.as_str(), local.port(), &properties[..])?;
+    if local.ip().is_unspecified() {
+        info = info.enable_addr_auto();
+    }
+    let fullname = info.get_fullname().to_string();
+    let daemon = ServiceDaemon::new()?;
+    daemon.register(info)?;
+    Ok((daemon, fullname))
+}
+
+/// Withdraw the advertisement, so browsers drop the engine right away instead of
+/// when its records expire
+pub fn stop() {
+    if let Some((daemon, fullname)) = DAEMON.get() {
+        if let Ok(status) = daemon.unregister(fullname) {
+            let _ = status.recv_timeout(std::time::Duration::from_secs(1));
+        }
+        let _ = daemon.shutdown();
+    }
+}
@@ -3,6 +3,135 @@ use std::thread::sleep;
 use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
 use sysinfo::System;
 use tokio::task;
+use crate::artifacts;
+use crate::histogram::Histogram;
+use crate::logs;
+use crate::result::{StressError, StressResult};
+
+// How the test memory is held (lock and hugepages are privileged, see capabilities)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryOptions {
+    pub lock: bool,               // mlock() each block so it cannot be swapped out
+    pub hugepages: bool,          // Back each block with reserved huge pages (MAP_HUGETLB)
+    pub balloon: Option<Balloon>, // Grow and shrink instead of holding a constant block
+    pub touch: Touch,             // How much of each page every pass writes
+    pub advice: Option<Advice>,   // madvise() hint for each block
+}
+
+// How a pass dirties the memory it holds
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Touch {
+    #[default]
+    Page, // One byte per page: keeps every page resident at little CPU cost
+    Line, // Every byte, whole cache lines at a time: real memory write bandwidth
+}
+
+// Transparent huge page hint for the blocks (not the reserved pool of `hugepages`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Advice {
+    Hugepage,   // MADV_HUGEPAGE: ask for transparent huge pages
+    NoHugepage, // MADV_NOHUGEPAGE: keep to base pages
+}
+
+// Ballooning pattern: each thread grows its allocation from the floor to the
+// ceiling (the test size) and releases it back, once per period
+#[derive(Debug, Clone, Copy)]
+pub struct Balloon {
+    pub floor_mb: usize,  // Per thread
+    pub period_secs: f64, // One full grow/shrink cycle
+}
+
+// Allocation step of the ballooning pattern; each chunk is its own mapping, so
+// releasing one returns its pages to the kernel
+const BALLOON_CHUNK_MB: usize = 4;
+
+// Base page size the touch strategies step by
+const PAGE_SIZE: usize = 4096;
+
+// Cache line size written at once by Touch::Line
+const CACHE_LINE: usize = 64;
+
+// How often a ballooning thread resizes its allocation
+const BALLOON_STEP: Duration = Duration::from_millis(250);
+
+// A thread's memory block: an anonymous mapping, populated and dirtied up front
+// A plain `vec![0u8; n]` may stay backed by the shared zero page until written, and
+// pages holding only zeros can be merged again (KSM), so the memory would not really be used
+struct Block {
+    ptr: *mut u8,
+    len: usize,
+}
+
+// The mapping is owned by exactly one worker thread
+unsafe impl Send for Block {}
+
+impl Block {
+    fn allocate(bytes: usize, options: MemoryOptions) -> Result<Block, String> {
+        let mut flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_POPULATE;
+        if options.hugepages {
+            flags |= libc::MAP_HUGETLB;
+        }
+        // SAFETY: anonymous private mapping, unmapped in Drop
+        let ptr = unsafe {
+            libc::mmap(std::ptr::null_mut(), bytes, libc::PROT_READ | libc::PROT_WRITE, flags, -1, 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            let kind = if options.hugepages { "huge page mapping" } else { "mapping" };
+            return Err(format!("{} failed: {}", kind, std::io::Error::last_os_error()));
+        }
+        let mut block = Block { ptr: ptr as *mut u8, len: bytes };
+        if let Some(advice) = options.advice {
+            let advice = match advice {
+                Advice::Hugepage => libc::MADV_HUGEPAGE,
+                Advice::NoHugepage => libc::MADV_NOHUGEPAGE,
+            };
+            // SAFETY: advice on the mapping owned by this block
+            if unsafe { libc::madvise(ptr, bytes, advice) } != 0 {
+                return Err(format!("madvise failed: {}", std::io::Error::last_os_error()));
+            }
+        }
+        if options.lock {
+            // SAFETY: locks pages of the mapping owned by this block
+            if unsafe { libc::mlock(ptr, bytes) } != 0 {
+                return Err(format!("mlock failed: {}", std::io::Error::last_os_error()));
+            }
+        }
+        // Non-zero content in every page, so none of them stays or becomes shared
+        touch(block.as_mut_slice(), options.touch, 0);
+        Ok(block)
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: the mapping is valid for len bytes until Drop
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        // SAFETY: unmaps the region mapped in allocate (also drops any lock)
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+    }
+}
+
+// Write one pass over a block; every pass writes different, never all-zero values
+fn touch(block: &mut [u8], strategy: Touch, pass: u64) {
+    match strategy {
+        Touch::Page => {
+            for (page, chunk) in block.chunks_mut(PAGE_SIZE).enumerate() {
+                chunk[0] = ((page as u64 ^ pass) as u8) | 1;
+            }
+        }
+        Touch::Line => {
+            for (line, chunk) in block.chunks_mut(CACHE_LINE).enumerate() {
+                let value = ((line as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ pass) | 1;
+                for word in chunk.chunks_mut(8) {
+                    word.copy_from_slice(&value.to_le_bytes()[..word.len()]);
+                }
+            }
+        }
+    }
+}
 
 pub async fn stress_memory(
     threads: usize,
@@ -10,57 +139,167 @@ pub async fn stress_memory(
     duration: u64,
     stop_flag: Arc<AtomicBool>,
     task_id: String,
-) {
-    if duration == 0 {
-        println!("Running memory stress test indefinitely. To stop, send a POST request to: http://localhost:8080/stop/{}", task_id);
-    }
-    
-    println!(
+) -> Result<StressResult, StressError> {
+    stress_memory_with(threads, mb_per_thread, duration, stop_flag, task_id, MemoryOptions::default()).await
+}
+
+pub async fn stress_memory_with(
+    threads: usize,
+    mb_per_thread: usize,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: String,
+    options: MemoryOptions,
+) -> Result<StressResult, StressError> {
+    logs::emit(&task_id, format!(
         "Spawning {} threads. Each will allocate {} MB (Total: {} MB)",
         threads,
         mb_per_thread,
         threads * mb_per_thread
-    );
+    ));
 
 
 
     let mut handles = Vec::new();
+    let started = Instant::now();
 
     for thread_id in 0..threads {
         let stop = Arc::clone(&stop_flag);
+        let task_id = task_id.clone();
+
+        if let Some(balloon) = options.balloon {
+            handles.push(task::spawn_blocking(move || {
+                balloon_thread(thread_id, mb_per_thread, balloon, options, duration, &stop, &task_id)
+            }));
+            continue;
+        }
 
         let handle = task::spawn_blocking(move || {
-            let mut memory_block = vec![0u8; mb_per_thread * 1024 * 1024];
+            let mut block = match Block::allocate(mb_per_thread * 1024 * 1024, options) {
+                Ok(block) => block,
+                Err(e) => return (0, Some(format!("[Thread {}] {}", thread_id, e)), Histogram::new()),
+            };
+            let memory_block = block.as_mut_slice();
             let start = Instant::now();
-            
+            let mut passes = 0u64;
+            let mut times = Histogram::new();
+
             // if duration == 0 run indefinetly
             while (duration == 0 || start.elapsed() < Duration::from_secs(duration))
                 && !stop.load(Ordering::SeqCst)
             {
-                for i in (0..memory_block.len()).step_by(4096) {
-                    memory_block[i] = i as u8;
-                }
+                passes += 1;
+                let pass_start = Instant::now();
+                touch(memory_block, options.touch, passes);
+                times.record(pass_start.elapsed());
 
                 // Sleep to reduce CPU 
                 sleep(Duration::from_millis(500));
             }
 
-            println!("[Thread {}] Memory stress test completed.", thread_id);
+            logs::emit(&task_id, format!("[Thread {}] Memory stress test completed.", thread_id));
+            (passes, None, times)
         });
 
         handles.push(handle);
     }
 
+    let mut result = StressResult::default();
+    let mut times = Histogram::new();
     for handle in handles {
-        handle.await.unwrap();
+        let (passes, error, thread_times) = handle.await?;
+        result.iterations += passes;
+        result.errors.extend(error);
+        times.merge(&thread_times);
+    }
+    result.iteration_times = times.percentiles();
+    artifacts::record_histogram(&task_id, "iteration_times", &times);
+    if let Some(times) = &result.iteration_times {
+        logs::emit(&task_id, format!(
+            "Iteration time p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+            times.p50_us / 1000.0,
+            times.p95_us / 1000.0,
+            times.p99_us / 1000.0,
+            times.max_us / 1000.0
+        ));
     }
+    result.elapsed_secs = started.elapsed().as_secs_f64();
+    result.stopped = stop_flag.load(Ordering::SeqCst);
 
+    // No thread could allocate its block
+    if result.errors.len() == threads && threads > 0 {
+        return Err(StressError::Unavailable(result.errors.join("; ")));
+    }
+    Ok(result)
+}
+
+// One thread of the ballooning pattern: follow a triangle wave between floor and
+// ceiling, touching every page it holds; returns (completed cycles, error, step times)
+fn balloon_thread(
+    thread_id: usize,
+    ceiling_mb: usize,
+    balloon: Balloon,
+    options: MemoryOptions,
+    duration: u64,
+    stop: &AtomicBool,
+    task_id: &str,
+) -> (u64, Option<String>, Histogram) {
+    let chunk_bytes = BALLOON_CHUNK_MB * 1024 * 1024;
+    let (floor, ceiling) = (balloon.floor_mb / BALLOON_CHUNK_MB, ceiling_mb.div_ceil(BALLOON_CHUNK_MB));
+    let mut chunks: Vec<Block> = Vec::with_capacity(ceiling);
+    let start = Instant::now();
+    let mut cycles = 0u64;
+    let mut passes = 0u64;
+    let mut times = Histogram::new();
+
+    while (duration == 0 || start.elapsed() < Duration::from_secs(duration)) && !stop.load(Ordering::SeqCst) {
+        // Position in the current cycle: 0 -> 1 (ceiling) -> 0
+        let phase = (start.elapsed().as_secs_f64() / balloon.period_secs).fract();
+        let level = 1.0 - (2.0 * phase - 1.0).abs();
+        let target = floor + ((ceiling - floor) as f64 * level).round() as usize;
+
+        let step_start = Instant::now();
+        while chunks.len() < target {
+            match Block::allocate(chunk_bytes, options) {
+                Ok(chunk) => chunks.push(chunk),
+                Err(e) => return (cycles, Some(format!("[Thread {}] {}", thread_id, e)), times),
+            }
+        }
+        chunks.truncate(target);
+
+        // Keep what is held resident
+        passes += 1;
+        for chunk in chunks.iter_mut() {
+            touch(chunk.as_mut_slice(), options.touch, passes);
+        }
+        times.record(step_start.elapsed());
+
+        let completed = (start.elapsed().as_secs_f64() / balloon.period_secs) as u64;
+        if completed > cycles {
+            cycles = completed;
+            if thread_id == 0 {
+                logs::emit(task_id, format!("Balloon cycle {} completed ({}-{} MB per thread)", cycles, balloon.floor_mb, ceiling_mb));
+            }
+        }
+        sleep(BALLOON_STEP);
+    }
+
+    logs::emit(task_id, format!("[Thread {}] Memory stress test completed.", thread_id));
+    (cycles, None, times)
 }
 
 pub fn check_memory_usage() {
     let mut sys = System::new_all();
     sys.refresh_memory();
 
-    println!("Total Memory: {} MB", sys.total_memory() / 1024);
-    println!("Used Memory: {} MB", sys.used_memory() / 1024);
+    tracing::debug!(total_mb = sys.total_memory() / 1024, used_mb = sys.used_memory() / 1024, "Memory usage");
+}
+
+// System-wide count of processes killed by the OOM killer (None where /proc/vmstat has no counter)
+pub fn oom_kill_count() -> Option<u64> {
+    let vmstat = std::fs::read_to_string("/proc/vmstat").ok()?;
+    vmstat
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|count| count.trim().parse().ok())
 }
@@ -0,0 +1,88 @@
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sysinfo::{Disks, System};
+
+// Host-level snapshot polled by the GUI while tests run
+#[derive(Serialize)]
+pub struct HostMetrics {
+    pub timestamp: f64,        // Unix time in seconds
+    pub cpu_percent: f32,      // Global CPU usage since the previous sample
+    pub memory_used_mb: u64,
+    pub memory_total_mb: u64,
+    pub disk_read_mb_s: f64,   // Read throughput since the previous sample
+    pub disk_write_mb_s: f64,  // Write throughput since the previous sample
+    pub running_tasks: usize,
+}
+
+// CPU usage and disk throughput are deltas, so the previous sample is kept around
+struct Sampler {
+    system: System,
+    disks: Disks,
+    last_disk: Option<(Instant, u64, u64)>, // (time, total read bytes, total written bytes)
+}
+
+static SAMPLER: Lazy<Mutex<Sampler>> = Lazy::new(|| {
+    let mut system = System::new();
+    system.refresh_cpu_usage();
+    Mutex::new(Sampler {
+        system,
+        disks: Disks::new_with_refreshed_list(),
+        last_disk: None,
+    })
+});
+
+// Name this engine reports itself under in exported metrics (MOGWAI_NODE_NAME, or the hostname)
+pub fn node_name() -> String {
+    std::env::var("MOGWAI_NODE_NAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .or_else(|| hostname::get().ok().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub fn sample(running_tasks: usize) -> HostMetrics {
+    let mut sampler = SAMPLER.lock().unwrap_or_else(|e| e.into_inner());
+
+    sampler.system.refresh_cpu_usage();
+    sampler.system.refresh_memory();
+    sampler.disks.refresh(true);
+
+    // Count each device once; the same disk can be mounted at several paths
+    let mut seen = Vec::new();
+    let (mut read, mut written) = (0u64, 0u64);
+    for disk in sampler.disks.list() {
+        if seen.contains(&disk.name()) {
+            continue;
+        }
+        seen.push(disk.name());
+        let usage = disk.usage();
+        read += usage.total_read_bytes;
+        written += usage.total_written_bytes;
+    }
+
+    let now = Instant::now();
+    let (disk_read_mb_s, disk_write_mb_s) = match sampler.last_disk {
+        Some((then, last_read, last_written)) => {
+            let secs = now.duration_since(then).as_secs_f64().max(0.001);
+            let mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0) / secs;
+            (mb(read.saturating_sub(last_read)), mb(written.saturating_sub(last_written)))
+        }
+        None => (0.0, 0.0),
+    };
+    sampler.last_disk = Some((now, read, written));
+
+    HostMetrics {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64(),
+        cpu_percent: sampler.system.global_cpu_usage(),
+        memory_used_mb: sampler.system.used_memory() / (1024 * 1024),
+        memory_total_mb: sampler.system.total_memory() / (1024 * 1024),
+        disk_read_mb_s,
+        disk_write_mb_s,
+        running_tasks,
+    }
+}
@@ -0,0 +1,244 @@
+//! OpenTelemetry trace export
+//!
+//! With `MOGWAI_OTLP_ENDPOINT` (or the standard `OTEL_EXPORTER_OTLP_ENDPOINT`) set to a
+//! collector, Jaeger or Tempo, the engine sends its spans there over OTLP/HTTP (JSON
+//! encoding, posted to `<endpoint>/v1/traces`; `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` gives
+//! the full URL instead). Each test shows up as:
+//!
+//! - `request`: the call that submitted it, continuing the caller's trace when it sent a
+//!   W3C `traceparent` header (the controller does)
+//! - `task`: its execution, with the task's log lines and its final metrics as events
+//!
+//! Reads (`GET`, `HEAD`, `OPTIONS`) are not exported. `OTEL_SERVICE_NAME` names the
+//! service (default `mogwai-engine`) and `OTEL_EXPORTER_OTLP_HEADERS` adds headers to the
+//! export calls, e.g. `authorization=Bearer abc`. Spans are sent in batches every two
+//! seconds; while the endpoint is down at most 2048 wait, the oldest are dropped.
+
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_SERVICE_NAME: &str = "mogwai-engine";
+const EXPORT_INTERVAL: Duration = Duration::from_secs(2);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_QUEUE: usize = 2048;
+
+/// Span events kept per span; later ones are counted, not kept
+pub const MAX_EVENTS: usize = 128;
+
+/// A finished span, ready to export
+#[derive(Debug)]
+pub struct SpanRecord {
+    pub name: &'static str,
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub parent_span_id: Option<[u8; 8]>,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Map<String, Value>,
+    pub events: Vec<SpanEvent>,
+    pub dropped_events: usize,
+    pub error: Option<String>, // Message of the first warning or error logged in the span
+}
+
+/// Something logged while a span was entered
+#[derive(Debug)]
+pub struct SpanEvent {
+    pub time: SystemTime,
+    pub name: String,
+    pub attributes: Map<String, Value>,
+}
+
+struct Config {
+    url: String,
+    headers: Vec<(String, String)>,
+    service: String,
+}
+
+// Read without logging: the logger asks while it holds its span table
+static CONFIG: Lazy<Option<Config>> = Lazy::new(|| {
+    let var = |name: &str| std::env::var(name).ok().map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+    let url = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").or_else(|| {
+        var("MOGWAI_OTLP_ENDPOINT")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_ENDPOINT"))
+            .map(|base| format!("{}/v1/traces", base.trim_end_matches('/')))
+    })?;
+    let headers = var("OTEL_EXPORTER_OTLP_HEADERS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let service = var("OTEL_SERVICE_NAME").unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string());
+    Some(Config { url, headers, service })
+});
+
+static QUEUE: Lazy<Mutex<Vec<SpanRecord>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Whether spans are exported
+pub fn enabled() -> bool {
+    CONFIG.is_some()
+}
+
+/// Queue a finished span for the next batch
+pub fn export(span: SpanRecord) {
+    if !enabled() || !exported(&span) {
+        return;
+    }
+    let mut queue = QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    if queue.len() >= MAX_QUEUE {
+        queue.remove(0);
+    }
+    queue.push(span);
+}
+
+/// Polling reads would drown the tests
+fn exported(span: &SpanRecord) -> bool {
+    let method = span.attributes.get("http.request.method").and_then(Value::as_str);
+    !(span.name == "request" && matches!(method, Some("GET" | "HEAD" | "OPTIONS")))
+}
+
+/// Start sending batches in the background if an endpoint is configured
+pub fn start() {
+    let Some(config) = CONFIG.as_ref() else {
+        return;
+    };
+    tracing::info!(url = %config.url, service = %config.service, "Exporting traces over OTLP");
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder().timeout(EXPORT_TIMEOUT).build().unwrap_or_default();
+        let mut tick = tokio::time::interval(EXPORT_INTERVAL);
+        let mut failing = false;
+        loop {
+            tick.tick().await;
+            let spans = std::mem::take(&mut *QUEUE.lock().unwrap_or_else(|e| e.into_inner()));
+            if spans.is_empty() {
+                continue;
+            }
+            match send(&client, config, &spans).await {
+                Ok(()) if failing => {
+                    tracing::info!("OTLP trace export works again");
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Report a failing endpoint once, not on every batch
+                Err(e) if !failing => {
+                    tracing::warn!(error = %e, "OTLP trace export failed");
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+async fn send(client: &reqwest::Client, config: &Config, spans: &[SpanRecord]) -> Result<(), String> {
+    let mut request = client
+        .post(&config.url)
+        .header("Content-Type", "application/json")
+        .body(encode(&config.service, spans).to_string());
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{} answered {}", config.url, response.status()));
+    }
+    Ok(())
+}
+
+/// An `ExportTraceServiceRequest` in the OTLP JSON encoding
+fn encode(service: &str, spans: &[SpanRecord]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let mut encoded = json!({
+                "traceId": hex(&span.trace_id),
+                "spanId": hex(&span.span_id),
+                "name": span.name,
+                "kind": kind(span.name),
+                "startTimeUnixNano": nanos(span.start),
+                "endTimeUnixNano": nanos(span.end),
+                "attributes": attributes(&span.attributes),
+                "events": span.events.iter().map(|event| json!({
+                    "timeUnixNano": nanos(event.time),
+                    "name": event.name,
+                    "attributes": attributes(&event.attributes),
+                })).collect::<Vec<_>>(),
+                "droppedEventsCount": span.dropped_events,
+                "status": match &span.error {
+                    Some(message) => json!({ "code": 2, "message": message }),
+                    None => json!({ "code": 0 }),
+                },
+            });
+            if let Some(parent) = &span.parent_span_id {
+                encoded["parentSpanId"] = Value::from(hex(parent));
+            }
+            encoded
+        })
+        .collect();
+    json!({
+        "resourceSpans": [{
+            "resource": { "attributes": attributes(&Map::from_iter([("service.name".to_string(), Value::from(service))])) },
+            "scopeSpans": [{ "scope": { "name": "mogwai", "version": env!("CARGO_PKG_VERSION") }, "spans": spans }],
+        }]
+    })
+}
+
+/// Requests are served, everything else is internal work
+fn kind(name: &str) -> u8 {
+    match name {
+        "request" => 2,  // SPAN_KIND_SERVER
+        "dispatch" => 3, // SPAN_KIND_CLIENT
+        _ => 1,          // SPAN_KIND_INTERNAL
+    }
+}
+
+fn attributes(fields: &Map<String, Value>) -> Vec<Value> {
+    fields
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Bool(flag) => json!({ "boolValue": flag }),
+                Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+                Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+                Value::String(text) => json!({ "stringValue": text }),
+                other => json!({ "stringValue": other.to_string() }),
+            };
+            json!({ "key": key, "value": value })
+        })
+        .collect()
+}
+
+fn nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The trace id and parent span id of a W3C `traceparent` header
+pub fn parse_traceparent(header: &str) -> Option<([u8; 16], [u8; 8])> {
+    let mut parts = header.trim().split('-');
+    let (version, trace, span) = (parts.next()?, parts.next()?, parts.next()?);
+    if version.len() != 2 || version == "ff" {
+        return None;
+    }
+    let trace: [u8; 16] = unhex(trace)?.try_into().ok()?;
+    let span: [u8; 8] = unhex(span)?.try_into().ok()?;
+    (trace != [0; 16] && span != [0; 8]).then_some((trace, span))
+}
+
+/// The `traceparent` header naming a span as the parent of the callee's spans
+pub fn traceparent(trace_id: &[u8; 16], span_id: &[u8; 8]) -> String {
+    format!("00-{}-{}-01", hex(trace_id), hex(span_id))
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
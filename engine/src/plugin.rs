@@ -0,0 +1,224 @@
+//! Custom workloads loaded from shared libraries
+//!
+//! At startup the engine loads every `.so` (`.dylib` on macOS) in the plugin
+//! directory (`MOGWAI_PLUGIN_DIR`, default `./plugins`) and registers it as a
+//! test type served under `/custom/{name}`. A plugin exports two C functions:
+//!
+//! ```c
+//! // Unique name of the workload ([A-Za-z0-9_-]+), e.g. "codec"
+//! const char *mogwai_plugin_name(void);
+//!
+//! // Run the workload. `params_json` holds task_id, intensity, duration, load, size
+//! // and any other fields of the request body. Stop early once *stop becomes
+//! // non-zero. On success return 0 and optionally write a JSON result (iterations,
+//! // achieved_load, mb_per_s, errors) to `out`; on failure return non-zero and
+//! // write an error message to `out`. `out` holds `out_len` bytes.
+//! int mogwai_plugin_run(const char *params_json, const volatile unsigned char *stop,
+//!                       char *out, size_t out_len);
+//! ```
+//!
+//! `mogwai_plugin_run` may be called from several threads at once. Plugins need a
+//! dynamically linked engine build (the engine image built with `--build-arg PLUGINS=true`);
+//! a statically linked engine, such as the default image, says so at startup and loads none.
+
+use libc::{c_char, c_int, c_void, size_t, RTLD_NOW};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+
+use crate::result::{StressError, StressResult};
+use crate::stress::{self, StressFuture, StressTest, TestContext};
+
+/// Default directory searched for plugins
+pub const DEFAULT_PLUGIN_DIR: &str = "./plugins";
+
+/// Size of the buffer a plugin writes its result or error into
+const OUTPUT_SIZE: usize = 64 * 1024;
+
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type RunFn = unsafe extern "C" fn(*const c_char, *const u8, *mut c_char, size_t) -> c_int;
+
+/// Names of the loaded plugins (the only test types reachable through /custom)
+static PLUGINS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A workload implemented by a shared library
+pub struct PluginTest {
+    name: &'static str, // Leaked once per plugin; plugins live as long as the engine
+    run: RunFn,
+}
+
+impl StressTest for PluginTest {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn label(&self) -> &'static str {
+        self.name
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        format!(
+            "Starting custom workload '{}' with {} threads for {} seconds...",
+            self.name, ctx.intensity, ctx.duration
+        )
+    }
+
+    // Plugins may not support running until stopped (duration 0)
+    fn extendable(&self, _ctx: &TestContext) -> bool {
+        false
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        let run = self.run;
+        Box::pin(async move {
+            let params = serde_json::to_string(&ctx).map_err(|e| StressError::InvalidParameter(e.to_string()))?;
+            tokio::task::spawn_blocking(move || call_plugin(run, &params, &stop_flag)).await?
+        })
+    }
+}
+
+/// Call the plugin's run function and turn its output into a result
+fn call_plugin(run: RunFn, params: &str, stop_flag: &AtomicBool) -> Result<StressResult, StressError> {
+    let params = CString::new(params).map_err(|e| StressError::InvalidParameter(e.to_string()))?;
+    let mut out = vec![0u8; OUTPUT_SIZE];
+    let started = Instant::now();
+
+    // AtomicBool has the same layout as u8, so the plugin can poll it directly
+    let status = unsafe {
+        run(
+            params.as_ptr(),
+            stop_flag.as_ptr() as *const u8,
+            out.as_mut_ptr() as *mut c_char,
+            OUTPUT_SIZE,
+        )
+    };
+
+    let end = out.iter().position(|&b| b == 0).unwrap_or(OUTPUT_SIZE);
+    let output = String::from_utf8_lossy(&out[..end]).trim().to_string();
+    if status != 0 {
+        let message = if output.is_empty() { format!("exit status {}", status) } else { output };
+        return Err(StressError::Plugin(message));
+    }
+
+    let mut result = if output.is_empty() {
+        StressResult::default()
+    } else {
+        serde_json::from_str(&output)
+            .map_err(|e| StressError::Plugin(format!("invalid result JSON: {}", e)))?
+    };
+    if result.elapsed_secs == 0.0 {
+        result.elapsed_secs = started.elapsed().as_secs_f64();
+    }
+    result.stopped |= stop_flag.load(Ordering::SeqCst);
+    Ok(result)
+}
+
+/// Whether this build can load plugins; a statically linked engine has no dynamic loader
+pub fn supported() -> bool {
+    !cfg!(target_feature = "crt-static")
+}
+
+/// Load and register every plugin in `dir`; returns the registered names
+pub fn load_plugins(dir: &Path) -> Vec<String> {
+    let mut paths: Vec<_> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "so" || ext == "dylib"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+
+    if !supported() {
+        let skipped: Vec<_> = paths.iter().map(|path| path.display().to_string()).collect();
+        if skipped.is_empty() {
+            tracing::info!("Custom workloads unavailable: this engine is statically linked and cannot load plugins");
+        } else {
+            tracing::warn!(plugins = ?skipped, "Custom workloads unavailable: this engine is statically linked and cannot load plugins; skipping them");
+        }
+        return Vec::new();
+    }
+
+    let builtin = stress::names();
+    let mut loaded = Vec::new();
+    for path in paths {
+        match load_plugin(&path) {
+            Ok(test) if builtin.iter().any(|name| name == test.name) => {
+                tracing::warn!(plugin = %path.display(), test_type = test.name, "Skipping plugin: its test type is built in");
+            }
+            Ok(test) if loaded.iter().any(|name| name == test.name) => {
+                tracing::warn!(plugin = %path.display(), test_type = test.name, "Skipping plugin: its test type is already loaded");
+            }
+            Ok(test) => {
+                tracing::info!(plugin = %path.display(), test_type = test.name, "Loaded plugin");
+                loaded.push(test.name.to_string());
+                stress::register(Arc::new(test));
+            }
+            Err(e) => tracing::warn!(plugin = %path.display(), error = %e, "Failed to load plugin"),
+        }
+    }
+
+    PLUGINS.lock().unwrap_or_else(|e| e.into_inner()).extend(loaded.iter().cloned());
+    loaded
+}
+
+/// Names of the loaded plugins
+pub fn names() -> Vec<String> {
+    PLUGINS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Open one shared library and resolve its entry points
+fn load_plugin(path: &Path) -> Result<PluginTest, String> {
+    let c_path = CString::new(path.as_os_str().to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+
+    // The handle is never closed: registered tests keep pointers into the library
+    let handle = unsafe { libc::dlopen(c_path.as_ptr(), RTLD_NOW) };
+    if handle.is_null() {
+        return Err(dl_error());
+    }
+
+    let name_fn = symbol(handle, c"mogwai_plugin_name")?;
+    let run_fn = symbol(handle, c"mogwai_plugin_run")?;
+    let (name_fn, run) = unsafe {
+        (
+            std::mem::transmute::<*mut c_void, NameFn>(name_fn),
+            std::mem::transmute::<*mut c_void, RunFn>(run_fn),
+        )
+    };
+
+    let name_ptr = unsafe { name_fn() };
+    if name_ptr.is_null() {
+        return Err("mogwai_plugin_name returned NULL".to_string());
+    }
+    let name = unsafe { CStr::from_ptr(name_ptr) }.to_string_lossy().to_string();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("invalid plugin name '{}'", name));
+    }
+
+    Ok(PluginTest { name: Box::leak(name.into_boxed_str()), run })
+}
+
+/// Address of an exported symbol
+fn symbol(handle: *mut c_void, name: &CStr) -> Result<*mut c_void, String> {
+    let address = unsafe { libc::dlsym(handle, name.as_ptr()) };
+    if address.is_null() {
+        Err(format!("missing symbol {}", name.to_string_lossy()))
+    } else {
+        Ok(address)
+    }
+}
+
+/// Last dynamic loader error
+fn dl_error() -> String {
+    let message = unsafe { libc::dlerror() };
+    if message.is_null() {
+        "unknown dlopen error".to_string()
+    } else {
+        unsafe { CStr::from_ptr(message) }.to_string_lossy().to_string()
+    }
+}
@@ -0,0 +1,261 @@
+//! StatsD and Prometheus Pushgateway export
+//!
+//! A lightweight push mode for environments where neither scraping nor remote write
+//! is possible. Every `MOGWAI_PUSH_INTERVAL_SECS` (default 10) the engine sends
+//!
+//! - with `MOGWAI_STATSD_ADDR` (`host:port`), StatsD lines over UDP under
+//!   `<MOGWAI_STATSD_PREFIX, default mogwai>.<node>.`: gauges `host.cpu_percent`,
+//!   `host.memory_used_mb`, `host.disk_read_mb_s`, `host.disk_write_mb_s`,
+//!   `running_tasks` and `tasks.<type>.running`, and counters `tasks.finished`,
+//!   `tasks.failed` and `tasks.iterations` for the tasks finished since the last push
+//! - with `MOGWAI_PUSHGATEWAY_URL`, the group `job="mogwai", instance=<node>` to the
+//!   Pushgateway: the `mogwai_host_*` and `mogwai_running_tasks` gauges of the remote-write
+//!   export, `mogwai_task_running` and `mogwai_task_elapsed_seconds` per running task,
+//!   and the counters `mogwai_tasks_finished_total` and `mogwai_tasks_failed_total`
+//!
+//! `<node>` is `MOGWAI_NODE_NAME`, or the hostname.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+use crate::metrics::{self, HostMetrics};
+use crate::thread_manager::{self, TaskInfo, GLOBAL_REGISTRY};
+
+/// Push interval unless MOGWAI_PUSH_INTERVAL_SECS is set
+const DEFAULT_INTERVAL_SECS: u64 = 10;
+
+/// StatsD prefix unless MOGWAI_STATSD_PREFIX is set
+const DEFAULT_STATSD_PREFIX: &str = "mogwai";
+
+/// Give up on a Pushgateway push after this long
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lines per StatsD datagram, to stay well below common UDP size limits
+const STATSD_LINES_PER_PACKET: usize = 20;
+
+/// Where to push
+struct Config {
+    statsd: Option<String>,      // host:port
+    statsd_prefix: String,       // Prefix including the node, e.g. "mogwai.node-1"
+    pushgateway: Option<String>, // Group URL, e.g. http://pushgateway:9091/metrics/job/mogwai/instance/node-1
+    interval: Duration,
+}
+
+impl Config {
+    /// None unless MOGWAI_STATSD_ADDR or MOGWAI_PUSHGATEWAY_URL is set
+    fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let statsd = var("MOGWAI_STATSD_ADDR");
+        let pushgateway = var("MOGWAI_PUSHGATEWAY_URL");
+        if statsd.is_none() && pushgateway.is_none() {
+            return None;
+        }
+        let node = metrics::node_name();
+        let interval = var("MOGWAI_PUSH_INTERVAL_SECS")
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        let pushgateway = match pushgateway.map(|url| reqwest::Url::parse(&url)) {
+            Some(Ok(mut url)) => {
+                url.path_segments_mut()
+                    .map_err(|_| ())
+                    .ok()?
+                    .pop_if_empty()
+                    .extend(["metrics", "job", "mogwai", "instance", &node]);
+                Some(url.to_string())
+            }
+            Some(Err(e)) => {
+                tracing::warn!(error = %e, "MOGWAI_PUSHGATEWAY_URL is not a valid URL; Pushgateway push disabled");
+                None
+            }
+            None => None,
+        };
+        Some(Config {
+            statsd,
+            statsd_prefix: format!(
+                "{}.{}",
+                var("MOGWAI_STATSD_PREFIX").unwrap_or_else(|| DEFAULT_STATSD_PREFIX.to_string()),
+                statsd_segment(&node)
+            ),
+            pushgateway,
+            interval: Duration::from_secs(interval),
+        })
+    }
+}
+
+/// One round of numbers, shared by both sinks
+struct Snapshot {
+    host: HostMetrics,
+    tasks: Vec<TaskInfo>,
+    finished: u64,   // Tasks finished since the previous round
+    failed: u64,     // ... of which ended with an error
+    iterations: u64, // Iterations of the finished tasks
+}
+
+/// Start pushing in the background if StatsD or a Pushgateway is configured
+pub fn start() {
+    let Some(config) = Config::from_env() else {
+        return;
+    };
+    if let Some(addr) = &config.statsd {
+        tracing::info!(address = %addr, interval_secs = config.interval.as_secs(), "Sending StatsD metrics");
+    }
+    if let Some(url) = &config.pushgateway {
+        tracing::info!(url = %url, interval_secs = config.interval.as_secs(), "Pushing metrics to the Pushgateway");
+    }
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder().timeout(PUSH_TIMEOUT).build().unwrap_or_default();
+        let socket = match &config.statsd {
+            Some(_) => UdpSocket::bind("0.0.0.0:0").await.map_err(|e| tracing::warn!(error = %e, "StatsD socket failed")).ok(),
+            None => None,
+        };
+        let mut tick = tokio::time::interval(config.interval);
+        let mut last_push = now_secs();
+        let (mut finished_total, mut failed_total) = (0u64, 0u64);
+        let (mut statsd_failing, mut pushgateway_failing) = (false, false);
+        loop {
+            tick.tick().await;
+            let pushed_at = now_secs();
+            let snapshot = collect(last_push);
+            last_push = pushed_at;
+            finished_total += snapshot.finished;
+            failed_total += snapshot.failed;
+
+            if let (Some(addr), Some(socket)) = (&config.statsd, &socket) {
+                let result = send_statsd(socket, addr, &statsd_lines(&config.statsd_prefix, &snapshot)).await;
+                report("StatsD", addr, result, &mut statsd_failing);
+            }
+            if let Some(url) = &config.pushgateway {
+                let body = exposition(&snapshot, finished_total, failed_total);
+                report("Pushgateway", url, push_group(&client, url, body).await, &mut pushgateway_failing);
+            }
+        }
+    });
+}
+
+/// Log a failing sink once, and again when it recovers
+fn report(sink: &str, target: &str, result: Result<(), String>, failing: &mut bool) {
+    match result {
+        Ok(()) if *failing => {
+            tracing::info!(sink, target, "Push works again");
+            *failing = false;
+        }
+        Err(e) if !*failing => {
+            tracing::warn!(sink, target, error = %e, "Push failed");
+            *failing = true;
+        }
+        _ => {}
+    }
+}
+
+/// Current host and task numbers, and the tasks that finished after `since` (Unix seconds)
+fn collect(since: f64) -> Snapshot {
+    let tasks = thread_manager::task_infos(&GLOBAL_REGISTRY);
+    let host = metrics::sample(tasks.len());
+    let (mut finished, mut failed, mut iterations) = (0, 0, 0);
+    for entry in thread_manager::list_results().iter().filter(|entry| entry.finished_at > since) {
+        finished += 1;
+        if entry.error.is_some() {
+            failed += 1;
+        }
+        iterations += entry.result.as_ref().map_or(0, |result| result.iterations);
+    }
+    Snapshot { host, tasks, finished, failed, iterations }
+}
+
+/// StatsD gauges and counters of a snapshot
+fn statsd_lines(prefix: &str, snapshot: &Snapshot) -> Vec<String> {
+    let host = &snapshot.host;
+    let mut lines = vec![
+        format!("{}.host.cpu_percent:{}|g", prefix, host.cpu_percent),
+        format!("{}.host.memory_used_mb:{}|g", prefix, host.memory_used_mb),
+        format!("{}.host.disk_read_mb_s:{:.3}|g", prefix, host.disk_read_mb_s),
+        format!("{}.host.disk_write_mb_s:{:.3}|g", prefix, host.disk_write_mb_s),
+        format!("{}.running_tasks:{}|g", prefix, snapshot.tasks.len()),
+        format!("{}.tasks.finished:{}|c", prefix, snapshot.finished),
+        format!("{}.tasks.failed:{}|c", prefix, snapshot.failed),
+        format!("{}.tasks.iterations:{}|c", prefix, snapshot.iterations),
+    ];
+    let mut running: BTreeMap<&str, usize> = BTreeMap::new();
+    for task in &snapshot.tasks {
+        *running.entry(&task.test_type).or_default() += 1;
+    }
+    for (test_type, count) in running {
+        lines.push(format!("{}.tasks.{}.running:{}|g", prefix, statsd_segment(test_type), count));
+    }
+    lines
+}
+
+/// Send lines in a few datagrams; UDP gives no delivery feedback beyond local errors
+async fn send_statsd(socket: &UdpSocket, addr: &str, lines: &[String]) -> Result<(), String> {
+    for packet in lines.chunks(STATSD_LINES_PER_PACKET) {
+        socket.send_to(packet.join("\n").as_bytes(), addr).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Keep a name part free of StatsD's separators
+fn statsd_segment(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Prometheus text exposition of a snapshot
+fn exposition(snapshot: &Snapshot, finished_total: u64, failed_total: u64) -> String {
+    let host = &snapshot.host;
+    let now = now_secs();
+    let mut text = String::new();
+    let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>| {
+        text.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+        for (labels, value) in samples {
+            text.push_str(&format!("{}{} {}\n", name, labels, value));
+        }
+    };
+    family("mogwai_host_cpu_percent", "gauge", "Host CPU usage in percent", vec![(String::new(), host.cpu_percent as f64)]);
+    family("mogwai_host_memory_used_bytes", "gauge", "Host memory in use", vec![(String::new(), (host.memory_used_mb * 1024 * 1024) as f64)]);
+    family("mogwai_host_disk_read_bytes_per_second", "gauge", "Host disk read throughput", vec![(String::new(), host.disk_read_mb_s * 1024.0 * 1024.0)]);
+    family("mogwai_host_disk_write_bytes_per_second", "gauge", "Host disk write throughput", vec![(String::new(), host.disk_write_mb_s * 1024.0 * 1024.0)]);
+    family("mogwai_running_tasks", "gauge", "Tasks running on the engine", vec![(String::new(), snapshot.tasks.len() as f64)]);
+    let task_labels = |task: &TaskInfo| format!("{{task_id=\"{}\",type=\"{}\"}}", label_value(&task.id), label_value(&task.test_type));
+    family("mogwai_task_running", "gauge", "1 for every running task", snapshot.tasks.iter().map(|task| (task_labels(task), 1.0)).collect());
+    family(
+        "mogwai_task_elapsed_seconds",
+        "gauge",
+        "Seconds a running task has been running",
+        snapshot.tasks.iter().map(|task| (task_labels(task), now - task.started_at)).collect(),
+    );
+    family("mogwai_tasks_finished_total", "counter", "Tasks finished since the engine started", vec![(String::new(), finished_total as f64)]);
+    family("mogwai_tasks_failed_total", "counter", "Tasks that ended with an error since the engine started", vec![(String::new(), failed_total as f64)]);
+    text
+}
+
+/// Escape a Prometheus label value
+fn label_value(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Replace the engine's group on the Pushgateway, so tasks that ended drop out
+async fn push_group(client: &reqwest::Client, url: &str, body: String) -> Result<(), String> {
+    let response = client
+        .put(url)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("{} {}", status, text.trim()));
+    }
+    Ok(())
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
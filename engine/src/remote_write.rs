@@ -0,0 +1,207 @@
+//! Prometheus remote-write export
+//!
+//! Engine pods are often gone before a scrape reaches them, so with
+//! `MOGWAI_REMOTE_WRITE_URL` set the engine pushes its samples to a remote-write
+//! endpoint (Prometheus with `--web.enable-remote-write-receiver`, Mimir, Thanos,
+//! VictoriaMetrics, ...) every `MOGWAI_REMOTE_WRITE_INTERVAL_SECS` (default 15):
+//!
+//! - host: `mogwai_host_cpu_percent`, `mogwai_host_memory_used_bytes`,
+//!   `mogwai_host_disk_read_bytes_per_second`, `mogwai_host_disk_write_bytes_per_second`,
+//!   `mogwai_running_tasks`
+//! - each running task: `mogwai_task_running` (always 1), `mogwai_task_elapsed_seconds`
+//! - each task finished since the last push: `mogwai_task_iterations`,
+//!   `mogwai_task_achieved_load_percent`, `mogwai_task_throughput_bytes_per_second`,
+//!   `mogwai_task_page_faults_per_second` (with a `kind` label of `minor` or `major`),
+//!   `mogwai_task_failed` (1 when it ended with an error)
+//!
+//! Every series has an `instance` label (`MOGWAI_NODE_NAME`, or the hostname); task
+//! series add `task_id`, and running tasks `type`. `MOGWAI_REMOTE_WRITE_TOKEN` is sent
+//! as a bearer token.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use prost::Message;
+use crate::metrics;
+use crate::thread_manager::{self, GLOBAL_REGISTRY};
+
+/// Push interval unless MOGWAI_REMOTE_WRITE_INTERVAL_SECS is set
+const DEFAULT_INTERVAL_SECS: u64 = 15;
+
+/// Give up on a push after this long; the next one carries fresh samples anyway
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Remote-write 1.0 protobuf messages (prometheus/prompb, types.proto and remote.proto)
+
+#[derive(Clone, PartialEq, Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64, // Milliseconds since the Unix epoch
+}
+
+/// Where and how often to push
+struct Config {
+    url: String,
+    token: Option<String>,
+    interval: Duration,
+    instance: String,
+}
+
+impl Config {
+    /// None when MOGWAI_REMOTE_WRITE_URL is unset
+    fn from_env() -> Option<Self> {
+        let url = std::env::var("MOGWAI_REMOTE_WRITE_URL").ok().filter(|url| !url.is_empty())?;
+        let interval = std::env::var("MOGWAI_REMOTE_WRITE_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        Some(Config {
+            url,
+            token: std::env::var("MOGWAI_REMOTE_WRITE_TOKEN").ok().filter(|token| !token.is_empty()),
+            interval: Duration::from_secs(interval),
+            instance: metrics::node_name(),
+        })
+    }
+}
+
+/// Start pushing in the background if MOGWAI_REMOTE_WRITE_URL is set
+pub fn start() {
+    let Some(config) = Config::from_env() else {
+        return;
+    };
+    tracing::info!(url = %config.url, interval_secs = config.interval.as_secs(), "Pushing metrics with remote write");
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder().timeout(PUSH_TIMEOUT).build().unwrap_or_default();
+        let mut tick = tokio::time::interval(config.interval);
+        let mut last_push = now_secs();
+        let mut failing = false;
+        loop {
+            tick.tick().await;
+            let pushed_at = now_secs();
+            let request = collect(&config.instance, last_push);
+            last_push = pushed_at;
+
+            match push(&client, &config, &request).await {
+                Ok(()) if failing => {
+                    tracing::info!(url = %config.url, "Remote write works again");
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Report a failing endpoint once, not on every push
+                Err(e) if !failing => {
+                    tracing::warn!(url = %config.url, error = %e, "Remote write failed");
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+/// The current samples, and those of tasks that finished after `since` (Unix seconds)
+fn collect(instance: &str, since: f64) -> WriteRequest {
+    let now = now_secs();
+    let timestamp = (now * 1000.0) as i64;
+    let mut timeseries = Vec::new();
+    let mut add = |name: &str, labels: &[(&str, &str)], value: f64, timestamp: i64| {
+        let mut labels: Vec<Label> = [("__name__", name), ("instance", instance)]
+            .iter()
+            .chain(labels)
+            .map(|(name, value)| Label { name: name.to_string(), value: value.to_string() })
+            .collect();
+        // Receivers expect the labels sorted by name
+        labels.sort_by(|a, b| a.name.cmp(&b.name));
+        timeseries.push(TimeSeries { labels, samples: vec![Sample { value, timestamp }] });
+    };
+
+    let tasks = thread_manager::task_infos(&GLOBAL_REGISTRY);
+    let host = metrics::sample(tasks.len());
+    add("mogwai_host_cpu_percent", &[], host.cpu_percent as f64, timestamp);
+    add("mogwai_host_memory_used_bytes", &[], (host.memory_used_mb * 1024 * 1024) as f64, timestamp);
+    add("mogwai_host_disk_read_bytes_per_second", &[], host.disk_read_mb_s * 1024.0 * 1024.0, timestamp);
+    add("mogwai_host_disk_write_bytes_per_second", &[], host.disk_write_mb_s * 1024.0 * 1024.0, timestamp);
+    add("mogwai_running_tasks", &[], tasks.len() as f64, timestamp);
+
+    for task in &tasks {
+        let labels = [("task_id", task.id.as_str()), ("type", task.test_type.as_str())];
+        add("mogwai_task_running", &labels, 1.0, timestamp);
+        add("mogwai_task_elapsed_seconds", &labels, now - task.started_at, timestamp);
+    }
+
+    for finished in thread_manager::list_results().iter().filter(|entry| entry.finished_at > since) {
+        let labels = [("task_id", finished.task_id.as_str())];
+        let at = (finished.finished_at * 1000.0) as i64;
+        add("mogwai_task_failed", &labels, if finished.error.is_some() { 1.0 } else { 0.0 }, at);
+        let Some(result) = &finished.result else {
+            continue;
+        };
+        add("mogwai_task_iterations", &labels, result.iterations as f64, at);
+        if let Some(load) = result.achieved_load {
+            add("mogwai_task_achieved_load_percent", &labels, load, at);
+        }
+        if let Some(mb_per_s) = result.mb_per_s {
+            add("mogwai_task_throughput_bytes_per_second", &labels, mb_per_s * 1024.0 * 1024.0, at);
+        }
+        if let Some(faults) = &result.faults {
+            let task_id = finished.task_id.as_str();
+            add("mogwai_task_page_faults_per_second", &[("task_id", task_id), ("kind", "minor")], faults.minor_per_s, at);
+            add("mogwai_task_page_faults_per_second", &[("task_id", task_id), ("kind", "major")], faults.major_per_s, at);
+        }
+    }
+
+    WriteRequest { timeseries }
+}
+
+/// Send one snappy-compressed WriteRequest
+async fn push(client: &reqwest::Client, config: &Config, request: &WriteRequest) -> Result<(), String> {
+    let body = snap::raw::Encoder::new()
+        .compress_vec(&request.encode_to_vec())
+        .map_err(|e| e.to_string())?;
+    let mut post = client
+        .post(&config.url)
+        .header("Content-Encoding", "snappy")
+        .header("Content-Type", "application/x-protobuf")
+        .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+        .body(body);
+    if let Some(token) = &config.token {
+        post = post.bearer_auth(token);
+    }
+    let response = post.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("{} {}", status, text.trim()));
+    }
+    Ok(())
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
@@ -0,0 +1,107 @@
+//! Request ids, to follow one call through the CLI, controller and engine logs
+//!
+//! Every request carries an `X-Request-Id`: the caller's when it sent a usable one (up to
+//! 128 letters, digits and `-_.:`), otherwise a fresh one. The engine logs it with each
+//! call that changes something or fails, returns it in the `X-Request-Id` response header,
+//! adds it to error bodies and records it on the tasks the request started.
+//! A W3C `traceparent` header puts the request in the caller's trace (see `otlp`).
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use serde_json::Value;
+use std::time::Instant;
+use tracing::Instrument;
+
+pub const HEADER: &str = "x-request-id";
+
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    // The id of the request being handled, for code that has no HttpRequest at hand
+    static CURRENT: String;
+}
+
+/// The id of the request being handled, if any
+pub fn current() -> Option<String> {
+    CURRENT.try_with(String::clone).ok()
+}
+
+/// The caller's id when it is usable, otherwise a new one
+pub fn accept_or_generate(header: Option<&str>) -> String {
+    header
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_LEN)
+        .filter(|id| id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+/// Middleware giving every request an id
+pub async fn middleware(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let id = accept_or_generate(req.headers().get(HEADER).and_then(|value| value.to_str().ok()));
+    let started = Instant::now();
+    let method = req.method().clone();
+    let path = req.path().to_string();
+
+    // Everything logged while handling the request carries its id; a traced caller's
+    // traceparent makes the request (and the tasks it starts) part of the caller's trace
+    let traceparent = req.headers().get("traceparent").and_then(|value| value.to_str().ok()).map(str::to_string);
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        traceparent,
+        otel.http.request.method = %method,
+        otel.url.path = %path,
+        otel.http.response.status_code = tracing::field::Empty,
+    );
+    let response = CURRENT.scope(id.clone(), async move { next.call(req).await }.instrument(span.clone())).await?.map_into_boxed_body();
+    let status = response.status();
+    let failed = status.is_client_error() || status.is_server_error();
+    span.record("otel.http.response.status_code", status.as_u16());
+    if failed || !matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        let duration_ms = started.elapsed().as_millis() as u64;
+        span.in_scope(|| {
+            if failed {
+                tracing::warn!(method = %method, path, status = status.as_u16(), duration_ms, "Request failed");
+            } else {
+                tracing::info!(method = %method, path, status = status.as_u16(), duration_ms, "Request handled");
+            }
+        });
+    }
+    let mut response = if failed { with_id_in_body(response, &id).await } else { response };
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HeaderName::from_static(HEADER), value);
+    }
+    Ok(response)
+}
+
+/// Adds the id to an error body: a `request_id` field in JSON objects, a suffix on text
+async fn with_id_in_body(response: ServiceResponse<BoxBody>, id: &str) -> ServiceResponse<BoxBody> {
+    let (req, res) = response.into_parts();
+    let json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let (head, payload) = res.into_parts();
+    let Ok(bytes) = body::to_bytes(payload).await else {
+        return ServiceResponse::new(req, head.set_body(BoxBody::new(())));
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut fields)) if json => {
+            fields.entry("request_id").or_insert_with(|| Value::from(id));
+            Value::Object(fields).to_string()
+        }
+        // Relayed errors may carry the id already
+        _ if text.contains(id) => text.into_owned(),
+        _ if text.trim().is_empty() => String::new(),
+        _ => format!("{} (request ID: {})", text.trim_end(), id),
+    };
+    let res: HttpResponse = head.set_body(BoxBody::new(body));
+    ServiceResponse::new(req, res)
+}
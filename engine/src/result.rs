@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// What a stress test achieved; stored as the task's result when it finishes
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct StressResult {
+    pub iterations: u64,                      // Work loops (cpu), page-touch passes or balloon cycles (mem), write/read cycles (disk), children (fork), acquisitions (lock), map cycles (pagefault), calls (syscall)
+    pub achieved_load: Option<f64>,           // Measured busy time per thread in percent (cpu)
+    pub cpu_load: Option<f64>,                // CPU time the threads actually got per thread in percent; below achieved_load when throttled (cpu)
+    pub thread_loads: Vec<ThreadLoad>,        // Per worker thread (cpu)
+    pub system_load: Option<f64>,             // Mean node-wide CPU utilization in percent (cpu, closed loop)
+    pub mb_per_s: Option<f64>,                // Bytes written plus read per second (disk)
+    pub elapsed_secs: f64,                    // Wall time of the test
+    pub stopped: bool,                        // Ended early through the stop flag
+    pub oom_kills: Option<u64>,               // Kernel OOM kills while the test ran (mem, Linux)
+    pub errors: Vec<String>,                  // Non-fatal problems, at most one per thread
+    pub paths: Vec<PathThroughput>,           // Per target directory (disk tests with several paths)
+    pub corrupt_blocks: Option<u64>,          // 4 KiB blocks read back differently than written (disk, verify)
+    pub mismatches: Vec<Mismatch>,            // The first corrupt blocks found
+    pub lock: Option<LockStats>,              // Acquisition rate and wait times (lock)
+    pub faults: Option<FaultStats>,           // Page faults the threads took (pagefault)
+    pub syscalls: Option<SyscallStats>,       // Call rates, in total and per thread (syscall)
+    pub iteration_times: Option<Percentiles>, // Time of one touch pass or balloon step (mem), write/read cycle (disk); pauses excluded
+}
+
+// Load one CPU worker thread achieved, over its own run time
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ThreadLoad {
+    pub thread: usize,
+    pub busy_percent: f64, // Wall time spent in the work phase
+    pub cpu_percent: f64,  // CPU time the kernel gave the thread; lower than busy_percent when preempted or throttled
+}
+
+// Distribution of recorded timings, in microseconds
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Percentiles {
+    pub count: u64, // Timings recorded
+    pub mean_us: f64,
+    pub p50_us: f64,
+    pub p90_us: f64,
+    pub p95_us: f64,
+    pub p99_us: f64,
+    pub p999_us: f64,
+    pub max_us: f64,
+}
+
+// What the threads of a lock-contention test saw
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LockStats {
+    pub acquisitions_per_s: f64,
+    pub contended_percent: f64, // Acquisitions that found the lock taken and had to wait
+    pub wait: Percentiles,      // Time from asking for the lock to holding it
+}
+
+// Page faults the threads of a page-fault test took, from their own resource usage
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FaultStats {
+    pub minor: u64, // Served without I/O (anonymous memory, page cache hits)
+    pub major: u64, // Needed a read from the device
+    pub minor_per_s: f64,
+    pub major_per_s: f64,
+}
+
+// Call rates of a syscall test
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SyscallStats {
+    pub calls_per_s: f64,          // All threads together
+    pub ns_per_call: f64,          // Mean wall time of one call, kernel entry and exit included
+    pub threads: Vec<ThreadCalls>, // Per worker thread
+}
+
+// Calls one syscall worker thread made
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ThreadCalls {
+    pub thread: usize,
+    pub calls: u64,
+    pub calls_per_s: f64,
+}
+
+// Node health over a soak test, sampled by the probes (see soak)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Health {
+    pub interval_secs: u64,
+    pub samples: Vec<HealthSample>,
+    pub breach: Option<String>, // The threshold a probe crossed, which ended the test
+}
+
+// One round of probes; a probe that could not run leaves its field empty
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct HealthSample {
+    pub at_secs: f64,                 // Since the test started
+    pub available_mb: Option<u64>,    // MemAvailable
+    pub load_per_cpu: Option<f64>,    // 1-minute load average divided by the CPUs
+    pub disk_latency_ms: Option<f64>, // A 4 KiB write and fsync
+}
+
+// Throughput of one target directory of a disk test
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PathThroughput {
+    pub path: String,
+    pub threads: usize,
+    pub mb_per_s: f64,               // Bytes written plus read per second
+    pub errors: Vec<String>,         // Non-fatal problems of this path's threads
+    pub corrupt_blocks: Option<u64>, // Blocks that failed verification (verify)
+}
+
+// A block that read back differently than it was written
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Mismatch {
+    pub file: String,
+    pub offset: u64, // Byte offset of the block in the file
+    pub cycle: u64,  // Write/read cycle that found it
+}
+
+// Why a stress test could not run (or failed completely)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum StressError {
+    InvalidParameter(String), // Rejected before any load was generated
+    Io(String),               // Every disk operation failed
+    Fork(String),             // fork() failed; children created so far were reaped
+    WorkerPanicked(String),   // A worker thread panicked
+    Plugin(String),           // A custom workload reported a failure
+    Killed(String),           // The isolated task process was killed (e.g. by its cgroup's OOM killer)
+    Unavailable(String),      // Needs a privileged operation this engine cannot use (see capabilities)
+    Limit(String),            // Beyond the operator's safety limits (see limits)
+    Unhealthy(String),        // A soak-test health probe crossed its threshold (see soak)
+}
+
+impl fmt::Display for StressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StressError::InvalidParameter(msg) => write!(f, "Invalid parameter: {}", msg),
+            StressError::Io(msg) => write!(f, "I/O error: {}", msg),
+            StressError::Fork(msg) => write!(f, "Fork failed: {}", msg),
+            StressError::WorkerPanicked(msg) => write!(f, "Worker thread panicked: {}", msg),
+            StressError::Plugin(msg) => write!(f, "Custom workload failed: {}", msg),
+            StressError::Killed(msg) => write!(f, "Task process killed: {}", msg),
+            StressError::Unavailable(msg) => write!(f, "Unavailable: {}", msg),
+            StressError::Limit(msg) => write!(f, "Safety limit: {}", msg),
+            StressError::Unhealthy(msg) => write!(f, "Node unhealthy: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StressError {}
+
+impl From<tokio::task::JoinError> for StressError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        StressError::WorkerPanicked(e.to_string())
+    }
+}
@@ -0,0 +1,94 @@
+//! Retention of finished tasks' data
+//!
+//! Results, lifecycle events and artifacts (with the test files kept for them) pile up on
+//! a long-lived engine. They are bounded by:
+//!
+//! - `MOGWAI_RETENTION_SECS`: age past which they are dropped (default: no age limit)
+//! - `MOGWAI_RETENTION_COUNT`: finished tasks whose results are kept (default 100)
+//!
+//! A background pruner applies the limits every minute; POST /prune applies them right
+//! away, or stricter ones given in its body. Artifacts go with their task's result, and
+//! events by age. Nothing of a running task is dropped.
+
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::{artifacts, events};
+use crate::thread_manager::{self, GLOBAL_REGISTRY};
+
+/// Finished tasks kept unless MOGWAI_RETENTION_COUNT is set
+const DEFAULT_COUNT: usize = 100;
+
+/// How often the background pruner runs
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Retention limits; the body of POST /prune, where unset fields take the configured ones
+#[derive(Deserialize, Clone, Copy, Default, Debug)]
+pub struct Policy {
+    pub max_age_secs: Option<u64>,
+    pub max_count: Option<usize>,
+}
+
+/// What a prune dropped
+#[derive(Serialize, Default, Debug)]
+pub struct Pruned {
+    pub results: usize,
+    pub artifacts: usize,
+    pub events: usize,
+}
+
+fn configured() -> &'static Policy {
+    static POLICY: OnceLock<Policy> = OnceLock::new();
+    POLICY.get_or_init(|| {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok());
+        Policy {
+            max_age_secs: var("MOGWAI_RETENTION_SECS").filter(|&secs| secs > 0),
+            max_count: var("MOGWAI_RETENTION_COUNT").map(|count| count as usize),
+        }
+    })
+}
+
+/// Results kept as tasks finish
+pub fn max_count() -> usize {
+    configured().max_count.unwrap_or(DEFAULT_COUNT)
+}
+
+/// Start the background pruner
+pub fn start() {
+    let policy = configured();
+    if let Some(secs) = policy.max_age_secs {
+        tracing::info!(max_age_secs = secs, max_count = max_count(), "Pruning finished tasks' data");
+    }
+    tokio::spawn(async {
+        let mut tick = tokio::time::interval(PRUNE_INTERVAL);
+        tick.tick().await;
+        loop {
+            tick.tick().await;
+            let pruned = prune(Policy::default(), |_| true);
+            if pruned.results + pruned.artifacts + pruned.events > 0 {
+                tracing::debug!(?pruned, "Pruned finished tasks' data");
+            }
+        }
+    });
+}
+
+/// Apply `policy`, falling back to the configured limits, to the tasks `matches` selects
+pub fn prune(policy: Policy, matches: impl Fn(&str) -> bool) -> Pruned {
+    let max_age = policy.max_age_secs.or(configured().max_age_secs);
+    let max_count = policy.max_count.unwrap_or_else(max_count);
+    let cutoff = max_age.map(|secs| {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() - secs as f64
+    });
+
+    let results = thread_manager::prune_results(cutoff, max_count, &matches).len();
+    // Artifacts outlive neither the task nor its result
+    let artifacts = artifacts::prune(|id| matches(id) && thread_manager::get_result(id).is_none());
+    let events = match cutoff {
+        Some(cutoff) => {
+            let running = thread_manager::list_tasks(&GLOBAL_REGISTRY);
+            events::prune(cutoff, |id| matches(id) && !running.iter().any(|running| running == id))
+        }
+        None => 0,
+    };
+    Pruned { results, artifacts, events }
+}
@@ -0,0 +1,305 @@
+//! Upload of task results and logs to S3-compatible object storage
+//!
+//! Results and logs otherwise live only in the engine's memory and are lost with the
+//! pod. With `MOGWAI_S3_BUCKET` set, every finished task is uploaded as
+//!
+//! - `<prefix>/<batch>/<node>/<task_id>/task.json`: the task as listed by GET /tasks
+//! - `<prefix>/<batch>/<node>/<task_id>/result.json`: the entry of GET /results
+//! - `<prefix>/<batch>/<node>/<task_id>/logs.txt`: the task's log lines
+//!
+//! where `<prefix>` is `MOGWAI_S3_PREFIX` (default `mogwai`) and `<batch>` the request's
+//! `batch_id` (`unbatched` without one), so each batch lands under its own key prefix.
+//! `MOGWAI_S3_ENDPOINT` selects an S3-compatible store (MinIO, Ceph, R2, ...; default AWS
+//! in `MOGWAI_S3_REGION`, default us-east-1). Credentials come from
+//! `MOGWAI_S3_ACCESS_KEY_ID` and `MOGWAI_S3_SECRET_ACCESS_KEY` (or the usual `AWS_*`
+//! variables). Requests use path-style URLs and AWS Signature Version 4.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast::error::RecvError;
+use crate::{logs, metrics};
+use crate::thread_manager::{self, TaskInfo, TaskResult, GLOBAL_REGISTRY};
+
+/// Key prefix unless MOGWAI_S3_PREFIX is set
+const DEFAULT_PREFIX: &str = "mogwai";
+
+/// How often finished tasks are looked for
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Log lines kept per task; older lines are dropped first
+const MAX_LOG_LINES: usize = 10_000;
+
+/// Wait this long after a task finished before uploading it, for its last log lines
+const SETTLE_SECS: f64 = 1.0;
+
+/// Forget log lines of tasks without a result after this long (e.g. rejected submissions)
+const STALE_SECS: f64 = 300.0;
+
+/// Give up on an upload after this long
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bucket, credentials and key layout
+pub struct Config {
+    endpoint: reqwest::Url, // Service URL; objects are at <endpoint>/<bucket>/<key>
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    prefix: String,
+}
+
+impl Config {
+    /// None unless MOGWAI_S3_BUCKET and credentials are set
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let bucket = var("MOGWAI_S3_BUCKET")?;
+        let region = var("MOGWAI_S3_REGION")
+            .or_else(|| var("AWS_REGION"))
+            .unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = var("MOGWAI_S3_ENDPOINT").unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = match reqwest::Url::parse(&endpoint) {
+            Ok(url) => url,
+            Err(e) => {
+                tracing::warn!(endpoint = %endpoint, error = %e, "MOGWAI_S3_ENDPOINT is not a valid URL; uploads disabled");
+                return None;
+            }
+        };
+        let (Some(access_key), Some(secret_key)) = (
+            var("MOGWAI_S3_ACCESS_KEY_ID").or_else(|| var("AWS_ACCESS_KEY_ID")),
+            var("MOGWAI_S3_SECRET_ACCESS_KEY").or_else(|| var("AWS_SECRET_ACCESS_KEY")),
+        ) else {
+            tracing::warn!("MOGWAI_S3_BUCKET is set without credentials; uploads disabled");
+            return None;
+        };
+        Some(Config {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            session_token: var("MOGWAI_S3_SESSION_TOKEN").or_else(|| var("AWS_SESSION_TOKEN")),
+            prefix: var("MOGWAI_S3_PREFIX")
+                .map(|prefix| prefix.trim_matches('/').to_string())
+                .unwrap_or_else(|| DEFAULT_PREFIX.to_string()),
+        })
+    }
+
+    /// Key under the configured prefix, e.g. `mogwai/<rest>`
+    pub fn key(&self, rest: &str) -> String {
+        if self.prefix.is_empty() {
+            rest.to_string()
+        } else {
+            format!("{}/{}", self.prefix, rest)
+        }
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+}
+
+/// A task seen running, waiting for its result
+#[derive(Default)]
+struct Pending {
+    info: Option<TaskInfo>,
+    logs: Vec<String>,
+    last_seen: f64, // Unix time of the last log line or running listing
+}
+
+/// Start uploading finished tasks in the background if MOGWAI_S3_BUCKET is set
+pub fn start() {
+    let Some(config) = Config::from_env() else {
+        return;
+    };
+    tracing::info!(bucket = %config.bucket, prefix = %config.prefix, "Uploading finished tasks to S3");
+
+    let (_, mut log_lines) = logs::subscribe();
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder().timeout(UPLOAD_TIMEOUT).build().unwrap_or_default();
+        let node = metrics::node_name();
+        let mut pending: HashMap<String, Pending> = HashMap::new();
+        let mut tick = tokio::time::interval(POLL_INTERVAL);
+        let mut uploaded_until = now_secs();
+        let mut failing = false;
+        loop {
+            tokio::select! {
+                line = log_lines.recv() => match line {
+                    Ok(line) => {
+                        let task = pending.entry(line.task_id).or_default();
+                        if task.logs.len() >= MAX_LOG_LINES {
+                            task.logs.remove(0);
+                        }
+                        task.logs.push(format!("{:.3} {}", line.timestamp, line.message));
+                        task.last_seen = line.timestamp;
+                    }
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                },
+                _ = tick.tick() => {
+                    let now = now_secs();
+                    for info in thread_manager::task_infos(&GLOBAL_REGISTRY) {
+                        let task = pending.entry(info.id.clone()).or_default();
+                        task.info = Some(info);
+                        task.last_seen = now;
+                    }
+
+                    // Tasks that finished since the last upload and had time to log their last lines
+                    let cutoff = now - SETTLE_SECS;
+                    let results: Vec<TaskResult> = thread_manager::list_results()
+                        .into_iter()
+                        .filter(|entry| entry.finished_at > uploaded_until && entry.finished_at <= cutoff)
+                        .collect();
+                    uploaded_until = uploaded_until.max(cutoff);
+                    for result in results {
+                        let task = pending.remove(&result.task_id).unwrap_or_default();
+                        match upload_task(&client, &config, &node, &result, task).await {
+                            Ok(()) if failing => {
+                                tracing::info!(bucket = %config.bucket, "S3 uploads work again");
+                                failing = false;
+                            }
+                            Ok(()) => {}
+                            // Report a failing bucket once, not for every task
+                            Err(e) if !failing => {
+                                tracing::warn!(task_id = %result.task_id, bucket = %config.bucket, error = %e, "S3 upload failed");
+                                failing = true;
+                            }
+                            Err(_) => {}
+                        }
+                    }
+                    pending.retain(|_, task| now - task.last_seen < STALE_SECS);
+                }
+            }
+        }
+    });
+}
+
+/// Upload the task, result and log objects of one finished task
+async fn upload_task(
+    client: &reqwest::Client,
+    config: &Config,
+    node: &str,
+    result: &TaskResult,
+    task: Pending,
+) -> Result<(), String> {
+    let batch = task.info.as_ref().and_then(|info| info.batch_id.clone()).unwrap_or_else(|| "unbatched".to_string());
+    let dir = config.key(&format!("{}/{}/{}", key_segment(&batch), key_segment(node), key_segment(&result.task_id)));
+
+    if let Some(info) = &task.info {
+        let body = serde_json::to_vec_pretty(info).map_err(|e| e.to_string())?;
+        put_object(client, config, &format!("{}/task.json", dir), body, "application/json").await?;
+    }
+    let body = serde_json::to_vec_pretty(result).map_err(|e| e.to_string())?;
+    put_object(client, config, &format!("{}/result.json", dir), body, "application/json").await?;
+    let mut body = task.logs.join("\n");
+    body.push('\n');
+    put_object(client, config, &format!("{}/logs.txt", dir), body.into_bytes(), "text/plain; charset=utf-8").await
+}
+
+/// Keep ids usable as a single key segment
+fn key_segment(text: &str) -> String {
+    text.replace('/', "_")
+}
+
+/// PUT one object, signed with AWS Signature Version 4
+pub async fn put_object(
+    client: &reqwest::Client,
+    config: &Config,
+    key: &str,
+    body: Vec<u8>,
+    content_type: &str,
+) -> Result<(), String> {
+    let path = format!(
+        "{}/{}/{}",
+        config.endpoint.path().trim_end_matches('/'),
+        uri_encode(&config.bucket),
+        uri_encode(key)
+    );
+    let mut url = config.endpoint.clone();
+    url.set_path(&path);
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(format!("endpoint {} has no host", config.endpoint)),
+    };
+
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex(&Sha256::digest(&body));
+
+    // Signed headers, sorted by name
+    let mut headers = vec![
+        ("content-type", content_type.to_string()),
+        ("host", host),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &config.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_request = format!("PUT\n{}\n\n{}\n{}\n{}", url.path(), canonical_headers, signed_headers, payload_hash);
+
+    let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = hmac(format!("AWS4{}", config.secret_key).as_bytes(), date.as_bytes());
+    for part in [config.region.as_str(), "s3", "aws4_request"] {
+        key = hmac(&key, part.as_bytes());
+    }
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        config.access_key, scope, signed_headers, signature
+    );
+
+    let mut request = client.put(url).header("Authorization", authorization).body(body);
+    for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+        request = request.header(name, value);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("{} {}", status, text.trim()));
+    }
+    Ok(())
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encode everything but unreserved characters and '/'
+fn uri_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
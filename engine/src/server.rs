@@ -0,0 +1,582 @@
+//! The engine's HTTP API
+//!
+//! Served by the engine binary on port 8080, and by `mogwai serve` on bare-metal
+//! hosts, so both take the same requests from the CLI, GUI and controller.
+
+use actix_web::{web, App, HttpRequest, HttpServer, Responder, HttpResponse};
+use serde::Deserialize;
+use std::sync::{Arc, atomic::AtomicBool};
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tracing::Instrument;
+use tokio_stream::wrappers::{BroadcastStream, IntervalStream, ReceiverStream};
+use crate::{agent, artifacts, calibration, capabilities, cgroup, clients, cors, disk_stress, estimate, events, influx, limits, listing, logging, logs, mdns, metrics, otlp, plugin, push, remote_write, request_id, retention, s3, soak, stress, sys_info, thread_manager, watchdog};
+use crate::StressError;
+use crate::events::EventKind;
+use crate::stress::{TestContext, TestParams};
+use crate::thread_manager::{ExtendError, TaskInfo, GLOBAL_REGISTRY};
+
+// The calling client (None = anonymous), or the error rejecting the request
+fn caller(req: &HttpRequest) -> Result<Option<String>, actix_web::Error> {
+    let header = |name| req.headers().get(name).and_then(|value| value.to_str().ok());
+    clients::identify(header("Authorization"), header(clients::CLIENT_HEADER)).map_err(|e| {
+        if clients::tokens_required() {
+            actix_web::error::ErrorUnauthorized(e)
+        } else {
+            actix_web::error::ErrorBadRequest(e)
+        }
+    })
+}
+
+// Start a test of the type the path names, e.g. POST /cpu-stress or /lock-stress
+async fn start_stress_test(req: HttpRequest, test_type: web::Path<String>, params: web::Json<TestParams>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    // The fork flag turns the CPU request into a fork test
+    let name = match test_type.as_str() {
+        "cpu" if params.fork == Some(true) => "fork",
+        name => name,
+    };
+    start_test(name, params.into_inner(), client.as_deref())
+}
+
+// Custom workload loaded from the plugin directory
+async fn start_custom_test(req: HttpRequest, name: web::Path<String>, params: web::Json<TestParams>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    if !plugin::names().contains(&name) {
+        return HttpResponse::NotFound().body(format!("Unknown custom workload: {}", name));
+    }
+    start_test(&name, params.into_inner(), client.as_deref())
+}
+
+// Names of the loaded custom workloads
+async fn list_custom_tests() -> impl Responder {
+    HttpResponse::Ok().json(plugin::names())
+}
+
+// Safety limits every request is held to
+async fn get_limits() -> impl Responder {
+    HttpResponse::Ok().json(limits::current())
+}
+
+// Privileged operations and whether this engine can use them
+async fn get_capabilities() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "restricted": capabilities::restricted(),
+        "capabilities": capabilities::status(),
+        "plugins": plugin::supported(),
+    }))
+}
+
+// Validate the parameters, then run the registered test type as a stoppable task owned by the client
+fn start_test(name: &str, mut params: TestParams, client: Option<&str>) -> HttpResponse {
+    let Some(test) = stress::lookup(name) else {
+        return HttpResponse::NotFound().body(format!("Unknown test type: {}", name));
+    };
+    let submitted = serde_json::to_value(&params).unwrap_or_default();
+    let checked = calibration::apply(&mut params, test.name())
+        .and_then(|()| limits::enforce(test.as_ref(), &mut params))
+        .and_then(|adjustments| test.validate(&params).map(|()| adjustments));
+    let adjustments = match checked {
+        Ok(adjustments) => adjustments,
+        Err(e @ (StressError::Unavailable(_) | StressError::Limit(_))) => return HttpResponse::Forbidden().body(e.to_string()),
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let soak = match soak::options(&params.extra) {
+        Ok(soak) => soak,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let task_id = clients::scoped_id(client, thread_manager::generate_task_id(test.name()));
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let flag_clone = stop_flag.clone();
+    let mut ctx = TestContext::new(task_id.clone(), &params);
+    let announcement = test.describe(&ctx);
+    let expected_duration = test.expected_duration(&ctx);
+    let until_stopped = ctx.duration == 0 && test.extendable(&ctx);
+
+    // The engine enforces the duration itself, so POST /extend can prolong the run
+    let deadline = (ctx.duration > 0 && test.extendable(&ctx)).then_some(ctx.duration);
+    if deadline.is_some() {
+        ctx.duration = 0;
+    }
+
+    let info = TaskInfo::new(&task_id, test.name(), expected_duration, submitted);
+    // Everything the task logs carries these, and the request's id
+    let span = tracing::info_span!("task", task_id = %task_id, test_type = test.name(), node = tracing::field::Empty);
+    if let Some(node) = &info.node {
+        span.record("node", node.as_str());
+    }
+    let spawn = {
+        let task_id = task_id.clone(); // clone scoped for async block
+        let recorded = info.clone();
+        let test = Arc::clone(&test);
+        let stop_flag = Arc::clone(&stop_flag);
+
+        move || {
+            if let Some(duration) = deadline {
+                thread_manager::watch_deadline(&task_id, duration, stop_flag);
+            }
+            tokio::spawn(async move {
+                let _recorder = artifacts::start(&recorded);
+                for adjustment in adjustments {
+                    logs::emit(&task_id, format!("Safety limit: {}", adjustment));
+                }
+                logs::emit(&task_id, announcement);
+                if until_stopped {
+                    logs::emit(&task_id, format!(
+                        "Running indefinitely. To stop, send a POST request to: http://localhost:8080/stop/{}", task_id));
+                }
+                events::record(&task_id, EventKind::Started, None);
+                let probes = soak.map(|options| soak::start(&task_id, options, Arc::clone(&flag_clone)));
+                let run = async {
+                    if cgroup::enabled() {
+                        cgroup::run_isolated(test.as_ref(), ctx, flag_clone).await
+                    } else {
+                        test.run(ctx, flag_clone).await
+                    }
+                };
+                let mut outcome = events::with_progress(&task_id, run).await;
+                let health = match probes {
+                    Some(probes) => Some(probes.finish().await),
+                    None => None,
+                };
+                // Reaching the deadline is a normal end, not a stop
+                if thread_manager::clear_deadline(&task_id) {
+                    if let Ok(result) = &mut outcome {
+                        result.stopped = false;
+                    }
+                }
+                if let Err(e) = &outcome {
+                    logs::emit(&task_id, format!("Error: {}", e));
+                }
+                thread_manager::record_result(&task_id, outcome, health);
+                logs::emit(&task_id, format!("{} stress test finished", test.label()));
+            }.instrument(span))
+        }
+    };
+
+    if let Err(full) = thread_manager::register_task(task_id.clone(), stop_flag, info, spawn) {
+        return HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", full.retry_after.to_string()))
+            .body(format!(
+                "Engine is at its limit of {} running tasks; retry in {} seconds",
+                full.running, full.retry_after
+            ));
+    }
+
+    HttpResponse::Ok().body(format!("{} stress task started with ID: {}", test.label(), task_id))
+}
+
+// Task listing (the caller's own tasks)
+async fn list_running_tasks(req: HttpRequest, query: web::Query<listing::ListQuery>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    let registry = &GLOBAL_REGISTRY;
+    let lock = registry.lock().unwrap_or_else(|e| e.into_inner());
+    tracing::debug!(tasks = ?lock.keys(), "Listing tasks");
+    drop(lock);
+    let tasks: Vec<TaskInfo> = thread_manager::task_infos(registry)
+        .into_iter()
+        .filter(|task| clients::owns(client.as_deref(), &task.id))
+        .collect();
+    listing_response(&query, tasks)
+}
+
+// Query of /stop and /stop-all
+#[derive(Deserialize)]
+struct StopQuery {
+    grace: Option<u64>, // Seconds before a task that ignores the stop flag is killed
+}
+
+// Task stopping
+async fn stop_running_task(req: HttpRequest, id: web::Path<String>, query: web::Query<StopQuery>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    if !clients::owns(client.as_deref(), &id) {
+        return HttpResponse::Forbidden().body(format!("Task {} belongs to another client", id));
+    }
+    let grace = query.grace.map(Duration::from_secs).unwrap_or_else(thread_manager::stop_grace);
+    if !thread_manager::stop_task(&id, &GLOBAL_REGISTRY, grace) {
+        return HttpResponse::NotFound().body(format!("No running task {}", id));
+    }
+    HttpResponse::Ok().body(format!("-> POST/stop{} request sent", id))
+}
+
+// Body of POST /extend/{id}
+#[derive(Deserialize)]
+struct ExtendRequest {
+    seconds: u64, // Added to the task's remaining duration
+}
+
+// Prolong a running task without restarting it
+async fn extend_running_task(req: HttpRequest, id: web::Path<String>, body: web::Json<ExtendRequest>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    if !clients::owns(client.as_deref(), &id) {
+        return HttpResponse::Forbidden().body(format!("Task {} belongs to another client", id));
+    }
+    let duration = thread_manager::task_infos(&GLOBAL_REGISTRY)
+        .into_iter()
+        .find(|task| task.id == *id)
+        .map_or(0, |task| task.duration);
+    let seconds = match limits::extension(duration, body.seconds) {
+        Ok(seconds) => seconds,
+        Err(e) => return HttpResponse::Forbidden().body(e.to_string()),
+    };
+    match thread_manager::extend_deadline(&id, seconds, &GLOBAL_REGISTRY) {
+        Ok(remaining) => {
+            logs::emit(&id, format!("Duration extended by {} seconds ({} seconds left)", seconds, remaining));
+            HttpResponse::Ok().json(serde_json::json!({ "task_id": *id, "remaining_secs": remaining }))
+        }
+        Err(ExtendError::UnknownTask) => HttpResponse::NotFound().body(format!("No running task {}", id)),
+        Err(ExtendError::NotExtendable) => HttpResponse::Conflict().body(format!(
+            "Task {} has no duration to extend (it runs until stopped, or its test type keeps its own time)",
+            id
+        )),
+    }
+}
+
+async fn stop_all_tasks(req: HttpRequest, query: web::Query<StopQuery>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    let registry = &GLOBAL_REGISTRY;
+    let task_ids: Vec<String> = thread_manager::list_tasks(registry)
+        .into_iter()
+        .filter(|id| clients::owns(client.as_deref(), id))
+        .collect();
+
+    let grace = query.grace.map(Duration::from_secs).unwrap_or_else(thread_manager::stop_grace);
+    for id in &task_ids {
+        thread_manager::stop_task(id, registry, grace);
+    }
+
+    HttpResponse::Ok().body(format!("-> POST/stop-all request sent to all {} tasks", task_ids.len()))
+}
+
+// Results of the caller's recently finished tasks (oldest first)
+async fn list_task_results(req: HttpRequest, query: web::Query<listing::ListQuery>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    let results: Vec<_> = thread_manager::list_results()
+        .into_iter()
+        .filter(|result| clients::owns(client.as_deref(), &result.task_id))
+        .collect();
+    listing_response(&query, results)
+}
+
+// A listing as the plain array, or as a page when the query asks for one
+fn listing_response<T: listing::Listed + serde::Serialize>(query: &listing::ListQuery, entries: Vec<T>) -> HttpResponse {
+    if query.is_empty() {
+        return HttpResponse::Ok().json(entries);
+    }
+    match query.page(entries) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+// Result of one finished task
+async fn get_task_result(req: HttpRequest, id: web::Path<String>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    match thread_manager::get_result(&id).filter(|result| clients::owns(client.as_deref(), &result.task_id)) {
+        Some(result) => HttpResponse::Ok().json(result),
+        None => HttpResponse::NotFound().body(format!("No result for task {} (still running or unknown)", id)),
+    }
+}
+
+// A task's samples, logs, histograms and kept files as a tar.gz (see artifacts)
+async fn get_artifacts(req: HttpRequest, id: web::Path<String>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    if !clients::owns(client.as_deref(), &id) {
+        return HttpResponse::NotFound().body(format!("No artifacts for task {}", id));
+    }
+    let Some(chunks) = artifacts::archive(&id) else {
+        return HttpResponse::NotFound().body(format!("No artifacts for task {} (unknown or too old)", id));
+    };
+    let body = ReceiverStream::new(chunks).map(|chunk| chunk.map(web::Bytes::from).map_err(actix_web::Error::from));
+    HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.tar.gz\"", id.replace(['"', '/', ':'], "_"))))
+        .streaming(body)
+}
+
+// Stored intensity calibration of this node
+async fn get_calibration() -> impl Responder {
+    match calibration::load() {
+        Some(stored) => HttpResponse::Ok().json(stored),
+        None => HttpResponse::NotFound().body("This node is not calibrated; POST /calibration first"),
+    }
+}
+
+// Probe the hardware (about six seconds), store the level mapping and return it
+async fn run_calibration() -> impl Responder {
+    let calibrated = web::block(|| {
+        // Same working directory as the disk stress test files
+        let result = calibration::calibrate(std::path::Path::new("."));
+        calibration::save(&result).map(|()| result)
+    })
+    .await;
+    match calibrated {
+        Ok(Ok(result)) => HttpResponse::Ok().json(result),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Calibration failed: {}", e)),
+    }
+}
+
+#[derive(Deserialize)]
+struct SysInfoQuery {
+    format: Option<sys_info::ReportFormat>, // json (default), human, yaml, csv or prometheus
+    bench: Option<bool>,                    // Append baseline micro-benchmark scores (takes a few seconds)
+}
+
+// Hardware report of this node (same output as the sys_info binary)
+async fn get_sysinfo(query: web::Query<SysInfoQuery>) -> impl Responder {
+    let format = query.format.unwrap_or_default();
+    let bench = query.bench.unwrap_or(false);
+    let report = web::block(move || {
+        let mut info = sys_info::gather_system_info();
+        if bench {
+            // Same working directory as the disk stress test files
+            info.bench = Some(sys_info::bench::run(std::path::Path::new(".")));
+        }
+        sys_info::render(&info, format)
+    })
+    .await;
+    match report {
+        Ok(Ok(body)) => HttpResponse::Ok().content_type(format.content_type()).body(body),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(format!("Failed to render system info: {}", e)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to gather system info: {}", e)),
+    }
+}
+
+// Expected resource use of a battery of tests and whether this node can take it now
+async fn estimate_battery(request: web::Json<estimate::EstimateRequest>) -> impl Responder {
+    // Disk fill tests read the filesystem usage
+    match web::block(move || estimate::estimate(request.into_inner())).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to estimate: {}", e)),
+    }
+}
+
+// Host metrics snapshot (CPU %, memory, disk MB/s)
+async fn get_metrics() -> impl Responder {
+    let running = thread_manager::list_tasks(&GLOBAL_REGISTRY).len();
+    let snapshot = web::block(move || metrics::sample(running)).await;
+    match snapshot {
+        Ok(snapshot) => HttpResponse::Ok().json(snapshot),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to sample metrics: {}", e)),
+    }
+}
+
+#[derive(Deserialize)]
+struct LogQuery {
+    task: Option<String>, // Only stream lines of this task
+}
+
+// Live task output as Server-Sent Events, starting with the recent backlog
+async fn stream_logs(req: HttpRequest, query: web::Query<LogQuery>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    let task = query.into_inner().task;
+    let (backlog, receiver) = logs::subscribe();
+
+    // Lagging subscribers skip the lines they missed instead of disconnecting
+    let lines = tokio_stream::iter(backlog)
+        .chain(BroadcastStream::new(receiver).filter_map(Result::ok))
+        .filter(move |line| task.as_ref().is_none_or(|task| *task == line.task_id))
+        .filter(move |line| clients::owns(client.as_deref(), &line.task_id))
+        .map(|line| logs::to_event(&line));
+
+    // Comment lines keep proxies from closing an idle stream
+    let keepalive = IntervalStream::new(tokio::time::interval(Duration::from_secs(15)))
+        .map(|_| ": keepalive\n\n".to_string());
+
+    let events = lines
+        .merge(keepalive)
+        .map(|event| Ok::<_, actix_web::Error>(web::Bytes::from(event)));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+// Query of GET /events
+#[derive(Deserialize)]
+struct EventQuery {
+    since: Option<f64>,   // Only events after this Unix time
+    task: Option<String>, // Only this task's events
+}
+
+// Recent task events (the caller's own tasks), oldest first
+async fn list_events(req: HttpRequest, query: web::Query<EventQuery>) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    let query = query.into_inner();
+    let events: Vec<_> = events::since(query.since.unwrap_or(0.0))
+        .into_iter()
+        .filter(|event| query.task.as_ref().is_none_or(|task| *task == event.task_id))
+        .filter(|event| clients::owns(client.as_deref(), &event.task_id))
+        .collect();
+    HttpResponse::Ok().json(events)
+}
+
+// Apply the retention limits now, or the stricter ones in the (optional) body; a named
+// client prunes only its own tasks
+async fn prune_task_data(req: HttpRequest, body: web::Bytes) -> impl Responder {
+    let client = match caller(&req) {
+        Ok(client) => client,
+        Err(e) => return e.error_response(),
+    };
+    let policy = if body.iter().all(u8::is_ascii_whitespace) {
+        retention::Policy::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(policy) => policy,
+            Err(e) => return HttpResponse::BadRequest().body(format!("Invalid prune request: {}", e)),
+        }
+    };
+    let pruned = retention::prune(policy, |id| clients::owns(client.as_deref(), id));
+    HttpResponse::Ok().json(pruned)
+}
+
+// Every API route; served under /v1 and, for older clients, without a prefix
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/{type}-stress", web::post().to(start_stress_test))
+        .route("/custom", web::get().to(list_custom_tests))
+        .route("/custom/{name}", web::post().to(start_custom_test))
+        .route("/tasks", web::get().to(list_running_tasks))
+        .route("/stop/{id}", web::post().to(stop_running_task))
+        .route("/stop-all", web::post().to(stop_all_tasks))
+        .route("/extend/{id}", web::post().to(extend_running_task))
+        .route("/results", web::get().to(list_task_results))
+        .route("/results/{id}", web::get().to(get_task_result))
+        .route("/artifacts/{id}", web::get().to(get_artifacts))
+        .route("/metrics", web::get().to(get_metrics))
+        .route("/sysinfo", web::get().to(get_sysinfo))
+        .route("/capabilities", web::get().to(get_capabilities))
+        .route("/limits", web::get().to(get_limits))
+        .route("/calibration", web::get().to(get_calibration))
+        .route("/calibration", web::post().to(run_calibration))
+        .route("/logs/stream", web::get().to(stream_logs))
+        .route("/events", web::get().to(list_events))
+        .route("/estimate", web::post().to(estimate_battery))
+        .route("/prune", web::post().to(prune_task_data));
+}
+
+/// Serve the API on `bind` (e.g. `0.0.0.0:8080`) until SIGINT or SIGTERM
+///
+/// A binary serving it must also hand `cgroup::CHILD_FLAG` invocations to
+/// `cgroup::run_child`, since isolated tasks re-run the current executable.
+pub async fn serve(bind: &str) -> std::io::Result<()> {
+    logging::init();
+    if std::env::var("MOGWAI_CGROUPS").is_ok_and(|v| v == "1" || v == "true") {
+        match cgroup::init() {
+            Ok(base) => tracing::info!(cgroup = %base.display(), "Stress tasks run in their own cgroups"),
+            Err(e) => tracing::warn!(error = %e, "cgroup isolation disabled, running tasks in-process"),
+        }
+    }
+
+    // Probe privileged operations once, so requests needing them are refused up front
+    if capabilities::restricted() {
+        tracing::info!("Restricted mode: privileged operations are disabled");
+    }
+    for status in capabilities::status().iter().filter(|s| !s.available) {
+        tracing::warn!(capability = ?status.capability, reason = status.reason.as_deref().unwrap_or("unknown"), "Capability unavailable");
+    }
+
+    // Read the safety limits now, so they show up in the startup log
+    limits::current();
+    tracing::info!("{}", cors::describe());
+
+    // Register custom workloads before serving requests
+    let plugin_dir = std::env::var("MOGWAI_PLUGIN_DIR").unwrap_or_else(|_| plugin::DEFAULT_PLUGIN_DIR.to_string());
+    plugin::load_plugins(std::path::Path::new(&plugin_dir));
+
+    // Kill tasks that run far past their duration
+    thread_manager::watch_hard_deadlines(Arc::clone(&GLOBAL_REGISTRY));
+
+    // Kill everything if the node stops responding
+    watchdog::start();
+
+    // Push metrics where scraping short-lived engine pods is unreliable
+    remote_write::start();
+    influx::start();
+    push::start();
+
+    // Send test traces to Jaeger or Tempo
+    otlp::start();
+
+    // Keep results and logs beyond the life of the pod
+    s3::start();
+
+    // Drop old results, events and artifacts on long-lived engines
+    retention::start();
+
+    // Setup HTTP server to handle requests
+    let server = HttpServer::new(move || {
+        //using move to transfer ownership of task registry
+        App::new()
+            .wrap(actix_web::middleware::from_fn(request_id::middleware))
+            .wrap(cors::middleware())
+            .service(web::scope("/v1").configure(routes))
+            // The unversioned paths predate /v1 and stay as aliases for existing clients
+            .configure(routes)
+    })
+    .bind(bind)?;
+
+    // Engines that the controller cannot reach connect out to it instead
+    if let Some(&local) = server.addrs().first() {
+        agent::start(local);
+        // Lab networks without Kubernetes find engines by mDNS
+        mdns::start(local);
+    }
+    let server = server
+        .disable_signals()      // Tasks are stopped first, see below
+        .run();
+
+    // On SIGINT/SIGTERM stop the running tasks and remove their test files before exiting,
+    // instead of leaving orphaned workers and multi-GB files behind
+    let handle = server.handle();
+    tokio::spawn(async move {
+        thread_manager::shutdown_signal().await;
+        tracing::info!("Shutting down: stopping running tasks");
+        let killed = thread_manager::shutdown(&GLOBAL_REGISTRY, thread_manager::stop_grace()).await;
+        if killed > 0 {
+            tracing::warn!(killed, "Force-killed tasks that did not stop in time");
+        }
+        let removed = disk_stress::remove_temp_files();
+        if removed > 0 {
+            tracing::info!(removed, "Removed leftover test files");
+        }
+        let _ = tokio::task::spawn_blocking(mdns::stop).await;
+        handle.stop(true).await;
+    });
+    server.await
+}
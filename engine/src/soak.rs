@@ -0,0 +1,184 @@
+//! Soak tests: node health probes alongside a long stress run
+//!
+//! Any test takes a `soak` object next to its other parameters, e.g.
+//! `"soak": {"interval": 60, "min_available_mb": 1024, "max_load_per_cpu": 4, "max_disk_latency_ms": 50}`.
+//! While the test runs, every `interval` seconds (default 60) the engine samples
+//!
+//! - memory headroom: `MemAvailable` from `/proc/meminfo`
+//! - load: the 1-minute load average from `/proc/loadavg`, divided by the CPUs
+//! - disk latency: writing and fsyncing 4 KiB in `path` (default: the working directory)
+//!
+//! and appends the sample to the task's health timeline, returned with its result. A
+//! sample beyond a threshold stops the test and fails the task with an `unhealthy` error
+//! naming the probe; the timeline up to then is kept. Thresholds left out are not checked.
+
+use serde_json::{Map, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::{self, JoinHandle};
+use crate::disk_stress::{remove_temp_file, track_temp_file};
+use crate::logs;
+use crate::result::{Health, HealthSample, StressError};
+
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// Past this many samples every other one is dropped, so a long soak keeps its whole span
+const MAX_SAMPLES: usize = 10_000;
+
+/// Bytes the disk probe writes
+const PROBE_BYTES: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct SoakOptions {
+    pub interval: Duration,
+    pub min_available_mb: Option<u64>,
+    pub max_load_per_cpu: Option<f64>,
+    pub max_disk_latency_ms: Option<f64>,
+    pub path: PathBuf, // Directory of the disk probe's file
+}
+
+/// The soak options of a request; None when it has no `soak` object
+pub fn options(extra: &Map<String, Value>) -> Result<Option<SoakOptions>, StressError> {
+    let Some(soak) = extra.get("soak") else {
+        return Ok(None);
+    };
+    let invalid = |msg: String| StressError::InvalidParameter(format!("soak: {}", msg));
+    let fields = soak.as_object().ok_or_else(|| invalid("expected an object".to_string()))?;
+    let number = |name: &str| -> Result<Option<f64>, StressError> {
+        match fields.get(name) {
+            None => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .filter(|n| *n >= 0.0)
+                .map(Some)
+                .ok_or_else(|| invalid(format!("{} must be a number of at least 0", name))),
+        }
+    };
+    if let Some(unknown) = fields.keys().find(|name| {
+        !matches!(name.as_str(), "interval" | "min_available_mb" | "max_load_per_cpu" | "max_disk_latency_ms" | "path")
+    }) {
+        return Err(invalid(format!("unknown field {}", unknown)));
+    }
+    let interval = number("interval")?.map_or(DEFAULT_INTERVAL_SECS, |secs| secs as u64);
+    if interval == 0 {
+        return Err(invalid("interval must be at least 1 second".to_string()));
+    }
+    let path = match fields.get("path") {
+        None => PathBuf::from("."),
+        Some(Value::String(path)) => PathBuf::from(path),
+        Some(_) => return Err(invalid("path must be a string".to_string())),
+    };
+    Ok(Some(SoakOptions {
+        interval: Duration::from_secs(interval),
+        min_available_mb: number("min_available_mb")?.map(|mb| mb as u64),
+        max_load_per_cpu: number("max_load_per_cpu")?,
+        max_disk_latency_ms: number("max_disk_latency_ms")?,
+        path,
+    }))
+}
+
+/// Probes running next to a test, until `finish`
+pub struct Probes {
+    done: Arc<AtomicBool>,
+    handle: JoinHandle<Health>,
+}
+
+/// Start probing; a breach sets the test's stop flag
+pub fn start(task_id: &str, options: SoakOptions, stop_flag: Arc<AtomicBool>) -> Probes {
+    let done = Arc::new(AtomicBool::new(false));
+    let task_id = task_id.to_string();
+    let finished = Arc::clone(&done);
+    let handle = task::spawn_blocking(move || {
+        let file = options.path.join(format!(".mogwai-soak-{}", task_id));
+        track_temp_file(&file);
+        let mut health = Health { interval_secs: options.interval.as_secs(), ..Health::default() };
+        let started = Instant::now();
+        let mut next = started;
+        while !finished.load(Ordering::SeqCst) {
+            if Instant::now() < next {
+                std::thread::sleep((next - Instant::now()).min(Duration::from_millis(200)));
+                continue;
+            }
+            next += options.interval;
+
+            let sample = HealthSample {
+                at_secs: started.elapsed().as_secs_f64(),
+                available_mb: available_mb(),
+                load_per_cpu: load_per_cpu(),
+                disk_latency_ms: disk_latency_ms(&file),
+            };
+            let breach = breach(&options, &sample);
+            if health.samples.len() >= MAX_SAMPLES {
+                let mut index = 0;
+                health.samples.retain(|_| {
+                    index += 1;
+                    index % 2 == 1
+                });
+            }
+            health.samples.push(sample);
+            if let Some(breach) = breach {
+                logs::emit(&task_id, format!("Health probe failed, stopping the test: {}", breach));
+                health.breach = Some(breach);
+                stop_flag.store(true, Ordering::SeqCst);
+                break;
+            }
+        }
+        remove_temp_file(&file);
+        health
+    });
+    Probes { done, handle }
+}
+
+impl Probes {
+    /// Stop probing; the timeline so far
+    pub async fn finish(self) -> Health {
+        self.done.store(true, Ordering::SeqCst);
+        self.handle.await.unwrap_or_default()
+    }
+}
+
+/// The first threshold a sample crosses
+fn breach(options: &SoakOptions, sample: &HealthSample) -> Option<String> {
+    if let (Some(min), Some(available)) = (options.min_available_mb, sample.available_mb) {
+        if available < min {
+            return Some(format!("{} MB of memory available, below {} MB", available, min));
+        }
+    }
+    if let (Some(max), Some(load)) = (options.max_load_per_cpu, sample.load_per_cpu) {
+        if load > max {
+            return Some(format!("load {:.2} per CPU, above {:.2}", load, max));
+        }
+    }
+    if let (Some(max), Some(latency)) = (options.max_disk_latency_ms, sample.disk_latency_ms) {
+        if latency > max {
+            return Some(format!("disk write latency {:.1} ms, above {:.1} ms", latency, max));
+        }
+    }
+    None
+}
+
+fn available_mb() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024)
+}
+
+fn load_per_cpu() -> Option<f64> {
+    let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+    let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    Some(load / cpus as f64)
+}
+
+fn disk_latency_ms(file: &Path) -> Option<f64> {
+    let start = Instant::now();
+    let mut handle = OpenOptions::new().create(true).write(true).truncate(true).open(file).ok()?;
+    handle.write_all(&[0x5a; PROBE_BYTES]).ok()?;
+    handle.sync_all().ok()?;
+    Some(start.elapsed().as_secs_f64() * 1000.0)
+}
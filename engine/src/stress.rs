@@ -0,0 +1,910 @@
+//! Pluggable stress test types
+//!
+//! Every test type implements `StressTest` and is looked up by name in a registry,
+//! so the engine's route handlers share one start path instead of repeating the
+//! spawn/register/record boilerplate per type. New types only need an
+//! implementation and a `register` call.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock, atomic::AtomicBool};
+
+use crate::capabilities::{self, Capability};
+use crate::disk_stress::DiskOptions;
+use crate::memory_stress::{Advice, Balloon, MemoryOptions, Touch};
+use crate::result::{StressError, StressResult};
+use crate::codec_stress::{self, Codec};
+use crate::fault_stress::{self, Backing, FaultOptions};
+use crate::lock_stress::{self, LockOptions, Primitive};
+use crate::syscall_stress::{self, Syscall, SyscallOptions};
+use crate::{cpu_stress, disk_fill, disk_stress, fork_stress, logs, memory_stress};
+
+/// Request body of the stress endpoints (all optional)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TestParams {
+    pub intensity: Option<usize>, // Threads (child processes for fork)
+    pub duration: Option<u64>,    // Seconds, 0 = until stopped
+    pub load: Option<f64>,        // Target CPU load in percent
+    pub system_load: Option<f64>, // Target node-wide CPU utilization in percent (closed loop)
+    pub size: Option<usize>,      // MB per thread (memory) or per file (disk)
+    pub fork: Option<bool>,       // /cpu-stress runs the fork test instead
+    pub level: Option<u8>,        // Calibrated intensity level (1-10) filling the fields left out
+    #[serde(flatten)]
+    pub extra: Map<String, Value>, // Other fields, passed on to custom workloads
+}
+
+/// Parameters of one run with the defaults filled in
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TestContext {
+    pub task_id: String,
+    pub intensity: usize,
+    pub duration: u64,
+    pub load: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_load: Option<f64>,
+    pub size: usize,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl TestContext {
+    pub fn new(task_id: String, params: &TestParams) -> Self {
+        TestContext {
+            task_id,
+            // Closed-loop CPU tests need every core to reach high targets
+            intensity: params.intensity.unwrap_or(if params.system_load.is_some() { num_cpus::get() } else { 4 }),
+            duration: params.duration.unwrap_or(10),
+            load: params.load,
+            system_load: params.system_load,
+            size: params.size.unwrap_or(256),
+            extra: params.extra.clone(),
+        }
+    }
+}
+
+/// Resources a run is expected to take at its peak (see `POST /estimate`)
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Estimate {
+    pub memory_mb: u64,                  // Memory the test allocates
+    pub disk_mb: BTreeMap<PathBuf, u64>, // Space its files take, per directory
+    pub cpu_cores: f64,                  // Cores kept busy on average
+}
+
+/// Future returned by `StressTest::run`
+pub type StressFuture = Pin<Box<dyn Future<Output = Result<StressResult, StressError>> + Send>>;
+
+/// A test type the engine can run
+pub trait StressTest: Send + Sync {
+    /// Registry key and task id prefix, e.g. "cpu"
+    fn name(&self) -> &'static str;
+
+    /// Name used in messages, e.g. "CPU"
+    fn label(&self) -> &'static str;
+
+    /// Reject bad parameters before a task is started
+    fn validate(&self, _params: &TestParams) -> Result<(), StressError> {
+        Ok(())
+    }
+
+    /// Log line announcing the run
+    fn describe(&self, ctx: &TestContext) -> String {
+        format!(
+            "Starting {} stress test with {} threads for {} seconds...",
+            self.label().to_lowercase(),
+            ctx.intensity,
+            ctx.duration
+        )
+    }
+
+    /// Generate load until the duration passes or `stop_flag` is set
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture;
+
+    /// Whether `stop_flag` ends this run cleanly at any point, so the engine can run it
+    /// until stopped and enforce (and extend) the duration itself
+    fn extendable(&self, _ctx: &TestContext) -> bool {
+        true
+    }
+
+    /// Resources this run is expected to take; by default every thread keeps a core busy
+    /// and allocates nothing worth counting
+    fn estimate(&self, ctx: &TestContext) -> Result<Estimate, StressError> {
+        Ok(Estimate { cpu_cores: ctx.intensity as f64, ..Default::default() })
+    }
+
+    /// Seconds this run is expected to take (0 = until stopped, or not known up front);
+    /// the engine kills it once it overruns this by more than the deadline margin
+    fn expected_duration(&self, ctx: &TestContext) -> u64 {
+        ctx.duration
+    }
+}
+
+static REGISTRY: Lazy<RwLock<BTreeMap<String, Arc<dyn StressTest>>>> = Lazy::new(|| {
+    let builtin: [Arc<dyn StressTest>; 9] = [
+        Arc::new(CpuTest),
+        Arc::new(MemoryTest),
+        Arc::new(DiskTest),
+        Arc::new(ForkTest),
+        Arc::new(CompressTest),
+        Arc::new(CryptoTest),
+        Arc::new(LockTest),
+        Arc::new(PageFaultTest),
+        Arc::new(SyscallTest),
+    ];
+    RwLock::new(builtin.into_iter().map(|test| (test.name().to_string(), test)).collect())
+});
+
+/// Add a test type (replaces one with the same name)
+pub fn register(test: Arc<dyn StressTest>) {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry.insert(test.name().to_string(), test);
+}
+
+/// Test type by name
+pub fn lookup(name: &str) -> Option<Arc<dyn StressTest>> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.get(name).cloned()
+}
+
+/// Names of all registered test types
+pub fn names() -> Vec<String> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.keys().cloned().collect()
+}
+
+/// Boolean option from the request body (absent = false)
+fn flag(extra: &Map<String, Value>, name: &str) -> bool {
+    extra.get(name).and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// Directories a request's `paths` must lie in: those of `MOGWAI_DISK_ROOTS`
+/// (comma-separated, default the temp directory) and `MOGWAI_DISK_DIR`
+fn disk_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = std::env::var("MOGWAI_DISK_ROOTS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|root| !root.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    if roots.is_empty() {
+        roots.push(std::env::temp_dir());
+    }
+    roots.extend(std::env::var_os("MOGWAI_DISK_DIR").filter(|dir| !dir.is_empty()).map(PathBuf::from));
+    roots.iter().filter_map(|root| root.canonicalize().ok()).collect()
+}
+
+/// Target directories of a disk request (`"paths": ["/mnt/ssd", ...]`), resolved and
+/// held to the disk roots; without any, `MOGWAI_DISK_DIR` (e.g. a provisioned volume
+/// mounted by the controller) if set
+fn disk_paths(extra: &Map<String, Value>) -> Result<Vec<PathBuf>, StressError> {
+    let Some(paths) = extra.get("paths") else {
+        let default_dir = std::env::var_os("MOGWAI_DISK_DIR").filter(|dir| !dir.is_empty());
+        return Ok(default_dir.map(PathBuf::from).into_iter().collect());
+    };
+    let invalid = || StressError::InvalidParameter("paths must be an array of directory names".to_string());
+    let roots = disk_roots();
+    paths
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|path| {
+            let path = path.as_str().map(PathBuf::from).ok_or_else(invalid)?;
+            // Resolving symlinks and `..` first, so neither leads out of the roots
+            let resolved = path
+                .canonicalize()
+                .ok()
+                .filter(|resolved| resolved.is_dir())
+                .ok_or_else(|| StressError::InvalidParameter(format!("{} is not a directory", path.display())))?;
+            if !roots.iter().any(|root| resolved.starts_with(root)) {
+                let roots: Vec<String> = roots.iter().map(|root| root.display().to_string()).collect();
+                return Err(StressError::InvalidParameter(format!(
+                    "{} is outside the directories disk tests may use ({}; see MOGWAI_DISK_ROOTS)",
+                    path.display(),
+                    roots.join(", ")
+                )));
+            }
+            Ok(resolved)
+        })
+        .collect()
+}
+
+/// Chunk size in KiB of a disk request with `"chunked": true` (`chunk_kb`, default 1024)
+fn disk_chunk_kb(extra: &Map<String, Value>) -> Result<Option<usize>, StressError> {
+    if !flag(extra, "chunked") {
+        return Ok(None);
+    }
+    match extra.get("chunk_kb").and_then(Value::as_u64) {
+        None => Ok(Some(disk_stress::DEFAULT_CHUNK_KB)),
+        Some(kb) if kb > 0 && kb % 4 == 0 => Ok(Some(kb as usize)),
+        Some(kb) => Err(StressError::InvalidParameter(format!("chunk_kb {} is not a positive multiple of 4", kb))),
+    }
+}
+
+/// Fill mode of a disk request (`"mode": "fill"`)
+struct DiskFill {
+    dir: PathBuf,         // First of `paths`, or MOGWAI_DISK_DIR, or the working directory
+    percent: f64,         // `fill_percent`, target used percentage
+    safety_floor_mb: u64, // `safety_floor_mb`, free space that must remain
+}
+
+fn disk_fill_options(extra: &Map<String, Value>) -> Result<Option<DiskFill>, StressError> {
+    match extra.get("mode").and_then(Value::as_str) {
+        None | Some("cycle") => Ok(None),
+        Some("fill") => Ok(Some(DiskFill {
+            dir: disk_paths(extra)?.into_iter().next().unwrap_or_else(|| PathBuf::from(".")),
+            percent: extra.get("fill_percent").and_then(Value::as_f64).unwrap_or(90.0),
+            safety_floor_mb: extra
+                .get("safety_floor_mb")
+                .and_then(Value::as_u64)
+                .unwrap_or(disk_fill::DEFAULT_SAFETY_FLOOR_MB),
+        })),
+        Some(other) => Err(StressError::InvalidParameter(format!("unknown disk mode '{}' (cycle or fill)", other))),
+    }
+}
+
+/// Ballooning options of a memory request (`"pattern": "balloon"`, `floor` MB, `period` seconds)
+fn balloon(extra: &Map<String, Value>) -> Result<Option<Balloon>, StressError> {
+    match extra.get("pattern").and_then(Value::as_str) {
+        None | Some("constant") => Ok(None),
+        Some("balloon") => {
+            let period_secs = extra.get("period").and_then(Value::as_f64).unwrap_or(20.0);
+            if period_secs < 1.0 {
+                return Err(StressError::InvalidParameter(format!("period {}s is shorter than 1 second", period_secs)));
+            }
+            let floor_mb = extra.get("floor").and_then(Value::as_u64).unwrap_or(0) as usize;
+            Ok(Some(Balloon { floor_mb, period_secs }))
+        }
+        Some(other) => Err(StressError::InvalidParameter(format!(
+            "unknown memory pattern '{}' (constant or balloon)",
+            other
+        ))),
+    }
+}
+
+/// Touch strategy of a memory request (`"touch": "page"` or `"line"`)
+fn memory_touch(extra: &Map<String, Value>) -> Result<Touch, StressError> {
+    match extra.get("touch").and_then(Value::as_str) {
+        None | Some("page") => Ok(Touch::Page),
+        Some("line") => Ok(Touch::Line),
+        Some(other) => Err(StressError::InvalidParameter(format!("unknown touch strategy '{}' (page or line)", other))),
+    }
+}
+
+/// Transparent huge page hint of a memory request (`"madvise": "hugepage"` or `"nohugepage"`)
+fn memory_advice(extra: &Map<String, Value>) -> Result<Option<Advice>, StressError> {
+    match extra.get("madvise").and_then(Value::as_str) {
+        None => Ok(None),
+        Some("hugepage") => Ok(Some(Advice::Hugepage)),
+        Some("nohugepage") => Ok(Some(Advice::NoHugepage)),
+        Some(other) => Err(StressError::InvalidParameter(format!(
+            "unknown madvise hint '{}' (hugepage or nohugepage)",
+            other
+        ))),
+    }
+}
+
+/// Compression level of a compress request (`zstd_level`, 1-19)
+fn zstd_level(extra: &Map<String, Value>) -> Result<i32, StressError> {
+    match extra.get("zstd_level").and_then(Value::as_i64) {
+        None => Ok(codec_stress::DEFAULT_ZSTD_LEVEL),
+        Some(level @ 1..=19) => Ok(level as i32),
+        Some(level) => Err(StressError::InvalidParameter(format!("zstd_level {} is not between 1 and 19", level))),
+    }
+}
+
+/// Algorithm of a crypto request (`"algorithm": "aes-gcm"` or `"sha256"`)
+fn crypto_codec(extra: &Map<String, Value>) -> Result<Codec, StressError> {
+    match extra.get("algorithm").and_then(Value::as_str) {
+        None | Some("aes-gcm") => Ok(Codec::AesGcm),
+        Some("sha256") => Ok(Codec::Sha256),
+        Some(other) => Err(StressError::InvalidParameter(format!(
+            "unknown algorithm '{}' (aes-gcm or sha256)",
+            other
+        ))),
+    }
+}
+
+/// What backs the regions of a page fault request (`backing`, default anonymous)
+fn fault_backing(extra: &Map<String, Value>) -> Result<Backing, StressError> {
+    match extra.get("backing").and_then(Value::as_str) {
+        None | Some("anonymous") => Ok(Backing::Anonymous),
+        Some("file") => Ok(Backing::File),
+        Some(other) => Err(StressError::InvalidParameter(format!(
+            "unknown backing '{}' (anonymous or file)",
+            other
+        ))),
+    }
+}
+
+/// Calls of a syscall request: `mix` is a list of names (equal weights) or an object of
+/// names and weights, e.g. `{"getpid": 3, "write": 1}`; by default every call once
+fn syscall_mix(extra: &Map<String, Value>) -> Result<SyscallOptions, StressError> {
+    let invalid = |message: String| StressError::InvalidParameter(message);
+    let syscall = |name: &str| {
+        Syscall::from_name(name).ok_or_else(|| {
+            invalid(format!("unknown syscall '{}' (getpid, clock_gettime, read or write)", name))
+        })
+    };
+    let mix = match extra.get("mix") {
+        None => return Ok(SyscallOptions::default()),
+        Some(Value::Array(names)) => names
+            .iter()
+            .map(|name| {
+                let name = name.as_str().ok_or_else(|| invalid("mix must list syscall names".to_string()))?;
+                Ok((syscall(name)?, 1))
+            })
+            .collect::<Result<Vec<_>, StressError>>()?,
+        Some(Value::Object(weights)) => weights
+            .iter()
+            .map(|(name, weight)| {
+                let weight = weight
+                    .as_u64()
+                    .filter(|&weight| weight <= 100)
+                    .ok_or_else(|| invalid(format!("weight of {} must be between 0 and 100", name)))?;
+                Ok((syscall(name)?, weight as u32))
+            })
+            .collect::<Result<Vec<_>, StressError>>()?,
+        Some(_) => return Err(invalid("mix must be a list of syscalls or an object of weights".to_string())),
+    };
+    if mix.iter().all(|&(_, weight)| weight == 0) {
+        return Err(invalid("mix has no syscall with a weight above 0".to_string()));
+    }
+    Ok(SyscallOptions { mix })
+}
+
+/// Contention shape of a lock request (`primitive`, `counters`, `hold_us`, `think_us`, `read_percent`)
+fn lock_options(extra: &Map<String, Value>) -> Result<LockOptions, StressError> {
+    let invalid = |message: String| StressError::InvalidParameter(message);
+    let defaults = LockOptions::default();
+    let micros = |name: &str, default: std::time::Duration| match extra.get(name) {
+        None => Ok(default),
+        Some(value) => value
+            .as_f64()
+            .filter(|&us| (0.0..=1_000_000.0).contains(&us))
+            .map(|us| std::time::Duration::from_secs_f64(us / 1_000_000.0))
+            .ok_or_else(|| invalid(format!("{} must be between 0 and 1000000 microseconds", name))),
+    };
+    let primitive = match extra.get("primitive").and_then(Value::as_str) {
+        None | Some("mutex") => Primitive::Mutex,
+        Some("rwlock") => Primitive::RwLock,
+        Some(other) => return Err(invalid(format!("unknown lock primitive '{}' (mutex or rwlock)", other))),
+    };
+    let counters = match extra.get("counters") {
+        None => defaults.counters,
+        Some(value) => value
+            .as_u64()
+            .filter(|&n| n >= 1)
+            .ok_or_else(|| invalid("counters must be at least 1".to_string()))? as usize,
+    };
+    let read_percent = match extra.get("read_percent") {
+        None => defaults.read_percent,
+        Some(value) => value
+            .as_f64()
+            .filter(|percent| (0.0..=100.0).contains(percent))
+            .ok_or_else(|| invalid("read_percent must be between 0 and 100".to_string()))?,
+    };
+    Ok(LockOptions {
+        primitive,
+        counters,
+        hold: micros("hold_us", defaults.hold)?,
+        think: micros("think_us", defaults.think)?,
+        read_percent,
+    })
+}
+
+/// Size must be at least 1 MB
+fn validate_size(params: &TestParams) -> Result<(), StressError> {
+    match params.size {
+        Some(0) => Err(StressError::InvalidParameter("size must be at least 1 MB".to_string())),
+        _ => Ok(()),
+    }
+}
+
+pub struct CpuTest;
+
+impl StressTest for CpuTest {
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn label(&self) -> &'static str {
+        "CPU"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        match (params.load, params.system_load) {
+            (Some(_), Some(_)) => Err(StressError::InvalidParameter(
+                "use either load (per thread) or system_load (node-wide), not both".to_string(),
+            )),
+            (Some(load), None) if !(0.0..=100.0).contains(&load) => Err(StressError::InvalidParameter(format!(
+                "target load {} is not between 0 and 100",
+                load
+            ))),
+            (None, Some(target)) if !(0.0..=100.0).contains(&target) => Err(StressError::InvalidParameter(format!(
+                "system load {} is not between 0 and 100",
+                target
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        if let Some(target) = ctx.system_load {
+            return format!(
+                "Starting closed-loop CPU stress test with {} threads holding the node at {}% for {} seconds...",
+                ctx.intensity, target, ctx.duration
+            );
+        }
+        format!(
+            "Starting CPU stress test with {} threads at {}% load for {} seconds...",
+            ctx.intensity,
+            ctx.load.unwrap_or(100.0),
+            ctx.duration
+        )
+    }
+
+    fn estimate(&self, ctx: &TestContext) -> Result<Estimate, StressError> {
+        let cpu_cores = match ctx.system_load {
+            // The closed loop adds load until the whole node is at the target
+            Some(target) => num_cpus::get() as f64 * target / 100.0,
+            None => ctx.intensity as f64 * ctx.load.unwrap_or(100.0) / 100.0,
+        };
+        Ok(Estimate { cpu_cores, ..Default::default() })
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        Box::pin(async move {
+            if let Some(target) = ctx.system_load {
+                return cpu_stress::stress_cpu_closed_loop(ctx.intensity, target, ctx.duration, stop_flag, ctx.task_id).await;
+            }
+            cpu_stress::stress_cpu(
+                ctx.intensity,
+                ctx.load.unwrap_or(100.0),
+                ctx.duration,
+                ctx.load.is_some(),
+                ctx.duration == 0,
+                stop_flag,
+                ctx.task_id,
+            )
+            .await
+        })
+    }
+}
+
+pub struct MemoryTest;
+
+impl StressTest for MemoryTest {
+    fn name(&self) -> &'static str {
+        "mem"
+    }
+
+    fn label(&self) -> &'static str {
+        "Memory"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        validate_size(params)?;
+        if flag(&params.extra, "lock") {
+            capabilities::require(Capability::Mlock)?;
+        }
+        if flag(&params.extra, "hugepages") {
+            capabilities::require(Capability::Hugepages)?;
+        }
+        memory_touch(&params.extra)?;
+        if memory_advice(&params.extra)?.is_some() && flag(&params.extra, "hugepages") {
+            return Err(StressError::InvalidParameter("madvise cannot be combined with hugepages".to_string()));
+        }
+        if let Some(pattern) = balloon(&params.extra)? {
+            if flag(&params.extra, "lock") || flag(&params.extra, "hugepages") {
+                return Err(StressError::InvalidParameter(
+                    "the balloon pattern cannot be combined with lock or hugepages".to_string(),
+                ));
+            }
+            let size = params.size.unwrap_or(256);
+            if pattern.floor_mb >= size {
+                return Err(StressError::InvalidParameter(format!(
+                    "floor {} MB must be below the size ({} MB)",
+                    pattern.floor_mb, size
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        if let Ok(Some(pattern)) = balloon(&ctx.extra) {
+            return format!(
+                "Starting memory ballooning test between {} and {} MB every {} seconds for {} seconds...",
+                pattern.floor_mb, ctx.size, pattern.period_secs, ctx.duration
+            );
+        }
+        format!("Starting memory stress test with {} MB for {} seconds...", ctx.size, ctx.duration)
+    }
+
+    // Threads touch their blocks twice a second and sleep in between
+    fn estimate(&self, ctx: &TestContext) -> Result<Estimate, StressError> {
+        Ok(Estimate { memory_mb: (ctx.intensity as u64).saturating_mul(ctx.size as u64), ..Default::default() })
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        Box::pin(async move {
+            memory_stress::check_memory_usage();
+            let oom_before = memory_stress::oom_kill_count();
+            let options = MemoryOptions {
+                lock: flag(&ctx.extra, "lock"),
+                hugepages: flag(&ctx.extra, "hugepages"),
+                balloon: balloon(&ctx.extra)?,
+                touch: memory_touch(&ctx.extra)?,
+                advice: memory_advice(&ctx.extra)?,
+            };
+            let outcome =
+                memory_stress::stress_memory_with(ctx.intensity, ctx.size, ctx.duration, stop_flag, ctx.task_id, options).await;
+            memory_stress::check_memory_usage();
+            outcome.map(|mut result| {
+                result.oom_kills = oom_before
+                    .zip(memory_stress::oom_kill_count())
+                    .map(|(before, after)| after.saturating_sub(before));
+                result
+            })
+        })
+    }
+}
+
+pub struct DiskTest;
+
+impl StressTest for DiskTest {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn label(&self) -> &'static str {
+        "Disk"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        validate_size(params)?;
+        if flag(&params.extra, "direct") {
+            capabilities::require(Capability::DirectIo)?;
+        }
+        let paths = disk_paths(&params.extra)?;
+        disk_chunk_kb(&params.extra)?;
+        if let Some(missing) = paths.iter().find(|path| !path.is_dir()) {
+            return Err(StressError::InvalidParameter(format!("{} is not a directory", missing.display())));
+        }
+        if let Some(fill) = disk_fill_options(&params.extra)? {
+            if paths.len() > 1 || flag(&params.extra, "direct") {
+                return Err(StressError::InvalidParameter(
+                    "fill mode takes at most one path and cannot be combined with direct".to_string(),
+                ));
+            }
+            disk_fill::plan(&fill.dir, fill.percent, fill.safety_floor_mb)?;
+            return Ok(());
+        }
+        let threads = params.intensity.unwrap_or(4);
+        if paths.len() > threads {
+            return Err(StressError::InvalidParameter(format!(
+                "{} paths need at least as many threads (intensity is {})",
+                paths.len(),
+                threads
+            )));
+        }
+        Ok(())
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        if let Ok(Some(fill)) = disk_fill_options(&ctx.extra) {
+            return format!(
+                "Starting disk fill test to {}% of {} for {} seconds (safety floor {} MB)...",
+                fill.percent,
+                fill.dir.display(),
+                ctx.duration,
+                fill.safety_floor_mb
+            );
+        }
+        format!("Starting disk stress test with {} MB for {} seconds...", ctx.size, ctx.duration)
+    }
+
+    // The duration of a fill test only counts once the disk is full
+    fn extendable(&self, ctx: &TestContext) -> bool {
+        !matches!(disk_fill_options(&ctx.extra), Ok(Some(_)))
+    }
+
+    // Filling first takes however long the disk needs
+    fn expected_duration(&self, ctx: &TestContext) -> u64 {
+        if self.extendable(ctx) { ctx.duration } else { 0 }
+    }
+
+    fn estimate(&self, ctx: &TestContext) -> Result<Estimate, StressError> {
+        if let Some(fill) = disk_fill_options(&ctx.extra)? {
+            let to_write = disk_fill::plan(&fill.dir, fill.percent, fill.safety_floor_mb)?;
+            return Ok(Estimate {
+                disk_mb: BTreeMap::from([(fill.dir, to_write / (1024 * 1024))]),
+                cpu_cores: 1.0,
+                ..Default::default()
+            });
+        }
+        // Every thread writes one file of `size` MB from a buffer of the same size
+        let mut dirs = disk_paths(&ctx.extra)?;
+        if dirs.is_empty() {
+            dirs.push(PathBuf::from("."));
+        }
+        let mut disk_mb = BTreeMap::new();
+        for thread_id in 0..ctx.intensity {
+            let total: &mut u64 = disk_mb.entry(dirs[thread_id % dirs.len()].clone()).or_default();
+            *total = total.saturating_add(ctx.size as u64);
+        }
+        // plus a read buffer: one chunk when chunked, else the whole file
+        let read_mb = disk_chunk_kb(&ctx.extra)?.map_or(ctx.size, |kb| kb.div_ceil(1024));
+        Ok(Estimate {
+            memory_mb: (ctx.intensity as u64).saturating_mul((ctx.size as u64).saturating_add(read_mb as u64)),
+            disk_mb,
+            cpu_cores: ctx.intensity as f64,
+        })
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        if let Ok(Some(fill)) = disk_fill_options(&ctx.extra) {
+            return Box::pin(disk_fill::fill_disk(
+                fill.dir,
+                fill.percent,
+                fill.safety_floor_mb,
+                ctx.duration,
+                stop_flag,
+                ctx.task_id,
+            ));
+        }
+        let options = DiskOptions {
+            direct: flag(&ctx.extra, "direct"),
+            paths: disk_paths(&ctx.extra).unwrap_or_default(),
+            verify: flag(&ctx.extra, "verify"),
+            chunk_kb: disk_chunk_kb(&ctx.extra).unwrap_or_default(),
+            keep_files: flag(&ctx.extra, "keep_files"),
+        };
+        Box::pin(disk_stress::stress_disk_with(ctx.intensity, ctx.size, ctx.duration, stop_flag, ctx.task_id, options))
+    }
+}
+
+/// Child processes that sleep for the duration; a stop request terminates them
+pub struct ForkTest;
+
+impl StressTest for ForkTest {
+    fn name(&self) -> &'static str {
+        "fork"
+    }
+
+    fn label(&self) -> &'static str {
+        "Fork"
+    }
+
+    fn validate(&self, _params: &TestParams) -> Result<(), StressError> {
+        capabilities::require(Capability::Fork)
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        format!(
+            "Starting fork stress test with {} processes for {} seconds...",
+            ctx.intensity, ctx.duration
+        )
+    }
+
+    // The children sleep for the duration on their own
+    fn extendable(&self, _ctx: &TestContext) -> bool {
+        false
+    }
+
+    // Sleeping children cost process slots, not CPU
+    fn estimate(&self, _ctx: &TestContext) -> Result<Estimate, StressError> {
+        Ok(Estimate::default())
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        Box::pin(async move {
+            let (processes, duration, task_id) = (ctx.intensity, ctx.duration, ctx.task_id.clone());
+            let outcome = tokio::task::spawn_blocking(move || {
+                fork_stress::stress_fork(processes, duration, &stop_flag, &task_id)
+            })
+            .await?;
+            if let Ok(result) = &outcome {
+                logs::emit(&ctx.task_id, format!("Created {} child processes.", result.iterations));
+            }
+            outcome
+        })
+    }
+}
+
+/// zstd compression and decompression of an in-memory buffer
+pub struct CompressTest;
+
+impl StressTest for CompressTest {
+    fn name(&self) -> &'static str {
+        "compress"
+    }
+
+    fn label(&self) -> &'static str {
+        "Compression"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        zstd_level(&params.extra).map(|_| ())
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        format!(
+            "Starting zstd compression stress test with {} threads at level {} for {} seconds...",
+            ctx.intensity,
+            zstd_level(&ctx.extra).unwrap_or(codec_stress::DEFAULT_ZSTD_LEVEL),
+            ctx.duration
+        )
+    }
+
+    fn estimate(&self, ctx: &TestContext) -> Result<Estimate, StressError> {
+        Ok(codec_stress::estimate(ctx.intensity))
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        let level = zstd_level(&ctx.extra).unwrap_or(codec_stress::DEFAULT_ZSTD_LEVEL);
+        Box::pin(codec_stress::stress_codec(Codec::Zstd { level }, ctx.intensity, ctx.duration, stop_flag, ctx.task_id))
+    }
+}
+
+/// AES-256-GCM encryption or SHA-256 hashing of an in-memory buffer
+pub struct CryptoTest;
+
+impl StressTest for CryptoTest {
+    fn name(&self) -> &'static str {
+        "crypto"
+    }
+
+    fn label(&self) -> &'static str {
+        "Crypto"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        crypto_codec(&params.extra).map(|_| ())
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        format!(
+            "Starting {} crypto stress test with {} threads for {} seconds...",
+            crypto_codec(&ctx.extra).unwrap_or(Codec::AesGcm).name(),
+            ctx.intensity,
+            ctx.duration
+        )
+    }
+
+    fn estimate(&self, ctx: &TestContext) -> Result<Estimate, StressError> {
+        Ok(codec_stress::estimate(ctx.intensity))
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        let codec = crypto_codec(&ctx.extra).unwrap_or(Codec::AesGcm);
+        Box::pin(codec_stress::stress_codec(codec, ctx.intensity, ctx.duration, stop_flag, ctx.task_id))
+    }
+}
+
+/// Threads contending on shared counters behind a Mutex or RwLock
+pub struct LockTest;
+
+impl StressTest for LockTest {
+    fn name(&self) -> &'static str {
+        "lock"
+    }
+
+    fn label(&self) -> &'static str {
+        "Lock"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        lock_options(&params.extra).map(|_| ())
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        let options = lock_options(&ctx.extra).unwrap_or_default();
+        format!(
+            "Starting {} contention test with {} threads on {} counters, holding {} us, for {} seconds...",
+            options.primitive.name(),
+            ctx.intensity,
+            options.counters,
+            options.hold.as_secs_f64() * 1_000_000.0,
+            ctx.duration
+        )
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        let options = lock_options(&ctx.extra).unwrap_or_default();
+        Box::pin(lock_stress::stress_lock(ctx.intensity, ctx.duration, stop_flag, ctx.task_id, options))
+    }
+}
+
+/// Threads mapping, touching and unmapping regions to keep the kernel faulting pages in
+pub struct PageFaultTest;
+
+impl StressTest for PageFaultTest {
+    fn name(&self) -> &'static str {
+        "pagefault"
+    }
+
+    fn label(&self) -> &'static str {
+        "Page fault"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        validate_size(params)?;
+        fault_backing(&params.extra)?;
+        disk_paths(&params.extra).map(|_| ())
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        format!(
+            "Starting page fault stress test with {} threads mapping {} MB {} regions for {} seconds...",
+            ctx.intensity,
+            ctx.size,
+            fault_backing(&ctx.extra).unwrap_or_default().name(),
+            ctx.duration
+        )
+    }
+
+    // Each thread maps one region at a time; file-backed regions are test files instead
+    fn estimate(&self, ctx: &TestContext) -> Result<Estimate, StressError> {
+        let mb = ctx.size as u64;
+        if fault_backing(&ctx.extra)? == Backing::Anonymous {
+            return Ok(Estimate { memory_mb: (ctx.intensity as u64).saturating_mul(mb), cpu_cores: ctx.intensity as f64, ..Default::default() });
+        }
+        let mut dirs = disk_paths(&ctx.extra)?;
+        if dirs.is_empty() {
+            dirs.push(PathBuf::from("."));
+        }
+        let mut disk_mb = BTreeMap::new();
+        for thread_id in 0..ctx.intensity {
+            let total: &mut u64 = disk_mb.entry(dirs[thread_id % dirs.len()].clone()).or_default();
+            *total = total.saturating_add(mb);
+        }
+        Ok(Estimate { disk_mb, cpu_cores: ctx.intensity as f64, ..Default::default() })
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        let options = FaultOptions {
+            backing: fault_backing(&ctx.extra).unwrap_or_default(),
+            paths: disk_paths(&ctx.extra).unwrap_or_default(),
+        };
+        Box::pin(fault_stress::stress_faults(ctx.intensity, ctx.size, ctx.duration, stop_flag, ctx.task_id, options))
+    }
+}
+
+/// Threads issuing cheap system calls in a tight loop
+pub struct SyscallTest;
+
+impl StressTest for SyscallTest {
+    fn name(&self) -> &'static str {
+        "syscall"
+    }
+
+    fn label(&self) -> &'static str {
+        "Syscall"
+    }
+
+    fn validate(&self, params: &TestParams) -> Result<(), StressError> {
+        syscall_mix(&params.extra).map(|_| ())
+    }
+
+    fn describe(&self, ctx: &TestContext) -> String {
+        let mix = syscall_mix(&ctx.extra).unwrap_or_default().mix;
+        let names: Vec<&str> = mix.iter().map(|(syscall, _)| syscall.name()).collect();
+        format!(
+            "Starting syscall stress test ({}) with {} threads for {} seconds...",
+            names.join(", "),
+            ctx.intensity,
+            ctx.duration
+        )
+    }
+
+    fn run(&self, ctx: TestContext, stop_flag: Arc<AtomicBool>) -> StressFuture {
+        let options = syscall_mix(&ctx.extra).unwrap_or_default();
+        Box::pin(syscall_stress::stress_syscalls(ctx.intensity, ctx.duration, stop_flag, ctx.task_id, options))
+    }
+}
@@ -0,0 +1,117 @@
+//! Baseline micro-benchmarks
+//!
+//! A few seconds of single-core CPU work, a memory copy loop and a sequential
+//! disk write give a rough performance baseline per node. Scores are normalized
+//! so the reference machine (a 2020-era server core with NVMe storage) scores 100.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::hint::black_box;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Time spent on each benchmark
+const BENCH_TIME: Duration = Duration::from_secs(2);
+
+/// Reference results that score 100
+const REFERENCE_CPU_MOPS: f64 = 400.0;
+const REFERENCE_MEMORY_MB_S: f64 = 10_000.0;
+const REFERENCE_DISK_MB_S: f64 = 1_000.0;
+
+/// Size of the memory copy buffers
+const MEMORY_BUFFER_MB: usize = 64;
+
+/// Upper bound for the disk test file
+const DISK_FILE_MB: usize = 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchResult {
+    pub cpu_mops: f64,              // Millions of integer hash rounds per second on one core
+    pub memory_mb_s: f64,           // Memory copy bandwidth (bytes read + written)
+    pub disk_write_mb_s: Option<f64>, // Sequential write including fsync (None when the directory is not writable)
+    pub cpu_score: f64,
+    pub memory_score: f64,
+    pub disk_score: Option<f64>,
+    pub disk_path: String,          // Directory the disk test wrote to
+    pub error: Option<String>,      // Why the disk test could not run
+}
+
+/// Run all benchmarks; the disk test writes a temporary file in `dir`
+pub fn run(dir: &Path) -> BenchResult {
+    let cpu_mops = cpu_bench();
+    let memory_mb_s = memory_bench();
+    let (disk_write_mb_s, error) = match disk_bench(dir) {
+        Ok(rate) => (Some(rate), None),
+        Err(e) => (None, Some(format!("Disk benchmark failed in {}: {}", dir.display(), e))),
+    };
+
+    let score = |value: f64, reference: f64| (value / reference * 1000.0).round() / 10.0;
+    BenchResult {
+        cpu_mops,
+        memory_mb_s,
+        disk_write_mb_s,
+        cpu_score: score(cpu_mops, REFERENCE_CPU_MOPS),
+        memory_score: score(memory_mb_s, REFERENCE_MEMORY_MB_S),
+        disk_score: disk_write_mb_s.map(|rate| score(rate, REFERENCE_DISK_MB_S)),
+        disk_path: dir.display().to_string(),
+        error,
+    }
+}
+
+/// Single-core integer throughput (xorshift/multiply rounds)
+fn cpu_bench() -> f64 {
+    const BATCH: u64 = 100_000;
+    let start = Instant::now();
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut rounds = 0u64;
+    while start.elapsed() < BENCH_TIME {
+        for _ in 0..BATCH {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state = state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        }
+        state = black_box(state);
+        rounds += BATCH;
+    }
+    rounds as f64 / start.elapsed().as_secs_f64() / 1e6
+}
+
+/// Memory bandwidth of repeated buffer copies
+fn memory_bench() -> f64 {
+    let size = MEMORY_BUFFER_MB * 1024 * 1024;
+    let source = vec![1u8; size];
+    let mut target = vec![0u8; size];
+
+    let start = Instant::now();
+    let mut copies = 0u64;
+    while start.elapsed() < BENCH_TIME {
+        target.copy_from_slice(black_box(&source));
+        black_box(&mut target);
+        copies += 1;
+    }
+    // Each copy reads and writes the buffer
+    (copies * 2 * MEMORY_BUFFER_MB as u64) as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Sequential write throughput, synced to disk so the page cache does not inflate it
+fn disk_bench(dir: &Path) -> std::io::Result<f64> {
+    let path = dir.join(format!("mogwai_bench_{}.tmp", std::process::id()));
+    let chunk = vec![0xA5u8; 1024 * 1024];
+
+    let result = (|| {
+        let mut file = File::create(&path)?;
+        let start = Instant::now();
+        let mut written = 0usize;
+        while written < DISK_FILE_MB && start.elapsed() < BENCH_TIME {
+            file.write_all(&chunk)?;
+            written += 1;
+        }
+        file.sync_all()?;
+        Ok(written as f64 / start.elapsed().as_secs_f64())
+    })();
+
+    let _ = fs::remove_file(&path);
+    result
+}
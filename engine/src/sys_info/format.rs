@@ -0,0 +1,287 @@
+//! Output formats for the hardware report
+//!
+//! JSON is the canonical form (piped into the AI planner); human is the console
+//! report; YAML and CSV are for people and spreadsheets; Prometheus writes the
+//! node_exporter textfile-collector format so hardware facts can be scraped.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{console_report, SystemInfo};
+
+/// Output format of the hardware report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Human,
+    Yaml,
+    Csv,
+    Prometheus,
+}
+
+impl ReportFormat {
+    /// File extension used when saving the report
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Human => "txt",
+            ReportFormat::Yaml => "yaml",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Prometheus => "prom",
+        }
+    }
+
+    /// Content type used when serving the report over HTTP
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "application/json",
+            ReportFormat::Human => "text/plain; charset=utf-8",
+            ReportFormat::Yaml => "application/yaml",
+            ReportFormat::Csv => "text/csv; charset=utf-8",
+            ReportFormat::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+        }
+    }
+}
+
+/// Render the report in the given format
+pub fn render(info: &SystemInfo, format: ReportFormat) -> Result<String, String> {
+    match format {
+        ReportFormat::Json => serde_json::to_string(info).map_err(|e| e.to_string()),
+        ReportFormat::Human => Ok(console_report(info)),
+        ReportFormat::Yaml => serde_yaml::to_string(info).map_err(|e| e.to_string()),
+        ReportFormat::Csv => {
+            let value = serde_json::to_value(info).map_err(|e| e.to_string())?;
+            Ok(to_csv(&value))
+        }
+        ReportFormat::Prometheus => Ok(to_prometheus(info)),
+    }
+}
+
+/// "key,value" rows with dotted keys (e.g. disks.0.device); lists of plain values are joined with ';'
+fn to_csv(value: &Value) -> String {
+    let mut rows = vec!["key,value".to_string()];
+    flatten("", value, &mut rows);
+    rows.join("\n") + "\n"
+}
+
+/// Append one CSV row per leaf value below `prefix`
+fn flatten(prefix: &str, value: &Value, rows: &mut Vec<String>) {
+    let key = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (name, child) in map {
+                flatten(&key(name), child, rows);
+            }
+        }
+        Value::Array(items) if items.iter().all(|item| !item.is_object() && !item.is_array()) => {
+            let joined = items.iter().map(plain).collect::<Vec<_>>().join(";");
+            rows.push(format!("{},{}", csv_field(prefix), csv_field(&joined)));
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                flatten(&key(&index.to_string()), child, rows);
+            }
+        }
+        leaf => rows.push(format!("{},{}", csv_field(prefix), csv_field(&plain(leaf)))),
+    }
+}
+
+/// Scalar value without JSON quoting (null becomes empty)
+fn plain(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Quote a CSV field when needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Prometheus text exposition format (one gauge family per fact)
+fn to_prometheus(info: &SystemInfo) -> String {
+    let mut out = String::new();
+
+    let system = &info.system;
+    let cpu_model = info.cpu.as_ref().map_or("unknown", |cpu| cpu.model.as_str());
+    family(&mut out, "mogwai_node_info", "Static facts about the node (always 1)");
+    sample(
+        &mut out,
+        "mogwai_node_info",
+        &[
+            ("hostname", &system.hostname),
+            ("os", &system.name),
+            ("os_version", &system.version),
+            ("machine", &system.machine),
+            ("cpu_model", cpu_model),
+        ],
+        1.0,
+    );
+
+    if let Some(cpu) = &info.cpu {
+        family(&mut out, "mogwai_cpu_cores", "Number of CPU cores");
+        sample(&mut out, "mogwai_cpu_cores", &[("kind", "logical")], cpu.total_cores as f64);
+        if let Some(physical) = cpu.physical_cores {
+            sample(&mut out, "mogwai_cpu_cores", &[("kind", "physical")], physical as f64);
+        }
+    }
+
+    if let Some(cpu) = info.cpu.as_ref().filter(|cpu| !cpu.cores.is_empty()) {
+        family(&mut out, "mogwai_cpu_core_frequency_hertz", "Current core frequency");
+        for core in &cpu.cores {
+            let id = core.id.to_string();
+            sample(&mut out, "mogwai_cpu_core_frequency_hertz", &[("core", &id)], core.frequency_mhz as f64 * 1e6);
+        }
+        family(&mut out, "mogwai_cpu_core_max_frequency_hertz", "Highest frequency the core can reach");
+        for core in &cpu.cores {
+            if let Some(max) = core.max_frequency_mhz {
+                let id = core.id.to_string();
+                sample(&mut out, "mogwai_cpu_core_max_frequency_hertz", &[("core", &id)], max as f64 * 1e6);
+            }
+        }
+        family(&mut out, "mogwai_cpu_core_usage_percent", "Core utilization averaged over the sampling window");
+        for core in &cpu.cores {
+            let id = core.id.to_string();
+            sample(&mut out, "mogwai_cpu_core_usage_percent", &[("core", &id)], core.usage_percent as f64);
+        }
+        family(&mut out, "mogwai_cpu_core_governor_info", "Frequency scaling governor of the core (always 1)");
+        for core in &cpu.cores {
+            if let Some(governor) = &core.governor {
+                let id = core.id.to_string();
+                sample(&mut out, "mogwai_cpu_core_governor_info", &[("core", &id), ("governor", governor)], 1.0);
+            }
+        }
+    }
+
+    if let Some(memory) = &info.memory {
+        family(&mut out, "mogwai_memory_total_bytes", "Total memory");
+        sample(&mut out, "mogwai_memory_total_bytes", &[], memory.total_bytes as f64);
+        family(&mut out, "mogwai_memory_available_bytes", "Available memory when the report was taken");
+        sample(&mut out, "mogwai_memory_available_bytes", &[], memory.available_bytes as f64);
+    }
+
+    if let Some(limits) = &info.limits {
+        family(&mut out, "mogwai_rlimit", "Resource limit of the reporting process (+Inf = unlimited)");
+        for (resource, limit) in [("nofile", limits.nofile), ("nproc", limits.nproc), ("memlock", limits.memlock)] {
+            if let Some(limit) = limit {
+                for (kind, value) in [("soft", limit.soft), ("hard", limit.hard)] {
+                    let value = value.map_or(f64::INFINITY, |v| v as f64);
+                    sample(&mut out, "mogwai_rlimit", &[("resource", resource), ("kind", kind)], value);
+                }
+            }
+        }
+        family(&mut out, "mogwai_kernel_param", "Numeric kernel parameter (sysctl)");
+        for (name, value) in &limits.kernel {
+            if let Ok(value) = value.parse::<f64>() {
+                sample(&mut out, "mogwai_kernel_param", &[("name", name)], value);
+            }
+        }
+    }
+
+    if let Some(bench) = &info.bench {
+        family(&mut out, "mogwai_bench_score", "Baseline micro-benchmark score (reference machine = 100)");
+        sample(&mut out, "mogwai_bench_score", &[("kind", "cpu")], bench.cpu_score);
+        sample(&mut out, "mogwai_bench_score", &[("kind", "memory")], bench.memory_score);
+        if let Some(score) = bench.disk_score {
+            sample(&mut out, "mogwai_bench_score", &[("kind", "disk")], score);
+        }
+    }
+
+    if let Some(disks) = info.disks.as_ref().filter(|disks| !disks.is_empty()) {
+        let labels = |disk: &super::DiskInfo| {
+            [
+                ("device", disk.device.clone()),
+                ("mountpoint", disk.mountpoint.clone().unwrap_or_default()),
+                ("fstype", disk.filesystem.clone().unwrap_or_default()),
+            ]
+        };
+        family(&mut out, "mogwai_disk_total_bytes", "Size of the filesystem");
+        for disk in disks {
+            let labels = labels(disk);
+            sample(&mut out, "mogwai_disk_total_bytes", &borrow(&labels), disk.total_bytes as f64);
+        }
+        family(&mut out, "mogwai_disk_free_bytes", "Free space when the report was taken");
+        for disk in disks {
+            let labels = labels(disk);
+            sample(&mut out, "mogwai_disk_free_bytes", &borrow(&labels), disk.free_bytes as f64);
+        }
+    }
+
+    if let Some(networks) = info.network.as_ref().filter(|networks| !networks.is_empty()) {
+        family(&mut out, "mogwai_network_info", "Network interface addresses (always 1)");
+        for nic in networks {
+            let addresses = nic.ip_addresses.join(",");
+            sample(
+                &mut out,
+                "mogwai_network_info",
+                &[
+                    ("interface", &nic.name),
+                    ("mac_address", nic.mac_address.as_deref().unwrap_or("")),
+                    ("ip_addresses", &addresses),
+                ],
+                1.0,
+            );
+        }
+        family(&mut out, "mogwai_network_up", "Whether the interface is up with a link (1) or not (0)");
+        for nic in networks {
+            if let Some(up) = nic.is_up {
+                sample(&mut out, "mogwai_network_up", &[("interface", &nic.name)], if up { 1.0 } else { 0.0 });
+            }
+        }
+        family(&mut out, "mogwai_network_speed_bits_per_second", "Negotiated link speed");
+        for nic in networks {
+            if let Some(speed) = nic.speed_mbps {
+                let bits = speed as f64 * 1_000_000.0;
+                sample(&mut out, "mogwai_network_speed_bits_per_second", &[("interface", &nic.name)], bits);
+            }
+        }
+        family(&mut out, "mogwai_network_mtu_bytes", "Interface MTU");
+        for nic in networks {
+            sample(&mut out, "mogwai_network_mtu_bytes", &[("interface", &nic.name)], nic.mtu as f64);
+        }
+    }
+
+    out
+}
+
+/// HELP and TYPE lines of a gauge family
+fn family(out: &mut String, name: &str, help: &str) {
+    out.push_str(&format!("# HELP {} {}\n# TYPE {} gauge\n", name, help, name));
+}
+
+/// One sample line with escaped label values
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    let labels = labels
+        .iter()
+        .map(|(key, value)| {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{}=\"{}\"", key, escaped)
+        })
+        .collect::<Vec<_>>();
+    let value = if value.is_infinite() { "+Inf".to_string() } else { value.to_string() };
+    if labels.is_empty() {
+        out.push_str(&format!("{} {}\n", name, value));
+    } else {
+        out.push_str(&format!("{}{{{}}} {}\n", name, labels.join(","), value));
+    }
+}
+
+/// Borrow owned label values for `sample`
+fn borrow<'a>(labels: &'a [(&'static str, String)]) -> Vec<(&'static str, &'a str)> {
+    labels.iter().map(|(key, value)| (*key, value.as_str())).collect()
+}
@@ -0,0 +1,684 @@
+//! System Hardware Information Reporter
+//!
+//! Gathers basic hardware information from Windows, macOS, and Linux systems
+//! into a standardized report suitable for AI input or system analysis.
+//! Used by the sys_info binary and the engine's GET /sysinfo endpoint.
+
+pub mod bench;
+mod format;
+
+pub use bench::BenchResult;
+pub use format::{render, ReportFormat};
+
+use chrono::prelude::*;
+use hostname::get as get_hostname;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::time::Duration;
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CpuInfo {
+    pub model: String,
+    pub physical_cores: Option<usize>,
+    pub total_cores: usize,
+    pub max_frequency: Option<String>,
+    #[serde(default)]
+    pub cores: Vec<CoreInfo>, // Per logical core, sampled while the report is taken
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CoreInfo {
+    pub id: usize,
+    pub frequency_mhz: u64,             // Current frequency at the end of the sampling window
+    pub max_frequency_mhz: Option<u64>, // Highest frequency the core can reach (Linux cpufreq)
+    pub governor: Option<String>,       // Frequency scaling governor (Linux cpufreq)
+    pub usage_samples: Vec<f32>,        // Utilization (%) of each sampling interval
+    pub usage_percent: f32,             // Average of the samples
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MemoryInfo {
+    pub total: String,
+    pub available: String,
+    pub used_percent: f32,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiskInfo {
+    pub device: String,
+    pub mountpoint: Option<String>,
+    pub filesystem: Option<String>,
+    pub total: String,
+    pub free: String,
+    pub used_percent: f32,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkInfo {
+    pub name: String,
+    pub mac_address: Option<String>,
+    pub ip_addresses: Vec<String>, // Addresses with prefix length, e.g. "192.168.1.5/24"
+    pub is_up: Option<bool>,       // Administratively up with a link (None when unknown)
+    pub speed_mbps: Option<u64>,   // Negotiated link speed (None for virtual interfaces or when unknown)
+    pub mtu: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SystemBasicInfo {
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    pub machine: String,
+    pub hostname: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Dependencies {
+    pub sysinfo: bool,
+    pub wmi: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SystemInfo {
+    pub system: SystemBasicInfo,
+    pub timestamp: String,
+    pub cpu: Option<CpuInfo>,
+    pub memory: Option<MemoryInfo>,
+    pub disks: Option<Vec<DiskInfo>>,
+    pub network: Option<Vec<NetworkInfo>>,
+    #[serde(default)]
+    pub limits: Option<LimitsInfo>,
+    #[serde(default)]
+    pub bench: Option<BenchResult>, // Baseline micro-benchmark scores (sys_info bench, /sysinfo?bench=true)
+    pub dependencies: Dependencies,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LimitsInfo {
+    pub nofile: Option<ResourceLimit>,  // Open files
+    pub nproc: Option<ResourceLimit>,   // Processes/threads of the user (limits fork tests)
+    pub memlock: Option<ResourceLimit>, // Locked memory in bytes
+    pub kernel: BTreeMap<String, String>, // sysctl parameters (overcommit, swappiness, scheduler, ...)
+}
+
+/// Soft and hard resource limit (None = unlimited)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ResourceLimit {
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+}
+
+/// Kernel parameters that affect fork and memory stress outcomes (Linux, read from /proc/sys)
+#[cfg(unix)]
+const KERNEL_PARAMS: [&str; 11] = [
+    "vm.swappiness",
+    "vm.overcommit_memory",
+    "vm.overcommit_ratio",
+    "vm.max_map_count",
+    "kernel.pid_max",
+    "kernel.threads-max",
+    "kernel.sched_autogroup_enabled",
+    "kernel.sched_child_runs_first",
+    "kernel.sched_rt_period_us",
+    "kernel.sched_rt_runtime_us",
+    "kernel.numa_balancing",
+];
+
+/// Format bytes to human-readable format
+fn get_size_format(bytes: u64, factor: u64, suffix: &str) -> String {
+    let units = ["", "K", "M", "G", "T", "P", "Y"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= factor as f64 && unit_index < units.len() - 1 {
+        size /= factor as f64;
+        unit_index += 1;
+    }
+
+    format!("{:.2} {}{}", size, units[unit_index], suffix)
+}
+
+/// Resource limits of this process and kernel parameters
+#[cfg(unix)]
+fn get_limits_info() -> LimitsInfo {
+    let limit = |resource| {
+        let mut rlim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: getrlimit only writes into the provided struct
+        if unsafe { libc::getrlimit(resource, &mut rlim) } != 0 {
+            return None;
+        }
+        let value = |v: libc::rlim_t| (v != libc::RLIM_INFINITY).then_some(v);
+        Some(ResourceLimit { soft: value(rlim.rlim_cur), hard: value(rlim.rlim_max) })
+    };
+
+    let kernel = KERNEL_PARAMS
+        .iter()
+        .filter_map(|name| {
+            let path = format!("/proc/sys/{}", name.replace('.', "/"));
+            let value = std::fs::read_to_string(path).ok()?;
+            Some((name.to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    LimitsInfo {
+        nofile: limit(libc::RLIMIT_NOFILE),
+        nproc: limit(libc::RLIMIT_NPROC),
+        memlock: limit(libc::RLIMIT_MEMLOCK),
+        kernel,
+    }
+}
+
+/// Number of utilization samples taken per core
+const CPU_SAMPLES: usize = 5;
+
+/// Sample per-core utilization and read the current frequency and cpufreq settings
+fn get_core_info() -> Vec<CoreInfo> {
+    let mut sys = System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()));
+    let interval = sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.max(Duration::from_millis(200));
+
+    // Usage is measured between two refreshes
+    let mut samples = vec![Vec::with_capacity(CPU_SAMPLES); sys.cpus().len()];
+    for _ in 0..CPU_SAMPLES {
+        std::thread::sleep(interval);
+        sys.refresh_cpu_usage();
+        for (core, cpu) in samples.iter_mut().zip(sys.cpus()) {
+            core.push(cpu.cpu_usage());
+        }
+    }
+    sys.refresh_cpu_frequency();
+
+    sys.cpus()
+        .iter()
+        .zip(samples)
+        .enumerate()
+        .map(|(id, (cpu, usage_samples))| {
+            let cpufreq = |file: &str| {
+                std::fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/cpufreq/{}", id, file))
+                    .ok()
+                    .map(|value| value.trim().to_string())
+            };
+            CoreInfo {
+                id,
+                frequency_mhz: cpu.frequency(),
+                // cpufreq reports kHz
+                max_frequency_mhz: cpufreq("cpuinfo_max_freq")
+                    .and_then(|khz| khz.parse::<u64>().ok())
+                    .map(|khz| khz / 1000),
+                governor: cpufreq("scaling_governor"),
+                usage_percent: usage_samples.iter().sum::<f32>() / usage_samples.len().max(1) as f32,
+                usage_samples,
+            }
+        })
+        .collect()
+}
+
+/// Gather network interfaces with their addresses, link state and speed
+fn get_network_info() -> Vec<NetworkInfo> {
+    let link_states = get_link_states();
+    let networks = sysinfo::Networks::new_with_refreshed_list();
+
+    let mut interfaces: Vec<NetworkInfo> = networks
+        .iter()
+        .map(|(interface_name, network)| {
+            let mac = network.mac_address();
+            let (is_up, speed_mbps) = link_states.get(interface_name).copied().unwrap_or((None, None));
+            let mut ip_addresses: Vec<String> = network
+                .ip_networks()
+                .iter()
+                .map(|ip| format!("{}/{}", ip.addr, ip.prefix))
+                .collect();
+            ip_addresses.sort();
+            NetworkInfo {
+                name: interface_name.to_string(),
+                mac_address: (!mac.is_unspecified()).then(|| mac.to_string()),
+                ip_addresses,
+                is_up,
+                speed_mbps,
+                mtu: network.mtu(),
+            }
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    interfaces
+}
+
+/// Link state and speed (Mb/s) per interface from getifaddrs flags and /sys/class/net
+#[cfg(unix)]
+fn get_link_states() -> HashMap<String, (Option<bool>, Option<u64>)> {
+    let mut flags: HashMap<String, u32> = HashMap::new();
+    let mut addrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs allocates the list and freeifaddrs releases it; entries are only read in between
+    unsafe {
+        if libc::getifaddrs(&mut addrs) == 0 {
+            let mut cursor = addrs;
+            while let Some(entry) = cursor.as_ref() {
+                if !entry.ifa_name.is_null() {
+                    let name = std::ffi::CStr::from_ptr(entry.ifa_name).to_string_lossy().into_owned();
+                    *flags.entry(name).or_insert(0) |= entry.ifa_flags;
+                }
+                cursor = entry.ifa_next;
+            }
+            libc::freeifaddrs(addrs);
+        }
+    }
+
+    flags
+        .into_iter()
+        .map(|(name, flags)| {
+            let is_up = flags & libc::IFF_UP as u32 != 0 && flags & libc::IFF_RUNNING as u32 != 0;
+            // Virtual interfaces report -1 or fail to read
+            let speed = std::fs::read_to_string(format!("/sys/class/net/{}/speed", name))
+                .ok()
+                .and_then(|speed| speed.trim().parse::<i64>().ok())
+                .filter(|speed| *speed > 0)
+                .map(|speed| speed as u64);
+            (name, (Some(is_up), speed))
+        })
+        .collect()
+}
+
+/// Link state and speed (Mb/s) per interface from Get-NetAdapter
+#[cfg(windows)]
+fn get_link_states() -> HashMap<String, (Option<bool>, Option<u64>)> {
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-NetAdapter | ForEach-Object { $_.Name + '|' + $_.Status + '|' + $_.ReceiveLinkSpeed }",
+        ])
+        .output();
+    let Ok(output) = output else {
+        return HashMap::new();
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().split('|');
+            let name = fields.next()?.to_string();
+            let is_up = fields.next()? == "Up";
+            // ReceiveLinkSpeed is in bits per second
+            let speed = fields.next()?.parse::<u64>().ok().filter(|bps| *bps > 0).map(|bps| bps / 1_000_000);
+            Some((name, (Some(is_up), speed)))
+        })
+        .collect()
+}
+
+/// Gather information for Windows systems
+#[cfg(target_os = "windows")]
+fn get_windows_info(sys: &System) -> HashMap<String, serde_json::Value> {
+    let mut info = HashMap::new();
+
+    // CPU Information
+    let cpu_info = CpuInfo {
+        model: sys.cpus().first().map(|cpu| cpu.brand().to_string()).unwrap_or_else(|| "unknown".to_string()),
+        physical_cores: System::physical_core_count(),
+        total_cores: sys.cpus().len(),
+        max_frequency: sys.cpus().first().map(|cpu| format!("{} MHz", cpu.frequency())),
+        cores: Vec::new(),
+    };
+
+    info.insert("cpu".to_string(), serde_json::to_value(cpu_info).unwrap());
+
+    // Memory Information
+    let memory_info = MemoryInfo {
+        total: get_size_format(sys.total_memory(), 1024, "B"),
+        available: get_size_format(sys.available_memory(), 1024, "B"),
+        used_percent: ((sys.total_memory() - sys.available_memory()) as f32 / sys.total_memory() as f32) * 100.0,
+        total_bytes: sys.total_memory(),
+        available_bytes: sys.available_memory(),
+    };
+    info.insert("memory".to_string(), serde_json::to_value(memory_info).unwrap());
+
+    // Disk Information
+    let mut disks = Vec::new();
+    for disk in (sysinfo::Disks::new_with_refreshed_list()).list() {
+        disks.push(DiskInfo {
+            device: disk.name().to_str().unwrap_or("Unknown").to_string(),
+            mountpoint: Some(disk.mount_point().to_str().unwrap_or("Unknown").to_string()),
+            filesystem: None,
+            total: get_size_format(disk.total_space(), 1024, "B"),
+            free: get_size_format(disk.available_space(), 1024, "B"),
+            used_percent: ((disk.total_space() - disk.available_space()) as f32 / disk.total_space() as f32) * 100.0,
+            total_bytes: disk.total_space(),
+            free_bytes: disk.available_space(),
+        });
+    }
+    info.insert("disks".to_string(), serde_json::to_value(disks).unwrap());
+
+    // Network Information
+    info.insert("network".to_string(), serde_json::to_value(get_network_info()).unwrap());
+
+    info
+}
+
+/// Gather information for Linux systems
+#[cfg(target_os = "linux")]
+fn get_linux_info(sys: &System) -> HashMap<String, serde_json::Value> {
+    let mut info = HashMap::new();
+
+    // CPU Information
+    let mut cpu_info = CpuInfo {
+        model: sys.cpus().first().map(|cpu| cpu.brand().to_string()).unwrap_or_else(|| "unknown".to_string()),
+        physical_cores: System::physical_core_count(),
+        total_cores: sys.cpus().len(),
+        max_frequency: None,
+        cores: Vec::new(),
+    };
+
+    // Try to get more detailed CPU info from /proc/cpuinfo
+    if let Ok(output) = std::fs::read_to_string("/proc/cpuinfo") {
+        for line in output.lines() {
+            if line.starts_with("model name") {
+                if let Some(model) = line.split(':').nth(1) {
+                    cpu_info.model = model.trim().to_string();
+                    break;
+                }
+            }
+        }
+    }
+
+    info.insert("cpu".to_string(), serde_json::to_value(cpu_info).unwrap());
+
+    // Memory Information
+    let memory_info = MemoryInfo {
+        total: get_size_format(sys.total_memory(), 1024, "B"),
+        available: get_size_format(sys.available_memory(), 1024, "B"),
+        used_percent: ((sys.total_memory() - sys.available_memory()) as f32 / sys.total_memory() as f32) * 100.0,
+        total_bytes: sys.total_memory(),
+        available_bytes: sys.available_memory(),
+    };
+    info.insert("memory".to_string(), serde_json::to_value(memory_info).unwrap());
+
+    // Disk Information
+    let mut disks = Vec::new();
+    for disk in (sysinfo::Disks::new_with_refreshed_list()).list() {
+        disks.push(DiskInfo {
+            device: disk.name().to_str().unwrap_or("Unknown").to_string(),
+            mountpoint: Some(disk.mount_point().to_str().unwrap_or("Unknown").to_string()),
+            filesystem: Some(disk.file_system().to_string_lossy().into_owned()),
+            total: get_size_format(disk.total_space(), 1024, "B"),
+            free: get_size_format(disk.available_space(), 1024, "B"),
+            used_percent: ((disk.total_space() - disk.available_space()) as f32 / disk.total_space() as f32) * 100.0,
+            total_bytes: disk.total_space(),
+            free_bytes: disk.available_space(),
+        });
+    }
+    info.insert("disks".to_string(), serde_json::to_value(disks).unwrap());
+
+    // Network Information
+    info.insert("network".to_string(), serde_json::to_value(get_network_info()).unwrap());
+
+    info
+}
+
+/// Gather information for macOS systems
+#[cfg(target_os = "macos")]
+fn get_macos_info(sys: &System) -> HashMap<String, serde_json::Value> {
+    let mut info = HashMap::new();
+
+    // CPU Information
+    let cpu_info = CpuInfo {
+        model: sys.cpus().first().map(|cpu| cpu.brand().to_string()).unwrap_or_else(|| "unknown".to_string()),
+        physical_cores: System::physical_core_count(),
+        total_cores: sys.cpus().len(),
+        max_frequency: None,
+        cores: Vec::new(),
+    };
+
+    info.insert("cpu".to_string(), serde_json::to_value(cpu_info).unwrap());
+
+    // Memory Information
+    let memory_info = MemoryInfo {
+        total: get_size_format(sys.total_memory(), 1024, "B"),
+        available: get_size_format(sys.available_memory(), 1024, "B"),
+        used_percent: ((sys.total_memory() - sys.available_memory()) as f32 / sys.total_memory() as f32) * 100.0,
+        total_bytes: sys.total_memory(),
+        available_bytes: sys.available_memory(),
+    };
+    info.insert("memory".to_string(), serde_json::to_value(memory_info).unwrap());
+
+    // Disk Information
+    let mut disks = Vec::new();
+    for disk in (sysinfo::Disks::new_with_refreshed_list()).list() {
+        disks.push(DiskInfo {
+            device: disk.name().to_str().unwrap_or("Unknown").to_string(),
+            mountpoint: Some(disk.mount_point().to_str().unwrap_or("Unknown").to_string()),
+            filesystem: Some(disk.file_system().to_string_lossy().into_owned()),
+            total: get_size_format(disk.total_space(), 1024, "B"),
+            free: get_size_format(disk.available_space(), 1024, "B"),
+            used_percent: ((disk.total_space() - disk.available_space()) as f32 / disk.total_space() as f32) * 100.0,
+            total_bytes: disk.total_space(),
+            free_bytes: disk.available_space(),
+        });
+    }
+    info.insert("disks".to_string(), serde_json::to_value(disks).unwrap());
+
+    // Network Information
+    info.insert("network".to_string(), serde_json::to_value(get_network_info()).unwrap());
+
+    info
+}
+
+/// Gather all system information
+pub fn gather_system_info() -> SystemInfo {
+    // Use RefreshKind::everything() to update all available data
+    let refresh_kind = RefreshKind::everything();
+    let sys = System::new_with_specifics(refresh_kind);
+
+    // Get hostname
+    let hostname = match get_hostname() {
+        Ok(name) => name.to_string_lossy().into_owned(),
+        Err(_) => "unknown".to_string(),
+    };
+
+    // Get OS information
+    let os = os_info::get();
+
+    // Common information across all platforms
+    let mut info = SystemInfo {
+        system: SystemBasicInfo {
+            name: os.os_type().to_string(),
+            version: os.version().to_string(),
+            platform: format!("{} {}", os.os_type(), os.version()),
+            machine: env::consts::ARCH.to_string(),
+            hostname,
+        },
+        timestamp: Utc::now().to_rfc3339(),
+        cpu: None,
+        memory: None,
+        disks: None,
+        network: None,
+        limits: None,
+        bench: None,
+        dependencies: Dependencies {
+            sysinfo: true,
+            wmi: cfg!(target_os = "windows"),
+        },
+        error: None,
+    };
+
+    // Get OS-specific information
+    let hw_info = if cfg!(target_os = "windows") {
+        #[cfg(target_os = "windows")]
+        {
+            get_windows_info(&sys)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            HashMap::new()
+        }
+    } else if cfg!(target_os = "linux") {
+        #[cfg(target_os = "linux")]
+        {
+            get_linux_info(&sys)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            HashMap::new()
+        }
+    } else if cfg!(target_os = "macos") {
+        #[cfg(target_os = "macos")]
+        {
+            get_macos_info(&sys)
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            HashMap::new()
+        }
+    } else {
+        info.error = Some(format!("Unsupported operating system: {}", info.system.name));
+        HashMap::new()
+    };
+
+    // Combine information
+    if let Some(cpu) = hw_info.get("cpu") {
+        info.cpu = serde_json::from_value(cpu.clone()).ok();
+    }
+    if let Some(cpu) = info.cpu.as_mut() {
+        cpu.cores = get_core_info();
+        if cpu.max_frequency.is_none() {
+            cpu.max_frequency = cpu
+                .cores
+                .iter()
+                .filter_map(|core| core.max_frequency_mhz)
+                .max()
+                .map(|mhz| format!("{} MHz", mhz));
+        }
+    }
+    if let Some(memory) = hw_info.get("memory") {
+        info.memory = serde_json::from_value(memory.clone()).ok();
+    }
+    if let Some(disks) = hw_info.get("disks") {
+        info.disks = serde_json::from_value(disks.clone()).ok();
+    }
+    if let Some(network) = hw_info.get("network") {
+        info.network = serde_json::from_value(network.clone()).ok();
+    }
+    #[cfg(unix)]
+    {
+        info.limits = Some(get_limits_info());
+    }
+
+    info
+}
+
+/// Human-readable report, as printed to the console
+pub fn console_report(info: &SystemInfo) -> String {
+    let mut lines = Vec::new();
+    lines.push("\n=== System Hardware Information Report ===".to_string());
+    lines.push(format!("Operating System: {} {}", info.system.name, info.system.version));
+    lines.push(format!("Machine Type: {}", info.system.machine));
+    lines.push(format!("Hostname: {}", info.system.hostname));
+
+    if let Some(cpu) = &info.cpu {
+        lines.push("\n--- CPU Information ---".to_string());
+        lines.push(format!("Model: {}", cpu.model));
+        if let Some(cores) = cpu.physical_cores {
+            lines.push(format!("Physical Cores: {}", cores));
+        }
+        lines.push(format!("Total Cores: {}", cpu.total_cores));
+        if let Some(freq) = &cpu.max_frequency {
+            lines.push(format!("Max Frequency: {}", freq));
+        }
+        for core in &cpu.cores {
+            let mut line = format!("  Core {}: {} MHz", core.id, core.frequency_mhz);
+            if let Some(max) = core.max_frequency_mhz {
+                line.push_str(&format!(" (max {} MHz)", max));
+            }
+            if let Some(governor) = &core.governor {
+                line.push_str(&format!(", governor {}", governor));
+            }
+            line.push_str(&format!(", {:.1}% used", core.usage_percent));
+            lines.push(line);
+        }
+    }
+
+    if let Some(memory) = &info.memory {
+        lines.push("\n--- Memory Information ---".to_string());
+        lines.push(format!("Total: {}", memory.total));
+        lines.push(format!("Available: {}", memory.available));
+        lines.push(format!("Used Percent: {:.1}%", memory.used_percent));
+    }
+
+    if let Some(disks) = &info.disks {
+        lines.push("\n--- Disk Information ---".to_string());
+        for (i, disk) in disks.iter().enumerate() {
+            lines.push(format!("\nDisk {}:", i + 1));
+            lines.push(format!("  Device: {}", disk.device));
+            if let Some(mountpoint) = &disk.mountpoint {
+                lines.push(format!("  Mountpoint: {}", mountpoint));
+            }
+            if let Some(filesystem) = &disk.filesystem {
+                lines.push(format!("  Filesystem: {}", filesystem));
+            }
+            lines.push(format!("  Total: {}", disk.total));
+            lines.push(format!("  Free: {}", disk.free));
+            lines.push(format!("  Used Percent: {:.1}%", disk.used_percent));
+        }
+    }
+
+    if let Some(networks) = &info.network {
+        lines.push("\n--- Network Information ---".to_string());
+        for (i, nic) in networks.iter().enumerate() {
+            lines.push(format!("\nNetwork Interface {}:", i + 1));
+            lines.push(format!("  Name: {}", nic.name));
+            if let Some(mac) = &nic.mac_address {
+                lines.push(format!("  Mac Address: {}", mac));
+            }
+            if !nic.ip_addresses.is_empty() {
+                lines.push(format!("  IP Addresses: {}", nic.ip_addresses.join(", ")));
+            } else {
+                lines.push("  IP Addresses: None".to_string());
+            }
+            match nic.is_up {
+                Some(true) => lines.push("  State: Up".to_string()),
+                Some(false) => lines.push("  State: Down".to_string()),
+                None => {}
+            }
+            if let Some(speed) = nic.speed_mbps {
+                lines.push(format!("  Speed: {} Mb/s", speed));
+            }
+            lines.push(format!("  MTU: {}", nic.mtu));
+        }
+    }
+
+    if let Some(limits) = &info.limits {
+        lines.push("\n--- Limits ---".to_string());
+        let describe = |value: Option<u64>| value.map_or("unlimited".to_string(), |v| v.to_string());
+        let resources = [
+            ("Open Files", limits.nofile),
+            ("Processes", limits.nproc),
+            ("Locked Memory", limits.memlock),
+        ];
+        for (name, limit) in resources {
+            if let Some(limit) = limit {
+                lines.push(format!("{}: {} (hard {})", name, describe(limit.soft), describe(limit.hard)));
+            }
+        }
+        for (name, value) in &limits.kernel {
+            lines.push(format!("{}: {}", name, value));
+        }
+    }
+
+    if let Some(bench) = &info.bench {
+        lines.push("\n--- Baseline Benchmark (reference machine = 100) ---".to_string());
+        lines.push(format!("CPU (single core): {:.1} ({:.0} Mops/s)", bench.cpu_score, bench.cpu_mops));
+        lines.push(format!("Memory Bandwidth: {:.1} ({:.0} MB/s)", bench.memory_score, bench.memory_mb_s));
+        match (bench.disk_score, bench.disk_write_mb_s) {
+            (Some(score), Some(rate)) => {
+                lines.push(format!("Sequential Disk Write: {:.1} ({:.0} MB/s in {})", score, rate, bench.disk_path))
+            }
+            _ => lines.push(format!("Sequential Disk Write: {}", bench.error.as_deref().unwrap_or("not run"))),
+        }
+    }
+
+    lines.join("\n")
+}
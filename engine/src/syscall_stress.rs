@@ -0,0 +1,164 @@
+// Syscall workload: threads issue a mix of cheap system calls in a tight loop, so the
+// result is dominated by the cost of entering and leaving the kernel
+// Used to measure syscall overhead and the impact of mitigations (PTI, retpolines, ...)
+// Calls go through syscall(2) directly: glibc answers clock_gettime from the vDSO and
+// would never enter the kernel
+
+use std::fs::OpenOptions;
+use std::os::unix::io::AsRawFd;
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+use tokio::task;
+use crate::logs;
+use crate::result::{StressError, StressResult, SyscallStats, ThreadCalls};
+
+// Calls between two checks of the clock and the stop flag
+const BATCH: u64 = 1024;
+
+// The system calls the workload can issue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Syscall {
+    Getpid,
+    ClockGettime,
+    Read,  // One byte from /dev/null
+    Write, // One byte to /dev/null
+}
+
+impl Syscall {
+    pub const ALL: [Syscall; 4] = [Syscall::Getpid, Syscall::ClockGettime, Syscall::Read, Syscall::Write];
+
+    // Name used in requests and log lines
+    pub fn name(&self) -> &'static str {
+        match self {
+            Syscall::Getpid => "getpid",
+            Syscall::ClockGettime => "clock_gettime",
+            Syscall::Read => "read",
+            Syscall::Write => "write",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Syscall> {
+        Syscall::ALL.into_iter().find(|syscall| syscall.name() == name)
+    }
+}
+
+// Which calls to issue, and how often relative to each other
+#[derive(Debug, Clone)]
+pub struct SyscallOptions {
+    pub mix: Vec<(Syscall, u32)>, // Weights; a call with weight 2 is issued twice as often as one with 1
+}
+
+impl Default for SyscallOptions {
+    fn default() -> Self {
+        SyscallOptions { mix: Syscall::ALL.into_iter().map(|syscall| (syscall, 1)).collect() }
+    }
+}
+
+// Issue one call; the results are ignored, the cost of the round trip is what counts
+fn issue(syscall: Syscall, fd: libc::c_int, byte: &mut u8) {
+    // SAFETY: plain calls on an open descriptor, a valid timespec and a one-byte buffer
+    unsafe {
+        match syscall {
+            Syscall::Getpid => {
+                libc::syscall(libc::SYS_getpid);
+            }
+            Syscall::ClockGettime => {
+                let mut now: libc::timespec = std::mem::zeroed();
+                libc::syscall(libc::SYS_clock_gettime, libc::CLOCK_MONOTONIC, &mut now as *mut libc::timespec);
+            }
+            Syscall::Read => {
+                libc::syscall(libc::SYS_read, fd, byte as *mut u8, 1usize);
+            }
+            Syscall::Write => {
+                libc::syscall(libc::SYS_write, fd, byte as *const u8, 1usize);
+            }
+        }
+    }
+}
+
+pub async fn stress_syscalls(
+    threads: usize,
+    duration: u64,
+    stop_flag: Arc<AtomicBool>,
+    task_id: String,
+    options: SyscallOptions,
+) -> Result<StressResult, StressError> {
+    // The mix spelled out once, e.g. [getpid, getpid, write], and cycled through
+    let schedule: Vec<Syscall> = options
+        .mix
+        .iter()
+        .flat_map(|&(syscall, weight)| std::iter::repeat_n(syscall, weight as usize))
+        .collect();
+    if schedule.is_empty() {
+        return Err(StressError::InvalidParameter("the syscall mix is empty".to_string()));
+    }
+    let schedule = Arc::new(schedule);
+    let started = Instant::now();
+    let mut handles = Vec::new();
+
+    for thread_id in 0..threads {
+        let schedule = Arc::clone(&schedule);
+        let stop = Arc::clone(&stop_flag);
+        let task_id = task_id.clone();
+
+        let handle = task::spawn_blocking(move || {
+            let dev_null = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open("/dev/null")
+                .map_err(|e| StressError::Io(format!("cannot open /dev/null: {}", e)))?;
+            let fd = dev_null.as_raw_fd();
+            let mut byte = 0u8;
+            let start = Instant::now();
+            let mut calls = 0u64;
+            let mut next = 0;
+
+            while (duration == 0 || start.elapsed() < Duration::from_secs(duration))
+                && !stop.load(Ordering::SeqCst)
+            {
+                for _ in 0..BATCH {
+                    issue(schedule[next], fd, &mut byte);
+                    next = (next + 1) % schedule.len();
+                }
+                calls += BATCH;
+            }
+
+            let secs = start.elapsed().as_secs_f64();
+            logs::emit(&task_id, format!(
+                "[Thread {}] Syscall stress test completed: {:.0} calls/s",
+                thread_id,
+                calls as f64 / secs.max(f64::EPSILON)
+            ));
+            Ok::<_, StressError>((calls, secs))
+        });
+        handles.push(handle);
+    }
+
+    let mut result = StressResult::default();
+    let mut stats = SyscallStats::default();
+    let mut busy_secs = 0.0;
+    for (thread, handle) in handles.into_iter().enumerate() {
+        let (calls, secs) = handle.await??;
+        result.iterations += calls;
+        busy_secs += secs;
+        let calls_per_s = if secs > 0.0 { calls as f64 / secs } else { 0.0 };
+        stats.calls_per_s += calls_per_s;
+        stats.threads.push(ThreadCalls { thread, calls, calls_per_s });
+    }
+    result.elapsed_secs = started.elapsed().as_secs_f64();
+    result.stopped = stop_flag.load(Ordering::SeqCst);
+    if result.iterations > 0 {
+        stats.ns_per_call = busy_secs * 1e9 / result.iterations as f64;
+    }
+
+    let mix: Vec<String> = options.mix.iter().map(|(syscall, weight)| format!("{}x{}", syscall.name(), weight)).collect();
+    logs::emit(&task_id, format!(
+        "{} calls/s over {} threads ({}), {:.0} ns per call",
+        stats.calls_per_s.round(),
+        threads,
+        mix.join(" "),
+        stats.ns_per_call
+    ));
+    result.syscalls = Some(stats);
+    Ok(result)
+}
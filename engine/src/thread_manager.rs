@@ -1,8 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use tokio::task::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use tokio::task::{AbortHandle, JoinHandle};
 use once_cell::sync::Lazy;
+use crate::result::{Health, StressError, StressResult};
+use crate::events::{self, EventKind};
+use crate::{cgroup, fork_stress, logs, request_id, retention};
 
 static TASK_COUNTER: AtomicUsize = AtomicUsize::new(1);
 
@@ -10,7 +15,75 @@ pub static GLOBAL_REGISTRY: Lazy<TaskRegistry> = Lazy::new(|| {
     Arc::new(Mutex::new(HashMap::new()))
 });
 
-pub type TaskRegistry = Arc<Mutex<HashMap<String, (JoinHandle<()>, Arc<AtomicBool>)>>>;
+// A running task: its stop flag, a handle to abort the task's future, and what it is
+pub struct TaskEntry {
+    pub stop_flag: Arc<AtomicBool>,
+    pub abort: AbortHandle,
+    pub info: TaskInfo,
+}
+
+// Lifecycle state of a running task
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    Stopping, // Stop flag set, waiting for the task to end (or the grace period)
+}
+
+// Entry of GET /tasks
+#[derive(Serialize, Clone, Debug)]
+pub struct TaskInfo {
+    pub id: String,                // Engine task id, used for /stop
+    #[serde(rename = "type")]
+    pub test_type: String,         // Registered test type (cpu, mem, disk, ...)
+    pub state: TaskState,
+    pub started_at: f64,           // Unix time in seconds
+    pub duration: u64,             // Seconds the task is due to run, extensions included (0 = until stopped)
+    pub test_id: Option<String>,   // Caller's own id of the test (request "id")
+    pub name: Option<String>,      // Caller's name of the test
+    pub batch_id: Option<String>,  // Caller's batch the test belongs to
+    pub node: Option<String>,      // Node the caller sent the test to (request "node")
+    pub request_id: Option<String>, // X-Request-Id of the call that started the task
+    pub params: serde_json::Value, // Request body as submitted
+}
+
+impl TaskInfo {
+    pub fn new(id: &str, test_type: &str, duration: u64, mut params: serde_json::Value) -> Self {
+        // Fields left out of the request stay out
+        if let Some(fields) = params.as_object_mut() {
+            fields.retain(|_, value| !value.is_null());
+        }
+        let field = |name: &str| params.get(name).and_then(serde_json::Value::as_str).map(str::to_string);
+        TaskInfo {
+            id: id.to_string(),
+            test_type: test_type.to_string(),
+            state: TaskState::Running,
+            started_at: now(),
+            duration,
+            test_id: field("id"),
+            name: field("name"),
+            batch_id: field("batch_id"),
+            node: field("node"),
+            request_id: request_id::current(),
+            params,
+        }
+    }
+}
+
+pub type TaskRegistry = Arc<Mutex<HashMap<String, TaskEntry>>>;
+
+// Grace period between the stop flag and the force-kill unless MOGWAI_STOP_GRACE_SECS is set
+const DEFAULT_STOP_GRACE_SECS: u64 = 10;
+
+// Time a timed task may overrun its duration before the watchdog kills it
+// unless MOGWAI_DEADLINE_MARGIN_SECS is set
+const DEFAULT_DEADLINE_MARGIN_SECS: u64 = 30;
+
+// How often the watchdog checks running tasks against their hard deadline
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+
+// Tasks asked to stop that have not finished yet
+static STOPPING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
 
 
 pub fn generate_task_id(prefix: &str) -> String {
@@ -18,49 +91,468 @@ pub fn generate_task_id(prefix: &str) -> String {
     format!("{}-{}", prefix, id)
 }
 
+// Most tasks running at once (MOGWAI_MAX_TASKS; unset or 0 = no limit)
+pub fn max_tasks() -> Option<usize> {
+    std::env::var("MOGWAI_MAX_TASKS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&max| max > 0)
+}
+
+// Why a task was not started
+#[derive(Debug)]
+pub struct AtCapacity {
+    pub running: usize,
+    pub retry_after: u64, // Seconds until the first running task is due to end (estimate)
+}
+
+// Retry-After when no running task has a known end
+const DEFAULT_RETRY_AFTER_SECS: u64 = 30;
+
+// Start a task through `spawn` and track it until its future completes (or is aborted)
+// Refused while MOGWAI_MAX_TASKS tasks are running; the registry stays locked from
+// the check to the insert, so concurrent submissions cannot overshoot the limit
 pub fn register_task(
     id: String,
-    handle: JoinHandle<()>,
     stop_flag: Arc<AtomicBool>,
-) {
+    info: TaskInfo,
+    spawn: impl FnOnce() -> JoinHandle<()>,
+) -> Result<(), AtCapacity> {
     let registry = &GLOBAL_REGISTRY;
 
-    // dummy placeholder
-    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
-
-    {
-        let mut guard = registry.lock().unwrap();
-        guard.insert(id.clone(), (tokio::spawn(async { let _ = rx.await; }), stop_flag.clone()));
-        println!("- Task registered: {} | Total now: {}", id, guard.len());
-    }
+    let handle = {
+        let mut guard = registry.lock().unwrap_or_else(|e| e.into_inner());
+        if max_tasks().is_some_and(|max| guard.len() >= max) {
+            return Err(AtCapacity {
+                running: guard.len(),
+                retry_after: next_deadline_secs().unwrap_or(DEFAULT_RETRY_AFTER_SECS).max(1),
+            });
+        }
+        events::record(&id, EventKind::Registered, Some(info.test_type.clone()));
+        let handle = spawn();
+        guard.insert(id.clone(), TaskEntry { stop_flag, abort: handle.abort_handle(), info });
+        tracing::info!(task_id = %id, running = guard.len(), "Task registered");
+        handle
+    };
 
     let registry_clone = Arc::clone(registry);
-    let id_clone = id.clone();
 
     tokio::spawn(async move {
         let _ = handle.await;
 
-        let mut guard = registry_clone.lock().unwrap();
-        guard.remove(&id_clone);
-        println!("- Cleaned up finished task: {}", id_clone);
+        let mut guard = registry_clone.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.remove(&id).is_some() {
+            tracing::info!(task_id = %id, "Cleaned up finished task");
+            events::record(&id, EventKind::CleanedUp, None);
+        }
+    });
+    Ok(())
+}
+
+// How long stopped tasks get to end on their own (MOGWAI_STOP_GRACE_SECS)
+pub fn stop_grace() -> Duration {
+    let secs = std::env::var("MOGWAI_STOP_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_STOP_GRACE_SECS);
+    Duration::from_secs(secs)
+}
+
+// Two-phase stop: set the task's stop flag, and if it is still running after the
+// grace period, abort it and kill its processes (see force_kill)
+// Returns false when no such task is running
+pub fn stop_task(id: &str, registry: &TaskRegistry, grace: Duration) -> bool {
+    let Some(flag) = registry.lock().unwrap_or_else(|e| e.into_inner()).get_mut(id).map(|entry| {
+        entry.info.state = TaskState::Stopping;
+        Arc::clone(&entry.stop_flag)
+    }) else {
+        return false;
+    };
+    flag.store(true, Ordering::SeqCst);
+
+    // A repeated stop request keeps the first grace period
+    if STOPPING.lock().unwrap_or_else(|e| e.into_inner()).insert(id.to_string()) {
+        events::record(id, EventKind::Stopping, Some(format!("{} second grace period", grace.as_secs())));
+        let (id, registry) = (id.to_string(), Arc::clone(registry));
+        tokio::spawn(async move {
+            tokio::time::sleep(grace).await;
+            // Finished in time: record_result already took it out of STOPPING
+            if STOPPING.lock().unwrap_or_else(|e| e.into_inner()).remove(&id) {
+                let message = format!("did not stop within the {} second grace period", grace.as_secs());
+                force_kill(&id, &registry, message, StopOutcome::Killed);
+            }
+        });
+    }
+    true
+}
+
+// End a task that ignored its stop flag (after the stop grace period, or past its
+// hard deadline): its future is aborted and its cgroup or forked children are killed;
+// worker threads of in-process tests cannot be killed and are left to notice the flag eventually
+fn force_kill(id: &str, registry: &TaskRegistry, message: String, outcome: StopOutcome) {
+    let entry = registry.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    if let Some(entry) = &entry {
+        entry.stop_flag.store(true, Ordering::SeqCst);
+        entry.abort.abort();
+    }
+    let children = fork_stress::kill_children(id);
+    cgroup::kill(id);
+    clear_deadline(id);
+
+    logs::emit(id, format!("Force-killed: {}{}", message, match children {
+        0 => String::new(),
+        n => format!(" ({} child processes killed)", n),
+    }));
+    tracing::warn!(task_id = %id, "Force-killed task");
+    events::record(id, EventKind::Killed, Some(message.clone()));
+    events::record(id, EventKind::CleanedUp, None);
+    push_result(TaskResult {
+        task_id: id.to_string(),
+        test_type: entry.as_ref().map(|entry| entry.info.test_type.clone()),
+        node: entry.as_ref().and_then(|entry| entry.info.node.clone()),
+        request_id: entry.and_then(|entry| entry.info.request_id),
+        finished_at: now(),
+        result: None,
+        error: Some(StressError::Killed(message)),
+        stop: Some(outcome),
+        health: None,
     });
+}
 
-    // lets the dummy task exit immediately
-    drop(tx);
+// Most seconds an indefinite task may run (MOGWAI_MAX_LIFETIME_SECS; unset or 0 = no limit)
+fn max_lifetime() -> Option<u64> {
+    std::env::var("MOGWAI_MAX_LIFETIME_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&max| max > 0)
 }
 
+// Margin past its duration before a timed task is killed (MOGWAI_DEADLINE_MARGIN_SECS)
+fn deadline_margin() -> u64 {
+    std::env::var("MOGWAI_DEADLINE_MARGIN_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_DEADLINE_MARGIN_SECS)
+}
 
+// Watchdog that kills runaway tasks: timed tasks still running deadline_margin() seconds
+// after their duration (extensions included), and indefinite tasks older than max_lifetime()
+// Runs for the life of the engine; started once from main
+pub fn watch_hard_deadlines(registry: TaskRegistry) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(WATCHDOG_INTERVAL);
+        loop {
+            tick.tick().await;
+            let (margin, lifetime, now) = (deadline_margin(), max_lifetime(), now());
+            let overdue: Vec<(String, String)> = {
+                let guard = registry.lock().unwrap_or_else(|e| e.into_inner());
+                guard
+                    .values()
+                    .filter_map(|entry| {
+                        let info = &entry.info;
+                        let ran = now - info.started_at;
+                        let message = if info.duration > 0 {
+                            (ran > (info.duration + margin) as f64).then(|| format!(
+                                "killed by deadline: still running {} seconds after its {} second duration",
+                                margin, info.duration
+                            ))
+                        } else {
+                            lifetime.filter(|&max| ran > max as f64).map(|max| format!(
+                                "killed by deadline: ran past the {} second maximum lifetime", max
+                            ))
+                        }?;
+                        Some((info.id.clone(), message))
+                    })
+                    .collect()
+            };
+            for (id, message) in overdue {
+                // A pending grace-period kill has nothing left to do
+                STOPPING.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+                force_kill(&id, &registry, message, StopOutcome::Deadline);
+            }
+        }
+    });
+}
 
+// Kill every running task at once, without a grace period, because the node is in
+// trouble; each is recorded with `"stop": "emergency"` and the reason
+// Returns how many tasks were killed
+pub fn emergency_stop(registry: &TaskRegistry, reason: &str) -> usize {
+    let running = list_tasks(registry);
+    for id in &running {
+        STOPPING.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        force_kill(id, registry, format!("emergency stop: {}", reason), StopOutcome::Emergency);
+    }
+    running.len()
+}
 
+// Resolves on the first SIGINT (Ctrl-C) or SIGTERM (e.g. pod deletion)
+pub async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let (Ok(mut interrupt), Ok(mut terminate)) = (signal(SignalKind::interrupt()), signal(SignalKind::terminate())) else {
+        // Without handlers the default action still ends the process
+        return std::future::pending().await;
+    };
+    tokio::select! {
+        _ = interrupt.recv() => {}
+        _ = terminate.recv() => {}
+    }
+}
 
-pub fn stop_task(id: &str, registry: &TaskRegistry) {
-    if let Some((_, flag)) = registry.lock().unwrap().get(id) {
+// Stop every running task before the engine exits: set all stop flags, wait up to
+// `grace` for the tasks to end (and clean up after themselves), then kill the rest
+// Returns how many tasks had to be killed
+pub async fn shutdown(registry: &TaskRegistry, grace: Duration) -> usize {
+    let flags: Vec<Arc<AtomicBool>> = registry
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values_mut()
+        .map(|entry| {
+            entry.info.state = TaskState::Stopping;
+            Arc::clone(&entry.stop_flag)
+        })
+        .collect();
+    for flag in &flags {
         flag.store(true, Ordering::SeqCst);
     }
+
+    let deadline = Instant::now() + grace;
+    while !registry.lock().unwrap_or_else(|e| e.into_inner()).is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let remaining = list_tasks(registry);
+    for id in &remaining {
+        STOPPING.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        let message = format!("engine shut down; did not stop within {} seconds", grace.as_secs());
+        force_kill(id, registry, message, StopOutcome::Killed);
+    }
+    remaining.len()
 }
 
 pub fn list_tasks(registry: &TaskRegistry) -> Vec<String> {
-    let guard = registry.lock().unwrap();
+    let guard = registry.lock().unwrap_or_else(|e| e.into_inner());
     let keys: Vec<String> = guard.keys().cloned().collect();
     keys
 }
+
+// Details of every running task, oldest first
+pub fn task_infos(registry: &TaskRegistry) -> Vec<TaskInfo> {
+    let guard = registry.lock().unwrap_or_else(|e| e.into_inner());
+    let mut infos: Vec<TaskInfo> = guard.values().map(|entry| entry.info.clone()).collect();
+    infos.sort_by(|a, b| a.started_at.total_cmp(&b.started_at));
+    infos
+}
+
+// End of a timed task; the watcher started by watch_deadline stops it then
+struct Deadline {
+    at: Instant,
+    expired: bool, // The watcher stopped the task because its time was up
+}
+
+// Deadlines of running timed tasks (tasks with duration 0 have none)
+static DEADLINES: Lazy<Mutex<HashMap<String, Deadline>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Why a task's duration could not be extended
+#[derive(Debug)]
+pub enum ExtendError {
+    UnknownTask,   // Not running
+    NotExtendable, // Runs until stopped, or its test type keeps its own duration (fork, custom)
+}
+
+// Stop the task through its stop flag once its duration is up
+// The deadline is kept in DEADLINES so /extend can move it while the task runs
+pub fn watch_deadline(id: &str, duration: u64, stop_flag: Arc<AtomicBool>) {
+    let at = Instant::now() + Duration::from_secs(duration);
+    DEADLINES.lock().unwrap_or_else(|e| e.into_inner()).insert(id.to_string(), Deadline { at, expired: false });
+
+    let id = id.to_string();
+    tokio::spawn(async move {
+        loop {
+            let at = {
+                let mut deadlines = DEADLINES.lock().unwrap_or_else(|e| e.into_inner());
+                let Some(deadline) = deadlines.get_mut(&id) else {
+                    return; // Finished or stopped early
+                };
+                if Instant::now() >= deadline.at {
+                    deadline.expired = true;
+                    stop_flag.store(true, Ordering::SeqCst);
+                    return;
+                }
+                deadline.at
+            };
+            // Wake at least once a second to notice a task that ended early
+            let wait = at.saturating_duration_since(Instant::now()).min(Duration::from_secs(1));
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+// Add seconds to a running timed task; returns the seconds it has left
+pub fn extend_deadline(id: &str, seconds: u64, registry: &TaskRegistry) -> Result<u64, ExtendError> {
+    let mut guard = registry.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = guard.get_mut(id) else {
+        return Err(ExtendError::UnknownTask);
+    };
+    let mut deadlines = DEADLINES.lock().unwrap_or_else(|e| e.into_inner());
+    match deadlines.get_mut(id) {
+        Some(deadline) if !deadline.expired => {
+            deadline.at += Duration::from_secs(seconds);
+            entry.info.duration += seconds;
+            let remaining = deadline.at.saturating_duration_since(Instant::now()).as_secs();
+            events::record(id, EventKind::Extended, Some(format!("{} seconds left", remaining)));
+            Ok(remaining)
+        }
+        _ => Err(ExtendError::NotExtendable),
+    }
+}
+
+// Seconds until the first running timed task is due to end
+fn next_deadline_secs() -> Option<u64> {
+    let deadlines = DEADLINES.lock().unwrap_or_else(|e| e.into_inner());
+    deadlines
+        .values()
+        .map(|deadline| deadline.at.saturating_duration_since(Instant::now()).as_secs())
+        .min()
+}
+
+// Forget a finished task's deadline; true when the task ran until its deadline
+pub fn clear_deadline(id: &str) -> bool {
+    DEADLINES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(id)
+        .is_some_and(|deadline| deadline.expired)
+}
+
+// How a task that was asked to stop ended
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopOutcome {
+    Graceful,  // Ended on its own within the grace period
+    Killed,    // Aborted after the grace period
+    Deadline,  // Killed by the watchdog for running past its hard deadline
+    Emergency, // Killed because the node became unresponsive (see watchdog)
+}
+
+// Outcome of a finished task
+#[derive(Serialize, Clone)]
+pub struct TaskResult {
+    pub task_id: String,
+    #[serde(rename = "type")]
+    pub test_type: Option<String>,     // Test type, as in /tasks
+    pub node: Option<String>,          // Node the caller sent the test to, as in /tasks
+    pub request_id: Option<String>,    // X-Request-Id of the call that started the task, as in /tasks
+    pub finished_at: f64,              // Unix time in seconds
+    pub result: Option<StressResult>,  // Set when the test ran
+    pub error: Option<StressError>,    // Set when the test failed
+    pub stop: Option<StopOutcome>,     // Set when a stop request (or the deadline watchdog) ended the task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<Health>,        // Health timeline of soak tests (see soak)
+}
+
+static RESULTS: Lazy<Mutex<VecDeque<TaskResult>>> = Lazy::new(|| {
+    Mutex::new(VecDeque::new())
+});
+
+// A soak test whose probes found the node unhealthy fails, but keeps the result of its run
+pub fn record_result(id: &str, outcome: Result<StressResult, StressError>, health: Option<Health>) {
+    let unhealthy = health.as_ref().and_then(|health| health.breach.clone()).map(StressError::Unhealthy);
+    if let Some(error) = &unhealthy {
+        events::record(id, EventKind::Failed, Some(error.to_string()));
+    }
+    match &outcome {
+        _ if unhealthy.is_some() => {}
+        Ok(result) => events::record(id, EventKind::Finished, Some(format!(
+            "{} after {:.1} seconds",
+            if result.stopped { "stopped" } else { "completed" },
+            result.elapsed_secs
+        ))),
+        Err(error) => events::record(id, EventKind::Failed, Some(error.to_string())),
+    }
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), unhealthy),
+        Err(error) => (None, Some(error)),
+    };
+    // In the task's span, so traces show the metrics and failure of each run
+    if let Some(result) = &result {
+        tracing::info!(
+            iterations = result.iterations,
+            elapsed_secs = result.elapsed_secs,
+            stopped = result.stopped,
+            achieved_load = result.achieved_load,
+            cpu_load = result.cpu_load,
+            system_load = result.system_load,
+            mb_per_s = result.mb_per_s,
+            oom_kills = result.oom_kills,
+            corrupt_blocks = result.corrupt_blocks,
+            p99_us = result.iteration_times.as_ref().map(|times| times.p99_us),
+            "Task metrics"
+        );
+    }
+    if let Some(error) = &error {
+        tracing::warn!(error = %error, "Task failed");
+    }
+    let stopped = STOPPING.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    // The task is still registered: it is removed once its future has returned
+    let info = GLOBAL_REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).get(id).map(|entry| entry.info.clone());
+    push_result(TaskResult {
+        task_id: id.to_string(),
+        test_type: info.as_ref().map(|info| info.test_type.clone()),
+        node: info.as_ref().and_then(|info| info.node.clone()),
+        request_id: info.and_then(|info| info.request_id),
+        finished_at: now(),
+        result,
+        error,
+        stop: stopped.then_some(StopOutcome::Graceful),
+        health,
+    });
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+// Results beyond the retention count are dropped, oldest first
+fn push_result(entry: TaskResult) {
+    let mut results = RESULTS.lock().unwrap_or_else(|e| e.into_inner());
+    while results.len() >= retention::max_count().max(1) {
+        results.pop_front();
+    }
+    results.push_back(entry);
+}
+
+// Drop the results `matches` selects that finished before `cutoff` (Unix seconds), and
+// all but the newest `keep` of them; returns the IDs of the dropped results
+pub fn prune_results(cutoff: Option<f64>, keep: usize, matches: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut results = RESULTS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut kept = 0;
+    let mut dropped = Vec::new();
+    for index in (0..results.len()).rev() {
+        let entry = &results[index];
+        if !matches(&entry.task_id) {
+            continue;
+        }
+        if kept < keep && cutoff.is_none_or(|cutoff| entry.finished_at >= cutoff) {
+            kept += 1;
+            continue;
+        }
+        if let Some(entry) = results.remove(index) {
+            dropped.push(entry.task_id);
+        }
+    }
+    dropped
+}
+
+pub fn get_result(id: &str) -> Option<TaskResult> {
+    let results = RESULTS.lock().unwrap_or_else(|e| e.into_inner());
+    results.iter().rev().find(|entry| entry.task_id == id).cloned()
+}
+
+pub fn list_results() -> Vec<TaskResult> {
+    let results = RESULTS.lock().unwrap_or_else(|e| e.into_inner());
+    results.iter().cloned().collect()
+}
@@ -0,0 +1,151 @@
+//! Node unresponsiveness watchdog
+//!
+//! A stress test that overshoots can leave the node barely responsive, and with it the
+//! engine's API, so nobody can stop the test anymore. A plain OS thread, independent of
+//! the async runtime, checks every second for
+//!
+//! - event-loop lag: the runtime has not run a heartbeat for `MOGWAI_WATCHDOG_LAG_SECS`
+//!   (default 10) seconds
+//! - memory pressure: the node's PSI `full avg10` in `/proc/pressure/memory` is above
+//!   `MOGWAI_WATCHDOG_MEMORY_PSI` percent (default 50), i.e. all non-idle tasks stall on
+//!   memory that much of the time
+//! - allocator stalls: allocating and touching 1 MB takes longer than
+//!   `MOGWAI_WATCHDOG_ALLOC_MS` (default 2000)
+//!
+//! When one of them trips while tasks are running, every task is killed at once and
+//! recorded in `/results` with `"stop": "emergency"` and the reason. `MOGWAI_WATCHDOG=0`
+//! disables the watchdog.
+
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::thread_manager::{self, GLOBAL_REGISTRY};
+
+/// How often the watchdog thread checks the node
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the runtime records its heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Node-wide memory pressure (Linux 4.20+ with PSI enabled)
+const MEMORY_PRESSURE: &str = "/proc/pressure/memory";
+
+/// Bytes the allocator probe allocates and touches
+const PROBE_BYTES: usize = 1024 * 1024;
+
+const DEFAULT_LAG_SECS: u64 = 10;
+const DEFAULT_MEMORY_PSI: f64 = 50.0;
+const DEFAULT_ALLOC_MS: u64 = 2000;
+
+/// Reference point of the heartbeat; monotonic, so wall-clock steps (NTP corrections,
+/// VM resumes) are not mistaken for event-loop lag
+static STARTED: Lazy<Instant> = Lazy::new(Instant::now);
+
+/// Milliseconds since STARTED of the runtime's last heartbeat
+static HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+
+/// Thresholds beyond which the node counts as unresponsive
+struct Thresholds {
+    lag: Duration,
+    memory_psi: f64,
+    alloc: Duration,
+}
+
+impl Thresholds {
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<f64>().ok()).filter(|&v| v > 0.0);
+        Thresholds {
+            lag: Duration::from_secs(var("MOGWAI_WATCHDOG_LAG_SECS").map_or(DEFAULT_LAG_SECS, |v| v as u64)),
+            memory_psi: var("MOGWAI_WATCHDOG_MEMORY_PSI").unwrap_or(DEFAULT_MEMORY_PSI),
+            alloc: Duration::from_millis(var("MOGWAI_WATCHDOG_ALLOC_MS").map_or(DEFAULT_ALLOC_MS, |v| v as u64)),
+        }
+    }
+}
+
+/// Start the heartbeat on the runtime and the watchdog thread, unless MOGWAI_WATCHDOG=0
+pub fn start() {
+    if matches!(std::env::var("MOGWAI_WATCHDOG").as_deref(), Ok("0") | Ok("false")) {
+        tracing::info!("Unresponsiveness watchdog disabled");
+        return;
+    }
+    let thresholds = Thresholds::from_env();
+    let psi = memory_full_avg10().is_some();
+    tracing::info!(
+        lag_secs = thresholds.lag.as_secs(),
+        alloc_ms = thresholds.alloc.as_millis() as u64,
+        memory_psi = %if psi { format!("{}%", thresholds.memory_psi) } else { "unavailable (no PSI)".to_string() },
+        "Unresponsiveness watchdog started"
+    );
+
+    HEARTBEAT.store(now_millis(), Ordering::Relaxed);
+    tokio::spawn(async {
+        let mut tick = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            tick.tick().await;
+            HEARTBEAT.store(now_millis(), Ordering::Relaxed);
+        }
+    });
+
+    let spawned = std::thread::Builder::new()
+        .name("mogwai-watchdog".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            if thread_manager::list_tasks(&GLOBAL_REGISTRY).is_empty() {
+                continue;
+            }
+            if let Some(reason) = check(&thresholds, psi) {
+                tracing::error!(reason = %reason, "Watchdog: node unresponsive, killing all tasks");
+                let killed = thread_manager::emergency_stop(&GLOBAL_REGISTRY, &reason);
+                tracing::error!(killed, "Watchdog: emergency stop done");
+            }
+        });
+    if let Err(e) = spawned {
+        tracing::error!(error = %e, "Unresponsiveness watchdog failed to start");
+    }
+}
+
+/// Why the node counts as unresponsive, if it does
+fn check(thresholds: &Thresholds, psi: bool) -> Option<String> {
+    let lag = Duration::from_millis(now_millis().saturating_sub(HEARTBEAT.load(Ordering::Relaxed)));
+    if lag > thresholds.lag {
+        return Some(format!("event loop stalled for {:.1} seconds", lag.as_secs_f64()));
+    }
+    if psi {
+        if let Some(pressure) = memory_full_avg10().filter(|&pressure| pressure > thresholds.memory_psi) {
+            return Some(format!(
+                "memory pressure {:.1}% over the last 10 seconds, limit {}%",
+                pressure, thresholds.memory_psi
+            ));
+        }
+    }
+    let alloc = probe_allocation();
+    if alloc > thresholds.alloc {
+        return Some(format!("allocating 1 MB took {} ms", alloc.as_millis()));
+    }
+    None
+}
+
+/// `full avg10` of the node's memory pressure, in percent
+fn memory_full_avg10() -> Option<f64> {
+    let text = std::fs::read_to_string(MEMORY_PRESSURE).ok()?;
+    let line = text.lines().find(|line| line.starts_with("full "))?;
+    line.split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))
+        .and_then(|value| value.parse().ok())
+}
+
+/// Time to allocate and touch PROBE_BYTES; long when the node reclaims or swaps hard
+fn probe_allocation() -> Duration {
+    let started = Instant::now();
+    let mut buffer = vec![0u8; PROBE_BYTES];
+    for page in buffer.chunks_mut(4096) {
+        page[0] = 1;
+    }
+    std::hint::black_box(&buffer);
+    started.elapsed()
+}
+
+/// Milliseconds since STARTED
+fn now_millis() -> u64 {
+    STARTED.elapsed().as_millis() as u64
+}
@@ -0,0 +1,112 @@
+/**
+ * AI test plan generation
+ *
+ * Rust port of the CLI's mogAI.py: the requested intensity and the system
+ * information are sent to the Mistral agent, whose reply is a series of blocks,
+ * each a "# rationale" comment line followed by a JSON test configuration.
+ */
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+
+use crate::api;
+
+/// Mistral agents completion endpoint
+const MISTRAL_AGENTS_URL: &str = "https://api.mistral.ai/v1/agents/completions";
+
+/// One test proposed by the agent (same format the CLI parses)
+#[derive(Debug, Clone, Deserialize)]
+pub struct AiTest {
+    pub test_type: String, // cpu, mem or disk
+    #[serde(default)]
+    pub threads: u32, // Number of threads
+    pub duration: u32, // Duration in seconds
+    #[serde(default)]
+    pub load: Option<u32>, // CPU load percentage
+    #[serde(default)]
+    pub size: Option<u32>, // Size in MB
+    #[serde(default)]
+    pub fork: Option<bool>, // Fork processes instead of threads
+    #[serde(skip)]
+    pub rationale: String, // The block's comment line, explaining why the test was proposed
+}
+
+/// Credential from the environment, falling back to a .env file in the working directory (like mogAI.py)
+fn credential(name: &str) -> Option<String> {
+    if let Ok(value) = std::env::var(name) {
+        return Some(value).filter(|v| !v.trim().is_empty());
+    }
+    let env_file = fs::read_to_string(".env").ok()?;
+    env_file
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == name)
+        .map(|(_, value)| value.trim().trim_matches(|c| c == '\'' || c == '"').to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Ask the agent for a test plan; returns the parsed tests and warnings for blocks that could not be parsed
+pub async fn generate_plan(
+    intensity: u32,
+    system_info: String,
+) -> Result<(Vec<AiTest>, Vec<String>), String> {
+    let api_key = credential("MISTRAL_API_KEY")
+        .ok_or("MISTRAL_API_KEY is not set (environment or .env file)")?;
+    let agent_id = credential("MISTRAL_AGENT_ID")
+        .ok_or("MISTRAL_AGENT_ID is not set (environment or .env file)")?;
+
+    // Same input the CLI pipes into mogAI.py
+    let prompt = format!("{{intensity: {}}}\n{}", intensity, system_info);
+    let body = json!({
+        "agent_id": agent_id,
+        "messages": [{ "role": "user", "content": prompt }],
+    });
+
+    let response: Value = api::decode(api::post_json_bearer(MISTRAL_AGENTS_URL, &api_key, &body).await?)?;
+    let content = response
+        .pointer("/choices/0/message/content")
+        .and_then(Value::as_str)
+        .ok_or("The AI response contained no message")?;
+
+    let (tests, warnings) = parse_plan(content);
+    if tests.is_empty() {
+        return Err(format!("No valid test configurations in the AI response:\n{}", content.trim()));
+    }
+    Ok((tests, warnings))
+}
+
+/// Split the agent's reply into blocks and parse each "# rationale" + JSON pair
+pub fn parse_plan(content: &str) -> (Vec<AiTest>, Vec<String>) {
+    let mut tests = Vec::new();
+    let mut warnings = Vec::new();
+
+    for block in content.split("\n\n").filter(|b| !b.trim().is_empty()) {
+        let rationale = block
+            .lines()
+            .find(|l| l.trim_start().starts_with('#'))
+            .map(|l| l.trim().trim_start_matches('#').trim().to_string())
+            .unwrap_or_default();
+
+        // Models sometimes wrap the JSON in a ``` fence
+        let json_part = block
+            .lines()
+            .filter(|l| !l.trim_start().starts_with('#') && !l.trim_start().starts_with("```"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if json_part.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<AiTest>(&json_part) {
+            Ok(mut test) => {
+                test.rationale = rationale;
+                tests.push(test);
+            }
+            Err(e) => warnings.push(format!("Skipped an unparseable test configuration: {}", e)),
+        }
+    }
+
+    (tests, warnings)
+}
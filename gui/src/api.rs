@@ -0,0 +1,207 @@
+/**
+ * HTTP client for the engine and controller APIs
+ *
+ * All requests go through a shared reqwest client with typed request/response
+ * structs, so payloads are serialized properly, HTTP status codes are visible
+ * and errors are returned as messages instead of being lost in curl output.
+ */
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Shared client (connection pooling across requests)
+fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .timeout(Duration::from_secs(30))
+            .default_headers(mogwai_identity::headers())
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// Client for long-lived streams (no overall timeout, only for connecting)
+fn stream_client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .default_headers(mogwai_identity::headers())
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+// ===== REQUEST / RESPONSE TYPES =====
+/// Body of /cpu-stress, /mem-stress and /disk-stress requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StressRequest {
+    pub id: String,
+    pub batch_id: String,
+    pub name: String,
+    pub intensity: u32, // Number of threads (or processes when forking)
+    pub duration: u64,  // Duration in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load: Option<f64>, // CPU load percentage (CPU tests)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>, // Size in MB (memory and disk tests)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fork: Option<bool>, // Fork processes instead of threads (CPU tests)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>, // Target node (required by the controller)
+}
+
+/// Entry of the controller's GET /nodes response
+#[derive(Debug, Deserialize)]
+pub struct NodeInfo {
+    pub name: String,
+}
+
+/// Entry of the controller's GET /engines response
+#[derive(Debug, Clone, Deserialize)]
+pub struct EngineInfo {
+    pub node: String,
+    pub pod: String,
+    pub phase: String, // Pod phase (Pending, Running, Failed, ...)
+    pub ready: bool,
+}
+
+/// Body of /spawn-engine and /remove-engine requests
+#[derive(Debug, Serialize)]
+struct NodeRequest<'a> {
+    node_name: &'a str,
+}
+
+/// Raw HTTP response: status code and body text
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl ApiResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Convert a non-success status into an error message
+    pub fn into_result(self) -> Result<String, String> {
+        if self.is_success() {
+            Ok(self.body)
+        } else if self.body.trim().is_empty() {
+            Err(format!("HTTP {}", self.status))
+        } else {
+            Err(format!("HTTP {}: {}", self.status, self.body.trim()))
+        }
+    }
+}
+
+/// Describe a transport-level error
+fn describe(url: &str, e: reqwest::Error) -> String {
+    if e.is_connect() {
+        format!("Could not connect to {}", url)
+    } else if e.is_timeout() {
+        format!("Request to {} timed out", url)
+    } else {
+        format!("Request to {} failed: {}", url, e)
+    }
+}
+
+/// Read status and body from a response
+async fn read_response(url: &str, response: reqwest::Response) -> Result<ApiResponse, String> {
+    let status = response.status().as_u16();
+    let body = response.text().await.map_err(|e| describe(url, e))?;
+    Ok(ApiResponse { status, body })
+}
+
+// ===== REQUESTS =====
+/// GET a URL
+pub async fn get(url: &str) -> Result<ApiResponse, String> {
+    let response = client().get(url).send().await.map_err(|e| describe(url, e))?;
+    read_response(url, response).await
+}
+
+/// Open a streaming GET (e.g. Server-Sent Events); the body is read chunk by chunk
+pub async fn get_stream(url: &str) -> Result<reqwest::Response, String> {
+    let response = stream_client().get(url).send().await.map_err(|e| describe(url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status().as_u16()));
+    }
+    Ok(response)
+}
+
+/// POST without a body
+pub async fn post(url: &str) -> Result<ApiResponse, String> {
+    let response = client().post(url).send().await.map_err(|e| describe(url, e))?;
+    read_response(url, response).await
+}
+
+/// POST a JSON body
+pub async fn post_json<T: Serialize>(url: &str, body: &T) -> Result<ApiResponse, String> {
+    let response = client()
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| describe(url, e))?;
+    read_response(url, response).await
+}
+
+/// POST a JSON body with a bearer token (used for the AI recommender; model responses can be slow)
+pub async fn post_json_bearer<T: Serialize>(
+    url: &str,
+    token: &str,
+    body: &T,
+) -> Result<ApiResponse, String> {
+    let response = client()
+        .post(url)
+        .bearer_auth(token)
+        .timeout(Duration::from_secs(120))
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| describe(url, e))?;
+    read_response(url, response).await
+}
+
+/// Decode a successful response body as JSON
+pub fn decode<T: DeserializeOwned>(response: ApiResponse) -> Result<T, String> {
+    let body = response.into_result()?;
+    serde_json::from_str(&body).map_err(|_| format!("Unexpected response: {}", body.trim()))
+}
+
+/// GET a URL and decode the JSON response
+pub async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, String> {
+    decode(get(url).await?)
+}
+
+/// Node names from the controller's GET /nodes
+pub async fn fetch_nodes(server_url: &str) -> Result<Vec<String>, String> {
+    let nodes: Vec<NodeInfo> = get_json(&format!("{}/nodes", server_url)).await?;
+    Ok(nodes.into_iter().map(|node| node.name).collect())
+}
+
+/// Engine pods from the controller's GET /engines
+pub async fn fetch_engines(server_url: &str) -> Result<Vec<EngineInfo>, String> {
+    get_json(&format!("{}/engines", server_url)).await
+}
+
+/// Hardware report (sys_info JSON) from the engine's GET /sysinfo, or GET /sysinfo/{node} on the controller
+pub async fn fetch_system_info(server_url: &str, node: Option<&str>) -> Result<String, String> {
+    let url = match node {
+        Some(node) => format!("{}/sysinfo/{}", server_url, node),
+        None => format!("{}/sysinfo", server_url),
+    };
+    get(&url).await?.into_result()
+}
+
+/// Call /spawn-engine or /remove-engine for a node and return the response body
+pub async fn engine_action(server_url: &str, action: &str, node: &str) -> Result<String, String> {
+    post_json(&format!("{}/{}", server_url, action), &NodeRequest { node_name: node })
+        .await?
+        .into_result()
+        .map(|body| body.trim().to_string())
+}
@@ -0,0 +1,157 @@
+/**
+ * Live metric charts
+ *
+ * Samples from the engine's /metrics endpoint are kept in a rolling history
+ * and drawn as simple line charts on an iced canvas while tests run.
+ */
+use iced::mouse;
+use iced::widget::canvas::{self, Canvas, Frame, Geometry, Path, Stroke};
+use iced::{Color, Element, Length, Point, Rectangle, Renderer, Theme};
+use serde_json::Value;
+
+/// Maximum number of samples kept per chart (about five minutes at one sample per second)
+pub const MAX_SAMPLES: usize = 300;
+
+// ===== METRIC SAMPLES =====
+/// A single metrics snapshot received from the server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricSample {
+    pub cpu_percent: f32,   // Global CPU usage (%)
+    pub memory_used_mb: f32, // Used memory (MB)
+    pub memory_total_mb: f32, // Total memory (MB), used as the memory chart's ceiling
+    pub disk_mb_s: f32,     // Combined disk read + write throughput (MB/s)
+}
+
+impl MetricSample {
+    /// Parse a /metrics response
+    pub fn from_json(json: &Value) -> Option<Self> {
+        let field = |name: &str| json.get(name).and_then(Value::as_f64).map(|v| v as f32);
+        Some(MetricSample {
+            cpu_percent: field("cpu_percent")?,
+            memory_used_mb: field("memory_used_mb")?,
+            memory_total_mb: field("memory_total_mb").unwrap_or(0.0),
+            disk_mb_s: field("disk_read_mb_s").unwrap_or(0.0) + field("disk_write_mb_s").unwrap_or(0.0),
+        })
+    }
+}
+
+/// Append a sample, dropping the oldest once the history is full
+pub fn push_sample(history: &mut Vec<MetricSample>, sample: MetricSample) {
+    if history.len() >= MAX_SAMPLES {
+        history.remove(0);
+    }
+    history.push(sample);
+}
+
+// ===== LINE CHART =====
+/// Line chart of one series; `max` is the fixed ceiling (None = scale to the data)
+struct LineChart {
+    title: &'static str,
+    unit: &'static str,
+    values: Vec<f32>,
+    max: Option<f32>,
+    color: Color,
+}
+
+impl<Message> canvas::Program<Message, Renderer> for LineChart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (width, height) = (bounds.width, bounds.height);
+
+        // Background and axis, tinted from the theme so the chart works in light and dark mode
+        let palette = theme.palette();
+        frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color { a: 0.04, ..palette.text });
+        let axis = Path::line(Point::new(0.0, height - 1.0), Point::new(width, height - 1.0));
+        frame.stroke(&axis, Stroke::default().with_color(Color { a: 0.3, ..palette.text }));
+
+        let data_max = self.values.iter().cloned().fold(0.0_f32, f32::max);
+        let max = self.max.filter(|m| *m > 0.0).unwrap_or(data_max.max(1.0) * 1.1);
+
+        // Series
+        if self.values.len() > 1 {
+            let step = width / (MAX_SAMPLES - 1) as f32;
+            let offset = width - step * (self.values.len() - 1) as f32; // Newest sample at the right edge
+            let line = Path::new(|builder| {
+                for (i, value) in self.values.iter().enumerate() {
+                    let x = offset + step * i as f32;
+                    let y = height - (value / max).clamp(0.0, 1.0) * (height - 4.0) - 2.0;
+                    if i == 0 {
+                        builder.move_to(Point::new(x, y));
+                    } else {
+                        builder.line_to(Point::new(x, y));
+                    }
+                }
+            });
+            frame.stroke(&line, Stroke::default().with_color(self.color).with_width(2.0));
+        }
+
+        // Title with the latest value
+        let label = match self.values.last() {
+            Some(value) => format!("{}: {:.1} {}", self.title, value, self.unit),
+            None => format!("{}: waiting for data...", self.title),
+        };
+        frame.fill_text(canvas::Text {
+            content: label,
+            position: Point::new(6.0, 4.0),
+            color: palette.text,
+            size: 14.0,
+            ..canvas::Text::default()
+        });
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Build a chart widget for one series
+fn chart<'a, Message: 'a>(
+    title: &'static str,
+    unit: &'static str,
+    values: Vec<f32>,
+    max: Option<f32>,
+    color: Color,
+) -> Element<'a, Message> {
+    Canvas::new(LineChart { title, unit, values, max, color })
+        .width(Length::Fill)
+        .height(Length::Fixed(110.0))
+        .into()
+}
+
+/// CPU, memory and disk charts for the collected history
+pub fn metric_charts<'a, Message: 'a>(history: &[MetricSample]) -> Element<'a, Message> {
+    let memory_total = history.last().map(|s| s.memory_total_mb);
+
+    iced::widget::Column::new()
+        .push(chart(
+            "CPU Load",
+            "%",
+            history.iter().map(|s| s.cpu_percent).collect(),
+            Some(100.0),
+            Color::from_rgb(0.85, 0.35, 0.25),
+        ))
+        .push(chart(
+            "Memory Used",
+            "MB",
+            history.iter().map(|s| s.memory_used_mb).collect(),
+            memory_total,
+            Color::from_rgb(0.25, 0.5, 0.85),
+        ))
+        .push(chart(
+            "Disk I/O",
+            "MB/s",
+            history.iter().map(|s| s.disk_mb_s).collect(),
+            None,
+            Color::from_rgb(0.3, 0.7, 0.4),
+        ))
+        .spacing(8)
+        .width(Length::Fill)
+        .into()
+}
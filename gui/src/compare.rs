@@ -0,0 +1,299 @@
+/**
+ * Side-by-side run comparison
+ *
+ * Two runs (batches finished in this session, or JSON reports saved to the results
+ * directory) are matched test by test and node by node, and every parameter, status
+ * and metric is listed next to its counterpart with the relative change, so a
+ * regression after a node change shows up without exporting to a spreadsheet.
+ */
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::report::{BatchReport, HostSummary, TestReport};
+
+/// Number of batches from this session kept for comparison
+pub const MAX_HISTORY: usize = 20;
+
+/// Changes smaller than this (in percent) are not highlighted
+const THRESHOLD_PERCENT: f64 = 5.0;
+
+/// Reads one host metric from a node's summary
+type HostField = fn(&HostSummary) -> f32;
+
+/// A run that can be picked on the Compare tab
+#[derive(Debug, Clone)]
+pub struct CompareRun {
+    pub label: String,             // Unique label shown in the run pickers
+    pub reports: Vec<BatchReport>, // One report per node
+}
+
+impl CompareRun {
+    /// Run for a batch that finished in this session
+    pub fn from_session(reports: Vec<BatchReport>) -> Self {
+        let first = reports.first();
+        let batch_id = first.map_or("-", |r| r.batch_id.as_str());
+        let started = first
+            .and_then(|r| chrono::DateTime::parse_from_rfc3339(&r.started_at).ok())
+            .map(|t| t.format("%H:%M:%S").to_string())
+            .unwrap_or_default();
+        CompareRun {
+            label: format!(
+                "Session: batch {} ({})",
+                batch_id.chars().take(8).collect::<String>(),
+                started
+            ),
+            reports,
+        }
+    }
+}
+
+/// How a value changed from the left run to the right run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Unchanged, // Equal, or within the threshold
+    Better,    // Moved in the metric's good direction
+    Worse,     // Moved in the metric's bad direction (a possible regression)
+    Changed,   // Differs, but the metric has no good direction (parameters, status, host load)
+}
+
+/// One line of the comparison
+#[derive(Debug, Clone)]
+pub struct CompareRow {
+    pub section: String,      // Test or host the line belongs to, e.g. "node-1 · CPU"
+    pub name: String,         // Parameter or metric name
+    pub left: String,         // Value in the left run ("-" when missing)
+    pub right: String,        // Value in the right run ("-" when missing)
+    pub delta: Option<f64>,   // Relative change in percent (numeric values only)
+    pub trend: Trend,
+}
+
+/// Load the JSON reports saved in `dir` (newest first); other files are ignored
+pub fn load_saved_runs(dir: &Path) -> Result<Vec<CompareRun>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    let mut runs: Vec<(SystemTime, CompareRun)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let reports = parse_reports(&fs::read_to_string(&path).ok()?)?;
+            let modified = fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            let name = path.file_name()?.to_string_lossy().to_string();
+            Some((modified, CompareRun { label: format!("Saved: {}", name), reports }))
+        })
+        .collect();
+
+    runs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(runs.into_iter().map(|(_, run)| run).collect())
+}
+
+/// Parse a JSON export (an object for single-node runs, an array for multi-node runs)
+fn parse_reports(data: &str) -> Option<Vec<BatchReport>> {
+    match serde_json::from_str::<Value>(data).ok()? {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| serde_json::from_value(item).ok())
+            .collect(),
+        object => serde_json::from_value(object).ok().map(|report| vec![report]),
+    }
+}
+
+/// Compare two runs line by line
+pub fn compare(left: &CompareRun, right: &CompareRun) -> Vec<CompareRow> {
+    let left_tests = keyed_tests(&left.reports);
+    let right_tests = keyed_tests(&right.reports);
+    let keys: BTreeSet<&(String, String, usize)> = left_tests.keys().chain(right_tests.keys()).collect();
+
+    let mut rows = Vec::new();
+    for key in keys {
+        let (node, test_type, occurrence) = key;
+        let section = match occurrence {
+            1 => format!("{} · {}", node, test_type),
+            n => format!("{} · {} #{}", node, test_type, n),
+        };
+        let (a, b) = (left_tests.get(key).copied(), right_tests.get(key).copied());
+
+        rows.push(text_row(&section, "Parameters", a.map(parameters), b.map(parameters)));
+        rows.push(text_row(&section, "Status", a.map(status), b.map(status)));
+
+        let metric_names: BTreeSet<&String> = a
+            .into_iter()
+            .chain(b)
+            .flat_map(|test| test.metrics.keys())
+            .collect();
+        for name in metric_names {
+            let value = |test: Option<&TestReport>| test.and_then(|t| t.metrics.get(name)).copied();
+            rows.push(number_row(&section, name, value(a), value(b), direction(name)));
+        }
+    }
+
+    // Host metrics polled during each run, per node
+    let nodes: BTreeSet<String> = left.reports.iter().chain(&right.reports).map(node_name).collect();
+    for node in nodes {
+        let host = |run: &CompareRun| {
+            run.reports
+                .iter()
+                .find(|report| node_name(report) == node)
+                .and_then(|report| report.host_metrics.clone())
+        };
+        let (a, b) = (host(left), host(right));
+        if a.is_none() && b.is_none() {
+            continue;
+        }
+        let section = format!("{} · Host", node);
+        let fields: [(&str, HostField); 4] = [
+            ("Average CPU (%)", |h| h.cpu_avg_percent),
+            ("Peak CPU (%)", |h| h.cpu_peak_percent),
+            ("Peak memory (MB)", |h| h.memory_peak_mb),
+            ("Peak disk I/O (MB/s)", |h| h.disk_peak_mb_s),
+        ];
+        for (name, field) in fields {
+            rows.push(number_row(
+                &section,
+                name,
+                a.as_ref().map(|h| field(h) as f64),
+                b.as_ref().map(|h| field(h) as f64),
+                Direction::None,
+            ));
+        }
+    }
+
+    rows
+}
+
+/// Which way a metric should move
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Higher,
+    Lower,
+    None,
+}
+
+/// Which way an engine result metric should move, by the rules the controller's baselines use:
+/// rates and achieved loads up; latencies, contention, OOM kills and corrupt blocks down.
+/// Fields that only describe the run (elapsed time, iteration counts) have no direction
+fn direction(metric: &str) -> Direction {
+    let name = metric.rsplit('.').next().unwrap_or(metric);
+    if name.ends_with("_us") || matches!(name, "ns_per_call" | "contended_percent" | "oom_kills" | "corrupt_blocks") {
+        Direction::Lower
+    } else if name.ends_with("_per_s") || matches!(name, "achieved_load" | "cpu_load" | "system_load") {
+        Direction::Higher
+    } else {
+        Direction::None
+    }
+}
+
+/// Node name used to match reports ("local" when talking to an engine directly)
+fn node_name(report: &BatchReport) -> String {
+    report.node.clone().unwrap_or_else(|| "local".to_string())
+}
+
+/// Tests keyed by (node, test type, occurrence of that type on the node)
+fn keyed_tests(reports: &[BatchReport]) -> BTreeMap<(String, String, usize), &TestReport> {
+    let mut tests = BTreeMap::new();
+    for report in reports {
+        let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+        for test in &report.tests {
+            let occurrence = seen.entry(test.test_type.as_str()).or_insert(0);
+            *occurrence += 1;
+            tests.insert((node_name(report), test.test_type.clone(), *occurrence), test);
+        }
+    }
+    tests
+}
+
+/// Short description of a test's parameters
+fn parameters(test: &TestReport) -> String {
+    match &test.request {
+        Some(r) => {
+            let mut parts = vec![format!("{} threads", r.intensity), format!("{}s", r.duration)];
+            if let Some(load) = r.load {
+                parts.push(format!("{}% load", load));
+            }
+            if let Some(size) = r.size {
+                parts.push(format!("{} MB", size));
+            }
+            if r.fork == Some(true) {
+                parts.push("fork".to_string());
+            }
+            parts.join(", ")
+        }
+        None => "-".to_string(),
+    }
+}
+
+/// Final status, error or HTTP status of a test
+fn status(test: &TestReport) -> String {
+    match (&test.error, &test.status, test.http_status) {
+        (Some(error), _, _) => error.clone(),
+        (None, Some(status), _) => status.clone(),
+        (None, None, Some(code)) => format!("HTTP {}", code),
+        (None, None, None) => "-".to_string(),
+    }
+}
+
+/// Row for a textual value
+fn text_row(section: &str, name: &str, left: Option<String>, right: Option<String>) -> CompareRow {
+    let trend = if left == right { Trend::Unchanged } else { Trend::Changed };
+    CompareRow {
+        section: section.to_string(),
+        name: name.to_string(),
+        left: left.unwrap_or_else(|| "-".to_string()),
+        right: right.unwrap_or_else(|| "-".to_string()),
+        delta: None,
+        trend,
+    }
+}
+
+/// Row for a numeric value with its relative change
+fn number_row(
+    section: &str,
+    name: &str,
+    left: Option<f64>,
+    right: Option<f64>,
+    direction: Direction,
+) -> CompareRow {
+    let delta = match (left, right) {
+        (Some(a), Some(b)) if a != 0.0 => Some((b - a) / a.abs() * 100.0),
+        _ => None,
+    };
+    let trend = match (left, right, delta) {
+        (Some(a), Some(b), _) if a == b => Trend::Unchanged,
+        (_, _, Some(d)) if d.abs() < THRESHOLD_PERCENT => Trend::Unchanged,
+        (Some(_), Some(_), Some(d)) => match direction {
+            Direction::Higher if d > 0.0 => Trend::Better,
+            Direction::Lower if d < 0.0 => Trend::Better,
+            Direction::None => Trend::Changed,
+            _ => Trend::Worse,
+        },
+        // Rising from zero, e.g. the first OOM kill
+        (Some(_), Some(b), None) => match direction {
+            Direction::Higher if b > 0.0 => Trend::Better,
+            Direction::Lower if b > 0.0 => Trend::Worse,
+            _ => Trend::Changed,
+        },
+        _ => Trend::Changed, // Missing on one side
+    };
+    CompareRow {
+        section: section.to_string(),
+        name: name.to_string(),
+        left: left.map_or_else(|| "-".to_string(), format_number),
+        right: right.map_or_else(|| "-".to_string(), format_number),
+        delta,
+        trend,
+    }
+}
+
+/// Number with up to two decimals
+fn format_number(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
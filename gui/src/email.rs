@@ -0,0 +1,157 @@
+/**
+ * Email report dispatch
+ *
+ * A batch can email its rendered report to a list of recipients when it completes,
+ * for people who read results in their inbox rather than in the GUI or Grafana.
+ * The SMTP server is configured in <config dir>/mogwai/smtp.json (or the file named
+ * by MOGWAI_SMTP_CONFIG); MOGWAI_SMTP_PASSWORD overrides the stored password.
+ */
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Connection security of the SMTP server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls, // Plain connection upgraded with STARTTLS (port 587)
+    Tls,      // TLS from the start (port 465)
+    None,     // Unencrypted, for local relays only
+}
+
+/// SMTP settings from the config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,        // Defaults to the standard port for the security mode
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>, // No authentication when unset
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,             // Sender, e.g. "Mogwai <mogwai@example.com>"
+}
+
+/// Body format of an emailed report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmailFormat {
+    #[default]
+    Html, // The styled HTML report
+    Text, // The plain-text results shown in the GUI
+}
+
+impl EmailFormat {
+    pub const ALL: [EmailFormat; 2] = [EmailFormat::Html, EmailFormat::Text];
+}
+
+impl std::fmt::Display for EmailFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Html => write!(f, "HTML"),
+            Self::Text => write!(f, "Text"),
+        }
+    }
+}
+
+/// Where a batch's report goes once the batch completes
+#[derive(Debug, Clone)]
+pub struct ReportEmail {
+    pub recipients: Vec<String>,
+    pub format: EmailFormat,
+}
+
+/// Location of the SMTP config file
+pub fn smtp_config_path() -> PathBuf {
+    if let Ok(path) = std::env::var("MOGWAI_SMTP_CONFIG") {
+        return PathBuf::from(path);
+    }
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("mogwai")
+        .join("smtp.json")
+}
+
+/// Load the SMTP settings; a missing file means email is not configured
+pub fn load_smtp_config() -> Result<SmtpConfig, String> {
+    let path = smtp_config_path();
+    let data = fs::read_to_string(&path).map_err(|_| {
+        format!("Email is not configured. Create {} with the SMTP settings.", path.display())
+    })?;
+    let mut config: SmtpConfig = serde_json::from_str(&data)
+        .map_err(|e| format!("SMTP config {} is invalid: {}", path.display(), e))?;
+    if let Ok(password) = std::env::var("MOGWAI_SMTP_PASSWORD") {
+        config.password = Some(password);
+    }
+    Ok(config)
+}
+
+/// Split a comma- or semicolon-separated recipient list and check every address
+pub fn parse_recipients(input: &str) -> Result<Vec<String>, String> {
+    let recipients: Vec<String> = input
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(str::to_string)
+        .collect();
+    for address in &recipients {
+        address
+            .parse::<Mailbox>()
+            .map_err(|_| format!("Invalid email address '{}'.", address))?;
+    }
+    Ok(recipients)
+}
+
+/// Send a rendered report; returns a confirmation for the status line
+pub async fn send_report(
+    email: ReportEmail,
+    subject: String,
+    body: String,
+) -> Result<String, String> {
+    let config = load_smtp_config()?;
+
+    let from = config
+        .from
+        .parse::<Mailbox>()
+        .map_err(|_| format!("Invalid sender address '{}'.", config.from))?;
+    let mut builder = Message::builder().from(from).subject(subject);
+    for recipient in &email.recipients {
+        let mailbox = recipient
+            .parse::<Mailbox>()
+            .map_err(|_| format!("Invalid email address '{}'.", recipient))?;
+        builder = builder.to(mailbox);
+    }
+    let content_type = match email.format {
+        EmailFormat::Html => ContentType::TEXT_HTML,
+        EmailFormat::Text => ContentType::TEXT_PLAIN,
+    };
+    let message = builder
+        .header(content_type)
+        .body(body)
+        .map_err(|e| format!("Failed to build the email: {}", e))?;
+
+    let mut transport = match config.security {
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host),
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)),
+    }
+    .map_err(|e| format!("Invalid SMTP host '{}': {}", config.host, e))?;
+    if let Some(port) = config.port {
+        transport = transport.port(port);
+    }
+    if let Some(username) = config.username {
+        transport = transport.credentials(Credentials::new(username, config.password.unwrap_or_default()));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| format!("Failed to send the report email: {}", e))?;
+    Ok(format!("Report emailed to {}.", email.recipients.join(", ")))
+}
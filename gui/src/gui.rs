@@ -7,18 +7,35 @@
  */
 // === LIBRARY IMPORTS ===
 use iced::widget::{
-    toggler, Button, Checkbox, Column, Container, PickList, Row, Rule, Scrollable, Space, Text,
-    TextInput,
+    scrollable, toggler, Button, Checkbox, Column, Container, PickList, ProgressBar, Row, Rule, Scrollable,
+    Slider, Space, Text, TextInput,
 };
 use iced::{alignment, Alignment, Application, Color, Command, Element, Length, Settings, Theme};
+use serde::{Deserialize, Serialize};
 use serde_json::{from_str as json_from_str, to_string_pretty, Value};
+use std::collections::BTreeMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::Path;
 use std::process::Command as ProcessCommand;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+use crate::ai::{self, AiTest};
+use crate::api::{self, ApiResponse, EngineInfo, StressRequest};
+use crate::charts::{self, MetricSample};
+use crate::compare::{self, CompareRun, Trend};
+use crate::email::{self, EmailFormat, ReportEmail};
+use crate::logs;
+use crate::prefs::{self, ThemeChoice};
+use crate::progress::{self, ProgressBoard, ProgressState, TestProgress};
+use crate::presets::{self, Preset};
+use crate::report::{self, BatchReport, ExportFormat, HostSummary, TestReport};
+use crate::schedule::{self, ScheduledTest};
+
 // ===== ENVIRONMENT CONFIGURATION =====
 /**
  * Defines available environments for running tests
@@ -27,18 +44,14 @@ use uuid::Uuid;
  * - Custom: Custom URL environment specified by the user
  */
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Default environment is Local
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Environment {
+    #[default]
     Local,      // Local development environment
     Kubernetes, // Kubernetes cluster environment
     Custom,     // Custom URL environment
 }
-// Default environment is Local
-impl Default for Environment {
-    fn default() -> Self {
-        Environment::Local
-    }
-}
 // Display for environment types
 impl std::fmt::Display for Environment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -50,6 +63,50 @@ impl std::fmt::Display for Environment {
     }
 }
 
+// ===== TABS =====
+/// Top-level views of the application
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Tests, // Test configuration, execution and results
+    Tasks, // Running tasks with stop controls
+    Scheduled, // Tests scheduled for later with cancel controls
+    Engines, // Engine pods per node with spawn/remove controls (Kubernetes only)
+    Compare, // Two runs side by side with metric deltas
+}
+
+/// A running task as reported by the server's task list
+#[derive(Debug, Clone)]
+pub struct TaskRow {
+    id: String,      // Task ID used for /stop
+    details: String, // Extra fields reported for the task (if any)
+}
+
+/// Outcome of a batch on a single target node
+#[derive(Debug, Clone)]
+pub struct NodeResult {
+    node: Option<String>, // Target node (None when talking to an engine directly)
+    text: String,         // Human-readable report
+    report: BatchReport,  // Structured report for export
+}
+
+/// A test proposed by the AI recommender, editable before running
+#[derive(Debug, Clone)]
+pub struct PlanEntry {
+    enabled: bool,          // Whether the entry is included when the plan is run
+    test: TestType,         // Test type
+    settings: TestSettings, // Parameters proposed by the AI (editable)
+    rationale: String,      // Why the AI proposed this test
+}
+
+/// Editable text field of a plan entry
+#[derive(Debug, Clone, Copy)]
+pub enum PlanField {
+    Duration,
+    Intensity,
+    Size,
+    Load,
+}
+
 // ===== APPLICATION MESSAGES =====
 /**
  * Message types for handling user interactions and async operations
@@ -59,32 +116,199 @@ impl std::fmt::Display for Environment {
 pub enum Message {
     ToggleTest(TestType, bool), // Message to toggle the selection of a test type (test type, is checked)
     RunPressed,                 // Message when the "Run Tests" button is pressed
+    CancelPressed,              // Message when the "Cancel" button is pressed while tests run
     ListTasksPressed,           // Message when the "List Tasks" button is pressed
     ServerUrlChanged(String),   // Message when the server URL input field changes (new URL value)
-    DurationChanged(String), // Message when the test duration input field changes (new duration value)
-    IntensityChanged(String), // Message when the test intensity input field changes (new intensity value)
-    SizeChanged(String),      // Message when the test size input field changes (new size value)
+    DurationChanged(TestType, String), // Message when a test's duration input field changes (test type, new duration value)
+    IntensityChanged(TestType, String), // Message when a test's intensity input field changes (test type, new intensity value)
+    SizeChanged(TestType, String),      // Message when a test's size input field changes (test type, new size value)
     LoadChanged(String), // Message when the CPU load percentage input field changes (new load value)
     ForkToggled(bool),   // Message when the "Fork Test" toggle is changed (new toggle state)
+    ToggleSection(TestType), // Message to expand/collapse a test's parameter section
     ToggleAdvanced,      // Message to toggle the visibility of advanced settings
-    TestComplete(String), // Message received when a test execution completes (test results as a string)
-    TasksListed(String),  // Message received with the list of running tasks (as a string)
+    TestComplete(Vec<NodeResult>), // Message received when a test execution completes (one result per target node)
+    TasksListed(Result<Vec<TaskRow>, String>), // Message received with the list of running tasks (Err with error message)
     EnvironmentSelected(Environment), // Message when a different environment is selected from the dropdown (new environment)
-    #[allow(dead_code)]
-    LogsReceived(String), // Message received containing logs from the test execution (as a string, currently not fully used in UI)
+    LogsReceived(String), // Message received with a line from the live log stream
+    ClearLogsPressed,     // Message when the "Clear" button of the log pane is pressed
     NodeStatusReceived(String), // Message received with the status of the nodes involved in the test (as a string)
-    SaveResultsPressed,         // Message when the "Save Results" button is pressed
-    ResultsSaved(Result<(), String>), // Message indicating the result of the save operation (Ok for success, Err with error message)
+    SaveResultsPressed,         // Message when the "Save Results" button is pressed (opens the save dialog)
+    ExportFormatSelected(ExportFormat), // Message when a file format is picked in the save dialog
+    ExportFileChanged(String),  // Message when the file name in the save dialog changes
+    ConfirmSavePressed,         // Message when "Save" is pressed in the save dialog
+    CancelSavePressed,          // Message when "Cancel" is pressed in the save dialog
+    ResultsSaved(Result<String, String>), // Message indicating the result of the save operation (Ok with the saved path, Err with error message)
+    RefreshNodesPressed,              // Message when the "Refresh" button next to the node picker is pressed
+    NodesFetched(Result<Vec<String>, String>), // Message received with the node names from GET /nodes (Err with error message)
+    NodeToggled(String, bool),        // Message when a node checkbox is toggled (node name, is checked)
+    AllNodesToggled(bool),            // Message when the "All nodes" checkbox is toggled (is checked)
+    PollMetrics,                      // Periodic tick while tests run, triggers a /metrics request
+    ProgressTick,                     // Periodic tick while tests run, refreshes the progress bars
+    MetricsReceived(Result<MetricSample, String>), // Message received with a metrics snapshot (Err with error message)
+    TabSelected(Tab),                 // Message when a tab is selected
+    RefreshTasks,                     // Periodic tick on the Tasks tab (or the Refresh button)
+    StopTaskPressed(String),          // Message when a task's "Stop" button is pressed (task ID)
+    StopAllPressed,                   // Message when the "Stop All" button is pressed
+    StopCompleted(Result<String, String>), // Message received when a stop request finishes (server response or error)
+    ScheduleToggled(bool),            // Message when "Schedule for later" is toggled (new toggle state)
+    ScheduleDateChanged(String),      // Message when the schedule date input changes (YYYY-MM-DD)
+    ScheduleHourSelected(u32),        // Message when an hour is picked for the scheduled run
+    ScheduleMinuteSelected(u32),      // Message when a minute is picked for the scheduled run
+    ScheduleInPressed(i64),           // Message when a quick "+N min" button is pressed (minutes from now)
+    CancelScheduled(String),          // Message when a scheduled batch's "Cancel" button is pressed (schedule ID)
+    SchedulerTick,                    // Periodic tick that starts scheduled batches once they are due
+    PresetNameChanged(String),        // Message when the preset name input changes (new name)
+    SavePresetPressed,                // Message when the "Save Preset" button is pressed
+    PresetSelected(String),           // Message when a preset is picked from the preset dropdown (preset name)
+    DeletePresetPressed,              // Message when the "Delete" button next to the preset dropdown is pressed
+    ThemeSelected(ThemeChoice),       // Message when a theme is picked (light/dark/system)
+    AiIntensityChanged(u32),          // Message when the AI plan intensity slider moves (1-10)
+    AiPlanPressed,                    // Message when the "AI Plan" button is pressed
+    AiPlanReceived(Result<(Vec<AiTest>, Vec<String>), String>), // Message received with the AI's proposed tests and parse warnings (Err with error message)
+    PlanEntryToggled(usize, bool),    // Message when a plan entry's checkbox is toggled (entry index, is checked)
+    PlanFieldChanged(usize, PlanField, String), // Message when a plan entry's input changes (entry index, field, new value)
+    PlanForkToggled(usize, bool),     // Message when a CPU plan entry's "Fork" checkbox is toggled (entry index, new state)
+    RunPlanPressed,                   // Message when "Run Plan" is pressed
+    DiscardPlanPressed,               // Message when "Discard" is pressed on the AI plan
+    RefreshEngines,                   // Periodic tick on the Engines tab (or the Refresh button)
+    EnginesFetched(Result<Vec<EngineInfo>, String>), // Message received with the engine pods from GET /engines (Err with error message)
+    SpawnEnginePressed(String),       // Message when a node's "Spawn" button is pressed (node name)
+    RemoveEnginePressed(String),      // Message when a node's "Remove" button is pressed (node name)
+    EngineActionCompleted(String, Result<String, String>), // Message received when a spawn/remove request finishes (node name, server response or error)
+    RefreshSavedRuns,                 // Message when the Compare tab opens (or its "Refresh" button is pressed), reloads the saved JSON reports
+    SavedRunsLoaded(Result<Vec<CompareRun>, String>), // Message received with the runs saved in the results directory (Err with error message)
+    CompareLeftSelected(String),      // Message when a run is picked for the left column (run label)
+    CompareRightSelected(String),     // Message when a run is picked for the right column (run label)
+    SwapComparePressed,               // Message when the "Swap" button of the Compare tab is pressed
+    EmailToggled(bool),               // Message when "Email report when done" is toggled (new toggle state)
+    EmailRecipientsChanged(String),   // Message when the recipient list input changes (comma-separated addresses)
+    EmailFormatSelected(EmailFormat), // Message when the email body format is picked (HTML/text)
+    ReportEmailed(Result<String, String>), // Message received when the report email was sent (confirmation or error)
 }
 // ===== TEST TYPES =====
 ///Types of stress tests available in the application
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TestType {
     Cpu,    //CPU stress test
     Memory, //memory stress test
     Disk,   //disk stress test
 }
 
+// ===== TEST SETTINGS =====
+/**
+ * Parameter set for a single test type, kept as raw user input
+ * Each selected test has its own settings so e.g. a 30-second CPU test can run
+ * alongside a 10-minute disk test in the same batch
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSettings {
+    duration: String,  // The duration of the test, as a string from user input
+    intensity: String, // The intensity of the test (e.g., number of threads), as a string
+    size: String,      // The size parameter for memory and disk tests (in MB), as a string
+    load: String,      // The CPU load percentage for the CPU test, as a string
+    fork: bool,        // Flag indicating if the CPU test should fork separate processes
+    #[serde(default)]
+    expanded: bool,    // Flag to control the visibility of the parameter section
+}
+
+// ===== INPUT VALIDATION =====
+/// Accepted range for durations (seconds, up to 24 hours)
+const DURATION_RANGE: std::ops::RangeInclusive<u64> = 1..=86_400;
+/// Accepted range for intensity (threads or processes)
+const INTENSITY_RANGE: std::ops::RangeInclusive<u32> = 1..=1024;
+/// Accepted range for memory/disk sizes (MB, up to 100 GB)
+const SIZE_RANGE: std::ops::RangeInclusive<u32> = 1..=102_400;
+/// Accepted range for the CPU load percentage
+const LOAD_RANGE: std::ops::RangeInclusive<f64> = 1.0..=100.0;
+
+/// Validation errors of a test's input fields (None = valid)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldErrors {
+    duration: Option<String>,
+    intensity: Option<String>,
+    size: Option<String>,  // Memory and disk tests only
+    load: Option<String>,  // CPU tests only
+}
+
+impl FieldErrors {
+    fn is_valid(&self) -> bool {
+        *self == FieldErrors::default()
+    }
+}
+
+/// Check that a field parses and lies within the accepted range
+fn check_range<T>(value: &str, range: &std::ops::RangeInclusive<T>) -> Option<String>
+where
+    T: std::str::FromStr + PartialOrd + std::fmt::Display,
+{
+    match value.trim().parse::<T>() {
+        Err(_) if value.trim().is_empty() => Some("Required".to_string()),
+        Err(_) => Some("Must be a number".to_string()),
+        Ok(number) if !range.contains(&number) => {
+            Some(format!("Must be between {} and {}", range.start(), range.end()))
+        }
+        Ok(_) => None,
+    }
+}
+
+impl TestSettings {
+    /// Validate the fields used by the given test type
+    fn validate(&self, test: TestType) -> FieldErrors {
+        FieldErrors {
+            duration: check_range(&self.duration, &DURATION_RANGE),
+            intensity: check_range(&self.intensity, &INTENSITY_RANGE),
+            size: match test {
+                TestType::Cpu => None,
+                TestType::Memory | TestType::Disk => check_range(&self.size, &SIZE_RANGE),
+            },
+            load: match test {
+                TestType::Cpu => check_range(&self.load, &LOAD_RANGE),
+                TestType::Memory | TestType::Disk => None,
+            },
+        }
+    }
+}
+
+impl PlanEntry {
+    /// Editable entry for an AI-proposed test (None for unknown test types)
+    fn from_ai(test: &AiTest) -> Option<Self> {
+        let test_type = match test.test_type.to_lowercase().as_str() {
+            "cpu" => TestType::Cpu,
+            "mem" | "memory" => TestType::Memory,
+            "disk" => TestType::Disk,
+            _ => return None,
+        };
+        let defaults = TestSettings::defaults_for(test_type);
+        Some(PlanEntry {
+            enabled: true,
+            test: test_type,
+            settings: TestSettings {
+                duration: test.duration.to_string(),
+                intensity: test.threads.max(1).to_string(),
+                size: test.size.map_or(defaults.size, |size| size.to_string()),
+                load: test.load.map_or(defaults.load, |load| load.to_string()),
+                fork: test.fork.unwrap_or(false),
+                expanded: true,
+            },
+            rationale: test.rationale.clone(),
+        })
+    }
+}
+
+impl TestSettings {
+    /// Default parameters for a test type
+    fn defaults_for(test: TestType) -> Self {
+        TestSettings {
+            duration: String::from("10"),
+            intensity: String::from("4"),
+            size: String::from("256"),
+            load: if test == TestType::Cpu { String::from("70.0") } else { String::new() },
+            fork: false,
+            expanded: true,
+        }
+    }
+}
+
 // ===== MAIN APPLICATION STRUCT =====
 /**
  * Main application state container
@@ -95,19 +319,84 @@ pub struct GuiApp {
     selected_tests: Vec<TestType>, // Vector to store the currently selected test types
     server_url: String,            // The URL of the server to send test requests to
     environment: Environment,      // The currently selected environment
-    duration: String,              // The duration of the tests, as a string from user input
-    intensity: String, // The intensity of the tests (e.g., number of threads), as a string
-    size: String,      // The size parameter for memory and disk tests (in MB), as a string
-    load: String,      // The CPU load percentage for the CPU test, as a string
-    fork: bool,        // Flag indicating if the CPU test should fork separate processes
+    settings: HashMap<TestType, TestSettings>, // Independent parameter set for each test type
 
     // State tracking
     status_message: Option<String>, // Message to display status updates and results to the user
     node_status: Option<String>,    // Status information received from the test nodes
     test_results: Option<String>,   // The raw results of the completed tests
+    node_results: Vec<NodeResult>,  // Per-node results of the completed tests (text and structured, for export)
     show_advanced: bool,            // Flag to control the visibility of advanced settings
     running_tests: bool,            // Flag to indicate if tests are currently running
     last_test_id: Option<String>, // The ID of the last run test batch, used for fetching node status
+
+    // Kubernetes node selection
+    nodes: Vec<String>,            // Node names fetched from the controller's /nodes endpoint
+    selected_nodes: Vec<String>,   // The nodes tests are dispatched to (the controller requires at least one)
+    fetching_nodes: bool,          // Flag to indicate a /nodes request is in flight
+
+    // Progress
+    progress: ProgressBoard,           // Per-test progress written by the running batch
+    progress_rows: Vec<TestProgress>,  // Copy of the board taken on the last progress tick
+    cancel: Arc<AtomicBool>,           // Set to cancel the running batch (checked by execute_tests)
+
+    // Live logs
+    logs: VecDeque<String>, // Lines received from the log stream (newest last)
+    logs_since: f64,        // Unix time the current batch started; older backlog lines are skipped
+
+    // Live metrics
+    metrics_history: Vec<MetricSample>, // Samples polled from /metrics during the current run
+    metrics_error: Option<String>,      // Last metrics polling error, shown under the charts
+
+    // Engine deployment
+    engines: HashMap<String, EngineInfo>, // Engine pods keyed by node, from the last /engines refresh
+    engine_busy: HashSet<String>,         // Nodes with a spawn/remove request in flight
+    engines_message: Option<String>,      // Status line for the Engines tab (errors, action results)
+
+    // Run comparison
+    run_history: Vec<CompareRun>,   // Batches finished in this session (newest first)
+    saved_runs: Vec<CompareRun>,    // JSON reports found in the results directory (newest first)
+    compare_left: Option<String>,   // Label of the run shown in the left column
+    compare_right: Option<String>,  // Label of the run shown in the right column
+    compare_message: Option<String>, // Status line for the Compare tab (errors)
+
+    // Task management
+    tab: Tab,                       // The currently visible tab
+    tasks: Vec<TaskRow>,            // Tasks from the last task list refresh
+    tasks_message: Option<String>,  // Status line for the Tasks tab (errors, stop results)
+
+    // Scheduling
+    schedule_enabled: bool,         // Flag indicating RUN TESTS should schedule instead of running now
+    schedule_date: String,          // Date of the scheduled run (YYYY-MM-DD)
+    schedule_hour: u32,             // Hour of the scheduled run (local time)
+    schedule_minute: u32,           // Minute of the scheduled run (local time)
+    scheduled: Vec<ScheduledTest>,  // Batches waiting for their run time
+
+    // Email report
+    email_enabled: bool,               // Flag indicating the next batch emails its report when done
+    email_recipients: String,          // Recipient list as typed (comma-separated)
+    email_format: EmailFormat,         // Body format of the emailed report
+    batch_email: Option<ReportEmail>,  // Recipients of the running batch's report
+
+    // Presets
+    presets: BTreeMap<String, Preset>, // Saved presets keyed by name
+    preset_name: String,               // Name typed for saving the current configuration
+    selected_preset: Option<String>,   // The preset last loaded from the dropdown
+
+    // AI test plan
+    ai_intensity: u32,           // Intensity requested from the AI recommender (1-10)
+    ai_generating: bool,         // Flag to indicate an AI request is in flight
+    ai_plan: Vec<PlanEntry>,     // Tests proposed by the AI, editable before running
+    ai_message: Option<String>,  // Status line for the AI plan (errors, warnings)
+
+    // Appearance
+    theme_choice: ThemeChoice, // Selected theme (persisted)
+    system_dark: bool,         // Whether the desktop prefers a dark theme (detected at startup)
+
+    // Save dialog
+    show_save_dialog: bool,       // Flag to control the visibility of the save dialog
+    export_format: ExportFormat,  // File format selected in the save dialog
+    export_file: String,          // File name (without extension) inside the results directory
 }
 
 // === APPLICATION IMPLEMENTATION ===
@@ -120,31 +409,86 @@ impl Application for GuiApp {
      * Initialize the application with default settings
      */
     fn new(_flags: ()) -> (Self, Command<Self::Message>) {
-        (
+        let (mut app, command) = (
             GuiApp {
                 selected_tests: vec![],
                 server_url: String::from("http://localhost:8080"),
                 environment: Environment::Local,
-                duration: String::from("10"),
-                intensity: String::from("4"),
-                size: String::from("256"),
-                load: String::from("70.0"),
-                fork: false,
+                settings: [TestType::Cpu, TestType::Memory, TestType::Disk]
+                    .into_iter()
+                    .map(|test| (test, TestSettings::defaults_for(test)))
+                    .collect(),
                 status_message: None,
                 node_status: None,
                 show_advanced: false,
                 running_tests: false,
                 test_results: None,
+                node_results: vec![],
                 last_test_id: None,
+                nodes: vec![],
+                selected_nodes: vec![],
+                fetching_nodes: false,
+                progress: Arc::new(Mutex::new(Vec::new())),
+                progress_rows: vec![],
+                cancel: Arc::new(AtomicBool::new(false)),
+                logs: VecDeque::new(),
+                logs_since: 0.0,
+                metrics_history: vec![],
+                metrics_error: None,
+                engines: HashMap::new(),
+                engine_busy: HashSet::new(),
+                engines_message: None,
+                run_history: vec![],
+                saved_runs: vec![],
+                compare_left: None,
+                compare_right: None,
+                compare_message: None,
+                tab: Tab::Tests,
+                tasks: vec![],
+                tasks_message: None,
+                schedule_enabled: false,
+                schedule_date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+                schedule_hour: 0,
+                schedule_minute: 0,
+                scheduled: vec![],
+                email_enabled: false,
+                email_recipients: String::new(),
+                email_format: EmailFormat::default(),
+                batch_email: None,
+                presets: BTreeMap::new(),
+                preset_name: String::new(),
+                selected_preset: None,
+                ai_intensity: 5,
+                ai_generating: false,
+                ai_plan: vec![],
+                ai_message: None,
+                theme_choice: prefs::load_prefs().theme,
+                system_dark: prefs::system_prefers_dark(),
+                show_save_dialog: false,
+                export_format: ExportFormat::default(),
+                export_file: String::new(),
             },
             Command::none(),
-        )
+        );
+
+        // Load saved presets; a broken file is reported instead of discarded
+        match presets::load_presets() {
+            Ok(saved) => app.presets = saved,
+            Err(e) => app.status_message = Some(e),
+        }
+
+        (app, command)
     }
     //Set application window title
     fn title(&self) -> String {
         "Mogwai Test GUI".into()
     }
 
+    // Light or dark theme, following the desktop when "System" is selected
+    fn theme(&self) -> Theme {
+        self.theme_choice.theme(self.system_dark)
+    }
+
     /// Handle all application events and update state accordingly
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         match message {
@@ -159,11 +503,15 @@ impl Application for GuiApp {
                 }
             }
             Message::ServerUrlChanged(url) => self.server_url = url, // Update the server URL in the application state
-            Message::DurationChanged(duration) => self.duration = duration, // Update the test duration in the application state
-            Message::IntensityChanged(intensity) => self.intensity = intensity, // Update the test intensity in the application state
-            Message::SizeChanged(size) => self.size = size, // Update the test size in the application state
-            Message::LoadChanged(load) => self.load = load, // Update the CPU load percentage in the application state
-            Message::ForkToggled(fork) => self.fork = fork, // Update the fork option in the application state
+            Message::DurationChanged(test, duration) => self.settings_mut(test).duration = duration, // Update the test's duration
+            Message::IntensityChanged(test, intensity) => self.settings_mut(test).intensity = intensity, // Update the test's intensity
+            Message::SizeChanged(test, size) => self.settings_mut(test).size = size, // Update the test's size
+            Message::LoadChanged(load) => self.settings_mut(TestType::Cpu).load = load, // Update the CPU load percentage
+            Message::ForkToggled(fork) => self.settings_mut(TestType::Cpu).fork = fork, // Update the CPU fork option
+            Message::ToggleSection(test) => {
+                let settings = self.settings_mut(test);
+                settings.expanded = !settings.expanded; // Expand/collapse the test's parameter section
+            }
             Message::ToggleAdvanced => self.show_advanced = !self.show_advanced, // Toggle the visibility of advanced settings
             Message::EnvironmentSelected(env) => {
                 self.environment = env; // Update the selected environment in the application state
@@ -172,42 +520,167 @@ impl Application for GuiApp {
                     Environment::Kubernetes => "http://localhost:8081".to_string(), // Set default URL for Kubernetes environment
                     Environment::Custom => self.server_url.clone(), // Keep the existing custom URL
                 };
+                if env != Environment::Kubernetes && self.tab == Tab::Engines {
+                    self.tab = Tab::Tests; // The Engines tab only exists for the controller
+                }
+
+                // The controller needs a target node, so load the node list for Kubernetes
+                if env == Environment::Kubernetes {
+                    self.fetching_nodes = true;
+                    return fetch_nodes(self.server_url.clone());
+                }
+            }
+            Message::RefreshNodesPressed => {
+                self.fetching_nodes = true;
+                return fetch_nodes(self.server_url.clone());
+            }
+            Message::NodesFetched(result) => {
+                self.fetching_nodes = false;
+                match result {
+                    Ok(nodes) => {
+                        // Keep selected nodes that still exist, otherwise default to the first node
+                        self.selected_nodes.retain(|node| nodes.contains(node));
+                        if self.selected_nodes.is_empty() {
+                            self.selected_nodes.extend(nodes.first().cloned());
+                        }
+                        if nodes.is_empty() {
+                            self.status_message = Some("No nodes returned by the server.".to_string());
+                        }
+                        self.nodes = nodes;
+                    }
+                    Err(e) => {
+                        self.nodes.clear();
+                        self.selected_nodes.clear();
+                        self.status_message = Some(format!("Failed to fetch nodes: {}", e));
+                    }
+                }
+            }
+            Message::NodeToggled(node, checked) => {
+                // Keep the selection in node-list order so result columns are stable
+                if checked && !self.selected_nodes.contains(&node) {
+                    self.selected_nodes.push(node);
+                    let order = &self.nodes;
+                    self.selected_nodes
+                        .sort_by_key(|n| order.iter().position(|o| o == n).unwrap_or(usize::MAX));
+                } else if !checked {
+                    self.selected_nodes.retain(|n| *n != node);
+                }
+            }
+            Message::AllNodesToggled(checked) => {
+                self.selected_nodes = if checked { self.nodes.clone() } else { vec![] };
             }
+            Message::PollMetrics => {
+                return fetch_metrics(self.metrics_url());
+            }
+            Message::ProgressTick => self.progress_rows = progress::snapshot(&self.progress),
+            Message::MetricsReceived(result) => match result {
+                Ok(sample) => {
+                    charts::push_sample(&mut self.metrics_history, sample);
+                    self.metrics_error = None;
+                }
+                Err(e) => self.metrics_error = Some(e),
+            },
 
             // === TEST EXECUTION & RESULTS ===
             // Handle test completion
-            Message::TestComplete(results) => {
+            Message::TestComplete(mut node_results) => {
                 self.running_tests = false; // Reset the running tests flag
+                self.progress_rows = progress::snapshot(&self.progress); // Show the final state of every bar
+
+                // Live metrics were polled from the first target node
+                let metrics_node = node_results.first().and_then(|r| r.node.clone());
+                for result in node_results.iter_mut() {
+                    if result.node == metrics_node {
+                        result.report.host_metrics = HostSummary::from_samples(&self.metrics_history);
+                    }
+                }
+
+                let mut results = combine_node_reports(&node_results);
+                if self.cancel.load(Ordering::SeqCst) {
+                    results = format!("Batch cancelled; remaining tests were skipped.\n\n{}", results);
+                }
                 self.status_message = Some(results.clone()); // Update the status message with the test results
                 self.test_results = Some(results); // Store the test results in the application state
 
+                // Remember the batch for the Compare tab; by default it is compared with the previous one
+                let run = CompareRun::from_session(node_results.iter().map(|r| r.report.clone()).collect());
+                self.compare_left = self.run_history.first().map(|previous| previous.label.clone());
+                self.compare_right = Some(run.label.clone());
+                self.run_history.insert(0, run);
+                self.run_history.truncate(compare::MAX_HISTORY);
+                self.node_results = node_results; // Keep the per-node results for display and export
+
+                let mut commands = Vec::new();
+                // Email the report if the batch asked for it
+                if let Some(email) = self.batch_email.take() {
+                    commands.push(self.email_report(email));
+                }
                 // Fetch node status as needed
                 if let Some(test_id) = &self.last_test_id {
-                    return fetch_node_status(self.server_url.clone(), test_id.clone());
+                    commands.push(fetch_node_status(self.server_url.clone(), test_id.clone()));
                 }
+                return Command::batch(commands);
+            }
+            Message::ReportEmailed(result) => {
+                let line = result.unwrap_or_else(|e| e);
+                self.status_message = Some(format!(
+                    "{}\n\n{}",
+                    self.status_message.clone().unwrap_or_default(),
+                    line
+                ));
             }
             Message::NodeStatusReceived(status) => {
                 self.node_status = Some(status); // Update the displayed node status
             }
-            Message::LogsReceived(logs) => {
-                if let Some(existing) = &self.node_status {
-                    self.node_status = Some(format!("{}\n\nLogs:\n{}", existing, logs));
-                } else {
-                    self.node_status = Some(format!("Logs:\n{}", logs));
-                }
+            Message::LogsReceived(line) => {
+                logs::push_line(&mut self.logs, line);
+                // Follow the newest line
+                return scrollable::snap_to(log_scroll_id(), scrollable::RelativeOffset::END);
             }
+            Message::ClearLogsPressed => self.logs.clear(),
 
             // Actions
             Message::SaveResultsPressed => {
-                if let Some(results) = &self.test_results {
-                    return save_results(results.clone());
-                } // Initiate the process of saving the test results to a file
+                if self.test_results.is_none() {
+                    self.status_message = Some("No test results to save yet.".to_string());
+                    return Command::none();
+                }
+                // Open the save dialog with a timestamped default file name
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                self.export_file = format!("mogwai_results_{}", timestamp);
+                self.show_save_dialog = true;
+            }
+            Message::ExportFormatSelected(format) => self.export_format = format,
+            Message::ExportFileChanged(file) => self.export_file = file,
+            Message::CancelSavePressed => self.show_save_dialog = false,
+            Message::ConfirmSavePressed => {
+                let text = self.test_results.clone().unwrap_or_default();
+                let reports: Vec<BatchReport> =
+                    self.node_results.iter().map(|r| r.report.clone()).collect();
+                match report::render(self.export_format, &text, &reports) {
+                    Ok(content) => {
+                        self.show_save_dialog = false;
+                        // Initiate the process of saving the test results to a file
+                        return save_results(content, &self.export_file, self.export_format);
+                    }
+                    Err(e) => {
+                        self.status_message = Some(format!(
+                            "{}\n\nFailed to save results: {}",
+                            self.status_message.clone().unwrap_or_default(),
+                            e
+                        ));
+                    }
+                }
             }
             Message::ResultsSaved(result) => match result {
-                Ok(_) => {
+                Ok(path) => {
                     self.status_message = Some(format!(
-                        "{}\n\nResults successfully saved to results directory.",
-                        self.status_message.clone().unwrap_or_default()
+                        "{}\n\nResults successfully saved to {}.",
+                        self.status_message.clone().unwrap_or_default(),
+                        path
                     ));// Update the status message on successful saving of results
                 }
                 Err(e) => {
@@ -218,13 +691,115 @@ impl Application for GuiApp {
                     )); // Update status on save failure
                 }
             },
-            Message::TasksListed(results) => {
-                self.status_message = Some(results);
-            } // Update status with the list of tasks
+            Message::TasksListed(result) => match result {
+                Ok(tasks) => self.tasks = tasks, // Replace the task rows with the fresh list
+                Err(e) => self.tasks_message = Some(e),
+            },
 
             Message::ListTasksPressed => {
-                self.status_message = Some("Fetching running tasks...".to_string());
-                return list_tasks(self.server_url.clone());
+                self.tab = Tab::Tasks;
+                return fetch_tasks(self.server_url.clone(), self.target_node());
+            }
+            Message::TabSelected(tab) => {
+                self.tab = tab;
+                if tab == Tab::Tasks {
+                    return fetch_tasks(self.server_url.clone(), self.target_node());
+                }
+                if tab == Tab::Compare {
+                    return load_saved_runs();
+                }
+                if tab == Tab::Engines {
+                    // The node list and the engine pods are both needed for the panel
+                    self.fetching_nodes = true;
+                    return Command::batch([
+                        fetch_nodes(self.server_url.clone()),
+                        fetch_engines(self.server_url.clone()),
+                    ]);
+                }
+            }
+            Message::RefreshSavedRuns => {
+                return load_saved_runs();
+            }
+            Message::SavedRunsLoaded(result) => match result {
+                Ok(runs) => {
+                    self.saved_runs = runs;
+                    self.compare_message = None;
+                }
+                Err(e) => self.compare_message = Some(e),
+            },
+            Message::CompareLeftSelected(label) => self.compare_left = Some(label),
+            Message::CompareRightSelected(label) => self.compare_right = Some(label),
+            Message::SwapComparePressed => {
+                std::mem::swap(&mut self.compare_left, &mut self.compare_right);
+            }
+            Message::RefreshEngines => {
+                return fetch_engines(self.server_url.clone());
+            }
+            Message::EnginesFetched(result) => match result {
+                Ok(engines) => {
+                    self.engines = engines
+                        .into_iter()
+                        .map(|engine| (engine.node.clone(), engine))
+                        .collect();
+                }
+                Err(e) => self.engines_message = Some(format!("Failed to list engines: {}", e)),
+            },
+            Message::SpawnEnginePressed(node) => {
+                self.engine_busy.insert(node.clone());
+                self.engines_message = Some(format!("Spawning engine on {}...", node));
+                return engine_action(self.server_url.clone(), "spawn-engine", node);
+            }
+            Message::RemoveEnginePressed(node) => {
+                self.engine_busy.insert(node.clone());
+                self.engines_message = Some(format!("Removing engine from {}...", node));
+                return engine_action(self.server_url.clone(), "remove-engine", node);
+            }
+            Message::EngineActionCompleted(node, result) => {
+                self.engine_busy.remove(&node);
+                self.engines_message = Some(match result {
+                    Ok(response) => format!("{}: {}", node, response),
+                    Err(e) => format!("{}: {}", node, e),
+                });
+                // Refresh right away so the new state shows up
+                return fetch_engines(self.server_url.clone());
+            }
+            Message::RefreshTasks => {
+                return fetch_tasks(self.server_url.clone(), self.target_node());
+            }
+            Message::StopTaskPressed(id) => {
+                self.tasks_message = Some(format!("Stopping task {}...", id));
+                return stop_task(self.server_url.clone(), self.target_node(), id);
+            }
+            Message::StopAllPressed => {
+                self.tasks_message = Some("Stopping all tasks...".to_string());
+                return stop_all_tasks(self.server_url.clone());
+            }
+            Message::StopCompleted(result) => {
+                self.tasks_message = Some(match result {
+                    Ok(response) => response,
+                    Err(e) => format!("Stop failed: {}", e),
+                });
+                // Refresh right away so stopped tasks disappear
+                return fetch_tasks(self.server_url.clone(), self.target_node());
+            }
+            Message::CancelPressed => {
+                if !self.running_tests {
+                    return Command::none();
+                }
+                // The batch stops waiting and skips the remaining tests once it sees the flag
+                self.cancel.store(true, Ordering::SeqCst);
+                self.status_message = Some("Cancelling: stopping dispatched tests...".to_string());
+
+                // Stop the tests already running on the server
+                let stops: Vec<Command<Message>> = progress::snapshot(&self.progress)
+                    .into_iter()
+                    .filter(|entry| entry.state == ProgressState::Running)
+                    .filter_map(|entry| {
+                        let task_id = entry.task_id?;
+                        Some(stop_task(self.server_url.clone(), entry.node, task_id))
+                    })
+                    .collect();
+                return Command::batch(stops);
             }
             Message::RunPressed => {
                 // Validation
@@ -232,36 +807,263 @@ impl Application for GuiApp {
                     self.status_message = Some("No tests selected.".to_string());
                     return Command::none();
                 }
+                if !self.inputs_valid() {
+                    self.status_message = Some("Some test parameters are invalid.".to_string());
+                    return Command::none();
+                }
 
-                if self.fork && !self.selected_tests.contains(&TestType::Cpu) {
-                    self.status_message =
-                        Some("Fork option requires CPU test to be selected.".to_string());
+                if self.environment == Environment::Kubernetes && self.selected_nodes.is_empty() {
+                    self.status_message = Some(
+                        "No node selected. Refresh the node list and pick at least one target node."
+                            .to_string(),
+                    );
                     return Command::none();
                 }
 
-                // Update state
-                self.running_tests = true;
-                self.status_message = Some("Running tests...".to_string());
+                let tests: Vec<(TestType, TestSettings)> = self
+                    .selected_tests
+                    .iter()
+                    .map(|&test| (test, self.settings_for(test)))
+                    .collect();
+                let email = match self.report_email() {
+                    Ok(email) => email,
+                    Err(e) => {
+                        self.status_message = Some(e);
+                        return Command::none();
+                    }
+                };
 
-                // Generate batch ID
-                let batch_id = Uuid::new_v4().to_string();
-                self.last_test_id = Some(batch_id.clone());
+                // Schedule for later instead of running now
+                if self.schedule_enabled {
+                    let now = chrono::Local::now();
+                    match schedule::parse_run_time(
+                        &self.schedule_date,
+                        self.schedule_hour,
+                        self.schedule_minute,
+                        now,
+                    ) {
+                        Ok(run_at) => {
+                            self.scheduled.push(ScheduledTest {
+                                id: Uuid::new_v4().to_string(),
+                                run_at,
+                                tests,
+                                server_url: self.server_url.clone(),
+                                nodes: self.target_nodes(),
+                                email,
+                            });
+                            self.scheduled.sort_by_key(|s| s.run_at);
+                            self.status_message = Some(format!(
+                                "Tests scheduled for {} ({}). Keep the GUI open until they start.",
+                                run_at.format("%Y-%m-%d %H:%M"),
+                                schedule::time_until(run_at, now)
+                            ));
+                        }
+                        Err(e) => self.status_message = Some(e),
+                    }
+                    return Command::none();
+                }
 
-                // Run tests
+                if self.running_tests {
+                    return Command::none();
+                }
+                return self.start_tests(tests, self.server_url.clone(), self.target_nodes(), email);
+            }
+            Message::ScheduleToggled(enabled) => {
+                self.schedule_enabled = enabled;
+                // Default to the next 5-minute slot at least 10 minutes out
+                if enabled {
+                    let (date, hour, minute) = schedule::picker_values_in(10, chrono::Local::now());
+                    self.schedule_date = date;
+                    self.schedule_hour = hour;
+                    self.schedule_minute = minute;
+                }
+            }
+            Message::ScheduleDateChanged(date) => self.schedule_date = date,
+            Message::ScheduleHourSelected(hour) => self.schedule_hour = hour,
+            Message::ScheduleMinuteSelected(minute) => self.schedule_minute = minute,
+            Message::ScheduleInPressed(minutes) => {
+                let (date, hour, minute) = schedule::picker_values_in(minutes, chrono::Local::now());
+                self.schedule_date = date;
+                self.schedule_hour = hour;
+                self.schedule_minute = minute;
+            }
+            Message::ThemeSelected(choice) => {
+                self.theme_choice = choice;
+                let saved = prefs::save_prefs(&prefs::Prefs { theme: choice });
+                if let Err(e) = saved {
+                    self.status_message = Some(format!("Failed to save theme: {}", e));
+                }
+            }
+            Message::PresetNameChanged(name) => self.preset_name = name,
+            Message::SavePresetPressed => {
+                let name = self.preset_name.trim().to_string();
+                if name.is_empty() {
+                    self.status_message = Some("Enter a preset name first.".to_string());
+                    return Command::none();
+                }
+                let preset = Preset {
+                    name: name.clone(),
+                    selected_tests: self.selected_tests.clone(),
+                    settings: self.settings.clone(),
+                    environment: self.environment,
+                    server_url: self.server_url.clone(),
+                    nodes: self.selected_nodes.clone(),
+                };
+                match presets::save_preset(preset) {
+                    Ok(saved) => {
+                        self.presets = saved;
+                        self.selected_preset = Some(name.clone());
+                        self.status_message = Some(format!(
+                            "Preset '{}' saved to {}",
+                            name,
+                            presets::presets_path().display()
+                        ));
+                    }
+                    Err(e) => self.status_message = Some(format!("Failed to save preset: {}", e)),
+                }
+            }
+            Message::PresetSelected(name) => {
+                let Some(preset) = self.presets.get(&name).cloned() else {
+                    return Command::none();
+                };
+                self.selected_tests = preset.selected_tests;
+                for (test, settings) in preset.settings {
+                    self.settings.insert(test, settings);
+                }
+                self.environment = preset.environment;
+                self.server_url = preset.server_url;
+                self.selected_nodes = preset.nodes;
+                self.preset_name = name.clone();
+                self.selected_preset = Some(name.clone());
+                self.status_message = Some(format!("Preset '{}' loaded.", name));
+
+                // Refresh the node list; the preset's nodes stay selected if they still exist
+                if self.environment == Environment::Kubernetes {
+                    self.fetching_nodes = true;
+                    return fetch_nodes(self.server_url.clone());
+                }
+            }
+            Message::DeletePresetPressed => {
+                if let Some(name) = self.selected_preset.take() {
+                    match presets::delete_preset(&name) {
+                        Ok(saved) => {
+                            self.presets = saved;
+                            self.status_message = Some(format!("Preset '{}' deleted.", name));
+                        }
+                        Err(e) => self.status_message = Some(format!("Failed to delete preset: {}", e)),
+                    }
+                }
+            }
+            Message::AiIntensityChanged(intensity) => self.ai_intensity = intensity,
+            Message::AiPlanPressed => {
+                self.ai_generating = true;
+                self.ai_message = Some(format!(
+                    "Asking the AI for a test plan (intensity {})...",
+                    self.ai_intensity
+                ));
+                let intensity = self.ai_intensity;
+                let (server_url, node) = (self.server_url.clone(), self.target_node());
                 return Command::perform(
-                    execute_tests(
-                        self.selected_tests.clone(),
-                        self.server_url.clone(),
-                        batch_id,
-                        self.duration.clone(),
-                        self.intensity.clone(),
-                        self.size.clone(),
-                        self.load.clone(),
-                        self.fork,
-                    ),
-                    Message::TestComplete,  // Send Message::TestComplete when the async operation finishes
+                    async move {
+                        // Plan for the target node's hardware; fall back to this machine for engines without /sysinfo
+                        let system_info = api::fetch_system_info(&server_url, node.as_deref())
+                            .await
+                            .unwrap_or_else(|_| get_system_info());
+                        ai::generate_plan(intensity, system_info).await
+                    },
+                    Message::AiPlanReceived,
                 );
             }
+            Message::AiPlanReceived(result) => {
+                self.ai_generating = false;
+                match result {
+                    Ok((tests, mut warnings)) => {
+                        self.ai_plan = tests
+                            .iter()
+                            .filter_map(|test| {
+                                let entry = PlanEntry::from_ai(test);
+                                if entry.is_none() {
+                                    warnings.push(format!("Skipped unknown test type '{}'", test.test_type));
+                                }
+                                entry
+                            })
+                            .collect();
+                        self.ai_message = (!warnings.is_empty()).then(|| warnings.join("\n"));
+                    }
+                    Err(e) => self.ai_message = Some(format!("AI plan failed: {}", e)),
+                }
+            }
+            Message::PlanEntryToggled(index, enabled) => {
+                if let Some(entry) = self.ai_plan.get_mut(index) {
+                    entry.enabled = enabled;
+                }
+            }
+            Message::PlanFieldChanged(index, field, value) => {
+                if let Some(entry) = self.ai_plan.get_mut(index) {
+                    match field {
+                        PlanField::Duration => entry.settings.duration = value,
+                        PlanField::Intensity => entry.settings.intensity = value,
+                        PlanField::Size => entry.settings.size = value,
+                        PlanField::Load => entry.settings.load = value,
+                    }
+                }
+            }
+            Message::PlanForkToggled(index, fork) => {
+                if let Some(entry) = self.ai_plan.get_mut(index) {
+                    entry.settings.fork = fork;
+                }
+            }
+            Message::DiscardPlanPressed => {
+                self.ai_plan.clear();
+                self.ai_message = None;
+            }
+            Message::RunPlanPressed => {
+                let tests: Vec<(TestType, TestSettings)> = self
+                    .ai_plan
+                    .iter()
+                    .filter(|entry| entry.enabled)
+                    .map(|entry| (entry.test, entry.settings.clone()))
+                    .collect();
+                if tests.is_empty() {
+                    self.ai_message = Some("No plan entries selected.".to_string());
+                    return Command::none();
+                }
+                if self.environment == Environment::Kubernetes && self.selected_nodes.is_empty() {
+                    self.ai_message = Some(
+                        "No node selected. Refresh the node list and pick at least one target node."
+                            .to_string(),
+                    );
+                    return Command::none();
+                }
+                let email = match self.report_email() {
+                    Ok(email) => email,
+                    Err(e) => {
+                        self.ai_message = Some(e);
+                        return Command::none();
+                    }
+                };
+                if self.running_tests {
+                    return Command::none();
+                }
+                return self.start_tests(tests, self.server_url.clone(), self.target_nodes(), email);
+            }
+            Message::CancelScheduled(id) => {
+                self.scheduled.retain(|s| s.id != id);
+            }
+            Message::SchedulerTick => {
+                // Start the earliest due batch once the previous run has finished
+                if self.running_tests {
+                    return Command::none();
+                }
+                let now = chrono::Local::now();
+                if let Some(index) = self.scheduled.iter().position(|s| s.is_due(now)) {
+                    let batch = self.scheduled.remove(index);
+                    return self.start_tests(batch.tests, batch.server_url, batch.nodes, batch.email);
+                }
+            }
+            Message::EmailToggled(enabled) => self.email_enabled = enabled,
+            Message::EmailRecipientsChanged(recipients) => self.email_recipients = recipients,
+            Message::EmailFormatSelected(format) => self.email_format = format,
         }
         Command::none() // Default case: no command to execute
     }
@@ -273,12 +1075,12 @@ impl Application for GuiApp {
             .push(
                 Text::new("Mogwai Stress Tool")
                     .size(32)
-                    .style(Color::from_rgb(0.3, 0.4, 0.5)),
+                    .style(self.heading_color()),
             )
             .push(
                 Text::new("Performance Test Utility")
                     .size(18)
-                    .style(Color::from_rgb(0.5, 0.5, 0.5)),
+                    .style(self.muted_color()),
             )
             .spacing(5)
             .width(Length::Fill)
@@ -300,6 +1102,20 @@ impl Application for GuiApp {
         // Advanced settings section (collapsible)
         let advanced_section = if self.show_advanced {
             Column::new()
+                .push(
+                    Row::new()
+                        .push(Text::new("Theme:").width(Length::FillPortion(1)))
+                        .push(
+                            PickList::new(
+                                &ThemeChoice::ALL[..],
+                                Some(self.theme_choice),
+                                Message::ThemeSelected,
+                            )
+                            .width(Length::FillPortion(2)),
+                        )
+                        .spacing(10)
+                        .align_items(Alignment::Center),
+                )
                 .push(
                     Row::new()
                         .push(Text::new("Environment:").width(Length::FillPortion(1)))
@@ -323,6 +1139,7 @@ impl Application for GuiApp {
                         .on_input(Message::ServerUrlChanged)
                         .padding(10),
                 )
+                .push(self.node_picker())
                 .spacing(10)
                 .width(Length::Fill)
         } else {
@@ -363,58 +1180,15 @@ impl Application for GuiApp {
             .spacing(10)
             .width(Length::Fill);
 
-        // Parameter inputs
+        // Parameter inputs - one collapsible section per selected test
         let params_title = Text::new("Test Parameters:").size(18);
 
-        let row1 = Row::new()
-            .push(
-                Container::new(
-                    TextInput::new("Duration (seconds)", &self.duration)
-                        .on_input(Message::DurationChanged)
-                        .padding(8),
-                )
-                .width(Length::Fill),
-            )
-            .push(
-                Container::new(
-                    TextInput::new("Intensity (threads)", &self.intensity)
-                        .on_input(Message::IntensityChanged)
-                        .padding(8),
-                )
-                .width(Length::Fill),
-            )
-            .spacing(10)
-            .width(Length::Fill);
-
-        let row2 = Row::new()
-            .push(
-                Container::new(
-                    TextInput::new("Size (MB)", &self.size)
-                        .on_input(Message::SizeChanged)
-                        .padding(8),
-                )
-                .width(Length::Fill),
-            )
-            .push(
-                Container::new(
-                    TextInput::new("CPU Load (%)", &self.load)
-                        .on_input(Message::LoadChanged)
-                        .padding(8),
-                )
-                .width(Length::Fill),
-            )
-            .spacing(10)
-            .width(Length::Fill);
-
-        // CPU options
-        let fork_section = Column::new()
-            .push(Text::new("CPU Test Options:").size(18))
-            .push(
-                Container::new(Checkbox::new("Fork Test", self.fork, Message::ForkToggled))
-                    .padding(5),
-            )
-            .spacing(5)
-            .width(Length::Fill);
+        let params_sections = self
+            .selected_tests
+            .iter()
+            .fold(Column::new().spacing(10), |column, &test| {
+                column.push(self.params_section(test))
+            });
 
         // Parameter help text
         let helper_text = Container::new(
@@ -422,8 +1196,11 @@ impl Application for GuiApp {
                 .push(
                     Text::new("Test Parameter Information:")
                         .size(16)
-                        .style(Color::from_rgb(0.3, 0.4, 0.5)),
+                        .style(self.heading_color()),
                 )
+                .push(Text::new(
+                    "Each selected test has its own parameters; click a section header to collapse it.",
+                ))
                 .push(Text::new(
                     "• CPU Test: Uses intensity (threads), duration, and load percentage",
                 ))
@@ -442,8 +1219,9 @@ impl Application for GuiApp {
         .padding(10)
         .width(Length::Fill);
 
-        // Action buttons
-        let run_button = if self.running_tests {
+        // Action buttons (scheduling stays available while a run is in progress)
+        let inputs_valid = self.inputs_valid();
+        let run_button = if self.running_tests && !self.schedule_enabled {
             Button::new(
                 Text::new("RUNNING...")
                     .size(18)
@@ -453,15 +1231,20 @@ impl Application for GuiApp {
             .style(iced::theme::Button::Secondary)
             .width(Length::Fill)
         } else {
-            Button::new(
-                Text::new("RUN TESTS")
+            let button = Button::new(
+                Text::new(if self.schedule_enabled { "SCHEDULE TESTS" } else { "RUN TESTS" })
                     .size(18)
                     .horizontal_alignment(alignment::Horizontal::Center),
             )
-            .on_press(Message::RunPressed)
             .padding([12, 30])
             .style(iced::theme::Button::Primary)
-            .width(Length::Fill)
+            .width(Length::Fill);
+            // Disabled until every selected test has valid parameters
+            if inputs_valid {
+                button.on_press(Message::RunPressed)
+            } else {
+                button
+            }
         };
 
         let list_tasks_button = Button::new(
@@ -485,9 +1268,26 @@ impl Application for GuiApp {
         .width(Length::Fill);
 
         // Button layouts
-        let primary_button_row = Row::new()
+        let mut primary_button_row = Row::new()
             .push(Container::new(run_button).width(Length::FillPortion(2)))
-            .push(Space::with_width(Length::Fixed(10.0)))
+            .push(Space::with_width(Length::Fixed(10.0)));
+        if self.running_tests {
+            primary_button_row = primary_button_row.push(
+                Container::new(
+                    Button::new(
+                        Text::new("CANCEL")
+                            .size(16)
+                            .horizontal_alignment(alignment::Horizontal::Center),
+                    )
+                    .on_press(Message::CancelPressed)
+                    .padding([8, 20])
+                    .style(iced::theme::Button::Destructive)
+                    .width(Length::Fill),
+                )
+                .width(Length::FillPortion(1)),
+            );
+        }
+        let primary_button_row = primary_button_row
             .push(Container::new(list_tasks_button).width(Length::FillPortion(1)))
             .spacing(10)
             .width(Length::Fixed(450.0));
@@ -497,70 +1297,1306 @@ impl Application for GuiApp {
             .spacing(10)
             .width(Length::Fixed(450.0));
 
-        // Results display
-        let test_results_view = Container::new(
-            Column::new()
-                .push(
-                    Text::new("Test Results:")
-                        .size(18)
-                        .style(Color::from_rgb(0.3, 0.4, 0.5)),
-                )
-                .push(
-                    Container::new(
-                        Scrollable::new(
-                            Text::new(
-                                self.status_message
-                                    .clone()
-                                    .unwrap_or_else(|| "No test results yet.".to_string()),
+        // Save dialog (format + file name), shown after SAVE RESULTS is pressed
+        let save_dialog: Element<'_, Message> = if self.show_save_dialog {
+            Container::new(
+                Column::new()
+                    .push(Text::new("Save Results").size(16).style(self.heading_color()))
+                    .push(
+                        Row::new()
+                            .push(Text::new("Format:").width(Length::FillPortion(1)))
+                            .push(
+                                PickList::new(
+                                    &ExportFormat::ALL[..],
+                                    Some(self.export_format),
+                                    Message::ExportFormatSelected,
+                                )
+                                .width(Length::FillPortion(3)),
                             )
-                            .size(14),
-                        )
-                        .height(Length::Fixed(400.0)),
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                    )
+                    .push(
+                        Row::new()
+                            .push(Text::new("File name:").width(Length::FillPortion(1)))
+                            .push(
+                                TextInput::new("mogwai_results", &self.export_file)
+                                    .on_input(Message::ExportFileChanged)
+                                    .on_submit(Message::ConfirmSavePressed)
+                                    .padding(6)
+                                    .width(Length::FillPortion(3)),
+                            )
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                    )
+                    .push(
+                        Text::new(format!(
+                            "Saved as results/{}.{}",
+                            self.export_file.trim(),
+                            self.export_format.extension()
+                        ))
+                        .size(12)
+                        .style(self.muted_color()),
+                    )
+                    .push(
+                        Row::new()
+                            .push(Space::with_width(Length::Fill))
+                            .push(
+                                Button::new(Text::new("Cancel").size(14))
+                                    .on_press(Message::CancelSavePressed)
+                                    .padding([6, 14])
+                                    .style(iced::theme::Button::Secondary),
+                            )
+                            .push(
+                                Button::new(Text::new("Save").size(14))
+                                    .on_press(Message::ConfirmSavePressed)
+                                    .padding([6, 14])
+                                    .style(iced::theme::Button::Primary),
+                            )
+                            .spacing(10),
+                    )
+                    .spacing(10),
+            )
+            .style(iced::theme::Container::Box)
+            .padding(12)
+            .width(Length::Fixed(450.0))
+            .into()
+        } else {
+            Column::new().into()
+        };
+
+        // Results display
+        let results_pane = |title: Option<String>, text: String| {
+            let mut pane = Column::new().spacing(6);
+            if let Some(title) = title {
+                pane = pane.push(Text::new(title).size(16).style(self.heading_color()));
+            }
+            Container::new(
+                pane.push(Scrollable::new(Text::new(text).size(14)).height(Length::Fixed(400.0))),
+            )
+            .style(iced::theme::Container::Box)
+            .padding(10)
+            .width(Length::Fill)
+        };
+
+        // One result column per node after a multi-node run, otherwise the status text
+        let results_body: Element<'_, Message> = if self.node_results.len() > 1 && !self.running_tests {
+            self.node_results
+                .iter()
+                .fold(Row::new().spacing(10), |row, result| {
+                    row.push(results_pane(
+                        Some(format!("Node: {}", result.node.as_deref().unwrap_or("-"))),
+                        result.text.clone(),
+                    ))
+                })
+                .into()
+        } else {
+            results_pane(
+                None,
+                self.status_message
+                    .clone()
+                    .unwrap_or_else(|| "No test results yet.".to_string()),
+            )
+            .into()
+        };
+
+        let test_results_view = Container::new(
+            Column::new()
+                .push(
+                    Text::new("Test Results:")
+                        .size(18)
+                        .style(self.heading_color()),
+                )
+                .push(results_body)
+                .spacing(10),
+        )
+        .width(Length::Fill);
+
+        // Tests tab layout
+        let tests_tab = Column::new()
+            .push(self.preset_controls())
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(advanced_toggle)
+            .push(advanced_section)
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(checkboxes)
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(params_title)
+            .push(params_sections)
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(helper_text)
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(self.schedule_controls())
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(self.email_controls())
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(self.ai_plan_view())
+            .push(Space::with_height(Length::Fixed(15.0)))
+            .push(Container::new(primary_button_row).center_x())
+            .push(if inputs_valid {
+                Container::new(Column::new())
+            } else {
+                Container::new(
+                    Text::new("Fix the highlighted test parameters to enable RUN TESTS.")
+                        .size(13)
+                        .style(self.error_color()),
+                )
+                .width(Length::Fill)
+                .center_x()
+            })
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(Container::new(secondary_button_row).center_x())
+            .push(Container::new(save_dialog).width(Length::Fill).center_x())
+            .push(Space::with_height(Length::Fixed(15.0)))
+            .push(self.progress_view())
+            .push(self.metrics_view())
+            .push(self.log_view())
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(test_results_view)
+            .spacing(8)
+            .width(Length::Fill);
+
+        let body: Element<'_, Message> = match self.tab {
+            Tab::Tests => tests_tab.into(),
+            Tab::Tasks => self.tasks_view(),
+            Tab::Scheduled => self.scheduled_view(),
+            Tab::Engines => self.engines_view(),
+            Tab::Compare => self.compare_view(),
+        };
+
+        // Main layout
+        let content = Column::new()
+            .push(header)
+            .push(self.tab_bar())
+            .push(separator)
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(body)
+            .spacing(8)
+            .width(Length::Fill);
+
+        Container::new(Scrollable::new(content))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .padding(30)
+            .into()
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
+        let mut subscriptions = Vec::new();
+
+        // Poll metrics and refresh the progress bars once a second while tests are running
+        if self.running_tests {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::PollMetrics),
+            );
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::ProgressTick),
+            );
+
+            // Stream live task output from every target
+            let targets = self.target_nodes();
+            if targets.is_empty() {
+                subscriptions.push(logs::subscription(
+                    format!("{}/logs/stream", self.server_url),
+                    None,
+                    self.logs_since,
+                    Message::LogsReceived,
+                ));
+            }
+            for node in targets {
+                subscriptions.push(logs::subscription(
+                    format!("{}/logs/{}/stream", self.server_url, node),
+                    Some(node),
+                    self.logs_since,
+                    Message::LogsReceived,
+                ));
+            }
+        }
+
+        // Check for due scheduled batches (also keeps the countdowns current)
+        if !self.scheduled.is_empty() {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::SchedulerTick),
+            );
+        }
+
+        // Track engine readiness while the Engines tab is open
+        if self.tab == Tab::Engines {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_secs(3)).map(|_| Message::RefreshEngines),
+            );
+        }
+
+        // Keep the task list fresh while the Tasks tab is open
+        if self.tab == Tab::Tasks {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_secs(3)).map(|_| Message::RefreshTasks),
+            );
+        }
+
+        iced::Subscription::batch(subscriptions)
+    }
+}
+
+impl GuiApp {
+    /// Text colors derived from the active theme so they stay readable in dark mode
+    fn heading_color(&self) -> Color {
+        self.theme().palette().primary
+    }
+
+    fn muted_color(&self) -> Color {
+        Color {
+            a: 0.6,
+            ..self.theme().palette().text
+        }
+    }
+
+    fn error_color(&self) -> Color {
+        self.theme().palette().danger
+    }
+
+    /// Start executing a batch of tests on every target node concurrently
+    fn start_tests(
+        &mut self,
+        tests: Vec<(TestType, TestSettings)>,
+        server_url: String,
+        nodes: Vec<String>,
+        email: Option<ReportEmail>,
+    ) -> Command<Message> {
+        // Update state
+        self.running_tests = true;
+        self.batch_email = email;
+        self.status_message = Some("Running tests...".to_string());
+        self.metrics_history.clear();
+        self.metrics_error = None;
+
+        // Generate batch ID
+        let batch_id = Uuid::new_v4().to_string();
+        self.last_test_id = Some(batch_id.clone());
+
+        // Only show log lines produced from now on
+        self.logs_since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        // Fresh progress board and cancel flag for the batch
+        self.progress = Arc::new(Mutex::new(Vec::new()));
+        self.progress_rows.clear();
+        self.cancel = Arc::new(AtomicBool::new(false));
+
+        // Run tests
+        let batch = Batch {
+            progress: self.progress.clone(),
+            cancel: self.cancel.clone(),
+        };
+        Command::perform(
+            execute_on_nodes(tests, server_url, batch_id, nodes, batch),
+            Message::TestComplete, // Send Message::TestComplete when the async operation finishes
+        )
+    }
+
+    /// Preset controls: load/delete from a dropdown, save the current configuration by name
+    fn preset_controls(&self) -> Element<'_, Message> {
+        let names: Vec<String> = self.presets.keys().cloned().collect();
+
+        let mut delete_button = Button::new(Text::new("Delete").size(14))
+            .padding([6, 12])
+            .style(iced::theme::Button::Destructive);
+        if self.selected_preset.is_some() {
+            delete_button = delete_button.on_press(Message::DeletePresetPressed);
+        }
+
+        Column::new()
+            .push(Text::new("Presets:").size(18))
+            .push(
+                Row::new()
+                    .push(
+                        PickList::new(names, self.selected_preset.clone(), Message::PresetSelected)
+                            .placeholder("Load a preset")
+                            .width(Length::FillPortion(2)),
+                    )
+                    .push(delete_button)
+                    .push(
+                        TextInput::new("Preset name", &self.preset_name)
+                            .on_input(Message::PresetNameChanged)
+                            .on_submit(Message::SavePresetPressed)
+                            .padding(6)
+                            .width(Length::FillPortion(2)),
+                    )
+                    .push(
+                        Button::new(Text::new("Save Preset").size(14))
+                            .on_press(Message::SavePresetPressed)
+                            .padding([6, 12])
+                            .style(iced::theme::Button::Secondary),
+                    )
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+            )
+            .spacing(8)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// AI test plan: intensity slider, generate button and the editable proposed tests
+    fn ai_plan_view(&self) -> Element<'_, Message> {
+        let mut generate_button = Button::new(
+            Text::new(if self.ai_generating { "Generating..." } else { "AI Plan" }).size(14),
+        )
+        .padding([6, 12])
+        .style(iced::theme::Button::Secondary);
+        if !self.ai_generating {
+            generate_button = generate_button.on_press(Message::AiPlanPressed);
+        }
+
+        let mut section = Column::new()
+            .push(Text::new("AI Test Plan:").size(18))
+            .push(
+                Row::new()
+                    .push(Text::new(format!("Intensity: {}", self.ai_intensity)).width(Length::Fixed(100.0)))
+                    .push(
+                        Slider::new(1..=10, self.ai_intensity, Message::AiIntensityChanged)
+                            .width(Length::Fill),
+                    )
+                    .push(generate_button)
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+            )
+            .spacing(8)
+            .width(Length::Fill);
+
+        if let Some(message) = &self.ai_message {
+            section = section.push(Text::new(message.clone()).size(14).style(self.muted_color()));
+        }
+
+        if self.ai_plan.is_empty() {
+            return section.into();
+        }
+
+        for (index, entry) in self.ai_plan.iter().enumerate() {
+            let settings = &entry.settings;
+            let errors = settings.validate(entry.test);
+            let input = |placeholder: &str, value: &str, field: PlanField, error: &Option<String>| {
+                self.validated_field(
+                    TextInput::new(placeholder, value)
+                        .on_input(move |value| Message::PlanFieldChanged(index, field, value))
+                        .padding(6),
+                    error,
+                )
+            };
+
+            let mut row = Row::new()
+                .push(
+                    Checkbox::new(
+                        format!("{} Test", get_test_name(&entry.test)),
+                        entry.enabled,
+                        move |checked| Message::PlanEntryToggled(index, checked),
+                    )
+                    .width(Length::Fixed(130.0)),
+                )
+                .push(input("Duration (seconds)", &settings.duration, PlanField::Duration, &errors.duration))
+                .push(input("Intensity (threads)", &settings.intensity, PlanField::Intensity, &errors.intensity))
+                .spacing(10)
+                .align_items(Alignment::Center);
+            row = match entry.test {
+                TestType::Cpu => row
+                    .push(input("CPU Load (%)", &settings.load, PlanField::Load, &errors.load))
+                    .push(Checkbox::new("Fork", settings.fork, move |fork| {
+                        Message::PlanForkToggled(index, fork)
+                    })),
+                TestType::Memory | TestType::Disk => {
+                    row.push(input("Size (MB)", &settings.size, PlanField::Size, &errors.size))
+                }
+            };
+
+            let mut entry_view = Column::new().push(row).spacing(4);
+            if !entry.rationale.is_empty() {
+                entry_view = entry_view.push(
+                    Text::new(entry.rationale.clone())
+                        .size(13)
+                        .style(self.muted_color()),
+                );
+            }
+            section = section.push(
+                Container::new(entry_view)
+                    .style(iced::theme::Container::Box)
+                    .padding(8)
+                    .width(Length::Fill),
+            );
+        }
+
+        let plan_valid = self
+            .ai_plan
+            .iter()
+            .filter(|entry| entry.enabled)
+            .all(|entry| entry.settings.validate(entry.test).is_valid());
+        let mut run_button = Button::new(Text::new("Run Plan").size(14))
+            .padding([6, 12])
+            .style(iced::theme::Button::Primary);
+        if !self.running_tests && plan_valid {
+            run_button = run_button.on_press(Message::RunPlanPressed);
+        }
+        section
+            .push(
+                Row::new()
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        Button::new(Text::new("Discard").size(14))
+                            .on_press(Message::DiscardPlanPressed)
+                            .padding([6, 12])
+                            .style(iced::theme::Button::Secondary),
+                    )
+                    .push(run_button)
+                    .spacing(10),
+            )
+            .into()
+    }
+
+    /// "Schedule for later" controls shown above the run button
+    fn schedule_controls(&self) -> Element<'_, Message> {
+        let mut section = Column::new()
+            .push(Checkbox::new(
+                "Schedule for later",
+                self.schedule_enabled,
+                Message::ScheduleToggled,
+            ))
+            .spacing(8);
+
+        if self.schedule_enabled {
+            section = section
+                .push(
+                    Row::new()
+                        .push(
+                            TextInput::new("Date (YYYY-MM-DD)", &self.schedule_date)
+                                .on_input(Message::ScheduleDateChanged)
+                                .padding(8)
+                                .width(Length::FillPortion(2)),
+                        )
+                        .push(
+                            PickList::new(
+                                &schedule::HOURS[..],
+                                Some(self.schedule_hour),
+                                Message::ScheduleHourSelected,
+                            )
+                            .width(Length::FillPortion(1)),
+                        )
+                        .push(Text::new(":"))
+                        .push(
+                            PickList::new(
+                                &schedule::MINUTES[..],
+                                Some(self.schedule_minute),
+                                Message::ScheduleMinuteSelected,
+                            )
+                            .width(Length::FillPortion(1)),
+                        )
+                        .spacing(8)
+                        .align_items(Alignment::Center),
+                )
+                .push(
+                    [15, 60, 240, 1440].iter().fold(
+                        Row::new()
+                            .push(Text::new("Quick:").size(14))
+                            .spacing(8)
+                            .align_items(Alignment::Center),
+                        |row, &minutes| {
+                            let label = match minutes {
+                                m if m < 60 => format!("+{} min", m),
+                                m if m < 1440 => format!("+{} h", m / 60),
+                                _ => "Tomorrow".to_string(),
+                            };
+                            row.push(
+                                Button::new(Text::new(label).size(14))
+                                    .on_press(Message::ScheduleInPressed(minutes))
+                                    .padding([4, 10])
+                                    .style(iced::theme::Button::Secondary),
+                            )
+                        },
+                    ),
+                )
+                .push(
+                    Text::new("Times are local. Scheduled tests start only while the GUI is running.")
+                        .size(12)
+                        .style(self.muted_color()),
+                );
+        }
+
+        section.width(Length::Fill).into()
+    }
+
+    /// "Email report when done" controls shown above the run button
+    fn email_controls(&self) -> Element<'_, Message> {
+        let mut section = Column::new()
+            .push(Checkbox::new(
+                "Email report when done",
+                self.email_enabled,
+                Message::EmailToggled,
+            ))
+            .spacing(8);
+
+        if self.email_enabled {
+            section = section
+                .push(
+                    Row::new()
+                        .push(
+                            TextInput::new("Recipients (comma-separated)", &self.email_recipients)
+                                .on_input(Message::EmailRecipientsChanged)
+                                .padding(8)
+                                .width(Length::FillPortion(3)),
+                        )
+                        .push(
+                            PickList::new(
+                                &EmailFormat::ALL[..],
+                                Some(self.email_format),
+                                Message::EmailFormatSelected,
+                            )
+                            .width(Length::FillPortion(1)),
+                        )
+                        .spacing(8)
+                        .align_items(Alignment::Center),
+                )
+                .push(
+                    Text::new(format!(
+                        "Sent through the SMTP server configured in {}.",
+                        email::smtp_config_path().display()
+                    ))
+                    .size(12)
+                    .style(self.muted_color()),
+                );
+        }
+
+        section.width(Length::Fill).into()
+    }
+
+    /// Recipients for the next batch's report (None when emailing is off)
+    fn report_email(&self) -> Result<Option<ReportEmail>, String> {
+        if !self.email_enabled {
+            return Ok(None);
+        }
+        let recipients = email::parse_recipients(&self.email_recipients)?;
+        if recipients.is_empty() {
+            return Err("Enter at least one email recipient.".to_string());
+        }
+        Ok(Some(ReportEmail { recipients, format: self.email_format }))
+    }
+
+    /// Render the finished batch's report and email it
+    fn email_report(&self, email: ReportEmail) -> Command<Message> {
+        let text = self.test_results.clone().unwrap_or_default();
+        let body = match email.format {
+            EmailFormat::Text => text,
+            EmailFormat::Html => {
+                let reports: Vec<BatchReport> =
+                    self.node_results.iter().map(|r| r.report.clone()).collect();
+                match report::render(ExportFormat::Html, &text, &reports) {
+                    Ok(html) => html,
+                    Err(e) => return Command::perform(async move { Err(e) }, Message::ReportEmailed),
+                }
+            }
+        };
+        let failed = self.node_results.iter().any(|r| {
+            r.report.error.is_some() || r.report.tests.iter().any(|t| t.error.is_some())
+        });
+        let subject = format!(
+            "Mogwai test report {} ({})",
+            chrono::Local::now().format("%Y-%m-%d %H:%M"),
+            if failed { "failed" } else { "completed" }
+        );
+        Command::perform(email::send_report(email, subject, body), Message::ReportEmailed)
+    }
+
+    /// Scheduled tests tab: one row per scheduled batch with a Cancel button
+    fn scheduled_view(&self) -> Element<'_, Message> {
+        let now = chrono::Local::now();
+        let title = Text::new(format!("Scheduled Tests ({})", self.scheduled.len()))
+            .size(18)
+            .style(self.heading_color());
+
+        let rows = if self.scheduled.is_empty() {
+            Column::new().push(
+                Text::new("No tests scheduled. Enable \"Schedule for later\" on the Tests tab.")
+                    .size(14),
+            )
+        } else {
+            self.scheduled.iter().fold(Column::new().spacing(6), |column, batch| {
+                let tests = batch
+                    .tests
+                    .iter()
+                    .map(|(test, settings)| format!("{} ({}s)", get_test_name(test), settings.duration))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let target = if batch.nodes.is_empty() {
+                    batch.server_url.clone()
+                } else {
+                    format!("{} → {}", batch.server_url, batch.nodes.join(", "))
+                };
+                let when = if batch.is_due(now) && self.running_tests {
+                    "waiting for the current run to finish".to_string()
+                } else {
+                    schedule::time_until(batch.run_at, now)
+                };
+
+                column.push(
+                    Container::new(
+                        Row::new()
+                            .push(
+                                Column::new()
+                                    .push(
+                                        Text::new(format!(
+                                            "{} ({})",
+                                            batch.run_at.format("%Y-%m-%d %H:%M"),
+                                            when
+                                        ))
+                                        .size(16),
+                                    )
+                                    .push(Text::new(format!("{} on {}", tests, target)).size(12))
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                Button::new(Text::new("Cancel").size(14))
+                                    .on_press(Message::CancelScheduled(batch.id.clone()))
+                                    .padding([6, 14])
+                                    .style(iced::theme::Button::Destructive),
+                            )
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                    )
+                    .style(iced::theme::Container::Box)
+                    .padding(8)
+                    .width(Length::Fill),
+                )
+            })
+        };
+
+        Column::new()
+            .push(title)
+            .push(rows)
+            .spacing(12)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Current settings for a test type
+    fn settings_for(&self, test: TestType) -> TestSettings {
+        self.settings
+            .get(&test)
+            .cloned()
+            .unwrap_or_else(|| TestSettings::defaults_for(test))
+    }
+
+    /// Mutable settings for a test type
+    fn settings_mut(&mut self, test: TestType) -> &mut TestSettings {
+        self.settings
+            .entry(test)
+            .or_insert_with(|| TestSettings::defaults_for(test))
+    }
+
+    /// Collapsible parameter section for a single test
+    fn params_section(&self, test: TestType) -> Element<'_, Message> {
+        let settings = self.settings.get(&test);
+        let expanded = settings.is_none_or(|s| s.expanded);
+        let marker = if expanded { "▼" } else { "▶" };
+
+        let header = Button::new(
+            Text::new(format!("{} {} Test", marker, get_test_name(&test))).size(16),
+        )
+        .on_press(Message::ToggleSection(test))
+        .padding([6, 10])
+        .style(iced::theme::Button::Text)
+        .width(Length::Fill);
+
+        let mut section = Column::new().push(header).spacing(8);
+
+        if let (true, Some(settings)) = (expanded, settings) {
+            let errors = settings.validate(test);
+            let mut row = Row::new()
+                .push(self.validated_field(
+                    TextInput::new("Duration (seconds)", &settings.duration)
+                        .on_input(move |value| Message::DurationChanged(test, value))
+                        .padding(8),
+                    &errors.duration,
+                ))
+                .push(self.validated_field(
+                    TextInput::new("Intensity (threads)", &settings.intensity)
+                        .on_input(move |value| Message::IntensityChanged(test, value))
+                        .padding(8),
+                    &errors.intensity,
+                ))
+                .spacing(10)
+                .width(Length::Fill);
+
+            row = match test {
+                TestType::Cpu => row.push(self.validated_field(
+                    TextInput::new("CPU Load (%)", &settings.load)
+                        .on_input(Message::LoadChanged)
+                        .padding(8),
+                    &errors.load,
+                )),
+                TestType::Memory | TestType::Disk => row.push(self.validated_field(
+                    TextInput::new("Size (MB)", &settings.size)
+                        .on_input(move |value| Message::SizeChanged(test, value))
+                        .padding(8),
+                    &errors.size,
+                )),
+            };
+
+            section = section.push(row);
+
+            if test == TestType::Cpu {
+                section = section.push(
+                    Container::new(Checkbox::new("Fork Test", settings.fork, Message::ForkToggled))
+                        .padding(5),
+                );
+            }
+        }
+
+        Container::new(section)
+            .style(iced::theme::Container::Box)
+            .padding(10)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Input field with its validation error (if any) shown underneath
+    fn validated_field<'a>(
+        &self,
+        input: TextInput<'a, Message>,
+        error: &Option<String>,
+    ) -> Element<'a, Message> {
+        let mut field = Column::new().push(input).spacing(2).width(Length::Fill);
+        if let Some(error) = error {
+            field = field.push(Text::new(error.clone()).size(12).style(self.error_color()));
+        }
+        field.into()
+    }
+
+    /// Whether every selected test has valid parameters
+    fn inputs_valid(&self) -> bool {
+        self.selected_tests
+            .iter()
+            .all(|&test| self.settings_for(test).validate(test).is_valid())
+    }
+
+    /// Node selection (Kubernetes environment only): one checkbox per node
+    fn node_picker(&self) -> Element<'_, Message> {
+        if self.environment != Environment::Kubernetes {
+            return Column::new().into();
+        }
+
+        let refresh_label = if self.fetching_nodes { "Loading..." } else { "Refresh" };
+        let mut refresh_button = Button::new(Text::new(refresh_label).size(14))
+            .padding([6, 12])
+            .style(iced::theme::Button::Secondary);
+        if !self.fetching_nodes {
+            refresh_button = refresh_button.on_press(Message::RefreshNodesPressed);
+        }
+
+        let header = Row::new()
+            .push(Text::new(format!(
+                "Nodes ({} of {} selected):",
+                self.selected_nodes.len(),
+                self.nodes.len()
+            )))
+            .push(Space::with_width(Length::Fill))
+            .push(Checkbox::new(
+                "All nodes",
+                !self.nodes.is_empty() && self.selected_nodes.len() == self.nodes.len(),
+                Message::AllNodesToggled,
+            ))
+            .push(refresh_button)
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+        let checkboxes = if self.nodes.is_empty() {
+            Column::new().push(
+                Text::new("No nodes loaded. Press Refresh to fetch the node list.")
+                    .size(14)
+                    .style(self.muted_color()),
+            )
+        } else {
+            self.nodes.iter().fold(Column::new().spacing(4), |column, node| {
+                let name = node.clone();
+                column.push(Checkbox::new(
+                    node.clone(),
+                    self.selected_nodes.contains(node),
+                    move |checked| Message::NodeToggled(name.clone(), checked),
+                ))
+            })
+        };
+
+        Column::new()
+            .push(header)
+            .push(checkboxes)
+            .spacing(8)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Tab selector shown under the header
+    fn tab_bar(&self) -> Element<'_, Message> {
+        let tab_button = |label: &'static str, tab: Tab| {
+            Button::new(
+                Text::new(label)
+                    .size(16)
+                    .horizontal_alignment(alignment::Horizontal::Center),
+            )
+            .on_press(Message::TabSelected(tab))
+            .padding([8, 20])
+            .style(if self.tab == tab {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Secondary
+            })
+            .width(Length::Fill)
+        };
+
+        let mut tabs = Row::new()
+            .push(tab_button("TESTS", Tab::Tests))
+            .push(tab_button("TASKS", Tab::Tasks))
+            .push(tab_button("SCHEDULED", Tab::Scheduled))
+            .push(tab_button("COMPARE", Tab::Compare))
+            .spacing(10)
+            .width(Length::Fixed(600.0));
+        if self.environment == Environment::Kubernetes {
+            tabs = tabs.push(tab_button("ENGINES", Tab::Engines)).width(Length::Fixed(750.0));
+        }
+
+        Container::new(tabs)
+        .width(Length::Fill)
+        .center_x()
+        .into()
+    }
+
+    /// Task management tab: one row per running task with a Stop button
+    fn tasks_view(&self) -> Element<'_, Message> {
+        let controls = Row::new()
+            .push(
+                Text::new(format!("Running Tasks ({})", self.tasks.len()))
+                    .size(18)
+                    .style(self.heading_color()),
+            )
+            .push(Space::with_width(Length::Fill))
+            .push(
+                Button::new(Text::new("REFRESH").size(14))
+                    .on_press(Message::RefreshTasks)
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Secondary),
+            )
+            .push(
+                Button::new(Text::new("STOP ALL").size(14))
+                    .on_press(Message::StopAllPressed)
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Destructive),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+        let rows = if self.tasks.is_empty() {
+            Column::new().push(Text::new("No running tasks found.").size(14))
+        } else {
+            self.tasks.iter().fold(Column::new().spacing(6), |column, task| {
+                column.push(
+                    Container::new(
+                        Row::new()
+                            .push(
+                                Column::new()
+                                    .push(Text::new(task.id.clone()).size(16))
+                                    .push(Text::new(task.details.clone()).size(12))
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                Button::new(Text::new("Stop").size(14))
+                                    .on_press(Message::StopTaskPressed(task.id.clone()))
+                                    .padding([6, 14])
+                                    .style(iced::theme::Button::Destructive),
+                            )
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                    )
+                    .style(iced::theme::Container::Box)
+                    .padding(8)
+                    .width(Length::Fill),
+                )
+            })
+        };
+
+        let mut view = Column::new().push(controls).push(rows).spacing(12);
+
+        if let Some(message) = &self.tasks_message {
+            view = view.push(Text::new(message.clone()).size(14));
+        }
+
+        view.push(
+            Text::new("The list refreshes every 3 seconds while this tab is open.")
+                .size(12)
+                .style(self.muted_color()),
+        )
+        .width(Length::Fill)
+        .into()
+    }
+
+    /// Engine deployment tab: one row per node with readiness and Spawn/Remove buttons
+    fn engines_view(&self) -> Element<'_, Message> {
+        let ready_count = self.engines.values().filter(|engine| engine.ready).count();
+        let controls = Row::new()
+            .push(
+                Text::new(format!(
+                    "Engines ({} of {} nodes ready)",
+                    ready_count,
+                    self.nodes.len()
+                ))
+                .size(18)
+                .style(self.heading_color()),
+            )
+            .push(Space::with_width(Length::Fill))
+            .push(
+                Button::new(Text::new("REFRESH").size(14))
+                    .on_press(Message::RefreshNodesPressed)
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Secondary),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+        let theme = self.theme();
+        let palette = theme.extended_palette();
+        let rows = if self.nodes.is_empty() {
+            Column::new().push(Text::new("No nodes found.").size(14))
+        } else {
+            self.nodes.iter().fold(Column::new().spacing(6), |column, node| {
+                let engine = self.engines.get(node);
+                let busy = self.engine_busy.contains(node);
+
+                // Readiness indicator: ready (green), starting/failed (yellow/red), none (muted)
+                let (indicator, color) = match engine {
+                    Some(engine) if engine.ready => ("● Ready".to_string(), palette.success.base.color),
+                    Some(engine) if engine.phase == "Failed" => {
+                        ("● Failed".to_string(), palette.danger.base.color)
+                    }
+                    Some(engine) => (format!("● {}", engine.phase), Color::from_rgb(0.9, 0.65, 0.1)),
+                    None => ("○ No engine".to_string(), self.muted_color()),
+                };
+
+                let mut spawn_button = Button::new(Text::new("Spawn").size(14))
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Primary);
+                if engine.is_none() && !busy {
+                    spawn_button = spawn_button.on_press(Message::SpawnEnginePressed(node.clone()));
+                }
+                let mut remove_button = Button::new(Text::new("Remove").size(14))
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Destructive);
+                if engine.is_some() && !busy {
+                    remove_button = remove_button.on_press(Message::RemoveEnginePressed(node.clone()));
+                }
+
+                column.push(
+                    Container::new(
+                        Row::new()
+                            .push(
+                                Column::new()
+                                    .push(Text::new(node.clone()).size(16))
+                                    .push(
+                                        Text::new(engine.map_or(String::new(), |e| e.pod.clone()))
+                                            .size(12)
+                                            .style(self.muted_color()),
+                                    )
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(if busy { "Working...".to_string() } else { indicator })
+                                    .size(14)
+                                    .style(color)
+                                    .width(Length::Fixed(120.0)),
+                            )
+                            .push(spawn_button)
+                            .push(remove_button)
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                    )
+                    .style(iced::theme::Container::Box)
+                    .padding(8)
+                    .width(Length::Fill),
+                )
+            })
+        };
+
+        let mut view = Column::new().push(controls).push(rows).spacing(12);
+
+        if let Some(message) = &self.engines_message {
+            view = view.push(Text::new(message.clone()).size(14));
+        }
+
+        view.push(
+            Text::new("Engine status refreshes every 3 seconds while this tab is open.")
+                .size(12)
+                .style(self.muted_color()),
+        )
+        .width(Length::Fill)
+        .into()
+    }
+
+    /// Compare tab: two runs side by side with changes highlighted
+    fn compare_view(&self) -> Element<'_, Message> {
+        let labels: Vec<String> = self
+            .run_history
+            .iter()
+            .chain(&self.saved_runs)
+            .map(|run| run.label.clone())
+            .collect();
+
+        let controls = Row::new()
+            .push(Text::new("Compare Runs").size(18).style(self.heading_color()))
+            .push(Space::with_width(Length::Fill))
+            .push(
+                Button::new(Text::new("SWAP").size(14))
+                    .on_press(Message::SwapComparePressed)
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Secondary),
+            )
+            .push(
+                Button::new(Text::new("REFRESH").size(14))
+                    .on_press(Message::RefreshSavedRuns)
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Secondary),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+        let pickers = Row::new()
+            .push(
+                PickList::new(labels.clone(), self.compare_left.clone(), Message::CompareLeftSelected)
+                    .placeholder("Baseline run...")
+                    .width(Length::FillPortion(1)),
+            )
+            .push(
+                PickList::new(labels, self.compare_right.clone(), Message::CompareRightSelected)
+                    .placeholder("Run to compare...")
+                    .width(Length::FillPortion(1)),
+            )
+            .spacing(10);
+
+        let mut view = Column::new().push(controls).push(pickers).spacing(12);
+
+        if let Some(message) = &self.compare_message {
+            view = view.push(Text::new(message.clone()).size(14));
+        }
+
+        let find = |label: &Option<String>| {
+            label.as_ref().and_then(|label| {
+                self.run_history
+                    .iter()
+                    .chain(&self.saved_runs)
+                    .find(|run| &run.label == label)
+            })
+        };
+        let (left, right) = match (find(&self.compare_left), find(&self.compare_right)) {
+            (Some(left), Some(right)) => (left, right),
+            _ => {
+                return view
+                    .push(
+                        Text::new(
+                            "Pick two runs: batches finished in this session, or JSON results saved to the results directory.",
+                        )
+                        .size(14)
+                        .style(self.muted_color()),
+                    )
+                    .width(Length::Fill)
+                    .into();
+            }
+        };
+
+        let theme = self.theme();
+        let palette = theme.extended_palette();
+        let trend_color = |trend: Trend| match trend {
+            Trend::Unchanged => self.muted_color(),
+            Trend::Better => palette.success.base.color,
+            Trend::Worse => palette.danger.base.color,
+            Trend::Changed => Color::from_rgb(0.9, 0.65, 0.1),
+        };
+
+        let cell = |text: String, size: u16| Text::new(text).size(size).width(Length::FillPortion(2));
+        let header = Row::new()
+            .push(cell("".to_string(), 14))
+            .push(cell(left.label.clone(), 14).style(self.heading_color()))
+            .push(cell(right.label.clone(), 14).style(self.heading_color()))
+            .push(Text::new("Change").size(14).style(self.heading_color()).width(Length::FillPortion(1)))
+            .spacing(10);
+
+        let rows = compare::compare(left, right);
+        let mut table = Column::new().push(header).spacing(4);
+        let mut section = None;
+        for row in rows {
+            if section.as_ref() != Some(&row.section) {
+                table = table
+                    .push(Space::with_height(Length::Fixed(6.0)))
+                    .push(Text::new(row.section.clone()).size(16).style(self.heading_color()));
+                section = Some(row.section.clone());
+            }
+            let change = match (row.delta, row.trend) {
+                (Some(delta), _) => format!("{:+.1}%", delta),
+                (None, Trend::Unchanged) => String::new(),
+                (None, _) => "changed".to_string(),
+            };
+            table = table.push(
+                Row::new()
+                    .push(cell(row.name, 14))
+                    .push(cell(row.left, 14))
+                    .push(cell(row.right, 14).style(trend_color(row.trend)))
+                    .push(
+                        Text::new(change)
+                            .size(14)
+                            .style(trend_color(row.trend))
+                            .width(Length::FillPortion(1)),
+                    )
+                    .spacing(10),
+            );
+        }
+
+        view.push(
+            Container::new(table)
+                .style(iced::theme::Container::Box)
+                .padding(10)
+                .width(Length::Fill),
+        )
+        .push(
+            Text::new("Green marks an improvement, red a possible regression and yellow any other change of 5% or more.")
+                .size(12)
+                .style(self.muted_color()),
+        )
+        .width(Length::Fill)
+        .into()
+    }
+
+    /// One progress bar with a remaining-time estimate per test of the current batch
+    fn progress_view(&self) -> Element<'_, Message> {
+        if self.progress_rows.is_empty() {
+            return Column::new().into();
+        }
+
+        let now = Instant::now();
+        self.progress_rows
+            .iter()
+            .fold(
+                Column::new()
+                    .push(Text::new("Progress:").size(18).style(self.heading_color()))
+                    .spacing(6),
+                |column, entry| {
+                    let status = Text::new(entry.status_text(now)).size(14);
+                    let status = if matches!(entry.state, ProgressState::Failed(_)) {
+                        status.style(self.error_color())
+                    } else {
+                        status
+                    };
+                    column.push(
+                        Row::new()
+                            .push(Text::new(entry.label.clone()).size(14).width(Length::Fixed(160.0)))
+                            .push(
+                                ProgressBar::new(0.0..=1.0, entry.fraction(now))
+                                    .height(Length::Fixed(14.0))
+                                    .width(Length::Fill),
+                            )
+                            .push(status.width(Length::Fixed(200.0)))
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                    )
+                },
+            )
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Scrolling pane with the live output of the running tasks
+    fn log_view(&self) -> Element<'_, Message> {
+        if !self.running_tests && self.logs.is_empty() {
+            return Column::new().into();
+        }
+
+        let lines = self.logs.iter().fold(Column::new().spacing(2), |column, line| {
+            column.push(Text::new(line.clone()).size(13))
+        });
+
+        Column::new()
+            .push(
+                Row::new()
+                    .push(Text::new("Live Logs:").size(18).style(self.heading_color()))
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        Button::new(Text::new("Clear").size(14))
+                            .on_press(Message::ClearLogsPressed)
+                            .padding([4, 12])
+                            .style(iced::theme::Button::Secondary),
                     )
-                    .style(iced::theme::Container::Box)
-                    .padding(10)
-                    .width(Length::Fill),
+                    .align_items(Alignment::Center),
+            )
+            .push(
+                Container::new(
+                    Scrollable::new(lines)
+                        .id(log_scroll_id())
+                        .height(Length::Fixed(200.0))
+                        .width(Length::Fill),
                 )
-                .spacing(10),
-        )
-        .width(Length::Fill);
-
-        // Main layout
-        let content = Column::new()
-            .push(header)
-            .push(separator)
-            .push(Space::with_height(Length::Fixed(10.0)))
-            .push(advanced_toggle)
-            .push(advanced_section)
-            .push(Space::with_height(Length::Fixed(10.0)))
-            .push(checkboxes)
-            .push(Space::with_height(Length::Fixed(10.0)))
-            .push(params_title)
-            .push(row1)
-            .push(row2)
-            .push(Space::with_height(Length::Fixed(10.0)))
-            .push(fork_section)
-            .push(Space::with_height(Length::Fixed(10.0)))
-            .push(helper_text)
-            .push(Space::with_height(Length::Fixed(15.0)))
-            .push(Container::new(primary_button_row).center_x())
+                .style(iced::theme::Container::Box)
+                .padding(10)
+                .width(Length::Fill),
+            )
             .push(Space::with_height(Length::Fixed(10.0)))
-            .push(Container::new(secondary_button_row).center_x())
-            .push(Space::with_height(Length::Fixed(15.0)))
-            .push(test_results_view)
-            .spacing(8)
-            .width(Length::Fill);
+            .spacing(6)
+            .width(Length::Fill)
+            .into()
+    }
 
-        Container::new(Scrollable::new(content))
+    /// Metrics endpoint for the current environment (the controller proxies per node)
+    fn metrics_url(&self) -> String {
+        match self.target_node() {
+            Some(node) => format!("{}/metrics/{}", self.server_url, node),
+            None => format!("{}/metrics", self.server_url),
+        }
+    }
+
+    /// Live metrics pane (shown while tests run and after a run with samples)
+    fn metrics_view(&self) -> Element<'_, Message> {
+        if !self.running_tests && self.metrics_history.is_empty() {
+            return Column::new().into();
+        }
+
+        let mut pane = Column::new()
+            .push(
+                Text::new("Live Metrics:")
+                    .size(18)
+                    .style(self.heading_color()),
+            )
+            .push(charts::metric_charts(&self.metrics_history))
+            .spacing(10);
+
+        if let Some(error) = &self.metrics_error {
+            pane = pane.push(
+                Text::new(format!("Metrics unavailable: {}", error))
+                    .size(14)
+                    .style(self.error_color()),
+            );
+        }
+
+        Container::new(pane)
+            .style(iced::theme::Container::Box)
+            .padding(10)
             .width(Length::Fill)
-            .height(Length::Fill)
-            .center_x()
-            .padding(30)
             .into()
     }
 
-    fn subscription(&self) -> iced::Subscription<Message> {
-        iced::Subscription::none()
+    /// Nodes to dispatch tests to (only the Kubernetes controller routes by node; empty = the server itself)
+    fn target_nodes(&self) -> Vec<String> {
+        if self.environment == Environment::Kubernetes {
+            self.selected_nodes.clone()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Node used for the Tasks tab and live metrics (the first selected node)
+    fn target_node(&self) -> Option<String> {
+        self.target_nodes().into_iter().next()
     }
 }
 
@@ -576,57 +2612,98 @@ fn fetch_node_status(server_url: String, test_id: String) -> Command<Message> {
             let endpoint = format!("{}/nodes/{}", server_url, test_id);
             println!("Fetching node status from: {}", endpoint);
 
-            let command = format!("curl -X GET {}", endpoint);
-            let output = ProcessCommand::new("sh").arg("-c").arg(&command).output();
-
-            match output {
-                Ok(output) => {
-                    if output.status.success() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-
-                        if stdout.trim().is_empty() {
-                            "No node status available.".to_string()
-                        } else {
-                            // Try to parse as JSON and format it nicely
-                            match json_from_str::<Value>(&stdout) {
-                                Ok(json) => {
-                                    // Check if it's a "Not Found" error
-                                    if let Some(detail) = json.get("detail") {
-                                        if detail.as_str() == Some("Not Found") {
-                                            format!("Node Status for Test {}:\n\nNo detailed node status available.", test_id)
-                                        } else {
-                                            // Format the node status nicely
-                                            format!(
-                                                "Node Status for Test {}:\n\n{}",
-                                                test_id,
-                                                format_node_status(&stdout)
-                                            )
-                                        }
-                                    } else {
-                                        // Format the node status nicely
-                                        format!(
-                                            "Node Status for Test {}:\n\n{}",
-                                            test_id,
-                                            format_node_status(&stdout)
-                                        )
-                                    }
-                                }
-                                Err(_) => format!("Node Status for Test {}:\n{}", test_id, stdout),
-                            }
-                        }
+            match api::get(&endpoint).await {
+                Ok(response) if response.status == 404 => format!(
+                    "Node Status for Test {}:\n\nNo detailed node status available.",
+                    test_id
+                ),
+                Ok(response) if response.is_success() => {
+                    if response.body.trim().is_empty() {
+                        "No node status available.".to_string()
                     } else {
-                        "Failed to fetch node status.".to_string()
+                        format!(
+                            "Node Status for Test {}:\n\n{}",
+                            test_id,
+                            format_node_status(&response.body)
+                        )
                     }
                 }
-                Err(_) => "Error connecting to server for node status.".to_string(),
+                Ok(response) => format!(
+                    "Failed to fetch node status (HTTP {}).",
+                    response.status
+                ),
+                Err(e) => format!("Error connecting to server for node status: {}", e),
             }
         },
         Message::NodeStatusReceived,
     )
 }
 
-/// Save test results to a file
-fn save_results(results: String) -> Command<Message> {
+/// Fetch node names from the controller
+fn fetch_nodes(server_url: String) -> Command<Message> {
+    Command::perform(
+        async move {
+            println!("Fetching nodes from: {}/nodes", server_url);
+            api::fetch_nodes(&server_url).await
+        },
+        Message::NodesFetched,
+    )
+}
+
+/// Scrollable ID of the log pane (used to follow new lines)
+fn log_scroll_id() -> scrollable::Id {
+    scrollable::Id::new("log-pane")
+}
+
+/// Load the JSON reports saved in the results directory for the Compare tab
+fn load_saved_runs() -> Command<Message> {
+    Command::perform(
+        async { compare::load_saved_runs(Path::new("results")) },
+        Message::SavedRunsLoaded,
+    )
+}
+
+/// Fetch the engine pods for the Engines tab
+fn fetch_engines(server_url: String) -> Command<Message> {
+    Command::perform(
+        async move { api::fetch_engines(&server_url).await },
+        Message::EnginesFetched,
+    )
+}
+
+/// Spawn or remove the engine on a node (action is "spawn-engine" or "remove-engine")
+fn engine_action(server_url: String, action: &'static str, node: String) -> Command<Message> {
+    Command::perform(
+        async move {
+            let result = api::engine_action(&server_url, action, &node).await;
+            (node, result)
+        },
+        |(node, result)| Message::EngineActionCompleted(node, result),
+    )
+}
+
+/// Fetch a metrics snapshot for the live charts
+fn fetch_metrics(endpoint: String) -> Command<Message> {
+    Command::perform(
+        async move {
+            let json: Value = api::get_json(&endpoint).await?;
+            MetricSample::from_json(&json)
+                .ok_or_else(|| format!("Unexpected metrics response: {}", json))
+        },
+        Message::MetricsReceived,
+    )
+}
+
+/// Save test results to a file in the results directory
+fn save_results(content: String, file_name: &str, format: ExportFormat) -> Command<Message> {
+    // Keep only the file name part so the file always lands in the results directory
+    let stem = Path::new(file_name.trim())
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or_else(|| "mogwai_results".to_string());
+    let filename = format!("{}.{}", stem, format.extension());
+
     Command::perform(
         async move {
             // Create results directory if it doesn't exist
@@ -637,21 +2714,15 @@ fn save_results(results: String) -> Command<Message> {
                 }
             }
 
-            // Generate filename with timestamp
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-            let filename = format!("mogwai_results_{}.txt", timestamp);
             let path = results_dir.join(filename);
 
             // Write results to file
             match File::create(&path) {
                 Ok(mut file) => {
-                    if let Err(e) = file.write_all(results.as_bytes()) {
+                    if let Err(e) = file.write_all(content.as_bytes()) {
                         return Err(format!("Failed to write to file: {}", e));
                     }
-                    Ok(())
+                    Ok(path.display().to_string())
                 }
                 Err(e) => Err(format!("Failed to create file: {}", e)),
             }
@@ -660,37 +2731,50 @@ fn save_results(results: String) -> Command<Message> {
     )
 }
 
-/// List running tasks
-fn list_tasks(server_url: String) -> Command<Message> {
+/// Fetch running tasks (engine: GET /tasks, controller: POST /tasks/{node})
+fn fetch_tasks(server_url: String, node: Option<String>) -> Command<Message> {
     Command::perform(
-        async move {
-            let endpoint = format!("{}/tasks", server_url);
-            println!("Fetching tasks from: {}", endpoint);
-
-            let command = format!("curl -X GET {}", endpoint);
-            let output = ProcessCommand::new("sh").arg("-c").arg(&command).output();
-
-            match output {
-                Ok(output) => {
-                    if output.status.success() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        if stdout.trim().is_empty() {
-                            "No running tasks found.".to_string()
-                        } else {
-                            parse_tasks_response(&stdout)
-                        }
-                    } else {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        format!("Failed to get tasks: {}", stderr)
-                    }
-                }
-                Err(e) => format!("Error fetching tasks: {}", e),
-            }
-        },
+        async move { list_tasks(&server_url, node.as_deref()).await },
         Message::TasksListed,
     )
 }
 
+/// Running tasks on the engine (GET /tasks) or on a node via the controller (POST /tasks/{node})
+async fn list_tasks(server_url: &str, node: Option<&str>) -> Result<Vec<TaskRow>, String> {
+    let response = match node {
+        Some(node) => api::post(&format!("{}/tasks/{}", server_url, node)).await?,
+        None => api::get(&format!("{}/tasks", server_url)).await?,
+    };
+    parse_tasks_response(&response.into_result()?)
+}
+
+/// Stop a single task (engine: /stop/{id}, controller: /stop/{node}/{id})
+fn stop_task(server_url: String, node: Option<String>, id: String) -> Command<Message> {
+    let endpoint = stop_endpoint(&server_url, node.as_deref(), &id);
+    Command::perform(post_request(endpoint), Message::StopCompleted)
+}
+
+/// Stop endpoint for a task (the controller needs the node)
+fn stop_endpoint(server_url: &str, node: Option<&str>, id: &str) -> String {
+    match node {
+        Some(node) => format!("{}/stop/{}/{}", server_url, node, id),
+        None => format!("{}/stop/{}", server_url, id),
+    }
+}
+
+/// Stop every running task
+fn stop_all_tasks(server_url: String) -> Command<Message> {
+    Command::perform(post_request(format!("{}/stop-all", server_url)), Message::StopCompleted)
+}
+
+/// Send an empty POST request and return the response body
+async fn post_request(endpoint: String) -> Result<String, String> {
+    api::post(&endpoint)
+        .await?
+        .into_result()
+        .map(|body| body.trim().to_string())
+}
+
 /// Format node status JSON into readable text
 fn format_node_status(json_str: &str) -> String {
     match json_from_str::<Value>(json_str) {
@@ -782,55 +2866,38 @@ fn format_json_value(value: &Value) -> String {
     }
 }
 
-/// Parse and format task list response
-fn parse_tasks_response(stdout: &str) -> String {
-    match json_from_str::<Value>(stdout) {
-        Ok(json) => {
-            // Check if it's a "Not Found" error
-            if let Some(detail) = json.get("detail") {
-                if detail.as_str() == Some("Not Found") {
-                    return "=== RUNNING TASKS ===\n\nNo task list available.".to_string();
-                }
-            } else if stdout.contains("[") && stdout.contains("]") {
-                // It's probably a JSON array
-                let mut result = String::from("=== RUNNING TASKS ===\n\n");
-
-                // Try to parse as an array
-                if let Some(tasks) = json.as_array() {
-                    if tasks.is_empty() {
-                        result.push_str("No running tasks found.\n");
-                    } else {
-                        result.push_str(&format!("Found {} running tasks:\n\n", tasks.len()));
+/// Parse the task list response into rows
+/// Tasks may be plain ID strings or objects with an "id" field plus extra details
+fn parse_tasks_response(body: &str) -> Result<Vec<TaskRow>, String> {
+    if body.trim().is_empty() {
+        return Ok(vec![]);
+    }
 
-                        for (i, task) in tasks.iter().enumerate() {
-                            format_task_item(&mut result, i, task);
-                        }
-                    }
-                    return result;
-                }
-            }
+    let json = json_from_str::<Value>(body).map_err(|_| format!("Unexpected response: {}", body))?;
+    let tasks = json
+        .as_array()
+        .ok_or_else(|| format!("Unexpected response: {}", body))?;
 
-            // Default: show raw JSON
-            format!("=== RUNNING TASKS ===\n\n{}", stdout)
-        }
-        Err(_) => format!("=== RUNNING TASKS ===\n\n{}", stdout),
-    }
+    Ok(tasks.iter().filter_map(task_row).collect())
 }
 
-/// Format a single task item in the task list
-fn format_task_item(result: &mut String, index: usize, task: &Value) {
-    if let Some(task_obj) = task.as_object() {
-        result.push_str(&format!("Task #{}: \n", index + 1));
-        for (key, value) in task_obj {
-            let display_value = format_json_value(value);
-            result.push_str(&format!("  • {}: {}\n", key, display_value));
-        }
-        result.push_str("\n");
-    } else if let Some(task_str) = task.as_str() {
-        result.push_str(&format!("Task #{}: {}\n", index + 1, task_str));
-    } else {
-        result.push_str(&format!("Task #{}: {}\n", index + 1, task.to_string()));
+/// Build a task row from a single task list entry
+fn task_row(task: &Value) -> Option<TaskRow> {
+    if let Some(id) = task.as_str() {
+        return Some(TaskRow { id: id.to_string(), details: String::new() });
     }
+
+    let task_obj = task.as_object()?;
+    let id = task_obj.get("id").and_then(Value::as_str)?.to_string();
+    let details = task_obj
+        .iter()
+        // The engine also reports the submitted request body, already covered by the form
+        .filter(|(key, value)| !matches!(key.as_str(), "id" | "params") && !value.is_null())
+        .map(|(key, value)| format!("{}: {}", key, format_json_value(value)))
+        .collect::<Vec<_>>()
+        .join(" • ");
+
+    Some(TaskRow { id, details })
 }
 
 /**
@@ -938,106 +3005,290 @@ fn get_memory_info() -> Option<(u64, u64)> {
     None
 }
 
+/// Execute the batch on every target node concurrently (no nodes = the server itself)
+async fn execute_on_nodes(
+    selected_tests: Vec<(TestType, TestSettings)>,
+    server_url: String,
+    batch_id: String,
+    nodes: Vec<String>,
+    batch: Batch,
+) -> Vec<NodeResult> {
+    let targets: Vec<Option<String>> = if nodes.is_empty() {
+        vec![None]
+    } else {
+        nodes.into_iter().map(Some).collect()
+    };
+
+    // Register every test up front so the whole batch shows as queued
+    {
+        let mut board = batch.progress.lock().unwrap_or_else(|e| e.into_inner());
+        for node in &targets {
+            for (index, (test, settings)) in selected_tests.iter().enumerate() {
+                board.push(TestProgress {
+                    key: progress_key(node.as_deref(), index),
+                    label: match node {
+                        Some(node) => format!("{} @ {}", get_test_name(test), node),
+                        None => get_test_name(test).to_string(),
+                    },
+                    node: node.clone(),
+                    task_id: None,
+                    duration: settings.duration.trim().parse().unwrap_or(0),
+                    started: None,
+                    state: ProgressState::Waiting,
+                });
+            }
+        }
+    }
+
+    let handles: Vec<_> = targets
+        .iter()
+        .cloned()
+        .map(|node| {
+            let (tests, server_url, batch_id, batch) = (
+                selected_tests.clone(),
+                server_url.clone(),
+                batch_id.clone(),
+                batch.clone(),
+            );
+            tokio::spawn(async move {
+                execute_tests(tests, server_url, batch_id, node, batch).await
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for (node, handle) in targets.into_iter().zip(handles) {
+        match handle.await {
+            Ok((text, report)) => results.push(NodeResult { node, text, report }),
+            Err(e) => {
+                let mut report = BatchReport::empty(&batch_id, &server_url, node.clone());
+                report.error = Some(format!("Test run aborted: {}", e));
+                results.push(NodeResult {
+                    node,
+                    text: format!("Test run aborted: {}", e),
+                    report,
+                });
+            }
+        }
+    }
+    results
+}
+
+/// State shared between the GUI and a running batch
+#[derive(Clone)]
+struct Batch {
+    progress: ProgressBoard, // Per-test progress (written by the batch)
+    cancel: Arc<AtomicBool>, // Set by the GUI's Cancel button
+}
+
+impl Batch {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+/// Progress board key of a test (node and position in the batch)
+fn progress_key(node: Option<&str>, index: usize) -> String {
+    format!("{}#{}", node.unwrap_or(""), index)
+}
+
+/// Join per-node text reports into one report (used for display and text export)
+fn combine_node_reports(node_results: &[NodeResult]) -> String {
+    if let [single] = node_results {
+        return single.text.clone();
+    }
+    node_results
+        .iter()
+        .map(|result| {
+            format!(
+                "####################################\nNODE: {}\n####################################\n{}",
+                result.node.as_deref().unwrap_or("-"),
+                result.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
 /// Execute tests with full metrics and reporting
 async fn execute_tests(
-    selected_tests: Vec<TestType>,
+    selected_tests: Vec<(TestType, TestSettings)>,
     server_url: String,
     batch_id: String,
-    duration: String,
-    intensity: String,
-    size: String,
-    load: String,
-    fork: bool,
-) -> String {
+    node: Option<String>,
+    batch: Batch,
+) -> (String, BatchReport) {
     let mut results = Vec::new();
+    let system_info = get_system_info();
+    let mut batch_report = BatchReport::empty(&batch_id, &server_url, node.clone());
+    batch_report.system_info = system_info.clone();
 
     // Add report header
     add_report_header(&mut results, &batch_id);
 
     // Add system information
-    results.push(format!("SYSTEM INFORMATION"));
-    results.push(format!("------------------------------------"));
-    results.push(get_system_info());
-    results.push(format!(""));
+    results.push("SYSTEM INFORMATION".to_string());
+    results.push("------------------------------------".to_string());
+    results.push(system_info);
+    results.push(String::new());
 
     // Process each selected test
-    for test in &selected_tests {
-        // Add test header
+    for (index, (test, settings)) in selected_tests.iter().enumerate() {
+        let key = progress_key(node.as_deref(), index);
         let test_name = get_test_name(test);
+
+        // Skip the remaining tests once the batch is cancelled
+        if batch.is_cancelled() {
+            results.push(format!("Test {} skipped: batch cancelled.", test_name));
+            progress::update(&batch.progress, &key, |p| p.state = ProgressState::Cancelled);
+            continue;
+        }
+
+        // Add test header
         add_test_header(&mut results, test_name);
 
         // Generate test ID and prepare payload
         let test_id = Uuid::new_v4().to_string();
-        let (endpoint, payload) = prepare_test_payload(
-            test, &test_id, &batch_id, &duration, &intensity, &size, &load, fork,
-        );
+        let mut test_report = TestReport {
+            test_id: test_id.clone(),
+            test_type: test_name.to_string(),
+            endpoint: String::new(),
+            request: None,
+            http_status: None,
+            task_id: None,
+            status: None,
+            error: None,
+            metrics: BTreeMap::new(),
+        };
+        let (endpoint, payload) = match prepare_test_payload(
+            test, &test_id, &batch_id, settings, node.clone(),
+        ) {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                results.push(format!("Invalid parameters: {}", e));
+                results.push(format!("Test {} skipped.", test_name));
+                results.push(String::new());
+                test_report.error = Some(format!("Invalid parameters: {}", e));
+                batch_report.tests.push(test_report);
+                progress::update(&batch.progress, &key, |p| p.state = ProgressState::Failed("invalid parameters".to_string()));
+                continue;
+            }
+        };
+        test_report.endpoint = endpoint.to_string();
+        test_report.request = Some(payload.clone());
 
         // Add request details
         add_request_details(&mut results, &server_url, endpoint, &test_id);
+        if let Some(node) = &node {
+            results.push(format!("  Node: {}", node));
+        }
 
         // Add test parameters based on test type
-        add_test_parameters(
-            &mut results,
-            test,
-            &duration,
-            &intensity,
-            &size,
-            &load,
-            fork,
-        );
+        add_test_parameters(&mut results, test, settings);
 
         // Add payload for reference
-        results.push(format!(""));
-        results.push(format!("JSON Payload:"));
-        results.push(format!("{}", payload));
-
-        // Execute the test
-        let command = format!(
-            "curl -X POST {}/{} -H \"Content-Type:application/json\" -d '{}'",
-            server_url, endpoint, payload
-        );
-
-        // Process response
-        let output = ProcessCommand::new("sh").arg("-c").arg(&command).output();
-        process_test_response(&mut results, output);
+        results.push(String::new());
+        results.push("JSON Payload:".to_string());
+        results.push(to_string_pretty(&payload).unwrap_or_default());
+
+        // Execute the test and process the response
+        let response = api::post_json(&format!("{}/{}", server_url, endpoint), &payload).await;
+        match &response {
+            Ok(response) => {
+                test_report.http_status = Some(response.status);
+                test_report.task_id = report::parse_task_id(&response.body);
+                if !response.is_success() {
+                    test_report.error = Some(format!("HTTP {}", response.status));
+                }
+            }
+            Err(e) => test_report.error = Some(e.clone()),
+        }
+        let started = response.as_ref().is_ok_and(ApiResponse::is_success);
+        process_test_response(&mut results, response);
+
+        if !started {
+            let error = test_report.error.clone().unwrap_or_default();
+            progress::update(&batch.progress, &key, |p| p.state = ProgressState::Failed(error));
+            batch_report.tests.push(test_report);
+            results.push(String::new());
+            results.push(format!("Test {} could not be started.", test_name));
+            results.push(String::new());
+            continue;
+        }
 
         // Wait for test completion
-        results.push(format!(""));
+        results.push(String::new());
         results.push(format!(
             "Test {} started, waiting for completion...",
             test_name
         ));
 
-        let wait_time = calculate_wait_time(&duration);
-        tokio::time::sleep(std::time::Duration::from_secs(wait_time)).await;
+        let task_id = test_report.task_id.clone();
+        progress::update(&batch.progress, &key, |p| {
+            p.state = ProgressState::Running;
+            p.started = Some(Instant::now());
+            p.task_id = task_id.clone();
+        });
+
+        // Cancelled while the request was in flight: the GUI could not see the task ID yet
+        if batch.is_cancelled() {
+            if let Some(task_id) = &task_id {
+                let _ = post_request(stop_endpoint(&server_url, node.as_deref(), task_id)).await;
+            }
+        }
+
+        let finished = wait_for_completion(
+            &server_url,
+            node.as_deref(),
+            task_id.as_deref(),
+            payload.duration,
+            &batch,
+        )
+        .await;
+        if !finished {
+            results.push(format!("Test {} cancelled.", test_name));
+            results.push(String::new());
+            test_report.status = Some("cancelled".to_string());
+            batch_report.tests.push(test_report);
+            progress::update(&batch.progress, &key, |p| p.state = ProgressState::Cancelled);
+            continue;
+        }
+        progress::update(&batch.progress, &key, |p| p.state = ProgressState::Done);
 
-        // Check for test results via status endpoint
-        check_test_status(&mut results, test, &server_url, &test_id).await;
+        // Fetch the task's result from the engine (through the controller with a node)
+        if let Some(result) =
+            fetch_test_result(&mut results, test, &server_url, node.as_deref(), task_id.as_deref()).await
+        {
+            test_report.record_result(&result);
+        }
+        batch_report.tests.push(test_report);
 
         // Add test completion marker
-        results.push(format!(""));
+        results.push(String::new());
         results.push(format!("Test {} completed.", test_name));
-        results.push(format!(""));
+        results.push(String::new());
     }
 
     // Add summary section
-    add_summary_section(&mut results, &batch_id, &selected_tests);
+    let tests: Vec<TestType> = selected_tests.iter().map(|(test, _)| *test).collect();
+    add_summary_section(&mut results, &batch_id, &tests);
 
     // Return the complete results
-    results.join("\n")
+    batch_report.finished_at = Some(chrono::Local::now().to_rfc3339());
+    (results.join("\n"), batch_report)
 }
 
 /// Add report header to results
 fn add_report_header(results: &mut Vec<String>, batch_id: &str) {
-    results.push(format!("===================================="));
-    results.push(format!("MOGWAI PERFORMANCE TEST REPORT"));
-    results.push(format!("===================================="));
+    results.push("====================================".to_string());
+    results.push("MOGWAI PERFORMANCE TEST REPORT".to_string());
+    results.push("====================================".to_string());
     results.push(format!(
         "Date/Time: {}",
         chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
     ));
     results.push(format!("Batch ID: {}", batch_id));
-    results.push(format!(""));
+    results.push(String::new());
 }
 
 /// Get user-friendly test name
@@ -1051,9 +3302,9 @@ fn get_test_name(test: &TestType) -> &'static str {
 
 /// Add test header to results
 fn add_test_header(results: &mut Vec<String>, test_name: &str) {
-    results.push(format!("===================================="));
+    results.push("====================================".to_string());
     results.push(format!("RUNNING {} TEST", test_name));
-    results.push(format!("===================================="));
+    results.push("====================================".to_string());
 }
 
 /// Prepare payload for test
@@ -1061,59 +3312,72 @@ fn prepare_test_payload(
     test: &TestType,
     test_id: &str,
     batch_id: &str,
-    duration: &str,
-    intensity: &str,
-    size: &str,
-    load: &str,
-    fork: bool,
-) -> (&'static str, String) {
+    settings: &TestSettings,
+    node: Option<String>,
+) -> Result<(&'static str, StressRequest), String> {
     let endpoint = match test {
         TestType::Cpu => "cpu-stress",
         TestType::Memory => "mem-stress",
         TestType::Disk => "disk-stress",
     };
 
+    let intensity = parse_field::<u32>("intensity", &settings.intensity)?;
+    let duration = parse_field::<u64>("duration", &settings.duration)?;
+
+    // The controller routes by node; the engine ignores the field
     let payload = match test {
-        TestType::Cpu => {
-            format!(
-                r#"{{"id": "{}", "batch_id": "{}", "name": "GUI Test", "intensity": {}, "duration": {}, "load": {}, "fork": {}}}"#,
-                test_id,
-                batch_id,
-                intensity,
-                duration,
-                load,
-                if fork { "true" } else { "false" }
-            )
-        }
-        TestType::Memory | TestType::Disk => {
-            format!(
-                r#"{{"id": "{}", "batch_id": "{}", "name": "GUI Test", "intensity": {}, "duration": {}, "size": {}}}"#,
-                test_id, batch_id, intensity, duration, size
-            )
-        }
+        TestType::Cpu => StressRequest {
+            id: test_id.to_string(),
+            batch_id: batch_id.to_string(),
+            name: "GUI Test".to_string(),
+            intensity,
+            duration,
+            load: Some(parse_field::<f64>("load", &settings.load)?),
+            size: None,
+            fork: Some(settings.fork),
+            node,
+        },
+        TestType::Memory | TestType::Disk => StressRequest {
+            id: test_id.to_string(),
+            batch_id: batch_id.to_string(),
+            name: "GUI Test".to_string(),
+            intensity,
+            duration,
+            load: None,
+            size: Some(parse_field::<u32>("size", &settings.size)?),
+            fork: None,
+            node,
+        },
     };
 
-    (endpoint, payload)
+    Ok((endpoint, payload))
+}
+
+/// Parse a numeric input field, naming the field in the error
+fn parse_field<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{} must be a number (got '{}')", name, value))
 }
 
 /// Add request details to results
 fn add_request_details(results: &mut Vec<String>, server_url: &str, endpoint: &str, test_id: &str) {
-    results.push(format!("Request Details:"));
+    results.push("Request Details:".to_string());
     results.push(format!("  Endpoint: {}/{}", server_url, endpoint));
     results.push(format!("  Test ID: {}", test_id));
 }
 
 /// Add test parameters to results
-fn add_test_parameters(
-    results: &mut Vec<String>,
-    test: &TestType,
-    duration: &str,
-    intensity: &str,
-    size: &str,
-    load: &str,
-    fork: bool,
-) {
-    results.push(format!("Test Parameters:"));
+fn add_test_parameters(results: &mut Vec<String>, test: &TestType, settings: &TestSettings) {
+    let (duration, intensity, size, load, fork) = (
+        settings.duration.as_str(),
+        settings.intensity.as_str(),
+        settings.size.as_str(),
+        settings.load.as_str(),
+        settings.fork,
+    );
+    results.push("Test Parameters:".to_string());
 
     match test {
         TestType::Cpu => {
@@ -1123,18 +3387,16 @@ fn add_test_parameters(
 
             // Add fork-specific information
             if fork {
-                results.push(format!("  • Fork Mode: Enabled (using separate processes)"));
+                results.push("  • Fork Mode: Enabled (using separate processes)".to_string());
                 results.push(format!("  • Process Count: {} processes", intensity));
 
                 // Add explanation of fork mode
-                results.push(format!("  • Fork Mode Details:"));
-                results.push(format!("    - Each process runs independently"));
-                results.push(format!("    - Parent process monitors child processes"));
-                results.push(format!(
-                    "    - System resources allocated separately for each process"
-                ));
+                results.push("  • Fork Mode Details:".to_string());
+                results.push("    - Each process runs independently".to_string());
+                results.push("    - Parent process monitors child processes".to_string());
+                results.push("    - System resources allocated separately for each process".to_string());
             } else {
-                results.push(format!("  • Fork Mode: Disabled (using threads)"));
+                results.push("  • Fork Mode: Disabled (using threads)".to_string());
 
                 // Add CPU mode explanation
                 if let Ok(load_val) = load.parse::<f64>() {
@@ -1143,7 +3405,7 @@ fn add_test_parameters(
                         let work_time = (cycle_time as f64 * load_val / 100.0) as u64;
                         let sleep_time = cycle_time - work_time;
 
-                        results.push(format!("  • CPU Cycle Details:"));
+                        results.push("  • CPU Cycle Details:".to_string());
                         results.push(format!(
                             "    - Work period: {} ms per 100ms cycle",
                             work_time
@@ -1153,9 +3415,7 @@ fn add_test_parameters(
                             sleep_time
                         ));
                     } else {
-                        results.push(format!(
-                            "  • CPU Cycle Details: Running at full capacity (100% busy loop)"
-                        ));
+                        results.push("  • CPU Cycle Details: Running at full capacity (100% busy loop)".to_string());
                     }
                 }
             }
@@ -1172,23 +3432,21 @@ fn add_test_parameters(
                 results.push(format!("  • Total Memory Allocation: {} MB", total_mb));
 
                 // Add memory test details
-                results.push(format!("  • Memory Test Details:"));
-                results.push(format!("    - Each thread allocates blocks of memory"));
-                results.push(format!(
-                    "    - Memory is actively used to prevent optimization"
-                ));
-                results.push(format!("    - 4KB page size access pattern"));
+                results.push("  • Memory Test Details:".to_string());
+                results.push("    - Each thread allocates blocks of memory".to_string());
+                results.push("    - Memory is actively used to prevent optimization".to_string());
+                results.push("    - 4KB page size access pattern".to_string());
             }
 
             // Get initial memory information
             let initial_memory = get_memory_info();
-            results.push(format!("  • System Memory Information (Pre-Test):"));
+            results.push("  • System Memory Information (Pre-Test):".to_string());
             if let Some((total, used)) = initial_memory {
                 results.push(format!("    - Total Memory: {} MB", total));
                 results.push(format!("    - Used Memory: {} MB", used));
                 results.push(format!("    - Free Memory: {} MB", total - used));
             } else {
-                results.push(format!("    - Memory information not available"));
+                results.push("    - Memory information not available".to_string());
             }
         }
         TestType::Disk => {
@@ -1203,177 +3461,226 @@ fn add_test_parameters(
                 results.push(format!("  • Total Disk Usage: {} MB", total_mb));
 
                 // Add disk test details
-                results.push(format!("  • Disk Test Details:"));
-                results.push(format!("    - Each thread creates a separate file"));
-                results.push(format!("    - Alternating write and read phases"));
-                results.push(format!("    - Files are cleaned up after test"));
-                results.push(format!("    - Sequential I/O pattern"));
+                results.push("  • Disk Test Details:".to_string());
+                results.push("    - Each thread creates a separate file".to_string());
+                results.push("    - Alternating write and read phases".to_string());
+                results.push("    - Files are cleaned up after test".to_string());
+                results.push("    - Sequential I/O pattern".to_string());
             }
         }
     }
 }
 
 /// Process test response
-fn process_test_response(
-    results: &mut Vec<String>,
-    output: Result<std::process::Output, std::io::Error>,
-) {
-    match output {
-        Ok(output) => {
-            let status_str = if output.status.success() {
+fn process_test_response(results: &mut Vec<String>, response: Result<ApiResponse, String>) {
+    match response {
+        Ok(response) => {
+            let status_str = if response.is_success() {
                 "SUCCESS"
             } else {
                 "FAILED"
             };
-            results.push(format!(""));
-            results.push(format!("Execution Status: {}", status_str));
+            results.push(String::new());
+            results.push(format!("Execution Status: {} (HTTP {})", status_str, response.status));
 
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if !stdout.is_empty() {
-                results.push(format!(""));
-                results.push(format!("Server Response:"));
+            if !response.body.is_empty() {
+                results.push(String::new());
+                results.push("Server Response:".to_string());
 
                 // Try to parse as JSON for better formatting
-                match json_from_str::<Value>(&stdout) {
+                match json_from_str::<Value>(&response.body) {
                     Ok(json) => match to_string_pretty(&json) {
-                        Ok(pretty) => results.push(format!("{}", pretty)),
-                        Err(_) => results.push(format!("{}", stdout)),
+                        Ok(pretty) => results.push(pretty),
+                        Err(_) => results.push(response.body.clone()),
                     },
-                    Err(_) => results.push(format!("{}", stdout)),
+                    Err(_) => results.push(response.body.clone()),
                 }
             }
-
-            // Add any error information
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                results.push(format!(""));
-                results.push(format!("Error Details:"));
-                results.push(format!("{}", stderr));
-            }
         }
         Err(e) => {
-            results.push(format!(""));
+            results.push(String::new());
             results.push(format!("Failed to execute test: {}", e));
         }
     }
 }
 
 /// Calculate wait time for test completion
-fn calculate_wait_time(duration: &str) -> u64 {
-    match duration.parse::<u64>() {
-        Ok(d) => d + 2, // Add a small buffer
-        Err(_) => 10,   // Default to 10 seconds if parsing fails
+/// Wait until the task leaves the server's task list (polled every second)
+/// Without a task ID, or while the task list cannot be fetched, the requested duration plus a small buffer is used
+/// Returns false if the batch was cancelled while waiting
+async fn wait_for_completion(
+    server_url: &str,
+    node: Option<&str>,
+    task_id: Option<&str>,
+    duration: u64,
+    batch: &Batch,
+) -> bool {
+    let started = Instant::now();
+    let fallback = std::time::Duration::from_secs(duration + 2);
+    let mut failures = 0;
+
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if batch.is_cancelled() {
+            return false;
+        }
+
+        let Some(task_id) = task_id else {
+            if started.elapsed() >= fallback {
+                return true;
+            }
+            continue;
+        };
+
+        match list_tasks(server_url, node).await {
+            Ok(tasks) if !tasks.iter().any(|task| task.id == task_id) => return true,
+            Ok(_) => failures = 0,
+            Err(_) => {
+                failures += 1;
+                if failures >= 5 && started.elapsed() >= fallback {
+                    return true;
+                }
+            }
+        }
     }
 }
 
-/// Check test status after completion
-async fn check_test_status(
+/// Endpoint of a finished task's result (through the controller when a node is selected)
+fn result_endpoint(server_url: &str, node: Option<&str>, id: &str) -> String {
+    match node {
+        Some(node) => format!("{}/results/{}/{}", server_url, node, id),
+        None => format!("{}/results/{}", server_url, id),
+    }
+}
+
+/// Fetch a finished task's result and add its status and metrics to the report
+/// The engine records the result as the task ends, so a 404 is retried briefly
+async fn fetch_test_result(
     results: &mut Vec<String>,
     test: &TestType,
     server_url: &str,
-    test_id: &str,
-) {
-    let status_command = format!("curl -X GET {}/status/{}", server_url, test_id);
-    results.push(format!("Checking test status..."));
-
-    let status_output = ProcessCommand::new("sh")
-        .arg("-c")
-        .arg(&status_command)
-        .output();
-
-    match status_output {
-        Ok(output) => {
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                if !stdout.trim().is_empty() {
-                    results.push(format!(""));
-                    results.push(format!("Final Test Status:"));
-
-                    match json_from_str::<Value>(&stdout) {
-                        Ok(json) => {
-                            // Get test status
-                            if let Some(status) = json.get("status") {
-                                if let Some(status_str) = status.as_str() {
-                                    results.push(format!("  • Status: {}", status_str));
-                                }
-                            }
-
-                            // Extract metrics
-                            process_test_metrics(results, test, &json);
-                        }
-                        Err(_) => results.push(format!("{}", stdout)),
-                    }
+    node: Option<&str>,
+    task_id: Option<&str>,
+) -> Option<Value> {
+    results.push("Fetching test result...".to_string());
+    let Some(task_id) = task_id else {
+        results.push("No task ID was returned; the result cannot be fetched.".to_string());
+        return None;
+    };
+    let url = result_endpoint(server_url, node, task_id);
+
+    let mut attempts = 0;
+    let response = loop {
+        attempts += 1;
+        match api::get(&url).await {
+            Ok(response) if response.status == 404 && attempts < 3 => {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            }
+            other => break other,
+        }
+    };
+    match response {
+        Ok(response) if response.is_success() => match json_from_str::<Value>(&response.body) {
+            Ok(json) => {
+                results.push(String::new());
+                results.push("Final Test Status:".to_string());
+                if let Some(error) = json.get("error").filter(|error| !error.is_null()) {
+                    results.push(format!("  • Status: failed ({})", error));
+                } else if json.get("stop").is_some_and(|stop| !stop.is_null()) {
+                    results.push("  • Status: stopped".to_string());
                 } else {
-                    results.push(format!("No status information available."));
+                    results.push("  • Status: completed".to_string());
                 }
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                results.push(format!("Failed to get status: {}", stderr));
+                process_test_metrics(results, test, &json);
+                Some(json)
             }
+            Err(_) => {
+                results.push(response.body.clone());
+                None
+            }
+        },
+        Ok(response) => {
+            results.push(format!("Failed to get the result: HTTP {}", response.status));
+            None
         }
         Err(e) => {
-            results.push(format!("Error checking test status: {}", e));
+            results.push(format!("Error fetching the test result: {}", e));
+            None
         }
     }
 }
 
-/// Process test metrics from status response
+/// Add the metrics of a task result to the report
 fn process_test_metrics(results: &mut Vec<String>, test: &TestType, json: &Value) {
-    if let Some(metrics) = json.get("metrics") {
-        results.push(format!(""));
-        results.push(format!("Test Metrics:"));
-
-        match test {
-            TestType::Cpu => {
-                if let Some(cpu_usage) = metrics.get("cpu_usage") {
-                    results.push(format!("  • CPU Usage: {}", cpu_usage));
-                }
-                if let Some(thread_count) = metrics.get("thread_count") {
-                    results.push(format!("  • Thread Count: {}", thread_count));
-                }
+    let Some(result) = json.get("result").filter(|result| !result.is_null()) else {
+        return;
+    };
+    results.push(String::new());
+    results.push("Test Metrics:".to_string());
+    let field = |name: &str| result.get(name).filter(|value| !value.is_null());
+    if let Some(elapsed) = field("elapsed_secs").and_then(Value::as_f64) {
+        results.push(format!("  • Elapsed: {:.1} s", elapsed));
+    }
+    if let Some(iterations) = field("iterations") {
+        results.push(format!("  • Iterations: {}", iterations));
+    }
+
+    match test {
+        TestType::Cpu => {
+            if let Some(load) = field("achieved_load").and_then(Value::as_f64) {
+                results.push(format!("  • Achieved Load: {:.1}% per thread", load));
+            }
+            if let Some(cpu) = field("cpu_load").and_then(Value::as_f64) {
+                results.push(format!("  • CPU Time: {:.1}% per thread", cpu));
+            }
+            if let Some(threads) = field("thread_loads").and_then(Value::as_array) {
+                results.push(format!("  • Thread Count: {}", threads.len()));
+            }
+        }
+        TestType::Memory => {
+            if let Some(kills) = field("oom_kills") {
+                results.push(format!("  • OOM Kills: {}", kills));
             }
-            TestType::Memory => {
-                if let Some(allocated) = metrics.get("allocated_mb") {
-                    results.push(format!("  • Allocated Memory: {} MB", allocated));
-                }
-                if let Some(total) = metrics.get("total_memory_mb") {
-                    results.push(format!("  • Total System Memory: {} MB", total));
-                }
-                if let Some(used) = metrics.get("used_memory_mb") {
-                    results.push(format!("  • Used System Memory: {} MB", used));
-                }
 
-                // Get post-test memory information
-                let final_memory = get_memory_info();
-                results.push(format!("  • System Memory Information (Post-Test):"));
-                if let Some((total, used)) = final_memory {
-                    results.push(format!("    - Total Memory: {} MB", total));
-                    results.push(format!("    - Used Memory: {} MB", used));
-                    results.push(format!("    - Free Memory: {} MB", total - used));
-                } else {
-                    results.push(format!("    - Memory information not available"));
-                }
+            // Get post-test memory information
+            let final_memory = get_memory_info();
+            results.push("  • System Memory Information (Post-Test):".to_string());
+            if let Some((total, used)) = final_memory {
+                results.push(format!("    - Total Memory: {} MB", total));
+                results.push(format!("    - Used Memory: {} MB", used));
+                results.push(format!("    - Free Memory: {} MB", total - used));
+            } else {
+                results.push("    - Memory information not available".to_string());
             }
-            TestType::Disk => {
-                if let Some(write_speed) = metrics.get("write_speed_mb_s") {
-                    results.push(format!("  • Write Speed: {} MB/s", write_speed));
-                }
-                if let Some(read_speed) = metrics.get("read_speed_mb_s") {
-                    results.push(format!("  • Read Speed: {} MB/s", read_speed));
-                }
-                if let Some(total) = metrics.get("total_io_mb") {
-                    results.push(format!("  • Total I/O: {} MB", total));
-                }
+        }
+        TestType::Disk => {
+            if let Some(speed) = field("mb_per_s").and_then(Value::as_f64) {
+                results.push(format!("  • Throughput: {:.1} MB/s (write plus read)", speed));
+            }
+            if let Some(corrupt) = field("corrupt_blocks") {
+                results.push(format!("  • Corrupt Blocks: {}", corrupt));
             }
         }
     }
+    if let Some(times) = field("iteration_times") {
+        let us = |name: &str| times.get(name).and_then(Value::as_f64).unwrap_or_default();
+        results.push(format!(
+            "  • Iteration Time: p50 {:.0} µs, p99 {:.0} µs, max {:.0} µs",
+            us("p50_us"),
+            us("p99_us"),
+            us("max_us")
+        ));
+    }
+    for error in field("errors").and_then(Value::as_array).into_iter().flatten() {
+        results.push(format!("  • Error: {}", error.as_str().unwrap_or_default()));
+    }
 }
 
 /// Add summary section to results
 fn add_summary_section(results: &mut Vec<String>, batch_id: &str, selected_tests: &[TestType]) {
-    results.push(format!("===================================="));
-    results.push(format!("TEST SUMMARY"));
-    results.push(format!("===================================="));
+    results.push("====================================".to_string());
+    results.push("TEST SUMMARY".to_string());
+    results.push("====================================".to_string());
     results.push(format!("Batch ID: {}", batch_id));
     results.push(format!("Tests Executed: {}", selected_tests.len()));
     results.push(format!(
@@ -0,0 +1,140 @@
+/**
+ * Live log stream
+ *
+ * Subscribes to the engine's /logs/stream (or the controller's /logs/{node}/stream)
+ * Server-Sent Events while tests run and turns each event into a display line.
+ * Lines older than the current batch (the server replays its recent backlog) are skipped.
+ */
+use chrono::{Local, TimeZone};
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+use crate::api;
+
+/// Maximum number of lines kept in the log pane
+pub const MAX_LINES: usize = 1000;
+
+/// Seconds to wait before reconnecting a failed stream
+const RETRY_SECS: u64 = 5;
+
+/// Event payload sent by the engine
+#[derive(Debug, Deserialize)]
+struct LogEvent {
+    timestamp: f64,
+    task_id: String,
+    message: String,
+}
+
+/// Connection state of one log stream
+pub enum LogStream {
+    Disconnected,
+    Connected {
+        response: Box<reqwest::Response>, // Boxed: far larger than the other variant
+        buffer: String,           // Received text not yet forming a complete event
+        pending: VecDeque<String>, // Parsed lines waiting to be delivered
+    },
+}
+
+/// Subscription delivering log lines from `url`; `label` (the node) prefixes each line
+pub fn subscription<Message: 'static + Send>(
+    url: String,
+    label: Option<String>,
+    since: f64,
+    on_line: fn(String) -> Message,
+) -> iced::Subscription<Message> {
+    iced::subscription::unfold(
+        (url.clone(), since as u64),
+        LogStream::Disconnected,
+        move |state| {
+            let (url, label) = (url.clone(), label.clone());
+            async move {
+                let (line, state) = next_line(state, &url, label.as_deref(), since).await;
+                (on_line(line), state)
+            }
+        },
+    )
+}
+
+/// Advance the stream until there is a line to show
+async fn next_line(
+    mut state: LogStream,
+    url: &str,
+    label: Option<&str>,
+    since: f64,
+) -> (String, LogStream) {
+    loop {
+        match state {
+            LogStream::Disconnected => match api::get_stream(url).await {
+                Ok(response) => {
+                    return (
+                        format!("-- Connected to {}", url),
+                        LogStream::Connected {
+                            response: Box::new(response),
+                            buffer: String::new(),
+                            pending: VecDeque::new(),
+                        },
+                    );
+                }
+                Err(e) => {
+                    tokio::time::sleep(std::time::Duration::from_secs(RETRY_SECS)).await;
+                    return (
+                        format!("-- Log stream unavailable: {} (retrying)", e),
+                        LogStream::Disconnected,
+                    );
+                }
+            },
+            LogStream::Connected { mut response, mut buffer, mut pending } => {
+                if let Some(line) = pending.pop_front() {
+                    return (line, LogStream::Connected { response, buffer, pending });
+                }
+
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        buffer.push_str(&String::from_utf8_lossy(&chunk));
+                        // Complete events end with a blank line
+                        while let Some(end) = buffer.find("\n\n") {
+                            let event: String = buffer.drain(..end + 2).collect();
+                            pending.extend(parse_event(&event, label, since));
+                        }
+                        state = LogStream::Connected { response, buffer, pending };
+                    }
+                    Ok(None) => return ("-- Log stream closed".to_string(), LogStream::Disconnected),
+                    Err(e) => {
+                        return (format!("-- Log stream error: {}", e), LogStream::Disconnected);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Display line for an SSE event (None for comments, keepalives and old lines)
+fn parse_event(event: &str, label: Option<&str>, since: f64) -> Option<String> {
+    let data: String = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim)
+        .collect();
+    let event: LogEvent = serde_json::from_str(&data).ok()?;
+    if event.timestamp < since {
+        return None;
+    }
+
+    let time = Local
+        .timestamp_millis_opt((event.timestamp * 1000.0) as i64)
+        .single()
+        .map(|t| t.format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+    Some(match label {
+        Some(label) => format!("[{}] {} {}: {}", time, label, event.task_id, event.message),
+        None => format!("[{}] {}: {}", time, event.task_id, event.message),
+    })
+}
+
+/// Append a line, dropping the oldest once the pane is full
+pub fn push_line(lines: &mut VecDeque<String>, line: String) {
+    if lines.len() >= MAX_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
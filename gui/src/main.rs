@@ -1,4 +1,15 @@
+mod ai;
+mod api;
+mod charts;
+mod compare;
+mod email;
 mod gui;
+mod logs;
+mod prefs;
+mod presets;
+mod progress;
+mod report;
+mod schedule;
 
 fn main() {
     match gui::run() {
@@ -0,0 +1,135 @@
+/**
+ * GUI preferences
+ *
+ * Small settings that should survive restarts (currently the theme choice).
+ * Stored in <config dir>/mogwai/gui_settings.json next to the presets file.
+ */
+use iced::Theme;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command as ProcessCommand;
+
+/// Theme selected in the GUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    Light,
+    Dark,
+    #[default]
+    System, // Follow the desktop's light/dark setting
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 3] = [ThemeChoice::Light, ThemeChoice::Dark, ThemeChoice::System];
+
+    /// iced theme for this choice
+    pub fn theme(self, system_dark: bool) -> Theme {
+        match self {
+            ThemeChoice::Light => Theme::Light,
+            ThemeChoice::Dark => Theme::Dark,
+            ThemeChoice::System if system_dark => Theme::Dark,
+            ThemeChoice::System => Theme::Light,
+        }
+    }
+}
+
+impl std::fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Light => write!(f, "Light"),
+            Self::Dark => write!(f, "Dark"),
+            Self::System => write!(f, "System"),
+        }
+    }
+}
+
+/// Persisted preferences
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Prefs {
+    #[serde(default)]
+    pub theme: ThemeChoice,
+}
+
+/// Location of the preferences file
+fn prefs_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("mogwai")
+        .join("gui_settings.json")
+}
+
+/// Load preferences, falling back to defaults when the file is missing or invalid
+pub fn load_prefs() -> Prefs {
+    fs::read_to_string(prefs_path())
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Save preferences
+pub fn save_prefs(prefs: &Prefs) -> Result<(), String> {
+    let path = prefs_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let data = serde_json::to_string_pretty(prefs)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/**
+* Detect whether the desktop prefers a dark theme
+*/
+pub fn system_prefers_dark() -> bool {
+    // GTK theme override (e.g. GTK_THEME=Adwaita:dark)
+    if let Ok(theme) = std::env::var("GTK_THEME") {
+        if theme.to_lowercase().contains("dark") {
+            return true;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // GNOME 42+ color scheme, then the older GTK theme name
+        for key in ["color-scheme", "gtk-theme"] {
+            if let Ok(output) = ProcessCommand::new("gsettings")
+                .args(["get", "org.gnome.desktop.interface", key])
+                .output()
+            {
+                if String::from_utf8_lossy(&output.stdout).to_lowercase().contains("dark") {
+                    return true;
+                }
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // Prints "Dark" only when dark mode is enabled
+        if let Ok(output) = ProcessCommand::new("defaults")
+            .args(["read", "-g", "AppleInterfaceStyle"])
+            .output()
+        {
+            return String::from_utf8_lossy(&output.stdout).trim() == "Dark";
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // AppsUseLightTheme is 0x0 when apps use the dark theme
+        if let Ok(output) = ProcessCommand::new("reg")
+            .args([
+                "query",
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ])
+            .output()
+        {
+            return String::from_utf8_lossy(&output.stdout).contains("0x0");
+        }
+    }
+
+    false
+}
@@ -0,0 +1,78 @@
+/**
+ * Saved presets
+ *
+ * A preset captures the whole test setup (selected tests, their parameters,
+ * environment, server URL and node) under a name so it survives restarts.
+ * Presets live in <config dir>/mogwai/gui_presets.json, or $MOGWAI_GUI_PRESETS if set.
+ */
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::gui::{Environment, TestSettings, TestType};
+
+/// A named snapshot of the GUI configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,                              // Preset name (unique key)
+    pub selected_tests: Vec<TestType>,             // Tests that were checked
+    pub settings: HashMap<TestType, TestSettings>, // Parameters for each test type
+    pub environment: Environment,                  // Selected environment
+    pub server_url: String,                        // Server URL
+    #[serde(default)]
+    pub nodes: Vec<String>,                        // Selected nodes (Kubernetes environment)
+}
+
+/// Location of the presets file
+pub fn presets_path() -> PathBuf {
+    if let Ok(path) = std::env::var("MOGWAI_GUI_PRESETS") {
+        return PathBuf::from(path);
+    }
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("mogwai")
+        .join("gui_presets.json")
+}
+
+/// Load all presets keyed by name (an absent file means no presets yet)
+pub fn load_presets() -> Result<BTreeMap<String, Preset>, String> {
+    let path = presets_path();
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let presets: Vec<Preset> = serde_json::from_str(&data)
+        .map_err(|e| format!("Preset file {} is invalid: {}", path.display(), e))?;
+    Ok(presets.into_iter().map(|p| (p.name.clone(), p)).collect())
+}
+
+/// Write all presets back to disk, creating the config directory if needed
+fn store_presets(presets: &BTreeMap<String, Preset>) -> Result<(), String> {
+    let path = presets_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let list: Vec<&Preset> = presets.values().collect();
+    let data = serde_json::to_string_pretty(&list)
+        .map_err(|e| format!("Failed to serialize presets: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Save (or overwrite) a preset and return the updated list
+pub fn save_preset(preset: Preset) -> Result<BTreeMap<String, Preset>, String> {
+    let mut presets = load_presets()?;
+    presets.insert(preset.name.clone(), preset);
+    store_presets(&presets)?;
+    Ok(presets)
+}
+
+/// Delete a preset by name and return the updated list
+pub fn delete_preset(name: &str) -> Result<BTreeMap<String, Preset>, String> {
+    let mut presets = load_presets()?;
+    presets.remove(name);
+    store_presets(&presets)?;
+    Ok(presets)
+}
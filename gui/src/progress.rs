@@ -0,0 +1,91 @@
+/**
+ * Per-test progress tracking
+ *
+ * execute_tests records the state of every test in the batch on a shared board
+ * while it polls the server's task list; the GUI copies the board on each
+ * progress tick to draw one progress bar with a remaining-time estimate per test.
+ */
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Board shared between the running batch (writer) and the GUI (reader)
+pub type ProgressBoard = Arc<Mutex<Vec<TestProgress>>>;
+
+/// Lifecycle of a test in the batch
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressState {
+    Waiting,        // Queued behind earlier tests of the batch
+    Running,        // Started on the server, still listed as a running task
+    Done,           // No longer listed as running
+    Cancelled,      // Stopped or skipped because the batch was cancelled
+    Failed(String), // Could not be started
+}
+
+/// Progress of one test in the batch
+#[derive(Debug, Clone)]
+pub struct TestProgress {
+    pub key: String,               // Unique key within the batch (node and position)
+    pub label: String,             // Display name, e.g. "CPU" or "CPU @ node-1"
+    pub node: Option<String>,      // Target node (None when talking to an engine directly)
+    pub task_id: Option<String>,   // Task ID reported by the server once started (used for cancelling)
+    pub duration: u64,             // Requested duration in seconds (0 = indefinite)
+    pub started: Option<Instant>,  // When the server accepted the test
+    pub state: ProgressState,
+}
+
+impl TestProgress {
+    /// Fraction complete (0.0 - 1.0); running tests past their duration stay just under 1.0
+    pub fn fraction(&self, now: Instant) -> f32 {
+        match (&self.state, self.started) {
+            (ProgressState::Done, _) => 1.0,
+            (ProgressState::Running, Some(started)) if self.duration > 0 => {
+                let elapsed = now.duration_since(started).as_secs_f32();
+                (elapsed / self.duration as f32).min(0.99)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Status text shown next to the bar
+    pub fn status_text(&self, now: Instant) -> String {
+        match (&self.state, self.started) {
+            (ProgressState::Waiting, _) => "Waiting".to_string(),
+            (ProgressState::Done, _) => "Done".to_string(),
+            (ProgressState::Cancelled, _) => "Cancelled".to_string(),
+            (ProgressState::Failed(e), _) => format!("Failed: {}", e),
+            (ProgressState::Running, Some(started)) => {
+                let elapsed = now.duration_since(started).as_secs();
+                if self.duration == 0 {
+                    format!("Running for {} (indefinite)", format_secs(elapsed))
+                } else if elapsed >= self.duration {
+                    "Finishing...".to_string()
+                } else {
+                    format!("ETA {}", format_secs(self.duration - elapsed))
+                }
+            }
+            (ProgressState::Running, None) => "Starting...".to_string(),
+        }
+    }
+}
+
+/// Update an entry on the board (no-op for unknown keys)
+pub fn update(board: &ProgressBoard, key: &str, f: impl FnOnce(&mut TestProgress)) {
+    let mut entries = board.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(entry) = entries.iter_mut().find(|entry| entry.key == key) {
+        f(entry);
+    }
+}
+
+/// Copy of the board for rendering
+pub fn snapshot(board: &ProgressBoard) -> Vec<TestProgress> {
+    board.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// "1h 2m", "3m 4s" or "5s"
+fn format_secs(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
@@ -0,0 +1,384 @@
+/**
+ * Structured test reports and export formats
+ *
+ * execute_tests fills a BatchReport alongside the human-readable text so results
+ * can be exported as JSON (for tooling), CSV (one row per test) or a styled HTML page.
+ * JSON exports can be read back for the Compare tab.
+ */
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::api::StressRequest;
+use crate::charts::MetricSample;
+
+// ===== REPORT TYPES =====
+/// Everything recorded for one batch of tests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub batch_id: String,
+    pub started_at: String,            // RFC3339 local time
+    pub finished_at: Option<String>,   // RFC3339 local time
+    pub server_url: String,
+    pub node: Option<String>,
+    pub system_info: String,
+    pub tests: Vec<TestReport>,
+    pub host_metrics: Option<HostSummary>, // Summary of the live metrics polled during the run
+    pub error: Option<String>,         // Set when the run on this node was aborted
+}
+
+impl BatchReport {
+    /// New report for a batch that is about to start
+    pub fn empty(batch_id: &str, server_url: &str, node: Option<String>) -> Self {
+        BatchReport {
+            batch_id: batch_id.to_string(),
+            started_at: chrono::Local::now().to_rfc3339(),
+            finished_at: None,
+            server_url: server_url.to_string(),
+            node,
+            system_info: String::new(),
+            tests: Vec::new(),
+            host_metrics: None,
+            error: None,
+        }
+    }
+}
+
+/// Result of a single test in the batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestReport {
+    pub test_id: String,
+    pub test_type: String,             // CPU, Memory or Disk
+    pub endpoint: String,
+    pub request: Option<StressRequest>, // None when the parameters were invalid
+    pub http_status: Option<u16>,
+    pub task_id: Option<String>,       // Task ID reported by the server
+    pub status: Option<String>,        // completed, stopped or failed, from the task's result
+    pub error: Option<String>,
+    pub metrics: BTreeMap<String, f64>, // Numeric fields of the task's result, e.g. achieved_load
+}
+
+/// Aggregates over the live metric samples of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostSummary {
+    pub samples: usize,
+    pub cpu_avg_percent: f32,
+    pub cpu_peak_percent: f32,
+    pub memory_peak_mb: f32,
+    pub disk_peak_mb_s: f32,
+}
+
+impl HostSummary {
+    /// Summarize the samples (None when nothing was collected)
+    pub fn from_samples(samples: &[MetricSample]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let peak = |f: fn(&MetricSample) -> f32| samples.iter().map(f).fold(0.0_f32, f32::max);
+        Some(HostSummary {
+            samples: samples.len(),
+            cpu_avg_percent: samples.iter().map(|s| s.cpu_percent).sum::<f32>() / samples.len() as f32,
+            cpu_peak_percent: peak(|s| s.cpu_percent),
+            memory_peak_mb: peak(|s| s.memory_used_mb),
+            disk_peak_mb_s: peak(|s| s.disk_mb_s),
+        })
+    }
+}
+
+impl TestReport {
+    /// Record a finished task's result, as served by the engine's /results/{id}
+    pub fn record_result(&mut self, json: &Value) {
+        let stopped = json.get("stop").is_some_and(|stop| !stop.is_null())
+            || json.pointer("/result/stopped").and_then(Value::as_bool).unwrap_or(false);
+        let status = if json.get("error").is_some_and(|error| !error.is_null()) {
+            "failed"
+        } else if stopped {
+            "stopped"
+        } else {
+            "completed"
+        };
+        self.status = Some(status.to_string());
+        if let Some(result) = json.get("result") {
+            collect_numbers(&mut self.metrics, "", result);
+        }
+    }
+}
+
+/// Numeric fields of `value`, nested objects flattened with dots (e.g. iteration_times.p99_us)
+/// Lists (per-thread and per-path breakdowns) are left out
+fn collect_numbers(metrics: &mut BTreeMap<String, f64>, prefix: &str, value: &Value) {
+    let Some(fields) = value.as_object() else {
+        return;
+    };
+    for (key, value) in fields {
+        let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        if value.is_object() {
+            collect_numbers(metrics, &name, value);
+        } else if let Some(number) = value.as_f64() {
+            metrics.insert(name, number);
+        }
+    }
+}
+
+/// Extract the task ID from a start response (JSON "task_id"/"id", or the text after "ID:")
+pub fn parse_task_id(body: &str) -> Option<String> {
+    if let Ok(json) = serde_json::from_str::<Value>(body) {
+        return ["task_id", "id"]
+            .iter()
+            .find_map(|key| json.get(*key).and_then(Value::as_str))
+            .map(str::to_string);
+    }
+    body.split("ID:")
+        .nth(1)
+        .map(|rest| rest.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+// ===== EXPORT FORMATS =====
+/// File formats offered when saving results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Text, // The human-readable report shown in the GUI
+    Json, // Full structured report
+    Csv,  // One row per test
+    Html, // Styled standalone report
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Text,
+        ExportFormat::Json,
+        ExportFormat::Csv,
+        ExportFormat::Html,
+    ];
+
+    /// File extension for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "Text (.txt)"),
+            Self::Json => write!(f, "JSON (.json)"),
+            Self::Csv => write!(f, "CSV (.csv)"),
+            Self::Html => write!(f, "HTML (.html)"),
+        }
+    }
+}
+
+/// Render the reports (one per node) in the given format (`text` is the GUI's text report)
+pub fn render(format: ExportFormat, text: &str, reports: &[BatchReport]) -> Result<String, String> {
+    if format != ExportFormat::Text && reports.is_empty() {
+        return Err("No structured results available for this run.".to_string());
+    }
+    match format {
+        ExportFormat::Text => Ok(text.to_string()),
+        // A single object for single-node runs, an array for multi-node runs
+        ExportFormat::Json => match reports {
+            [report] => serde_json::to_string_pretty(report),
+            _ => serde_json::to_string_pretty(reports),
+        }
+        .map_err(|e| format!("Failed to serialize report: {}", e)),
+        ExportFormat::Csv => Ok(to_csv(reports)),
+        ExportFormat::Html => Ok(to_html(reports)),
+    }
+}
+
+/// CSV with one row per test and node; metric columns are the union of all metric names
+fn to_csv(reports: &[BatchReport]) -> String {
+    let metric_names: BTreeSet<&String> = reports
+        .iter()
+        .flat_map(|r| r.tests.iter())
+        .flat_map(|t| t.metrics.keys())
+        .collect();
+
+    let mut header = vec![
+        "batch_id", "test_id", "test_type", "node", "intensity", "duration", "load", "size", "fork",
+        "http_status", "task_id", "status", "error",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect::<Vec<_>>();
+    header.extend(metric_names.iter().map(|name| name.to_string()));
+
+    let mut lines = vec![header.iter().map(|h| csv_field(h)).collect::<Vec<_>>().join(",")];
+
+    for (report, test) in reports.iter().flat_map(|r| r.tests.iter().map(move |t| (r, t))) {
+        let request = test.request.as_ref();
+        let opt = |value: Option<String>| value.unwrap_or_default();
+        let mut row = vec![
+            report.batch_id.clone(),
+            test.test_id.clone(),
+            test.test_type.clone(),
+            opt(report.node.clone()),
+            opt(request.map(|r| r.intensity.to_string())),
+            opt(request.map(|r| r.duration.to_string())),
+            opt(request.and_then(|r| r.load).map(|v| v.to_string())),
+            opt(request.and_then(|r| r.size).map(|v| v.to_string())),
+            opt(request.and_then(|r| r.fork).map(|v| v.to_string())),
+            opt(test.http_status.map(|v| v.to_string())),
+            opt(test.task_id.clone()),
+            opt(test.status.clone()),
+            opt(test.error.clone()),
+        ];
+        row.extend(
+            metric_names
+                .iter()
+                .map(|name| opt(test.metrics.get(*name).map(|v| v.to_string()))),
+        );
+        lines.push(row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Quote a CSV field when needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape text for HTML
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Standalone HTML report with one section per node
+fn to_html(reports: &[BatchReport]) -> String {
+    let batch_id = reports.first().map(|r| r.batch_id.as_str()).unwrap_or("-");
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>Mogwai Test Report {}</title>\n",
+        html_escape(batch_id)
+    ));
+    html.push_str(
+        "<style>\n\
+         body { font-family: sans-serif; margin: 2em; color: #222; }\n\
+         h1 { color: #4c6680; }\n\
+         table { border-collapse: collapse; margin-bottom: 1.5em; }\n\
+         th, td { border: 1px solid #ccc; padding: 6px 10px; text-align: left; }\n\
+         th { background: #eef2f6; }\n\
+         .ok { color: #2e7d32; } .failed { color: #c62828; }\n\
+         pre { background: #f6f6f6; padding: 1em; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    html.push_str("<h1>Mogwai Performance Test Report</h1>\n");
+    for report in reports {
+        html_section(&mut html, report, reports.len() > 1);
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// HTML section for one node's report
+fn html_section(html: &mut String, report: &BatchReport, show_node_heading: bool) {
+    if show_node_heading {
+        html.push_str(&format!(
+            "<h2>Node: {}</h2>\n",
+            html_escape(report.node.as_deref().unwrap_or("-"))
+        ));
+    }
+    html.push_str("<table>\n");
+    let mut summary_row = |label: &str, value: &str| {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            label,
+            html_escape(value)
+        ));
+    };
+    summary_row("Batch ID", &report.batch_id);
+    summary_row("Started", &report.started_at);
+    summary_row("Finished", report.finished_at.as_deref().unwrap_or("-"));
+    summary_row("Server", &report.server_url);
+    summary_row("Node", report.node.as_deref().unwrap_or("-"));
+    summary_row("Tests", &report.tests.len().to_string());
+    if let Some(error) = &report.error {
+        summary_row("Error", error);
+    }
+    html.push_str("</table>\n");
+
+    // Per-test results
+    html.push_str("<h3>Tests</h3>\n<table>\n<tr><th>Test</th><th>Parameters</th><th>HTTP</th><th>Task ID</th><th>Status</th><th>Metrics</th></tr>\n");
+    for test in &report.tests {
+        let parameters = match &test.request {
+            Some(r) => {
+                let mut parts = vec![
+                    format!("{} threads", r.intensity),
+                    format!("{}s", r.duration),
+                ];
+                if let Some(load) = r.load {
+                    parts.push(format!("{}% load", load));
+                }
+                if let Some(size) = r.size {
+                    parts.push(format!("{} MB", size));
+                }
+                if r.fork == Some(true) {
+                    parts.push("fork".to_string());
+                }
+                parts.join(", ")
+            }
+            None => "-".to_string(),
+        };
+        let succeeded = test.error.is_none() && test.http_status.is_some_and(|s| (200..300).contains(&s));
+        let status = match (&test.error, &test.status) {
+            (Some(error), _) => error.clone(),
+            (None, Some(status)) => status.clone(),
+            (None, None) if succeeded => "started".to_string(),
+            (None, None) => "-".to_string(),
+        };
+        let metrics = if test.metrics.is_empty() {
+            "-".to_string()
+        } else {
+            test.metrics
+                .iter()
+                .map(|(key, value)| format!("{}: {}", html_escape(key), value))
+                .collect::<Vec<_>>()
+                .join("<br>")
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>\n",
+            html_escape(&test.test_type),
+            html_escape(&parameters),
+            test.http_status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string()),
+            html_escape(test.task_id.as_deref().unwrap_or("-")),
+            if succeeded { "ok" } else { "failed" },
+            html_escape(&status),
+            metrics
+        ));
+    }
+    html.push_str("</table>\n");
+
+    // Live metrics summary
+    if let Some(host) = &report.host_metrics {
+        html.push_str("<h3>Host Metrics</h3>\n<table>\n");
+        html.push_str(&format!(
+            "<tr><th>Samples</th><td>{}</td></tr>\n\
+             <tr><th>Average CPU</th><td>{:.1}%</td></tr>\n\
+             <tr><th>Peak CPU</th><td>{:.1}%</td></tr>\n\
+             <tr><th>Peak Memory</th><td>{:.0} MB</td></tr>\n\
+             <tr><th>Peak Disk I/O</th><td>{:.1} MB/s</td></tr>\n",
+            host.samples, host.cpu_avg_percent, host.cpu_peak_percent, host.memory_peak_mb, host.disk_peak_mb_s
+        ));
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h3>System Information</h3>\n");
+    html.push_str(&format!("<pre>{}</pre>\n", html_escape(&report.system_info)));
+}
@@ -0,0 +1,88 @@
+/**
+ * Client-side test scheduling
+ *
+ * Scheduled batches are kept in memory and started by the GUI's scheduler tick
+ * once their run time is reached (the same approach as the CLI's scheduler),
+ * so the GUI has to stay open until the batch has started.
+ */
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone, Timelike};
+
+use crate::email::ReportEmail;
+use crate::gui::{TestSettings, TestType};
+
+/// A batch of tests waiting for its run time
+#[derive(Debug, Clone)]
+pub struct ScheduledTest {
+    pub id: String,                               // Schedule ID (used for cancelling)
+    pub run_at: DateTime<Local>,                  // When the batch should start
+    pub tests: Vec<(TestType, TestSettings)>,     // Tests and their parameters, captured when scheduled
+    pub server_url: String,                       // Server to send the tests to
+    pub nodes: Vec<String>,                       // Target nodes (Kubernetes environment; empty = the server itself)
+    pub email: Option<ReportEmail>,               // Where to email the report once the batch completes
+}
+
+impl ScheduledTest {
+    /// Whether the batch should start now
+    pub fn is_due(&self, now: DateTime<Local>) -> bool {
+        self.run_at <= now
+    }
+}
+
+/// Hours offered by the hour picker
+pub const HOURS: [u32; 24] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
+];
+
+/// Minutes offered by the minute picker (5-minute steps)
+pub const MINUTES: [u32; 12] = [0, 5, 10, 15, 20, 25, 30, 35, 40, 45, 50, 55];
+
+/// Build the local run time from the picker values and check it is in the future
+pub fn parse_run_time(
+    date: &str,
+    hour: u32,
+    minute: u32,
+    now: DateTime<Local>,
+) -> Result<DateTime<Local>, String> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}'. Use YYYY-MM-DD.", date.trim()))?;
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)
+        .ok_or_else(|| format!("Invalid time {:02}:{:02}.", hour, minute))?;
+
+    // Ambiguous local times (DST fall-back) use the earlier instant
+    let run_at = Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| "That time does not exist in the local timezone.".to_string())?;
+
+    if run_at <= now {
+        return Err(format!(
+            "Scheduled time {} is in the past.",
+            run_at.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    Ok(run_at)
+}
+
+/// Picker values (date, hour, minute) for a time `minutes` from now, rounded up to the picker's 5-minute steps
+pub fn picker_values_in(minutes: i64, now: DateTime<Local>) -> (String, u32, u32) {
+    let mut target = now + Duration::minutes(minutes);
+    let remainder = target.minute() % 5;
+    if remainder != 0 {
+        target += Duration::minutes((5 - remainder) as i64);
+    }
+    (
+        target.format("%Y-%m-%d").to_string(),
+        target.hour(),
+        target.minute(),
+    )
+}
+
+/// Human-readable countdown until the run time
+pub fn time_until(run_at: DateTime<Local>, now: DateTime<Local>) -> String {
+    let secs = (run_at - now).num_seconds().max(0);
+    match secs {
+        0..=59 => format!("in {}s", secs),
+        60..=3599 => format!("in {}m {}s", secs / 60, secs % 60),
+        _ => format!("in {}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
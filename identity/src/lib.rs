@@ -0,0 +1,38 @@
+//! Headers identifying the user of the CLI and GUI to the engines and controller
+//!
+//! Engines scope tasks per client (see the engine's clients module): the client is
+//! named by a bearer token, or by `X-Mogwai-Client` where the engine trusts that
+//! header (behind the controller, which authenticates users and sets it).
+
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+
+/// Header naming the calling client
+const CLIENT_HEADER: &str = "x-mogwai-client";
+
+/// `X-Mogwai-Client` from `MOGWAI_CLIENT` (default: the login name), and a bearer
+/// token from `MOGWAI_TOKEN` when set
+pub fn headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(value) = client_name().and_then(|name| HeaderValue::from_str(&name).ok()) {
+        headers.insert(CLIENT_HEADER, value);
+    }
+    if let Ok(token) = std::env::var("MOGWAI_TOKEN") {
+        if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+            headers.insert(AUTHORIZATION, value);
+        }
+    }
+    headers
+}
+
+/// The client name sent to the engines, reduced to the characters they accept
+fn client_name() -> Option<String> {
+    let name: String = std::env::var("MOGWAI_CLIENT")
+        .or_else(|_| std::env::var("USER"))
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+        .chars()
+        .take(64)
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .collect();
+    (!name.is_empty()).then_some(name)
+}